@@ -0,0 +1,38 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_examples::{
+    gadgets::add_many::{AddManyCircuit, ChainedAddCircuit},
+    prover::{prove_deterministic, setup},
+};
+use halo2_proofs::{circuit::Value, pasta::Fp};
+
+// Both circuits load the 2^16-row xor table of `U32Chip`.
+const K: u32 = 17;
+const SEED: u64 = 0;
+
+fn bench_add_many(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add_many");
+    group.sample_size(10);
+
+    for n in [16u32, 64] {
+        let words: Vec<u32> = (0..n).map(|i| u32::MAX - i * 0x0101_0101).collect();
+        let instances: Vec<Vec<Fp>> = vec![AddManyCircuit::outputs(&words).iter().map(|output| Fp::from(*output as u64)).collect()];
+        let values: Vec<Value<u32>> = words.iter().copied().map(Value::known).collect();
+
+        let circuit = AddManyCircuit { words: values.clone() };
+        let (params, pk) = setup(K, &circuit).unwrap();
+        group.bench_with_input(BenchmarkId::new("add_many", n), &n, |b, _| {
+            b.iter(|| black_box(prove_deterministic(&params, &pk, &circuit, &instances, SEED).unwrap()))
+        });
+
+        let circuit = ChainedAddCircuit { words: values };
+        let (params, pk) = setup(K, &circuit).unwrap();
+        group.bench_with_input(BenchmarkId::new("chained", n), &n, |b, _| {
+            b.iter(|| black_box(prove_deterministic(&params, &pk, &circuit, &instances, SEED).unwrap()))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_add_many);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_examples::{
+    hash_chain::HashChainCircuit,
+    prover::{prove_deterministic, setup},
+};
+use halo2_proofs::pasta::Fp;
+
+const SEED: u64 = 0;
+
+fn bench_hash_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash_chain");
+    group.sample_size(10);
+
+    // about 40 rows per Poseidon hash
+    for (iterations, k) in [(16, 10), (64, 12), (256, 14)] {
+        let (secret, salt) = (Fp::from(0x5ec2e7), Fp::from(0x5a17));
+        let circuit: HashChainCircuit = HashChainCircuit::new(secret, salt, iterations);
+        let instances = vec![<HashChainCircuit>::outputs(secret, salt, iterations).to_vec()];
+
+        let (params, pk) = setup(k, &circuit).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(iterations), &iterations, |b, _| {
+            b.iter(|| black_box(prove_deterministic(&params, &pk, &circuit, &instances, SEED).unwrap()))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_hash_chain);
+criterion_main!(benches);
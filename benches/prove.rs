@@ -0,0 +1,39 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use halo2_examples::{
+    decompose::decompose_range_check::DecomposeRangeCheckCircuit,
+    fibonaci::example1::FiboCircuit,
+    prover::{prove_deterministic, setup},
+};
+use halo2_proofs::{circuit::Value, pasta::Fp};
+
+// Proofs are seeded so every iteration, and every run, proves exactly the same thing.
+const SEED: u64 = 0;
+
+fn bench_prove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prove");
+    group.sample_size(10);
+
+    let circuit = FiboCircuit {
+        a: Value::known(Fp::from(1)),
+        b: Value::known(Fp::from(1)),
+    };
+    let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+    let (params, pk) = setup(4, &circuit).unwrap();
+    group.bench_function("fibonacci", |b| {
+        b.iter(|| black_box(prove_deterministic(&params, &pk, &circuit, &instances, SEED).unwrap()))
+    });
+
+    let circuit = DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+        value: Value::known(Fp::from(0x1234_5678_9abc_def0u64).into()),
+        num_bits: 64,
+    };
+    let (params, pk) = setup(9, &circuit).unwrap();
+    group.bench_function("decompose", |b| {
+        b.iter(|| black_box(prove_deterministic(&params, &pk, &circuit, &[], SEED).unwrap()))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_prove);
+criterion_main!(benches);
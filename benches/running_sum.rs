@@ -0,0 +1,68 @@
+use ff::Field;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_examples::decompose::helpers::{compute_running_sums, recompose, RunningSum};
+use halo2_proofs::{pasta::Fp, plonk::Assigned};
+
+const LOOKUP_NUM_BITS: usize = 8;
+
+fn limb_bits(limbs: &[u64]) -> impl Iterator<Item = bool> + '_ {
+    limbs.iter().flat_map(|limb| (0..64).map(move |i| (limb >> i) & 1 == 1))
+}
+
+// The value of the limbs modulo p, which is what the running sum starts from.
+fn limb_value(limbs: &[u64]) -> Assigned<Fp> {
+    let limbs: Vec<Fp> = limbs.iter().map(|limb| Fp::from(*limb)).collect();
+    Assigned::from(recompose(&limbs, 64))
+}
+
+fn bench_running_sum(c: &mut Criterion) {
+    let mut group = c.benchmark_group("running_sum");
+
+    for num_bits in [256, 2048] {
+        let limbs: Vec<u64> = (0..num_bits / 64).map(|_| rand::random()).collect();
+        let value = limb_value(&limbs);
+
+        // collect every bit first, then chunk, as `compute_running_sum` used to
+        group.bench_with_input(BenchmarkId::new("collected", num_bits), &limbs, |b, limbs| {
+            b.iter(|| {
+                let bits: Vec<bool> = limb_bits(limbs).collect();
+                let shift_inv = Assigned::from(Fp::from(1u64 << LOOKUP_NUM_BITS)).invert();
+                let mut z = value;
+                let mut running_sum = vec![];
+                for chunk in bits.chunks(LOOKUP_NUM_BITS) {
+                    let chunk = chunk.iter().rev().fold(Fp::zero(), |acc, bit| acc.double() + Fp::from(*bit as u64));
+                    z = (z - Assigned::from(chunk)) * shift_inv;
+                    running_sum.push((chunk, z));
+                }
+                black_box(running_sum)
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("lazy", num_bits), &limbs, |b, limbs| {
+            b.iter(|| {
+                let running_sum = RunningSum::from_bits(value, limb_bits(limbs), num_bits, LOOKUP_NUM_BITS).unwrap();
+                black_box(running_sum.last())
+            })
+        });
+    }
+
+    group.finish();
+}
+
+// Precomputing the running sums of many values, as `DecomposeChip::assign_many` does. Run
+// with and without `--features parallel` to compare.
+fn bench_running_sums(c: &mut Criterion) {
+    let mut group = c.benchmark_group("running_sums");
+
+    for count in [64, 1024] {
+        let values: Vec<Assigned<Fp>> = (0..count).map(|_| limb_value(&[rand::random(), rand::random(), rand::random()])).collect();
+        group.bench_with_input(BenchmarkId::new("192 bits", count), &values, |b, values| {
+            b.iter(|| black_box(compute_running_sums(values, 192, LOOKUP_NUM_BITS).unwrap()))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_running_sum, bench_running_sums);
+criterion_main!(benches);
@@ -0,0 +1,23 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use halo2_examples::table::range_values;
+use halo2_proofs::pasta::Fp;
+
+const RANGE: usize = 1 << 16;
+
+// What every synthesis pass of a 2^16-row range table used to compute, against the cached
+// vector it now reuses.
+fn bench_table_load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("table_load");
+
+    group.bench_function("recompute 2^16", |b| {
+        b.iter(|| black_box((0..RANGE as u64).map(Fp::from).collect::<Vec<_>>()))
+    });
+
+    range_values::<Fp>(RANGE);
+    group.bench_function("cached 2^16", |b| b.iter(|| black_box(range_values::<Fp>(RANGE))));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_table_load);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+#![no_main]
+
+use ff::{Field, PrimeFieldBits};
+use halo2_examples::decompose::helpers::{compute_running_sum, lebs2ip, recompose, Decomposition};
+use halo2_proofs::{arithmetic::FieldExt, pasta::Fp, plonk::Assigned};
+use libfuzzer_sys::fuzz_target;
+
+// [num_bits, lookup_num_bits, value bytes..]
+fuzz_target!(|data: &[u8]| {
+    let (num_bits, lookup_num_bits, bytes) = match data {
+        [num_bits, lookup_num_bits, bytes @ ..] => (*num_bits as usize, *lookup_num_bits as usize, bytes),
+        _ => return,
+    };
+
+    let mut wide = [0u8; 64];
+    let len = bytes.len().min(64);
+    wide[..len].copy_from_slice(&bytes[..len]);
+    let value = Fp::from_bytes_wide(&wide);
+
+    let bits: Vec<bool> = value.to_le_bits().iter().by_vals().take(num_bits).collect();
+    assert_eq!(lebs2ip(&bits).is_some(), bits.len() <= 64);
+
+    if let Some(decomposition) = compute_running_sum(Assigned::from(value), num_bits, lookup_num_bits) {
+        let Decomposition { chunks, running_sum } = decomposition;
+        assert_eq!(running_sum.len(), num_bits / lookup_num_bits);
+        assert_eq!(chunks.len(), running_sum.len());
+
+        // every chunk is K bits, and the chunks with what is left of the running sum make up
+        // the value: value = sum c_i * 2^{K*i} + z_C * 2^num_bits
+        if lookup_num_bits < 128 {
+            assert!(chunks.iter().all(|chunk| chunk.get_lower_128() >> lookup_num_bits == 0));
+        }
+        let z_c = running_sum.last().map(|z| z.evaluate()).unwrap_or(value);
+        let high = Fp::from(2).pow_vartime([num_bits as u64]);
+        assert_eq!(recompose(&chunks, lookup_num_bits) + z_c * high, value);
+
+        // a value that fits in `num_bits` is fully consumed by the running sum
+        let fits = value.to_le_bits().iter().by_vals().skip(num_bits).all(|bit| !bit);
+        if fits {
+            assert_eq!(z_c, Fp::zero());
+            assert_eq!(recompose(&chunks, lookup_num_bits), value);
+        }
+    }
+});
@@ -0,0 +1,33 @@
+#![no_main]
+
+use std::sync::OnceLock;
+
+use halo2_examples::fibonaci::example1::FiboCircuit;
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_vk, verify_proof, SingleVerifier, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Challenge255},
+};
+use libfuzzer_sys::fuzz_target;
+
+const K: u32 = 4;
+
+fn setup() -> &'static (Params<EqAffine>, VerifyingKey<EqAffine>) {
+    static SETUP: OnceLock<(Params<EqAffine>, VerifyingKey<EqAffine>)> = OnceLock::new();
+    SETUP.get_or_init(|| {
+        let params = Params::new(K);
+        let vk = keygen_vk(&params, &FiboCircuit::<Fp>::default()).unwrap();
+        (params, vk)
+    })
+}
+
+// Arbitrary bytes read as a proof for the Fibonacci example must be rejected, never panic.
+fuzz_target!(|data: &[u8]| {
+    let (params, vk) = setup();
+    let instances = [Fp::from(1), Fp::from(1), Fp::from(55)];
+
+    let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(data);
+    let strategy = SingleVerifier::new(params);
+    assert!(verify_proof(params, vk, strategy, &[&[&instances]], &mut transcript).is_err());
+});
@@ -0,0 +1,40 @@
+#![no_main]
+
+use halo2_examples::{
+    validate::ValidateWitness,
+    witness::WitnessFile,
+};
+use halo2_proofs::pasta::Fp;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let witness = match std::str::from_utf8(data).map(WitnessFile::from_json) {
+        Ok(Ok(witness)) => witness,
+        _ => return,
+    };
+
+    // every loaded witness must either convert into its circuit or be rejected with an error
+    match &witness {
+        WitnessFile::Fibonacci(fibo) => {
+            if let (Ok(circuit), Ok(instances)) = (fibo.example1_circuit::<Fp>(), fibo.public_inputs::<Fp>()) {
+                let _ = circuit.validate_witness(&instances);
+            }
+        }
+        WitnessFile::RangeCheck(range_check) => {
+            if let Ok(circuit) = range_check.example1_circuit::<Fp, 8>() {
+                let _ = circuit.validate_witness(&range_check.public_inputs());
+            }
+            if let Ok(circuit) = range_check.example2_circuit::<Fp, 8, 4>() {
+                let _ = circuit.validate_witness(&range_check.public_inputs());
+            }
+        }
+        WitnessFile::Decompose(decompose) => {
+            if let Ok(circuit) = decompose.circuit::<Fp, 8, 256>() {
+                let _ = circuit.validate_witness(&decompose.public_inputs());
+            }
+        }
+    }
+
+    let json = witness.to_json().unwrap();
+    assert_eq!(WitnessFile::from_json(&json).unwrap(), witness);
+});
@@ -0,0 +1,222 @@
+//! `gadgets::bits` against the new frontend: the same running sum from the most significant
+//! bit down,
+//!
+//!     acc_0 = 0,  acc_{i+1} = 2 * acc_i + b_{n-1-i},  acc_n = b_0 + 2 * b_1 + ... + 2^{n-1} * b_{n-1}
+//!
+//! with the bits of a value read from its little-endian repr, as ff 0.13 fields need not
+//! implement `PrimeFieldBits`, and no more than `F::CAPACITY` of them.
+
+use std::marker::PhantomData;
+
+use halo2_next::{circuit::*, halo2curves::ff::PrimeField, plonk::*, poly::Rotation};
+
+#[derive(Debug, Clone)]
+pub struct BitsConfig {
+    bit: Column<Advice>,
+    acc: Column<Advice>,
+    q_bit: Selector,
+    instance: Column<Instance>,
+}
+
+pub struct BitsChip<F: PrimeField> {
+    config: BitsConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> Chip<F> for BitsChip<F> {
+    type Config = BitsConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+/// Where `lay_out` gets a bit from.
+enum Bit<'a, F: PrimeField> {
+    Copied(&'a AssignedCell<F, F>),
+    Witnessed(Value<F>),
+}
+
+/// Bit `i` of `value`, from its little-endian repr.
+fn le_bit<F: PrimeField>(value: &F, i: usize) -> bool {
+    let repr = value.to_repr();
+    (repr.as_ref()[i / 8] >> (i % 8)) & 1 == 1
+}
+
+impl<F: PrimeField> BitsChip<F> {
+    pub fn construct(config: BitsConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> BitsConfig {
+        let bit = meta.advice_column();
+        let acc = meta.advice_column();
+        let q_bit = meta.selector();
+
+        // need a fixed column for the initial `acc_0 == 0`
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        meta.enable_equality(bit);
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+
+        meta.create_gate("bits", |meta| {
+            let q_bit = meta.query_selector(q_bit);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            Constraints::with_selector(
+                q_bit,
+                [
+                    ("bit is boolean", bit.clone() * (Expression::Constant(F::ONE) - bit.clone())),
+                    ("acc = 2 * acc_prev + bit", acc - acc_prev * F::from(2) - bit),
+                ],
+            )
+        });
+
+        BitsConfig { bit, acc, q_bit, instance }
+    }
+
+    /// `bits[0] + 2 * bits[1] + ... + 2^{n-1} * bits[n-1]`, after checking every bit is 0 or 1.
+    pub fn pack_bits(&self, layouter: impl Layouter<F>, bits: &[AssignedCell<F, F>]) -> Result<AssignedCell<F, F>, Error> {
+        let bits: Vec<_> = bits.iter().map(Bit::Copied).collect();
+        let (_, packed) = self.lay_out(layouter, "pack bits", &bits, None)?;
+        Ok(packed)
+    }
+
+    /// The `num_bits` low bits of `value`, little-endian, after checking there are no others.
+    pub fn unpack(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let bits: Vec<_> = (0..num_bits)
+            .map(|i| Bit::Witnessed(value.value().map(|value| F::from(le_bit(value, i) as u64))))
+            .collect();
+        let (bits, _) = self.lay_out(layouter, "unpack", &bits, Some(value))?;
+        Ok(bits)
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+
+    // Lays out the running sum over `bits`, given little-endian, and ties `acc_n` to `value`
+    // if there is one. Returns the bit cells, little-endian, and `acc_n`.
+    fn lay_out(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        bits: &[Bit<'_, F>],
+        value: Option<&AssignedCell<F, F>>,
+    ) -> Result<(Vec<AssignedCell<F, F>>, AssignedCell<F, F>), Error> {
+        if bits.len() > F::CAPACITY as usize {
+            return Err(Error::Synthesis);
+        }
+
+        layouter.assign_region(
+            || name,
+            |mut region| {
+                let mut acc = region.assign_advice_from_constant(|| "acc_0", self.config.acc, 0, F::ZERO)?;
+                let mut cells = Vec::with_capacity(bits.len());
+
+                for (i, bit) in bits.iter().enumerate().rev() {
+                    let offset = bits.len() - i;
+                    self.config.q_bit.enable(&mut region, offset)?;
+                    let cell = match bit {
+                        Bit::Copied(cell) => cell.copy_advice(|| format!("b_{}", i), &mut region, self.config.bit, offset)?,
+                        Bit::Witnessed(bit) => region.assign_advice(|| format!("b_{}", i), self.config.bit, offset, || *bit)?,
+                    };
+
+                    let next = acc.value().copied() * Value::known(F::from(2)) + cell.value().copied();
+                    acc = region.assign_advice(|| format!("acc_{}", offset), self.config.acc, offset, || next)?;
+                    cells.push(cell);
+                }
+
+                if let Some(value) = value {
+                    region.constrain_equal(value.cell(), acc.cell())?;
+                }
+                cells.reverse();
+                Ok((cells, acc))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_next::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::ff::PrimeField,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+
+    use super::{BitsChip, BitsConfig};
+
+    const K: u32 = 8;
+
+    /// Unpacks a private value into `num_bits` bits and packs them back into the public input.
+    struct BitsCircuit<F> {
+        value: Value<F>,
+        num_bits: usize,
+    }
+
+    impl<F: PrimeField> Circuit<F> for BitsCircuit<F> {
+        type Config = (BitsConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: Value::unknown(),
+                num_bits: self.num_bits,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (BitsChip::configure(meta, instance), input)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+            let (config, input) = config;
+            let chip = BitsChip::construct(config);
+
+            let value = layouter.assign_region(|| "load value", |mut region| region.assign_advice(|| "value", input, 0, || self.value))?;
+            let bits = chip.unpack(layouter.namespace(|| "unpack"), &value, self.num_bits)?;
+            let packed = chip.pack_bits(layouter.namespace(|| "pack"), &bits)?;
+            chip.expose_public(layouter.namespace(|| "packed"), &packed, 0)
+        }
+    }
+
+    fn test_bits<F: PrimeField + Ord>() {
+        for (value, num_bits) in [(0xdead_beefu64, 32), (0, 8), (u64::MAX, 64)] {
+            let circuit = BitsCircuit {
+                value: Value::known(F::from(value)),
+                num_bits,
+            };
+            MockProver::run(K, &circuit, vec![vec![F::from(value)]]).unwrap().assert_satisfied();
+        }
+
+        let circuit = BitsCircuit {
+            value: Value::known(F::from(1u64 << 32)),
+            num_bits: 32,
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![F::from(1u64 << 32)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    test_over_fields!(test_bits);
+}
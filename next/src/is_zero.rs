@@ -0,0 +1,135 @@
+//! `gadgets::is_zero` against the new frontend: the gate and the witness are the same, only
+//! the field bound and its constants change.
+
+use halo2_next::{arithmetic::Field, circuit::*, plonk::*, poly::Rotation};
+
+#[derive(Debug, Clone)]
+pub struct IsZeroConfig<F: Field> {
+    pub value_inv: Column<Advice>,
+    pub is_zero_expr: Expression<F>,
+}
+
+pub struct IsZeroChip<F: Field> {
+    config: IsZeroConfig<F>,
+}
+
+impl<F: Field> Chip<F> for IsZeroChip<F> {
+    type Config = IsZeroConfig<F>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: Field> IsZeroChip<F> {
+    pub fn construct(config: IsZeroConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value_inv: Column<Advice>,
+    ) -> IsZeroConfig<F> {
+        let mut is_zero_expr = Expression::Constant(F::ZERO);
+
+        meta.create_gate("is_zero", |meta| {
+            let value = value(meta);
+            let q_enable = q_enable(meta);
+            let value_inv = meta.query_advice(value_inv, Rotation::cur());
+
+            is_zero_expr = Expression::Constant(F::ONE) - value.clone() * value_inv;
+            vec![q_enable * value * is_zero_expr.clone()]
+        });
+
+        IsZeroConfig { value_inv, is_zero_expr }
+    }
+
+    pub fn assign(&self, region: &mut Region<'_, F>, offset: usize, value: Value<F>) -> Result<(), Error> {
+        let value_inv = value.map(|v| v.invert().unwrap_or(F::ZERO));
+        region.assign_advice(|| "value invert", self.config.value_inv, offset, || value_inv)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_next::{
+        arithmetic::Field,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+        poly::Rotation,
+    };
+
+    use super::{IsZeroChip, IsZeroConfig};
+
+    /// Exposes whether a private value is zero.
+    struct IsZeroCircuit<F> {
+        value: Value<F>,
+    }
+
+    impl<F: Field> Circuit<F> for IsZeroCircuit<F> {
+        type Config = (IsZeroConfig<F>, Column<Advice>, Column<Advice>, Selector, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { value: Value::unknown() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let [value, value_inv, out] = [(); 3].map(|_| meta.advice_column());
+            let q = meta.selector();
+            let instance = meta.instance_column();
+            meta.enable_equality(out);
+            meta.enable_equality(instance);
+
+            let is_zero = IsZeroChip::configure(
+                meta,
+                |meta| meta.query_selector(q),
+                |meta| meta.query_advice(value, Rotation::cur()),
+                value_inv,
+            );
+            let is_zero_expr = is_zero.is_zero_expr.clone();
+            meta.create_gate("out", |meta| {
+                let q = meta.query_selector(q);
+                let out = meta.query_advice(out, Rotation::cur());
+                vec![q * (out - is_zero_expr)]
+            });
+            (is_zero, value, out, q, instance)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+            let (is_zero, value, out, q, instance) = config;
+            let chip = IsZeroChip::construct(is_zero);
+            let cell = layouter.assign_region(
+                || "is zero",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    region.assign_advice(|| "value", value, 0, || self.value)?;
+                    chip.assign(&mut region, 0, self.value)?;
+                    let is_zero = self.value.map(|value| if value == F::ZERO { F::ONE } else { F::ZERO });
+                    region.assign_advice(|| "out", out, 0, || is_zero)
+                },
+            )?;
+            layouter.constrain_instance(cell.cell(), instance, 0)
+        }
+    }
+
+    fn test_is_zero<F: Field + From<u64> + Ord>() {
+        for (value, is_zero) in [(0, 1), (7, 0)] {
+            let circuit = IsZeroCircuit { value: Value::known(F::from(value)) };
+            MockProver::run(4, &circuit, vec![vec![F::from(is_zero)]]).unwrap().assert_satisfied();
+            let prover = MockProver::run(4, &circuit, vec![vec![F::from(1 - is_zero)]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    test_over_fields!(test_is_zero);
+}
@@ -0,0 +1,52 @@
+//! Ports of the core chips to the current halo2 frontend, the one modern tutorials follow.
+//! They live in a crate of their own because that frontend is only published as a git
+//! tag; `halo2-examples` itself stays on halo2_proofs 0.2 and the Pasta curves and builds
+//! from crates.io alone.
+//!
+//! The new API differs from 0.2 in three ways that reach the chips:
+//!
+//! - fields are bounded by `ff::Field` and `ff::PrimeField` (ff 0.13) instead of
+//!   `FieldExt`, with `F::ZERO` and `F::ONE` for `F::zero()` and `F::one()`, and bits taken
+//!   from the little-endian repr instead of `PrimeFieldBits`;
+//! - advice columns belong to a phase, and a gate can query a verifier challenge drawn
+//!   after the earlier phases are committed, which `layouter.get_challenge` returns as a
+//!   `Value`;
+//! - errors are the new `plonk::Error`, so the chips here return it directly instead of
+//!   `Halo2ExamplesError`, which wraps the 0.2 one.
+//!
+//! The modules are named after the `halo2-examples` modules they port. `multiset` shows
+//! what challenges buy: the shuffle check that `gadgets::multiset` has to derive `gamma`
+//! for with an in-circuit Poseidon chain takes it from the verifier here, and `lookup_rlc`
+//! folds a multi-column lookup into one expression with one.
+
+/// Runs a test written generically over the field on the Pasta fields and on the bn254
+/// scalar field, like `test_over_fields!` in `halo2-examples`, which cannot take bn254 on
+/// halo2_proofs 0.2: its `FieldExt` is implemented for the Pasta fields only.
+#[cfg(test)]
+macro_rules! test_over_fields {
+    ($($test:ident),+ $(,)?) => {
+        $(
+            mod $test {
+                #[test]
+                fn fp() {
+                    super::$test::<halo2_next::halo2curves::pasta::Fp>();
+                }
+
+                #[test]
+                fn fq() {
+                    super::$test::<halo2_next::halo2curves::pasta::Fq>();
+                }
+
+                #[test]
+                fn bn256() {
+                    super::$test::<halo2_next::halo2curves::bn256::Fr>();
+                }
+            }
+        )+
+    };
+}
+
+pub mod bits;
+pub mod is_zero;
+pub mod lookup_rlc;
+pub mod multiset;
@@ -0,0 +1,279 @@
+//! A three-column lookup, `c = a ^ b` on nibbles, two ways: as a tuple, one input
+//! expression per column, and compressed into one by a random linear combination with a
+//! verifier challenge `theta`, the way the zkEVM circuits look up their keccak and bytecode
+//! tables:
+//!
+//!     (a, b, c) in T   iff   a + theta * b + theta^2 * c  in  { t_a + theta * t_b + theta^2 * t_c }
+//!
+//! with overwhelming probability over `theta`, drawn after `a`, `b` and `c` are committed.
+//!
+//!       a     |    b     |    c     |  q_xor  ||  t_a  |  t_b  |  t_c (fixed)
+//!     -------------------------------------------------------------------------
+//!      a_0    |   b_0    |   c_0    |    1    ||   0   |   0   |    0
+//!      ...    |   ...    |   ...    |    1    ||  ...  |  ...  |   ...
+//!                                             ||  15   |  15   |    0
+//!
+//! halo2 already folds the columns of a tuple lookup with a `theta` of its own inside the
+//! argument, so for a single table both cost the same. Compressing in the circuit pays off
+//! once the compressed value is a cell of its own: it can sit in a second-phase advice column
+//! and be copied around, and tables of different widths can share a column.
+//!
+//! The base has to be a challenge. With a constant base a prover picks the tuple after
+//! seeing it, and finds one outside the table that compresses onto an entry, e.g.
+//! `(17, 1, 3)` onto `(1, 2, 3)` with base 16.
+
+use std::marker::PhantomData;
+
+use halo2_next::{circuit::*, halo2curves::ff::PrimeField, plonk::*, poly::Rotation};
+
+/// The bits of `a` and `b`.
+pub const NIBBLE_BITS: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct XorTableConfig {
+    a: Column<Fixed>,
+    b: Column<Fixed>,
+    c: Column<Fixed>,
+}
+
+#[derive(Debug, Clone)]
+pub struct XorConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    q_xor: Selector,
+    table: XorTableConfig,
+}
+
+pub struct XorChip<F: PrimeField> {
+    config: XorConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> Chip<F> for XorChip<F> {
+    type Config = XorConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: PrimeField> XorChip<F> {
+    pub fn construct(config: XorConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The naive lookup: `(a, b, c)` against `(t_a, t_b, t_c)`, column by column.
+    pub fn configure_tuple(meta: &mut ConstraintSystem<F>) -> XorConfig {
+        let config = Self::columns(meta);
+        let XorConfig { a, b, c, q_xor, ref table } = config;
+        let t = [table.a, table.b, table.c];
+
+        meta.lookup_any("xor", |meta| {
+            let q_xor = meta.query_selector(q_xor);
+            [a, b, c]
+                .into_iter()
+                .zip(t)
+                .map(|(input, t)| (q_xor.clone() * meta.query_advice(input, Rotation::cur()), meta.query_fixed(t, Rotation::cur())))
+                .collect()
+        });
+        config
+    }
+
+    /// The compressed lookup, one input expression folded with the challenge `theta`.
+    pub fn configure_rlc(meta: &mut ConstraintSystem<F>) -> XorConfig {
+        let theta = meta.challenge_usable_after(FirstPhase);
+        Self::configure_compressed(meta, |meta| meta.query_challenge(theta))
+    }
+
+    // The compressed lookup over any base, for the test that shows why it must be a
+    // challenge.
+    fn configure_compressed(meta: &mut ConstraintSystem<F>, base: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>) -> XorConfig {
+        let config = Self::columns(meta);
+        let XorConfig { a, b, c, q_xor, ref table } = config;
+        let t = [table.a, table.b, table.c];
+
+        meta.lookup_any("xor rlc", |meta| {
+            let q_xor = meta.query_selector(q_xor);
+            let base = base(meta);
+            let [a, b, c] = [a, b, c].map(|column| meta.query_advice(column, Rotation::cur()));
+            let [t_a, t_b, t_c] = t.map(|column| meta.query_fixed(column, Rotation::cur()));
+
+            // rows with q_xor off look up 0, which (0, 0, 0) compresses to
+            let rlc = |x: Expression<F>, y: Expression<F>, z: Expression<F>| x + base.clone() * (y + base.clone() * z);
+            vec![(q_xor * rlc(a, b, c), rlc(t_a, t_b, t_c))]
+        });
+        config
+    }
+
+    fn columns(meta: &mut ConstraintSystem<F>) -> XorConfig {
+        let [a, b, c] = [(); 3].map(|_| meta.advice_column());
+        let [t_a, t_b, t_c] = [(); 3].map(|_| meta.fixed_column());
+        XorConfig {
+            a,
+            b,
+            c,
+            q_xor: meta.complex_selector(),
+            table: XorTableConfig { a: t_a, b: t_b, c: t_c },
+        }
+    }
+
+    /// Fills the table with every `(a, b, a ^ b)` on nibbles.
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let table = &self.config.table;
+        layouter.assign_region(
+            || "xor table",
+            |mut region| {
+                for (row, (a, b)) in (0..1u64 << NIBBLE_BITS).flat_map(|a| (0..1u64 << NIBBLE_BITS).map(move |b| (a, b))).enumerate() {
+                    region.assign_fixed(|| "t_a", table.a, row, || Value::known(F::from(a)))?;
+                    region.assign_fixed(|| "t_b", table.b, row, || Value::known(F::from(b)))?;
+                    region.assign_fixed(|| "t_c", table.c, row, || Value::known(F::from(a ^ b)))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Checks `c = a ^ b` on every row, with `a` and `b` nibbles.
+    pub fn assign(&self, mut layouter: impl Layouter<F>, rows: &[[Value<F>; 3]]) -> Result<Vec<[AssignedCell<F, F>; 3]>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "xor",
+            |mut region| {
+                rows.iter()
+                    .enumerate()
+                    .map(|(offset, [a, b, c])| {
+                        config.q_xor.enable(&mut region, offset)?;
+                        Ok([
+                            region.assign_advice(|| "a", config.a, offset, || *a)?,
+                            region.assign_advice(|| "b", config.b, offset, || *b)?,
+                            region.assign_advice(|| "c", config.c, offset, || *c)?,
+                        ])
+                    })
+                    .collect()
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::marker::PhantomData;
+
+    use halo2_next::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::{ff::PrimeField, pasta},
+        plonk::{Circuit, ConstraintSystem, Error, Expression},
+    };
+
+    use super::{XorChip, XorConfig};
+
+    const K: u32 = 9;
+
+    trait Lookup {
+        fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>) -> XorConfig;
+    }
+
+    struct Tuple;
+    struct Rlc;
+    /// The compressed lookup with the constant base 16, unsound.
+    struct Base16;
+
+    impl Lookup for Tuple {
+        fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>) -> XorConfig {
+            XorChip::configure_tuple(meta)
+        }
+    }
+
+    impl Lookup for Rlc {
+        fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>) -> XorConfig {
+            XorChip::configure_rlc(meta)
+        }
+    }
+
+    impl Lookup for Base16 {
+        fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>) -> XorConfig {
+            XorChip::configure_compressed(meta, |_| Expression::Constant(F::from(16)))
+        }
+    }
+
+    /// Checks every row is a XOR of two nibbles.
+    struct XorCircuit<L> {
+        rows: Vec<[u64; 3]>,
+        _marker: PhantomData<L>,
+    }
+
+    impl<L> XorCircuit<L> {
+        fn new(rows: &[[u64; 3]]) -> Self {
+            Self {
+                rows: rows.to_vec(),
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    impl<F: PrimeField, L: Lookup> Circuit<F> for XorCircuit<L> {
+        type Config = XorConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::new(&self.rows)
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            L::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+            let chip = XorChip::construct(config);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            let rows: Vec<_> = self.rows.iter().map(|row| row.map(|x| Value::known(F::from(x)))).collect();
+            chip.assign(layouter.namespace(|| "xor"), &rows)?;
+            Ok(())
+        }
+    }
+
+    fn verifies<F: PrimeField + Ord, L: Lookup>(rows: &[[u64; 3]]) -> bool {
+        MockProver::<F>::run(K, &XorCircuit::<L>::new(rows), vec![]).unwrap().verify().is_ok()
+    }
+
+    fn test_xor_lookup<F: PrimeField + Ord>() {
+        let valid = [[1, 2, 3], [15, 15, 0], [9, 6, 15], [0, 0, 0]];
+        assert!(verifies::<F, Tuple>(&valid));
+        assert!(verifies::<F, Rlc>(&valid));
+
+        // a wrong XOR
+        assert!(!verifies::<F, Tuple>(&[[1, 2, 4]]));
+        assert!(!verifies::<F, Rlc>(&[[1, 2, 4]]));
+
+        // 17 + 16 * (1 + 16 * 3) = 1 + 16 * (2 + 16 * 3), not a nibble and not a XOR
+        let forged = [[17, 1, 3]];
+        assert!(!verifies::<F, Tuple>(&forged));
+        assert!(!verifies::<F, Rlc>(&forged));
+        assert!(verifies::<F, Base16>(&forged));
+    }
+
+    test_over_fields!(test_xor_lookup);
+
+    #[test]
+    fn test_xor_lookup_shape() {
+        let lookup = |configure: fn(&mut ConstraintSystem<pasta::Fp>) -> XorConfig| {
+            let mut meta = ConstraintSystem::default();
+            configure(&mut meta);
+            let lookups = meta.lookups();
+            assert_eq!(lookups.len(), 1);
+            (lookups[0].input_expressions().len(), meta.num_challenges())
+        };
+        assert_eq!(lookup(XorChip::configure_tuple), (3, 0));
+        assert_eq!(lookup(XorChip::configure_rlc), (1, 1));
+    }
+}
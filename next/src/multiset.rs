@@ -0,0 +1,202 @@
+//! `gadgets::multiset` with `gamma` drawn by the verifier: `a` is a permutation of `b` iff
+//! `prod(a_i + gamma) == prod(b_i + gamma)` for a random `gamma` chosen after both lists are
+//! committed, which a challenge is.
+//!
+//! The lists are witnessed in the first phase, and a running product in the second, once
+//! `gamma` is known:
+//!
+//!       a     |    b     |  z (second phase)  |  q_first  |  q_shuffle  |  q_last
+//!     ------------------------------------------------------------------------------
+//!      a_0    |   b_0    |         1          |     1     |      1      |
+//!      ...    |   ...    |        ...         |           |      1      |
+//!    a_{n-1}  | b_{n-1}  |      z_{n-1}       |           |      1      |
+//!             |          |        z_n         |           |             |     1
+//!
+//!     z_0 = 1,   z_{i+1} * (b_i + gamma) = z_i * (a_i + gamma),   z_n = 1
+//!
+//! No hashing is needed, where `gadgets::multiset` spends a Poseidon permutation per value.
+
+use halo2_next::{
+    arithmetic::Field,
+    circuit::*,
+    plonk::{Advice, Challenge, Column, ConstraintSystem, Constraints, Error, Expression, FirstPhase, SecondPhase, Selector},
+    poly::Rotation,
+};
+
+#[derive(Debug, Clone)]
+pub struct ShuffleConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    z: Column<Advice>,
+    gamma: Challenge,
+    q_first: Selector,
+    q_shuffle: Selector,
+    q_last: Selector,
+}
+
+pub struct ShuffleChip<F: Field> {
+    config: ShuffleConfig,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: Field> Chip<F> for ShuffleChip<F> {
+    type Config = ShuffleConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: Field> ShuffleChip<F> {
+    pub fn construct(config: ShuffleConfig) -> Self {
+        Self {
+            config,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> ShuffleConfig {
+        let [a, b] = [(); 2].map(|_| meta.advice_column_in(FirstPhase));
+        let gamma = meta.challenge_usable_after(FirstPhase);
+        let z = meta.advice_column_in(SecondPhase);
+        let [q_first, q_shuffle, q_last] = [(); 3].map(|_| meta.selector());
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+
+        meta.create_gate("shuffle", |meta| {
+            let [q_first, q_shuffle, q_last] = [q_first, q_shuffle, q_last].map(|q| meta.query_selector(q));
+            let [a, b] = [a, b].map(|column| meta.query_advice(column, Rotation::cur()));
+            let z_next = meta.query_advice(z, Rotation::next());
+            let z = meta.query_advice(z, Rotation::cur());
+            let gamma = meta.query_challenge(gamma);
+            let one = Expression::Constant(F::ONE);
+
+            Constraints::without_selector([
+                ("z_0 = 1", q_first * (one.clone() - z.clone())),
+                ("z_next * (b + gamma) = z * (a + gamma)", q_shuffle * (z_next * (b + gamma.clone()) - z.clone() * (a + gamma))),
+                ("z_n = 1", q_last * (one - z)),
+            ])
+        });
+
+        ShuffleConfig {
+            a,
+            b,
+            z,
+            gamma,
+            q_first,
+            q_shuffle,
+            q_last,
+        }
+    }
+
+    /// Checks `a` is a permutation of `b`. The cells must have been assigned in the first
+    /// phase.
+    pub fn assert_shuffle(&self, mut layouter: impl Layouter<F>, a: &[AssignedCell<F, F>], b: &[AssignedCell<F, F>]) -> Result<(), Error> {
+        if a.len() != b.len() || a.is_empty() {
+            return Err(Error::Synthesis);
+        }
+        let config = &self.config;
+        let gamma = layouter.get_challenge(config.gamma);
+
+        layouter.assign_region(
+            || "shuffle",
+            |mut region| {
+                config.q_first.enable(&mut region, 0)?;
+                let mut z = Value::known(F::ONE);
+                region.assign_advice(|| "z_0", config.z, 0, || z)?;
+                for (i, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                    config.q_shuffle.enable(&mut region, i)?;
+                    a.copy_advice(|| format!("a_{}", i), &mut region, config.a, i)?;
+                    b.copy_advice(|| format!("b_{}", i), &mut region, config.b, i)?;
+
+                    let ratio = a.value().zip(b.value()).zip(gamma).map(|((a, b), gamma)| {
+                        (*a + gamma) * (*b + gamma).invert().unwrap_or(F::ZERO)
+                    });
+                    z = z * ratio;
+                    region.assign_advice(|| format!("z_{}", i + 1), config.z, i + 1, || z)?;
+                }
+                config.q_last.enable(&mut region, a.len())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_next::{
+        arithmetic::Field,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+
+    use super::{ShuffleChip, ShuffleConfig};
+
+    const K: u32 = 5;
+
+    /// Checks two private lists hold the same values.
+    struct ShuffleCircuit {
+        a: Vec<u64>,
+        b: Vec<u64>,
+    }
+
+    impl<F: Field + From<u64>> Circuit<F> for ShuffleCircuit {
+        type Config = (ShuffleConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: self.a.clone(),
+                b: self.b.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            (ShuffleChip::configure(meta), input)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+            let (config, input) = config;
+            let chip = ShuffleChip::construct(config);
+
+            let mut load = |name: &str, values: &[u64]| {
+                layouter.assign_region(
+                    || name.to_string(),
+                    |mut region| {
+                        values
+                            .iter()
+                            .enumerate()
+                            .map(|(i, value)| region.assign_advice(|| format!("{}_{}", name, i), input, i, || Value::known(F::from(*value))))
+                            .collect::<Result<Vec<_>, _>>()
+                    },
+                )
+            };
+            let a = load("a", &self.a)?;
+            let b = load("b", &self.b)?;
+            chip.assert_shuffle(layouter.namespace(|| "shuffle"), &a, &b)
+        }
+    }
+
+    fn test_shuffle<F: Field + From<u64> + Ord>() {
+        let circuit = ShuffleCircuit {
+            a: vec![4, 8, 15, 16, 23, 42],
+            b: vec![42, 16, 4, 23, 15, 8],
+        };
+        MockProver::<F>::run(K, &circuit, vec![]).unwrap().assert_satisfied();
+
+        let circuit = ShuffleCircuit {
+            a: vec![4, 8, 15, 16, 23, 42],
+            b: vec![42, 16, 4, 23, 15, 15],
+        };
+        assert!(MockProver::<F>::run(K, &circuit, vec![]).unwrap().verify().is_err());
+    }
+
+    test_over_fields!(test_shuffle);
+}
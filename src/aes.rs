@@ -0,0 +1,189 @@
+//! One round of AES-128 encryption: the initial `AddRoundKey` and a full round,
+//!
+//!     out = AddRoundKey(MixColumns(ShiftRows(SubBytes(AddRoundKey(in, key_0)))), key_1)
+//!
+//! with the plaintext and both round keys private and the 16 output bytes public. The key
+//! schedule is not part of it; the two round keys are inputs.
+//!
+//! The state is 16 byte cells, column by column as in FIPS-197, and every step but one is
+//! made of the lookups of `AesChip`: `SubBytes` is an S-box lookup per byte, `AddRoundKey`
+//! an xor per byte, and `MixColumns` two `xtime`s and four xors per byte, from
+//!
+//!     b_r = 2 * a_r ^ 3 * a_{r+1} ^ a_{r+2} ^ a_{r+3},   3 * a = 2 * a ^ a
+//!
+//! `ShiftRows` costs nothing: it only changes which cell is passed where.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::Halo2ExamplesError,
+    gadgets::aes::{AesChip, AesConfig},
+    public_inputs::{InstanceChip, InstanceConfig},
+    reference::ref_aes_round,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// An AES state, column by column.
+pub type State<F> = [AssignedCell<F, F>; 16];
+
+pub fn add_round_key<F: FieldExt>(chip: &AesChip<F>, mut layouter: impl Layouter<F>, state: &State<F>, key: &State<F>) -> Result<State<F>, Halo2ExamplesError> {
+    let bytes = state
+        .iter()
+        .zip(key)
+        .enumerate()
+        .map(|(i, (byte, key))| chip.xor(layouter.namespace(|| format!("byte {}", i)), byte, key))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(bytes.try_into().expect("16 bytes"))
+}
+
+pub fn sub_bytes<F: FieldExt>(chip: &AesChip<F>, mut layouter: impl Layouter<F>, state: &State<F>) -> Result<State<F>, Halo2ExamplesError> {
+    let bytes = state
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| chip.sub_byte(layouter.namespace(|| format!("byte {}", i)), byte))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(bytes.try_into().expect("16 bytes"))
+}
+
+/// Row `r` moves `r` columns to the left.
+pub fn shift_rows<F: FieldExt>(state: &State<F>) -> State<F> {
+    std::array::from_fn(|i| state[(i + 4 * (i % 4)) % 16].clone())
+}
+
+pub fn mix_columns<F: FieldExt>(chip: &AesChip<F>, mut layouter: impl Layouter<F>, state: &State<F>) -> Result<State<F>, Halo2ExamplesError> {
+    let doubled = state
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| chip.xtime(layouter.namespace(|| format!("2 * byte {}", i)), byte))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let bytes = (0..16)
+        .map(|i| {
+            let (c, r) = (i / 4, i % 4);
+            let at = |j: usize| 4 * c + (r + j) % 4;
+            let mut layouter = layouter.namespace(|| format!("byte {}", i));
+            [&state[at(1)], &state[at(2)], &state[at(3)]]
+                .into_iter()
+                .try_fold(chip.xor(layouter.namespace(|| "2 * a_r ^ 2 * a_r+1"), &doubled[at(0)], &doubled[at(1)])?, |acc, byte| {
+                    chip.xor(layouter.namespace(|| "^ a_r+j"), &acc, byte)
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(bytes.try_into().expect("16 bytes"))
+}
+
+/// Encrypts `plaintext` by one round under `keys` and exposes the result.
+#[derive(Default)]
+pub struct AesRoundCircuit {
+    pub plaintext: [Value<u8>; 16],
+    pub keys: [[Value<u8>; 16]; 2],
+}
+
+impl AesRoundCircuit {
+    /// The public inputs, the 16 bytes of the state after the round.
+    pub fn instances(plaintext: [u8; 16], keys: [[u8; 16]; 2]) -> Vec<u64> {
+        ref_aes_round(plaintext, keys[0], keys[1]).map(u64::from).to_vec()
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for AesRoundCircuit {
+    type Config = (AesConfig<F>, LoadPrivateConfig, InstanceConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let aes = AesChip::configure(meta);
+        let instance = meta.instance_column();
+        let instance = InstanceChip::configure(meta, instance, aes.advice[0]);
+        (aes, LoadPrivateChip::configure(meta, 4), instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (aes_config, load_config, instance_config) = config;
+        let aes = AesChip::construct(aes_config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let instance = InstanceChip::construct(instance_config);
+        aes.load_table(&mut layouter)?;
+
+        let mut load = |name: &str, bytes: &[Value<u8>; 16]| -> Result<State<F>, Halo2ExamplesError> {
+            let values: Vec<_> = bytes.iter().map(|byte| byte.map(|byte| F::from(byte as u64))).collect();
+            let cells = load_chip.load_privates(layouter.namespace(|| format!("load {}", name)), &values)?;
+            Ok(cells.try_into().expect("16 bytes"))
+        };
+        let plaintext = load("plaintext", &self.plaintext)?;
+        let [key_0, key_1] = [load("key 0", &self.keys[0])?, load("key 1", &self.keys[1])?];
+
+        let state = add_round_key(&aes, layouter.namespace(|| "add round key 0"), &plaintext, &key_0)?;
+        let state = sub_bytes(&aes, layouter.namespace(|| "sub bytes"), &state)?;
+        let state = shift_rows(&state);
+        let state = mix_columns(&aes, layouter.namespace(|| "mix columns"), &state)?;
+        let state = add_round_key(&aes, layouter.namespace(|| "add round key 1"), &state, &key_1)?;
+
+        for (i, byte) in state.iter().enumerate() {
+            instance.expose(layouter.namespace(|| format!("output {}", i)), "output", byte, i)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ValidateWitness<F> for AesRoundCircuit {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let bytes = |bytes: &[Value<u8>; 16], name: &'static str| -> Result<[u8; 16], InvalidWitness> {
+            let bytes = bytes.iter().map(|byte| known(*byte, name)).collect::<Result<Vec<_>, _>>()?;
+            Ok(bytes.try_into().expect("16 bytes"))
+        };
+        let plaintext = bytes(&self.plaintext, "plaintext")?;
+        let keys = [bytes(&self.keys[0], "key_0")?, bytes(&self.keys[1], "key_1")?];
+
+        let expected: Vec<_> = Self::instances(plaintext, keys).into_iter().map(F::from).collect();
+        check_instances(instances, &expected)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{arithmetic::Field, circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::AesRoundCircuit;
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 17;
+
+    // the first round of the example in FIPS-197, appendix B
+    const PLAINTEXT: [u8; 16] = [0x32, 0x43, 0xf6, 0xa8, 0x88, 0x5a, 0x30, 0x8d, 0x31, 0x31, 0x98, 0xa2, 0xe0, 0x37, 0x07, 0x34];
+    const KEYS: [[u8; 16]; 2] = [
+        [0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c],
+        [0xa0, 0xfa, 0xfe, 0x17, 0x88, 0x54, 0x2c, 0xb1, 0x23, 0xa3, 0x39, 0x39, 0x2a, 0x6c, 0x76, 0x05],
+    ];
+
+    fn circuit() -> AesRoundCircuit {
+        AesRoundCircuit {
+            plaintext: PLAINTEXT.map(Value::known),
+            keys: KEYS.map(|key| key.map(Value::known)),
+        }
+    }
+
+    #[test]
+    fn test_aes_round() {
+        let circuit = circuit();
+        let instances: Vec<Fp> = AesRoundCircuit::instances(PLAINTEXT, KEYS).into_iter().map(Fp::from).collect();
+        assert_eq!(instances[..4], [0xa4, 0x9c, 0x7f, 0xf2].map(Fp::from));
+        assert_eq!(circuit.validate_witness(&[instances.clone()]), Ok(()));
+
+        let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_aes_round_wrong_output() {
+        let mut instances: Vec<Fp> = AesRoundCircuit::instances(PLAINTEXT, KEYS).into_iter().map(Fp::from).collect();
+        instances[15] += Fp::one();
+
+        let prover = MockProver::run(K, &circuit(), vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! An anonymous airdrop claim: the claimant proves their address is in the airdrop tree,
+//! publishes a nullifier so the same leaf cannot be claimed twice, and names the recipient
+//! of the tokens, without revealing which leaf is theirs.
+//!
+//! Every claimant holds a secret `k`. The airdrop tree commits to their addresses and
+//! amounts, and the nullifier is a second hash of the secret, which nobody can link to an
+//! address without it:
+//!
+//!     address = hash2(k, 0),   leaf = hash2(address, amount),   nullifier = hash2(k, 1)
+//!
+//! with the circuit's `CircuitHasher`, Poseidon unless another is picked.
+//!
+//! The public inputs are the tree's root, the nullifier, the recipient and the amount. The
+//! contract paying out keeps the nullifiers it has seen and rejects a proof whose nullifier
+//! is among them. The recipient takes no part in any constraint but the copy to its instance
+//! row; that is enough, as the verifier commits to every instance in the transcript, so a
+//! proof taken from the mempool fails for any other recipient.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    gadgets::{
+        commit::CommitChip,
+        hasher::CircuitHasher,
+        merkle::{root_from_path, MerkleChip, MerkleConfig},
+    },
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The second input of the hash giving the address.
+const ADDRESS_TAG: u64 = 0;
+
+/// The second input of the hash giving the nullifier.
+const NULLIFIER_TAG: u64 = 1;
+
+/// The address of the claimant holding `secret`.
+pub fn address<H: CircuitHasher<Fp>>(secret: Fp) -> Fp {
+    H::hash2_native(secret, Fp::from(ADDRESS_TAG))
+}
+
+/// The leaf granting `amount` to `address`.
+pub fn leaf<H: CircuitHasher<Fp>>(address: Fp, amount: u64) -> Fp {
+    H::hash2_native(address, Fp::from(amount))
+}
+
+/// The nullifier of the claim of `secret`.
+pub fn nullifier<H: CircuitHasher<Fp>>(secret: Fp) -> Fp {
+    H::hash2_native(secret, Fp::from(NULLIFIER_TAG))
+}
+
+/// Claims the leaf at `index` of a tree of depth `DEPTH` for `recipient`, hashing with `H`.
+pub struct AirdropCircuit<const DEPTH: usize, H: CircuitHasher<Fp> = CommitChip> {
+    pub secret: Value<Fp>,
+    pub amount: Value<u64>,
+    pub index: Value<u64>,
+    pub siblings: [Value<Fp>; DEPTH],
+    pub recipient: Value<Fp>,
+    pub hasher: PhantomData<H>,
+}
+
+impl<const DEPTH: usize, H: CircuitHasher<Fp>> AirdropCircuit<DEPTH, H> {
+    /// The root, the nullifier, the recipient and the amount, in instance order.
+    pub fn outputs(root: Fp, secret: Fp, recipient: Fp, amount: u64) -> [Fp; 4] {
+        [root, nullifier::<H>(secret), recipient, Fp::from(amount)]
+    }
+}
+
+impl<const DEPTH: usize, H: CircuitHasher<Fp>> Circuit<Fp> for AirdropCircuit<DEPTH, H> {
+    type Config = (MerkleConfig<H>, LoadPrivateConfig, PublicOutputsConfig, ConstantsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            secret: Value::unknown(),
+            amount: Value::unknown(),
+            index: Value::unknown(),
+            siblings: [Value::unknown(); DEPTH],
+            recipient: Value::unknown(),
+            hasher: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let merkle = MerkleChip::configure(meta);
+        (
+            merkle,
+            LoadPrivateChip::configure(meta, 1),
+            PublicOutputs::configure(meta, instance),
+            ConstantsChip::configure(meta),
+        )
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, load_config, outputs_config, constants_config) = config;
+        let chip = MerkleChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+        let constants = ConstantsChip::construct(constants_config);
+
+        let values = [self.secret, self.amount.map(Fp::from), self.recipient];
+        let [secret, amount, recipient]: [_; 3] = load_chip
+            .load_privates(layouter.namespace(|| "load claim"), &values)?
+            .try_into()
+            .expect("three cells loaded");
+        let address_tag = constants.load_constant(layouter.namespace(|| "address tag"), "address tag", Fp::from(ADDRESS_TAG))?;
+        let nullifier_tag =
+            constants.load_constant(layouter.namespace(|| "nullifier tag"), "nullifier tag", Fp::from(NULLIFIER_TAG))?;
+
+        // 1. The leaf is in the tree.
+        let address = chip.hasher().hash2(layouter.namespace(|| "address"), &secret, &address_tag)?;
+        let leaf = chip.hasher().hash2(layouter.namespace(|| "leaf"), &address, &amount)?;
+        let bits: Vec<_> = (0..DEPTH)
+            .map(|i| self.index.map(|index| Fp::from((index >> i) & 1)))
+            .collect();
+        let bits = load_chip.load_privates(layouter.namespace(|| "load index bits"), &bits)?;
+        let siblings = load_chip.load_privates(layouter.namespace(|| "load siblings"), &self.siblings)?;
+        let root = chip.compute_root(layouter.namespace(|| "root"), &leaf, &bits, &siblings)?;
+
+        // 2. Its nullifier.
+        let nullifier = chip.hasher().hash2(layouter.namespace(|| "nullifier"), &secret, &nullifier_tag)?;
+
+        outputs.expose("root", &root);
+        outputs.expose("nullifier", &nullifier);
+        outputs.expose("recipient", &recipient);
+        outputs.expose("amount", &amount);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl<const DEPTH: usize, H: CircuitHasher<Fp>> ValidateWitness<Fp> for AirdropCircuit<DEPTH, H> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let secret = known(self.secret, "secret")?;
+        let amount = known(self.amount, "amount")?;
+        let index = known(self.index, "index")?;
+        if index >> DEPTH != 0 {
+            return Err(InvalidWitness::InvalidParameter(format!(
+                "index {} is outside a tree of depth {}",
+                index, DEPTH
+            )));
+        }
+        let siblings = self
+            .siblings
+            .iter()
+            .map(|sibling| known(*sibling, "siblings"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let root = root_from_path::<H>(leaf::<H>(address::<H>(secret), amount), index, &siblings);
+        check_instances(instances, &Self::outputs(root, secret, known(self.recipient, "recipient")?, amount))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::marker::PhantomData;
+
+    use ff::Field;
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+    use rand::rngs::OsRng;
+
+    use super::{address, leaf, nullifier, AirdropCircuit};
+    use crate::{
+        gadgets::{commit::CommitChip, merkle::MerkleTree, mimc::MimcChip},
+        validate::ValidateWitness,
+    };
+
+    const DEPTH: usize = 4;
+    const K: u32 = 10;
+
+    #[test]
+    fn test_airdrop_claim() {
+        let secrets: Vec<_> = (0..5).map(|_| Fp::random(OsRng)).collect();
+        let amounts = [100, 250, 50, 1000, 75];
+        let leaves: Vec<_> = secrets.iter().zip(amounts).map(|(secret, amount)| leaf::<CommitChip>(address::<CommitChip>(*secret), amount)).collect();
+        let tree = MerkleTree::new(DEPTH, &leaves);
+
+        let claim = |index: u64, secret: Fp, amount: u64, recipient: Fp| AirdropCircuit::<DEPTH> {
+            secret: Value::known(secret),
+            amount: Value::known(amount),
+            index: Value::known(index),
+            siblings: tree.path(index).try_into().map(|siblings: [Fp; DEPTH]| siblings.map(Value::known)).unwrap(),
+            recipient: Value::known(recipient),
+            hasher: PhantomData,
+        };
+        let run = |circuit: &AirdropCircuit<DEPTH>, secret: Fp, amount: u64, recipient: Fp| {
+            let instances = vec![AirdropCircuit::<DEPTH>::outputs(tree.root(), secret, recipient, amount).to_vec()];
+            let valid = circuit.validate_witness(&instances).is_ok();
+            assert_eq!(MockProver::run(K, circuit, instances).unwrap().verify().is_ok(), valid);
+            valid
+        };
+
+        let (secret, recipient) = (secrets[3], Fp::from(0xa11ce));
+        assert!(run(&claim(3, secret, 1000, recipient), secret, 1000, recipient));
+
+        // claiming more than granted
+        assert!(!run(&claim(3, secret, 2000, recipient), secret, 2000, recipient));
+        // another claimant's leaf
+        assert!(!run(&claim(1, secret, 250, recipient), secret, 250, recipient));
+        // a secret outside the tree
+        let stranger = Fp::random(OsRng);
+        assert!(!run(&claim(3, stranger, 1000, recipient), stranger, 1000, recipient));
+        // the proof for one recipient does not verify for another
+        let circuit = claim(3, secret, 1000, recipient);
+        let instances = vec![AirdropCircuit::<DEPTH>::outputs(tree.root(), secret, Fp::from(0xb0b), 1000).to_vec()];
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+
+        // a second claim verifies as well, but repeats the nullifier, which only depends on the secret
+        assert!(run(&claim(3, secret, 1000, Fp::from(0xb0b)), secret, 1000, Fp::from(0xb0b)));
+        assert_ne!(nullifier::<CommitChip>(secrets[3]), nullifier::<CommitChip>(secrets[1]));
+    }
+
+    #[test]
+    fn test_airdrop_claim_mimc() {
+        type Mimc = MimcChip<Fp>;
+        let secret = Fp::random(OsRng);
+        let leaves = [Fp::from(7), leaf::<Mimc>(address::<Mimc>(secret), 500)];
+        let tree = MerkleTree::<Mimc>::with_hasher(DEPTH, &leaves);
+        let circuit = AirdropCircuit::<DEPTH, Mimc> {
+            secret: Value::known(secret),
+            amount: Value::known(500),
+            index: Value::known(1),
+            siblings: tree.path(1).try_into().map(|siblings: [Fp; DEPTH]| siblings.map(Value::known)).unwrap(),
+            recipient: Value::known(Fp::from(0xa11ce)),
+            hasher: PhantomData,
+        };
+        let outputs = AirdropCircuit::<DEPTH, Mimc>::outputs(tree.root(), secret, Fp::from(0xa11ce), 500);
+        assert!(circuit.validate_witness(&[outputs.to_vec()]).is_ok());
+        MockProver::run(K, &circuit, vec![outputs.to_vec()]).unwrap().assert_satisfied();
+
+        // the Poseidon nullifier of the same secret
+        let mut outputs = outputs;
+        outputs[1] = nullifier::<CommitChip>(secret);
+        assert!(MockProver::run(K, &circuit, vec![outputs.to_vec()]).unwrap().verify().is_err());
+    }
+}
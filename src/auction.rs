@@ -0,0 +1,405 @@
+//! A sealed-bid second-price auction: N bidders commit to private bids, and the circuit
+//! proves who won and what they pay without revealing any bid.
+//!
+//! The public inputs are every bidder's commitment `Poseidon(bid, salt)`, the winner's index
+//! and the clearing price. The circuit recomputes the commitments from the private bids and
+//! salts, range checks the bids to `BID_BITS` bits and then
+//!
+//! 1. selects the winner with a one-hot vector `e`, i.e. booleans summing to 1, which yields
+//!    the winner's index `sum(e_i * i)` and bid `sum(e_i * bid_i)`;
+//! 2. constrains the winner's bid to be the largest with `MinMaxChip::max`, and every bid
+//!    before it to be smaller, so that a tie goes to the lowest index: `before_i` is
+//!    `bid_i + 1` up to the winner and 0 from it on, and none of them may exceed the
+//!    winner's bid;
+//! 3. masks the winner's bid to 0 and takes the largest of the rest as the price, which is
+//!    the second-highest bid, or the highest again on a tie.
+//!
+//!         e      |   bid    |  masked  |  before  |  count   |  winner  |  win_bid  | index | q_select
+//!       -------------------------------------------------------------------------------------------------
+//!                |          |          |          |    0     |    0     |     0     |       |
+//!        e_0     |  bid_0   |  m_0     |  p_0     | count_1  |  w_1     |  b_1      |   0   |    1
+//!        ...     |   ...    |  ...     |  ...     |   ...    |  ...     |  ...      |  ...  |    1
+//!      e_{N-1}   | bid_{N-1}| m_{N-1}  | p_{N-1}  | count_N  |  w_N     |  b_N      |  N-1  |    1
+//!
+//!     m_i = (1 - e_i) * bid_i,   p_i = (1 - count_{i+1}) * (bid_i + 1),
+//!     count_N = 1,   w_N = winner,   b_N = max(bid) >= max(p)
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gadgets::{
+        commit::{commit, CommitChip, CommitConfig},
+        min_max::{MinMaxChip, MinMaxConfig},
+    },
+    gate::GateBuilder,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// Bids are below `2^BID_BITS`.
+pub const BID_BITS: usize = 32;
+
+#[derive(Debug, Clone)]
+pub struct AuctionConfig {
+    e: Column<Advice>,
+    bid: Column<Advice>,
+    masked: Column<Advice>,
+    before: Column<Advice>,
+    count: Column<Advice>,
+    winner: Column<Advice>,
+    win_bid: Column<Advice>,
+    index: Column<Fixed>,
+    q_select: Selector,
+    commit: CommitConfig,
+    min_max: MinMaxConfig,
+    load: LoadPrivateConfig,
+    constants: ConstantsConfig,
+}
+
+/// The cells `AuctionCircuit::select_winner` produces.
+struct Selected {
+    masked: Vec<AssignedCell<Fp, Fp>>,
+    before: Vec<AssignedCell<Fp, Fp>>,
+    winner: AssignedCell<Fp, Fp>,
+    win_bid: AssignedCell<Fp, Fp>,
+}
+
+/// Settles an auction between `bids.len()` bidders.
+#[derive(Default)]
+pub struct AuctionCircuit {
+    pub bids: Vec<Value<u32>>,
+    pub salts: Vec<Value<Fp>>,
+}
+
+impl AuctionCircuit {
+    /// The winner, the lowest index among the highest bids, and the price, the highest of
+    /// the other bids.
+    pub fn outcome(bids: &[u32]) -> (usize, u32) {
+        let winner = (0..bids.len()).fold(0, |winner, i| if bids[i] > bids[winner] { i } else { winner });
+        let price = bids
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != winner)
+            .map(|(_, bid)| *bid)
+            .max()
+            .unwrap_or_default();
+        (winner, price)
+    }
+
+    /// The public inputs: every commitment, then the winner and the price.
+    pub fn instances(bids: &[u32], salts: &[Fp]) -> Vec<Fp> {
+        let (winner, price) = Self::outcome(bids);
+        bids.iter()
+            .zip(salts.iter())
+            .map(|(bid, salt)| commit(Fp::from(*bid as u64), *salt))
+            .chain([Fp::from(winner as u64), Fp::from(price as u64)])
+            .collect()
+    }
+
+    fn select_winner(
+        config: &AuctionConfig,
+        mut layouter: impl Layouter<Fp>,
+        bids: &[AssignedCell<Fp, Fp>],
+        winner: Value<usize>,
+    ) -> Result<Selected, Halo2ExamplesError> {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        let zero = constants.load_constant(layouter.namespace(|| "zero"), "zero", Fp::zero())?;
+        let one = constants.load_constant(layouter.namespace(|| "one"), "one", Fp::one())?;
+
+        layouter
+            .assign_region(
+                || "select winner",
+                |mut region| {
+                    let mut count = zero.copy_advice(|| "count_0", &mut region, config.count, 0)?;
+                    let mut index = zero.copy_advice(|| "winner_0", &mut region, config.winner, 0)?;
+                    let mut win_bid = zero.copy_advice(|| "win_bid_0", &mut region, config.win_bid, 0)?;
+                    let mut masked = Vec::with_capacity(bids.len());
+                    let mut before = Vec::with_capacity(bids.len());
+
+                    for (i, bid) in bids.iter().enumerate() {
+                        let offset = i + 1;
+                        config.q_select.enable(&mut region, offset)?;
+                        region.assign_fixed(|| format!("index_{}", i), config.index, offset, || Value::known(Fp::from(i as u64)))?;
+
+                        let e = winner.map(|winner| Fp::from((winner == i) as u64));
+                        region.assign_advice(|| format!("e_{}", i), config.e, offset, || e)?;
+                        let bid = bid.copy_advice(|| format!("bid_{}", i), &mut region, config.bid, offset)?;
+
+                        let m = e.zip(bid.value()).map(|(e, bid)| (Fp::one() - e) * bid);
+                        masked.push(region.assign_advice(|| format!("m_{}", i), config.masked, offset, || m)?);
+
+                        let next = count.value().copied() + e;
+                        count = region.assign_advice(|| format!("count_{}", offset), config.count, offset, || next)?;
+                        let p = count.value().zip(bid.value()).map(|(count, bid)| (Fp::one() - count) * (bid + Fp::one()));
+                        before.push(region.assign_advice(|| format!("p_{}", i), config.before, offset, || p)?);
+                        let next = index.value().copied() + e * Value::known(Fp::from(i as u64));
+                        index = region.assign_advice(|| format!("winner_{}", offset), config.winner, offset, || next)?;
+                        let next = win_bid.value().copied() + e * bid.value().copied();
+                        win_bid = region.assign_advice(|| format!("win_bid_{}", offset), config.win_bid, offset, || next)?;
+                    }
+
+                    region.constrain_equal(count.cell(), one.cell())?;
+                    Ok(Selected {
+                        masked,
+                        before,
+                        winner: index,
+                        win_bid,
+                    })
+                },
+            )
+            .in_chip("AuctionCircuit", "select winner")
+    }
+}
+
+impl Circuit<Fp> for AuctionCircuit {
+    type Config = AuctionConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            bids: vec![Value::unknown(); self.bids.len()],
+            salts: vec![Value::unknown(); self.salts.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let commit = CommitChip::configure(meta);
+        let min_max = MinMaxChip::<Fp, BID_BITS>::configure(meta, instance);
+        let load = LoadPrivateChip::configure(meta, 2);
+
+        let [e, bid, masked, before, count, winner, win_bid] = [(); 7].map(|_| meta.advice_column());
+        let index = meta.fixed_column();
+        let q_select = meta.selector();
+
+        // the zeros the sums start from and the final count of 1 are loaded constants
+        let constants = ConstantsChip::configure_with(meta, count);
+        for column in [bid, masked, before, count, winner, win_bid] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("select winner", |meta| {
+            let q_select = meta.query_selector(q_select);
+            let e = meta.query_advice(e, Rotation::cur());
+            let bid = meta.query_advice(bid, Rotation::cur());
+            let masked = meta.query_advice(masked, Rotation::cur());
+            let before = meta.query_advice(before, Rotation::cur());
+            let index = meta.query_fixed(index, Rotation::cur());
+            let zero = Expression::Constant(Fp::zero());
+            let one = Expression::Constant(Fp::one());
+            let count_cur = meta.query_advice(count, Rotation::cur());
+            let [count, winner, win_bid] = [count, winner, win_bid].map(|column| {
+                meta.query_advice(column, Rotation::cur()) - meta.query_advice(column, Rotation::prev())
+            });
+
+            // the running count is 0 before the winner and 1 from it on
+            GateBuilder::with_selector(q_select)
+                .boolean("e is boolean", e.clone())
+                .select("masked = e ? 0 : bid", masked, e.clone(), zero.clone(), bid.clone())
+                .select("before = count ? 0 : bid + 1", before, count_cur, zero, bid.clone() + one)
+                .equal("count = count_prev + e", count, e.clone())
+                .equal("winner = winner_prev + e * index", winner, e.clone() * index)
+                .equal("win_bid = win_bid_prev + e * bid", win_bid, e * bid)
+        });
+
+        AuctionConfig {
+            e,
+            bid,
+            masked,
+            before,
+            count,
+            winner,
+            win_bid,
+            index,
+            q_select,
+            commit,
+            min_max,
+            load,
+            constants,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let bids: Value<Vec<u32>> = self.bids.iter().copied().collect();
+        let winner = bids.map(|bids| Self::outcome(&bids).0);
+        self.settle(config, layouter, winner)
+    }
+}
+
+impl AuctionCircuit {
+    /// Lays out the auction with `winner` as the index the one-hot vector picks.
+    fn settle(&self, config: AuctionConfig, mut layouter: impl Layouter<Fp>, winner: Value<usize>) -> Result<(), Error> {
+        let commit_chip = CommitChip::construct(config.commit.clone());
+        let min_max = MinMaxChip::<Fp, BID_BITS>::construct(config.min_max.clone());
+        let load_chip = LoadPrivateChip::construct(config.load.clone());
+
+        // 1. Commitments to range checked bids.
+        let mut bids = Vec::with_capacity(self.bids.len());
+        for (i, (bid, salt)) in self.bids.iter().zip(self.salts.iter()).enumerate() {
+            let bid = bid.map(|bid| Fp::from(bid as u64));
+            let [bid, salt]: [_; 2] = load_chip
+                .load_privates(layouter.namespace(|| format!("load bid_{}", i)), &[bid, *salt])?
+                .try_into()
+                .expect("two cells loaded");
+            min_max.bits().unpack(layouter.namespace(|| format!("range check bid_{}", i)), &bid, BID_BITS)?;
+            let commitment = commit_chip.commit(layouter.namespace(|| format!("commit bid_{}", i)), &bid, &salt)?;
+            min_max.expose_public(layouter.namespace(|| format!("commitment_{}", i)), &commitment, i)?;
+            bids.push(bid);
+        }
+
+        // 2. The winner holds the highest bid, and every bid before it is lower.
+        let selected = Self::select_winner(&config, layouter.namespace(|| "select winner"), &bids, winner)?;
+        let highest = min_max.max(layouter.namespace(|| "highest bid"), &bids)?;
+        let before: Vec<_> = selected.before.iter().cloned().chain([selected.win_bid.clone()]).collect();
+        let bound = min_max.max(layouter.namespace(|| "lowest index"), &before)?;
+        layouter
+            .assign_region(
+                || "winner bid",
+                |mut region| {
+                    region.constrain_equal(selected.win_bid.cell(), highest.cell())?;
+                    region.constrain_equal(selected.win_bid.cell(), bound.cell())
+                },
+            )
+            .in_chip("AuctionCircuit", "winner bid")?;
+
+        // 3. The price is the highest of the other bids.
+        let price = min_max.max(layouter.namespace(|| "price"), &selected.masked)?;
+        min_max.expose_public(layouter.namespace(|| "winner"), &selected.winner, bids.len())?;
+        min_max.expose_public(layouter.namespace(|| "price"), &price, bids.len() + 1)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for AuctionCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        if self.bids.len() < 2 || self.bids.len() != self.salts.len() {
+            return Err(InvalidWitness::InvalidParameter(format!(
+                "need at least two bidders with one salt each, found {} bids and {} salts",
+                self.bids.len(),
+                self.salts.len()
+            )));
+        }
+        let bids = self
+            .bids
+            .iter()
+            .map(|bid| {
+                let bid = known(*bid, "bid")?;
+                check_below(Fp::from(bid as u64), 1u128 << BID_BITS, "bid")?;
+                Ok(bid)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        let salts = self
+            .salts
+            .iter()
+            .map(|salt| known(*salt, "salt"))
+            .collect::<Result<Vec<_>, _>>()?;
+        check_instances(instances, &Self::instances(&bids, &salts))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{AuctionCircuit, AuctionConfig};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 10;
+
+    /// An auction whose prover picks `winner` instead of the lowest index among the highest
+    /// bids.
+    struct PickedWinner {
+        auction: AuctionCircuit,
+        winner: usize,
+    }
+
+    impl Circuit<Fp> for PickedWinner {
+        type Config = AuctionConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                auction: self.auction.without_witnesses(),
+                winner: self.winner,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            AuctionCircuit::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            self.auction.settle(config, layouter, Value::known(self.winner))
+        }
+    }
+
+    fn auction(bids: &[u32]) -> (AuctionCircuit, Vec<Fp>) {
+        let salts: Vec<_> = (0..bids.len()).map(|i| Fp::from(0x5a17 + i as u64)).collect();
+        let circuit = AuctionCircuit {
+            bids: bids.iter().copied().map(Value::known).collect(),
+            salts: salts.iter().copied().map(Value::known).collect(),
+        };
+        (circuit, AuctionCircuit::instances(bids, &salts))
+    }
+
+    #[test]
+    fn test_auction() {
+        for bids in [vec![30, 70, 50, 10], vec![5, 9], vec![40, 40, 20]] {
+            let (circuit, instances) = auction(&bids);
+            assert_eq!(circuit.validate_witness(&[instances.clone()]), Ok(()));
+
+            let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+            prover.assert_satisfied();
+        }
+        assert_eq!(AuctionCircuit::outcome(&[30, 70, 50, 10]), (1, 50));
+        assert_eq!(AuctionCircuit::outcome(&[40, 40, 20]), (0, 40));
+    }
+
+    #[test]
+    fn test_auction_wrong_outcome() {
+        let bids = [30, 70, 50, 10];
+        let (circuit, instances) = auction(&bids);
+
+        // the winner paying their own bid
+        let mut wrong = instances.clone();
+        wrong[bids.len() + 1] = Fp::from(70);
+        let prover = MockProver::run(K, &circuit, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // the runner-up announced as the winner
+        let mut wrong = instances.clone();
+        wrong[bids.len()] = Fp::from(2);
+        let prover = MockProver::run(K, &circuit, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // a commitment to a different bid
+        let mut wrong = instances;
+        wrong[0] = super::commit(Fp::from(31), Fp::from(0x5a17));
+        let prover = MockProver::run(K, &circuit, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_auction_tie_goes_to_lowest_index() {
+        let bids = [20, 40, 40, 10];
+        let (auction, instances) = auction(&bids);
+        assert_eq!(AuctionCircuit::outcome(&bids), (1, 40));
+
+        let circuit = PickedWinner { auction, winner: 1 };
+        MockProver::run(K, &circuit, vec![instances.clone()]).unwrap().assert_satisfied();
+
+        // the later of the tied bidders announced as the winner, at the same price
+        let circuit = PickedWinner { winner: 2, ..circuit };
+        let mut tampered = instances;
+        tampered[bids.len()] = Fp::from(2);
+        let prover = MockProver::run(K, &circuit, vec![tampered]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,17 @@
+//! Runs every example circuit with sample inputs and prints how each fared.
+//!
+//! cargo run --release --bin gallery               # mock prover only
+//! cargo run --release --bin gallery -- --prove    # real proofs as well
+
+use std::process;
+
+use halo2_examples::dev::gallery::{gallery, to_markdown};
+
+fn main() {
+    let real = std::env::args().skip(1).any(|arg| arg == "--prove");
+    let entries = gallery(real);
+    print!("{}", to_markdown(&entries));
+    if entries.iter().any(|entry| !entry.ok()) {
+        process::exit(1);
+    }
+}
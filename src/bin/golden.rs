@@ -0,0 +1,28 @@
+//! Checks the verifying keys of the example circuits against their golden files, or
+//! rewrites the golden files after an intended change.
+//!
+//! cargo run --release --bin golden               # check
+//! cargo run --release --bin golden -- --bless   # rewrite
+
+use std::process;
+
+use halo2_examples::dev::golden::{bless, golden_dir, goldens, mismatches};
+
+fn main() {
+    let blessing = std::env::args().skip(1).any(|arg| arg == "--bless");
+    let goldens = goldens();
+    let dir = golden_dir();
+    if blessing {
+        bless(&goldens, &dir).expect("could not write the golden files");
+        println!("blessed {} verifying keys in {}", goldens.len(), dir.display());
+        return;
+    }
+
+    let mismatches = mismatches(&goldens, &dir);
+    for golden in &mismatches {
+        println!("{}: verifying key differs from {}", golden.name, golden.path(&dir).display());
+    }
+    if !mismatches.is_empty() {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,16 @@
+//! Prints the size and proving cost of every example circuit.
+//!
+//! cargo run --release --bin stats            # Markdown
+//! cargo run --release --bin stats -- --csv   # CSV
+
+use halo2_examples::dev::stats::{examples, to_csv, to_markdown};
+
+fn main() {
+    let csv = std::env::args().skip(1).any(|arg| arg == "--csv");
+    let stats = examples();
+    if csv {
+        print!("{}", to_csv(&stats, true));
+    } else {
+        print!("{}", to_markdown(&stats, true));
+    }
+}
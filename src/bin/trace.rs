@@ -0,0 +1,43 @@
+//! Prints every cell an example circuit assigns, region by region.
+//!
+//! cargo run --bin trace -- "decompose"
+//! cargo run --bin trace                   # lists the examples
+
+use std::process;
+
+use halo2_examples::dev::{
+    stats::{min_k, visit_examples, ExampleVisitor},
+    trace::trace,
+};
+use halo2_proofs::{pasta::Fp, plonk::Circuit};
+
+struct Print {
+    name: Option<String>,
+    found: bool,
+}
+
+impl ExampleVisitor for Print {
+    fn visit<C: Circuit<Fp>>(&mut self, name: &'static str, circuit: &C, instances: Vec<Vec<Fp>>) {
+        match &self.name {
+            Some(wanted) if wanted == name => {
+                let k = min_k(name, circuit, &instances);
+                print!("{}", trace(k, circuit, instances));
+                self.found = true;
+            }
+            Some(_) => {}
+            None => println!("{}", name),
+        }
+    }
+}
+
+fn main() {
+    let mut print = Print {
+        name: std::env::args().nth(1),
+        found: false,
+    };
+    visit_examples(&mut print);
+    if let (Some(name), false) = (&print.name, print.found) {
+        eprintln!("no example named {:?}, run without arguments for the list", name);
+        process::exit(1);
+    }
+}
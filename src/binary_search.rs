@@ -0,0 +1,245 @@
+//! Checks an off-circuit binary search: a committed, private array is sorted and holds a
+//! public target at a public index, the first one it does,
+//!
+//!     arr[0] <= arr[1] <= ... <= arr[N-1],   arr[index] == target,   arr[index-1] < target
+//!
+//! so the index is where a binary search for the target lands, its insertion point. The
+//! array is committed to with `VectorCommitChip`, whose `open` also picks `arr[index]` with
+//! `SelectChip`; `MinMaxChip::assert_sorted` compares each neighbouring pair.
+//!
+//! For the last condition to hold at `index = 0` too, which has no `arr[-1]`, another
+//! `SelectChip` picks from a shifted copy of the array with a 0 in front,
+//!
+//!     [0, arr[0] + 1, ..., arr[N-2] + 1][index] <= target
+//!
+//! Values are range checked to `VALUE_BITS` bits and compared in one bit more, which
+//! `arr[i] + 1` may need. The public inputs are the commitment, the target and the index;
+//! the array, its blinding factor and its other values stay private.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::{LoadInstructions, LoadPrivateChip, LoadPrivateConfig},
+    error::ResultExt,
+    gadgets::{
+        arith::{ArithChip, ArithConfig},
+        commit::CommitChip,
+        min_max::{MinMaxChip, MinMaxConfig},
+        select::{SelectChip, SelectConfig},
+        vector_commit::{vector_commitment, VectorCommitChip, VectorCommitConfig},
+    },
+    public_inputs::{InstanceChip, InstanceConfig, InstanceLayout},
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The public inputs of `BinarySearchCircuit`.
+pub const INSTANCES: InstanceLayout = InstanceLayout(&[&["commitment", "target", "index"]]);
+
+/// The bits array values are range checked to.
+pub const VALUE_BITS: usize = 16;
+
+const COMPARE_BITS: usize = VALUE_BITS + 1;
+
+/// The first index of `target` in the sorted `values`, if it is there at all.
+pub fn search(values: &[u64], target: u64) -> Option<usize> {
+    let index = values.partition_point(|value| *value < target);
+    if values.get(index) == Some(&target) {
+        Some(index)
+    } else {
+        None
+    }
+}
+
+/// Proves that a committed, sorted array of `N` values holds a public target first at a
+/// public index.
+pub struct BinarySearchCircuit<const N: usize> {
+    pub values: [Value<u64>; N],
+    pub blinding: Value<Fp>,
+}
+
+impl<const N: usize> Default for BinarySearchCircuit<N> {
+    fn default() -> Self {
+        Self {
+            values: [Value::unknown(); N],
+            blinding: Value::unknown(),
+        }
+    }
+}
+
+impl<const N: usize> BinarySearchCircuit<N> {
+    pub fn new(values: [u64; N], blinding: Fp) -> Self {
+        Self {
+            values: values.map(Value::known),
+            blinding: Value::known(blinding),
+        }
+    }
+
+    /// The commitment to `values` under `blinding`.
+    pub fn commitment(values: &[u64; N], blinding: Fp) -> Fp {
+        vector_commitment::<CommitChip>(&values.map(Fp::from), blinding)
+    }
+
+    /// The public inputs, as laid out by `INSTANCES`, for `target` found at `index`.
+    pub fn instances(values: &[u64; N], blinding: Fp, target: u64, index: usize) -> Vec<Fp> {
+        vec![Self::commitment(values, blinding), Fp::from(target), Fp::from(index as u64)]
+    }
+}
+
+impl<const N: usize> Circuit<Fp> for BinarySearchCircuit<N> {
+    type Config = (VectorCommitConfig, MinMaxConfig, SelectConfig, ArithConfig, LoadPrivateConfig, InstanceConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let vector = VectorCommitChip::configure(meta);
+        let min_max = MinMaxChip::<Fp, COMPARE_BITS>::configure(meta, instance);
+        let select = SelectChip::configure(meta);
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let arith = ArithChip::configure(meta, advice);
+        let load = LoadPrivateChip::configure(meta, 1);
+        let public = meta.advice_column();
+        (vector, min_max, select, arith, load, InstanceChip::configure(meta, instance, public))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (vector_config, min_max_config, select_config, arith_config, load_config, instance_config) = config;
+        let vector = VectorCommitChip::construct(vector_config);
+        let min_max = MinMaxChip::<Fp, COMPARE_BITS>::construct(min_max_config);
+        let select = SelectChip::construct(select_config);
+        let arith = ArithChip::construct(arith_config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let instance = InstanceChip::construct(instance_config);
+
+        let values = self
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let cell = load_chip.load_private(layouter.namespace(|| format!("load arr_{}", i)), value.map(Fp::from))?;
+                min_max.bits().unpack(layouter.namespace(|| format!("range check arr_{}", i)), &cell, VALUE_BITS)?;
+                Ok(cell)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let blinding = load_chip.load_private(layouter.namespace(|| "load blinding"), self.blinding)?;
+        let target = instance.load(layouter.namespace(|| "load target"), "target", INSTANCES.row("target"))?;
+        let index = instance.load(layouter.namespace(|| "load index"), "index", INSTANCES.row("index"))?;
+
+        // 1. arr[index] == target, in the committed array
+        let (commitment, found) = vector.open(layouter.namespace(|| "open"), &values, &blinding, &index)?;
+        instance.expose(layouter.namespace(|| "commitment"), "commitment", &commitment, INSTANCES.row("commitment"))?;
+        layouter
+            .assign_region(|| "found target", |mut region| region.constrain_equal(found.cell(), target.cell()))
+            .in_chip("BinarySearchCircuit", "found target")?;
+
+        // 2. the array is sorted
+        min_max.assert_sorted(layouter.namespace(|| "sorted"), &values)?;
+
+        // 3. arr[index - 1] < target, if there is one
+        let mut below = vec![arith.load_constant(layouter.namespace(|| "no predecessor"), Fp::zero())?];
+        for (i, value) in values.iter().take(N.saturating_sub(1)).enumerate() {
+            below.push(arith.add_constant(layouter.namespace(|| format!("arr_{} + 1", i)), value, Fp::one())?);
+        }
+        let below = select.select(layouter.namespace(|| "predecessor"), &below, &index)?;
+        let max = min_max.max(layouter.namespace(|| "max(arr[index - 1] + 1, target)"), &[below, target.clone()])?;
+        layouter
+            .assign_region(|| "first index", |mut region| region.constrain_equal(max.cell(), target.cell()))
+            .in_chip("BinarySearchCircuit", "first index")?;
+        Ok(())
+    }
+}
+
+impl<const N: usize> ValidateWitness<Fp> for BinarySearchCircuit<N> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let values = self
+            .values
+            .iter()
+            .map(|value| {
+                let value = known(*value, "value")?;
+                check_below(Fp::from(value), 1 << VALUE_BITS, "value")?;
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        let values: [u64; N] = values.try_into().expect("N values");
+        let blinding = known(self.blinding, "blinding")?;
+        if values.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(InvalidWitness::InvalidParameter("the array is not sorted".to_string()));
+        }
+
+        let target = instances.first().and_then(|column| column.get(INSTANCES.row("target"))).ok_or(
+            InvalidWitness::WrongInstanceCount {
+                expected: 3,
+                found: instances.iter().map(Vec::len).sum(),
+            },
+        )?;
+        check_below(*target, 1 << VALUE_BITS, "target")?;
+        let target = target.get_lower_128() as u64;
+        let index = search(&values, target)
+            .ok_or_else(|| InvalidWitness::InvalidParameter(format!("{} is not in the array", target)))?;
+        check_instances(instances, &Self::instances(&values, blinding, target, index))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use rand::rngs::OsRng;
+
+    use super::{search, BinarySearchCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 10;
+
+    const VALUES: [u64; 8] = [2, 3, 5, 5, 8, 13, 21, 65535];
+
+    #[test]
+    fn test_binary_search() {
+        let blinding = Fp::random(OsRng);
+        let circuit = BinarySearchCircuit::new(VALUES, blinding);
+        for target in [2, 5, 13, 65535] {
+            let index = search(&VALUES, target).unwrap();
+            let instances = vec![BinarySearchCircuit::instances(&VALUES, blinding, target, index)];
+            assert_eq!(circuit.validate_witness(&instances), Ok(()));
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_binary_search_wrong_index() {
+        let blinding = Fp::random(OsRng);
+        let circuit = BinarySearchCircuit::new(VALUES, blinding);
+        let run = |target: u64, index: usize| {
+            let instances = vec![BinarySearchCircuit::instances(&VALUES, blinding, target, index)];
+            MockProver::run(K, &circuit, instances).unwrap().verify().is_err()
+        };
+
+        // 5 is at 3 too, but a binary search lands on 2
+        assert_eq!(search(&VALUES, 5), Some(2));
+        assert!(run(5, 3));
+        // 4 is not there, though 2 is where it would go
+        assert_eq!(search(&VALUES, 4), None);
+        assert!(run(4, 2));
+        // another value, and an index past the end
+        assert!(run(5, 4));
+        assert!(run(5, 8));
+    }
+
+    #[test]
+    fn test_binary_search_unsorted() {
+        let values = [2, 3, 8, 5, 13, 21, 34, 55];
+        let blinding = Fp::random(OsRng);
+        let circuit = BinarySearchCircuit::new(values, blinding);
+        let instances = vec![BinarySearchCircuit::instances(&values, blinding, 8, 2)];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+
+        // a sorted array, but not the committed one
+        let instances = vec![BinarySearchCircuit::instances(&VALUES, Fp::random(OsRng), 5, 2)];
+        let circuit = BinarySearchCircuit::new(VALUES, blinding);
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Blake2s (RFC 7693) of a one-block message, on the words of `U32Chip`.
+//!
+//! The compression function mixes a 16-word state `v`, the hash value `h` followed by the
+//! IV with the byte count `t` and the final-block flag xored in, with the 16 message words
+//! over 10 rounds of 8 calls to `G`:
+//!
+//!     a = a + b + x; d = (d ^ a) >>> 16;
+//!     c = c + d;     b = (b ^ c) >>> 12;
+//!     a = a + b + y; d = (d ^ a) >>> 8;
+//!     c = c + d;     b = (b ^ c) >>> 7;
+//!
+//! the ChaCha quarter round with a message word added into `a` twice and right rotations.
+//! The new hash value is `h_i ^ v_i ^ v_{i+8}`. Every step is one operation of the word
+//! chip, so every word in the circuit is range checked and a compression is 80 `G`s of 6
+//! additions, 4 xors and 4 rotations each.
+//!
+//! `Blake2sCircuit` hashes a private 64-byte message, the longest that fits in one block,
+//! and exposes the 32-byte digest as 8 little-endian words. The initial hash value and the
+//! IV are constants, so they are fixed in the circuit rather than witnessed.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::{
+    error::Halo2ExamplesError,
+    gadgets::word::{U32Chip, U32Config, U32},
+    reference::{
+        blake2s::{block_words, initial_hash, IV, MIX_INDICES, SIGMA},
+        ref_blake2s,
+    },
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The mixing function `G` on `[a, b, c, d]` with the message words `x` and `y`.
+pub fn mix<F: FieldExt>(
+    chip: &U32Chip<F>,
+    mut layouter: impl Layouter<F>,
+    [a, b, c, d]: [&U32<F>; 4],
+    x: &U32<F>,
+    y: &U32<F>,
+) -> Result<[U32<F>; 4], Halo2ExamplesError> {
+    let a = chip.add(layouter.namespace(|| "a + b"), a, b)?;
+    let a = chip.add(layouter.namespace(|| "a + x"), &a, x)?;
+    let d = xor_rotate(chip, layouter.namespace(|| "d = (d ^ a) >>> 16"), d, &a, 16)?;
+    let c = chip.add(layouter.namespace(|| "c + d"), c, &d)?;
+    let b = xor_rotate(chip, layouter.namespace(|| "b = (b ^ c) >>> 12"), b, &c, 12)?;
+    let a = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+    let a = chip.add(layouter.namespace(|| "a + y"), &a, y)?;
+    let d = xor_rotate(chip, layouter.namespace(|| "d = (d ^ a) >>> 8"), &d, &a, 8)?;
+    let c = chip.add(layouter.namespace(|| "c + d"), &c, &d)?;
+    let b = xor_rotate(chip, layouter.namespace(|| "b = (b ^ c) >>> 7"), &b, &c, 7)?;
+    Ok([a, b, c, d])
+}
+
+// `(x ^ y) >>> bits`
+fn xor_rotate<F: FieldExt>(
+    chip: &U32Chip<F>,
+    mut layouter: impl Layouter<F>,
+    x: &U32<F>,
+    y: &U32<F>,
+    bits: usize,
+) -> Result<U32<F>, Halo2ExamplesError> {
+    let xor = chip.xor(layouter.namespace(|| "xor"), x, y)?;
+    chip.rotate_right(layouter.namespace(|| "rotate"), &xor, bits)
+}
+
+/// The compression function: `h` after the block `m`, with `t` bytes hashed so far
+/// including this block and `last` set for the final one.
+pub fn compress<F: FieldExt>(
+    chip: &U32Chip<F>,
+    mut layouter: impl Layouter<F>,
+    h: &[U32<F>; 8],
+    m: &[U32<F>; 16],
+    t: u64,
+    last: bool,
+) -> Result<[U32<F>; 8], Halo2ExamplesError> {
+    let mut tail = IV;
+    tail[4] ^= t as u32;
+    tail[5] ^= (t >> 32) as u32;
+    if last {
+        tail[6] ^= u32::MAX;
+    }
+
+    let mut v = h.to_vec();
+    for (i, word) in tail.into_iter().enumerate() {
+        v.push(chip.assign_constant(layouter.namespace(|| format!("v_{}", i + 8)), word)?);
+    }
+
+    for (round, sigma) in SIGMA.iter().enumerate() {
+        for (i, indices) in MIX_INDICES.into_iter().enumerate() {
+            let [a, b, c, d] = indices.map(|index| &v[index]);
+            let (x, y) = (&m[sigma[2 * i]], &m[sigma[2 * i + 1]]);
+            let mixed = mix(chip, layouter.namespace(|| format!("round {}, G {}", round, i)), [a, b, c, d], x, y)?;
+            for (index, word) in indices.into_iter().zip(mixed) {
+                v[index] = word;
+            }
+        }
+    }
+
+    let h = (0..8)
+        .map(|i| {
+            let mut layouter = layouter.namespace(|| format!("h_{}", i));
+            let xor = chip.xor(layouter.namespace(|| "h_i ^ v_i"), &h[i], &v[i])?;
+            chip.xor(layouter.namespace(|| "^ v_i+8"), &xor, &v[i + 8])
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(h.try_into().expect("8 words"))
+}
+
+/// Hashes a private 64-byte message and exposes the digest.
+pub struct Blake2sCircuit {
+    pub message: [Value<u8>; 64],
+}
+
+impl Default for Blake2sCircuit {
+    fn default() -> Self {
+        Self {
+            message: [Value::unknown(); 64],
+        }
+    }
+}
+
+impl Blake2sCircuit {
+    /// The public inputs, the digest of `message` as 8 little-endian words.
+    pub fn instances(message: [u8; 64]) -> Vec<u64> {
+        ref_blake2s(&message)
+            .chunks(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as u64)
+            .collect()
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for Blake2sCircuit {
+    type Config = U32Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        U32Chip::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = U32Chip::construct(config);
+        chip.load_tables(&mut layouter)?;
+
+        let h = initial_hash()
+            .into_iter()
+            .enumerate()
+            .map(|(i, word)| chip.assign_constant(layouter.namespace(|| format!("h_{}", i)), word))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let block = Value::<Vec<_>>::from_iter(self.message).map(|bytes| block_words(&bytes.try_into().unwrap()));
+        let m = (0..16)
+            .map(|i| chip.assign(layouter.namespace(|| format!("m_{}", i)), block.map(|words| words[i])))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let h = compress(
+            &chip,
+            layouter.namespace(|| "compress"),
+            &h.try_into().expect("8 words"),
+            &m.try_into().expect("16 words"),
+            64,
+            true,
+        )?;
+        for (row, word) in h.iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "digest"), word, row)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ValidateWitness<F> for Blake2sCircuit {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let message = self.message.iter().map(|byte| known(*byte, "message")).collect::<Result<Vec<_>, _>>()?;
+        let expected: Vec<_> = Self::instances(message.try_into().unwrap()).into_iter().map(F::from).collect();
+        check_instances(instances, &expected)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use blake2::{Blake2s256, Digest};
+    use halo2_proofs::{arithmetic::Field, circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::Blake2sCircuit;
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 17;
+
+    fn message() -> [u8; 64] {
+        std::array::from_fn(|i| (i * 7 + 3) as u8)
+    }
+
+    #[test]
+    fn test_blake2s() {
+        let message = message();
+        let instances: Vec<Fp> = Blake2sCircuit::instances(message).into_iter().map(Fp::from).collect();
+        let digest = Blake2s256::digest(message);
+        let words: Vec<Fp> = digest
+            .chunks(4)
+            .map(|bytes| Fp::from(u32::from_le_bytes(bytes.try_into().unwrap()) as u64))
+            .collect();
+        assert_eq!(instances, words);
+
+        let circuit = Blake2sCircuit {
+            message: message.map(Value::known),
+        };
+        assert_eq!(circuit.validate_witness(&[instances.clone()]), Ok(()));
+        MockProver::run(K, &circuit, vec![instances]).unwrap().assert_satisfied();
+    }
+
+    #[test]
+    fn test_blake2s_wrong_digest() {
+        let message = message();
+        let circuit = Blake2sCircuit {
+            message: message.map(Value::known),
+        };
+
+        for row in 0..8 {
+            let mut instances: Vec<Fp> = Blake2sCircuit::instances(message).into_iter().map(Fp::from).collect();
+            instances[row] += Fp::one();
+            let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+            assert!(prover.verify().is_err(), "digest word {} is not bound", row);
+        }
+    }
+}
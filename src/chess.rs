@@ -0,0 +1,654 @@
+//! Chess move legality: a claimed move from a public position follows the movement rules of
+//! the piece it moves, a teaching example for encoding a rule system as fixed tables.
+//!
+//! Squares are numbered `8 * rank + file` from a1 = 0, and pieces `1` to `6` for the white
+//! pawn, knight, bishop, rook, queen and king, `7` to `12` for the black ones, 0 for an
+//! empty square. The position is public, and `SelectChip` picks the piece on the move's
+//! squares out of it. Then four tables do the rest, each with an all-zero row for the rows
+//! their lookup is off:
+//!
+//! - `squares`, `(square, rank, file)`, splits both squares into their rank and file;
+//! - `rules`, `(piece, colour, dx, dy, mode, steps, stride, start)`, has a row for every
+//!   displacement of every piece, with the piece's colour plus one, whether the move must
+//!   go to an empty square, capture, or either, how many squares it passes over and the
+//!   step between them, and the rank a pawn's double step must start on, or `ANY_RANK`;
+//! - `targets`, `(side, mode, target)`, lists what each side may find on the square it moves
+//!   to, for each mode: nothing, unless it must capture, or one of the other side's pieces,
+//!   unless it must not;
+//! - `path`, `(steps, k, active)`, says whether the `k`th square along the way is passed over.
+//!
+//! The move itself takes two rows, and each of the `MAX_PATH` squares it may pass over one:
+//!
+//!        from  |  to  | from_rank | from_file | to_rank | to_file |  q_square
+//!     ---------------------------------------------------------------------------
+//!        f     |  t   |    fr     |    ff     |   tr    |   tf    |    1
+//!
+//!        side  |  piece  |  target  | ranks, files | mode | steps | stride | start |  q_rule
+//!     --------------------------------------------------------------------------------------
+//!         s    |  p      |    x     |  fr ff tr tf |  m   |   n   |   d    |  r0   |    1
+//!
+//!     (p, s + 1, tf - ff, tr - fr, m, n, d, r0) in rules,   (s + 1, m, x) in targets,
+//!     r0 = ANY_RANK or r0 = fr
+//!
+//!        from  | stride | steps |  k  |  active  |     square       |  q_path
+//!     ----------------------------------------------------------------------------
+//!         f    |   d    |   n   |  k  |   a_k    | f + k * d * a_k  |    1       k = 1 to MAX_PATH
+//!
+//! with every square passed over empty: `a_k * board[square_k] = 0`. A slide stays between
+//! its two squares on the board, so it cannot wrap around an edge, and a displacement that
+//! would, like a rook from h1 to a2, has no row in `rules`.
+//!
+//! Left out: checks, castling, en passant and promotion, each another table or two.
+//!
+//! The public inputs are the side to move, 0 for white, the 64 squares, and the move. Leave
+//! the move unexposed and the proof says only that the side has a legal move.
+
+use std::iter;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    error::ResultExt,
+    gadgets::select::{SelectChip, SelectConfig},
+    gate::GateBuilder,
+    public_inputs::{InstanceChip, InstanceConfig},
+    table::FixedTableConfig,
+    validate::{check_below, known, InvalidWitness, ValidateWitness},
+};
+
+pub const EMPTY: u64 = 0;
+pub const PAWN: u64 = 1;
+pub const KNIGHT: u64 = 2;
+pub const BISHOP: u64 = 3;
+pub const ROOK: u64 = 4;
+pub const QUEEN: u64 = 5;
+pub const KING: u64 = 6;
+/// Added to a white piece for the black one.
+pub const BLACK: u64 = 6;
+
+pub const WHITE_TO_MOVE: u64 = 0;
+pub const BLACK_TO_MOVE: u64 = 1;
+
+/// The move goes to an empty square or captures.
+pub const MOVE_OR_CAPTURE: u64 = 0;
+/// The move goes to an empty square, a pawn's push.
+pub const MOVE_ONLY: u64 = 1;
+/// The move captures, a pawn's diagonal.
+pub const CAPTURE_ONLY: u64 = 2;
+
+/// The `start` of a rule any rank can use.
+pub const ANY_RANK: u64 = 8;
+
+/// The most squares a move passes over, a slide across the board.
+pub const MAX_PATH: usize = 6;
+
+/// The public inputs before the move: the side to move and the squares.
+const BOARD_ROWS: usize = 65;
+
+/// One displacement of one piece.
+#[derive(Debug, Clone, Copy)]
+pub struct Rule {
+    pub piece: u64,
+    pub dx: i64,
+    pub dy: i64,
+    pub mode: u64,
+    /// The squares passed over, each `stride` further along.
+    pub steps: u64,
+    pub stride: i64,
+    pub start: u64,
+}
+
+impl Rule {
+    fn new(piece: u64, (dx, dy): (i64, i64)) -> Self {
+        Self {
+            piece,
+            dx,
+            dy,
+            mode: MOVE_OR_CAPTURE,
+            steps: 0,
+            stride: 0,
+            start: ANY_RANK,
+        }
+    }
+
+    fn slide(piece: u64, (sx, sy): (i64, i64), n: i64) -> Self {
+        Self {
+            steps: n as u64 - 1,
+            stride: 8 * sy + sx,
+            ..Self::new(piece, (n * sx, n * sy))
+        }
+    }
+
+    /// The rule's row of the `rules` table.
+    pub fn row(&self) -> [Fp; 8] {
+        [
+            Fp::from(self.piece),
+            Fp::from(colour(self.piece).expect("a piece") + 1),
+            signed(self.dx),
+            signed(self.dy),
+            Fp::from(self.mode),
+            Fp::from(self.steps),
+            signed(self.stride),
+            Fp::from(self.start),
+        ]
+    }
+}
+
+fn signed(x: i64) -> Fp {
+    if x < 0 {
+        -Fp::from(x.unsigned_abs())
+    } else {
+        Fp::from(x as u64)
+    }
+}
+
+/// The side `piece` belongs to, `None` for an empty square.
+pub fn colour(piece: u64) -> Option<u64> {
+    match piece {
+        1..=6 => Some(WHITE_TO_MOVE),
+        7..=12 => Some(BLACK_TO_MOVE),
+        _ => None,
+    }
+}
+
+/// Every displacement of every piece.
+pub fn rules() -> Vec<Rule> {
+    let diagonals = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+    let orthogonals = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+    let knight = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+
+    let mut rules = Vec::new();
+    for side in [WHITE_TO_MOVE, BLACK_TO_MOVE] {
+        let piece = |piece| piece + side * BLACK;
+        let forward = if side == WHITE_TO_MOVE { 1 } else { -1 };
+        let slides = |piece: u64, directions: &[(i64, i64)]| {
+            directions.iter().flat_map(move |direction| (1..8).map(move |n| Rule::slide(piece, *direction, n))).collect::<Vec<_>>()
+        };
+
+        rules.push(Rule {
+            mode: MOVE_ONLY,
+            ..Rule::new(piece(PAWN), (0, forward))
+        });
+        rules.push(Rule {
+            mode: MOVE_ONLY,
+            start: if side == WHITE_TO_MOVE { 1 } else { 6 },
+            ..Rule::slide(piece(PAWN), (0, forward), 2)
+        });
+        for dx in [-1, 1] {
+            rules.push(Rule {
+                mode: CAPTURE_ONLY,
+                ..Rule::new(piece(PAWN), (dx, forward))
+            });
+        }
+        rules.extend(knight.iter().map(|displacement| Rule::new(piece(KNIGHT), *displacement)));
+        rules.extend(slides(piece(BISHOP), &diagonals));
+        rules.extend(slides(piece(ROOK), &orthogonals));
+        rules.extend(slides(piece(QUEEN), &[diagonals, orthogonals].concat()));
+        rules.extend(diagonals.iter().chain(&orthogonals).map(|displacement| Rule::new(piece(KING), *displacement)));
+    }
+    rules
+}
+
+/// Whether `side` may find `target` on the square it moves to, in `mode`.
+pub fn allowed_target(side: u64, mode: u64, target: u64) -> bool {
+    match colour(target) {
+        None => mode != CAPTURE_ONLY,
+        Some(owner) => owner != side && mode != MOVE_ONLY,
+    }
+}
+
+/// The square named e.g. `e4`.
+pub fn square(name: &str) -> usize {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next().and_then(|rank| rank.to_digit(10)), chars.next()) {
+        (Some(file @ 'a'..='h'), Some(rank @ 1..=8), None) => 8 * (rank as usize - 1) + (file as usize - 'a' as usize),
+        _ => panic!("no square {}", name),
+    }
+}
+
+/// The squares of the piece placement of a FEN, e.g. `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR`.
+pub fn board(fen: &str) -> [u64; 64] {
+    let mut board = [EMPTY; 64];
+    for (i, rank) in fen.split('/').enumerate() {
+        let mut file = 0;
+        for c in rank.chars() {
+            match c.to_digit(10) {
+                Some(empty) => file += empty as usize,
+                None => {
+                    let piece = "PNBRQK".find(c.to_ascii_uppercase()).expect("a piece letter") as u64 + 1;
+                    board[8 * (7 - i) + file] = if c.is_ascii_lowercase() { piece + BLACK } else { piece };
+                    file += 1;
+                }
+            }
+        }
+    }
+    board
+}
+
+/// Whether `side` may move from `from` to `to` on `board`, by the rules above.
+pub fn is_legal(board: &[u64; 64], side: u64, from: usize, to: usize) -> bool {
+    let (dx, dy) = ((to % 8) as i64 - (from % 8) as i64, (to / 8) as i64 - (from / 8) as i64);
+    let rule = rules().into_iter().find(|rule| rule.piece == board[from] && (rule.dx, rule.dy) == (dx, dy));
+    match rule {
+        Some(rule) => {
+            colour(rule.piece) == Some(side)
+                && (rule.start == ANY_RANK || rule.start == (from / 8) as u64)
+                && allowed_target(side, rule.mode, board[to])
+                && (1..=rule.steps as i64).all(|k| board[(from as i64 + k * rule.stride) as usize] == EMPTY)
+        }
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChessConfig {
+    select: SelectConfig,
+    instance: InstanceConfig,
+    from: Column<Advice>,
+    to: Column<Advice>,
+    from_rank: Column<Advice>,
+    from_file: Column<Advice>,
+    to_rank: Column<Advice>,
+    to_file: Column<Advice>,
+    side: Column<Advice>,
+    piece: Column<Advice>,
+    target: Column<Advice>,
+    mode: Column<Advice>,
+    steps: Column<Advice>,
+    stride: Column<Advice>,
+    start: Column<Advice>,
+    active: Column<Advice>,
+    square: Column<Advice>,
+    occupied: Column<Advice>,
+    k: Column<Fixed>,
+    q_square: Selector,
+    q_rule: Selector,
+    q_path: Selector,
+    q_clear: Selector,
+    squares: [FixedTableConfig<Fp>; 3],
+    rules: [FixedTableConfig<Fp>; 8],
+    targets: [FixedTableConfig<Fp>; 3],
+    path: [FixedTableConfig<Fp>; 3],
+}
+
+/// Proves the move `from`, `to` is legal for the side to move in a public position.
+#[derive(Default)]
+pub struct ChessMoveCircuit {
+    pub from: Value<u64>,
+    pub to: Value<u64>,
+}
+
+impl ChessMoveCircuit {
+    pub fn new(from: &str, to: &str) -> Self {
+        Self {
+            from: Value::known(square(from) as u64),
+            to: Value::known(square(to) as u64),
+        }
+    }
+
+    /// The public inputs: the side to move, the squares of `board` and the move.
+    pub fn instances(board: &[u64; 64], side: u64, from: &str, to: &str) -> Vec<Fp> {
+        iter::once(side)
+            .chain(board.iter().copied())
+            .chain([square(from) as u64, square(to) as u64])
+            .map(Fp::from)
+            .collect()
+    }
+}
+
+impl Circuit<Fp> for ChessMoveCircuit {
+    type Config = ChessConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let select = SelectChip::configure(meta);
+        let [from, to, from_rank, from_file, to_rank, to_file] = [(); 6].map(|_| meta.advice_column());
+        let [side, piece, target, mode, steps, stride, start] = [(); 7].map(|_| meta.advice_column());
+        let [active, square, occupied, public] = [(); 4].map(|_| meta.advice_column());
+        let k = meta.fixed_column();
+        let [q_square, q_rule, q_path] = [(); 3].map(|_| meta.complex_selector());
+        let q_clear = meta.selector();
+        let squares = ["chess square", "chess rank", "chess file"].map(|name| FixedTableConfig::configure(meta, name));
+        let rules = ["rule piece", "rule colour", "rule dx", "rule dy", "rule mode", "rule steps", "rule stride", "rule start"]
+            .map(|name| FixedTableConfig::configure(meta, name));
+        let targets = ["target side", "target mode", "target piece"].map(|name| FixedTableConfig::configure(meta, name));
+        let path = ["path steps", "path k", "path active"].map(|name| FixedTableConfig::configure(meta, name));
+        for column in [from, to, from_rank, from_file, to_rank, to_file, side, piece, target, steps, stride, active, square, occupied] {
+            meta.enable_equality(column);
+        }
+
+        for (square, rank, file) in [(from, from_rank, from_file), (to, to_rank, to_file)] {
+            meta.lookup(|meta| {
+                let q_square = meta.query_selector(q_square);
+                let inputs = [square, rank, file].map(|column| q_square.clone() * meta.query_advice(column, Rotation::cur()));
+                inputs.into_iter().zip(squares.iter().map(|table| table.column)).collect()
+            });
+        }
+
+        let one = Expression::Constant(Fp::one());
+        meta.lookup(|meta| {
+            let q_rule = meta.query_selector(q_rule);
+            let [side, piece, from_rank, from_file, to_rank, to_file, mode, steps, stride, start] =
+                [side, piece, from_rank, from_file, to_rank, to_file, mode, steps, stride, start].map(|column| meta.query_advice(column, Rotation::cur()));
+            let inputs = [piece, side + one.clone(), to_file - from_file, to_rank - from_rank, mode, steps, stride, start];
+            inputs.into_iter().map(|input| q_rule.clone() * input).zip(rules.iter().map(|table| table.column)).collect()
+        });
+
+        meta.lookup(|meta| {
+            let q_rule = meta.query_selector(q_rule);
+            let [side, mode, target] = [side, mode, target].map(|column| meta.query_advice(column, Rotation::cur()));
+            let inputs = [side + one.clone(), mode, target];
+            inputs.into_iter().map(|input| q_rule.clone() * input).zip(targets.iter().map(|table| table.column)).collect()
+        });
+
+        meta.create_gate("rule", |meta| {
+            let q_rule = meta.query_selector(q_rule);
+            let side = meta.query_advice(side, Rotation::cur());
+            let from_rank = meta.query_advice(from_rank, Rotation::cur());
+            let start = meta.query_advice(start, Rotation::cur());
+
+            GateBuilder::with_selector(q_rule).boolean("side is boolean", side).constrain(
+                "start = ANY_RANK or start = from_rank",
+                (start.clone() - Expression::Constant(Fp::from(ANY_RANK))) * (start - from_rank),
+            )
+        });
+
+        meta.lookup(|meta| {
+            let q_path = meta.query_selector(q_path);
+            let steps = meta.query_advice(steps, Rotation::cur());
+            let k = meta.query_fixed(k, Rotation::cur());
+            let active = meta.query_advice(active, Rotation::cur());
+            [steps, k, active].into_iter().map(|input| q_path.clone() * input).zip(path.iter().map(|table| table.column)).collect()
+        });
+
+        meta.create_gate("path", |meta| {
+            let q_path = meta.query_selector(q_path);
+            let [from, stride, active, square] = [from, stride, active, square].map(|column| meta.query_advice(column, Rotation::cur()));
+            let k = meta.query_fixed(k, Rotation::cur());
+
+            GateBuilder::with_selector(q_path).equal("square = from + k * stride * active", square, from + k * stride * active)
+        });
+
+        meta.create_gate("clear", |meta| {
+            let q_clear = meta.query_selector(q_clear);
+            let active = meta.query_advice(active, Rotation::cur());
+            let occupied = meta.query_advice(occupied, Rotation::cur());
+
+            GateBuilder::with_selector(q_clear).constrain("active * occupied = 0", active * occupied)
+        });
+
+        ChessConfig {
+            select,
+            instance: InstanceChip::configure(meta, instance, public),
+            from,
+            to,
+            from_rank,
+            from_file,
+            to_rank,
+            to_file,
+            side,
+            piece,
+            target,
+            mode,
+            steps,
+            stride,
+            start,
+            active,
+            square,
+            occupied,
+            k,
+            q_square,
+            q_rule,
+            q_path,
+            q_clear,
+            squares,
+            rules,
+            targets,
+            path,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let select = SelectChip::construct(config.select.clone());
+        let instance = InstanceChip::construct(config.instance.clone());
+
+        // the tables, each from an all-zero row
+        let squares: Vec<_> = (0..64u64).map(|square| [square, square / 8, square % 8].map(Fp::from)).collect();
+        let rules: Vec<_> = iter::once([Fp::zero(); 8]).chain(rules().iter().map(Rule::row)).collect();
+        let targets: Vec<_> = iter::once([Fp::zero(); 3])
+            .chain(
+                [WHITE_TO_MOVE, BLACK_TO_MOVE]
+                    .into_iter()
+                    .flat_map(|side| [MOVE_OR_CAPTURE, MOVE_ONLY, CAPTURE_ONLY].into_iter().flat_map(move |mode| (0..=12).map(move |target| (side, mode, target))))
+                    .filter(|(side, mode, target)| allowed_target(*side, *mode, *target))
+                    .map(|(side, mode, target)| [side + 1, mode, target].map(Fp::from)),
+            )
+            .collect();
+        let path: Vec<_> = iter::once([Fp::zero(); 3])
+            .chain((0..=MAX_PATH as u64).flat_map(|steps| (1..=MAX_PATH as u64).map(move |k| [steps, k, (k <= steps) as u64].map(Fp::from))))
+            .collect();
+        for (i, table) in config.squares.iter().enumerate() {
+            table.load(&mut layouter, &squares.iter().map(|row| row[i]).collect::<Vec<_>>())?;
+        }
+        for (i, table) in config.rules.iter().enumerate() {
+            table.load(&mut layouter, &rules.iter().map(|row| row[i]).collect::<Vec<_>>())?;
+        }
+        for (i, table) in config.targets.iter().enumerate() {
+            table.load(&mut layouter, &targets.iter().map(|row| row[i]).collect::<Vec<_>>())?;
+        }
+        for (i, table) in config.path.iter().enumerate() {
+            table.load(&mut layouter, &path.iter().map(|row| row[i]).collect::<Vec<_>>())?;
+        }
+
+        // 1. the position, and the move's squares split into ranks and files
+        let side = instance.load(layouter.namespace(|| "load side"), "side", 0)?;
+        let board = (0..64)
+            .map(|square| instance.load(layouter.namespace(|| format!("load square {}", square)), "square", square + 1))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (from, to, coordinates) = layouter
+            .assign_region(
+                || "squares",
+                |mut region| {
+                    config.q_square.enable(&mut region, 0)?;
+                    let from = region.assign_advice(|| "from", config.from, 0, || self.from.map(Fp::from))?;
+                    let to = region.assign_advice(|| "to", config.to, 0, || self.to.map(Fp::from))?;
+                    let mut coordinates = Vec::with_capacity(4);
+                    for (name, square, rank, file) in [("from", self.from, config.from_rank, config.from_file), ("to", self.to, config.to_rank, config.to_file)] {
+                        coordinates.push(region.assign_advice(|| format!("{} rank", name), rank, 0, || square.map(|square| Fp::from(square / 8)))?);
+                        coordinates.push(region.assign_advice(|| format!("{} file", name), file, 0, || square.map(|square| Fp::from(square % 8)))?);
+                    }
+                    Ok((from, to, coordinates))
+                },
+            )
+            .in_chip("ChessMoveCircuit", "squares")?;
+
+        // 2. the piece moved follows one of its rules
+        let piece = select.select(layouter.namespace(|| "board[from]"), &board, &from)?;
+        let target = select.select(layouter.namespace(|| "board[to]"), &board, &to)?;
+        let (stride, steps) = layouter
+            .assign_region(
+                || "rule",
+                |mut region| {
+                    config.q_rule.enable(&mut region, 0)?;
+                    let side = side.copy_advice(|| "side", &mut region, config.side, 0)?;
+                    let piece = piece.copy_advice(|| "piece", &mut region, config.piece, 0)?;
+                    target.copy_advice(|| "target", &mut region, config.target, 0)?;
+                    let columns = [config.from_rank, config.from_file, config.to_rank, config.to_file];
+                    let [from_rank, from_file, to_rank, to_file] = [0, 1, 2, 3].map(|i| coordinates[i].value().copied());
+                    for (cell, column) in coordinates.iter().zip(columns) {
+                        cell.copy_advice(|| "coordinate", &mut region, column, 0)?;
+                    }
+
+                    let key = piece.value().copied().zip(side.value().copied()).zip(to_file - from_file).zip(to_rank - from_rank);
+                    let row = key.map(|(((piece, side), dx), dy)| {
+                        let key = [piece, side + Fp::one(), dx, dy];
+                        rules.iter().skip(1).find(|row| row[..4] == key).copied().unwrap_or([Fp::zero(); 8])
+                    });
+                    region.assign_advice(|| "mode", config.mode, 0, || row.map(|row| row[4]))?;
+                    let steps = region.assign_advice(|| "steps", config.steps, 0, || row.map(|row| row[5]))?;
+                    let stride = region.assign_advice(|| "stride", config.stride, 0, || row.map(|row| row[6]))?;
+                    region.assign_advice(|| "start", config.start, 0, || row.map(|row| row[7]))?;
+                    Ok((stride, steps))
+                },
+            )
+            .in_chip("ChessMoveCircuit", "rule")?;
+
+        // 3. every square it passes over is empty
+        let passed = layouter
+            .assign_region(
+                || "path",
+                |mut region| {
+                    let mut passed = Vec::with_capacity(MAX_PATH);
+                    for offset in 0..MAX_PATH {
+                        let k = Fp::from(offset as u64 + 1);
+                        config.q_path.enable(&mut region, offset)?;
+                        region.assign_fixed(|| "k", config.k, offset, || Value::known(k))?;
+                        let from = from.copy_advice(|| "from", &mut region, config.from, offset)?;
+                        let stride = stride.copy_advice(|| "stride", &mut region, config.stride, offset)?;
+                        let steps = steps.copy_advice(|| "steps", &mut region, config.steps, offset)?;
+                        let active = steps.value().map(|steps| Fp::from((steps.get_lower_128() > offset as u128) as u64));
+                        let active = region.assign_advice(|| "active", config.active, offset, || active)?;
+                        let square = from.value().copied() + Value::known(k) * stride.value().copied() * active.value().copied();
+                        let square = region.assign_advice(|| "square", config.square, offset, || square)?;
+                        passed.push((active, square));
+                    }
+                    Ok(passed)
+                },
+            )
+            .in_chip("ChessMoveCircuit", "path")?;
+        for (k, (active, square)) in passed.iter().enumerate() {
+            let occupied = select.select(layouter.namespace(|| format!("board[square_{}]", k + 1)), &board, square)?;
+            layouter
+                .assign_region(
+                    || "clear",
+                    |mut region| {
+                        config.q_clear.enable(&mut region, 0)?;
+                        active.copy_advice(|| "active", &mut region, config.active, 0)?;
+                        occupied.copy_advice(|| "occupied", &mut region, config.occupied, 0)?;
+                        Ok(())
+                    },
+                )
+                .in_chip("ChessMoveCircuit", "clear")?;
+        }
+
+        instance.expose(layouter.namespace(|| "from"), "from", &from, BOARD_ROWS)?;
+        instance.expose(layouter.namespace(|| "to"), "to", &to, BOARD_ROWS + 1)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for ChessMoveCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let from = known(self.from, "from")?;
+        let to = known(self.to, "to")?;
+        check_below(Fp::from(from), 64, "from")?;
+        check_below(Fp::from(to), 64, "to")?;
+
+        let public = instances.first().filter(|column| column.len() == BOARD_ROWS + 2).ok_or(InvalidWitness::WrongInstanceCount {
+            expected: BOARD_ROWS + 2,
+            found: instances.iter().map(Vec::len).sum(),
+        })?;
+        check_below(public[0], 2, "side")?;
+        let mut board = [EMPTY; 64];
+        for (square, value) in board.iter_mut().zip(&public[1..BOARD_ROWS]) {
+            check_below(*value, 2 * BLACK as u128 + 1, "square")?;
+            *square = value.get_lower_128() as u64;
+        }
+        if public[BOARD_ROWS..] != [Fp::from(from), Fp::from(to)] {
+            return Err(InvalidWitness::InvalidParameter("the move is not the one claimed".to_string()));
+        }
+        if !is_legal(&board, public[0].get_lower_128() as u64, from as usize, to as usize) {
+            return Err(InvalidWitness::InvalidParameter(format!("no legal move from {} to {}", from, to)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::dev::MockProver;
+
+    use super::{board, is_legal, rules, square, ChessMoveCircuit, BLACK_TO_MOVE, WHITE_TO_MOVE};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 10;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+    // 1. e4 d5
+    const SCANDINAVIAN: &str = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR";
+    // 1. e4 e5
+    const OPEN_GAME: &str = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR";
+    const ROOK_ENDGAME: &str = "4k3/8/8/8/8/8/8/4K2R";
+
+    fn run(fen: &str, side: u64, from: &str, to: &str) -> bool {
+        let board = board(fen);
+        let circuit = ChessMoveCircuit::new(from, to);
+        let instances = vec![ChessMoveCircuit::instances(&board, side, from, to)];
+        let legal = is_legal(&board, side, square(from), square(to));
+        assert_eq!(circuit.validate_witness(&instances).is_ok(), legal);
+        let proved = MockProver::run(K, &circuit, instances).unwrap().verify().is_ok();
+        assert_eq!(proved, legal, "{} to {}", from, to);
+        proved
+    }
+
+    #[test]
+    fn test_rules() {
+        // per side: 4 pawn, 8 knight, 28 bishop, 28 rook, 56 queen and 8 king moves
+        assert_eq!(rules().len(), 2 * 132);
+        assert_eq!(board(START)[square("e1")], super::KING);
+        assert_eq!(board(START)[square("d8")], super::QUEEN + super::BLACK);
+    }
+
+    #[test]
+    fn test_chess_legal() {
+        // a double step, a knight over the pawns, and black's reply
+        assert!(run(START, WHITE_TO_MOVE, "e2", "e4"));
+        assert!(run(START, WHITE_TO_MOVE, "g1", "f3"));
+        assert!(run(START, BLACK_TO_MOVE, "e7", "e5"));
+
+        // a pawn's capture and push, and a queen's diagonal
+        assert!(run(SCANDINAVIAN, WHITE_TO_MOVE, "e4", "d5"));
+        assert!(run(SCANDINAVIAN, WHITE_TO_MOVE, "e4", "e5"));
+        assert!(run(SCANDINAVIAN, WHITE_TO_MOVE, "d1", "h5"));
+
+        // a rook across the board
+        assert!(run(ROOK_ENDGAME, WHITE_TO_MOVE, "h1", "h8"));
+        assert!(run(ROOK_ENDGAME, WHITE_TO_MOVE, "h1", "f1"));
+    }
+
+    #[test]
+    fn test_chess_illegal() {
+        // a pawn three squares, a blocked bishop, onto an own piece, the other side's pawn
+        assert!(!run(START, WHITE_TO_MOVE, "e2", "e5"));
+        assert!(!run(START, WHITE_TO_MOVE, "f1", "c4"));
+        assert!(!run(START, WHITE_TO_MOVE, "e1", "e2"));
+        assert!(!run(START, WHITE_TO_MOVE, "e7", "e5"));
+
+        // a pawn onto an empty diagonal, a double step from rank 4, and into the pawn in front
+        assert!(!run(SCANDINAVIAN, WHITE_TO_MOVE, "e4", "f5"));
+        assert!(!run(SCANDINAVIAN, WHITE_TO_MOVE, "e4", "e6"));
+        assert!(!run(OPEN_GAME, WHITE_TO_MOVE, "e4", "e5"));
+
+        // a rook around the edge of the board, and through its own king
+        assert!(!run(ROOK_ENDGAME, WHITE_TO_MOVE, "h1", "a2"));
+        assert!(!run(ROOK_ENDGAME, WHITE_TO_MOVE, "h1", "d1"));
+
+        // an empty square
+        assert!(!run(ROOK_ENDGAME, WHITE_TO_MOVE, "a1", "a2"));
+    }
+
+    #[test]
+    fn test_chess_wrong_move() {
+        // a legal move proved, another claimed
+        let board = board(START);
+        let circuit = ChessMoveCircuit::new("e2", "e4");
+        let instances = vec![ChessMoveCircuit::instances(&board, WHITE_TO_MOVE, "e2", "e3")];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+}
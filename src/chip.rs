@@ -0,0 +1,125 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::error::{Halo2ExamplesError, ResultExt};
+
+/// Input loading shared by the chips of this crate, in the style of the halo2 book's
+/// `NumericInstructions`.
+///
+/// Every chip takes its inputs as `AssignedCell`s and copies them into its own regions, so
+/// chips compose inside larger circuits by passing the cells returned by one chip into the
+/// next. A chip only has to name the advice column its inputs are witnessed into; that
+/// column must have equality enabled. The example circuits witness their inputs with
+/// `LoadPrivateChip` instead; these defaults are for using a chip on its own.
+pub trait LoadInstructions<F: FieldExt>: Chip<F> {
+    fn input_column(&self) -> Column<Advice>;
+
+    /// Witnesses a private value.
+    fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let column = self.input_column();
+        layouter
+            .assign_region(
+                || "load private",
+                |mut region| region.assign_advice(|| "private input", column, 0, || value),
+            )
+            .in_chip(std::any::type_name::<Self>(), "load private")
+    }
+
+    /// Witnesses a constant. The circuit must have a constant column, e.g. the one
+    /// `ConstantsChip::configure` enables.
+    fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        constant: F,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let column = self.input_column();
+        layouter
+            .assign_region(
+                || "load constant",
+                |mut region| region.assign_advice_from_constant(|| "constant input", column, 0, constant),
+            )
+            .in_chip(std::any::type_name::<Self>(), "load constant")
+    }
+}
+
+/// Dedicated advice columns, with equality enabled, for witnessing the private inputs of a
+/// circuit. The returned cells are copied into the chips that consume them, so no chip has
+/// to lend one of its own columns for loading.
+#[derive(Debug, Clone)]
+pub struct LoadPrivateConfig {
+    inputs: Vec<Column<Advice>>,
+}
+
+pub struct LoadPrivateChip<F: FieldExt> {
+    config: LoadPrivateConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Chip<F> for LoadPrivateChip<F> {
+    type Config = LoadPrivateConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> LoadPrivateChip<F> {
+    pub fn construct(config: LoadPrivateConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, num_columns: usize) -> LoadPrivateConfig {
+        assert!(num_columns > 0);
+        let inputs: Vec<_> = (0..num_columns).map(|_| meta.advice_column()).collect();
+        for column in inputs.iter() {
+            meta.enable_equality(*column);
+        }
+        LoadPrivateConfig { inputs }
+    }
+
+    /// Witnesses a private value.
+    pub fn load_private(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        Ok(self.load_privates(layouter, &[value])?.remove(0))
+    }
+
+    /// Witnesses several private values in one region, filling the input columns row by row.
+    pub fn load_privates(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Value<F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Halo2ExamplesError> {
+        let columns = &self.config.inputs;
+        layouter
+            .assign_region(
+                || "load private",
+                |mut region| {
+                    values
+                        .iter()
+                        .enumerate()
+                        .map(|(i, value)| {
+                            let (row, column) = (i / columns.len(), columns[i % columns.len()]);
+                            region.assign_advice(|| format!("private input {}", i), column, row, || *value)
+                        })
+                        .collect()
+                },
+            )
+            .in_chip("LoadPrivateChip", "load private")
+    }
+}
@@ -0,0 +1,190 @@
+//! Variants of the examples whose public inputs are published as one Poseidon commitment.
+//!
+//! A verifier contract pays for every instance cell it reads, so instead of exposing each
+//! output the circuits below hand them to `PublicOutputs::constrain_committed`, which hashes
+//! them in-circuit with `CommitChip::commit_all` and exposes the hash alone:
+//!
+//!     instance[0] = commit_all([x_0, ..., x_{n-1}]),   h_0 = n,   h_{i+1} = Poseidon(x_i, h_i)
+//!
+//! The verifier still knows every `x_i`; it recomputes the commitment off-circuit with
+//! `commit_all`, or `PublicInputs::build_committed` and `public_inputs::committed` for the
+//! instances the plain circuit would take. The price is one Poseidon permutation per value
+//! in the circuit, in exchange for a single instance row whatever the number of outputs.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    decompose::decompose_range_check::{DecomposeChip, DecomposeConfig},
+    fibonaci::example1::{FiboChip, FiboConfig},
+    gadgets::commit::{commit_all, CommitChip, CommitConfig},
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    reference::ref_fibonacci,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The Fibonacci circuit of example1, committing to its seeds and 10th term.
+#[derive(Default)]
+pub struct CommittedFiboCircuit {
+    pub a: Value<Fp>,
+    pub b: Value<Fp>,
+}
+
+impl CommittedFiboCircuit {
+    /// The seeds and the 10th term, the values the commitment opens to.
+    pub fn outputs(a: Fp, b: Fp) -> [Fp; 3] {
+        [a, b, ref_fibonacci(a, b, 10)]
+    }
+}
+
+impl Circuit<Fp> for CommittedFiboCircuit {
+    type Config = (FiboConfig, LoadPrivateConfig, PublicOutputsConfig, CommitConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        let fibo = FiboChip::configure(meta, advice);
+        let load = LoadPrivateChip::configure(meta, 1);
+        let outputs = PublicOutputs::configure(meta, instance);
+        (fibo, load, outputs, CommitChip::configure(meta))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, load_config, outputs_config, commit_config) = config;
+        let chip = FiboChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let commit_chip = CommitChip::construct(commit_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+
+        let a = load_chip.load_private(layouter.namespace(|| "load a"), self.a)?;
+        let mut prev_b = load_chip.load_private(layouter.namespace(|| "load b"), self.b)?;
+        let mut prev_c = chip.assign_first_row(layouter.namespace(|| "first row"), &a, &prev_b)?;
+        outputs.expose("fib_a", &a);
+        outputs.expose("fib_b", &prev_b);
+
+        for _ in 3..10 {
+            let c = chip.assign_next_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            prev_b = prev_c;
+            prev_c = c;
+        }
+
+        outputs.expose("fib_out", &prev_c);
+        outputs.constrain_committed(layouter.namespace(|| "public outputs"), &commit_chip)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for CommittedFiboCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let outputs = Self::outputs(known(self.a, "a")?, known(self.b, "b")?);
+        check_instances(instances, &[commit_all(&outputs)])
+    }
+}
+
+/// Decomposes a private 128-bit value into bytes and commits to all 16 of them, where the
+/// plain decompose example would spend an instance row on each.
+#[derive(Default)]
+pub struct CommittedBytesCircuit {
+    pub value: Value<u128>,
+}
+
+impl CommittedBytesCircuit {
+    /// The bytes of `value`, least significant first, the values the commitment opens to.
+    pub fn outputs(value: u128) -> Vec<Fp> {
+        value.to_le_bytes().iter().map(|byte| Fp::from(*byte as u64)).collect()
+    }
+}
+
+impl Circuit<Fp> for CommittedBytesCircuit {
+    type Config = (DecomposeConfig<Fp, 256>, LoadPrivateConfig, PublicOutputsConfig, CommitConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let decompose = DecomposeConfig::configure_with_chunks(meta);
+        let load = LoadPrivateChip::configure(meta, 1);
+        let outputs = PublicOutputs::configure(meta, instance);
+        (decompose, load, outputs, CommitChip::configure(meta))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, load_config, outputs_config, commit_config) = config;
+        let chip = DecomposeChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let commit_chip = CommitChip::construct(commit_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+        chip.load_table(&mut layouter)?;
+
+        let value = load_chip.load_private(layouter.namespace(|| "load value"), self.value.map(Fp::from_u128))?;
+        let bytes: Vec<usize> = (0..16).collect();
+        chip.assign_and_expose(layouter.namespace(|| "decompose value"), &value, 128, &bytes, &mut outputs)?;
+        outputs.constrain_committed(layouter.namespace(|| "public outputs"), &commit_chip)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for CommittedBytesCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let value = known(self.value, "value")?;
+        check_instances(instances, &[commit_all(&Self::outputs(value))])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::{CommittedBytesCircuit, CommittedFiboCircuit};
+    use crate::{
+        fibonaci::INSTANCES,
+        gadgets::commit::commit_all,
+        public_inputs::{committed, PublicInputs},
+        validate::ValidateWitness,
+    };
+
+    #[test]
+    fn test_committed_fibonacci() {
+        let circuit = CommittedFiboCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        let public_inputs = PublicInputs::new(INSTANCES)
+            .set("fib_a", Fp::from(1))
+            .set("fib_b", Fp::from(1))
+            .set("fib_out", Fp::from(55));
+        let instances = public_inputs.clone().build_committed().unwrap();
+        assert_eq!(instances, vec![vec![commit_all(&CommittedFiboCircuit::outputs(Fp::from(1), Fp::from(1)))]]);
+        assert!(circuit.validate_witness(&instances).is_ok());
+        MockProver::run(9, &circuit, instances).unwrap().assert_satisfied();
+
+        // the commitment to a wrong output
+        let instances = public_inputs.set("fib_out", Fp::from(56)).build_committed().unwrap();
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(9, &circuit, instances).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_committed_bytes() {
+        let value: u128 = rand::random();
+        let circuit = CommittedBytesCircuit {
+            value: Value::known(value),
+        };
+        let mut bytes = CommittedBytesCircuit::outputs(value);
+        let instances = committed(&[bytes.clone()]);
+        assert!(circuit.validate_witness(&instances).is_ok());
+        MockProver::run(11, &circuit, instances).unwrap().assert_satisfied();
+
+        bytes[5] += Fp::one();
+        let prover = MockProver::run(11, &circuit, committed(&[bytes])).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
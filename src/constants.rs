@@ -0,0 +1,161 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::error::{Halo2ExamplesError, ResultExt};
+
+/// The advice column constants are copied into. `configure` also makes sure the constraint
+/// system has a fixed column they are copied from, adding one with `enable_constant` for the
+/// first chip that asks and sharing it with every later one, so a circuit that loads its
+/// constants through `ConstantsChip` never has to.
+#[derive(Debug, Clone)]
+pub struct ConstantsConfig {
+    advice: Column<Advice>,
+}
+
+/// Loads named constants as `AssignedCell`s that other chips copy from.
+///
+/// Each distinct value is assigned once per synthesis; loading it again returns the cell
+/// that was already assigned, so e.g. every `z_C == 0` check in a circuit can copy from the
+/// same zero.
+#[derive(Debug, Clone)]
+pub struct ConstantsChip<F: FieldExt> {
+    config: ConstantsConfig,
+    assigned: RefCell<HashMap<Vec<u8>, AssignedCell<F, F>>>,
+}
+
+impl<F: FieldExt> Chip<F> for ConstantsChip<F> {
+    type Config = ConstantsConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> ConstantsChip<F> {
+    pub fn construct(config: ConstantsConfig) -> Self {
+        Self {
+            config,
+            assigned: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> ConstantsConfig {
+        let advice = meta.advice_column();
+        Self::configure_with(meta, advice)
+    }
+
+    /// Like `configure`, copying the constants into an `advice` column the caller already
+    /// has rather than a new one. Each constant takes a region of its own, so the column
+    /// can be one the caller's gates query as well.
+    pub fn configure_with(meta: &mut ConstraintSystem<F>, advice: Column<Advice>) -> ConstantsConfig {
+        Self::enable_constants(meta);
+        meta.enable_equality(advice);
+
+        ConstantsConfig { advice }
+    }
+
+    /// Adds the fixed column constants are assigned in, unless the constraint system has
+    /// one already, for chips such as Poseidon's that assign constants themselves.
+    pub fn enable_constants(meta: &mut ConstraintSystem<F>) {
+        if meta.constants().is_empty() {
+            let fixed = meta.fixed_column();
+            meta.enable_constant(fixed);
+        }
+    }
+
+    /// The cell holding `constant`, assigning it under `name` the first time it is loaded.
+    pub fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        constant: F,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let key = constant.to_repr().as_ref().to_vec();
+        if let Some(cell) = self.assigned.borrow().get(&key) {
+            return Ok(cell.clone());
+        }
+
+        let region_name = format!("load constant {}", name);
+        let cell = layouter
+            .assign_region(
+                || region_name.clone(),
+                |mut region| region.assign_advice_from_constant(|| name, self.config.advice, 0, constant),
+            )
+            .in_chip("ConstantsChip", &region_name)?;
+        self.assigned.borrow_mut().insert(key, cell.clone());
+        Ok(cell)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+
+    use super::{ConstantsChip, ConstantsConfig};
+
+    // Loads 1 three times and 2 once, and copies each load into an unrelated column.
+    #[derive(Default)]
+    struct LoadConstants;
+
+    impl Circuit<Fp> for LoadConstants {
+        type Config = (ConstantsConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let copies = meta.advice_column();
+            meta.enable_equality(copies);
+            (ConstantsChip::configure(meta), copies)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (config, copies) = config;
+            let chip = ConstantsChip::construct(config);
+
+            let cells = [("one", Fp::one()), ("two", Fp::from(2)), ("one", Fp::one()), ("also one", Fp::one())]
+                .into_iter()
+                .map(|(name, constant)| chip.load_constant(layouter.namespace(|| name), name, constant))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(chip.assigned.borrow().len(), 2);
+
+            layouter.assign_region(
+                || "copy constants",
+                |mut region| {
+                    for (row, cell) in cells.iter().enumerate() {
+                        cell.copy_advice(|| "copy", &mut region, copies, row)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_load_constant() {
+        let prover = MockProver::run(4, &LoadConstants, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_constants_column_is_shared() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let advice = meta.advice_column();
+        ConstantsChip::configure(&mut meta);
+        ConstantsChip::configure_with(&mut meta, advice);
+        assert_eq!((meta.num_fixed_columns(), meta.constants().len()), (1, 1));
+    }
+}
@@ -0,0 +1,345 @@
+//! An anonymous credential with selective disclosure: an issuer signs the Poseidon hash of
+//! a holder's attributes, and the holder proves facts about them without showing the
+//! credential, the signature or the attributes beyond the one disclosed.
+//!
+//! The credential is `commit_all([subject, age, country, role])`, signed by the issuer with
+//! the Schnorr signatures of `eddsa`. The public inputs are the issuer's key, the disclosed
+//! `role`, and the predicates the other attributes satisfy:
+//!
+//! 1. `min_age <= age <= max_age`, with all three below `2^AGE_BITS` and compared with
+//!    `MinMaxChip`: `age` is both `max(age, min_age)` and `min(age, max_age)`;
+//! 2. `country` is one of `ALLOWLIST_LEN` allowed codes, as the product of its differences
+//!    from them is 0;
+//!
+//!        country  |  allowed  |    prod    |  q_allow
+//!       ------------------------------------------------
+//!                 |           |     1      |
+//!        country  |    a_0    |   prod_1   |     1
+//!          ...    |    ...    |    ...     |     1
+//!        country  |  a_{n-1}  |   prod_n   |     1
+//!
+//!     prod_i = prod_{i-1} * (country - a_{i-1}),   prod_n = 0
+//!
+//! 3. the signature verifies under the issuer's key.
+//!
+//! `subject` is never disclosed; it names the holder to the issuer and blinds the hash, so
+//! the few possible ages and countries cannot be tried one by one against it.
+
+use halo2_proofs::{
+    circuit::*,
+    pasta::{pallas, Fp},
+    plonk::*,
+    poly::Rotation,
+};
+use rand::RngCore;
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    eddsa::{self, EdDsaChip, EdDsaConfig, Signature},
+    error::{Halo2ExamplesError, ResultExt},
+    gadgets::{
+        commit::commit_all,
+        ecc::coordinates,
+        min_max::{MinMaxChip, MinMaxConfig},
+    },
+    gate::GateBuilder,
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// Ages and their bounds are below `2^AGE_BITS`.
+pub const AGE_BITS: usize = 8;
+
+/// The number of allowed countries.
+pub const ALLOWLIST_LEN: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Credential {
+    pub subject: Fp,
+    pub age: u64,
+    pub country: u64,
+    pub role: u64,
+}
+
+impl Credential {
+    pub fn attributes(&self) -> [Fp; 4] {
+        [self.subject, Fp::from(self.age), Fp::from(self.country), Fp::from(self.role)]
+    }
+
+    /// The hash the issuer signs.
+    pub fn hash(&self) -> Fp {
+        commit_all(&self.attributes())
+    }
+
+    /// The issuer's signature on the credential.
+    pub fn issue(&self, issuer_key: pallas::Scalar, rng: impl RngCore) -> Signature {
+        eddsa::sign(issuer_key, self.hash(), rng)
+    }
+}
+
+/// What the holder proves about the credential.
+#[derive(Debug, Clone, Copy)]
+pub struct Predicates {
+    pub min_age: u64,
+    pub max_age: u64,
+    pub allowlist: [u64; ALLOWLIST_LEN],
+}
+
+#[derive(Debug, Clone)]
+pub struct CredentialConfig {
+    country: Column<Advice>,
+    allowed: Column<Advice>,
+    prod: Column<Advice>,
+    q_allow: Selector,
+    eddsa: EdDsaConfig,
+    min_max: MinMaxConfig,
+    load: LoadPrivateConfig,
+    outputs: PublicOutputsConfig,
+    constants: ConstantsConfig,
+}
+
+/// Discloses the role of a credential and proves the rest satisfies `predicates`.
+#[derive(Default)]
+pub struct CredentialCircuit {
+    pub issuer: Value<pallas::Affine>,
+    pub credential: Value<Credential>,
+    pub signature: Value<Signature>,
+    pub predicates: Value<Predicates>,
+}
+
+impl CredentialCircuit {
+    /// The issuer's key, the role, the age bounds and the allowlist, in instance order.
+    pub fn outputs(issuer: pallas::Affine, role: u64, predicates: &Predicates) -> Vec<Fp> {
+        let (x, y) = coordinates(issuer);
+        [x, y]
+            .into_iter()
+            .chain([role, predicates.min_age, predicates.max_age].map(Fp::from))
+            .chain(predicates.allowlist.map(Fp::from))
+            .collect()
+    }
+
+    fn check_allowlist(
+        &self,
+        config: &CredentialConfig,
+        mut layouter: impl Layouter<Fp>,
+        country: &AssignedCell<Fp, Fp>,
+        allowlist: &[AssignedCell<Fp, Fp>],
+    ) -> Result<(), Halo2ExamplesError> {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        let zero = constants.load_constant(layouter.namespace(|| "zero"), "zero", Fp::zero())?;
+        let one = constants.load_constant(layouter.namespace(|| "one"), "one", Fp::one())?;
+        layouter
+            .assign_region(
+                || "country is allowed",
+                |mut region| {
+                    let mut prod = one.copy_advice(|| "prod_0", &mut region, config.prod, 0)?;
+                    for (i, allowed) in allowlist.iter().enumerate() {
+                        let offset = i + 1;
+                        config.q_allow.enable(&mut region, offset)?;
+                        let country = country.copy_advice(|| "country", &mut region, config.country, offset)?;
+                        let allowed = allowed.copy_advice(|| format!("a_{}", i), &mut region, config.allowed, offset)?;
+
+                        let next = prod.value().copied() * (country.value().copied() - allowed.value().copied());
+                        prod = region.assign_advice(|| format!("prod_{}", offset), config.prod, offset, || next)?;
+                    }
+                    region.constrain_equal(prod.cell(), zero.cell())
+                },
+            )
+            .in_chip("CredentialCircuit", "country is allowed")
+    }
+}
+
+impl Circuit<Fp> for CredentialCircuit {
+    type Config = CredentialConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let eddsa = EdDsaChip::configure(meta, instance);
+        let min_max = MinMaxChip::<Fp, AGE_BITS>::configure(meta, instance);
+        let load = LoadPrivateChip::configure(meta, 1);
+        let outputs = PublicOutputs::configure(meta, instance);
+
+        let [country, allowed, prod] = [(); 3].map(|_| meta.advice_column());
+        let q_allow = meta.selector();
+
+        // the product starts from and ends at loaded constants
+        let constants = ConstantsChip::configure_with(meta, prod);
+        for column in [country, allowed] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("country is allowed", |meta| {
+            let q_allow = meta.query_selector(q_allow);
+            let country = meta.query_advice(country, Rotation::cur());
+            let allowed = meta.query_advice(allowed, Rotation::cur());
+            let prod = meta.query_advice(prod, Rotation::cur());
+            let prod_prev = meta.query_advice(prod, Rotation::prev());
+
+            GateBuilder::with_selector(q_allow).equal("prod = prod_prev * (country - allowed)", prod, prod_prev * (country - allowed))
+        });
+
+        CredentialConfig {
+            country,
+            allowed,
+            prod,
+            q_allow,
+            eddsa,
+            min_max,
+            load,
+            outputs,
+            constants,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let eddsa = EdDsaChip::construct(config.eddsa.clone());
+        let min_max = MinMaxChip::<Fp, AGE_BITS>::construct(config.min_max.clone());
+        let load_chip = LoadPrivateChip::construct(config.load.clone());
+        let mut outputs = PublicOutputs::construct(config.outputs.clone());
+
+        // 1. The signed credential.
+        let issuer = eddsa.ecc().witness_point(layouter.namespace(|| "issuer"), self.issuer)?;
+        let attributes: Vec<_> = (0..4)
+            .map(|i| self.credential.map(|credential| credential.attributes()[i]))
+            .collect();
+        let attributes = load_chip.load_privates(layouter.namespace(|| "load attributes"), &attributes)?;
+        let hash = eddsa.commit().commit_all(layouter.namespace(|| "hash credential"), &attributes)?;
+        let [_, age, country, role]: [_; 4] = attributes.try_into().expect("four attributes loaded");
+        eddsa.verify(layouter.namespace(|| "verify issuer signature"), &issuer, &hash, self.signature)?;
+
+        // 2. The age predicate.
+        let bounds = [
+            self.predicates.map(|predicates| Fp::from(predicates.min_age)),
+            self.predicates.map(|predicates| Fp::from(predicates.max_age)),
+        ];
+        let [min_age, max_age]: [_; 2] = load_chip
+            .load_privates(layouter.namespace(|| "load age bounds"), &bounds)?
+            .try_into()
+            .expect("two bounds loaded");
+        for (name, cell) in [("age", &age), ("min_age", &min_age), ("max_age", &max_age)] {
+            min_max.bits().unpack(layouter.namespace(|| format!("range check {}", name)), cell, AGE_BITS)?;
+        }
+        let above_min = min_max.max(layouter.namespace(|| "max(age, min_age)"), &[age.clone(), min_age.clone()])?;
+        let below_max = min_max.min(layouter.namespace(|| "min(age, max_age)"), &[age.clone(), max_age.clone()])?;
+        layouter
+            .assign_region(
+                || "age in range",
+                |mut region| {
+                    region.constrain_equal(above_min.cell(), age.cell())?;
+                    region.constrain_equal(below_max.cell(), age.cell())
+                },
+            )
+            .in_chip("CredentialCircuit", "age in range")?;
+
+        // 3. The country predicate.
+        let allowlist: Vec<_> = (0..ALLOWLIST_LEN)
+            .map(|i| self.predicates.map(|predicates| Fp::from(predicates.allowlist[i])))
+            .collect();
+        let allowlist = load_chip.load_privates(layouter.namespace(|| "load allowlist"), &allowlist)?;
+        self.check_allowlist(&config, layouter.namespace(|| "country is allowed"), &country, &allowlist)?;
+
+        outputs.expose("issuer_x", &issuer.x);
+        outputs.expose("issuer_y", &issuer.y);
+        outputs.expose("role", &role);
+        outputs.expose("min_age", &min_age);
+        outputs.expose("max_age", &max_age);
+        for (i, allowed) in allowlist.iter().enumerate() {
+            outputs.expose(&format!("allowed_{}", i), allowed);
+        }
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for CredentialCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let issuer = known(self.issuer, "issuer")?;
+        let credential = known(self.credential, "credential")?;
+        let predicates = known(self.predicates, "predicates")?;
+        if !eddsa::verify(issuer, credential.hash(), &known(self.signature, "signature")?) {
+            return Err(InvalidWitness::InvalidParameter(
+                "the issuer did not sign the credential".to_string(),
+            ));
+        }
+
+        let bound = 1 << AGE_BITS;
+        check_below(Fp::from(credential.age), bound, "age")?;
+        check_below(Fp::from(predicates.min_age), bound, "min_age")?;
+        check_below(Fp::from(predicates.max_age), bound, "max_age")?;
+        if credential.age < predicates.min_age || credential.age > predicates.max_age {
+            return Err(InvalidWitness::InvalidParameter(format!(
+                "age {} is not in [{}, {}]",
+                credential.age, predicates.min_age, predicates.max_age
+            )));
+        }
+        if !predicates.allowlist.contains(&credential.country) {
+            return Err(InvalidWitness::InvalidParameter(format!(
+                "country {} is not allowed",
+                credential.country
+            )));
+        }
+        check_instances(instances, &Self::outputs(issuer, credential.role, &predicates))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::Value,
+        dev::MockProver,
+        pasta::{pallas, Fp},
+    };
+    use rand::rngs::OsRng;
+
+    use super::{Credential, CredentialCircuit, Predicates};
+    use crate::{eddsa::public_key, validate::ValidateWitness};
+
+    const K: u32 = 12;
+
+    #[test]
+    fn test_credential() {
+        let issuer_key = pallas::Scalar::random(OsRng);
+        let issuer = public_key(issuer_key);
+        let credential = Credential {
+            subject: Fp::random(OsRng),
+            age: 34,
+            country: 276,
+            role: 2,
+        };
+        let signature = credential.issue(issuer_key, OsRng);
+        let predicates = Predicates {
+            min_age: 18,
+            max_age: 65,
+            allowlist: [40, 250, 276, 380],
+        };
+        let run = |credential: Credential, predicates: Predicates, role: u64| {
+            let circuit = CredentialCircuit {
+                issuer: Value::known(issuer),
+                credential: Value::known(credential),
+                signature: Value::known(signature),
+                predicates: Value::known(predicates),
+            };
+            let instances = vec![CredentialCircuit::outputs(issuer, role, &predicates)];
+            let valid = circuit.validate_witness(&instances).is_ok();
+            assert_eq!(MockProver::run(K, &circuit, instances).unwrap().verify().is_ok(), valid);
+            valid
+        };
+
+        assert!(run(credential, predicates, 2));
+        // another role disclosed
+        assert!(!run(credential, predicates, 3));
+        // too young
+        assert!(!run(credential, Predicates { min_age: 35, ..predicates }, 2));
+        // a country not on the list
+        assert!(!run(credential, Predicates { allowlist: [40, 250, 380, 724], ..predicates }, 2));
+        // attributes the issuer did not sign
+        assert!(!run(Credential { age: 20, ..credential }, predicates, 2));
+    }
+}
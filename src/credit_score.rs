@@ -0,0 +1,206 @@
+//! A credit check that reveals nothing but its outcome: a weighted score of `N` private
+//! attributes exceeds a public threshold,
+//!
+//!     score = w_0 a_0 + ... + w_{N-1} a_{N-1} > threshold
+//!
+//! with the lender's weights public, so the applicant proves the score under the published
+//! model without disclosing e.g. their payment history or their utilisation. The score is an
+//! `InnerProductChip` accumulation of the attributes against the weights.
+//!
+//! Attributes and weights are range checked to `ATTRIBUTE_BITS` bits each, so the score is
+//! below `N * 2^(2 * ATTRIBUTE_BITS)`, which is below `2^SCORE_BITS` for `N` up to 255, and
+//! cannot wrap around the modulus. The comparison is `MinMaxChip`'s, like the Hamming
+//! example's: `threshold + 1` is at most the score exactly when the score is
+//! `max(threshold + 1, score)`, compared in one bit more than the threshold is checked to.
+//!
+//! The public inputs are the weights followed by the threshold. The attributes and the score
+//! stay private.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::LoadInstructions,
+    error::ResultExt,
+    gadgets::{
+        arith::{ArithChip, ArithConfig},
+        inner_product::{InnerProductChip, InnerProductConfig},
+        min_max::{MinMaxChip, MinMaxConfig},
+    },
+    public_inputs::{InstanceChip, InstanceConfig},
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The bits every attribute and weight is range checked to.
+pub const ATTRIBUTE_BITS: usize = 8;
+
+/// The bits the threshold is range checked to, and the score stays below.
+pub const SCORE_BITS: usize = 24;
+
+const COMPARE_BITS: usize = SCORE_BITS + 1;
+
+/// The score of `attributes` under `weights`.
+pub fn score(attributes: &[u64], weights: &[u64]) -> u64 {
+    attributes.iter().zip(weights).map(|(attribute, weight)| attribute * weight).sum()
+}
+
+/// Proves `N` private attributes score above a public threshold under public weights.
+pub struct CreditScoreCircuit<const N: usize> {
+    pub attributes: [Value<u64>; N],
+}
+
+impl<const N: usize> Default for CreditScoreCircuit<N> {
+    fn default() -> Self {
+        Self {
+            attributes: [Value::unknown(); N],
+        }
+    }
+}
+
+impl<const N: usize> CreditScoreCircuit<N> {
+    pub fn new(attributes: [u64; N]) -> Self {
+        Self {
+            attributes: attributes.map(Value::known),
+        }
+    }
+
+    /// The public inputs: the weights, then the threshold.
+    pub fn instances(weights: [u64; N], threshold: u64) -> Vec<Fp> {
+        weights.into_iter().chain([threshold]).map(Fp::from).collect()
+    }
+}
+
+impl<const N: usize> Circuit<Fp> for CreditScoreCircuit<N> {
+    type Config = (InnerProductConfig, MinMaxConfig, ArithConfig, InstanceConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let inner_product = InnerProductChip::configure(meta, instance);
+        let min_max = MinMaxChip::<Fp, COMPARE_BITS>::configure(meta, instance);
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let arith = ArithChip::configure(meta, advice);
+        let public = meta.advice_column();
+        (inner_product, min_max, arith, InstanceChip::configure(meta, instance, public))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (inner_product_config, min_max_config, arith_config, instance_config) = config;
+        let inner_product = InnerProductChip::construct(inner_product_config);
+        let min_max = MinMaxChip::<Fp, COMPARE_BITS>::construct(min_max_config);
+        let arith = ArithChip::construct(arith_config);
+        let instance = InstanceChip::construct(instance_config);
+        let bits = min_max.bits();
+
+        // 1. the score, from range checked attributes and weights
+        let mut attributes = Vec::with_capacity(N);
+        let mut weights = Vec::with_capacity(N);
+        for (i, attribute) in self.attributes.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("attribute {}", i));
+            let attribute = arith.load_private(layouter.namespace(|| "load attribute"), attribute.map(Fp::from))?;
+            bits.unpack(layouter.namespace(|| "range check attribute"), &attribute, ATTRIBUTE_BITS)?;
+            let weight = instance.load(layouter.namespace(|| "load weight"), "weight", i)?;
+            bits.unpack(layouter.namespace(|| "range check weight"), &weight, ATTRIBUTE_BITS)?;
+            attributes.push(attribute);
+            weights.push(weight);
+        }
+        let score = inner_product.inner_product(layouter.namespace(|| "score"), &attributes, &weights)?;
+
+        // 2. score > threshold
+        let threshold = instance.load(layouter.namespace(|| "load threshold"), "threshold", N)?;
+        bits.unpack(layouter.namespace(|| "range check threshold"), &threshold, SCORE_BITS)?;
+        let threshold = arith.add_constant(layouter.namespace(|| "threshold + 1"), &threshold, Fp::one())?;
+        let max = min_max.max(layouter.namespace(|| "max(threshold + 1, score)"), &[threshold, score.clone()])?;
+        layouter
+            .assign_region(|| "score above threshold", |mut region| region.constrain_equal(max.cell(), score.cell()))
+            .in_chip("CreditScoreCircuit", "score above threshold")?;
+        Ok(())
+    }
+}
+
+impl<const N: usize> ValidateWitness<Fp> for CreditScoreCircuit<N> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|attribute| {
+                let attribute = known(*attribute, "attribute")?;
+                check_below(Fp::from(attribute), 1 << ATTRIBUTE_BITS, "attribute")?;
+                Ok(attribute)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+
+        let public = instances.first().filter(|column| column.len() == N + 1).ok_or(InvalidWitness::WrongInstanceCount {
+            expected: N + 1,
+            found: instances.iter().map(Vec::len).sum(),
+        })?;
+        let weights = public[..N]
+            .iter()
+            .map(|weight| {
+                check_below(*weight, 1 << ATTRIBUTE_BITS, "weight")?;
+                Ok(weight.get_lower_128() as u64)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        check_below(public[N], 1 << SCORE_BITS, "threshold")?;
+        let threshold = public[N].get_lower_128() as u64;
+        if score(&attributes, &weights) <= threshold {
+            return Err(InvalidWitness::InvalidParameter("the score does not exceed the threshold".to_string()));
+        }
+
+        let weights = weights.try_into().expect("N weights");
+        check_instances(instances, &Self::instances(weights, threshold))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::{score, CreditScoreCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 9;
+
+    // on-time payments (%), unused credit (%), years of history, accounts in good standing
+    const ATTRIBUTES: [u64; 4] = [96, 70, 12, 3];
+    const WEIGHTS: [u64; 4] = [5, 3, 4, 2];
+
+    #[test]
+    fn test_credit_score() {
+        assert_eq!(score(&ATTRIBUTES, &WEIGHTS), 744);
+        let circuit = CreditScoreCircuit::new(ATTRIBUTES);
+        for threshold in [0, 700, 743] {
+            let instances = vec![CreditScoreCircuit::instances(WEIGHTS, threshold)];
+            assert_eq!(circuit.validate_witness(&instances), Ok(()));
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_credit_score_below_threshold() {
+        let circuit = CreditScoreCircuit::new(ATTRIBUTES);
+        // a score equal to the threshold does not exceed it
+        for threshold in [744, 800] {
+            let instances = vec![CreditScoreCircuit::instances(WEIGHTS, threshold)];
+            assert!(circuit.validate_witness(&instances).is_err());
+            assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+        }
+
+        // weights other than the lender's: one cut by one drops the score to 648
+        let mut instances = CreditScoreCircuit::instances(WEIGHTS, 700);
+        instances[0] -= Fp::one();
+        assert!(MockProver::run(K, &circuit, vec![instances]).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_credit_score_attribute_out_of_range() {
+        // an attribute past 8 bits scores its way over the threshold
+        let circuit = CreditScoreCircuit::new([96, 70, 12, 300]);
+        let instances = vec![CreditScoreCircuit::instances(WEIGHTS, 1000)];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+}
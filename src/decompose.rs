@@ -1,2 +1,3 @@
-mod decompose_range_check;
-mod helpers;
\ No newline at end of file
+pub mod decompose_range_check;
+pub mod runtime;
+pub mod helpers;
\ No newline at end of file
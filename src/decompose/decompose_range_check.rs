@@ -6,6 +6,16 @@ use halo2_proofs::{
     circuit::{floor_planner::V1, *}, plonk::*, poly::Rotation
 };
 use super::helpers;
+use crate::{
+    chip::{LoadInstructions, LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{ErrorSite, Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    public_inputs::PublicOutputs,
+    region::RegionBuilder,
+    table,
+    validate::{check_below, check_bits, check_instances, check_zero, debug_check, known, InvalidWitness, ValidateWitness},
+};
 
 /// This gadget range-constrains an element witnessed in the circuit to be N bits.
 ///
@@ -61,17 +71,12 @@ impl<F: FieldExt, const RANGE: usize> RangeTableConfig<F, RANGE> {
     }
 
     fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        layouter.assign_table(|| "load range check table", |mut table| {
-            for value in 0..RANGE {
-                table.assign_cell(|| "num bits", self.value, value, || Value::known(F::from(value as u64)))?;
-            }
-            Ok(())
-        })
+        table::load_range_table(layouter, self.value, RANGE)
     }
 }
 
 #[derive(Debug, Clone)]
-struct DecomposeConfig<F: FieldExt, const RANGE: usize> {
+pub struct DecomposeConfig<F: FieldExt, const RANGE: usize> {
     // You'll need an advice column to witness your running sum;
     running_sum: Column<Advice>,
     // A selector to constrain the running sum;
@@ -79,19 +84,37 @@ struct DecomposeConfig<F: FieldExt, const RANGE: usize> {
     q_decompose: Selector,
     // And of course, the K-bit lookup table
     lookup_table: RangeTableConfig<F, RANGE>,
+    // Only set up by `configure_with_chunks`, for `assign_and_expose`
+    expose: Option<ExposeConfig>,
+    // The zero `z_C` is constrained to
+    constants: ConstantsConfig,
     _marker: PhantomData<F>,
 }
 
+/// The extra columns `assign_and_expose` needs to make chunks public:
+///
+///     | running_sum |  chunk  |  q_chunk  |
+///     ------------------------------------
+///     |     z_i     |   c_i   |     1     |
+///     |   z_{i+1}   |         |           |
+///
+/// The chunk column only holds the chunks that are exposed, each tied to the running sum
+/// by `c_i = z_i - z_{i+1} * 2^K` and handed to the circuit's `PublicOutputs`.
+#[derive(Debug, Clone)]
+struct ExposeConfig {
+    chunk: Column<Advice>,
+    q_chunk: Selector,
+}
+
 impl<F: FieldExt + PrimeFieldBits, const RANGE: usize> DecomposeConfig<F, RANGE> {
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
         // Create the needed columns and internal configs.
         let running_sum = meta.advice_column();
         let q_decompose = meta.complex_selector();
         let lookup_table = RangeTableConfig::configure(meta);
 
-        // need a fixed column for `constrain_constant` used to enforce `z_C == 0`
-        let constant = meta.fixed_column();
-        meta.enable_constant(constant);
+        // `z_C == 0` is enforced against a loaded constant
+        let constants = ConstantsChip::configure_with(meta, running_sum);
         meta.enable_equality(running_sum);
 
         // Range-constrain each K-bit chunk `c_i = z_i - z_{i+1} * 2^K` derived from the running sum.
@@ -118,53 +141,218 @@ impl<F: FieldExt + PrimeFieldBits, const RANGE: usize> DecomposeConfig<F, RANGE>
             running_sum,
             q_decompose,
             lookup_table,
+            expose: None,
+            constants,
             _marker: PhantomData,
         }
     }
 
-    fn assign(
+    /// Like `configure`, with a column for the chunks that are made public.
+    pub fn configure_with_chunks(meta: &mut ConstraintSystem<F>) -> Self {
+        let mut config = Self::configure(meta);
+        let running_sum = config.running_sum;
+        let chunk = meta.advice_column();
+        let q_chunk = meta.selector();
+
+        meta.enable_equality(chunk);
+
+        meta.create_gate("exposed chunk", |meta| {
+            let q_chunk = meta.query_selector(q_chunk);
+            let z_cur = meta.query_advice(running_sum, Rotation::cur());
+            let z_next = meta.query_advice(running_sum, Rotation::next());
+            let chunk = meta.query_advice(chunk, Rotation::cur());
+            let num_bits = (RANGE as i32 + 1).ilog2();
+
+            GateBuilder::with_selector(q_chunk).equal("c_i = z_i - z_{i+1} * 2^K", chunk, z_cur - z_next * Expression::Constant(F::from(1 << num_bits)))
+        });
+
+        config.expose = Some(ExposeConfig { chunk, q_chunk });
+        config
+    }
+}
+
+pub struct DecomposeChip<F: FieldExt, const RANGE: usize> {
+    config: DecomposeConfig<F, RANGE>,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt, const RANGE: usize> Chip<F> for DecomposeChip<F, RANGE> {
+    type Config = DecomposeConfig<F, RANGE>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize> LoadInstructions<F> for DecomposeChip<F, RANGE> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.running_sum
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const RANGE: usize> DecomposeChip<F, RANGE> {
+    pub fn construct(config: DecomposeConfig<F, RANGE>) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Halo2ExamplesError> {
+        self.config.lookup_table.load(layouter).in_chip("DecomposeChip", "load range check table")
+    }
+
+    pub fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<(), Halo2ExamplesError> {
+        self.decompose(layouter, value, num_bits, &[])?;
+        Ok(())
+    }
+
+    /// Decomposes `value` like `assign`, and exposes the chunks `c_i` listed in `exposed`, in
+    /// that order, as `chunk_i`, e.g. `exposed = [0, 1, ..., 7]` with K = 8 makes the low 64
+    /// bits public as 8 bytes. The config must come from `configure_with_chunks`.
+    pub fn assign_and_expose(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+        exposed: &[usize],
+        outputs: &mut PublicOutputs<F>,
+    ) -> Result<(), Halo2ExamplesError> {
+        if self.config.expose.is_none() {
+            return Err(Halo2ExamplesError::new("DecomposeChip", Error::Synthesis).in_region("expose chunks"));
+        }
+
+        let chunks = self.decompose(layouter, value, num_bits, exposed)?;
+        for (i, chunk) in exposed.iter().zip(chunks.iter()) {
+            outputs.expose(format!("chunk_{}", i), chunk);
+        }
+        Ok(())
+    }
+
+    /// Decomposes each of `values` like `assign`, in a region of its own. The running sums of
+    /// all the values are computed up front, before the first region is laid out, and with
+    /// the `parallel` feature they are computed on rayon's thread pool.
+    pub fn assign_many(
         &self,
         mut layouter: impl Layouter<F>,
-        value: AssignedCell<Assigned<F>, F>,
+        values: &[AssignedCell<F, F>],
         num_bits: usize,
-    ) -> Result<(), Error> {
+    ) -> Result<(), Halo2ExamplesError> {
+        let lookup_num_bits = Self::lookup_num_bits(num_bits)?;
+        let decompositions = values
+            .iter()
+            .map(|value| value.value().map(|&v| Assigned::from(v)))
+            .collect::<Value<Vec<_>>>()
+            .map(|values| helpers::compute_running_sums(&values, num_bits, lookup_num_bits).expect("parameters are checked above"));
+
+        for (i, value) in values.iter().enumerate() {
+            let decomposition = decompositions.as_ref().map(|decompositions| &decompositions[i]);
+            self.lay_out(layouter.namespace(|| format!("decompose value {}", i)), value, num_bits / lookup_num_bits, &[], || {
+                decomposition.map(|decomposition| decomposition.chunks.iter().copied().zip(decomposition.running_sum.iter().copied()))
+            })?;
+        }
+        Ok(())
+    }
+
+    // K, if `num_bits` can be decomposed into K-bit chunks.
+    fn lookup_num_bits(num_bits: usize) -> Result<usize, Halo2ExamplesError> {
         let lookup_num_bits = (RANGE as i32 + 1).ilog2() as usize;
-        assert_eq!(num_bits % lookup_num_bits, 0);
+        if num_bits % lookup_num_bits != 0 || num_bits > F::NUM_BITS as usize {
+            return Err(Halo2ExamplesError::new("DecomposeChip", Error::Synthesis).in_region("Decompose Region"));
+        }
+        Ok(lookup_num_bits)
+    }
 
-        layouter.assign_region(|| "Decompose Region", |mut region| {
-            let mut offset = 0;
-            // 0. Copy in the witnessed `value` 
-            let mut z = value.copy_advice(
-                || "copy value to initialize running sum", &mut region, self.running_sum, offset)?;
-            offset += 1;
-
-            // 1. Compute the interstitial running sum values {z_0, ..., z_C}}
-            let running_sum = value.value().map(|&v| helpers::compute_running_sum(v, num_bits, lookup_num_bits)).transpose_vec(num_bits / lookup_num_bits);
-
-            // 2. Assign the running sum values
-            for z_i in running_sum.into_iter() {
-                z = region.assign_advice(|| format!("assign z_{}", offset), self.running_sum, offset, || z_i)?;
-                offset += 1;
-            }
+    // Decomposes `value`, computing its running sum lazily while it is laid out.
+    fn decompose(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+        exposed: &[usize],
+    ) -> Result<Vec<AssignedCell<F, F>>, Halo2ExamplesError> {
+        let lookup_num_bits = Self::lookup_num_bits(num_bits)?;
+        self.lay_out(layouter, value, num_bits / lookup_num_bits, exposed, || {
+            value.value().map(|&v| helpers::running_sum(Assigned::from(v), num_bits, lookup_num_bits).expect("parameters are checked above"))
+        })
+    }
+
+    // Lays out the running sum yielded by `steps`, and assigns the chunks listed in `exposed`
+    // next to it. `steps` is called once per pass of the floor planner.
+    fn lay_out<I: Iterator<Item = (F, Assigned<F>)>>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_chunks: usize,
+        exposed: &[usize],
+        steps: impl Fn() -> Value<I>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Halo2ExamplesError> {
+        if exposed.iter().any(|&i| i >= num_chunks) {
+            return Err(Halo2ExamplesError::new("DecomposeChip", Error::Synthesis).in_region("Decompose Region"));
+        }
 
-            // 3. Make sure to enable the relevant selector on each row of the running sum
-            for row in 0..(num_bits / lookup_num_bits) {
-                self.q_decompose.enable(&mut region, row)?;
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", F::zero())?;
+        let site = ErrorSite::default();
+        layouter.assign_region(|| "Decompose Region", |mut region| {
+            let mut region = RegionBuilder::new(&mut region).report_to(&site);
+
+            // 0. Copy in the witnessed `value` as z_0. The selector is enabled on the row of
+            //    each z_i whose chunk c_i = z_i - z_{i+1} * 2^K is looked up, i.e. z_0..z_{C-1}.
+            region.enable_selector(&self.config.q_decompose)?;
+            region.copy_advice("z_0", value, self.config.running_sum)?;
+
+            // 1. The interstitial running sum values {z_1, ..., z_C}, with their chunks
+            let mut running_sum = steps();
+
+            // 2. Assign the running sum values, and the exposed chunks c_i on the row of z_i
+            let mut chunks: Vec<Option<AssignedCell<F, F>>> = vec![None; exposed.len()];
+            for i in 0..num_chunks {
+                if i + 1 < num_chunks {
+                    region.enable_selector(&self.config.q_decompose)?;
+                }
+                let step = running_sum.as_mut().map(|running_sum| running_sum.next().expect("one z_i per chunk"));
+                debug_check(&step, || format!("c_{} of the running sum", i), |(chunk, z)| {
+                    check_below(*chunk, 1 << (RANGE as i32 + 1).ilog2(), "c_i")?;
+                    if i + 1 == num_chunks {
+                        check_zero(z.evaluate(), "z_C")?;
+                    }
+                    Ok(())
+                });
+
+                if let (Some(expose), true) = (&self.config.expose, exposed.contains(&i)) {
+                    expose.q_chunk.enable(region.region(), i)?;
+                    let chunk = region.region().assign_advice(
+                        || format!("c_{}", i), expose.chunk, i, || step.map(|(chunk, _)| chunk))?;
+                    for (slot, _) in chunks.iter_mut().zip(exposed).filter(|(_, e)| **e == i) {
+                        *slot = Some(chunk.clone());
+                    }
+                }
+
+                region.assign_advice(format!("z_{}", i + 1), self.config.running_sum, || step.map(|(_, z)| z))?;
             }
 
-            // 4. Constrain the final running sum `z_C` to be 0.
-            region.constrain_constant(z.cell(), F::zero())
-        })
+            // 3. Constrain the final running sum `z_C` to be 0.
+            region.constrain_equal_to(&format!("z_{}", num_chunks), &zero)?;
+            Ok(chunks.into_iter().flatten().collect())
+        }).map_err(|err| site.into_error("DecomposeChip", "Decompose Region", err))
     }
 }
 
-struct DecomposeRangeCheckCircuit<F, const LOOKUP_NUM_BITS: usize, const RANGE: usize> {
-    value: Value<Assigned<F>>,
-    num_bits: usize, // multiple of LOOKUP_NUM_BITS
+pub struct DecomposeRangeCheckCircuit<F, const LOOKUP_NUM_BITS: usize, const RANGE: usize> {
+    pub value: Value<Assigned<F>>,
+    pub num_bits: usize, // multiple of LOOKUP_NUM_BITS
 }
 
 impl<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize, const RANGE: usize> Circuit<F> for DecomposeRangeCheckCircuit<F, LOOKUP_NUM_BITS, RANGE> {
-    type Config = DecomposeConfig<F, RANGE>;
+    type Config = (DecomposeConfig<F, RANGE>, LoadPrivateConfig);
     type FloorPlanner = V1;
 
     fn without_witnesses(&self) -> Self {
@@ -175,26 +363,55 @@ impl<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize, const RANGE: us
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        DecomposeConfig::configure(meta)
+        let decompose = DecomposeConfig::configure(meta);
+        (decompose, LoadPrivateChip::configure(meta, 1))
     }
 
     fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
-        config.lookup_table.load(&mut layouter)?;
+        let (config, load_config) = config;
+        let chip = DecomposeChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        chip.load_table(&mut layouter)?;
 
-        let value = layouter.assign_region(|| "witness region", |mut region| {
-            region.assign_advice(|| "witness value", config.running_sum, 0, || self.value)
-        })?;
+        let value = load_chip.load_private(layouter.namespace(|| "witness value"), self.value.map(|v| v.evaluate()))?;
 
-        config.assign(layouter.namespace(|| "decompose value"), value, self.num_bits)
+        chip.assign(layouter.namespace(|| "decompose value"), &value, self.num_bits)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize, const RANGE: usize> ValidateWitness<F>
+    for DecomposeRangeCheckCircuit<F, LOOKUP_NUM_BITS, RANGE>
+{
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        if self.num_bits % LOOKUP_NUM_BITS != 0 {
+            return Err(InvalidWitness::InvalidParameter(format!(
+                "num_bits {} is not a multiple of {}",
+                self.num_bits, LOOKUP_NUM_BITS
+            )));
+        }
+        check_bits(known(self.value, "value")?.evaluate(), self.num_bits, "value")?;
+        check_instances(instances, &[])
     }
 }
 
 #[cfg(test)]
 mod test {
-    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{floor_planner::V1, Layouter, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
     use rand;
 
-    use super::DecomposeRangeCheckCircuit;
+    use super::{DecomposeChip, DecomposeConfig, DecomposeRangeCheckCircuit};
+    use crate::{
+        chip::LoadInstructions,
+        dev::layout_diff::assert_region_rows,
+        public_inputs::{PublicOutputs, PublicOutputsConfig},
+    };
 
     const K: u32 = 9;
     const NUM_BITS: usize = 8;
@@ -209,25 +426,107 @@ mod test {
         };
         let prover = MockProver::run(K, &circuit, vec![]).unwrap();
         prover.assert_satisfied();
+        // z_0 to z_8
+        assert_region_rows(&circuit, &[("Decompose Region", 9)]);
     }
 
-    #[cfg(feature = "dev-graph")]
+    #[cfg(feature = "debug-assertions")]
     #[test]
-    fn plot_decompose_1() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("decompose-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root
-            .titled("Decompose Range Check Layout", ("sans-serif", 60))
-            .unwrap();
-
+    #[should_panic(expected = "bad witness at c_7 of the running sum: `z_C` must be 0")]
+    fn test_decompose_debug_assertions() {
+        // 2^64 does not fit in 64 bits, which synthesis now reports rather than z_8 != 0
         let circuit = DecomposeRangeCheckCircuit::<Fp, NUM_BITS, RANGE> {
-            value: Value::unknown(),
+            value: Value::known(Fp::from_u128(1 << 64).into()),
             num_bits: 64,
         };
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(9, &circuit, &root)
-            .unwrap();
+        let _ = MockProver::run(K, &circuit, vec![]);
+    }
+
+    // Decomposes a 128-bit value and exposes its low 64 bits as 8 byte chunks.
+    struct ExposeLowBits(Value<Fp>);
+
+    impl Circuit<Fp> for ExposeLowBits {
+        type Config = (DecomposeConfig<Fp, RANGE>, PublicOutputsConfig);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self(Value::unknown())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            (DecomposeConfig::configure_with_chunks(meta), PublicOutputs::configure(meta, instance))
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (config, outputs_config) = config;
+            let chip = DecomposeChip::construct(config);
+            let mut outputs = PublicOutputs::construct(outputs_config);
+            chip.load_table(&mut layouter)?;
+
+            let value = chip.load_private(layouter.namespace(|| "witness value"), self.0)?;
+            chip.assign_and_expose(layouter.namespace(|| "decompose value"), &value, 128, &[0, 1, 2, 3, 4, 5, 6, 7], &mut outputs)?;
+            assert_eq!(outputs.row("chunk_3"), Some(3));
+            outputs.constrain(layouter.namespace(|| "public outputs"))?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assign_and_expose() {
+        let value: u128 = rand::random();
+        let circuit = ExposeLowBits(Value::known(Fp::from_u128(value)));
+        let mut low_bytes: Vec<Fp> = value.to_le_bytes()[..8].iter().map(|byte| Fp::from(*byte as u64)).collect();
+
+        let prover = MockProver::run(K, &circuit, vec![low_bytes.clone()]).unwrap();
+        prover.assert_satisfied();
+        // the exposed chunks sit next to the running sum, z_0 to z_16
+        assert_region_rows(&circuit, &[("Decompose Region", 17)]);
+
+        low_bytes[3] += Fp::one();
+        let prover = MockProver::run(K, &circuit, vec![low_bytes]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    // Decomposes every value into 64 bits, in one batch.
+    struct DecomposeMany(Vec<Value<Fp>>);
+
+    impl Circuit<Fp> for DecomposeMany {
+        type Config = DecomposeConfig<Fp, RANGE>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self(vec![Value::unknown(); self.0.len()])
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            DecomposeConfig::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = DecomposeChip::construct(config);
+            chip.load_table(&mut layouter)?;
+
+            let values = self
+                .0
+                .iter()
+                .map(|value| chip.load_private(layouter.namespace(|| "witness value"), *value))
+                .collect::<Result<Vec<_>, _>>()?;
+            chip.assign_many(layouter.namespace(|| "decompose values"), &values, 64)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assign_many() {
+        let values: Vec<Value<Fp>> = (0..4).map(|_| Value::known(Fp::from(rand::random::<u64>()))).collect();
+        let prover = MockProver::run(K, &DecomposeMany(values.clone()), vec![]).unwrap();
+        prover.assert_satisfied();
+        assert_region_rows(&DecomposeMany(values.clone()), &[("Decompose Region", 4 * 9)]);
+
+        let mut values = values;
+        values[2] = Value::known(Fp::from_u128(1 << 64));
+        let prover = MockProver::run(K, &DecomposeMany(values), vec![]).unwrap();
+        assert!(prover.verify().is_err());
     }
 }
\ No newline at end of file
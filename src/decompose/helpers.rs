@@ -1,37 +1,205 @@
 use ff::PrimeFieldBits;
 use halo2_proofs::{arithmetic::FieldExt, plonk::Assigned};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-pub(super) fn lebs2ip(bits: &[bool]) -> u64 {
-    assert!(bits.len() <= 64);
-    bits.iter()
+/// Little-endian bits to integer, or `None` if there are more than 64 bits.
+pub fn lebs2ip(bits: &[bool]) -> Option<u64> {
+    if bits.len() > 64 {
+        return None;
+    }
+    Some(bits.iter()
         .enumerate()
-        .fold(0u64, |acc, (i, b)| acc + if *b { 1 << i } else { 0 })
+        .fold(0u64, |acc, (i, b)| acc + if *b { 1 << i } else { 0 }))
+}
+
+/// Integer to `NUM_BITS` little-endian bits, the inverse of `lebs2ip`. Bits past the 64th
+/// are zero.
+pub fn i2lebsp<const NUM_BITS: usize>(int: u64) -> [bool; NUM_BITS] {
+    let mut bits = [false; NUM_BITS];
+    for (i, bit) in bits.iter_mut().enumerate().take(64) {
+        *bit = (int >> i) & 1 == 1;
+    }
+    bits
+}
+
+/// 2^K as a field element.
+fn shift<F: FieldExt>(lookup_num_bits: usize) -> F {
+    F::from(2u64).pow_vartime(&[lookup_num_bits as u64])
+}
+
+/// Lazily decomposes a value into K-bit chunks, yielding `(c_i, z_{i+1})` for each chunk.
+///
+/// Bits are pulled from `bits` one chunk at a time and everything else is computed in the
+/// field, so a value spread over many limbs (wider than the field, in which case the running
+/// sum is taken modulo p) never has to be collected into a bit vector first.
+pub struct RunningSum<F: FieldExt, I> {
+    bits: I,
+    lookup_num_bits: usize,
+    remaining: usize,
+    z: Assigned<F>,
+    shift_inv: Assigned<F>,
+}
+
+impl<F: FieldExt, I: Iterator<Item = bool>> RunningSum<F, I> {
+    /// Decomposes the `num_bits` little-endian `bits` of `value`. Returns `None` unless
+    /// `num_bits` is a multiple of a non-zero `lookup_num_bits`.
+    pub fn from_bits(value: Assigned<F>, bits: I, num_bits: usize, lookup_num_bits: usize) -> Option<Self> {
+        if lookup_num_bits == 0 || num_bits % lookup_num_bits != 0 {
+            return None;
+        }
+        Some(Self {
+            bits,
+            lookup_num_bits,
+            remaining: num_bits / lookup_num_bits,
+            z: value,
+            shift_inv: Assigned::from(shift::<F>(lookup_num_bits)).invert(),
+        })
+    }
 }
 
-// Function to compute the interstitial running sum values {z_1, ..., z_C}}
-pub(super) fn compute_running_sum<F: FieldExt + PrimeFieldBits>(
+impl<F: FieldExt, I: Iterator<Item = bool>> Iterator for RunningSum<F, I> {
+    type Item = (F, Assigned<F>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let mut chunk = F::zero();
+        let mut power = F::one();
+        for bit in self.bits.by_ref().take(self.lookup_num_bits) {
+            if bit {
+                chunk += power;
+            }
+            power = power.double();
+        }
+
+        // z_{i+1} = (z_i - c_i) * 2^{-K}:
+        self.z = (self.z - Assigned::from(chunk)) * self.shift_inv;
+        Some((chunk, self.z))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// The lazy running sum of the low `num_bits` bits of a field element. Returns `None`
+/// unless `num_bits` is a non-zero multiple of `lookup_num_bits` that fits in the field.
+pub fn running_sum<F: FieldExt + PrimeFieldBits>(
     value: Assigned<F>,
     num_bits: usize,
     lookup_num_bits: usize,
-) -> Vec<Assigned<F>> {
-    let mut running_sum = vec![];
-    let mut z = value;
-
-    // Get the little-endian bit representation of `value`.
-    let value: Vec<_> = value
-        .evaluate()
-        .to_le_bits()
-        .iter()
-        .by_vals()
-        .take(num_bits)
-        .collect();
-    for chunk in value.chunks(lookup_num_bits) {
-        let chunk = Assigned::from(F::from(lebs2ip(chunk)));
-        // z_{i+1} = (z_i - c_i) * 2^{-K}:
-        z = (z - chunk) * Assigned::from(F::from(1u64 << lookup_num_bits)).invert();
-        running_sum.push(z);
+) -> Option<RunningSum<F, impl Iterator<Item = bool>>> {
+    if num_bits > F::NUM_BITS as usize {
+        return None;
     }
+    let bits = value.evaluate().to_le_bits();
+    RunningSum::from_bits(value, (0..num_bits).map(move |i| bits[i]), num_bits, lookup_num_bits)
+}
 
-    assert_eq!(running_sum.len(), num_bits / lookup_num_bits);
-    running_sum
-}
\ No newline at end of file
+/// The K-bit chunks {c_0, ..., c_{C-1}} of a value and its running sum {z_1, ..., z_C}.
+#[derive(Debug, Clone)]
+pub struct Decomposition<F: FieldExt> {
+    pub chunks: Vec<F>,
+    pub running_sum: Vec<Assigned<F>>,
+}
+
+// Function to compute the chunks and the interstitial running sum values {z_1, ..., z_C}
+// all at once, see `running_sum` for the lazy version.
+pub fn compute_running_sum<F: FieldExt + PrimeFieldBits>(
+    value: Assigned<F>,
+    num_bits: usize,
+    lookup_num_bits: usize,
+) -> Option<Decomposition<F>> {
+    let (chunks, running_sum) = running_sum(value, num_bits, lookup_num_bits)?.unzip();
+    Some(Decomposition { chunks, running_sum })
+}
+
+/// `compute_running_sum` of every value, spread over rayon's thread pool with the `parallel`
+/// feature. Returns `None` if the parameters are invalid.
+pub fn compute_running_sums<F: FieldExt + PrimeFieldBits>(
+    values: &[Assigned<F>],
+    num_bits: usize,
+    lookup_num_bits: usize,
+) -> Option<Vec<Decomposition<F>>> {
+    #[cfg(feature = "parallel")]
+    let values = values.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let values = values.iter();
+
+    values.map(|value| compute_running_sum(*value, num_bits, lookup_num_bits)).collect()
+}
+
+/// Recomposes K-bit chunks into `sum c_i * 2^{K*i}`, the inverse of `compute_running_sum`.
+pub fn recompose<F: FieldExt>(chunks: &[F], lookup_num_bits: usize) -> F {
+    let shift = shift::<F>(lookup_num_bits);
+    chunks.iter().rev().fold(F::zero(), |acc, chunk| acc * shift + chunk)
+}
+
+#[cfg(test)]
+mod test {
+    use ff::{Field, PrimeFieldBits};
+    use halo2_proofs::{arithmetic::FieldExt, pasta::Fp, plonk::Assigned};
+    use proptest::prelude::*;
+
+    use super::{compute_running_sum, i2lebsp, lebs2ip, recompose, RunningSum};
+
+    proptest! {
+        #[test]
+        fn prop_lebs2ip_matches_reference(value: u64, len in 0usize..=64) {
+            let bits: Vec<bool> = (0..len).map(|i| (value >> i) & 1 == 1).collect();
+            let expected = bits.iter().rev().fold(0u128, |acc, bit| acc * 2 + *bit as u128);
+            prop_assert_eq!(lebs2ip(&bits).map(u128::from), Some(expected));
+        }
+
+        #[test]
+        fn prop_i2lebsp_inverts_lebs2ip(value: u64) {
+            prop_assert_eq!(lebs2ip(&i2lebsp::<64>(value)), Some(value));
+            prop_assert_eq!(lebs2ip(&i2lebsp::<16>(value)), Some(value & 0xffff));
+        }
+
+        #[test]
+        fn prop_running_sum_recomposes(value: u64, lookup_num_bits in prop::sample::select(vec![1usize, 2, 4, 8, 16, 64])) {
+            let shift = Fp::from(2).pow_vartime(&[lookup_num_bits as u64]);
+            let decomposition = compute_running_sum(Assigned::from(Fp::from(value)), 64, lookup_num_bits).unwrap();
+            prop_assert_eq!(decomposition.running_sum.len(), 64 / lookup_num_bits);
+            prop_assert_eq!(decomposition.running_sum.last().unwrap().evaluate(), Fp::zero());
+            prop_assert_eq!(recompose(&decomposition.chunks, lookup_num_bits), Fp::from(value));
+
+            // c_i = z_i - 2^K * z_{i+1} must be the i-th K-bit chunk
+            let mut z = Fp::from(value);
+            for (chunk, z_next) in decomposition.chunks.iter().zip(decomposition.running_sum.iter().map(|z| z.evaluate())) {
+                prop_assert_eq!(*chunk, z - z_next * shift);
+                prop_assert!(chunk.to_le_bits().iter().by_vals().skip(lookup_num_bits).all(|bit| !bit));
+                z = z_next;
+            }
+        }
+
+        #[test]
+        fn prop_running_sum_from_limbs(limbs: [u64; 4]) {
+            // lazily decomposing the bits of the limbs gives the same chunks as the field value
+            let bits = limbs.iter().flat_map(|limb| (0..64).map(move |i| (limb >> i) & 1 == 1));
+            let value = recompose(&limbs.map(Fp::from), 64);
+            let (chunks, running_sum): (Vec<_>, Vec<_>) =
+                RunningSum::from_bits(Assigned::from(value), bits, 256, 8).unwrap().unzip();
+            prop_assert_eq!(chunks.len(), 32);
+            prop_assert_eq!(recompose(&chunks, 8), value);
+            prop_assert_eq!(running_sum.last().unwrap().evaluate(), Fp::zero());
+        }
+
+        #[test]
+        fn prop_wide_running_sum_recomposes(bytes in prop::array::uniform31(any::<u8>()), lookup_num_bits in prop::sample::select(vec![4usize, 8, 62, 124])) {
+            // 248-bit values, wider than any intermediate integer type
+            let mut wide = [0u8; 64];
+            wide[..31].copy_from_slice(&bytes);
+            let value = Fp::from_bytes_wide(&wide);
+
+            let decomposition = compute_running_sum(Assigned::from(value), 248, lookup_num_bits).unwrap();
+            prop_assert_eq!(decomposition.running_sum.last().unwrap().evaluate(), Fp::zero());
+            prop_assert_eq!(recompose(&decomposition.chunks, lookup_num_bits), value);
+        }
+    }
+}
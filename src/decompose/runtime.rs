@@ -0,0 +1,228 @@
+use std::marker::PhantomData;
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, *}, plonk::*, poly::Rotation
+};
+use super::helpers;
+use crate::{
+    chip::{LoadInstructions, LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    params::DecomposeParams,
+    table,
+    validate::{check_below, check_bits, check_instances, check_zero, debug_check, known, InvalidWitness, ValidateWitness},
+};
+
+/// The running-sum decomposition of `decompose_range_check`, with the chunk width K and the
+/// value width N chosen at runtime instead of through const generics.
+///
+/// The only place K enters the constraint system of the const-generic version is the
+/// `2^K` constant in the chunk expression. Here it is read from a fixed column instead,
+/// and the lookup table is filled with 0..2^K at synthesis time:
+///
+///     | running_sum |  shift  |  q_decompose  |  lookup_table  |
+///     --------------------------------------------------------
+///     |     z_0     |   2^K   |       1       |       0       |
+///     |     z_1     |   2^K   |       1       |       1       |
+///     |     ...     |   ...   |      ...      |      ...      |
+///     |     z_C     |    0    |       0       |      ...      |
+#[derive(Debug, Clone)]
+pub struct RuntimeDecomposeConfig<F: FieldExt> {
+    running_sum: Column<Advice>,
+    shift: Column<Fixed>,
+    q_decompose: Selector,
+    lookup_table: TableColumn,
+    constants: ConstantsConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt + PrimeFieldBits> RuntimeDecomposeConfig<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let running_sum = meta.advice_column();
+        let shift = meta.fixed_column();
+        let q_decompose = meta.complex_selector();
+        let lookup_table = meta.lookup_table_column();
+
+        // `z_C == 0` is enforced against a loaded constant
+        let constants = ConstantsChip::configure_with(meta, running_sum);
+        meta.enable_equality(running_sum);
+
+        // c_i = z_i - z_{i+1} * 2^K, and 0 on rows where q_decompose is off.
+        meta.lookup(|meta| {
+            let q_decompose = meta.query_selector(q_decompose);
+            let z_cur = meta.query_advice(running_sum, Rotation::cur());
+            let z_next = meta.query_advice(running_sum, Rotation::next());
+            let shift = meta.query_fixed(shift, Rotation::cur());
+
+            vec![(q_decompose * (z_cur - z_next * shift), lookup_table)]
+        });
+
+        Self {
+            running_sum,
+            shift,
+            q_decompose,
+            lookup_table,
+            constants,
+            _marker: PhantomData,
+        }
+    }
+}
+
+pub struct RuntimeDecomposeChip<F: FieldExt> {
+    config: RuntimeDecomposeConfig<F>,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for RuntimeDecomposeChip<F> {
+    type Config = RuntimeDecomposeConfig<F>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> LoadInstructions<F> for RuntimeDecomposeChip<F> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.running_sum
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> RuntimeDecomposeChip<F> {
+    pub fn construct(config: RuntimeDecomposeConfig<F>) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>, lookup_bits: usize) -> Result<(), Halo2ExamplesError> {
+        table::load_range_table(layouter, self.config.lookup_table, 1 << lookup_bits).in_chip("RuntimeDecomposeChip", "load range check table")
+    }
+
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        params: &DecomposeParams,
+    ) -> Result<(), Halo2ExamplesError> {
+        let lookup_bits = params.lookup_bits;
+        let num_chunks = params.num_chunks();
+        if lookup_bits == 0 || lookup_bits >= 64 || params.num_bits % lookup_bits != 0 || params.num_bits > F::NUM_BITS as usize {
+            return Err(Halo2ExamplesError::new("RuntimeDecomposeChip", Error::Synthesis).in_region("Decompose Region"));
+        }
+
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", F::zero())?;
+        layouter.assign_region(|| "Decompose Region", |mut region| {
+            let mut z = value.copy_advice(
+                || "copy value to initialize running sum", &mut region, self.config.running_sum, 0)?.cell();
+
+            let mut running_sum = value
+                .value()
+                .map(|&v| helpers::running_sum(Assigned::from(v), params.num_bits, lookup_bits).expect("parameters are checked above"));
+
+            for row in 0..num_chunks {
+                let step = running_sum.as_mut().map(|running_sum| running_sum.next().expect("one z_i per chunk"));
+                debug_check(&step, || format!("c_{} of the running sum", row), |(chunk, z)| {
+                    check_below(*chunk, 1 << lookup_bits, "c_i")?;
+                    if row + 1 == num_chunks {
+                        check_zero(z.evaluate(), "z_C")?;
+                    }
+                    Ok(())
+                });
+                let z_i = step.map(|(_, z)| z);
+                self.config.q_decompose.enable(&mut region, row)?;
+                region.assign_fixed(|| "2^K", self.config.shift, row, || Value::known(F::from(1u64 << lookup_bits)))?;
+                z = region.assign_advice(|| format!("assign z_{}", row + 1), self.config.running_sum, row + 1, || z_i)?.cell();
+            }
+
+            region.constrain_equal(z, zero.cell())
+        }).in_chip("RuntimeDecomposeChip", "Decompose Region")
+    }
+}
+
+pub struct RuntimeDecomposeCircuit<F> {
+    pub value: Value<Assigned<F>>,
+    pub params: DecomposeParams,
+}
+
+impl<F: FieldExt + PrimeFieldBits> Circuit<F> for RuntimeDecomposeCircuit<F> {
+    type Config = (RuntimeDecomposeConfig<F>, LoadPrivateConfig);
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            value: Value::unknown(),
+            params: self.params.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let decompose = RuntimeDecomposeConfig::configure(meta);
+        (decompose, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = RuntimeDecomposeChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        chip.load_table(&mut layouter, self.params.lookup_bits)?;
+
+        let value = load_chip.load_private(layouter.namespace(|| "witness value"), self.value.map(|v| v.evaluate()))?;
+
+        chip.assign(layouter.namespace(|| "decompose value"), &value, &self.params)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> ValidateWitness<F> for RuntimeDecomposeCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        self.params
+            .validate()
+            .map_err(|err| InvalidWitness::InvalidParameter(err.to_string()))?;
+        check_bits(known(self.value, "value")?.evaluate(), self.params.num_bits, "value")?;
+        check_instances(instances, &[])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::RuntimeDecomposeCircuit;
+    use crate::{dev::layout_diff::assert_region_rows, params::DecomposeParams};
+
+    #[test]
+    fn test_runtime_decompose() {
+        let value: u64 = rand::random();
+        for (k, lookup_bits, num_bits) in [(9, 8, 64), (5, 4, 64), (6, 2, 64), (9, 8, 32)] {
+            let params = DecomposeParams { k, lookup_bits, num_bits };
+            params.validate().unwrap();
+
+            let value = if num_bits < 64 { value >> (64 - num_bits) } else { value };
+            let circuit = RuntimeDecomposeCircuit::<Fp> {
+                value: Value::known(Fp::from(value).into()),
+                params: params.clone(),
+            };
+            let prover = MockProver::run(params.k, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+            // z_0 to z_C
+            assert_region_rows(&circuit, &[("Decompose Region", num_bits / lookup_bits + 1), ("load private", 1)]);
+        }
+    }
+
+    #[test]
+    fn test_runtime_decompose_too_wide() {
+        let params = DecomposeParams { k: 5, lookup_bits: 4, num_bits: 32 };
+        let circuit = RuntimeDecomposeCircuit::<Fp> {
+            value: Value::known(Fp::from(1u64 << 32).into()),
+            params: params.clone(),
+        };
+        let prover = MockProver::run(params.k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
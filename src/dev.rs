@@ -0,0 +1,18 @@
+pub mod tamper;
+pub mod mutate;
+pub mod trace;
+pub mod diff_witness;
+pub mod rotation;
+pub mod failure;
+pub mod layout_diff;
+pub mod constraint_system;
+#[cfg(all(feature = "fibonacci", feature = "range-check", feature = "gadgets"))]
+pub mod stats;
+#[cfg(all(feature = "fibonacci", feature = "range-check", feature = "gadgets"))]
+pub mod golden;
+#[cfg(all(feature = "fibonacci", feature = "range-check", feature = "gadgets"))]
+pub mod gallery;
+pub mod zk;
+pub mod fields;
+#[cfg(all(feature = "dev-graph", feature = "fibonacci", feature = "range-check", feature = "gadgets"))]
+pub mod layout;
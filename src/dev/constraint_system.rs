@@ -0,0 +1,202 @@
+//! A readable dump of what a circuit's `configure` produced: its gates, lookups and the
+//! columns in the permutation argument, as text or JSON.
+
+use std::fmt;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::{Any, Circuit, Column, ConstraintSystem, Expression},
+};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConstraintDump {
+    pub name: String,
+    pub expression: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GateDump {
+    pub name: String,
+    pub constraints: Vec<ConstraintDump>,
+}
+
+/// A lookup of `inputs` into `table`, paired up by position.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LookupDump {
+    pub inputs: Vec<String>,
+    pub table: Vec<String>,
+}
+
+/// The constraint system of a circuit. Columns are written `A2` (advice column 2), `F0`,
+/// `I0`, and a rotated query as `A2@+1`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConstraintSystemDump {
+    pub degree: usize,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub selectors: usize,
+    pub gates: Vec<GateDump>,
+    pub lookups: Vec<LookupDump>,
+    /// The columns with equality enabled.
+    pub permutation: Vec<String>,
+}
+
+impl ConstraintSystemDump {
+    pub fn of<F: FieldExt, C: Circuit<F>>() -> Self {
+        let mut cs = ConstraintSystem::<F>::default();
+        C::configure(&mut cs);
+
+        let gates = cs
+            .gates()
+            .iter()
+            .map(|gate| GateDump {
+                name: gate.name().to_string(),
+                constraints: gate
+                    .polynomials()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, polynomial)| ConstraintDump {
+                        name: gate.constraint_name(i).to_string(),
+                        expression: expression(polynomial),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let lookups = cs
+            .lookups()
+            .iter()
+            .map(|lookup| LookupDump {
+                inputs: lookup.input_expressions().iter().map(expression).collect(),
+                table: lookup.table_expressions().iter().map(expression).collect(),
+            })
+            .collect();
+
+        Self {
+            degree: cs.degree(),
+            advice_columns: cs.num_advice_columns(),
+            fixed_columns: cs.num_fixed_columns(),
+            instance_columns: cs.num_instance_columns(),
+            selectors: cs.num_selectors(),
+            gates,
+            lookups,
+            permutation: cs.permutation().get_columns().iter().map(column).collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl fmt::Display for ConstraintSystemDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "columns: {} advice, {} fixed, {} instance, {} selectors; degree {}",
+            self.advice_columns, self.fixed_columns, self.instance_columns, self.selectors, self.degree
+        )?;
+
+        for gate in &self.gates {
+            writeln!(f, "gate {:?}", gate.name)?;
+            for constraint in &gate.constraints {
+                if constraint.name.is_empty() {
+                    writeln!(f, "  {} = 0", constraint.expression)?;
+                } else {
+                    writeln!(f, "  {:?}: {} = 0", constraint.name, constraint.expression)?;
+                }
+            }
+        }
+
+        for (i, lookup) in self.lookups.iter().enumerate() {
+            writeln!(f, "lookup {}: ({}) in ({})", i, lookup.inputs.join(", "), lookup.table.join(", "))?;
+        }
+
+        writeln!(f, "permutation: {}", self.permutation.join(", "))
+    }
+}
+
+fn column(column: &Column<Any>) -> String {
+    let prefix = match column.column_type() {
+        Any::Advice => "A",
+        Any::Fixed => "F",
+        Any::Instance => "I",
+    };
+    format!("{}{}", prefix, column.index())
+}
+
+fn query(prefix: &str, column_index: usize, rotation: i32) -> String {
+    match rotation {
+        0 => format!("{}{}", prefix, column_index),
+        rotation => format!("{}{}@{:+}", prefix, column_index, rotation),
+    }
+}
+
+/// Small constants and their negations are printed in decimal, anything else as the
+/// field's own debug representation.
+fn constant<F: FieldExt>(value: &F) -> String {
+    let small = |value: &F| {
+        let lower = value.get_lower_128();
+        (lower < 1 << 64 && F::from_u128(lower) == *value).then_some(lower)
+    };
+    match (small(value), small(&-*value)) {
+        (Some(value), _) => value.to_string(),
+        (None, Some(negated)) => format!("-{}", negated),
+        (None, None) => format!("{:?}", value),
+    }
+}
+
+fn expression<F: FieldExt>(expr: &Expression<F>) -> String {
+    match expr {
+        Expression::Constant(value) => constant(value),
+        Expression::Selector(selector) => format!("{:?}", selector),
+        Expression::Fixed { column_index, rotation, .. } => query("F", *column_index, rotation.0),
+        Expression::Advice { column_index, rotation, .. } => query("A", *column_index, rotation.0),
+        Expression::Instance { column_index, rotation, .. } => query("I", *column_index, rotation.0),
+        Expression::Negated(inner) => format!("-{}", factor(inner)),
+        Expression::Sum(left, right) => match right.as_ref() {
+            Expression::Negated(right) => format!("{} - {}", expression(left), factor(right)),
+            right => format!("{} + {}", expression(left), expression(right)),
+        },
+        Expression::Product(left, right) => format!("{} * {}", factor(left), factor(right)),
+        Expression::Scaled(inner, scalar) => format!("{} * {}", factor(inner), constant(scalar)),
+    }
+}
+
+/// An operand of a product or negation, parenthesized if it is a sum.
+fn factor<F: FieldExt>(inner: &Expression<F>) -> String {
+    match inner {
+        Expression::Sum(..) => format!("({})", expression(inner)),
+        inner => expression(inner),
+    }
+}
+
+#[cfg(all(test, feature = "fibonacci", feature = "decompose"))]
+mod test {
+    use halo2_proofs::pasta::Fp;
+
+    use super::ConstraintSystemDump;
+    use crate::{decompose::decompose_range_check::DecomposeRangeCheckCircuit, fibonaci::example2::FiboCircuit};
+
+    #[test]
+    fn test_dump_fibonacci() {
+        let dump = ConstraintSystemDump::of::<Fp, FiboCircuit<Fp>>();
+        assert_eq!(dump.gates.len(), 1);
+        assert_eq!(dump.gates[0].name, "add");
+        assert!(dump.gates[0].constraints[0].expression.ends_with(" * (A0 + A0@+1 - A0@+2)"));
+        assert_eq!(dump.permutation, vec!["A0", "I0"]);
+        assert!(dump.lookups.is_empty());
+
+        let json: serde_json::Value = serde_json::from_str(&dump.to_json().unwrap()).unwrap();
+        assert_eq!(json["gates"][0]["name"], "add");
+    }
+
+    #[test]
+    fn test_dump_decompose() {
+        let dump = ConstraintSystemDump::of::<Fp, DecomposeRangeCheckCircuit<Fp, 8, 256>>();
+        assert_eq!(dump.lookups.len(), 1);
+        assert!(dump.to_string().contains("lookup 0: "));
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+
+use halo2_proofs::{arithmetic::FieldExt, plonk::Circuit};
+
+use super::trace::{trace, TracedCell};
+
+/// A cell whose assigned value is not the one the reference model expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch<F> {
+    pub cell: TracedCell<F>,
+    pub expected: F,
+}
+
+/// The outcome of `diff_witness`, with the mismatches in the order the circuit assigned
+/// the cells, so the first one is usually the bug and the rest follow from it.
+#[derive(Debug, Clone)]
+pub struct WitnessDiff<F> {
+    /// How many cells the reference model had a value for.
+    pub checked: usize,
+    pub mismatches: Vec<Mismatch<F>>,
+}
+
+impl<F> WitnessDiff<F> {
+    pub fn is_empty(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl<F: fmt::Debug> fmt::Display for WitnessDiff<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} of {} checked cells differ", self.mismatches.len(), self.checked)?;
+        for Mismatch { cell, expected } in &self.mismatches {
+            writeln!(
+                f,
+                "  {} / {} (row {}): assigned {:?}, expected {:?}",
+                cell.region.as_deref().unwrap_or("(outside any region)"),
+                cell.annotation,
+                cell.row,
+                cell.value,
+                expected
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `circuit` through `MockProver` and compares every advice and fixed cell it assigns
+/// with an off-circuit reference model, whether or not the circuit is satisfied. `reference`
+/// is asked for each cell and returns the value it should hold, or `None` where the model
+/// has no opinion, e.g. for the cells of a gadget that is not being ported.
+///
+/// For a gadget ported from a specification, such as SHA-256, the model is the
+/// specification itself computing the same intermediate values. A wrong rotation amount
+/// then shows up as the first cell that differs, rather than as a failing gate somewhere
+/// downstream.
+pub fn diff_witness<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instances: Vec<Vec<F>>,
+    mut reference: impl FnMut(&TracedCell<F>) -> Option<F>,
+) -> WitnessDiff<F> {
+    let mut checked = 0;
+    let mismatches = trace(k, circuit, instances)
+        .cells
+        .into_iter()
+        .filter_map(|cell| {
+            let expected = reference(&cell)?;
+            checked += 1;
+            (cell.value != expected).then_some(Mismatch { cell, expected })
+        })
+        .collect();
+    WitnessDiff { checked, mismatches }
+}
+
+/// Panics with the diff unless the circuit's witness agrees with `reference` on every cell
+/// the reference has a value for.
+pub fn assert_witness_matches<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instances: Vec<Vec<F>>,
+    reference: impl FnMut(&TracedCell<F>) -> Option<F>,
+) {
+    let diff = diff_witness(k, circuit, instances, reference);
+    assert!(diff.checked > 0, "the reference model had no value for any cell");
+    assert!(diff.is_empty(), "{}", diff);
+}
+
+#[cfg(all(test, feature = "decompose"))]
+mod test {
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    use super::{assert_witness_matches, diff_witness};
+    use crate::{decompose::decompose_range_check::DecomposeRangeCheckCircuit, reference::ref_running_sum};
+
+    // z_i of the running sum of `value` in `bits`-bit chunks, by the name it is assigned
+    // under
+    fn running_sum(value: u64, num_bits: usize, bits: usize) -> impl Fn(&str) -> Option<Fp> {
+        let running_sum = ref_running_sum(Fp::from(value), num_bits, bits);
+        move |annotation| {
+            let i: usize = annotation.strip_prefix("z_")?.parse().ok()?;
+            running_sum.get(i).copied()
+        }
+    }
+
+    #[test]
+    fn test_diff_witness_running_sum() {
+        let value = 0x1234_5678_9abc_def0u64;
+        let circuit = DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::known(Fp::from(value).into()),
+            num_bits: 64,
+        };
+
+        let model = running_sum(value, 64, 8);
+        assert_witness_matches(9, &circuit, vec![], |cell| model(&cell.annotation));
+
+        // a model shifting by 7 bits instead of 8 parts ways at z_1
+        let model = running_sum(value, 63, 7);
+        let diff = diff_witness(9, &circuit, vec![], |cell| model(&cell.annotation));
+        assert_eq!(diff.checked, 9);
+        assert_eq!(diff.mismatches.len(), 8);
+        assert_eq!(diff.mismatches[0].cell.annotation, "z_1");
+        assert_eq!(diff.mismatches[0].expected, Fp::from(value >> 7));
+        assert!(diff
+            .to_string()
+            .starts_with("8 of 9 checked cells differ\n  Decompose Region / z_1 (row 1)"));
+    }
+}
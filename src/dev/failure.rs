@@ -0,0 +1,73 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::{FailureLocation, MockProver, VerifyFailure},
+    plonk::Circuit,
+};
+
+/// Runs `MockProver` and returns its failures, panicking if the circuit is satisfied.
+pub fn verify_failures<F: FieldExt, C: Circuit<F>>(k: u32, circuit: &C, instances: Vec<Vec<F>>) -> Vec<VerifyFailure> {
+    MockProver::run(k, circuit, instances)
+        .expect("synthesis failed")
+        .verify()
+        .expect_err("the circuit was expected to fail verification")
+}
+
+/// Whether some constraint of the gate named `gate` failed inside the region named `region`.
+///
+/// Gates and regions are matched by name only, through their `Display` output (their fields
+/// are private in `halo2_proofs::dev::metadata`), so adding a column or a region elsewhere
+/// in the circuit does not change the outcome.
+pub fn has_constraint_failure(failures: &[VerifyFailure], gate: &str, region: &str) -> bool {
+    failures.iter().any(|failure| match failure {
+        VerifyFailure::ConstraintNotSatisfied { constraint, location, .. } => {
+            constraint.to_string().ends_with(&format!("('{}')", gate)) && in_region(location, region)
+        }
+        _ => false,
+    })
+}
+
+/// Whether the lookup with index `lookup_index` failed inside the region named `region`.
+pub fn has_lookup_failure(failures: &[VerifyFailure], lookup_index: usize, region: &str) -> bool {
+    failures.iter().any(|failure| match failure {
+        VerifyFailure::Lookup { lookup_index: index, location } => *index == lookup_index && in_region(location, region),
+        _ => false,
+    })
+}
+
+fn in_region(location: &FailureLocation, name: &str) -> bool {
+    match location {
+        FailureLocation::InRegion { region, .. } => region.to_string().ends_with(&format!("('{}')", name)),
+        FailureLocation::OutsideRegion { .. } => false,
+    }
+}
+
+/// Asserts that `MockProver` rejects `circuit` with a failure of the named gate (or, with
+/// `lookup <index>`, of the given lookup) inside the named region.
+///
+/// ```ignore
+/// assert_constraint_fails!(circuit, 9, vec![], "Range Check", "assign value for simple range check");
+/// assert_constraint_fails!(circuit, 9, vec![], lookup 0, "assign value for lookup range check");
+/// ```
+#[macro_export]
+macro_rules! assert_constraint_fails {
+    ($circuit:expr, $k:expr, $instances:expr, lookup $index:expr, $region:expr) => {{
+        let failures = $crate::dev::failure::verify_failures($k, &$circuit, $instances);
+        assert!(
+            $crate::dev::failure::has_lookup_failure(&failures, $index, $region),
+            "expected lookup {} to fail in region {:?}, got {:#?}",
+            $index,
+            $region,
+            failures
+        );
+    }};
+    ($circuit:expr, $k:expr, $instances:expr, $gate:expr, $region:expr) => {{
+        let failures = $crate::dev::failure::verify_failures($k, &$circuit, $instances);
+        assert!(
+            $crate::dev::failure::has_constraint_failure(&failures, $gate, $region),
+            "expected gate {:?} to fail in region {:?}, got {:#?}",
+            $gate,
+            $region,
+            failures
+        );
+    }};
+}
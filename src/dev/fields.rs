@@ -0,0 +1,36 @@
+/// Runs tests written generically over the field on every field the crate supports, the
+/// Pasta fields `Fp` and `Fq`. halo2_proofs 0.2 implements its `FieldExt` for those only, so
+/// the bn254 leg, over halo2curves' `bn256::Fr`, runs in the `next/` crate, whose macro of
+/// the same name instantiates its tests over all three.
+///
+/// Each named function takes the field as its one type parameter; a module of the same name
+/// holds one `#[test]` per field, so a failure names the field it happened on:
+///
+/// ```ignore
+/// fn test_bits_round_trip<F: FieldExt + PrimeFieldBits>() { ... }
+///
+/// test_over_fields!(test_bits_round_trip, test_bits_out_of_range);
+/// // test::test_bits_round_trip::fp, test::test_bits_round_trip::fq, ...
+/// ```
+///
+/// The chips under test should then stay away from what holds for one field only: a
+/// modulus of 255 bits, a repr of some length, an element of 2-adic order. Bit widths go
+/// through `F::NUM_BITS` and `F::CAPACITY`, and small values through `get_lower_128`.
+#[macro_export]
+macro_rules! test_over_fields {
+    ($($test:ident),+ $(,)?) => {
+        $(
+            mod $test {
+                #[test]
+                fn fp() {
+                    super::$test::<halo2_proofs::pasta::Fp>();
+                }
+
+                #[test]
+                fn fq() {
+                    super::$test::<halo2_proofs::pasta::Fq>();
+                }
+            }
+        )+
+    };
+}
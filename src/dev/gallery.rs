@@ -0,0 +1,778 @@
+//! Every example of the crate run with sample inputs, for a first look at what is in it.
+//!
+//! The gallery is `stats::visit_examples` plus the applications built on the gadgets, each
+//! behind its feature. Every circuit is mock-proved at the smallest k it fits in, and with
+//! `prove` also proved and verified for real, which is what takes time.
+//!
+//! `cargo run --release --bin gallery [-- --prove]` prints the table.
+
+use std::time::{Duration, Instant};
+
+use halo2_proofs::{dev::MockProver, pasta::Fp, plonk::{Circuit, Error}};
+
+use super::{
+    layout_diff::layout_stats,
+    stats::{visit_examples, ExampleVisitor, MAX_K},
+};
+use crate::prover::{prove, setup, verify};
+
+/// How an example fared.
+#[derive(Debug)]
+pub struct GalleryEntry {
+    pub name: &'static str,
+    /// The smallest k at which `MockProver` accepts the circuit, `None` if there is none up
+    /// to `MAX_K`.
+    pub k: Option<u32>,
+    pub rows: usize,
+    /// One `MockProver` run and verification at `k`.
+    pub mock_time: Duration,
+    /// With `prove`, the time to prove and verify for real at `k`, or why that failed.
+    pub proof: Option<Result<Duration, Error>>,
+}
+
+impl GalleryEntry {
+    pub fn ok(&self) -> bool {
+        self.k.is_some() && !matches!(self.proof, Some(Err(_)))
+    }
+}
+
+/// Runs `circuit` as the gallery does. Unlike `stats::measure`, failures are reported
+/// rather than panicking, so one broken example does not hide the others.
+pub fn run<C: Circuit<Fp>>(name: &'static str, circuit: &C, instances: Vec<Vec<Fp>>, real: bool) -> GalleryEntry {
+    let verifies = |k| MockProver::run(k, circuit, instances.clone()).is_ok_and(|prover| prover.verify().is_ok());
+    let k = (1..=MAX_K).find(|&k| verifies(k));
+
+    let start = Instant::now();
+    verifies(k.unwrap_or(MAX_K));
+    let mock_time = start.elapsed();
+
+    let proof = match k {
+        Some(k) if real => Some(prove_and_verify(k, circuit, &instances)),
+        _ => None,
+    };
+
+    GalleryEntry {
+        name,
+        k,
+        rows: layout_stats(circuit).rows,
+        mock_time,
+        proof,
+    }
+}
+
+fn prove_and_verify<C: Circuit<Fp>>(k: u32, circuit: &C, instances: &[Vec<Fp>]) -> Result<Duration, Error> {
+    let start = Instant::now();
+    let (params, pk) = setup(k, circuit)?;
+    let proof = prove(&params, &pk, circuit, instances)?;
+    verify(&params, pk.get_vk(), instances, &proof)?;
+    Ok(start.elapsed())
+}
+
+/// Runs every example, proving each for real if `real` is set.
+pub fn gallery(real: bool) -> Vec<GalleryEntry> {
+    struct Run {
+        real: bool,
+        entries: Vec<GalleryEntry>,
+    }
+
+    impl ExampleVisitor for Run {
+        fn visit<C: Circuit<Fp>>(&mut self, name: &'static str, circuit: &C, instances: Vec<Vec<Fp>>) {
+            self.entries.push(run(name, circuit, instances, self.real));
+        }
+    }
+
+    let mut visitor = Run { real, entries: vec![] };
+    visit_gallery(&mut visitor);
+    visitor.entries
+}
+
+/// Hands every example, the gadget examples of `visit_examples` and then the applications
+/// the enabled features include, to `visitor`. Keys and salts come from a fixed seed, so
+/// every run sees the same inputs.
+pub fn visit_gallery(visitor: &mut impl ExampleVisitor) {
+    visit_examples(visitor);
+    #[cfg(feature = "hash")]
+    visit_commitments(visitor);
+    #[cfg(feature = "vm")]
+    visit_vm(visitor);
+    #[cfg(feature = "median")]
+    visit_median(visitor);
+    #[cfg(feature = "aes")]
+    visit_aes(visitor);
+    #[cfg(feature = "blake2s")]
+    visit_blake2s(visitor);
+    #[cfg(feature = "hmac")]
+    visit_hmac(visitor);
+    #[cfg(feature = "hash-chain")]
+    visit_hash_chain(visitor);
+    #[cfg(feature = "otp")]
+    visit_otp(visitor);
+    #[cfg(feature = "poly-commit")]
+    visit_poly_commit(visitor);
+    #[cfg(feature = "shamir")]
+    visit_shamir(visitor);
+    #[cfg(feature = "hamming")]
+    visit_hamming(visitor);
+    #[cfg(feature = "edit-distance")]
+    visit_edit_distance(visitor);
+    #[cfg(feature = "binary-search")]
+    visit_binary_search(visitor);
+    #[cfg(feature = "interval")]
+    visit_interval(visitor);
+    #[cfg(feature = "ledger")]
+    visit_ledger(visitor);
+    #[cfg(feature = "reserves")]
+    visit_reserves(visitor);
+    #[cfg(feature = "payroll")]
+    visit_payroll(visitor);
+    #[cfg(feature = "credit-score")]
+    visit_credit_score(visitor);
+    #[cfg(feature = "kyc")]
+    visit_kyc(visitor);
+    #[cfg(feature = "iban")]
+    visit_iban(visitor);
+    #[cfg(feature = "ean")]
+    visit_ean(visitor);
+    #[cfg(feature = "sudoku")]
+    visit_sudoku(visitor);
+    #[cfg(feature = "nonogram")]
+    visit_nonogram(visitor);
+    #[cfg(feature = "chess")]
+    visit_chess(visitor);
+    #[cfg(feature = "tic-tac-toe")]
+    visit_tic_tac_toe(visitor);
+    #[cfg(feature = "dice")]
+    visit_dice(visitor);
+    #[cfg(feature = "lottery")]
+    visit_lottery(visitor);
+    #[cfg(feature = "auction")]
+    visit_auction(visitor);
+    #[cfg(feature = "ecc")]
+    visit_signatures(visitor);
+    #[cfg(feature = "merkle")]
+    visit_airdrop(visitor);
+}
+
+#[cfg(feature = "hash")]
+fn visit_commitments(visitor: &mut impl ExampleVisitor) {
+    use halo2_proofs::circuit::Value;
+
+    use crate::{
+        committed::{CommittedBytesCircuit, CommittedFiboCircuit},
+        public_inputs::committed,
+        sinsemilla::{self, SinsemillaCircuit},
+    };
+
+    let (a, b) = (Fp::from(1), Fp::from(1));
+    visitor.visit(
+        "committed fibonacci",
+        &CommittedFiboCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        },
+        committed(&[CommittedFiboCircuit::outputs(a, b).to_vec()]),
+    );
+
+    let value = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210u128;
+    visitor.visit(
+        "committed bytes",
+        &CommittedBytesCircuit {
+            value: Value::known(value),
+        },
+        committed(&[CommittedBytesCircuit::outputs(value)]),
+    );
+
+    let message = [0xdead_beef, 7, 0, u32::MAX];
+    visitor.visit(
+        "sinsemilla",
+        &SinsemillaCircuit {
+            message: message.iter().copied().map(Value::known).collect(),
+        },
+        vec![vec![sinsemilla::hash(&message)]],
+    );
+}
+
+#[cfg(feature = "vm")]
+fn visit_vm(visitor: &mut impl ExampleVisitor) {
+    use halo2_proofs::circuit::Value;
+
+    use crate::vm::{Instruction::*, VmCircuit};
+
+    // acc := 3 * acc + 1, then count it down to zero, three instructions a step
+    let program = vec![Mul(3), Add(1), Jz(5), Add(-1), Jmp(2), Halt];
+    let (input, steps) = (Fp::from(2), 2 + 7 * 3 + 1 + 2);
+    let instances = vec![VmCircuit::instances(&program, input, steps)];
+    visitor.visit(
+        "vm",
+        &VmCircuit {
+            program,
+            steps,
+            input: Value::known(input),
+        },
+        instances,
+    );
+}
+
+#[cfg(feature = "median")]
+fn visit_median(visitor: &mut impl ExampleVisitor) {
+    use halo2_proofs::circuit::Value;
+
+    use crate::median::PercentileCircuit;
+
+    type Median = PercentileCircuit<16>;
+    let values = [42, 7, 65535, 7, 0, 300, 12];
+    let rank = Median::median_rank(values.len());
+    visitor.visit(
+        "median",
+        &Median {
+            values: values.iter().copied().map(Value::known).collect(),
+        },
+        vec![Median::instances(&values, rank).into_iter().map(Fp::from).collect()],
+    );
+}
+
+#[cfg(feature = "aes")]
+fn visit_aes(visitor: &mut impl ExampleVisitor) {
+    use halo2_proofs::circuit::Value;
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::aes::AesRoundCircuit;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0x0061_6573);
+    let [plaintext, key_0, key_1] = [(); 3].map(|_| {
+        let mut bytes = [0u8; 16];
+        rng.fill_bytes(&mut bytes);
+        bytes
+    });
+    visitor.visit(
+        "aes round",
+        &AesRoundCircuit {
+            plaintext: plaintext.map(Value::known),
+            keys: [key_0, key_1].map(|key| key.map(Value::known)),
+        },
+        vec![AesRoundCircuit::instances(plaintext, [key_0, key_1]).into_iter().map(Fp::from).collect()],
+    );
+}
+
+#[cfg(feature = "blake2s")]
+fn visit_blake2s(visitor: &mut impl ExampleVisitor) {
+    use halo2_proofs::circuit::Value;
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::blake2s::Blake2sCircuit;
+
+    let mut message = [0u8; 64];
+    ChaCha20Rng::seed_from_u64(0x0062_326b).fill_bytes(&mut message);
+    visitor.visit(
+        "blake2s",
+        &Blake2sCircuit {
+            message: message.map(Value::known),
+        },
+        vec![Blake2sCircuit::instances(message).into_iter().map(Fp::from).collect()],
+    );
+}
+
+#[cfg(feature = "hmac")]
+fn visit_hmac(visitor: &mut impl ExampleVisitor) {
+    use halo2_proofs::circuit::Value;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::hmac::HmacCircuit;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0x686d_6163);
+    let (key, message): ([u32; 8], [u32; 8]) = (rng.gen(), rng.gen());
+    visitor.visit(
+        "hmac-sha256",
+        &HmacCircuit {
+            key: key.map(Value::known),
+            message: message.map(Value::known),
+        },
+        vec![HmacCircuit::instances(key, message).into_iter().map(Fp::from).collect()],
+    );
+}
+
+#[cfg(feature = "hash-chain")]
+fn visit_hash_chain(visitor: &mut impl ExampleVisitor) {
+    use ff::Field;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::hash_chain::HashChainCircuit;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0x0063_6861);
+    let (secret, salt) = (Fp::random(&mut rng), Fp::random(&mut rng));
+    let circuit: HashChainCircuit = HashChainCircuit::new(secret, salt, 16);
+    visitor.visit("hash chain", &circuit, vec![<HashChainCircuit>::outputs(secret, salt, 16).to_vec()]);
+}
+
+#[cfg(feature = "otp")]
+fn visit_otp(visitor: &mut impl ExampleVisitor) {
+    use ff::Field;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::{gadgets::commit::CommitChip, otp};
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0x006f_7470);
+    let (seed, blinding) = (Fp::random(&mut rng), Fp::random(&mut rng));
+    let passwords = otp::chain::<CommitChip>(seed, 8);
+    visitor.visit(
+        "one-time password",
+        &otp::OtpCircuit::<8>::new(blinding),
+        vec![otp::OtpCircuit::<8>::instances(passwords[5], 3, blinding)],
+    );
+}
+
+#[cfg(feature = "poly-commit")]
+fn visit_poly_commit(visitor: &mut impl ExampleVisitor) {
+    use ff::Field;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::poly_commit::PolyCommitCircuit;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0x0070_6373);
+    let coeffs: [Fp; 8] = std::array::from_fn(|_| Fp::random(&mut rng));
+    let (blinding, x) = (Fp::random(&mut rng), Fp::random(&mut rng));
+    visitor.visit(
+        "polynomial commitment",
+        &PolyCommitCircuit::new(coeffs, blinding),
+        vec![PolyCommitCircuit::instances(&coeffs, blinding, x)],
+    );
+}
+
+#[cfg(feature = "shamir")]
+fn visit_shamir(visitor: &mut impl ExampleVisitor) {
+    use ff::Field;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::shamir::{self, ShamirCircuit};
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0x7368_616d);
+    let secret = Fp::random(&mut rng);
+    let shares = shamir::split(secret, 3, 5, &mut rng);
+    visitor.visit(
+        "shamir reconstruction",
+        &ShamirCircuit::<3>::new([shares[0], shares[2], shares[4]]),
+        vec![vec![ShamirCircuit::<3>::secret_hash(secret)]],
+    );
+}
+
+#[cfg(feature = "hamming")]
+fn visit_hamming(visitor: &mut impl ExampleVisitor) {
+    use crate::hamming::HammingCircuit;
+
+    let template = [0x5eed_1e55, 0xc0de_cafe, 0x0bad_f00d, 0x1234_5678];
+    let sample = [0x5eed_1e55 ^ 0x11, 0xc0de_cafe, 0x0bad_f00d ^ 0x0100_0000, 0x1234_5678];
+    visitor.visit(
+        "hamming distance",
+        &HammingCircuit::new(sample, template, 8),
+        vec![HammingCircuit::instances(template, 8)],
+    );
+}
+
+#[cfg(feature = "edit-distance")]
+fn visit_edit_distance(visitor: &mut impl ExampleVisitor) {
+    use crate::edit_distance::EditDistanceCircuit;
+
+    visitor.visit(
+        "edit distance",
+        &EditDistanceCircuit::new(b"flaw", b"lawn", 2),
+        vec![EditDistanceCircuit::<4, 4>::instances(b"lawn", 2)],
+    );
+}
+
+#[cfg(feature = "binary-search")]
+fn visit_binary_search(visitor: &mut impl ExampleVisitor) {
+    use halo2_proofs::pasta::Fp;
+
+    use crate::binary_search::BinarySearchCircuit;
+
+    let values = [1, 1, 2, 3, 5, 8, 13, 21];
+    let blinding = Fp::from(0xb1_5ec7);
+    visitor.visit(
+        "binary search",
+        &BinarySearchCircuit::new(values, blinding),
+        vec![BinarySearchCircuit::instances(&values, blinding, 5, 4)],
+    );
+}
+
+#[cfg(feature = "interval")]
+fn visit_interval(visitor: &mut impl ExampleVisitor) {
+    use halo2_proofs::pasta::Fp;
+
+    use crate::interval::{Claim, IntervalCircuit};
+
+    let calendar = [(540, 600), (660, 720), (840, 900)];
+    let (blinding, salt) = (Fp::from(0xca1e), Fp::from(0xb00c));
+    let booking = (600, 660);
+    visitor.visit(
+        "free slot",
+        &IntervalCircuit::new(calendar, blinding, booking, salt, Claim::Disjoint),
+        vec![IntervalCircuit::instances(&calendar, blinding, booking, salt)],
+    );
+}
+
+#[cfg(feature = "ledger")]
+fn visit_ledger(visitor: &mut impl ExampleVisitor) {
+    use crate::ledger::{LedgerCircuit, Posting};
+
+    // a purchase on account, settled in cash through a clearing account
+    let (cash, payables, clearing) = (1000, 2000, 1900);
+    let postings = [
+        Posting::debit(clearing, 400),
+        Posting::credit(payables, 400),
+        Posting::debit(payables, 400),
+        Posting::credit(cash, 400),
+        Posting::debit(cash, 400),
+        Posting::credit(clearing, 400),
+    ];
+    visitor.visit(
+        "ledger",
+        &LedgerCircuit::new(&postings, &[cash, payables, clearing]),
+        vec![LedgerCircuit::instances(&postings)],
+    );
+}
+
+#[cfg(feature = "reserves")]
+fn visit_reserves(visitor: &mut impl ExampleVisitor) {
+    use crate::reserves::ReservesCircuit;
+
+    let accounts = [(Fp::from(0xa11ce), 1_500), (Fp::from(0xb0b), 20_000), (Fp::from(0xca401), 0)];
+    visitor.visit(
+        "proof of reserves",
+        &ReservesCircuit::<2>::new(&accounts),
+        vec![ReservesCircuit::<2>::instances(&accounts)],
+    );
+}
+
+#[cfg(feature = "payroll")]
+fn visit_payroll(visitor: &mut impl ExampleVisitor) {
+    use crate::payroll::{cents, PayrollCircuit};
+
+    let salaries = ["48000.00", "51500.75", "60250.00"].map(|amount| cents(amount).expect("a valid amount"));
+    let [min, max, budget] = ["45000", "65000", "175000"].map(|amount| cents(amount).expect("a valid amount"));
+    visitor.visit(
+        "payroll",
+        &PayrollCircuit::new(&salaries),
+        vec![PayrollCircuit::instances(min, max, budget as u64)],
+    );
+}
+
+#[cfg(feature = "credit-score")]
+fn visit_credit_score(visitor: &mut impl ExampleVisitor) {
+    use crate::credit_score::CreditScoreCircuit;
+
+    // on-time payments (%), unused credit (%), years of history, accounts in good standing
+    visitor.visit(
+        "credit score",
+        &CreditScoreCircuit::new([88, 45, 6, 2]),
+        vec![CreditScoreCircuit::instances([5, 3, 4, 2], 550)],
+    );
+}
+
+#[cfg(feature = "kyc")]
+fn visit_kyc(visitor: &mut impl ExampleVisitor) {
+    use crate::kyc::{denylist, KycCircuit};
+
+    let (allowlist, denylist) = ([40, 250, 276, 380], denylist(&[364, 408, 760]));
+    let salt = Fp::from(0x5a17);
+    visitor.visit(
+        "kyc",
+        &KycCircuit::new(&allowlist, &denylist, 250, salt),
+        vec![KycCircuit::instances(250, salt, &denylist)],
+    );
+}
+
+#[cfg(feature = "iban")]
+fn visit_iban(visitor: &mut impl ExampleVisitor) {
+    use crate::iban::IbanCircuit;
+
+    visitor.visit(
+        "iban",
+        &IbanCircuit::<22>::new("GB82 WEST 1234 5698 7654 32"),
+        vec![IbanCircuit::<22>::instances("GB")],
+    );
+}
+
+#[cfg(feature = "ean")]
+fn visit_ean(visitor: &mut impl ExampleVisitor) {
+    use crate::ean::EanCircuit;
+
+    visitor.visit(
+        "ean",
+        &EanCircuit::<13>::new("978-0-306-40615-7"),
+        vec![EanCircuit::<13>::instances(true)],
+    );
+}
+
+#[cfg(feature = "sudoku")]
+fn visit_sudoku(visitor: &mut impl ExampleVisitor) {
+    use crate::sudoku::{clues, SudokuCircuit};
+
+    let clues = clues("53..7.... 6..195... .98....6. 8...6...3 4..8.3..1 7...2...6 .6....28. ...419..5 ....8..79");
+    let solution = [
+        [5, 3, 4, 6, 7, 8, 9, 1, 2],
+        [6, 7, 2, 1, 9, 5, 3, 4, 8],
+        [1, 9, 8, 3, 4, 2, 5, 6, 7],
+        [8, 5, 9, 7, 6, 1, 4, 2, 3],
+        [4, 2, 6, 8, 5, 3, 7, 9, 1],
+        [7, 1, 3, 9, 2, 4, 8, 5, 6],
+        [9, 6, 1, 5, 3, 7, 2, 8, 4],
+        [2, 8, 7, 4, 1, 9, 6, 3, 5],
+        [3, 4, 5, 2, 8, 6, 1, 7, 9],
+    ];
+    visitor.visit(
+        "sudoku",
+        &SudokuCircuit::new(solution, clues.len()),
+        vec![SudokuCircuit::instances(&clues)],
+    );
+}
+
+#[cfg(feature = "nonogram")]
+fn visit_nonogram(visitor: &mut impl ExampleVisitor) {
+    use crate::nonogram::{clues, NonogramCircuit};
+
+    let heart = [".#.#.", "#####", "#####", ".###.", "..#.."].map(|row| {
+        let mut cells = [false; 5];
+        for (cell, c) in cells.iter_mut().zip(row.chars()) {
+            *cell = c == '#';
+        }
+        cells
+    });
+    let (rows, cols) = clues(&heart);
+    visitor.visit(
+        "nonogram",
+        &NonogramCircuit::new(heart),
+        vec![NonogramCircuit::<5, 5>::instances(&rows, &cols)],
+    );
+}
+
+#[cfg(feature = "chess")]
+fn visit_chess(visitor: &mut impl ExampleVisitor) {
+    use crate::chess::{board, ChessMoveCircuit, WHITE_TO_MOVE};
+
+    let board = board("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR");
+    visitor.visit(
+        "chess move",
+        &ChessMoveCircuit::new("d1", "h5"),
+        vec![ChessMoveCircuit::instances(&board, WHITE_TO_MOVE, "d1", "h5")],
+    );
+}
+
+#[cfg(feature = "tic-tac-toe")]
+fn visit_tic_tac_toe(visitor: &mut impl ExampleVisitor) {
+    use crate::tic_tac_toe::{TicTacToeCircuit, DRAW};
+
+    visitor.visit(
+        "tic-tac-toe",
+        &TicTacToeCircuit::new(&[0, 1, 2, 4, 3, 5, 7, 6, 8]),
+        vec![TicTacToeCircuit::instances(DRAW)],
+    );
+}
+
+#[cfg(feature = "dice")]
+fn visit_dice(visitor: &mut impl ExampleVisitor) {
+    use ff::Field;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::dice::DiceCircuit;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0x0064_6963);
+    let (seed, blinding) = (Fp::random(&mut rng), Fp::random(&mut rng));
+    let circuit: DiceCircuit = DiceCircuit::new(seed, blinding);
+    visitor.visit("dice", &circuit, vec![<DiceCircuit>::instances(seed, blinding, Fp::from(1))]);
+}
+
+#[cfg(feature = "lottery")]
+fn visit_lottery(visitor: &mut impl ExampleVisitor) {
+    use std::marker::PhantomData;
+
+    use ff::Field;
+    use halo2_proofs::circuit::Value;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::{
+        gadgets::{commit::CommitChip, merkle::MerkleTree},
+        lottery::{ticket, wins, LotteryCircuit},
+    };
+
+    const DEPTH: usize = 4;
+    const WIN_BITS: usize = 2;
+    let mut rng = ChaCha20Rng::seed_from_u64(0x6c6f_7474);
+    let draw = Fp::random(&mut rng);
+    let mut secrets: Vec<_> = (0..8).map(|_| Fp::random(&mut rng)).collect();
+
+    // sell a winning ticket among the others, so the draw is claimed whatever the seed
+    let index = 3;
+    secrets[index] = std::iter::repeat_with(|| Fp::random(&mut rng))
+        .find(|secret| wins(ticket::<CommitChip>(*secret), draw, WIN_BITS))
+        .expect("an endless search");
+    let tickets: Vec<_> = secrets.iter().map(|secret| ticket::<CommitChip>(*secret)).collect();
+    let tree = MerkleTree::new(DEPTH, &tickets);
+    let recipient = Fp::from(0xa11ce);
+    let siblings: [Fp; DEPTH] = tree.path(index as u64).try_into().expect("a path of DEPTH siblings");
+    visitor.visit(
+        "lottery claim",
+        &LotteryCircuit::<DEPTH, WIN_BITS> {
+            secret: Value::known(secrets[index]),
+            index: Value::known(index as u64),
+            siblings: siblings.map(Value::known),
+            draw: Value::known(draw),
+            recipient: Value::known(recipient),
+            hasher: PhantomData,
+        },
+        vec![LotteryCircuit::<DEPTH, WIN_BITS>::outputs(tree.root(), draw, secrets[index], recipient).to_vec()],
+    );
+}
+
+#[cfg(feature = "auction")]
+fn visit_auction(visitor: &mut impl ExampleVisitor) {
+    use halo2_proofs::circuit::Value;
+
+    use crate::auction::AuctionCircuit;
+
+    let bids = [30, 70, 50, 10];
+    let salts: Vec<_> = (0..bids.len()).map(|i| Fp::from(0x5a17 + i as u64)).collect();
+    visitor.visit(
+        "sealed-bid auction",
+        &AuctionCircuit {
+            bids: bids.iter().copied().map(Value::known).collect(),
+            salts: salts.iter().copied().map(Value::known).collect(),
+        },
+        vec![AuctionCircuit::instances(&bids, &salts)],
+    );
+}
+
+#[cfg(feature = "ecc")]
+fn visit_signatures(visitor: &mut impl ExampleVisitor) {
+    use ff::Field;
+    use halo2_proofs::{circuit::Value, pasta::pallas};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::{
+        credential::{Credential, CredentialCircuit, Predicates},
+        eddsa::{public_key, sign, EdDsaCircuit},
+    };
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0x6761_6c6c);
+    let secret_key = pallas::Scalar::random(&mut rng);
+    let key = public_key(secret_key);
+    let message = Fp::from(42);
+    let signature = sign(secret_key, message, &mut rng);
+    visitor.visit(
+        "eddsa",
+        &EdDsaCircuit {
+            public_key: Value::known(key),
+            message: Value::known(message),
+            signature: Value::known(signature),
+        },
+        vec![EdDsaCircuit::outputs(key, message).to_vec()],
+    );
+
+    let credential = Credential {
+        subject: Fp::random(&mut rng),
+        age: 34,
+        country: 276,
+        role: 2,
+    };
+    let predicates = Predicates {
+        min_age: 18,
+        max_age: 65,
+        allowlist: [40, 250, 276, 380],
+    };
+    visitor.visit(
+        "credential",
+        &CredentialCircuit {
+            issuer: Value::known(key),
+            credential: Value::known(credential),
+            signature: Value::known(credential.issue(secret_key, &mut rng)),
+            predicates: Value::known(predicates),
+        },
+        vec![CredentialCircuit::outputs(key, credential.role, &predicates)],
+    );
+}
+
+#[cfg(feature = "merkle")]
+fn visit_airdrop(visitor: &mut impl ExampleVisitor) {
+    use std::marker::PhantomData;
+
+    use ff::Field;
+    use halo2_proofs::circuit::Value;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::{
+        airdrop::{address, leaf, AirdropCircuit},
+        gadgets::{commit::CommitChip, merkle::MerkleTree},
+    };
+
+    const DEPTH: usize = 4;
+    let mut rng = ChaCha20Rng::seed_from_u64(0x6169_7264);
+    let secrets: Vec<_> = (0..5).map(|_| Fp::random(&mut rng)).collect();
+    let amounts = [100, 250, 50, 1000, 75];
+    let leaves: Vec<_> = secrets
+        .iter()
+        .zip(amounts)
+        .map(|(secret, amount)| leaf::<CommitChip>(address::<CommitChip>(*secret), amount))
+        .collect();
+    let tree = MerkleTree::new(DEPTH, &leaves);
+
+    let (index, recipient) = (3, Fp::from(0xa11ce));
+    let siblings: [Fp; DEPTH] = tree.path(index).try_into().expect("a path of DEPTH siblings");
+    visitor.visit(
+        "airdrop claim",
+        &AirdropCircuit::<DEPTH> {
+            secret: Value::known(secrets[3]),
+            amount: Value::known(amounts[3]),
+            index: Value::known(index),
+            siblings: siblings.map(Value::known),
+            recipient: Value::known(recipient),
+            hasher: PhantomData,
+        },
+        vec![AirdropCircuit::<DEPTH>::outputs(tree.root(), secrets[3], recipient, amounts[3]).to_vec()],
+    );
+}
+
+/// The entries as a Markdown table.
+pub fn to_markdown(entries: &[GalleryEntry]) -> String {
+    let mut table = "| example | ok | k | rows | mock ms | prove ms |\n| --- | --- | --- | --- | --- | --- |\n".to_string();
+    for entry in entries {
+        let k = entry.k.map_or("-".to_string(), |k| k.to_string());
+        let proof = match &entry.proof {
+            None => "-".to_string(),
+            Some(Ok(time)) => time.as_millis().to_string(),
+            Some(Err(err)) => format!("{:?}", err),
+        };
+        table.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            entry.name,
+            if entry.ok() { "yes" } else { "no" },
+            k,
+            entry.rows,
+            entry.mock_time.as_millis(),
+            proof
+        ));
+    }
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use super::{gallery, to_markdown};
+
+    #[test]
+    fn test_gallery_inputs_are_valid() {
+        let entries = gallery(false);
+        let failed: Vec<_> = entries.iter().filter(|entry| !entry.ok()).map(|entry| entry.name).collect();
+        assert!(failed.is_empty(), "no k up to MAX_K satisfies {:?}", failed);
+        assert_eq!(to_markdown(&entries).lines().count(), entries.len() + 2);
+    }
+}
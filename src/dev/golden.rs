@@ -0,0 +1,82 @@
+//! Golden files of the verifying keys of the example circuits, so that a change to a
+//! constraint system, which breaks every verifier deployed for the old key, never goes
+//! unnoticed.
+//!
+//! A key is fingerprinted by its pinned form, `{:#?}` of `VerifyingKey::pinned`: the domain,
+//! the gates, columns, permutation and lookups of the constraint system, and the
+//! commitments to the fixed columns and the permutation. It is computed at the smallest k
+//! the circuit is satisfied at, as `stats` measures it, since a circuit outgrowing its k
+//! changes its key too. The goldens live in `tests/golden`, one `<circuit>.vk` file each.
+//!
+//! `tests/golden.rs` fails on any difference or missing golden; `cargo run --bin golden -- --bless`,
+//! or the test run with `BLESS=1`, rewrites the goldens once a change is intended, and is
+//! worth a line in the changelog.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use halo2_proofs::{pasta::Fp, plonk::Circuit};
+
+use super::stats::{min_k, visit_examples, ExampleVisitor};
+use crate::prover::setup;
+
+/// The pinned verifying key of an example circuit.
+#[derive(Debug, Clone)]
+pub struct Golden {
+    pub name: &'static str,
+    pub fingerprint: String,
+}
+
+impl Golden {
+    /// The golden file of the circuit in `dir`.
+    pub fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.vk", self.name.replace(' ', "_")))
+    }
+}
+
+/// `{:#?}` of the pinned verifying key of `circuit` at its smallest k.
+pub fn fingerprint<C: Circuit<Fp>>(name: &str, circuit: &C, instances: &[Vec<Fp>]) -> String {
+    let k = min_k(name, circuit, instances);
+    let (_, pk) = setup(k, circuit).expect("keygen failed");
+    format!("{:#?}\n", pk.get_vk().pinned())
+}
+
+/// The fingerprints of every example circuit.
+pub fn goldens() -> Vec<Golden> {
+    struct Fingerprint(Vec<Golden>);
+
+    impl ExampleVisitor for Fingerprint {
+        fn visit<C: Circuit<Fp>>(&mut self, name: &'static str, circuit: &C, instances: Vec<Vec<Fp>>) {
+            let fingerprint = fingerprint(name, circuit, &instances);
+            self.0.push(Golden { name, fingerprint });
+        }
+    }
+
+    let mut goldens = Fingerprint(vec![]);
+    visit_examples(&mut goldens);
+    goldens.0
+}
+
+/// Where the golden files are kept.
+pub fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+/// The circuits whose golden file in `dir` is missing or differs from `goldens`.
+pub fn mismatches<'a>(goldens: &'a [Golden], dir: &Path) -> Vec<&'a Golden> {
+    goldens
+        .iter()
+        .filter(|golden| fs::read_to_string(golden.path(dir)).map_or(true, |file| file.replace("\r\n", "\n") != golden.fingerprint))
+        .collect()
+}
+
+/// Writes the golden file of each of `goldens` into `dir`.
+pub fn bless(goldens: &[Golden], dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for golden in goldens {
+        fs::write(golden.path(dir), &golden.fingerprint)?;
+    }
+    Ok(())
+}
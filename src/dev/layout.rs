@@ -0,0 +1,167 @@
+use std::{error::Error, marker::PhantomData, path::Path};
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{SimpleFloorPlanner, Value},
+    dev::CircuitLayout,
+    pasta::Fp,
+    plonk::Circuit,
+};
+use plotters::prelude::*;
+
+use crate::{
+    decompose::{decompose_range_check::DecomposeRangeCheckCircuit, runtime::RuntimeDecomposeCircuit},
+    fibonaci::{example1, example2},
+    floor_planner::{ShiftedPlanner, WithPlanner},
+    gadgets::is_equal::IsEqualCircuit,
+    params::DecomposeParams,
+    range_check::{self, dynamic::DynamicRangeCheckCircuit},
+};
+
+/// How `render_layouts` draws each circuit.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Renders every circuit with `2^k` rows instead of its own default. Rendering panics if
+    /// a circuit does not fit.
+    pub k: Option<u32>,
+    /// Labels each region with its name.
+    pub show_labels: bool,
+    pub mark_equality_cells: bool,
+    pub show_equality_constraints: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            k: None,
+            show_labels: true,
+            mark_equality_cells: false,
+            show_equality_constraints: false,
+        }
+    }
+}
+
+/// Renders the layout of every example circuit into `dir`, as `<name>-layout.png` and
+/// `<name>-layout.svg`. Circuits are laid out without witnesses, so only their shape shows.
+pub fn render_layouts(dir: impl AsRef<Path>, options: &RenderOptions) -> Result<(), Box<dyn Error>> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+
+    render(dir, options, "fibonaci-example1", 4, &example1::FiboCircuit::<Fp>::default())?;
+    render(dir, options, "fibonaci-example2", 4, &example2::FiboCircuit::<Fp>(PhantomData))?;
+    render(
+        dir,
+        options,
+        "range-check-example1",
+        4,
+        &range_check::example1::RangeCheckCircuit::<Fp, 8>::default(),
+    )?;
+    render(
+        dir,
+        options,
+        "range-check-example2",
+        9,
+        &range_check::example2::RangeCheckCircuit::<Fp, 8, 4>::default(),
+    )?;
+    render(
+        dir,
+        options,
+        "range-check-dynamic",
+        9,
+        &DynamicRangeCheckCircuit::<Fp, 8> {
+            values: vec![(Value::unknown(), 4), (Value::unknown(), 8)],
+        },
+    )?;
+    render(
+        dir,
+        options,
+        "decompose",
+        9,
+        &DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::unknown(),
+            num_bits: 64,
+        },
+    )?;
+    let params = DecomposeParams::default();
+    render(
+        dir,
+        options,
+        "decompose-runtime",
+        params.k,
+        &RuntimeDecomposeCircuit::<Fp> {
+            value: Value::unknown(),
+            params,
+        },
+    )?;
+    render(dir, options, "is-equal", 4, &IsEqualCircuit::<Fp>::default())?;
+
+    // the decompose circuit under other floor planners than its own `V1`
+    let decompose = || DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+        value: Value::unknown(),
+        num_bits: 64,
+    };
+    render(dir, options, "decompose-simple", 9, &WithPlanner::<_, SimpleFloorPlanner>::new(decompose()))?;
+    render(
+        dir,
+        options,
+        "decompose-shifted",
+        9,
+        &WithPlanner::<_, ShiftedPlanner<SimpleFloorPlanner, 16>>::new(decompose()),
+    )?;
+    Ok(())
+}
+
+fn render<F: Field, C: Circuit<F>>(
+    dir: &Path,
+    options: &RenderOptions,
+    name: &str,
+    default_k: u32,
+    circuit: &C,
+) -> Result<(), Box<dyn Error>> {
+    let k = options.k.unwrap_or(default_k);
+    // taller images for circuits with a lookup table, so the rows stay legible
+    let size = if k > 6 { (1024, 3096) } else { (1024, 768) };
+
+    let png = dir.join(format!("{}-layout.png", name));
+    draw(BitMapBackend::new(&png, size).into_drawing_area(), options, name, k, circuit)?;
+    let svg = dir.join(format!("{}-layout.svg", name));
+    draw(SVGBackend::new(&svg, size).into_drawing_area(), options, name, k, circuit)
+}
+
+fn draw<F: Field, C: Circuit<F>, DB: DrawingBackend>(
+    root: DrawingArea<DB, plotters::coord::Shift>,
+    options: &RenderOptions,
+    name: &str,
+    k: u32,
+    circuit: &C,
+) -> Result<(), Box<dyn Error>> {
+    root.fill(&WHITE).map_err(|err| err.to_string())?;
+    let root = root
+        .titled(&format!("{} layout", name), ("sans-serif", 60))
+        .map_err(|err| err.to_string())?;
+
+    CircuitLayout::default()
+        .show_labels(options.show_labels)
+        .mark_equality_cells(options.mark_equality_cells)
+        .show_equality_constraints(options.show_equality_constraints)
+        .render(k, circuit, &root)
+        .map_err(|err| err.to_string())?;
+    root.present().map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{render_layouts, RenderOptions};
+
+    #[test]
+    fn test_render_layouts() {
+        let dir = std::env::temp_dir().join("halo2-examples-layouts");
+        render_layouts(&dir, &RenderOptions::default()).unwrap();
+
+        for name in ["fibonaci-example1", "decompose", "is-equal", "decompose-simple"] {
+            assert!(dir.join(format!("{}-layout.png", name)).exists());
+            assert!(dir.join(format!("{}-layout.svg", name)).exists());
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use std::{collections::HashSet, fmt};
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Value,
+    plonk::*,
+};
+
+/// Cells and fixed-column rows that the floor planner assigns outside of any region, i.e.
+/// lookup tables and the constants `V1` places after the regions.
+pub const OUTSIDE_REGIONS: &str = "(outside regions)";
+
+/// The rows and cells used by every region with the same name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionUsage {
+    pub name: String,
+    /// How many times a region with this name was entered.
+    pub count: usize,
+    /// Rows spanned, summed over every region with this name.
+    pub rows: usize,
+    /// Advice and fixed cells assigned.
+    pub cells: usize,
+}
+
+/// The shape of a circuit as laid out by its own floor planner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutStats {
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub selectors: usize,
+    /// Rows from the first to the last one the circuit touches.
+    pub rows: usize,
+    /// Distinct advice and fixed cells assigned.
+    pub cells: usize,
+    pub copies: usize,
+    /// In the order the regions were first entered.
+    pub regions: Vec<RegionUsage>,
+}
+
+impl LayoutStats {
+    /// The share of the advice and fixed cells in `rows` that are assigned.
+    pub fn utilization(&self) -> f64 {
+        let available = self.rows * (self.advice_columns + self.fixed_columns);
+        if available == 0 {
+            return 0.0;
+        }
+        self.cells as f64 / available as f64
+    }
+
+    fn region(&self, name: &str) -> Option<&RegionUsage> {
+        self.regions.iter().find(|region| region.name == name)
+    }
+}
+
+/// Lays `circuit` out without a prover and measures it. Only the shape is recorded, so the
+/// circuit may carry unknown witnesses.
+pub fn layout_stats<F: Field, C: Circuit<F>>(circuit: &C) -> LayoutStats {
+    let mut cs = ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+
+    let mut recorder = LayoutRecorder::default();
+    C::FloorPlanner::synthesize(&mut recorder, circuit, config, cs.constants().clone())
+        .expect("synthesis failed");
+
+    let rows = recorder.max_row.map_or(0, |max| max + 1);
+    LayoutStats {
+        advice_columns: cs.num_advice_columns(),
+        fixed_columns: cs.num_fixed_columns(),
+        instance_columns: cs.num_instance_columns(),
+        selectors: cs.num_selectors(),
+        rows,
+        cells: recorder.cells.len(),
+        copies: recorder.copies,
+        regions: recorder.regions,
+    }
+}
+
+/// Panics unless every region named in `expected` spans the given number of rows, summed
+/// over every region with that name, so a gadget that starts using more rows fails its
+/// tests rather than only showing up in the stats.
+///
+/// ```ignore
+/// assert_region_rows(&circuit, &[("Decompose Region", 9), ("load private", 1)]);
+/// ```
+pub fn assert_region_rows<F: Field, C: Circuit<F>>(circuit: &C, expected: &[(&str, usize)]) {
+    let stats = layout_stats(circuit);
+    let mismatches: Vec<String> = expected
+        .iter()
+        .filter_map(|&(name, rows)| match stats.region(name) {
+            Some(region) if region.rows == rows => None,
+            Some(region) => Some(format!("{:?} spans {} rows, expected {}", name, region.rows, rows)),
+            None => Some(format!("{:?} is not a region of the circuit", name)),
+        })
+        .collect();
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+/// Two circuits' layouts side by side. Displays as a table with one row per column count,
+/// region and total, and the change from `left` to `right`.
+#[derive(Debug, Clone)]
+pub struct LayoutDiff {
+    pub left: (String, LayoutStats),
+    pub right: (String, LayoutStats),
+}
+
+/// Compares the layouts of two circuits, e.g. the single- and multi-column Fibonacci.
+pub fn layout_diff<F: Field, L: Circuit<F>, R: Circuit<F>>(
+    left_name: &str,
+    left: &L,
+    right_name: &str,
+    right: &R,
+) -> LayoutDiff {
+    LayoutDiff {
+        left: (left_name.to_string(), layout_stats(left)),
+        right: (right_name.to_string(), layout_stats(right)),
+    }
+}
+
+impl fmt::Display for LayoutDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (left_name, left) = &self.left;
+        let (right_name, right) = &self.right;
+
+        let mut lines: Vec<(String, usize, usize)> = vec![
+            ("advice columns".to_string(), left.advice_columns, right.advice_columns),
+            ("fixed columns".to_string(), left.fixed_columns, right.fixed_columns),
+            ("instance columns".to_string(), left.instance_columns, right.instance_columns),
+            ("selectors".to_string(), left.selectors, right.selectors),
+        ];
+
+        let mut names: Vec<&str> = left.regions.iter().map(|region| region.name.as_str()).collect();
+        for region in &right.regions {
+            if !names.contains(&region.name.as_str()) {
+                names.push(&region.name);
+            }
+        }
+        for name in names {
+            let rows = |stats: &LayoutStats| stats.region(name).map_or(0, |region| region.rows);
+            let cells = |stats: &LayoutStats| stats.region(name).map_or(0, |region| region.cells);
+            lines.push((format!("{}: rows", name), rows(left), rows(right)));
+            lines.push((format!("{}: cells", name), cells(left), cells(right)));
+        }
+
+        lines.push(("rows".to_string(), left.rows, right.rows));
+        lines.push(("cells".to_string(), left.cells, right.cells));
+        lines.push(("copies".to_string(), left.copies, right.copies));
+
+        let width = lines.iter().map(|(label, _, _)| label.len()).max().unwrap_or(0);
+        let left_width = left_name.len().max(6);
+        let right_width = right_name.len().max(6);
+
+        writeln!(f, "{:width$} | {:>left_width$} | {:>right_width$} | {:>6}", "", left_name, right_name, "diff")?;
+        writeln!(
+            f,
+            "{}-|-{}-|-{}-|-{}",
+            "-".repeat(width),
+            "-".repeat(left_width),
+            "-".repeat(right_width),
+            "-".repeat(6)
+        )?;
+        for (label, l, r) in lines {
+            let diff = r as i64 - l as i64;
+            writeln!(f, "{:width$} | {:>left_width$} | {:>right_width$} | {:>+6}", label, l, r, diff)?;
+        }
+        writeln!(
+            f,
+            "{:width$} | {:>left_width$.1}% | {:>right_width$.1}% |",
+            "utilization",
+            left.utilization() * 100.0,
+            right.utilization() * 100.0,
+            left_width = left_width - 1,
+            right_width = right_width - 1,
+        )
+    }
+}
+
+/// The region currently being assigned, and the rows it has touched so far.
+struct OpenRegion {
+    index: usize,
+    rows: Option<(usize, usize)>,
+}
+
+/// An `Assignment` that keeps no values, only which cells and rows each region uses.
+#[derive(Default)]
+struct LayoutRecorder {
+    regions: Vec<RegionUsage>,
+    current: Option<OpenRegion>,
+    cells: HashSet<(Column<Any>, usize)>,
+    max_row: Option<usize>,
+    copies: usize,
+}
+
+impl LayoutRecorder {
+    fn touch(&mut self, row: usize) {
+        self.max_row = Some(self.max_row.map_or(row, |max| max.max(row)));
+        if let Some(current) = &mut self.current {
+            current.rows = Some(current.rows.map_or((row, row), |(min, max)| (min.min(row), max.max(row))));
+        }
+    }
+
+    fn record_cell(&mut self, column: Column<Any>, row: usize) {
+        self.touch(row);
+        if !self.cells.insert((column, row)) {
+            return;
+        }
+        let index = match &self.current {
+            Some(current) => current.index,
+            None => self.region_index(OUTSIDE_REGIONS),
+        };
+        self.regions[index].cells += 1;
+    }
+
+    fn region_index(&mut self, name: &str) -> usize {
+        match self.regions.iter().position(|region| region.name == name) {
+            Some(index) => index,
+            None => {
+                self.regions.push(RegionUsage {
+                    name: name.to_string(),
+                    count: 0,
+                    rows: 0,
+                    cells: 0,
+                });
+                self.regions.len() - 1
+            }
+        }
+    }
+}
+
+impl<F: Field> Assignment<F> for LayoutRecorder {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let index = self.region_index(&name_fn().into());
+        self.regions[index].count += 1;
+        self.current = Some(OpenRegion { index, rows: None });
+    }
+
+    fn exit_region(&mut self) {
+        if let Some(OpenRegion { index, rows: Some((min, max)) }) = self.current.take() {
+            self.regions[index].rows += max - min + 1;
+        }
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(&mut self, _: A, column: Column<Advice>, row: usize, _: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.record_cell(column.into(), row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(&mut self, _: A, column: Column<Fixed>, row: usize, _: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.record_cell(column.into(), row);
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        self.copies += 1;
+        Ok(())
+    }
+
+    fn fill_from_row(&mut self, _: Column<Fixed>, _: usize, _: Value<Assigned<F>>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[cfg(all(test, feature = "fibonacci", feature = "decompose"))]
+mod test {
+    use std::marker::PhantomData;
+
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    use super::{assert_region_rows, layout_diff, layout_stats, LayoutStats};
+    use crate::{decompose::decompose_range_check::DecomposeRangeCheckCircuit, fibonaci::{example1, example2}};
+
+    #[test]
+    fn test_fibonacci_layout_diff() {
+        let diff = layout_diff(
+            "example1",
+            &example1::FiboCircuit::<Fp>::default(),
+            "example2",
+            &example2::FiboCircuit::<Fp>(PhantomData),
+        );
+        let (_, multi) = &diff.left;
+        let (_, single) = &diff.right;
+
+        // example1 spreads the terms over three columns, example2 stacks all ten in one
+        assert!(multi.advice_columns > single.advice_columns);
+        assert!(multi.rows < single.rows);
+        assert_eq!(single.regions.iter().find(|region| region.name == "fibonaci table").unwrap().rows, 10);
+
+        let table = diff.to_string();
+        assert!(table.contains("fibonaci table: rows"));
+        assert!(table.contains("next row: rows"));
+    }
+
+    #[test]
+    fn test_decompose_layout_stats() {
+        let narrow = layout_stats(&DecomposeRangeCheckCircuit::<Fp, 4, 16> {
+            value: Value::unknown(),
+            num_bits: 64,
+        });
+        let wide = layout_stats(&DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::unknown(),
+            num_bits: 64,
+        });
+
+        // halving the lookup width doubles the running sum, z_0 to z_C
+        let rows = |stats: &LayoutStats| {
+            stats.regions.iter().find(|region| region.name == "Decompose Region").unwrap().rows
+        };
+        assert_eq!(rows(&narrow), 16 + 1);
+        assert_eq!(rows(&wide), 8 + 1);
+        assert_eq!(narrow.advice_columns, wide.advice_columns);
+    }
+
+    #[test]
+    #[should_panic(expected = "\"Decompose Region\" spans 9 rows, expected 17")]
+    fn test_assert_region_rows_mismatch() {
+        let circuit = DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::unknown(),
+            num_bits: 64,
+        };
+        assert_region_rows(&circuit, &[("Decompose Region", 17)]);
+    }
+}
@@ -0,0 +1,320 @@
+use std::{cell::RefCell, marker::PhantomData};
+
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{Layouter, Value},
+    dev::MockProver,
+    plonk::*,
+};
+
+use super::tamper::tamper;
+
+/// A selector the circuit enables, named after the first region that enables it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutant {
+    pub selector: Selector,
+    pub region: Option<String>,
+}
+
+/// The outcome of `mutate`: every selector the circuit enables, and those which could be
+/// switched off without any of the negative cases noticing.
+#[derive(Debug)]
+pub struct MutationReport {
+    pub mutants: Vec<Mutant>,
+    pub survivors: Vec<Mutant>,
+}
+
+#[derive(Default)]
+struct MutateState {
+    target: Option<Selector>,
+    region: Option<String>,
+    seen: Vec<Mutant>,
+}
+
+thread_local! {
+    // As in `tamper`, the floor planner has no access to the wrapper circuit, so the selector
+    // to disable is handed to it through here.
+    static STATE: RefCell<MutateState> = RefCell::new(MutateState::default());
+}
+
+/// Checks every gate of a circuit is load-bearing: re-runs the negative cases once per
+/// selector, each time with that selector never enabled, and reports the selectors whose
+/// removal none of them detects.
+///
+/// The negative cases are the `invalid` circuits, which `MockProver` must reject as they
+/// are, and every tampered witness of the `valid` ones (see `tamper`). A mutant is caught
+/// when an invalid circuit starts to verify, or a tampered cell the full circuit catches
+/// goes through. The tampered witnesses matter for gates an honest witness cannot violate,
+/// such as the lookup on the chunks of a running sum.
+///
+/// A selector is the unit of mutation: gates and lookups behind the same selector are
+/// disabled together, and constraints behind a fixed column instead are left alone.
+pub fn mutate<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    valid: &[(C, Vec<Vec<F>>)],
+    invalid: &[(C, Vec<Vec<F>>)],
+) -> MutationReport {
+    let (uncaught, mutants) = run(None, || {
+        for (circuit, instances) in invalid {
+            let prover = MockProver::run(k, &Mutated(circuit), instances.clone()).expect("synthesis failed");
+            assert!(prover.verify().is_err(), "every invalid circuit must fail verification");
+        }
+        valid
+            .iter()
+            .map(|(circuit, instances)| tamper(k, &Mutated(circuit), instances.clone()).uncaught.len())
+            .collect::<Vec<_>>()
+    });
+
+    let survivors = mutants
+        .iter()
+        .filter(|mutant| {
+            let (caught, _) = run(Some(mutant.selector), || {
+                invalid.iter().any(|(circuit, instances)| {
+                    let prover = MockProver::run(k, &Mutated(circuit), instances.clone()).expect("synthesis failed");
+                    prover.verify().is_ok()
+                }) || valid.iter().zip(&uncaught).any(|((circuit, instances), uncaught)| {
+                    tamper(k, &Mutated(circuit), instances.clone()).uncaught.len() > *uncaught
+                })
+            });
+            !caught
+        })
+        .cloned()
+        .collect();
+
+    MutationReport { mutants, survivors }
+}
+
+/// Panics unless disabling any selector of the circuit lets one of the negative cases
+/// through.
+pub fn assert_gates_load_bearing<F: FieldExt, C: Circuit<F>>(k: u32, valid: &[(C, Vec<Vec<F>>)], invalid: &[(C, Vec<Vec<F>>)]) {
+    let report = mutate(k, valid, invalid);
+    assert!(
+        report.survivors.is_empty(),
+        "{} of {} selectors can be disabled without any negative case passing: {:#?}",
+        report.survivors.len(),
+        report.mutants.len(),
+        report.survivors
+    );
+}
+
+// Runs `f` with `target` disabled, returning what it returned and the selectors enabled
+// meanwhile, without duplicates.
+fn run<T>(target: Option<Selector>, f: impl FnOnce() -> T) -> (T, Vec<Mutant>) {
+    STATE.with(|state| {
+        *state.borrow_mut() = MutateState {
+            target,
+            ..Default::default()
+        }
+    });
+    let result = f();
+    let state = STATE.with(|state| std::mem::take(&mut *state.borrow_mut()));
+    (result, state.seen)
+}
+
+/// Wraps a circuit so that it is laid out by `MutatePlanner`.
+///
+/// Like `tamper::Tampered`, the wrapper is only ever run through `MockProver`.
+struct Mutated<'c, C>(&'c C);
+
+impl<'c, F: Field, C: Circuit<F>> Circuit<F> for Mutated<'c, C> {
+    type Config = C::Config;
+    type FloorPlanner = MutatePlanner<C::FloorPlanner>;
+
+    fn without_witnesses(&self) -> Self {
+        Mutated(self.0)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+/// Runs the circuit's own floor planner against a `MutateAssignment`.
+struct MutatePlanner<P>(PhantomData<P>);
+
+impl<P: FloorPlanner> FloorPlanner for MutatePlanner<P> {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let mut cs = MutateAssignment { cs };
+        P::synthesize(&mut cs, circuit, config, constants)
+    }
+}
+
+/// Forwards everything to the wrapped `Assignment`, except that the selector chosen in
+/// `STATE` is never enabled.
+struct MutateAssignment<'a, CS> {
+    cs: &'a mut CS,
+}
+
+impl<'a, F: Field, CS: Assignment<F>> Assignment<F> for MutateAssignment<'a, CS> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let name: String = name_fn().into();
+        STATE.with(|state| state.borrow_mut().region = Some(name.clone()));
+        self.cs.enter_region(|| name);
+    }
+
+    fn exit_region(&mut self) {
+        STATE.with(|state| state.borrow_mut().region = None);
+        self.cs.exit_region();
+    }
+
+    fn enable_selector<A, AR>(&mut self, annotation: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let is_target = STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            if !state.seen.iter().any(|mutant| mutant.selector == *selector) {
+                let region = state.region.clone();
+                state.seen.push(Mutant {
+                    selector: *selector,
+                    region,
+                });
+            }
+            state.target == Some(*selector)
+        });
+        if is_target {
+            return Ok(());
+        }
+        self.cs.enable_selector(annotation, selector, row)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.assign_advice(annotation, column, row, to)
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.assign_fixed(annotation, column, row, to)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(&mut self, column: Column<Fixed>, row: usize, to: Value<Assigned<F>>) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}
+
+#[cfg(all(test, feature = "range-check", feature = "decompose"))]
+mod test {
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, pasta::Fp};
+
+    use super::{assert_gates_load_bearing, mutate};
+    use crate::{
+        decompose::{decompose_range_check::DecomposeRangeCheckCircuit, runtime::RuntimeDecomposeCircuit},
+        params::CircuitParams,
+        range_check::{example1, example2, dynamic::DynamicRangeCheckCircuit},
+    };
+
+    #[test]
+    fn test_mutate_range_check1() {
+        let circuit = |value: u64| example1::RangeCheckCircuit::<Fp, 8> {
+            value: Value::known(Fp::from(value).into()),
+        };
+        assert_gates_load_bearing(4, &[(circuit(3), vec![])], &[(circuit(8), vec![])]);
+    }
+
+    #[test]
+    fn test_mutate_range_check2() {
+        let circuit = |value: u64, lookup_value: u64| example2::RangeCheckCircuit::<Fp, 8, 4> {
+            value: Value::known(Fp::from(value).into()),
+            lookup_value: Value::known(Fp::from(lookup_value).into()),
+        };
+        let report = mutate(9, &[(circuit(3, 12), vec![])], &[(circuit(8, 12), vec![]), (circuit(3, 16), vec![])]);
+        assert_eq!(report.mutants.len(), 2);
+        assert!(report.survivors.is_empty(), "{:#?}", report.survivors);
+
+        // a case out of both ranges tells neither check is needed on its own
+        let report = mutate(9, &[], &[(circuit(8, 16), vec![])]);
+        assert_eq!(report.survivors.len(), 2);
+    }
+
+    #[test]
+    fn test_mutate_dynamic_range_check() {
+        let circuit = |values: &[(u64, usize)]| DynamicRangeCheckCircuit::<Fp, 8> {
+            values: values.iter().map(|&(v, range)| (Value::known(Fp::from(v).into()), range)).collect(),
+        };
+        assert_gates_load_bearing(9, &[(circuit(&[(7, 8), (99, 100)]), vec![])], &[(circuit(&[(7, 8), (8, 8)]), vec![])]);
+    }
+
+    #[test]
+    fn test_mutate_decompose() {
+        // An honest running sum of a value that is too wide only breaks `z_C == 0`, so the
+        // chunk lookup is load-bearing through the tampered witnesses alone.
+        let circuit = |value: u128| DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::known(Fp::from_u128(value).into()),
+            num_bits: 64,
+        };
+        let valid = [(circuit(rand::random::<u64>() as u128), vec![])];
+        let invalid = [(circuit(1 << 64), vec![])];
+        assert_gates_load_bearing(9, &valid, &invalid);
+        assert_eq!(mutate(9, &[], &invalid).survivors.len(), 1);
+
+        let params = CircuitParams::default().decompose;
+        let circuit = |value: u64| RuntimeDecomposeCircuit::<Fp> {
+            value: Value::known(Fp::from(value).into()),
+            params: params.clone(),
+        };
+        assert_gates_load_bearing(params.k, &[(circuit(rand::random()), vec![])], &[]);
+    }
+}
@@ -0,0 +1,310 @@
+use std::fmt;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Value,
+    plonk::*,
+};
+
+/// The rotations a gate queries, relative to the row its selector is enabled on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GateRotations {
+    pub name: String,
+    /// The selectors the gate's constraints are multiplied by. A gate switched on by a fixed
+    /// column instead has none, and is not checked by `wrapping_queries`.
+    pub selectors: Vec<Selector>,
+    /// The lowest and highest rotation of any advice, fixed or instance query, e.g. `(0, 2)`
+    /// for a gate over `cur`, `next` and `Rotation(2)`.
+    pub min: i32,
+    pub max: i32,
+}
+
+impl GateRotations {
+    /// The rows out of `usable_rows` the gate cannot be enabled on, as its queries would
+    /// reach before row 0 or into the blinding rows, which wrap around: the first `-min`
+    /// rows and the last `max`.
+    pub fn unusable_rows(&self, usable_rows: usize) -> Vec<usize> {
+        let before = (-self.min).max(0) as usize;
+        let after = self.max.max(0) as usize;
+        (0..usable_rows)
+            .filter(|&row| row < before || row + after >= usable_rows)
+            .collect()
+    }
+}
+
+/// The rotation span of every gate of a circuit, and the rows the prover may use at `k`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotationReport {
+    pub k: u32,
+    /// `2^k` minus the blinding rows and the row `MockProver` keeps for the last
+    /// permutation product.
+    pub usable_rows: usize,
+    pub gates: Vec<GateRotations>,
+}
+
+impl fmt::Display for RotationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "k = {}: {} usable rows", self.k, self.usable_rows)?;
+        for gate in &self.gates {
+            write!(f, "gate {:?}: rotations {:+}..={:+}", gate.name, gate.min, gate.max)?;
+            match gate.unusable_rows(self.usable_rows)[..] {
+                [] => writeln!(f)?,
+                ref rows => writeln!(f, ", cannot be enabled on rows {:?}", rows)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the rotations of every gate from the circuit's `configure`.
+pub fn rotation_report<F: Field, C: Circuit<F>>(k: u32) -> RotationReport {
+    let mut cs = ConstraintSystem::<F>::default();
+    C::configure(&mut cs);
+    RotationReport {
+        k,
+        usable_rows: usable_rows(&cs, k),
+        gates: gate_rotations(&cs),
+    }
+}
+
+fn usable_rows<F: Field>(cs: &ConstraintSystem<F>, k: u32) -> usize {
+    (1usize << k).saturating_sub(cs.blinding_factors() + 1)
+}
+
+fn gate_rotations<F: Field>(cs: &ConstraintSystem<F>) -> Vec<GateRotations> {
+    cs.gates()
+        .iter()
+        .map(|gate| {
+            let mut selectors = vec![];
+            let mut span: Option<(i32, i32)> = None;
+            for polynomial in gate.polynomials() {
+                walk(polynomial, &mut selectors, &mut span);
+            }
+            let (min, max) = span.unwrap_or((0, 0));
+            GateRotations {
+                name: gate.name().to_string(),
+                selectors,
+                min,
+                max,
+            }
+        })
+        .collect()
+}
+
+fn walk<F: Field>(expr: &Expression<F>, selectors: &mut Vec<Selector>, span: &mut Option<(i32, i32)>) {
+    match expr {
+        Expression::Constant(_) => {}
+        Expression::Selector(selector) => {
+            if !selectors.contains(selector) {
+                selectors.push(*selector);
+            }
+        }
+        Expression::Fixed { rotation, .. } | Expression::Advice { rotation, .. } | Expression::Instance { rotation, .. } => {
+            let rotation = rotation.0;
+            *span = Some(span.map_or((rotation, rotation), |(min, max)| (min.min(rotation), max.max(rotation))));
+        }
+        Expression::Negated(inner) | Expression::Scaled(inner, _) => walk(inner, selectors, span),
+        Expression::Sum(left, right) | Expression::Product(left, right) => {
+            walk(left, selectors, span);
+            walk(right, selectors, span);
+        }
+    }
+}
+
+/// A selector enabled on a row where its gate queries a row that wraps around.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrappingQuery {
+    pub gate: String,
+    pub region: Option<String>,
+    pub row: usize,
+    /// The offending row, negative or past the usable rows.
+    pub queried: i64,
+}
+
+/// Lays `circuit` out without a prover and lists every enabled selector whose gate queries
+/// outside the usable rows at `k`. Such a gate does not fail: the query silently wraps to
+/// the other end of the column, or lands on a blinding row, so the constraint checks
+/// whatever happens to be there.
+pub fn wrapping_queries<F: Field, C: Circuit<F>>(k: u32, circuit: &C) -> Vec<WrappingQuery> {
+    let mut cs = ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+    let usable_rows = usable_rows(&cs, k) as i64;
+    let gates = gate_rotations(&cs);
+
+    let mut recorder = SelectorRecorder::default();
+    C::FloorPlanner::synthesize(&mut recorder, circuit, config, cs.constants().clone())
+        .expect("synthesis failed");
+
+    let mut wrapping = vec![];
+    for (selector, region, row) in recorder.enabled {
+        for gate in gates.iter().filter(|gate| gate.selectors.contains(&selector)) {
+            let (first, last) = (row as i64 + gate.min as i64, row as i64 + gate.max as i64);
+            let queried = match (first < 0, last >= usable_rows) {
+                (true, _) => first,
+                (false, true) => last,
+                (false, false) => continue,
+            };
+            wrapping.push(WrappingQuery {
+                gate: gate.name.clone(),
+                region: region.clone(),
+                row,
+                queried,
+            });
+        }
+    }
+    wrapping
+}
+
+/// Panics if any selector of `circuit` is enabled where its gate's queries wrap at `k`.
+pub fn assert_no_wrapping_queries<F: Field, C: Circuit<F>>(k: u32, circuit: &C) {
+    let wrapping = wrapping_queries(k, circuit);
+    assert!(
+        wrapping.is_empty(),
+        "{} selectors are enabled where their gate's queries wrap:\n{}{:#?}",
+        wrapping.len(),
+        rotation_report::<F, C>(k),
+        wrapping
+    );
+}
+
+/// An `Assignment` that keeps no values, only which selectors are enabled where.
+#[derive(Default)]
+struct SelectorRecorder {
+    region: Option<String>,
+    enabled: Vec<(Selector, Option<String>, usize)>,
+}
+
+impl<F: Field> Assignment<F> for SelectorRecorder {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.region = Some(name_fn().into());
+    }
+
+    fn exit_region(&mut self) {
+        self.region = None;
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.enabled.push((*selector, self.region.clone(), row));
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(&mut self, _: A, _: Column<Advice>, _: usize, _: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(&mut self, _: A, _: Column<Fixed>, _: usize, _: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(&mut self, _: Column<Fixed>, _: usize, _: Value<Assigned<F>>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        arithmetic::Field,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Selector},
+        poly::Rotation,
+    };
+
+    use super::{assert_no_wrapping_queries, rotation_report, wrapping_queries};
+
+    /// `a_i = a_{i-1} + 1` on the rows given, which the first row of the circuit cannot be.
+    struct CountCircuit {
+        rows: Vec<usize>,
+    }
+
+    impl Circuit<Fp> for CountCircuit {
+        type Config = (Column<Advice>, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { rows: self.rows.clone() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let (a, q) = (meta.advice_column(), meta.selector());
+            meta.create_gate("count", |meta| {
+                let q = meta.query_selector(q);
+                let prev = meta.query_advice(a, Rotation::prev());
+                let a = meta.query_advice(a, Rotation::cur());
+                vec![q * (a - prev - Expression::Constant(Fp::one()))]
+            });
+            (a, q)
+        }
+
+        fn synthesize(&self, (a, q): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            layouter.assign_region(
+                || "count",
+                |mut region| {
+                    for row in 0..4 {
+                        region.assign_advice(|| "a", a, row, || Value::known(Fp::from(row as u64)))?;
+                    }
+                    for &row in &self.rows {
+                        q.enable(&mut region, row)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_rotation_report() {
+        let report = rotation_report::<Fp, CountCircuit>(4);
+        assert_eq!((report.gates[0].min, report.gates[0].max), (-1, 0));
+        assert_eq!(report.gates[0].unusable_rows(report.usable_rows), vec![0]);
+        assert!(report.to_string().contains("gate \"count\": rotations -1..=+0, cannot be enabled on rows [0]"));
+    }
+
+    #[test]
+    fn test_wrapping_queries() {
+        assert_no_wrapping_queries(4, &CountCircuit { rows: vec![1, 2, 3] });
+
+        let wrapping = wrapping_queries(4, &CountCircuit { rows: vec![0, 1] });
+        assert_eq!(wrapping.len(), 1);
+        assert_eq!((wrapping[0].row, wrapping[0].queried), (0, -1));
+        assert_eq!(wrapping[0].region.as_deref(), Some("count"));
+    }
+}
@@ -0,0 +1,236 @@
+//! Size and cost of every example circuit, as a Markdown or CSV table.
+//!
+//! `cargo run --bin stats [-- --csv]` prints the table, and `tests/stats.rs` compares the
+//! sizes (everything but the timings) against the snapshot in `tests/stats.csv`.
+
+use std::{
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use halo2_proofs::{
+    circuit::Value,
+    dev::MockProver,
+    pasta::Fp,
+    plonk::{Circuit, ConstraintSystem},
+};
+
+use super::layout_diff::layout_stats;
+use crate::{
+    decompose::{decompose_range_check::DecomposeRangeCheckCircuit, runtime::RuntimeDecomposeCircuit},
+    fibonaci::{example1, example2, runtime::RuntimeFiboCircuit},
+    gadgets::is_equal::IsEqualCircuit,
+    params::CircuitParams,
+    prover::{prove, setup, verify},
+    range_check::{self, dynamic::DynamicRangeCheckCircuit, tagged::TaggedRangeCheckCircuit},
+    reference::ref_fibonacci,
+};
+
+/// The largest k `measure` tries before giving up on a circuit.
+pub const MAX_K: u32 = 16;
+
+#[derive(Debug, Clone)]
+pub struct CircuitStats {
+    pub name: &'static str,
+    /// The smallest k at which `MockProver` accepts the circuit.
+    pub k: u32,
+    /// Rows the layout touches, out of `2^k`.
+    pub rows: usize,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub gates: usize,
+    pub lookups: usize,
+    /// Bytes in a proof at `k`.
+    pub proof_size: usize,
+    pub prove_time: Duration,
+    pub verify_time: Duration,
+}
+
+/// The smallest k at which `MockProver` accepts `circuit`.
+///
+/// Panics if the circuit is not satisfied at any k up to `MAX_K`.
+pub fn min_k<C: Circuit<Fp>>(name: &str, circuit: &C, instances: &[Vec<Fp>]) -> u32 {
+    (1..=MAX_K)
+        .find(|&k| MockProver::run(k, circuit, instances.to_vec()).is_ok_and(|prover| prover.verify().is_ok()))
+        .unwrap_or_else(|| panic!("{} is not satisfied at any k up to {}", name, MAX_K))
+}
+
+/// Measures `circuit` at its smallest k, including a real proof and its verification.
+///
+/// Panics if the circuit is not satisfied at any k up to `MAX_K`, or if proving fails.
+pub fn measure<C: Circuit<Fp>>(name: &'static str, circuit: &C, instances: Vec<Vec<Fp>>) -> CircuitStats {
+    let k = min_k(name, circuit, &instances);
+
+    let mut cs = ConstraintSystem::default();
+    C::configure(&mut cs);
+
+    let (params, pk) = setup(k, circuit).expect("keygen failed");
+
+    let start = Instant::now();
+    let proof = prove(&params, &pk, circuit, &instances).expect("proving failed");
+    let prove_time = start.elapsed();
+
+    let start = Instant::now();
+    verify(&params, pk.get_vk(), &instances, &proof).expect("verification failed");
+    let verify_time = start.elapsed();
+
+    CircuitStats {
+        name,
+        k,
+        rows: layout_stats(circuit).rows,
+        advice_columns: cs.num_advice_columns(),
+        fixed_columns: cs.num_fixed_columns(),
+        instance_columns: cs.num_instance_columns(),
+        gates: cs.gates().len(),
+        lookups: cs.lookups().len(),
+        proof_size: proof.len(),
+        prove_time,
+        verify_time,
+    }
+}
+
+/// Something done to every example circuit, e.g. measuring it.
+pub trait ExampleVisitor {
+    fn visit<C: Circuit<Fp>>(&mut self, name: &'static str, circuit: &C, instances: Vec<Vec<Fp>>);
+}
+
+/// Measures every example circuit with a fixed, valid witness.
+pub fn examples() -> Vec<CircuitStats> {
+    struct Measure(Vec<CircuitStats>);
+
+    impl ExampleVisitor for Measure {
+        fn visit<C: Circuit<Fp>>(&mut self, name: &'static str, circuit: &C, instances: Vec<Vec<Fp>>) {
+            self.0.push(measure(name, circuit, instances));
+        }
+    }
+
+    let mut stats = Measure(vec![]);
+    visit_examples(&mut stats);
+    stats.0
+}
+
+/// Hands every example circuit, with a fixed, valid witness, to `visitor`.
+pub fn visit_examples(visitor: &mut impl ExampleVisitor) {
+    let params = CircuitParams::default();
+    let (a, b) = (Fp::from(1), Fp::from(1));
+    let fibonacci_instances = vec![vec![a, b, ref_fibonacci(a, b, 10)]];
+
+    visitor.visit(
+        "fibonaci example1",
+        &example1::FiboCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        },
+        fibonacci_instances.clone(),
+    );
+    visitor.visit("fibonaci example2", &example2::FiboCircuit::<Fp>(PhantomData), fibonacci_instances.clone());
+    visitor.visit(
+        "fibonaci runtime",
+        &RuntimeFiboCircuit::<Fp>::new(params.fibonacci),
+        fibonacci_instances,
+    );
+    visitor.visit(
+        "range check example1",
+        &range_check::example1::RangeCheckCircuit::<Fp, 8> {
+            value: Value::known(Fp::from(3).into()),
+        },
+        vec![],
+    );
+    visitor.visit(
+        "range check example2",
+        &range_check::example2::RangeCheckCircuit::<Fp, 8, 4> {
+            value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(12).into()),
+        },
+        vec![],
+    );
+    visitor.visit(
+        "range check tagged",
+        &TaggedRangeCheckCircuit::<Fp, 8, 4> {
+            value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(12).into()),
+        },
+        vec![],
+    );
+    visitor.visit(
+        "range check dynamic",
+        &DynamicRangeCheckCircuit::<Fp, 8> {
+            values: vec![(Value::known(Fp::from(99).into()), 100), (Value::known(Fp::from(7).into()), 8)],
+        },
+        vec![],
+    );
+    visitor.visit(
+        "decompose",
+        &DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::known(Fp::from(0x1234_5678_9abc_def0u64).into()),
+            num_bits: 64,
+        },
+        vec![],
+    );
+    visitor.visit(
+        "decompose runtime",
+        &RuntimeDecomposeCircuit::<Fp> {
+            value: Value::known(Fp::from(0x1234_5678_9abc_def0u64).into()),
+            params: params.decompose,
+        },
+        vec![],
+    );
+    visitor.visit(
+        "is equal",
+        &IsEqualCircuit {
+            a: Value::known(Fp::from(42)),
+            b: Value::known(Fp::from(42)),
+        },
+        vec![],
+    );
+}
+
+const HEADER: [&str; 9] = ["circuit", "k", "rows", "advice", "fixed", "instance", "gates", "lookups", "proof bytes"];
+const TIMINGS: [&str; 2] = ["prove ms", "verify ms"];
+
+fn cells(stats: &CircuitStats, timings: bool) -> Vec<String> {
+    let mut cells = vec![
+        stats.name.to_string(),
+        stats.k.to_string(),
+        stats.rows.to_string(),
+        stats.advice_columns.to_string(),
+        stats.fixed_columns.to_string(),
+        stats.instance_columns.to_string(),
+        stats.gates.to_string(),
+        stats.lookups.to_string(),
+        stats.proof_size.to_string(),
+    ];
+    if timings {
+        cells.push(stats.prove_time.as_millis().to_string());
+        cells.push(stats.verify_time.as_millis().to_string());
+    }
+    cells
+}
+
+fn header(timings: bool) -> Vec<&'static str> {
+    let mut header = HEADER.to_vec();
+    if timings {
+        header.extend(TIMINGS);
+    }
+    header
+}
+
+/// The stats as a Markdown table. Timings vary from run to run, so they are optional.
+pub fn to_markdown(stats: &[CircuitStats], timings: bool) -> String {
+    let header = header(timings);
+    let mut table = format!("| {} |\n|{}\n", header.join(" | "), " --- |".repeat(header.len()));
+    for stats in stats {
+        table.push_str(&format!("| {} |\n", cells(stats, timings).join(" | ")));
+    }
+    table
+}
+
+/// The stats as CSV, one line per circuit after the header.
+pub fn to_csv(stats: &[CircuitStats], timings: bool) -> String {
+    let mut csv = format!("{}\n", header(timings).join(","));
+    for stats in stats {
+        csv.push_str(&format!("{}\n", cells(stats, timings).join(",")));
+    }
+    csv
+}
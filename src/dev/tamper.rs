@@ -0,0 +1,359 @@
+use std::{cell::RefCell, marker::PhantomData};
+
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{Layouter, Value},
+    dev::MockProver,
+    plonk::*,
+};
+
+/// An advice cell that was perturbed during a tampered run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TamperedCell {
+    pub region: Option<String>,
+    pub annotation: String,
+    pub column: usize,
+    pub row: usize,
+}
+
+/// The outcome of `tamper`: how many advice assignments the circuit makes, and which of
+/// them could be changed without `MockProver` noticing.
+#[derive(Debug)]
+pub struct TamperReport {
+    pub assignments: usize,
+    pub uncaught: Vec<TamperedCell>,
+}
+
+#[derive(Default)]
+struct TamperState {
+    target: Option<usize>,
+    seen: usize,
+    tampered: Option<TamperedCell>,
+}
+
+thread_local! {
+    // `FloorPlanner::synthesize` has no access to the wrapper circuit, so the run's target
+    // is handed to the planner through here.
+    static STATE: RefCell<TamperState> = RefCell::new(TamperState::default());
+}
+
+/// Soundness check for a circuit: re-runs `MockProver` once per advice assignment, each
+/// time adding one to the value of that single cell, and reports every run that still
+/// verifies.
+///
+/// The untampered circuit must be satisfied. A cell that shows up in `uncaught` is either
+/// an under-constrained witness or a hint the relation legitimately leaves free (such as
+/// the inverse in `IsZero` when the value is zero); the caller decides which.
+pub fn tamper<F: FieldExt, C: Circuit<F>>(k: u32, circuit: &C, instances: Vec<Vec<F>>) -> TamperReport {
+    let (verified, assignments, _) = run(k, circuit, &instances, None);
+    assert!(verified, "the untampered circuit must be satisfied");
+
+    let uncaught = (0..assignments)
+        .filter_map(|target| match run(k, circuit, &instances, Some(target)) {
+            (true, _, tampered) => tampered,
+            (false, _, _) => None,
+        })
+        .collect();
+
+    TamperReport { assignments, uncaught }
+}
+
+/// Panics unless every advice assignment of `circuit` is pinned down by its constraints.
+pub fn assert_tamper_caught<F: FieldExt, C: Circuit<F>>(k: u32, circuit: &C, instances: Vec<Vec<F>>) {
+    let report = tamper(k, circuit, instances);
+    assert!(
+        report.uncaught.is_empty(),
+        "{} of {} advice assignments can be changed without failing verification: {:#?}",
+        report.uncaught.len(),
+        report.assignments,
+        report.uncaught
+    );
+}
+
+fn run<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instances: &[Vec<F>],
+    target: Option<usize>,
+) -> (bool, usize, Option<TamperedCell>) {
+    STATE.with(|state| {
+        *state.borrow_mut() = TamperState {
+            target,
+            ..Default::default()
+        }
+    });
+
+    let prover = MockProver::run(k, &Tampered(circuit), instances.to_vec()).expect("synthesis failed");
+    let verified = prover.verify().is_ok();
+
+    let state = STATE.with(|state| std::mem::take(&mut *state.borrow_mut()));
+    (verified, state.seen, state.tampered)
+}
+
+/// Wraps a circuit so that it is laid out by `TamperPlanner`.
+///
+/// `without_witnesses` keeps the wrapped circuit as is; the wrapper is only ever run through
+/// `MockProver`, never through keygen.
+struct Tampered<'c, C>(&'c C);
+
+impl<'c, F: Field, C: Circuit<F>> Circuit<F> for Tampered<'c, C> {
+    type Config = C::Config;
+    type FloorPlanner = TamperPlanner<C::FloorPlanner>;
+
+    fn without_witnesses(&self) -> Self {
+        Tampered(self.0)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+/// Runs the circuit's own floor planner against a `TamperAssignment`.
+struct TamperPlanner<P>(PhantomData<P>);
+
+impl<P: FloorPlanner> FloorPlanner for TamperPlanner<P> {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let mut cs = TamperAssignment { cs, region: None };
+        P::synthesize(&mut cs, circuit, config, constants)
+    }
+}
+
+/// Forwards everything to the wrapped `Assignment`, except that the advice assignment
+/// selected in `STATE` is shifted by one.
+struct TamperAssignment<'a, CS> {
+    cs: &'a mut CS,
+    region: Option<String>,
+}
+
+impl<'a, F: Field, CS: Assignment<F>> Assignment<F> for TamperAssignment<'a, CS> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let name: String = name_fn().into();
+        self.region = Some(name.clone());
+        self.cs.enter_region(|| name);
+    }
+
+    fn exit_region(&mut self) {
+        self.region = None;
+        self.cs.exit_region();
+    }
+
+    fn enable_selector<A, AR>(&mut self, annotation: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.enable_selector(annotation, selector, row)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let is_target = STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.seen += 1;
+            state.target == Some(state.seen - 1)
+        });
+        if !is_target {
+            return self.cs.assign_advice(annotation, column, row, to);
+        }
+
+        let annotation: String = annotation().into();
+        let tampered = TamperedCell {
+            region: self.region.clone(),
+            annotation: annotation.clone(),
+            column: column.index(),
+            row,
+        };
+        STATE.with(|state| state.borrow_mut().tampered = Some(tampered));
+
+        self.cs.assign_advice(
+            || annotation,
+            column,
+            row,
+            || {
+                to().map(|v| {
+                    let v: Assigned<F> = v.into();
+                    v + Assigned::from(F::one())
+                })
+            },
+        )
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.assign_fixed(annotation, column, row, to)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(&mut self, column: Column<Fixed>, row: usize, to: Value<Assigned<F>>) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}
+
+#[cfg(all(test, feature = "fibonacci", feature = "range-check", feature = "hash"))]
+mod test {
+    use std::marker::PhantomData;
+
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    use super::{assert_tamper_caught, tamper};
+    use crate::{
+        decompose::{decompose_range_check::DecomposeRangeCheckCircuit, runtime::RuntimeDecomposeCircuit},
+        fibonaci::{example1, example2, runtime::RuntimeFiboCircuit},
+        gadgets::{bits::BitsCircuit, byte_order::ByteSwapCircuit, is_equal::IsEqualCircuit, multiset::MultisetEqCircuit},
+        params::CircuitParams,
+        range_check::{self, dynamic::DynamicRangeCheckCircuit},
+    };
+
+    fn fibonacci_instances() -> Vec<Vec<Fp>> {
+        vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]]
+    }
+
+    #[test]
+    fn test_tamper_fibonacci() {
+        let circuit = example1::FiboCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        assert_tamper_caught(4, &circuit, fibonacci_instances());
+        assert_tamper_caught(4, &example2::FiboCircuit::<Fp>(PhantomData), fibonacci_instances());
+
+        let params = CircuitParams::default().fibonacci;
+        assert_tamper_caught(params.k, &RuntimeFiboCircuit::<Fp>::new(params), fibonacci_instances());
+    }
+
+    #[test]
+    fn test_tamper_range_checks() {
+        let circuit = range_check::example1::RangeCheckCircuit::<Fp, 8> {
+            value: Value::known(Fp::from(3).into()),
+        };
+        assert_tamper_caught(4, &circuit, vec![]);
+
+        let circuit = range_check::example2::RangeCheckCircuit::<Fp, 8, 4> {
+            value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(12).into()),
+        };
+        assert_tamper_caught(9, &circuit, vec![]);
+
+        let circuit = DynamicRangeCheckCircuit::<Fp, 8> {
+            values: vec![(Value::known(Fp::from(99).into()), 100), (Value::known(Fp::from(7).into()), 8)],
+        };
+        assert_tamper_caught(9, &circuit, vec![]);
+    }
+
+    #[test]
+    fn test_tamper_decompose() {
+        let circuit = DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::known(Fp::from(rand::random::<u64>()).into()),
+            num_bits: 64,
+        };
+        assert_tamper_caught(9, &circuit, vec![]);
+
+        let params = CircuitParams::default().decompose;
+        let circuit = RuntimeDecomposeCircuit::<Fp> {
+            value: Value::known(Fp::from(rand::random::<u64>()).into()),
+            params: params.clone(),
+        };
+        assert_tamper_caught(params.k, &circuit, vec![]);
+    }
+
+    #[test]
+    fn test_tamper_bits() {
+        let circuit = BitsCircuit {
+            value: Value::known(Fp::from(0xbeef)),
+            num_bits: 16,
+        };
+        assert_tamper_caught(6, &circuit, vec![vec![Fp::from(0xbeef)]]);
+    }
+
+    #[test]
+    fn test_tamper_byte_swap() {
+        let circuit = ByteSwapCircuit {
+            value: Value::known(Fp::from(0xbeef)),
+            num_bytes: 2,
+        };
+        assert_tamper_caught(9, &circuit, vec![vec![Fp::from(0xefbe)]]);
+    }
+
+    #[test]
+    fn test_tamper_multiset_eq() {
+        let circuit = MultisetEqCircuit {
+            a: vec![Value::known(Fp::from(3)), Value::known(Fp::from(5))],
+            b: vec![Value::known(Fp::from(5)), Value::known(Fp::from(3))],
+        };
+        assert_tamper_caught(9, &circuit, vec![vec![]]);
+    }
+
+    #[test]
+    fn test_tamper_is_equal() {
+        // When a == b the difference is zero and IsZero accepts any inverse, so the inverse
+        // hint is the one cell that is legitimately free.
+        let circuit = IsEqualCircuit {
+            a: Value::known(Fp::from(42)),
+            b: Value::known(Fp::from(42)),
+        };
+        let report = tamper(4, &circuit, vec![]);
+        let uncaught: Vec<_> = report.uncaught.iter().map(|cell| cell.annotation.as_str()).collect();
+        assert_eq!(uncaught, vec!["value invert"]);
+    }
+}
@@ -0,0 +1,277 @@
+use std::{any, cell::RefCell, fmt, marker::PhantomData};
+
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{Layouter, Value},
+    dev::MockProver,
+    plonk::*,
+};
+
+/// An advice or fixed cell as the circuit assigned it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedCell<F> {
+    pub region: Option<String>,
+    pub annotation: String,
+    pub column: Column<Any>,
+    pub row: usize,
+    pub value: F,
+}
+
+/// Every advice and fixed assignment of a `MockProver` run, in the order the circuit made
+/// them. Lookup tables are not included.
+#[derive(Debug, Clone)]
+pub struct Trace<F> {
+    pub cells: Vec<TracedCell<F>>,
+}
+
+impl<F: Field> Trace<F> {
+    /// The cells assigned in the region named `region`.
+    pub fn region<'t>(&'t self, region: &'t str) -> impl Iterator<Item = &'t TracedCell<F>> + 't {
+        self.cells.iter().filter(move |cell| cell.region.as_deref() == Some(region))
+    }
+
+    /// The value last assigned under `annotation` in the region named `region`.
+    ///
+    /// A name used by several regions, such as a chip's region called once per value,
+    /// resolves to the last of them.
+    pub fn value(&self, region: &str, annotation: &str) -> Option<F> {
+        self.region(region)
+            .filter(|cell| cell.annotation == annotation)
+            .last()
+            .map(|cell| cell.value)
+    }
+
+    /// The values of `column` in the region named `region`, by row.
+    pub fn column(&self, region: &str, column: impl Into<Column<Any>>) -> Vec<F> {
+        let column = column.into();
+        let mut cells: Vec<_> = self.region(region).filter(|cell| cell.column == column).collect();
+        cells.sort_by_key(|cell| cell.row);
+        cells.into_iter().map(|cell| cell.value).collect()
+    }
+}
+
+impl<F: fmt::Debug> fmt::Display for Trace<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut region = None;
+        for cell in &self.cells {
+            if cell.region != region {
+                region = cell.region.clone();
+                writeln!(f, "{}", region.as_deref().unwrap_or("(outside any region)"))?;
+            }
+            let column = match cell.column.column_type() {
+                Any::Advice => "advice",
+                Any::Fixed => "fixed",
+                Any::Instance => "instance",
+            };
+            writeln!(f, "  {} {} row {}: {} = {:?}", column, cell.column.index(), cell.row, cell.annotation, cell.value)?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    // `FloorPlanner::synthesize` cannot hand anything back to `trace`, so the cells are
+    // collected here, with their values type-erased as a thread local cannot be generic.
+    static CELLS: RefCell<Vec<TracedCell<Box<dyn any::Any>>>> = RefCell::new(vec![]);
+}
+
+/// Runs `circuit` through `MockProver` and records every advice and fixed assignment, so a
+/// test can check intermediate values, e.g. the `z_i` of a running sum, rather than only
+/// whether the circuit verifies. The circuit need not be satisfied.
+pub fn trace<F: FieldExt, C: Circuit<F>>(k: u32, circuit: &C, instances: Vec<Vec<F>>) -> Trace<F> {
+    CELLS.with(|cells| cells.borrow_mut().clear());
+    MockProver::run(k, &Traced(circuit), instances).expect("synthesis failed");
+    let cells = CELLS.with(|cells| std::mem::take(&mut *cells.borrow_mut()));
+
+    let cells = cells
+        .into_iter()
+        .map(|cell| TracedCell {
+            region: cell.region,
+            annotation: cell.annotation,
+            column: cell.column,
+            row: cell.row,
+            value: *cell.value.downcast::<F>().expect("traced over the same field"),
+        })
+        .collect();
+    Trace { cells }
+}
+
+/// Wraps a circuit so that it is laid out by `TracePlanner`.
+///
+/// Like `tamper::Tampered`, the wrapper is only ever run through `MockProver`.
+struct Traced<'c, C>(&'c C);
+
+impl<'c, F: Field, C: Circuit<F>> Circuit<F> for Traced<'c, C> {
+    type Config = C::Config;
+    type FloorPlanner = TracePlanner<C::FloorPlanner>;
+
+    fn without_witnesses(&self) -> Self {
+        Traced(self.0)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+/// Runs the circuit's own floor planner against a `TraceAssignment`.
+struct TracePlanner<P>(PhantomData<P>);
+
+impl<P: FloorPlanner> FloorPlanner for TracePlanner<P> {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let mut cs = TraceAssignment { cs, region: None };
+        P::synthesize(&mut cs, circuit, config, constants)
+    }
+}
+
+/// Forwards everything to the wrapped `Assignment`, recording each advice and fixed
+/// assignment on the way.
+struct TraceAssignment<'a, CS> {
+    cs: &'a mut CS,
+    region: Option<String>,
+}
+
+impl<'a, CS> TraceAssignment<'a, CS> {
+    // Unknown values are not recorded, `MockProver` refuses them anyway.
+    fn record<F: Field>(&self, annotation: &str, column: Column<Any>, row: usize, value: Value<Assigned<F>>) {
+        value.map(|value| {
+            let cell = TracedCell {
+                region: self.region.clone(),
+                annotation: annotation.to_string(),
+                column,
+                row,
+                value: Box::new(value.evaluate()) as Box<dyn any::Any>,
+            };
+            CELLS.with(|cells| cells.borrow_mut().push(cell));
+        });
+    }
+}
+
+impl<'a, F: Field, CS: Assignment<F>> Assignment<F> for TraceAssignment<'a, CS> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let name: String = name_fn().into();
+        self.region = Some(name.clone());
+        self.cs.enter_region(|| name);
+    }
+
+    fn exit_region(&mut self) {
+        self.region = None;
+        self.cs.exit_region();
+    }
+
+    fn enable_selector<A, AR>(&mut self, annotation: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.enable_selector(annotation, selector, row)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let annotation: String = annotation().into();
+        let value = to().map(|value| value.into());
+        self.record(&annotation, column.into(), row, value);
+        self.cs.assign_advice(|| annotation, column, row, || value)
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let annotation: String = annotation().into();
+        let value = to().map(|value| value.into());
+        self.record(&annotation, column.into(), row, value);
+        self.cs.assign_fixed(|| annotation, column, row, || value)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(&mut self, column: Column<Fixed>, row: usize, to: Value<Assigned<F>>) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}
+
+#[cfg(all(test, feature = "decompose"))]
+mod test {
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    use super::trace;
+    use crate::decompose::decompose_range_check::DecomposeRangeCheckCircuit;
+
+    #[test]
+    fn test_trace_running_sum() {
+        let value = 0x1234_5678_9abc_def0u64;
+        let circuit = DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::known(Fp::from(value).into()),
+            num_bits: 64,
+        };
+        let trace = trace(9, &circuit, vec![]);
+
+        // z_{i+1} = (z_i - c_i) / 2^8, down to z_8 = 0
+        for i in 0..=8 {
+            let z = (value as u128 >> (8 * i)) as u64;
+            assert_eq!(trace.value("Decompose Region", &format!("z_{}", i)), Some(Fp::from(z)), "z_{}", i);
+        }
+        let column = trace.region("Decompose Region").next().unwrap().column;
+        assert_eq!(trace.column("Decompose Region", column).len(), 9);
+        assert!(trace.to_string().contains("Decompose Region\n"));
+    }
+}
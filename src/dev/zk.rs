@@ -0,0 +1,97 @@
+use ff::PrimeField;
+use halo2_proofs::{pasta::Fp, plonk::Circuit};
+
+use crate::prover::{prove_deterministic, setup, verify};
+
+/// Zero-knowledge check for a circuit: proves it `runs` times, each with a differently
+/// seeded RNG, and panics unless
+///
+/// - every proof verifies against the same verifying key,
+/// - no two proofs are equal, i.e. the prover's randomness reaches the proof, and
+/// - none of the `secrets` (private witness values) appears, in its canonical encoding, in
+///   the public inputs or in any proof.
+///
+/// The last check only catches values copied verbatim, such as an unblinded advice
+/// evaluation; it says nothing about what could be derived from the proof.
+pub fn assert_zero_knowledge<C: Circuit<Fp>>(k: u32, circuit: &C, instances: Vec<Vec<Fp>>, secrets: &[Fp], runs: usize) {
+    assert!(runs >= 2, "at least two proofs are needed to compare");
+    let (params, pk) = setup(k, circuit).expect("keygen failed");
+
+    let proofs: Vec<Vec<u8>> = (0..runs as u64)
+        .map(|seed| prove_deterministic(&params, &pk, circuit, &instances, seed).expect("proving failed"))
+        .collect();
+
+    for (i, proof) in proofs.iter().enumerate() {
+        verify(&params, pk.get_vk(), &instances, proof).unwrap_or_else(|err| panic!("proof {} does not verify: {:?}", i, err));
+        if let Some(j) = proofs[..i].iter().position(|other| other == proof) {
+            panic!("proofs {} and {} are identical, the proof is not blinded", j, i);
+        }
+    }
+
+    for secret in secrets {
+        let repr = secret.to_repr();
+        let encoding: &[u8] = repr.as_ref();
+        assert!(
+            !instances.iter().flatten().any(|instance| instance == secret),
+            "secret {:?} is a public input",
+            secret
+        );
+        for (i, proof) in proofs.iter().enumerate() {
+            assert!(
+                !proof.windows(encoding.len()).any(|window| window == encoding),
+                "secret {:?} appears in proof {}",
+                secret,
+                i
+            );
+        }
+    }
+}
+
+#[cfg(all(test, feature = "fibonacci", feature = "gadgets"))]
+mod test {
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    use super::assert_zero_knowledge;
+    use crate::{
+        decompose::decompose_range_check::DecomposeRangeCheckCircuit, fibonaci::example1,
+        gadgets::is_equal::IsEqualCircuit, range_check,
+    };
+
+    #[test]
+    fn test_zero_knowledge_fibonacci() {
+        let circuit = example1::FiboCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        // the seeds and the output are public, the terms in between are not
+        let secrets = [Fp::from(13), Fp::from(21), Fp::from(34)];
+        assert_zero_knowledge(4, &circuit, vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]], &secrets, 3);
+    }
+
+    #[test]
+    fn test_zero_knowledge_range_check() {
+        let circuit = range_check::example1::RangeCheckCircuit::<Fp, 8> {
+            value: Value::known(Fp::from(5).into()),
+        };
+        assert_zero_knowledge(4, &circuit, vec![], &[Fp::from(5)], 3);
+    }
+
+    #[test]
+    fn test_zero_knowledge_decompose() {
+        let value = Fp::from(0xdead_beef_cafe_f00du64);
+        let circuit = DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::known(value.into()),
+            num_bits: 64,
+        };
+        assert_zero_knowledge(9, &circuit, vec![], &[value], 2);
+    }
+
+    #[test]
+    fn test_zero_knowledge_is_equal() {
+        let circuit = IsEqualCircuit {
+            a: Value::known(Fp::from(1234)),
+            b: Value::known(Fp::from(1234)),
+        };
+        assert_zero_knowledge(4, &circuit, vec![], &[Fp::from(1234)], 3);
+    }
+}
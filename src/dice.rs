@@ -0,0 +1,297 @@
+//! A die rolled by commit and reveal, so the roller cannot pick the outcome and nobody has
+//! to trust them.
+//!
+//! The roller picks a secret seed and publishes `commitment = hash2(seed, blinding)`. Only
+//! then does the other party pick a public nonce, the round number or a value of their
+//! own, and the roll is
+//!
+//!     roll = hash2(seed, nonce) mod 6,   the die showing roll + 1
+//!
+//! with the hash read as an integer. The seed is fixed before the nonce is known and the
+//! nonce cannot be aimed at a seed it does not know, so neither side steers the roll, and
+//! the circuit proves it against the commitment without opening it: the public inputs are
+//! the commitment, the nonce and the roll.
+//!
+//! `DivRemChip` only divides values below `2^128`, so the hash is first split in two halves
+//! of 127 bits, `hash = hi * 2^127 + lo`, which is exact for any hash below `2^254`, all but
+//! a `2^-125` fraction of them. As `2^127 = 2 mod 6`, the roll is that of `2 * (hi mod 6) + lo`,
+//! and one gate with a fixed weight makes both the split and the fold,
+//!
+//!      value   |   high   |   low   |  weight  |  q_split
+//!     ------------------------------------------------------
+//!      hash    |    hi    |   lo    |  2^127   |     1       value = high * weight + low
+//!       6      |          |         |          |     0
+//!     ------------------------------------------------------
+//!     folded   |  hi % 6  |   lo    |    2     |     1
+//!
+//! with `hi` and `lo` unpacked into 127 bits each. The divisor is pinned to 6, so the
+//! quotients below `2^127` cannot wrap around the field.
+
+use std::marker::PhantomData;
+
+use ff::Field;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::ResultExt,
+    gadgets::{
+        commit::CommitChip,
+        div_rem::{DivRemChip, DivRemConfig},
+        hasher::CircuitHasher,
+    },
+    gate::GateBuilder,
+    public_inputs::{InstanceChip, InstanceConfig, InstanceLayout},
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The public inputs of `DiceCircuit`.
+pub const INSTANCES: InstanceLayout = InstanceLayout(&[&["commitment", "nonce", "roll"]]);
+
+/// The bits of each half of the hash, and of the quotients and remainders by 6.
+const HALF_BITS: usize = 127;
+
+/// The faces of the die.
+const SIDES: u64 = 6;
+
+/// The halves `(hi, lo)` of `hash = hi * 2^127 + lo`, with `hi` too large for a hash above
+/// `2^254`.
+pub fn split(hash: Fp) -> (u128, u128) {
+    let lo = hash.get_lower_128() % (1 << HALF_BITS);
+    let shift = Fp::from_u128(1 << HALF_BITS).invert().unwrap();
+    let hi = ((hash - Fp::from_u128(lo)) * shift).get_lower_128();
+    (hi, lo)
+}
+
+/// The commitment the roller publishes for `seed`.
+pub fn commitment<H: CircuitHasher<Fp>>(seed: Fp, blinding: Fp) -> Fp {
+    H::hash2_native(seed, blinding)
+}
+
+/// `hash2(seed, nonce) mod 6`, computed natively.
+pub fn roll<H: CircuitHasher<Fp>>(seed: Fp, nonce: Fp) -> u64 {
+    let (hi, lo) = split(H::hash2_native(seed, nonce));
+    ((2 * (hi % SIDES as u128) + lo) % SIDES as u128) as u64
+}
+
+#[derive(Debug, Clone)]
+pub struct DiceConfig<H: CircuitHasher<Fp> = CommitChip> {
+    value: Column<Advice>,
+    high: Column<Advice>,
+    low: Column<Advice>,
+    weight: Column<Fixed>,
+    q_split: Selector,
+    hasher: H::Config,
+    div_rem: DivRemConfig,
+    load: LoadPrivateConfig,
+    instance: InstanceConfig,
+    constants: ConstantsConfig,
+}
+
+/// Proves the roll for a public nonce from the seed behind a public commitment, hashing
+/// with `H`.
+pub struct DiceCircuit<H: CircuitHasher<Fp> = CommitChip> {
+    pub seed: Value<Fp>,
+    pub blinding: Value<Fp>,
+    pub hasher: PhantomData<H>,
+}
+
+impl<H: CircuitHasher<Fp>> DiceCircuit<H> {
+    pub fn new(seed: Fp, blinding: Fp) -> Self {
+        Self {
+            seed: Value::known(seed),
+            blinding: Value::known(blinding),
+            hasher: PhantomData,
+        }
+    }
+
+    /// The public inputs, as laid out by `INSTANCES`: the commitment, the nonce and the roll.
+    pub fn instances(seed: Fp, blinding: Fp, nonce: Fp) -> Vec<Fp> {
+        vec![commitment::<H>(seed, blinding), nonce, Fp::from(roll::<H>(seed, nonce))]
+    }
+}
+
+impl<H: CircuitHasher<Fp>> Circuit<Fp> for DiceCircuit<H> {
+    type Config = DiceConfig<H>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            seed: Value::unknown(),
+            blinding: Value::unknown(),
+            hasher: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let hasher = H::configure(meta);
+        let div_rem = DivRemChip::<Fp, HALF_BITS>::configure(meta, instance);
+        let load = LoadPrivateChip::configure(meta, 1);
+        let public = meta.advice_column();
+        let instance = InstanceChip::configure(meta, instance, public);
+        let [value, high, low] = [(); 3].map(|_| meta.advice_column());
+        let weight = meta.fixed_column();
+        let q_split = meta.selector();
+
+        // the divisor 6 is a loaded constant
+        let constants = ConstantsChip::configure_with(meta, value);
+        for column in [value, high, low] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("split", |meta| {
+            let q_split = meta.query_selector(q_split);
+            let [value, high, low] = [value, high, low].map(|column| meta.query_advice(column, Rotation::cur()));
+            let weight = meta.query_fixed(weight, Rotation::cur());
+
+            GateBuilder::with_selector(q_split).equal("value = high * weight + low", value, high * weight + low)
+        });
+
+        DiceConfig {
+            value,
+            high,
+            low,
+            weight,
+            q_split,
+            hasher,
+            div_rem,
+            load,
+            instance,
+            constants,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let hasher = H::construct(config.hasher.clone());
+        let div_rem = DivRemChip::<Fp, HALF_BITS>::construct(config.div_rem.clone());
+        let load_chip = LoadPrivateChip::construct(config.load.clone());
+        let instance = InstanceChip::construct(config.instance.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+
+        // 1. the commitment to the seed, and the hash the roll is taken from
+        let [seed, blinding]: [_; 2] = load_chip
+            .load_privates(layouter.namespace(|| "load seed and blinding"), &[self.seed, self.blinding])?
+            .try_into()
+            .expect("two cells loaded");
+        let commitment = hasher.hash2(layouter.namespace(|| "commitment"), &seed, &blinding)?;
+        instance.expose(layouter.namespace(|| "commitment"), "commitment", &commitment, INSTANCES.row("commitment"))?;
+        let nonce = instance.load(layouter.namespace(|| "load nonce"), "nonce", INSTANCES.row("nonce"))?;
+        let hash = hasher.hash2(layouter.namespace(|| "hash"), &seed, &nonce)?;
+
+        // 2. its halves, each range checked to 127 bits
+        let six = constants.load_constant(layouter.namespace(|| "six"), "6", Fp::from(SIDES))?;
+        let (hi, lo) = layouter
+            .assign_region(
+                || "split",
+                |mut region| {
+                    config.q_split.enable(&mut region, 0)?;
+                    let weight = Fp::from_u128(1 << HALF_BITS);
+                    region.assign_fixed(|| "2^127", config.weight, 0, || Value::known(weight))?;
+                    let hash = hash.copy_advice(|| "hash", &mut region, config.value, 0)?;
+                    let halves = hash.value().map(|hash| split(*hash));
+                    let hi = region.assign_advice(|| "hi", config.high, 0, || halves.map(|(hi, _)| Fp::from_u128(hi)))?;
+                    let lo = region.assign_advice(|| "lo", config.low, 0, || halves.map(|(_, lo)| Fp::from_u128(lo)))?;
+                    Ok((hi, lo))
+                },
+            )
+            .in_chip("DiceCircuit", "split")?;
+        div_rem.bits().unpack(layouter.namespace(|| "range check hi"), &hi, HALF_BITS)?;
+        div_rem.bits().unpack(layouter.namespace(|| "range check lo"), &lo, HALF_BITS)?;
+
+        // 3. folded into a value below 2^128 with the same roll, and reduced modulo 6
+        let (_, hi_rem) = div_rem.div_rem(layouter.namespace(|| "hi mod 6"), &hi, &six)?;
+        let folded = layouter
+            .assign_region(
+                || "fold",
+                |mut region| {
+                    config.q_split.enable(&mut region, 0)?;
+                    region.assign_fixed(|| "2", config.weight, 0, || Value::known(Fp::from(2)))?;
+                    let hi_rem = hi_rem.copy_advice(|| "hi % 6", &mut region, config.high, 0)?;
+                    let lo = lo.copy_advice(|| "lo", &mut region, config.low, 0)?;
+                    let folded = hi_rem.value().copied() * Value::known(Fp::from(2)) + lo.value().copied();
+                    region.assign_advice(|| "folded", config.value, 0, || folded)
+                },
+            )
+            .in_chip("DiceCircuit", "fold")?;
+        let (_, roll) = div_rem.div_rem(layouter.namespace(|| "folded mod 6"), &folded, &six)?;
+        instance.expose(layouter.namespace(|| "roll"), "roll", &roll, INSTANCES.row("roll"))?;
+        Ok(())
+    }
+}
+
+impl<H: CircuitHasher<Fp>> ValidateWitness<Fp> for DiceCircuit<H> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let seed = known(self.seed, "seed")?;
+        let blinding = known(self.blinding, "blinding")?;
+        let nonce = instances.first().and_then(|column| column.get(INSTANCES.row("nonce"))).copied().ok_or(
+            InvalidWitness::WrongInstanceCount {
+                expected: 3,
+                found: instances.iter().map(Vec::len).sum(),
+            },
+        )?;
+        let (hi, _) = split(H::hash2_native(seed, nonce));
+        check_below(Fp::from_u128(hi), 1 << HALF_BITS, "high half of the hash")?;
+
+        check_instances(instances, &Self::instances(seed, blinding, nonce))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{arithmetic::FieldExt, dev::MockProver, pasta::Fp};
+    use rand::rngs::OsRng;
+
+    use super::{roll, split, DiceCircuit};
+    use crate::{gadgets::commit::CommitChip, validate::ValidateWitness};
+
+    const K: u32 = 11;
+
+    #[test]
+    fn test_split() {
+        let hash = Fp::random(OsRng);
+        let (hi, lo) = split(hash);
+        assert!(lo < 1 << 127);
+        assert_eq!(Fp::from_u128(hi) * Fp::from_u128(1 << 127) + Fp::from_u128(lo), hash);
+
+        // -1 is above 2^254, so its high half does not fit
+        assert!(split(-Fp::one()).0 >= 1 << 127);
+    }
+
+    #[test]
+    fn test_dice() {
+        let (seed, blinding) = (Fp::random(OsRng), Fp::random(OsRng));
+        let circuit = DiceCircuit::<CommitChip>::new(seed, blinding);
+
+        for nonce in 0..4 {
+            let nonce = Fp::from(nonce);
+            let instances = vec![DiceCircuit::<CommitChip>::instances(seed, blinding, nonce)];
+            assert!(roll::<CommitChip>(seed, nonce) < 6);
+            circuit.validate_witness(&instances).unwrap();
+            assert_eq!(MockProver::run(K, &circuit, instances).unwrap().verify(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_dice_wrong_roll() {
+        let (seed, blinding, nonce) = (Fp::random(OsRng), Fp::random(OsRng), Fp::random(OsRng));
+        let circuit = DiceCircuit::<CommitChip>::new(seed, blinding);
+        let mut instances = vec![DiceCircuit::<CommitChip>::instances(seed, blinding, nonce)];
+
+        // the next face, and a value with the same remainder
+        let rolled = roll::<CommitChip>(seed, nonce);
+        for other in [(rolled + 1) % 6, rolled + 6] {
+            instances[0][2] = Fp::from(other);
+            assert!(circuit.validate_witness(&instances).is_err());
+            assert!(MockProver::run(K, &circuit, instances.clone()).unwrap().verify().is_err());
+        }
+
+        // the roll of another seed, against the first one's commitment
+        let other = DiceCircuit::<CommitChip>::new(Fp::random(OsRng), blinding);
+        let instances = vec![DiceCircuit::<CommitChip>::instances(seed, blinding, nonce)];
+        assert!(other.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &other, instances).unwrap().verify().is_err());
+    }
+}
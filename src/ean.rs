@@ -0,0 +1,282 @@
+//! The check digit of an EAN product code, an ISBN-13 included: a weighted sum of the
+//! digits is a multiple of 10, with weights alternating 1 and 3 from the check digit, the
+//! last one, leftwards,
+//!
+//!     ISBN 978-0-306-40615-7:   9 + 3*7 + 8 + 3*0 + 3 + 3*0 + 6 + 3*4 + 0 + 3*6 + 1 + 3*5 + 7 = 100
+//!
+//! The code is private and the only public input is whether it is valid, 1 or 0.
+//!
+//! A teaching example for weighted sums: the weights are fixed, so they sit in a fixed
+//! column next to the digits, and one gate accumulates the sum a digit per row,
+//!
+//!       digit   |  weight  |   acc    |  q_digit
+//!     ---------------------------------------------
+//!               |          |    0     |
+//!        d_0    |   w_0    |  acc_1   |     1       acc = acc_prev + weight * digit
+//!        ...    |   ...    |   ...    |    ...      digit in 0..10
+//!     d_{LEN-1} | w_{LEN-1}|  acc_LEN |     1
+//!
+//! with every digit looked up in a table of `0..10`. `DivRemChip` then reduces the sum modulo
+//! 10, and `IsZeroChip` turns the remainder into the public bit:
+//!
+//!       digit   |  value_inv  |  valid  |  q_valid
+//!     ------------------------------------------------
+//!        r      |    1 / r    |  valid  |     1       valid = (r == 0)
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::ResultExt,
+    gadgets::{
+        div_rem::{DivRemChip, DivRemConfig},
+        is_zero::{IsZeroChip, IsZeroConfig},
+    },
+    gate::GateBuilder,
+    table::load_range_table,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The bits the weighted sum's quotient and remainder by 10 are range checked to, enough for
+/// codes of up to 28 digits.
+const DIV_BITS: usize = 8;
+
+/// The weight of digit `i` of a code of `len` digits: 1 for the check digit, then
+/// alternately 3 and 1 leftwards.
+pub fn weight(i: usize, len: usize) -> u64 {
+    if (len - 1 - i) % 2 == 0 {
+        1
+    } else {
+        3
+    }
+}
+
+/// The digits of `code`, ignoring hyphens and spaces, or `None` if it has other characters.
+pub fn digits(code: &str) -> Option<Vec<u64>> {
+    code.chars().filter(|c| !matches!(c, '-' | ' ')).map(|c| c.to_digit(10).map(u64::from)).collect()
+}
+
+/// Whether the weighted sum of `digits` is a multiple of 10.
+pub fn is_valid(digits: &[u64]) -> bool {
+    let sum: u64 = digits.iter().enumerate().map(|(i, digit)| weight(i, digits.len()) * digit).sum();
+    sum % 10 == 0
+}
+
+#[derive(Debug, Clone)]
+pub struct EanConfig {
+    digit: Column<Advice>,
+    weight: Column<Fixed>,
+    acc: Column<Advice>,
+    q_digit: Selector,
+    q_valid: Selector,
+    is_zero: IsZeroConfig<Fp>,
+    digits: TableColumn,
+    div_rem: DivRemConfig,
+    instance: Column<Instance>,
+    constants: ConstantsConfig,
+}
+
+/// Proves whether a private code of `LEN` digits has a valid check digit.
+pub struct EanCircuit<const LEN: usize> {
+    pub digits: [Value<u64>; LEN],
+}
+
+impl<const LEN: usize> Default for EanCircuit<LEN> {
+    fn default() -> Self {
+        Self {
+            digits: [Value::unknown(); LEN],
+        }
+    }
+}
+
+impl<const LEN: usize> EanCircuit<LEN> {
+    /// The circuit for `code`, e.g. "978-0-306-40615-7". Panics if it is not `LEN` digits.
+    pub fn new(code: &str) -> Self {
+        let digits: [u64; LEN] = digits(code)
+            .expect("a code of digits")
+            .try_into()
+            .unwrap_or_else(|digits: Vec<_>| panic!("{} digits, not {}", digits.len(), LEN));
+        Self {
+            digits: digits.map(Value::known),
+        }
+    }
+
+    /// The public input: whether the code is valid.
+    pub fn instances(valid: bool) -> Vec<Fp> {
+        vec![Fp::from(valid as u64)]
+    }
+}
+
+impl<const LEN: usize> Circuit<Fp> for EanCircuit<LEN> {
+    type Config = EanConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let div_rem = DivRemChip::<Fp, DIV_BITS>::configure(meta, instance);
+        let digit = meta.advice_column();
+        let weight = meta.fixed_column();
+        let acc = meta.advice_column();
+        let value_inv = meta.advice_column();
+        let q_digit = meta.complex_selector();
+        let q_valid = meta.selector();
+        let digits = meta.lookup_table_column();
+
+        // `acc_0 == 0` and the divisor 10 are loaded constants
+        let constants = ConstantsChip::configure_with(meta, acc);
+        meta.enable_equality(digit);
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+
+        meta.create_gate("weighted sum", |meta| {
+            let q_digit = meta.query_selector(q_digit);
+            let digit = meta.query_advice(digit, Rotation::cur());
+            let weight = meta.query_fixed(weight, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            GateBuilder::with_selector(q_digit).equal("acc = acc_prev + weight * digit", acc, acc_prev + weight * digit)
+        });
+
+        meta.lookup(|meta| {
+            let q_digit = meta.query_selector(q_digit);
+            let digit = meta.query_advice(digit, Rotation::cur());
+            vec![(q_digit * digit, digits)]
+        });
+
+        let is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_valid),
+            |meta| meta.query_advice(digit, Rotation::cur()),
+            value_inv,
+        );
+        let is_zero_expr = is_zero.is_zero_expr.clone();
+        meta.create_gate("valid", |meta| {
+            let q_valid = meta.query_selector(q_valid);
+            let valid = meta.query_advice(acc, Rotation::cur());
+
+            GateBuilder::with_selector(q_valid).equal("valid = (r == 0)", valid, is_zero_expr)
+        });
+
+        EanConfig {
+            digit,
+            weight,
+            acc,
+            q_digit,
+            q_valid,
+            is_zero,
+            digits,
+            div_rem,
+            instance,
+            constants,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let div_rem = DivRemChip::<Fp, DIV_BITS>::construct(config.div_rem.clone());
+        let is_zero = IsZeroChip::construct(config.is_zero.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+        load_range_table(&mut layouter, config.digits, 10)?;
+
+        // 1. the weighted sum of the digits, and the divisor
+        let zero = constants.load_constant(layouter.namespace(|| "zero"), "zero", Fp::zero())?;
+        let ten = constants.load_constant(layouter.namespace(|| "ten"), "10", Fp::from(10))?;
+        let sum = layouter
+            .assign_region(
+                || "weighted sum",
+                |mut region| {
+                    let mut acc = zero.copy_advice(|| "acc_0", &mut region, config.acc, 0)?;
+                    for (i, digit) in self.digits.iter().enumerate() {
+                        let offset = i + 1;
+                        let weight = Fp::from(weight(i, LEN));
+                        config.q_digit.enable(&mut region, offset)?;
+                        region.assign_fixed(|| format!("w_{}", i), config.weight, offset, || Value::known(weight))?;
+                        let digit = region.assign_advice(|| format!("d_{}", i), config.digit, offset, || digit.map(Fp::from))?;
+                        let next = acc.value().copied() + digit.value().copied() * Value::known(weight);
+                        acc = region.assign_advice(|| format!("acc_{}", offset), config.acc, offset, || next)?;
+                    }
+                    Ok(acc)
+                },
+            )
+            .in_chip("EanCircuit", "weighted sum")?;
+
+        // 2. whether it is a multiple of 10
+        let (_, rem) = div_rem.div_rem(layouter.namespace(|| "sum mod 10"), &sum, &ten)?;
+        let valid = layouter
+            .assign_region(
+                || "valid",
+                |mut region| {
+                    config.q_valid.enable(&mut region, 0)?;
+                    let rem = rem.copy_advice(|| "r", &mut region, config.digit, 0)?;
+                    is_zero.assign(&mut region, 0, rem.value().copied())?;
+                    let valid = rem.value().map(|rem| Fp::from((*rem == Fp::zero()) as u64));
+                    region.assign_advice(|| "valid", config.acc, 0, || valid)
+                },
+            )
+            .in_chip("EanCircuit", "valid")?;
+
+        layouter.constrain_instance(valid.cell(), config.instance, 0)?;
+        Ok(())
+    }
+}
+
+impl<const LEN: usize> ValidateWitness<Fp> for EanCircuit<LEN> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let digits = self
+            .digits
+            .iter()
+            .map(|digit| {
+                let digit = known(*digit, "digit")?;
+                check_below(Fp::from(digit), 10, "digit")?;
+                Ok(digit)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        check_instances(instances, &Self::instances(is_valid(&digits)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver};
+
+    use super::{digits, is_valid, EanCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 8;
+
+    fn run<const LEN: usize>(code: &str, valid: bool) -> bool {
+        let circuit = EanCircuit::<LEN>::new(code);
+        let instances = vec![EanCircuit::<LEN>::instances(valid)];
+        assert_eq!(circuit.validate_witness(&instances).is_ok(), valid == is_valid(&digits(code).unwrap()));
+        MockProver::run(K, &circuit, instances).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn test_ean() {
+        // an ISBN-13, an EAN-13 and an EAN-8
+        assert!(run::<13>("978-0-306-40615-7", true));
+        assert!(run::<13>("4006381333931", true));
+        assert!(run::<8>("7351 3537", true));
+
+        // a mistyped check digit and two swapped digits, proven invalid
+        assert!(run::<13>("978-0-306-40615-8", false));
+        assert!(run::<13>("978-0-306-46015-7", false));
+    }
+
+    #[test]
+    fn test_ean_wrong_validity() {
+        assert!(!run::<13>("978-0-306-40615-7", false));
+        assert!(!run::<13>("978-0-306-40615-8", true));
+
+        // a digit out of range, that would make the sum a multiple of 10 again
+        let mut circuit = EanCircuit::<13>::new("978-0-306-40615-8");
+        circuit.digits[12] = Value::known(19);
+        let instances = vec![EanCircuit::<13>::instances(true)];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,312 @@
+//! Verifies a Schnorr signature in EdDSA form on Pallas, the curve embedded in the circuit's
+//! field: Pallas coordinates live in `Fp`, so each point addition is one row of native
+//! arithmetic. A curve over a foreign field, like secp256k1 for ECDSA, needs every coordinate
+//! split into limbs and every product range-checked, for many rows per addition; hence the
+//! curve is picked along with the proof system, as Baby Jubjub is picked for BN254. There is
+//! no non-native ECDSA example in this crate to set this one against.
+//!
+//! Baby Jubjub is a twisted Edwards curve, whose addition law has no exceptional cases. Pallas
+//! is short Weierstrass, so the multiplications use the complete addition gate of
+//! `ScalarMulChip` instead. With `G` the Pallas generator, a key pair `(a, A = [a] G)`, and
+//! the message `m` an `Fp` element:
+//!
+//!     sign:    R = [r] G for a random r,   c = H(R, A, m),   s = r + c * a  (mod q)
+//!     verify:  [s] G = R + [c] A,          H(R, A, m) = commit_all([x_R, y_R, x_A, y_A, m])
+//!
+//! The challenge is a Poseidon hash in `Fp`, less than the order `q` of `G`, and is unpacked
+//! into 254 bits; a signer draws another `r` for the one challenge in 2^126 that does not fit.
+//! `s` is witnessed as 255 bits, so `s + q` passes too when it fits: the signature is
+//! malleable, as EdDSA is without its `s < q` check, which the circuit leaves out.
+//!
+//! The public key and the message are public; `R` and `s` stay private, so a proof also hides
+//! which of the signatures on `m` the prover holds.
+
+use ff::{Field, PrimeField, PrimeFieldBits};
+use group::{prime::PrimeCurveAffine, Curve};
+use halo2_proofs::{
+    circuit::*,
+    pasta::{pallas, Fp},
+    plonk::*,
+};
+use rand::RngCore;
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::Halo2ExamplesError,
+    gadgets::{
+        bits::{BitsChip, BitsConfig},
+        commit::{commit_all, CommitChip, CommitConfig},
+        ecc::{coordinates, AssignedPoint, EccChip, EccConfig},
+        scalar_mul::{ScalarMulChip, ScalarMulConfig},
+    },
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The bits of the challenge `c`.
+pub const CHALLENGE_BITS: usize = 254;
+
+/// The bits of `s`, enough for any scalar.
+pub const SCALAR_BITS: usize = pallas::Scalar::NUM_BITS as usize;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Signature {
+    pub r: pallas::Affine,
+    pub s: pallas::Scalar,
+}
+
+/// `A = [a] G`.
+pub fn public_key(secret_key: pallas::Scalar) -> pallas::Affine {
+    (pallas::Affine::generator() * secret_key).to_affine()
+}
+
+/// `H(R, A, m)`, if it fits in `CHALLENGE_BITS` bits.
+pub fn challenge(r: pallas::Affine, public_key: pallas::Affine, message: Fp) -> Option<Fp> {
+    let (x_r, y_r) = coordinates(r);
+    let (x_a, y_a) = coordinates(public_key);
+    let c = commit_all(&[x_r, y_r, x_a, y_a, message]);
+    c.to_le_bits().iter().skip(CHALLENGE_BITS).all(|bit| !*bit).then_some(c)
+}
+
+// `c` as a scalar; every `Fp` is below `q`.
+fn to_scalar(c: Fp) -> pallas::Scalar {
+    Option::from(pallas::Scalar::from_repr(c.to_repr())).expect("p < q")
+}
+
+pub fn sign(secret_key: pallas::Scalar, message: Fp, mut rng: impl RngCore) -> Signature {
+    let public_key = public_key(secret_key);
+    loop {
+        let nonce = pallas::Scalar::random(&mut rng);
+        if nonce.is_zero_vartime() {
+            continue;
+        }
+        let r = (pallas::Affine::generator() * nonce).to_affine();
+        if let Some(c) = challenge(r, public_key, message) {
+            return Signature {
+                r,
+                s: nonce + to_scalar(c) * secret_key,
+            };
+        }
+    }
+}
+
+/// `[s] G = R + [c] A`, computed natively.
+pub fn verify(public_key: pallas::Affine, message: Fp, signature: &Signature) -> bool {
+    let identity = |point: pallas::Affine| bool::from(point.is_identity());
+    if identity(public_key) || identity(signature.r) {
+        return false;
+    }
+    match challenge(signature.r, public_key, message) {
+        Some(c) => pallas::Affine::generator() * signature.s == signature.r + public_key * to_scalar(c),
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EdDsaConfig {
+    ecc: EccConfig,
+    scalar_mul: ScalarMulConfig,
+    commit: CommitConfig,
+    bits: BitsConfig,
+    load: LoadPrivateConfig,
+}
+
+/// Verifies signatures in-circuit, like `verify`.
+pub struct EdDsaChip {
+    config: EdDsaConfig,
+    ecc: EccChip,
+    scalar_mul: ScalarMulChip,
+    commit: CommitChip,
+    bits: BitsChip<Fp>,
+    load: LoadPrivateChip<Fp>,
+}
+
+impl Chip<Fp> for EdDsaChip {
+    type Config = EdDsaConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl EdDsaChip {
+    pub fn construct(config: EdDsaConfig) -> Self {
+        Self {
+            ecc: EccChip::construct(config.ecc.clone()),
+            scalar_mul: ScalarMulChip::construct(config.scalar_mul.clone()),
+            commit: CommitChip::construct(config.commit.clone()),
+            bits: BitsChip::construct(config.bits.clone()),
+            load: LoadPrivateChip::construct(config.load.clone()),
+            config,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, instance: Column<Instance>) -> EdDsaConfig {
+        EdDsaConfig {
+            ecc: EccChip::configure(meta),
+            scalar_mul: ScalarMulChip::configure(meta),
+            commit: CommitChip::configure(meta),
+            bits: BitsChip::configure(meta, instance),
+            load: LoadPrivateChip::configure(meta, 1),
+        }
+    }
+
+    /// The chip public keys are witnessed with.
+    pub fn ecc(&self) -> &EccChip {
+        &self.ecc
+    }
+
+    /// The chip challenges are hashed with, which can hash messages as well.
+    pub fn commit(&self) -> &CommitChip {
+        &self.commit
+    }
+
+    /// Constrains `signature` to be valid on `message` under `public_key`.
+    pub fn verify(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        public_key: &AssignedPoint,
+        message: &AssignedCell<Fp, Fp>,
+        signature: Value<Signature>,
+    ) -> Result<(), Halo2ExamplesError> {
+        let chip = &self.scalar_mul;
+        let r = self.ecc.witness_point(layouter.namespace(|| "R"), signature.map(|signature| signature.r))?;
+
+        let c = self.commit.commit_all(
+            layouter.namespace(|| "challenge"),
+            &[r.x.clone(), r.y.clone(), public_key.x.clone(), public_key.y.clone(), message.clone()],
+        )?;
+        let c_bits = self.bits.unpack(layouter.namespace(|| "challenge bits"), &c, CHALLENGE_BITS)?;
+
+        let s_bits = signature.map(|signature| signature.s.to_le_bits());
+        let s_bits: Vec<_> = (0..SCALAR_BITS)
+            .map(|i| s_bits.as_ref().map(|bits| Fp::from(bits[i] as u64)))
+            .collect();
+        let s_bits = self.load.load_privates(layouter.namespace(|| "load s"), &s_bits)?;
+
+        let generator = chip.constant_point(layouter.namespace(|| "G"), pallas::Affine::generator())?;
+        let lhs = chip.mul(layouter.namespace(|| "[s] G"), &generator, &s_bits)?;
+        let c_public_key = chip.mul(layouter.namespace(|| "[c] A"), public_key, &c_bits)?;
+        let rhs = chip.add(layouter.namespace(|| "R + [c] A"), &r, &c_public_key)?;
+        self.ecc.assert_equal(layouter.namespace(|| "[s] G = R + [c] A"), &lhs, &rhs)
+    }
+}
+
+/// Proves a private signature on a public message under a public key.
+#[derive(Default)]
+pub struct EdDsaCircuit {
+    pub public_key: Value<pallas::Affine>,
+    pub message: Value<Fp>,
+    pub signature: Value<Signature>,
+}
+
+impl EdDsaCircuit {
+    /// `x_A`, `y_A` and `m`, in instance order.
+    pub fn outputs(public_key: pallas::Affine, message: Fp) -> [Fp; 3] {
+        let (x, y) = coordinates(public_key);
+        [x, y, message]
+    }
+}
+
+impl Circuit<Fp> for EdDsaCircuit {
+    type Config = (EdDsaConfig, LoadPrivateConfig, PublicOutputsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let eddsa = EdDsaChip::configure(meta, instance);
+        (eddsa, LoadPrivateChip::configure(meta, 1), PublicOutputs::configure(meta, instance))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, load_config, outputs_config) = config;
+        let chip = EdDsaChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+
+        let public_key = chip.ecc().witness_point(layouter.namespace(|| "public key"), self.public_key)?;
+        let message = load_chip.load_private(layouter.namespace(|| "load message"), self.message)?;
+        chip.verify(layouter.namespace(|| "verify"), &public_key, &message, self.signature)?;
+
+        outputs.expose("public_key_x", &public_key.x);
+        outputs.expose("public_key_y", &public_key.y);
+        outputs.expose("message", &message);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for EdDsaCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let public_key = known(self.public_key, "public_key")?;
+        let message = known(self.message, "message")?;
+        if !verify(public_key, message, &known(self.signature, "signature")?) {
+            return Err(InvalidWitness::InvalidParameter(
+                "the signature does not verify under the public key".to_string(),
+            ));
+        }
+        check_instances(instances, &Self::outputs(public_key, message))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::Value,
+        dev::MockProver,
+        pasta::{pallas, Fp},
+    };
+    use rand::rngs::OsRng;
+
+    use super::{public_key, sign, verify, EdDsaCircuit, Signature};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 12;
+
+    fn circuit(public_key: pallas::Affine, message: Fp, signature: Signature) -> EdDsaCircuit {
+        EdDsaCircuit {
+            public_key: Value::known(public_key),
+            message: Value::known(message),
+            signature: Value::known(signature),
+        }
+    }
+
+    #[test]
+    fn test_eddsa() {
+        let secret_key = pallas::Scalar::random(OsRng);
+        let public_key = public_key(secret_key);
+        let message = Fp::from(42);
+        let signature = sign(secret_key, message, OsRng);
+        assert!(verify(public_key, message, &signature));
+
+        let instances = vec![EdDsaCircuit::outputs(public_key, message).to_vec()];
+        let valid = circuit(public_key, message, signature);
+        assert!(valid.validate_witness(&instances).is_ok());
+        MockProver::run(K, &valid, instances.clone()).unwrap().assert_satisfied();
+
+        // the signature on another message
+        let other = vec![EdDsaCircuit::outputs(public_key, Fp::from(43)).to_vec()];
+        let circuit_other = circuit(public_key, Fp::from(43), signature);
+        assert!(circuit_other.validate_witness(&other).is_err());
+        assert!(MockProver::run(K, &circuit_other, other).unwrap().verify().is_err());
+
+        // a forged s
+        let forged = Signature {
+            s: signature.s + pallas::Scalar::one(),
+            ..signature
+        };
+        let circuit_forged = circuit(public_key, message, forged);
+        assert!(circuit_forged.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit_forged, instances).unwrap().verify().is_err());
+    }
+}
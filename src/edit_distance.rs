@@ -0,0 +1,322 @@
+//! A bounded edit distance: a private string is at most `bound` insertions, deletions and
+//! substitutions away from a public one, by the Levenshtein dynamic program,
+//!
+//!     D[i][0] = i,   D[0][j] = j,
+//!     D[i][j] = min(D[i-1][j] + 1, D[i][j-1] + 1, D[i-1][j-1] + (a_i != b_j)),
+//!     D[N][M] <= bound
+//!
+//! with the table laid out by `DpGridChip` and `LevenshteinChip` as its step. A step row
+//! copies in the three neighbours and the two characters, and its gate works out the three
+//! candidates; `MinMaxChip::min` then picks `D[i][j]` among them, and the grid copies that
+//! cell into the steps below and to the right:
+//!
+//!       a   |  b   |  inv  |  up  |  left  |  diag  |  del  |  ins  |  sub  |  q_step
+//!     -----------------------------------------------------------------------------------
+//!      a_i  | b_j  |  inv  |  u   |   l    |   d    | u + 1 | l + 1 | d + n |    1
+//!
+//!     n = (a_i - b_j) * inv,   (a_i - b_j) * (1 - n) = 0
+//!
+//! so `n` is 1 for different characters, the witnessed `inv` being forced to their
+//! difference's inverse, and 0 for equal ones. The grid assigns the first row and column
+//! of the table from constants.
+//!
+//! Every table cell costs a step row, a three-way `min` and the range checks of its two
+//! differences, about `2 * EDIT_BITS + 4` rows, so `N * M` grows quickly: strings of a few
+//! dozen characters already take `k = 15`. Distances are compared in `EDIT_BITS` bits, which
+//! bounds both lengths below 256. The public inputs are the public string's characters,
+//! then the bound.
+
+use ff::Field;
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gadgets::{
+        dp_grid::{DpGridChip, DpGridConfig, DpNeighbours, DpTransition},
+        min_max::{MinMaxChip, MinMaxConfig},
+    },
+    gate::GateBuilder,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The bits table cells and the bound are compared in.
+pub const EDIT_BITS: usize = 8;
+
+/// The Levenshtein distance between `a` and `b`, computed natively.
+pub fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_i) in a.iter().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, b_j) in b.iter().enumerate() {
+            let up = row[j + 1];
+            row[j + 1] = (up + 1).min(row[j] + 1).min(diag + (a_i != b_j) as usize);
+            diag = up;
+        }
+    }
+    row[b.len()]
+}
+
+#[derive(Debug, Clone)]
+pub struct LevenshteinConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    inv: Column<Advice>,
+    up: Column<Advice>,
+    left: Column<Advice>,
+    diag: Column<Advice>,
+    del: Column<Advice>,
+    ins: Column<Advice>,
+    sub: Column<Advice>,
+    q_step: Selector,
+    min_max: MinMaxConfig,
+}
+
+/// The Levenshtein step of a `DpGridChip` table.
+pub struct LevenshteinChip {
+    config: LevenshteinConfig,
+    min_max: MinMaxChip<Fp, EDIT_BITS>,
+}
+
+impl Chip<Fp> for LevenshteinChip {
+    type Config = LevenshteinConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl LevenshteinChip {
+    pub fn construct(config: LevenshteinConfig) -> Self {
+        let min_max = MinMaxChip::construct(config.min_max.clone());
+        Self { config, min_max }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, instance: Column<Instance>) -> LevenshteinConfig {
+        let min_max = MinMaxChip::<Fp, EDIT_BITS>::configure(meta, instance);
+        let [a, b, inv, up, left, diag, del, ins, sub] = [(); 9].map(|_| meta.advice_column());
+        let q_step = meta.selector();
+        for column in [a, b, up, left, diag, del, ins, sub] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("edit step", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let [a, b, inv, up, left, diag, del, ins, sub] =
+                [a, b, inv, up, left, diag, del, ins, sub].map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(Fp::one());
+            let diff = a - b;
+            let differ = diff.clone() * inv;
+
+            GateBuilder::with_selector(q_step)
+                .constrain("differ is 1 unless a == b", diff * (one.clone() - differ.clone()))
+                .equal("del = up + 1", del, up + one.clone())
+                .equal("ins = left + 1", ins, left + one)
+                .equal("sub = diag + differ", sub, diag + differ)
+        });
+
+        LevenshteinConfig {
+            a,
+            b,
+            inv,
+            up,
+            left,
+            diag,
+            del,
+            ins,
+            sub,
+            q_step,
+            min_max,
+        }
+    }
+
+    /// The chip the candidates, and the distance and its bound, are compared with.
+    pub fn min_max(&self) -> &MinMaxChip<Fp, EDIT_BITS> {
+        &self.min_max
+    }
+}
+
+impl DpTransition<Fp> for LevenshteinChip {
+    type Config = LevenshteinConfig;
+
+    fn construct(config: LevenshteinConfig) -> Self {
+        LevenshteinChip::construct(config)
+    }
+
+    fn boundary(i: usize, j: usize) -> Fp {
+        // one of them is 0
+        Fp::from((i + j) as u64)
+    }
+
+    fn step(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        inputs: [&AssignedCell<Fp, Fp>; 2],
+        neighbours: DpNeighbours<'_, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        let config = &self.config;
+        let candidates = layouter
+            .assign_region(
+                || "step",
+                |mut region| {
+                    config.q_step.enable(&mut region, 0)?;
+                    let a = inputs[0].copy_advice(|| "a_i", &mut region, config.a, 0)?;
+                    let b = inputs[1].copy_advice(|| "b_j", &mut region, config.b, 0)?;
+                    let up = neighbours.up.copy_advice(|| "up", &mut region, config.up, 0)?;
+                    let left = neighbours.left.copy_advice(|| "left", &mut region, config.left, 0)?;
+                    let diag = neighbours.diag.copy_advice(|| "diag", &mut region, config.diag, 0)?;
+
+                    let diff = a.value().copied() - b.value().copied();
+                    let inv = diff.map(|diff| diff.invert().unwrap_or(Fp::zero()));
+                    region.assign_advice(|| "inv", config.inv, 0, || inv)?;
+                    let differ = diff * inv;
+
+                    let one = Value::known(Fp::one());
+                    let del = region.assign_advice(|| "del", config.del, 0, || up.value().copied() + one)?;
+                    let ins = region.assign_advice(|| "ins", config.ins, 0, || left.value().copied() + one)?;
+                    let sub = region.assign_advice(|| "sub", config.sub, 0, || diag.value().copied() + differ)?;
+                    Ok([del, ins, sub])
+                },
+            )
+            .in_chip("LevenshteinChip", "step")?;
+        self.min_max.min(layouter.namespace(|| "min"), &candidates)
+    }
+}
+
+/// Proves a private `N`-character string is within a public bound of a public `M`-character
+/// one.
+pub struct EditDistanceCircuit<const N: usize, const M: usize> {
+    pub private: [Value<u8>; N],
+    pub public: [Value<u8>; M],
+    pub bound: Value<u64>,
+}
+
+impl<const N: usize, const M: usize> Default for EditDistanceCircuit<N, M> {
+    fn default() -> Self {
+        Self {
+            private: [Value::unknown(); N],
+            public: [Value::unknown(); M],
+            bound: Value::unknown(),
+        }
+    }
+}
+
+impl<const N: usize, const M: usize> EditDistanceCircuit<N, M> {
+    pub fn new(private: &[u8; N], public: &[u8; M], bound: u64) -> Self {
+        Self {
+            private: private.map(Value::known),
+            public: public.map(Value::known),
+            bound: Value::known(bound),
+        }
+    }
+
+    /// The public inputs: the public string's characters, then the bound.
+    pub fn instances(public: &[u8; M], bound: u64) -> Vec<Fp> {
+        public.iter().map(|c| Fp::from(*c as u64)).chain([Fp::from(bound)]).collect()
+    }
+}
+
+impl<const N: usize, const M: usize> Circuit<Fp> for EditDistanceCircuit<N, M> {
+    type Config = (DpGridConfig<LevenshteinConfig>, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let levenshtein = LevenshteinChip::configure(meta, instance);
+        (DpGridChip::configure(meta, levenshtein), LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (grid_config, load_config) = config;
+        let grid = DpGridChip::<Fp, LevenshteinChip>::construct(grid_config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let min_max = grid.transition().min_max();
+
+        let to_field = |chars: &[Value<u8>]| chars.iter().map(|c| c.map(|c| Fp::from(c as u64))).collect::<Vec<_>>();
+        let private = load_chip.load_privates(layouter.namespace(|| "load private string"), &to_field(&self.private))?;
+        let public = load_chip.load_privates(layouter.namespace(|| "load public string"), &to_field(&self.public))?;
+        for (j, c) in public.iter().enumerate() {
+            min_max.expose_public(layouter.namespace(|| format!("b_{}", j)), c, j)?;
+        }
+
+        let table = grid.fill(layouter.namespace(|| "edit distances"), &private, &public)?;
+        let distance = table.last();
+
+        let bound = load_chip.load_private(layouter.namespace(|| "load bound"), self.bound.map(Fp::from))?;
+        min_max.bits().unpack(layouter.namespace(|| "range check bound"), &bound, EDIT_BITS)?;
+        min_max.expose_public(layouter.namespace(|| "bound"), &bound, M)?;
+        let max = min_max.max(layouter.namespace(|| "max(distance, bound)"), &[distance.clone(), bound.clone()])?;
+        layouter
+            .assign_region(|| "distance within bound", |mut region| region.constrain_equal(max.cell(), bound.cell()))
+            .in_chip("EditDistanceCircuit", "distance within bound")?;
+        Ok(())
+    }
+}
+
+impl<const N: usize, const M: usize> ValidateWitness<Fp> for EditDistanceCircuit<N, M> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let chars = |chars: &[Value<u8>], name: &'static str| -> Result<Vec<u8>, InvalidWitness> {
+            chars.iter().map(|c| known(*c, name)).collect()
+        };
+        let private = chars(&self.private, "private")?;
+        let public: [u8; M] = chars(&self.public, "public")?.try_into().expect("M characters");
+        let bound = known(self.bound, "bound")?;
+        check_below(Fp::from(N.max(M) as u64), 1 << EDIT_BITS, "string length")?;
+        check_below(Fp::from(bound), 1 << EDIT_BITS, "bound")?;
+        check_below(Fp::from(edit_distance(&private, &public) as u64), bound as u128 + 1, "edit distance")?;
+        check_instances(instances, &Self::instances(&public, bound))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::{edit_distance, EditDistanceCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 11;
+
+    #[test]
+    fn test_edit_distance_native() {
+        assert_eq!(edit_distance(b"kitten", b"sitting"), 3);
+        assert_eq!(edit_distance(b"flaw", b"lawn"), 2);
+        assert_eq!(edit_distance(b"", b"abc"), 3);
+        assert_eq!(edit_distance(b"same", b"same"), 0);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        for bound in [3, 4] {
+            let circuit = EditDistanceCircuit::new(b"kitten", b"sitting", bound);
+            let instances = vec![EditDistanceCircuit::<6, 7>::instances(b"sitting", bound)];
+            assert_eq!(circuit.validate_witness(&instances), Ok(()));
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_edit_distance_too_far() {
+        // one edit more than allowed
+        let circuit = EditDistanceCircuit::new(b"kitten", b"sitting", 2);
+        let instances = vec![EditDistanceCircuit::<6, 7>::instances(b"sitting", 2)];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+
+        // a different public string
+        let circuit = EditDistanceCircuit::new(b"kitten", b"sitting", 3);
+        let mut instances = EditDistanceCircuit::<6, 7>::instances(b"sitting", 3);
+        instances[0] = Fp::from(b'k' as u64);
+        assert!(MockProver::run(K, &circuit, vec![instances]).unwrap().verify().is_err());
+    }
+}
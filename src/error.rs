@@ -0,0 +1,168 @@
+use std::{cell::RefCell, fmt};
+
+use halo2_proofs::plonk::Error;
+
+/// A `plonk::Error` raised while a chip was laying out one of its regions, together with
+/// where it happened.
+///
+/// Chips return this from their public methods. It converts back into the wrapped
+/// `plonk::Error` so `Circuit::synthesize` can keep using `?`.
+#[derive(Debug)]
+pub struct Halo2ExamplesError {
+    pub chip: &'static str,
+    pub region: Option<String>,
+    pub offset: Option<usize>,
+    pub assigning: Option<String>,
+    pub source: Error,
+}
+
+impl Halo2ExamplesError {
+    pub fn new(chip: &'static str, source: Error) -> Self {
+        Self {
+            chip,
+            region: None,
+            offset: None,
+            assigning: None,
+            source,
+        }
+    }
+
+    pub fn in_region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    pub fn at(mut self, offset: usize, assigning: impl Into<String>) -> Self {
+        self.offset = Some(offset);
+        self.assigning = Some(assigning.into());
+        self
+    }
+}
+
+impl fmt::Display for Halo2ExamplesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.chip)?;
+        if let Some(region) = &self.region {
+            write!(f, ", region {:?}", region)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, ", offset {}", offset)?;
+        }
+        if let Some(assigning) = &self.assigning {
+            write!(f, ", assigning {:?}", assigning)?;
+        }
+        write!(f, ": {}", self.source)
+    }
+}
+
+impl std::error::Error for Halo2ExamplesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<Halo2ExamplesError> for Error {
+    fn from(err: Halo2ExamplesError) -> Self {
+        err.source
+    }
+}
+
+/// Attaches chip and region context to the result of a `Layouter` call.
+pub trait ResultExt<T> {
+    fn in_chip(self, chip: &'static str, region: &str) -> Result<T, Halo2ExamplesError>;
+}
+
+impl<T> ResultExt<T> for Result<T, Error> {
+    fn in_chip(self, chip: &'static str, region: &str) -> Result<T, Halo2ExamplesError> {
+        self.map_err(|err| Halo2ExamplesError::new(chip, err).in_region(region))
+    }
+}
+
+/// Records the last assignment a `RegionBuilder` failed on.
+///
+/// `assign_region` closures have to return a bare `plonk::Error`, so the offset and cell
+/// name are stashed here and picked up once the layouter hands the error back.
+#[derive(Debug, Default)]
+pub struct ErrorSite(RefCell<Option<(usize, String)>>);
+
+impl ErrorSite {
+    pub fn record(&self, offset: usize, assigning: &str) {
+        *self.0.borrow_mut() = Some((offset, assigning.to_string()));
+    }
+
+    pub fn into_error(self, chip: &'static str, region: &str, source: Error) -> Halo2ExamplesError {
+        let err = Halo2ExamplesError::new(chip, source).in_region(region);
+        match self.0.into_inner() {
+            Some((offset, assigning)) => err.at(offset, assigning),
+            None => err,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "decompose"))]
+mod test {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Assigned, Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{ErrorSite, Halo2ExamplesError};
+    use crate::{
+        chip::LoadInstructions,
+        decompose::decompose_range_check::{DecomposeChip, DecomposeConfig},
+    };
+
+    #[test]
+    fn test_error_display() {
+        let err = Halo2ExamplesError::new("DecomposeChip", Error::Synthesis)
+            .in_region("Decompose Region")
+            .at(3, "z_3");
+        assert_eq!(
+            err.to_string(),
+            format!("DecomposeChip, region \"Decompose Region\", offset 3, assigning \"z_3\": {}", Error::Synthesis)
+        );
+
+        let site = ErrorSite::default();
+        site.record(1, "z_1");
+        let err = site.into_error("DecomposeChip", "Decompose Region", Error::Synthesis);
+        assert_eq!((err.offset, err.assigning.as_deref()), (Some(1), Some("z_1")));
+    }
+
+    // Decomposes a witness into more chunks than there are usable rows, so the running sum
+    // runs off the end of the column.
+    struct TooManyChunks(Value<Assigned<Fp>>);
+
+    impl Circuit<Fp> for TooManyChunks {
+        type Config = DecomposeConfig<Fp, 256>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self(Value::unknown())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            DecomposeConfig::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = DecomposeChip::construct(config);
+            let value = chip.load_private(layouter.namespace(|| "witness value"), self.0.map(|v| v.evaluate()))?;
+
+            let err = chip.assign(layouter.namespace(|| "decompose value"), &value, 8 * 31).unwrap_err();
+            assert_eq!(err.region.as_deref(), Some("Decompose Region"));
+            assert!(err.offset.is_some() && err.assigning.is_some());
+            Err(err.into())
+        }
+    }
+
+    #[test]
+    fn test_error_context_from_chip() {
+        let circuit = TooManyChunks(Value::known(Fp::from(42).into()));
+        assert!(matches!(
+            MockProver::run(4, &circuit, vec![]),
+            Err(Error::NotEnoughRowsAvailable { .. })
+        ));
+    }
+}
@@ -1,2 +1,8 @@
-mod example1;
-mod example2;
\ No newline at end of file
+use crate::public_inputs::InstanceLayout;
+
+pub mod example1;
+pub mod example2;
+pub mod runtime;
+
+/// The public inputs of every Fibonacci example: the two seeds and the last term.
+pub const INSTANCES: InstanceLayout = InstanceLayout(&[&["fib_a", "fib_b", "fib_out"]]);
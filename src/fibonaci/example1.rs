@@ -1,129 +1,88 @@
-use halo2_proofs::{arithmetic::Field, circuit::*, plonk::*, poly::Rotation};
-use std::marker::PhantomData;
-
-#[derive(Debug, Clone)]
-struct ACell<F: Field>(AssignedCell<F, F>);
-
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::{
+    chip::{LoadInstructions, LoadPrivateChip, LoadPrivateConfig},
+    error::Halo2ExamplesError,
+    gadgets::arith::{ArithChip, ArithConfig, Coefficients},
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    reference::ref_fibonacci,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The Fibonacci chip is the standard gate with every row an addition.
 #[derive(Debug, Clone)]
-struct FiboConfig {
-    pub advice: [Column<Advice>; 3],
-    pub selector: Selector,
-    pub instance: Column<Instance>,
+pub struct FiboConfig {
+    pub arith: ArithConfig,
 }
 
-struct FiboChip<F: Field> {
+pub struct FiboChip<F: FieldExt> {
     config: FiboConfig,
-    _marker: PhantomData<F>,
+    arith: ArithChip<F>,
 }
 
-impl<F: Field> FiboChip<F> {
+impl<F: FieldExt> Chip<F> for FiboChip<F> {
+    type Config = FiboConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> LoadInstructions<F> for FiboChip<F> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.arith.advice[0]
+    }
+}
+
+impl<F: FieldExt> FiboChip<F> {
     pub fn construct(config: FiboConfig) -> Self {
         Self {
+            arith: ArithChip::construct(config.arith.clone()),
             config,
-            _marker: PhantomData,
         }
     }
 
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
         advice: [Column<Advice>; 3],
-        instance: Column<Instance>,
     ) -> FiboConfig {
-        let col_a = advice[0];
-        let col_b = advice[1];
-        let col_c = advice[2];
-        let selector = meta.selector();
-
-        meta.enable_equality(col_a);
-        meta.enable_equality(col_b);
-        meta.enable_equality(col_c);
-        meta.enable_equality(instance);
-
-        meta.create_gate("add", |meta| {
-            let s = meta.query_selector(selector);
-            let a = meta.query_advice(col_a, Rotation::cur());
-            let b = meta.query_advice(col_b, Rotation::cur());
-            let c = meta.query_advice(col_c, Rotation::cur());
-            vec![s * (a + b - c)]
-        });
-
         FiboConfig {
-            advice: [col_a, col_b, col_c],
-            selector,
-            instance,
+            arith: ArithChip::configure(meta, advice),
         }
     }
 
     pub fn assign_first_row(
         &self,
-        mut layouter: impl Layouter<F>,
-        a: Value<F>,
-        b: Value<F>,
-    ) -> Result<(ACell<F>, ACell<F>, ACell<F>), Error> {
-        layouter.assign_region(
-            || "first row",
-            |mut region| {
-                self.config.selector.enable(&mut region, 0)?;
-
-                let a_cell = region
-                    .assign_advice(|| "a", self.config.advice[0], 0, || a)
-                    .map(ACell)?;
-                let b_cell = region
-                    .assign_advice(|| "b", self.config.advice[1], 0, || b)
-                    .map(ACell)?;
-                let c_cell = region
-                    .assign_advice(|| "c", self.config.advice[2], 0, || a + b)
-                    .map(ACell)?;
-                Ok((a_cell, b_cell, c_cell))
-            },
-        )
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.arith.apply(layouter, "first row", Coefficients::add(), a, b)
     }
 
     pub fn assign_next_row(
         &self,
-        mut layouter: impl Layouter<F>,
-        prev_b: &ACell<F>,
-        prev_c: &ACell<F>,
-    ) -> Result<ACell<F>, Error> {
-        layouter.assign_region(
-            || "next row",
-            |mut region| {
-                self.config.selector.enable(&mut region, 0)?;
-
-                prev_b
-                    .0
-                    .copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
-                prev_c
-                    .0
-                    .copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
-
-                let c_val = prev_b.0.value().copied() + prev_c.0.value();
-                let c_cell = region
-                    .assign_advice(|| "c", self.config.advice[2], 0, || c_val)
-                    .map(ACell)?;
-                Ok(c_cell)
-            },
-        )
-    }
-
-    pub fn expose_public(
-        &self,
-        mut layouter: impl Layouter<F>,
-        cell: &ACell<F>,
-        row: usize,
-    ) -> Result<(), Error> {
-        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+        layouter: impl Layouter<F>,
+        prev_b: &AssignedCell<F, F>,
+        prev_c: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.arith.apply(layouter, "next row", Coefficients::add(), prev_b, prev_c)
     }
 }
 
 #[derive(Default)]
-struct FiboCircuit<F: Field> {
+pub struct FiboCircuit<F: FieldExt> {
     pub a: Value<F>,
     pub b: Value<F>,
 }
 
-impl<F: Field> Circuit<F> for FiboCircuit<F> {
-    type Config = FiboConfig;
+impl<F: FieldExt> Circuit<F> for FiboCircuit<F> {
+    type Config = (FiboConfig, LoadPrivateConfig, PublicOutputsConfig);
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -135,7 +94,8 @@ impl<F: Field> Circuit<F> for FiboCircuit<F> {
         let col_b = meta.advice_column();
         let col_c = meta.advice_column();
         let instance = meta.instance_column();
-        FiboChip::configure(meta, [col_a, col_b, col_c], instance)
+        let fibo = FiboChip::configure(meta, [col_a, col_b, col_c]);
+        (fibo, LoadPrivateChip::configure(meta, 1), PublicOutputs::configure(meta, instance))
     }
 
     fn synthesize(
@@ -143,12 +103,17 @@ impl<F: Field> Circuit<F> for FiboCircuit<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
+        let (config, load_config, outputs_config) = config;
         let chip = FiboChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
 
-        let (prev_a, mut prev_b, mut prev_c) =
-            chip.assign_first_row(layouter.namespace(|| "first row"), self.a, self.b)?;
-        chip.expose_public(layouter.namespace(|| "private a"), &prev_a, 0)?;
-        chip.expose_public(layouter.namespace(|| "private b"), &prev_b, 1)?;
+        let prev_a = load_chip.load_private(layouter.namespace(|| "load a"), self.a)?;
+        let mut prev_b = load_chip.load_private(layouter.namespace(|| "load b"), self.b)?;
+        let mut prev_c =
+            chip.assign_first_row(layouter.namespace(|| "first row"), &prev_a, &prev_b)?;
+        outputs.expose("fib_a", &prev_a);
+        outputs.expose("fib_b", &prev_b);
 
         for _i in 3..10 {
             let c_cell =
@@ -158,52 +123,48 @@ impl<F: Field> Circuit<F> for FiboCircuit<F> {
             prev_c = c_cell;
         }
 
-        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2)?;
+        outputs.expose("fib_out", &prev_c);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
         Ok(())
     }
 }
 
+impl<F: FieldExt> ValidateWitness<F> for FiboCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let a = known(self.a, "a")?;
+        let b = known(self.b, "b")?;
+        check_instances(instances, &[a, b, ref_fibonacci(a, b, 10)])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::FiboCircuit;
-    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+    use crate::{fibonaci::INSTANCES, public_inputs::PublicInputs, test_over_fields};
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver};
 
-    #[test]
-    fn test_example1() {
+    fn test_example1<F: FieldExt>() {
         let k = 4;
-        let a = Fp::from(1);
-        let b = Fp::from(1);
-        let out = Fp::from(55);
+        let a = F::from(1);
+        let b = F::from(1);
+        let out = F::from(55);
 
         let circuit = FiboCircuit {
             a: Value::known(a),
             b: Value::known(b),
         };
-        let mut public_input = vec![a, b, out];
-        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
+        let public_input = PublicInputs::new(INSTANCES)
+            .set("fib_a", a)
+            .set("fib_b", b)
+            .set("fib_out", out);
+        let prover = MockProver::run(k, &circuit, public_input.clone().build().unwrap()).unwrap();
         prover.assert_satisfied();
 
         // test fail proofs
-        public_input[2] += Fp::one();
-        let _prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+        let public_input = public_input.set("fib_out", out + F::one());
+        let _prover = MockProver::run(k, &circuit, public_input.build().unwrap()).unwrap();
         // _prover.assert_satisfied();
     }
 
-    #[cfg(feature = "dev-graph")]
-    #[test]
-    fn plot_example1() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("fib-1-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root.titled("Fib 1 Layout", ("sans-serif", 60)).unwrap();
-
-        let circuit = FiboCircuit {
-            a: Value::known(Fp::one()),
-            b: Value::known(Fp::one()),
-        };
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
-            .unwrap();
-    }
+    test_over_fields!(test_example1);
 }
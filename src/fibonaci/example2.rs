@@ -1,18 +1,47 @@
-use halo2_proofs::{arithmetic::Field, circuit::*, plonk::*, poly::Rotation};
+use halo2_proofs::{arithmetic::{Field, FieldExt}, circuit::*, plonk::*, poly::Rotation};
 use std::marker::PhantomData;
 
+use super::INSTANCES;
+use crate::{
+    chip::LoadInstructions,
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    reference::ref_fibonacci,
+    validate::{check_instances, InvalidWitness, ValidateWitness},
+};
+
 #[derive(Debug, Clone)]
-struct FiboConfig {
+pub struct FiboConfig {
     pub advice: Column<Advice>,
     pub selector: Selector,
     pub instance: Column<Instance>,
 }
 
-struct FiboChip<F: Field> {
+pub struct FiboChip<F: Field> {
     config: FiboConfig,
     _marker: PhantomData<F>,
 }
 
+impl<F: FieldExt> Chip<F> for FiboChip<F> {
+    type Config = FiboConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> LoadInstructions<F> for FiboChip<F> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.advice
+    }
+}
+
 impl<F: Field> FiboChip<F> {
     pub fn construct(config: FiboConfig) -> Self {
         Self {
@@ -42,7 +71,7 @@ impl<F: Field> FiboChip<F> {
             let a = meta.query_advice(advice, Rotation::cur());
             let b = meta.query_advice(advice, Rotation::next());
             let c = meta.query_advice(advice, Rotation(2));
-            vec![s * (a + b - c)]
+            GateBuilder::with_selector(s).constrain("a + b = c", a + b - c)
         });
 
         FiboConfig {
@@ -56,7 +85,7 @@ impl<F: Field> FiboChip<F> {
         &self,
         mut layouter: impl Layouter<F>,
         rows: usize,
-    ) -> Result<AssignedCell<F,F>, Error> {
+    ) -> Result<AssignedCell<F,F>, Halo2ExamplesError> {
         layouter.assign_region(
             || "fibonaci table",
             |mut region| {
@@ -66,14 +95,14 @@ impl<F: Field> FiboChip<F> {
                 let mut a_cell = region.assign_advice_from_instance(
                     || "a", 
                     self.config.instance, 
-                    0, 
+                    INSTANCES.row("fib_a"), 
                     self.config.advice, 
                     0,
                 )?;
                 let mut b_cell = region.assign_advice_from_instance(
                     || "b", 
                     self.config.instance, 
-                    1, 
+                    INSTANCES.row("fib_b"), 
                     self.config.advice, 
                     1, 
                 )?;
@@ -91,24 +120,15 @@ impl<F: Field> FiboChip<F> {
             
                 Ok(b_cell)
             },
-        )
-    }
-
-    pub fn expose_public(
-        &self,
-        mut layouter: impl Layouter<F>,
-        cell: AssignedCell<F,F>,
-        row: usize,
-    ) -> Result<(), Error> {
-        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+        ).in_chip("FiboChip", "fibonaci table")
     }
 }
 
 #[derive(Default)]
-struct FiboCircuit<F: Field>(PhantomData<F>);
+pub struct FiboCircuit<F: Field>(pub PhantomData<F>);
 
 impl<F: Field> Circuit<F> for FiboCircuit<F> {
-    type Config = FiboConfig;
+    type Config = (FiboConfig, PublicOutputsConfig);
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -118,7 +138,8 @@ impl<F: Field> Circuit<F> for FiboCircuit<F> {
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
         let advice_col = meta.advice_column();
         let instance = meta.instance_column();
-        FiboChip::configure(meta, advice_col, instance)
+        let fibo = FiboChip::configure(meta, advice_col, instance);
+        (fibo, PublicOutputs::configure(meta, instance))
     }
 
     fn synthesize(
@@ -126,20 +147,40 @@ impl<F: Field> Circuit<F> for FiboCircuit<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
+        let (config, outputs_config) = config;
         let chip = FiboChip::construct(config);
+        let mut outputs = PublicOutputs::construct(outputs_config).starting_at(INSTANCES.row("fib_out"));
 
         let out_cell = chip.assign(layouter.namespace(|| "fibonaci table"), 10)?;
 
-        chip.expose_public(layouter.namespace(|| "out"), out_cell, 2)?;
+        outputs.expose("fib_out", &out_cell);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
         Ok(())
     }
 }
 
+impl<F: FieldExt> ValidateWitness<F> for FiboCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        // the seeds are read from the instance column, so only the output can be inconsistent
+        let seeds = instances
+            .first()
+            .filter(|column| column.len() >= 2)
+            .ok_or(InvalidWitness::WrongInstanceCount {
+                expected: 3,
+                found: instances.iter().map(Vec::len).sum(),
+            })?;
+        let (a, b) = (seeds[0], seeds[1]);
+        let out = ref_fibonacci(a, b, 10);
+        check_instances(instances, &[a, b, out])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::FiboCircuit;
     use std::marker::PhantomData;
     use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use crate::dev::rotation::{assert_no_wrapping_queries, rotation_report};
 
     #[test]
     fn test_example2() {
@@ -153,24 +194,16 @@ mod tests {
         let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
         prover.assert_satisfied();
 
+        // "add" reads two rows ahead, so the selector must stay off the last two usable rows
+        let report = rotation_report::<Fp, FiboCircuit<Fp>>(k);
+        let usable = report.usable_rows;
+        assert_eq!((report.gates[0].min, report.gates[0].max), (0, 2));
+        assert_eq!(report.gates[0].unusable_rows(usable), vec![usable - 2, usable - 1]);
+        assert_no_wrapping_queries(k, &circuit);
+
         // test fail proofs
         public_input[2] += Fp::one();
         let _prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
         // _prover.assert_satisfied();
     }
-
-    #[cfg(feature = "dev-graph")]
-    #[test]
-    fn plot_example2() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("fib-2-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root.titled("Fib 2 Layout", ("sans-serif", 60)).unwrap();
-
-        let circuit = FiboCircuit::<Fp>(PhantomData);
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
-            .unwrap();
-    }
 }
@@ -0,0 +1,112 @@
+use halo2_proofs::{arithmetic::{Field, FieldExt}, circuit::*, plonk::*};
+use std::marker::PhantomData;
+
+use super::{example2::{FiboChip, FiboConfig}, INSTANCES};
+use crate::{
+    params::FibonacciParams,
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    reference::ref_fibonacci,
+    validate::{check_instances, InvalidWitness, ValidateWitness},
+};
+
+/// The single-column Fibonacci circuit of example2, with the number of rows taken from
+/// `FibonacciParams` instead of being fixed at 10.
+///
+/// The constraint system does not depend on the number of rows, only the assignment does,
+/// so one binary can prove any length that fits in `2^k` rows.
+pub struct RuntimeFiboCircuit<F: Field> {
+    pub params: FibonacciParams,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> RuntimeFiboCircuit<F> {
+    pub fn new(params: FibonacciParams) -> Self {
+        Self {
+            params,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: Field> Circuit<F> for RuntimeFiboCircuit<F> {
+    type Config = (FiboConfig, PublicOutputsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.params.clone())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice_col = meta.advice_column();
+        let instance = meta.instance_column();
+        let fibo = FiboChip::configure(meta, advice_col, instance);
+        (fibo, PublicOutputs::configure(meta, instance))
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (config, outputs_config) = config;
+        let chip = FiboChip::construct(config);
+        let mut outputs = PublicOutputs::construct(outputs_config).starting_at(INSTANCES.row("fib_out"));
+
+        let out_cell = chip.assign(layouter.namespace(|| "fibonaci table"), self.params.rows)?;
+
+        outputs.expose("fib_out", &out_cell);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ValidateWitness<F> for RuntimeFiboCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        self.params
+            .validate()
+            .map_err(|err| InvalidWitness::InvalidParameter(err.to_string()))?;
+
+        // the seeds are read from the instance column, so only the output can be inconsistent
+        let seeds = instances
+            .first()
+            .filter(|column| column.len() >= 2)
+            .ok_or(InvalidWitness::WrongInstanceCount {
+                expected: 3,
+                found: instances.iter().map(Vec::len).sum(),
+            })?;
+        let (a, b) = (seeds[0], seeds[1]);
+        let out = ref_fibonacci(a, b, self.params.rows);
+        check_instances(instances, &[a, b, out])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RuntimeFiboCircuit;
+    use crate::params::CircuitParams;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn fibonacci(rows: usize) -> u64 {
+        (2..rows).fold((1u64, 1u64), |(a, b), _| (b, a + b)).1
+    }
+
+    #[test]
+    fn test_runtime_fibonacci() {
+        for (k, rows) in [(4, 10), (5, 20), (6, 50)] {
+            let params = CircuitParams::from_toml(&format!("[fibonacci]\nk = {}\nrows = {}", k, rows))
+                .unwrap()
+                .fibonacci;
+
+            let circuit = RuntimeFiboCircuit::<Fp>::new(params.clone());
+            let public_input = vec![Fp::from(1), Fp::from(1), Fp::from(fibonacci(rows))];
+            let prover = MockProver::run(params.k, &circuit, vec![public_input]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // wrong output for the number of rows
+        let circuit = RuntimeFiboCircuit::<Fp>::new(CircuitParams::default().fibonacci);
+        let public_input = vec![Fp::from(1), Fp::from(1), Fp::from(fibonacci(11))];
+        let prover = MockProver::run(4, &circuit, vec![public_input]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
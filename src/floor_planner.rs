@@ -0,0 +1,250 @@
+//! Choosing the floor planner of an example circuit, and a small planner of our own.
+//!
+//! Every example fixes its planner in `Circuit::FloorPlanner`, `SimpleFloorPlanner` for most
+//! and `V1` for the decompose circuits. `WithPlanner` lays the same circuit out with another
+//! one, so their layouts can be compared with `dev::layout_diff` or rendered with
+//! `dev::layout`.
+//!
+//! `ShiftedPlanner` shows what a floor planner is responsible for. `halo2_proofs` keeps the
+//! region and cell types a planner builds on crate-private, so a planner written outside of
+//! it has to let another one place the regions; this one then moves the result down by a
+//! number of rows.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    plonk::*,
+};
+
+/// `C`, laid out by `P` instead of its own floor planner.
+pub struct WithPlanner<C, P> {
+    pub circuit: C,
+    _planner: PhantomData<P>,
+}
+
+impl<C, P> WithPlanner<C, P> {
+    pub fn new(circuit: C) -> Self {
+        Self {
+            circuit,
+            _planner: PhantomData,
+        }
+    }
+}
+
+impl<F: Field, C: Circuit<F>, P: FloorPlanner> Circuit<F> for WithPlanner<C, P> {
+    type Config = C::Config;
+    type FloorPlanner = P;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.circuit.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.circuit.synthesize(config, layouter)
+    }
+}
+
+/// Lays the circuit out with `P`, then moves every assignment `ROWS` rows down.
+///
+/// Gates and lookups only refer to rows relative to the one they are checked on, so the
+/// circuit stays satisfied wherever it is placed, as long as it is moved as a whole. The
+/// exception is the instance columns: public inputs are numbered from the first row of the
+/// proof, so instance rows are left where they are, while the advice cells copied to and
+/// from them move. The rows skipped at the top are left unassigned, which is zero, and so
+/// are the first `ROWS` rows of any lookup table.
+pub struct ShiftedPlanner<P, const ROWS: usize>(PhantomData<P>);
+
+impl<P: FloorPlanner, const ROWS: usize> FloorPlanner for ShiftedPlanner<P, ROWS> {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let mut cs = ShiftedAssignment::<_, ROWS>(cs);
+        P::synthesize(&mut cs, circuit, config, constants)
+    }
+}
+
+/// Forwards everything to the wrapped `Assignment`, with every row outside the instance
+/// columns moved down by `ROWS`.
+struct ShiftedAssignment<'a, CS, const ROWS: usize>(&'a mut CS);
+
+fn shift<const ROWS: usize>(column: Column<Any>, row: usize) -> usize {
+    match column.column_type() {
+        Any::Instance => row,
+        _ => row + ROWS,
+    }
+}
+
+impl<'a, F: Field, CS: Assignment<F>, const ROWS: usize> Assignment<F> for ShiftedAssignment<'a, CS, ROWS> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.0.enter_region(name_fn)
+    }
+
+    fn exit_region(&mut self) {
+        self.0.exit_region()
+    }
+
+    fn enable_selector<A, AR>(&mut self, annotation: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.0.enable_selector(annotation, selector, row + ROWS)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.0.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.0.assign_advice(annotation, column, row + ROWS, to)
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.0.assign_fixed(annotation, column, row + ROWS, to)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.0.copy(
+            left_column,
+            shift::<ROWS>(left_column, left_row),
+            right_column,
+            shift::<ROWS>(right_column, right_row),
+        )
+    }
+
+    fn fill_from_row(&mut self, column: Column<Fixed>, row: usize, to: Value<Assigned<F>>) -> Result<(), Error> {
+        self.0.fill_from_row(column, row + ROWS, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.0.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.0.pop_namespace(gadget_name)
+    }
+}
+
+#[cfg(all(test, feature = "fibonacci", feature = "decompose"))]
+mod test {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+    };
+
+    use super::{ShiftedPlanner, WithPlanner};
+    use crate::{
+        decompose::decompose_range_check::DecomposeRangeCheckCircuit,
+        dev::layout_diff::{layout_diff, layout_stats},
+        fibonaci::example1::FiboCircuit,
+    };
+
+    fn fibonacci() -> FiboCircuit<Fp> {
+        FiboCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        }
+    }
+
+    fn decompose() -> DecomposeRangeCheckCircuit<Fp, 8, 256> {
+        DecomposeRangeCheckCircuit {
+            value: Value::known(Fp::from(0x1234_5678_9abc_def0u64).into()),
+            num_bits: 64,
+        }
+    }
+
+    #[test]
+    fn test_with_planner() {
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        let prover = MockProver::run(4, &WithPlanner::<_, V1>::new(fibonacci()), instances).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(9, &WithPlanner::<_, SimpleFloorPlanner>::new(decompose()), vec![]).unwrap();
+        prover.assert_satisfied();
+
+        // the circuit's own planner gives the circuit's own layout
+        assert_eq!(layout_stats(&WithPlanner::<_, V1>::new(decompose())), layout_stats(&decompose()));
+    }
+
+    #[test]
+    fn test_shifted_planner() {
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        let shifted = WithPlanner::<_, ShiftedPlanner<SimpleFloorPlanner, 3>>::new(fibonacci());
+        let prover = MockProver::run(4, &shifted, instances.clone()).unwrap();
+        prover.assert_satisfied();
+        assert_eq!(layout_stats(&shifted).rows, layout_stats(&fibonacci()).rows + 3);
+
+        // public inputs stay on their rows, so a wrong one is still caught
+        let mut wrong = instances;
+        wrong[0][2] += Fp::one();
+        assert!(MockProver::run(4, &shifted, wrong).unwrap().verify().is_err());
+
+        let shifted = WithPlanner::<_, ShiftedPlanner<V1, 5>>::new(decompose());
+        let prover = MockProver::run(9, &shifted, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_planner_rows() {
+        let diff = layout_diff(
+            "V1",
+            &decompose(),
+            "SimpleFloorPlanner",
+            &WithPlanner::<_, SimpleFloorPlanner>::new(decompose()),
+        );
+
+        // both place the same regions, only where they go differs
+        let (_, v1) = &diff.left;
+        let (_, simple) = &diff.right;
+        assert_eq!(v1.cells, simple.cells, "{}", diff);
+        assert_eq!(v1.regions.len(), simple.regions.len(), "{}", diff);
+    }
+}
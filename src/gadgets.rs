@@ -1,2 +1,47 @@
-mod is_zero;
-mod is_equal;
\ No newline at end of file
+pub mod is_zero;
+pub mod is_equal;
+pub mod word;
+pub mod word64;
+pub mod add_many;
+pub mod bits;
+pub mod popcount;
+pub mod byte_order;
+pub mod signed;
+pub mod min_max;
+#[cfg(feature = "hash")]
+pub mod commit;
+pub mod select;
+pub mod arith;
+pub mod num;
+pub mod running_product;
+pub mod horner;
+pub mod inner_product;
+pub mod div_rem;
+pub mod lagrange;
+pub mod dp_grid;
+#[cfg(feature = "hash")]
+pub mod multiset;
+#[cfg(feature = "vm")]
+pub mod memory;
+pub mod padding;
+#[cfg(feature = "ecc")]
+pub mod ecc;
+#[cfg(feature = "ecc")]
+pub mod hash_to_curve;
+#[cfg(feature = "ecc")]
+pub mod scalar_mul;
+#[cfg(feature = "merkle")]
+pub mod merkle;
+#[cfg(feature = "merkle")]
+pub mod smt;
+#[cfg(feature = "merkle")]
+pub mod incremental_merkle;
+#[cfg(feature = "hash")]
+pub mod hasher;
+#[cfg(feature = "hash")]
+pub mod mimc;
+#[cfg(feature = "hash")]
+pub mod vector_commit;
+pub mod aes;
+pub mod chacha;
+pub mod sha256;
\ No newline at end of file
@@ -0,0 +1,290 @@
+//! The sum of many 32-bit words, with one range check at the end instead of one per addition.
+//!
+//! `U32Chip::add` range checks the sum of every pair it adds, so summing N words with it
+//! costs N - 1 carries and N - 1 decomposed results. Since N 32-bit words sum to less than
+//! 2^64, far below the field modulus, the running total can just as well grow unreduced:
+//! every row adds one word to it, and only the final total is split into the 32-bit sum and
+//! the carry, both range checked as words.
+//!
+//!        word   |   acc     |  selector
+//!       -------------------------------
+//!               |    0      |
+//!         w_0   |   acc_1   |  q_acc      acc_i = acc_{i-1} + w_{i-1}
+//!         ...   |   ...     |  q_acc
+//!       w_{N-1} |   acc_N   |  q_acc
+//!         sum   |   carry   |  q_final    acc_N = sum + carry * 2^32
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::word::{U32Chip, U32Config, U32};
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+};
+
+#[derive(Debug, Clone)]
+pub struct AddManyConfig {
+    word: Column<Advice>,
+    acc: Column<Advice>,
+    q_acc: Selector,
+    q_final: Selector,
+    words: U32Config,
+    constants: ConstantsConfig,
+}
+
+pub struct AddManyChip<F: FieldExt> {
+    config: AddManyConfig,
+    words: U32Chip<F>,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for AddManyChip<F> {
+    type Config = AddManyConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> AddManyChip<F> {
+    pub fn construct(config: AddManyConfig) -> Self {
+        let words = U32Chip::construct(config.words.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, words, constants }
+    }
+
+    /// Sets up the `U32Chip` the inputs are witnessed with and the result is range checked
+    /// with. The accumulator's initial zero is a constant loaded into the `acc` column.
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> AddManyConfig {
+        let words = U32Chip::configure(meta, instance);
+        let word = meta.advice_column();
+        let acc = meta.advice_column();
+        let q_acc = meta.selector();
+        let q_final = meta.selector();
+
+        let constants = ConstantsChip::configure_with(meta, acc);
+        meta.enable_equality(word);
+
+        meta.create_gate("add many", |meta| {
+            let q_acc = meta.query_selector(q_acc);
+            let word = meta.query_advice(word, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            GateBuilder::with_selector(q_acc).equal("acc = acc_prev + word", acc, acc_prev + word)
+        });
+
+        meta.create_gate("add many carry", |meta| {
+            let q_final = meta.query_selector(q_final);
+            let total = meta.query_advice(acc, Rotation::prev());
+            let sum = meta.query_advice(word, Rotation::cur());
+            let carry = meta.query_advice(acc, Rotation::cur());
+
+            GateBuilder::with_selector(q_final).equal("total = sum + carry * 2^32", total, sum + carry * F::from(1u64 << 32))
+        });
+
+        AddManyConfig {
+            word,
+            acc,
+            q_acc,
+            q_final,
+            words,
+            constants,
+        }
+    }
+
+    /// The chip the words to sum are witnessed with.
+    pub fn words(&self) -> &U32Chip<F> {
+        &self.words
+    }
+
+    /// `words` summed modulo 2^32, and the carry, i.e. how many times the sum wrapped.
+    pub fn add_many(
+        &self,
+        mut layouter: impl Layouter<F>,
+        words: &[U32<F>],
+    ) -> Result<(U32<F>, U32<F>), Halo2ExamplesError> {
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", F::zero())?;
+        let (sum, carry) = layouter
+            .assign_region(
+                || "add many",
+                |mut region| {
+                    zero.copy_advice(|| "acc_0", &mut region, self.config.acc, 0)?;
+
+                    let mut total = Value::known(0u64);
+                    for (i, word) in words.iter().enumerate() {
+                        let offset = i + 1;
+                        self.config.q_acc.enable(&mut region, offset)?;
+                        word.word.copy_advice(|| format!("w_{}", i), &mut region, self.config.word, offset)?;
+                        total = total.zip(word.value()).map(|(total, word)| total + word as u64);
+                        region.assign_advice(|| format!("acc_{}", offset), self.config.acc, offset, || total.map(F::from))?;
+                    }
+
+                    let offset = words.len() + 1;
+                    self.config.q_final.enable(&mut region, offset)?;
+                    let sum = region.assign_advice(|| "sum", self.config.word, offset, || {
+                        total.map(|total| F::from(total & u32::MAX as u64))
+                    })?;
+                    let carry = region.assign_advice(|| "carry", self.config.acc, offset, || total.map(|total| F::from(total >> 32)))?;
+                    Ok((sum, carry))
+                },
+            )
+            .in_chip("AddManyChip", "add many")?;
+
+        let sum = self.words.range_check(layouter.namespace(|| "sum"), &sum)?;
+        let carry = self.words.range_check(layouter.namespace(|| "carry"), &carry)?;
+        Ok((sum, carry))
+    }
+}
+
+/// Sums `words` with `AddManyChip` and exposes the sum and the carry.
+#[derive(Default)]
+pub struct AddManyCircuit {
+    pub words: Vec<Value<u32>>,
+}
+
+impl AddManyCircuit {
+    /// The sum of `words` modulo 2^32, and the carry.
+    pub fn outputs(words: &[u32]) -> [u32; 2] {
+        let total: u64 = words.iter().map(|word| *word as u64).sum();
+        [total as u32, (total >> 32) as u32]
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for AddManyCircuit {
+    type Config = AddManyConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            words: vec![Value::unknown(); self.words.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        AddManyChip::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = AddManyChip::construct(config);
+        chip.words().load_tables(&mut layouter)?;
+
+        let words = self
+            .words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| chip.words().assign(layouter.namespace(|| format!("w_{}", i)), *word))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (sum, carry) = chip.add_many(layouter.namespace(|| "add many"), &words)?;
+
+        chip.words().expose_public(layouter.namespace(|| "sum"), &sum, 0)?;
+        chip.words().expose_public(layouter.namespace(|| "carry"), &carry, 1)?;
+        Ok(())
+    }
+}
+
+/// The baseline `AddManyCircuit` is measured against: the same sum as a chain of
+/// `U32Chip::add`, with the carries counted by adding them up the same way.
+#[derive(Default)]
+pub struct ChainedAddCircuit {
+    pub words: Vec<Value<u32>>,
+}
+
+impl<F: FieldExt> Circuit<F> for ChainedAddCircuit {
+    type Config = U32Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            words: vec![Value::unknown(); self.words.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        U32Chip::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = U32Chip::construct(config);
+        chip.load_tables(&mut layouter)?;
+
+        let mut sum = chip.assign(layouter.namespace(|| "w_0"), self.words[0])?;
+        let mut carry = chip.assign(layouter.namespace(|| "carry"), Value::known(0))?;
+        for (i, word) in self.words.iter().enumerate().skip(1) {
+            let word = chip.assign(layouter.namespace(|| format!("w_{}", i)), *word)?;
+            let (next, overflow) = chip.add_with_carry(layouter.namespace(|| "add"), &sum, &word)?;
+            let overflow = chip.range_check(layouter.namespace(|| "carry out"), &overflow)?;
+            carry = chip.add(layouter.namespace(|| "count carry"), &carry, &overflow)?;
+            sum = next;
+        }
+
+        chip.expose_public(layouter.namespace(|| "sum"), &sum, 0)?;
+        chip.expose_public(layouter.namespace(|| "carry"), &carry, 1)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::{AddManyCircuit, ChainedAddCircuit};
+    use crate::dev::rotation::assert_no_wrapping_queries;
+
+    const K: u32 = 17;
+
+    fn words(n: u32) -> Vec<u32> {
+        (0..n).map(|i| u32::MAX - i * 0x0101_0101).collect()
+    }
+
+    fn instances(words: &[u32]) -> Vec<Vec<Fp>> {
+        vec![AddManyCircuit::outputs(words).iter().map(|output| Fp::from(*output as u64)).collect()]
+    }
+
+    #[test]
+    fn test_add_many() {
+        for n in [1, 2, 16] {
+            let words = words(n);
+            let circuit = AddManyCircuit {
+                words: words.iter().copied().map(Value::known).collect(),
+            };
+
+            let prover = MockProver::run(K, &circuit, instances(&words)).unwrap();
+            prover.assert_satisfied();
+            // both gates read the accumulator a row back
+            assert_no_wrapping_queries(K, &circuit);
+        }
+    }
+
+    #[test]
+    fn test_add_many_wrong_carry() {
+        let words = words(16);
+        let circuit = AddManyCircuit {
+            words: words.iter().copied().map(Value::known).collect(),
+        };
+
+        let mut wrong = instances(&words);
+        wrong[0][1] -= Fp::one();
+        let prover = MockProver::run(K, &circuit, wrong).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_chained_add_agrees() {
+        let words = words(16);
+        let circuit = ChainedAddCircuit {
+            words: words.iter().copied().map(Value::known).collect(),
+        };
+
+        let prover = MockProver::run(K, &circuit, instances(&words)).unwrap();
+        prover.assert_satisfied();
+    }
+}
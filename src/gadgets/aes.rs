@@ -0,0 +1,250 @@
+//! The byte operations of AES, each a lookup: the S-box, `xtime` (multiplication by `x` in
+//! `GF(2^8)`, from which `MixColumns` is built) and xor, for `AddRoundKey` and `MixColumns`.
+//!
+//! The three share one tagged table, so a circuit pays for a single lookup argument. Every
+//! operation is a row whose fixed `tag` says which part of the table it is looked up in, and
+//! the table starts with an all-zero row for the rows that look nothing up:
+//!
+//!       a   |   b   |  out  |  tag  |  q_lookup          tag  |  lhs  |  rhs  |  out
+//!     ---------------------------------------------      ----------------------------
+//!       x   |   y   | x ^ y |   1   |     1                0  |   0   |   0   |   0
+//!       x   |       | S(x)  |   2   |     1                1  |   a   |   b   | a ^ b     (2^16 rows)
+//!       x   |       | 2 * x |   3   |     1                2  |   a   |   0   |  S(a)     (2^8 rows)
+//!                                                          3  |   a   |   0   | 2 * a     (2^8 rows)
+//!
+//! Every operand is looked up as a byte, so cells out of this chip are bytes whatever the
+//! prover assigned to the cells that went in. The xor part makes the table 2^16 + 513 rows
+//! long, so the circuit needs `k >= 17`.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::LoadInstructions,
+    error::{Halo2ExamplesError, ResultExt},
+    reference::aes::{sbox, xtime},
+    table::FixedTableConfig,
+};
+
+const XOR: u64 = 1;
+const SBOX: u64 = 2;
+const XTIME: u64 = 3;
+
+const TABLE_ROWS: usize = 1 + (1 << 16) + 2 * (1 << 8);
+
+// `[tag, lhs, rhs, out]` of row `i` of the table
+fn table_row(i: usize) -> [u64; 4] {
+    match i {
+        0 => [0; 4],
+        i if i <= 1 << 16 => {
+            let (a, b) = ((i - 1) >> 8, (i - 1) & 0xff);
+            [XOR, a as u64, b as u64, (a ^ b) as u64]
+        }
+        i if i <= (1 << 16) + (1 << 8) => {
+            let a = (i - 1 - (1 << 16)) as u8;
+            [SBOX, a as u64, 0, sbox(a) as u64]
+        }
+        i => {
+            let a = (i - 1 - (1 << 16) - (1 << 8)) as u8;
+            [XTIME, a as u64, 0, xtime(a) as u64]
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AesConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 3],
+    tag: Column<Fixed>,
+    q_lookup: Selector,
+    table: [FixedTableConfig<F>; 4],
+}
+
+pub struct AesChip<F: FieldExt> {
+    config: AesConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Chip<F> for AesChip<F> {
+    type Config = AesConfig<F>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> LoadInstructions<F> for AesChip<F> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.advice[0]
+    }
+}
+
+impl<F: FieldExt> AesChip<F> {
+    pub fn construct(config: AesConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> AesConfig<F> {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let tag = meta.fixed_column();
+        let q_lookup = meta.complex_selector();
+        let table = ["aes tag", "aes lhs", "aes rhs", "aes out"].map(|name| FixedTableConfig::configure(meta, name));
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.lookup(|meta| {
+            let q_lookup = meta.query_selector(q_lookup);
+            let tag = meta.query_fixed(tag, Rotation::cur());
+            let [a, b, out] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+
+            [tag, a, b, out]
+                .into_iter()
+                .zip(table.iter())
+                .map(|(input, table)| (q_lookup.clone() * input, table.column))
+                .collect()
+        });
+
+        AesConfig {
+            advice,
+            tag,
+            q_lookup,
+            table,
+        }
+    }
+
+    /// Loads the table, computed once per process.
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Halo2ExamplesError> {
+        for (i, table) in self.config.table.iter().enumerate() {
+            table
+                .load_with(layouter, TABLE_ROWS, |row| F::from(table_row(row)[i]))
+                .in_chip("AesChip", "load table")?;
+        }
+        Ok(())
+    }
+
+    pub fn xor(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.lookup_row(layouter, "xor", XOR, a, Some(b), |a, b| a ^ b)
+    }
+
+    /// `S(a)`, the `SubBytes` of one byte.
+    pub fn sub_byte(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.lookup_row(layouter, "sub byte", SBOX, a, None, |a, _| sbox(a))
+    }
+
+    /// `2 * a` in `GF(2^8)`.
+    pub fn xtime(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.lookup_row(layouter, "xtime", XTIME, a, None, |a, _| xtime(a))
+    }
+
+    // One row looked up under `tag`, with `a` and `b` copied in, `b` left 0 if there is none.
+    fn lookup_row(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &'static str,
+        tag: u64,
+        a: &AssignedCell<F, F>,
+        b: Option<&AssignedCell<F, F>>,
+        op: impl Fn(u8, u8) -> u8,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let config = &self.config;
+        let [col_a, col_b, col_out] = config.advice;
+        let byte = |cell: &AssignedCell<F, F>| cell.value().map(|value| value.get_lower_128() as u8);
+        let out = byte(a)
+            .zip(b.map_or(Value::known(0), byte))
+            .map(|(a, b)| F::from(op(a, b) as u64));
+
+        layouter
+            .assign_region(
+                || name,
+                |mut region| {
+                    config.q_lookup.enable(&mut region, 0)?;
+                    region.assign_fixed(|| "tag", config.tag, 0, || Value::known(F::from(tag)))?;
+                    a.copy_advice(|| "a", &mut region, col_a, 0)?;
+                    match b {
+                        Some(b) => b.copy_advice(|| "b", &mut region, col_b, 0).map(|_| ())?,
+                        None => region.assign_advice(|| "b", col_b, 0, || Value::known(F::zero())).map(|_| ())?,
+                    }
+                    region.assign_advice(|| "out", col_out, 0, || out)
+                },
+            )
+            .in_chip("AesChip", name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{table_row, AesChip, AesConfig, TABLE_ROWS};
+    use crate::chip::LoadInstructions;
+
+    #[test]
+    fn test_table_rows() {
+        assert_eq!(table_row(0), [0; 4]);
+        assert_eq!(table_row(1 + 0x1234), [1, 0x12, 0x34, 0x26]);
+        assert_eq!(table_row(1 + (1 << 16) + 0x53), [2, 0x53, 0, 0xed]);
+        assert_eq!(table_row(TABLE_ROWS - 1), [3, 0xff, 0, 0xe5]);
+    }
+
+    /// `S(a ^ b)` and `2 * S(a ^ b)`, checked against `out`.
+    struct ByteCircuit {
+        a: u64,
+        b: u64,
+        out: [u64; 2],
+    }
+
+    impl Circuit<Fp> for ByteCircuit {
+        type Config = AesConfig<Fp>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { a: 0, b: 0, out: [0; 2] }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            AesChip::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = AesChip::construct(config);
+            chip.load_table(&mut layouter)?;
+
+            let a = chip.load_private(layouter.namespace(|| "a"), Value::known(Fp::from(self.a)))?;
+            let b = chip.load_private(layouter.namespace(|| "b"), Value::known(Fp::from(self.b)))?;
+            let sum = chip.xor(layouter.namespace(|| "a ^ b"), &a, &b)?;
+            let sub = chip.sub_byte(layouter.namespace(|| "S(a ^ b)"), &sum)?;
+            let double = chip.xtime(layouter.namespace(|| "2 * S(a ^ b)"), &sub)?;
+
+            for (cell, expected) in [sub, double].iter().zip(self.out) {
+                cell.value().assert_if_known(|value| value.get_lower_128() == expected as u128);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_aes_bytes() {
+        // S(0x19) = 0xd4, 2 * 0xd4 = 0xb3
+        let circuit = ByteCircuit { a: 0x32, b: 0x2b, out: [0xd4, 0xb3] };
+        MockProver::run(17, &circuit, vec![]).unwrap().assert_satisfied();
+
+        // 0x100 is not a byte, even if its low byte is
+        let circuit = ByteCircuit { a: 0x132, b: 0x2b, out: [0xd4, 0xb3] };
+        assert!(MockProver::run(17, &circuit, vec![]).unwrap().verify().is_err());
+    }
+}
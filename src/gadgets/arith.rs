@@ -0,0 +1,328 @@
+//! The PLONK standard gate: one gate whose fixed coefficients pick, row by row, which of
+//! addition, subtraction, multiplication, scaling or an equality check the row performs:
+//!
+//!     q * (s_m * a * b + s_a * a + s_b * b + s_c * c + s_const) = 0
+//!
+//!       a   |   b   |   c   | s_m | s_a | s_b | s_c | s_const |  q
+//!     ----------------------------------------------------------------
+//!       x   |   y   | x + y |  0  |  1  |  1  | -1  |    0    |  1
+//!       x   |   y   | x * y |  1  |  0  |  0  | -1  |    0    |  1
+//!       x   |       | k * x |  0  |  k  |  0  | -1  |    0    |  1
+//!       x   |   y   |       |  0  |  1  | -1  |  0  |    0    |  1     (x == y)
+//!       x   |  1/x  |       |  1  |  0  |  0  |  0  |   -1    |  1     (x * y == 1)
+//!
+//! Chips that only need such rows take an `ArithConfig` instead of creating gates of their
+//! own, so a circuit combining several of them pays for the gate and its columns once.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::LoadInstructions,
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+};
+
+/// The coefficients of one row of the gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coefficients<F> {
+    pub s_m: F,
+    pub s_a: F,
+    pub s_b: F,
+    pub s_c: F,
+    pub s_const: F,
+}
+
+impl<F: FieldExt> Coefficients<F> {
+    fn zero() -> Self {
+        Self {
+            s_m: F::zero(),
+            s_a: F::zero(),
+            s_b: F::zero(),
+            s_c: F::zero(),
+            s_const: F::zero(),
+        }
+    }
+
+    /// `c = a + b`
+    pub fn add() -> Self {
+        Self {
+            s_a: F::one(),
+            s_b: F::one(),
+            s_c: -F::one(),
+            ..Self::zero()
+        }
+    }
+
+    /// `c = a - b`
+    pub fn sub() -> Self {
+        Self {
+            s_a: F::one(),
+            s_b: -F::one(),
+            s_c: -F::one(),
+            ..Self::zero()
+        }
+    }
+
+    /// `c = a * b`
+    pub fn mul() -> Self {
+        Self {
+            s_m: F::one(),
+            s_c: -F::one(),
+            ..Self::zero()
+        }
+    }
+
+    /// `c = k * a`, `b` is ignored.
+    pub fn scale(k: F) -> Self {
+        Self {
+            s_a: k,
+            s_c: -F::one(),
+            ..Self::zero()
+        }
+    }
+
+    /// `c = a + k`, `b` is ignored.
+    pub fn add_constant(k: F) -> Self {
+        Self {
+            s_a: F::one(),
+            s_c: -F::one(),
+            s_const: k,
+            ..Self::zero()
+        }
+    }
+
+    /// `a == b`, `c` is left 0.
+    pub fn equal() -> Self {
+        Self {
+            s_a: F::one(),
+            s_b: -F::one(),
+            ..Self::zero()
+        }
+    }
+
+    /// `a * b == 1`, `c` is left 0.
+    pub fn inverse() -> Self {
+        Self {
+            s_m: F::one(),
+            s_const: -F::one(),
+            ..Self::zero()
+        }
+    }
+
+    /// The `c` that satisfies the row, 0 if `c` is not constrained.
+    fn output(&self, a: F, b: F) -> F {
+        let rest = self.s_m * a * b + self.s_a * a + self.s_b * b + self.s_const;
+        -rest * self.s_c.invert().unwrap_or(F::zero())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArithConfig {
+    pub advice: [Column<Advice>; 3],
+    s_m: Column<Fixed>,
+    s_a: Column<Fixed>,
+    s_b: Column<Fixed>,
+    s_c: Column<Fixed>,
+    s_const: Column<Fixed>,
+    q: Selector,
+}
+
+pub struct ArithChip<F: FieldExt> {
+    config: ArithConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Chip<F> for ArithChip<F> {
+    type Config = ArithConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> LoadInstructions<F> for ArithChip<F> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.advice[0]
+    }
+}
+
+impl<F: FieldExt> ArithChip<F> {
+    pub fn construct(config: ArithConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates the gate over `advice`, enabling equality on the three columns.
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> ArithConfig {
+        let [s_m, s_a, s_b, s_c, s_const] = [(); 5].map(|_| meta.fixed_column());
+        let q = meta.selector();
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("arith", |meta| {
+            let q = meta.query_selector(q);
+            let [a, b, c] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let [s_m, s_a, s_b, s_c, s_const] = [s_m, s_a, s_b, s_c, s_const].map(|column| meta.query_fixed(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q).constrain("s_m * a * b + s_a * a + s_b * b + s_c * c + s_const = 0", s_m * a.clone() * b.clone() + s_a * a + s_b * b + s_c * c + s_const)
+        });
+
+        ArithConfig {
+            advice,
+            s_m,
+            s_a,
+            s_b,
+            s_c,
+            s_const,
+            q,
+        }
+    }
+
+    /// Lays out one row of the gate at `offset` of a region of the caller's, with `a` and
+    /// `b` copied in, and returns `c`.
+    pub fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        coefficients: Coefficients<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        config.q.enable(region, offset)?;
+        let Coefficients { s_m, s_a, s_b, s_c, s_const } = coefficients;
+        for (name, column, value) in [("s_m", config.s_m, s_m), ("s_a", config.s_a, s_a), ("s_b", config.s_b, s_b), ("s_c", config.s_c, s_c), ("s_const", config.s_const, s_const)] {
+            region.assign_fixed(|| name, column, offset, || Value::known(value))?;
+        }
+
+        let [col_a, col_b, col_c] = config.advice;
+        let a = a.copy_advice(|| "a", region, col_a, offset)?;
+        let b = b.copy_advice(|| "b", region, col_b, offset)?;
+        let c = a.value().zip(b.value()).map(|(a, b)| coefficients.output(*a, *b));
+        region.assign_advice(|| "c", col_c, offset, || c)
+    }
+
+    /// One row of the gate in a region of its own, named `name`.
+    pub fn apply(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        coefficients: Coefficients<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        layouter
+            .assign_region(|| name, |mut region| self.assign_row(&mut region, 0, coefficients, a, b))
+            .in_chip("ArithChip", name)
+    }
+
+    pub fn add(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.apply(layouter, "add", Coefficients::add(), a, b)
+    }
+
+    pub fn sub(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.apply(layouter, "sub", Coefficients::sub(), a, b)
+    }
+
+    pub fn mul(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.apply(layouter, "mul", Coefficients::mul(), a, b)
+    }
+
+    pub fn scale(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>, k: F) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.apply(layouter, "scale", Coefficients::scale(k), a, a)
+    }
+
+    pub fn add_constant(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>, k: F) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.apply(layouter, "add constant", Coefficients::add_constant(k), a, a)
+    }
+
+    /// `1 / a`, witnessed and checked with `a * (1 / a) == 1`, which nothing satisfies for
+    /// `a = 0`: a zero `a` fails the proof.
+    pub fn invert(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let inverse = a.value().map(|a| a.invert().unwrap_or(F::zero()));
+        let inverse = self.load_private(layouter.namespace(|| "inverse"), inverse)?;
+        self.apply(layouter, "invert", Coefficients::inverse(), a, &inverse)?;
+        Ok(inverse)
+    }
+
+    /// Constrains `a == b` with a row of the gate rather than the permutation, for a check
+    /// that belongs to a row of its own, e.g. one another gate also reads.
+    pub fn assert_equal(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>) -> Result<(), Halo2ExamplesError> {
+        self.apply(layouter, "assert equal", Coefficients::equal(), a, b).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{ArithChip, ArithConfig};
+    use crate::{chip::LoadInstructions, test_over_fields};
+
+    /// `3 * (x * y) + 5 - x == out`, with every operation of the chip on the way.
+    #[derive(Default)]
+    struct ArithCircuit<F> {
+        x: Value<F>,
+        y: Value<F>,
+        out: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for ArithCircuit<F> {
+        type Config = ArithConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            ArithChip::configure(meta, advice)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+            let chip = ArithChip::construct(config);
+            let x = chip.load_private(layouter.namespace(|| "x"), self.x)?;
+            let y = chip.load_private(layouter.namespace(|| "y"), self.y)?;
+            let out = chip.load_private(layouter.namespace(|| "out"), self.out)?;
+
+            let product = chip.mul(layouter.namespace(|| "x * y"), &x, &y)?;
+            let scaled = chip.scale(layouter.namespace(|| "3 * x * y"), &product, F::from(3))?;
+            let shifted = chip.add_constant(layouter.namespace(|| "+ 5"), &scaled, F::from(5))?;
+            let result = chip.sub(layouter.namespace(|| "- x"), &shifted, &x)?;
+            let result = chip.add(layouter.namespace(|| "+ y"), &result, &y)?;
+            let result = chip.sub(layouter.namespace(|| "- y"), &result, &y)?;
+            chip.assert_equal(layouter.namespace(|| "result == out"), &result, &out)?;
+            Ok(())
+        }
+    }
+
+    fn test_arith<F: FieldExt>() {
+        let circuit = |out: u64| ArithCircuit {
+            x: Value::known(F::from(6)),
+            y: Value::known(F::from(7)),
+            out: Value::known(F::from(out)),
+        };
+
+        // 3 * 42 + 5 - 6
+        MockProver::run(5, &circuit(125), vec![]).unwrap().assert_satisfied();
+        assert!(MockProver::run(5, &circuit(126), vec![]).unwrap().verify().is_err());
+    }
+
+    test_over_fields!(test_arith);
+}
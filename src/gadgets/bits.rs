@@ -0,0 +1,265 @@
+//! Packing bit cells into a value and unpacking a value into bit cells.
+//!
+//! Where `DecomposeChip` range checks a value through K-bit chunks looked up in a table,
+//! this works on single bits, with nothing but a gate: every bit is constrained to be
+//! boolean, and a running sum from the most significant bit down rebuilds the value,
+//!
+//!     acc_0 = 0,  acc_{i+1} = 2 * acc_i + b_{n-1-i},  acc_n = b_0 + 2 * b_1 + ... + 2^{n-1} * b_{n-1}
+//!
+//!         bit      |   acc    |  q_bit
+//!       -----------------------------------
+//!                  |    0     |
+//!       b_{n-1}    |  acc_1   |    1
+//!         ...      |   ...    |    1
+//!         b_0      |  acc_n   |    1
+//!
+//! `pack_bits` copies the bits in and returns `acc_n`; `unpack` witnesses the bits and copies
+//! the value into `acc_n`, which also proves it is below `2^n`. For that to mean anything
+//! `2^n` must stay below the field modulus, so both refuse more than `F::CAPACITY` bits.
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_below, check_bits, check_instances, debug_check, known, InvalidWitness, ValidateWitness},
+};
+
+#[derive(Debug, Clone)]
+pub struct BitsConfig {
+    bit: Column<Advice>,
+    acc: Column<Advice>,
+    q_bit: Selector,
+    instance: Column<Instance>,
+    constants: ConstantsConfig,
+}
+
+pub struct BitsChip<F: FieldExt> {
+    config: BitsConfig,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for BitsChip<F> {
+    type Config = BitsConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+/// Where `lay_out` gets a bit from.
+enum Bit<'a, F: FieldExt> {
+    Copied(&'a AssignedCell<F, F>),
+    Witnessed(Value<bool>),
+}
+
+impl<F: FieldExt + PrimeFieldBits> BitsChip<F> {
+    pub fn construct(config: BitsConfig) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> BitsConfig {
+        let bit = meta.advice_column();
+        let acc = meta.advice_column();
+        let q_bit = meta.selector();
+
+        // the initial `acc_0 == 0` is copied from a loaded constant
+        let constants = ConstantsChip::configure_with(meta, acc);
+        meta.enable_equality(bit);
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+
+        meta.create_gate("bits", |meta| {
+            let q_bit = meta.query_selector(q_bit);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            GateBuilder::with_selector(q_bit)
+                .boolean("bit is boolean", bit.clone())
+                .equal("acc = 2 * acc_prev + bit", acc, acc_prev * F::from(2) + bit)
+        });
+
+        BitsConfig {
+            bit,
+            acc,
+            q_bit,
+            instance,
+            constants,
+        }
+    }
+
+    /// `bits[0] + 2 * bits[1] + ... + 2^{n-1} * bits[n-1]`, after checking every bit is 0 or 1.
+    pub fn pack_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let bits: Vec<_> = bits.iter().map(Bit::Copied).collect();
+        let (_, packed) = self.lay_out(layouter, "pack bits", &bits, None)?;
+        Ok(packed)
+    }
+
+    /// The `num_bits` low bits of `value`, little-endian, after checking there are no others.
+    pub fn unpack(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Halo2ExamplesError> {
+        let le_bits = value.value().map(|value| value.to_le_bits());
+        let bits: Vec<_> = (0..num_bits)
+            .map(|i| Bit::Witnessed(le_bits.as_ref().map(|le_bits| le_bits[i])))
+            .collect();
+        let (bits, _) = self.lay_out(layouter, "unpack", &bits, Some(value))?;
+        Ok(bits)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .constrain_instance(cell.cell(), self.config.instance, row)
+            .map_err(|err| Halo2ExamplesError::new("BitsChip", err).at(row, "instance"))
+    }
+
+    // Lays out the running sum over `bits`, given little-endian, and ties `acc_n` to `value`
+    // if there is one. Returns the bit cells, little-endian, and `acc_n`.
+    fn lay_out(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        bits: &[Bit<'_, F>],
+        value: Option<&AssignedCell<F, F>>,
+    ) -> Result<(Vec<AssignedCell<F, F>>, AssignedCell<F, F>), Halo2ExamplesError> {
+        if bits.len() > F::CAPACITY as usize {
+            return Err(Halo2ExamplesError::new("BitsChip", Error::Synthesis).in_region(name));
+        }
+
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", F::zero())?;
+        layouter
+            .assign_region(
+                || name,
+                |mut region| {
+                    let mut acc = zero.copy_advice(|| "acc_0", &mut region, self.config.acc, 0)?;
+                    let mut cells = Vec::with_capacity(bits.len());
+
+                    for (i, bit) in bits.iter().enumerate().rev() {
+                        let offset = bits.len() - i;
+                        self.config.q_bit.enable(&mut region, offset)?;
+                        let cell = match bit {
+                            Bit::Copied(cell) => cell.copy_advice(|| format!("b_{}", i), &mut region, self.config.bit, offset)?,
+                            Bit::Witnessed(bit) => region.assign_advice(|| format!("b_{}", i), self.config.bit, offset, || {
+                                bit.map(|bit| F::from(bit as u64))
+                            })?,
+                        };
+
+                        debug_check(&cell.value().copied(), || format!("b_{} of {:?}", i, name), |bit| check_below(*bit, 2, "b_i"));
+                        let next = acc.value().copied() * Value::known(F::from(2)) + cell.value().copied();
+                        acc = region.assign_advice(|| format!("acc_{}", offset), self.config.acc, offset, || next)?;
+                        cells.push(cell);
+                    }
+
+                    if let Some(value) = value {
+                        region.constrain_equal(value.cell(), acc.cell())?;
+                    }
+                    cells.reverse();
+                    Ok((cells, acc))
+                },
+            )
+            .in_chip("BitsChip", name)
+    }
+}
+
+/// Unpacks a private value into `num_bits` bits and packs them back into the public input.
+#[derive(Default)]
+pub struct BitsCircuit<F> {
+    pub value: Value<F>,
+    pub num_bits: usize,
+}
+
+impl<F: FieldExt + PrimeFieldBits> Circuit<F> for BitsCircuit<F> {
+    type Config = (BitsConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            value: Value::unknown(),
+            num_bits: self.num_bits,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let bits = BitsChip::configure(meta, instance);
+        (bits, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = BitsChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let value = load_chip.load_private(layouter.namespace(|| "load value"), self.value)?;
+        let bits = chip.unpack(layouter.namespace(|| "unpack"), &value, self.num_bits)?;
+        let packed = chip.pack_bits(layouter.namespace(|| "pack"), &bits)?;
+        chip.expose_public(layouter.namespace(|| "packed"), &packed, 0)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> ValidateWitness<F> for BitsCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let value = known(self.value, "value")?;
+        check_bits(value, self.num_bits, "value")?;
+        check_instances(instances, &[value])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::PrimeFieldBits;
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver};
+
+    use super::BitsCircuit;
+    use crate::test_over_fields;
+
+    const K: u32 = 8;
+
+    fn test_bits_round_trip<F: FieldExt + PrimeFieldBits>() {
+        for (value, num_bits) in [(0xdead_beefu64, 32), (0, 8), (1, 1), (u64::MAX, 64)] {
+            let circuit = BitsCircuit {
+                value: Value::known(F::from(value)),
+                num_bits,
+            };
+
+            let prover = MockProver::run(K, &circuit, vec![vec![F::from(value)]]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    fn test_bits_out_of_range<F: FieldExt + PrimeFieldBits>() {
+        let value = F::from(1u64 << 32);
+        let circuit = BitsCircuit {
+            value: Value::known(value),
+            num_bits: 32,
+        };
+
+        let prover = MockProver::run(K, &circuit, vec![vec![value]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    test_over_fields!(test_bits_round_trip, test_bits_out_of_range);
+}
@@ -0,0 +1,386 @@
+//! Byte decompositions of a value in either byte order, and byte swapping.
+//!
+//! SHA-256 and Keccak read and write their words big-endian, while everything in this crate
+//! decomposes little-endian. This gadget witnesses the `n` bytes of a value once, one per
+//! row from the most significant down, and runs two sums over them: `acc` from the top,
+//! which rebuilds the value reading the bytes as big-endian, and `rev` from the bottom,
+//! which rebuilds it reading them as little-endian, i.e. the value with its bytes swapped.
+//!
+//!         byte     |   acc    |   rev    |  q_byte
+//!       -------------------------------------------
+//!                  |    0     |          |
+//!       b_{n-1}    |  acc_1   |  rev_1   |    1      acc_i = 256 * acc_{i-1} + byte
+//!         ...      |   ...    |   ...    |    1      rev_i = 256 * rev_{i+1} + byte
+//!         b_0      |  acc_n   |  rev_n   |    1
+//!                  |          |    0     |
+//!
+//! `acc_n` is the value and `rev_1` its byte swap. Every byte is looked up in a byte table,
+//! and the value must have fewer than `F::CAPACITY` bits, so at most 31 bytes for the Pasta
+//! fields, for the decomposition to be unique.
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    table,
+    validate::{check_bits, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+const BYTE_RANGE: usize = 256;
+
+/// The bytes of a value, each range checked, and the value read in both byte orders.
+#[derive(Debug, Clone)]
+pub struct Bytes<F: FieldExt> {
+    /// Least significant byte first.
+    pub le: Vec<AssignedCell<F, F>>,
+    /// The bytes in `le` recomposed little-endian.
+    pub value: AssignedCell<F, F>,
+    /// The bytes in `le` recomposed big-endian.
+    pub swapped: AssignedCell<F, F>,
+}
+
+impl<F: FieldExt> Bytes<F> {
+    /// Most significant byte first.
+    pub fn be(&self) -> Vec<AssignedCell<F, F>> {
+        self.le.iter().rev().cloned().collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ByteOrderConfig {
+    byte: Column<Advice>,
+    acc: Column<Advice>,
+    rev: Column<Advice>,
+    q_byte: Selector,
+    bytes: TableColumn,
+    instance: Column<Instance>,
+    constants: ConstantsConfig,
+}
+
+pub struct ByteOrderChip<F: FieldExt> {
+    config: ByteOrderConfig,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for ByteOrderChip<F> {
+    type Config = ByteOrderConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+/// Where `lay_out` gets a byte from.
+enum Byte<'a, F: FieldExt> {
+    Copied(&'a AssignedCell<F, F>),
+    Witnessed(Value<u8>),
+}
+
+impl<F: FieldExt + PrimeFieldBits> ByteOrderChip<F> {
+    pub fn construct(config: ByteOrderConfig) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> ByteOrderConfig {
+        let byte = meta.advice_column();
+        let acc = meta.advice_column();
+        let rev = meta.advice_column();
+        let q_byte = meta.complex_selector();
+        let bytes = meta.lookup_table_column();
+
+        // the zeros the two sums start from are copied from a loaded constant
+        let constants = ConstantsChip::configure_with(meta, acc);
+        meta.enable_equality(byte);
+        meta.enable_equality(acc);
+        meta.enable_equality(rev);
+        meta.enable_equality(instance);
+
+        meta.create_gate("byte order", |meta| {
+            let q_byte = meta.query_selector(q_byte);
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let rev_next = meta.query_advice(rev, Rotation::next());
+            let rev = meta.query_advice(rev, Rotation::cur());
+            let base = F::from(BYTE_RANGE as u64);
+
+            GateBuilder::with_selector(q_byte)
+                .equal("acc = 256 * acc_prev + byte", acc, acc_prev * base + byte.clone())
+                .equal("rev = 256 * rev_next + byte", rev, rev_next * base + byte)
+        });
+
+        meta.lookup(|meta| {
+            let q_byte = meta.query_selector(q_byte);
+            let byte = meta.query_advice(byte, Rotation::cur());
+
+            vec![(q_byte * byte, bytes)]
+        });
+
+        ByteOrderConfig {
+            byte,
+            acc,
+            rev,
+            q_byte,
+            bytes,
+            instance,
+            constants,
+        }
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Halo2ExamplesError> {
+        table::load_range_table(layouter, self.config.bytes, BYTE_RANGE).in_chip("ByteOrderChip", "load byte table")
+    }
+
+    /// The `num_bytes` low bytes of `value`, after checking there are no others.
+    pub fn decompose(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bytes: usize,
+    ) -> Result<Bytes<F>, Halo2ExamplesError> {
+        let repr = value.value().map(|value| value.to_repr());
+        let bytes: Vec<_> = (0..num_bytes)
+            .map(|i| Byte::Witnessed(repr.as_ref().map(|repr| repr.as_ref().get(i).copied().unwrap_or(0))))
+            .collect();
+        self.lay_out(layouter, "decompose bytes", &bytes, Some(value))
+    }
+
+    /// `value` with its `num_bytes` low bytes in the opposite order, e.g. a word read
+    /// big-endian that should have been read little-endian.
+    pub fn byte_swap(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bytes: usize,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        Ok(self.decompose(layouter, value, num_bytes)?.swapped)
+    }
+
+    /// Recomposes byte cells given least significant first, after range checking them.
+    pub fn from_bytes_le(
+        &self,
+        layouter: impl Layouter<F>,
+        le: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let bytes: Vec<_> = le.iter().map(Byte::Copied).collect();
+        Ok(self.lay_out(layouter, "bytes from le", &bytes, None)?.value)
+    }
+
+    /// Recomposes byte cells given most significant first, after range checking them.
+    pub fn from_bytes_be(
+        &self,
+        layouter: impl Layouter<F>,
+        be: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let bytes: Vec<_> = be.iter().rev().map(Byte::Copied).collect();
+        Ok(self.lay_out(layouter, "bytes from be", &bytes, None)?.value)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .constrain_instance(cell.cell(), self.config.instance, row)
+            .map_err(|err| Halo2ExamplesError::new("ByteOrderChip", err).at(row, "instance"))
+    }
+
+    // Lays out both sums over `bytes`, given little-endian, and ties `acc_n` to `value` if
+    // there is one.
+    fn lay_out(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        bytes: &[Byte<'_, F>],
+        value: Option<&AssignedCell<F, F>>,
+    ) -> Result<Bytes<F>, Halo2ExamplesError> {
+        let num_bytes = bytes.len();
+        if num_bytes == 0 || num_bytes * 8 > F::CAPACITY as usize {
+            return Err(Halo2ExamplesError::new("ByteOrderChip", Error::Synthesis).in_region(name));
+        }
+
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", F::zero())?;
+        layouter
+            .assign_region(
+                || name,
+                |mut region| {
+                    // 1. The bytes, most significant first, with `acc` running down from 0.
+                    let mut acc = zero.copy_advice(|| "acc_0", &mut region, self.config.acc, 0)?;
+                    let mut le = Vec::with_capacity(num_bytes);
+                    for (i, byte) in bytes.iter().enumerate().rev() {
+                        let offset = num_bytes - i;
+                        self.config.q_byte.enable(&mut region, offset)?;
+                        let cell = match byte {
+                            Byte::Copied(cell) => cell.copy_advice(|| format!("b_{}", i), &mut region, self.config.byte, offset)?,
+                            Byte::Witnessed(byte) => region.assign_advice(|| format!("b_{}", i), self.config.byte, offset, || {
+                                byte.map(|byte| F::from(byte as u64))
+                            })?,
+                        };
+
+                        let next = acc.value().copied() * Value::known(F::from(BYTE_RANGE as u64)) + cell.value().copied();
+                        acc = region.assign_advice(|| format!("acc_{}", offset), self.config.acc, offset, || next)?;
+                        le.push(cell);
+                    }
+                    le.reverse();
+
+                    // 2. `rev` running up from 0, below the last byte.
+                    let mut rev = zero.copy_advice(|| "rev_end", &mut region, self.config.rev, num_bytes + 1)?;
+                    for (i, byte) in le.iter().enumerate() {
+                        let offset = num_bytes - i;
+                        let next = rev.value().copied() * Value::known(F::from(BYTE_RANGE as u64)) + byte.value().copied();
+                        rev = region.assign_advice(|| format!("rev_{}", offset), self.config.rev, offset, || next)?;
+                    }
+
+                    if let Some(value) = value {
+                        region.constrain_equal(value.cell(), acc.cell())?;
+                    }
+                    Ok(Bytes { le, value: acc, swapped: rev })
+                },
+            )
+            .in_chip("ByteOrderChip", name)
+    }
+}
+
+/// Byte-swaps the `num_bytes` low bytes of a private value into the public input.
+#[derive(Default)]
+pub struct ByteSwapCircuit<F> {
+    pub value: Value<F>,
+    pub num_bytes: usize,
+}
+
+impl<F: FieldExt + PrimeFieldBits> ByteSwapCircuit<F> {
+    /// `value` with its `num_bytes` low bytes reversed.
+    pub fn swapped(value: F, num_bytes: usize) -> F {
+        let repr = value.to_repr();
+        repr.as_ref()[..num_bytes]
+            .iter()
+            .fold(F::zero(), |acc, byte| acc * F::from(BYTE_RANGE as u64) + F::from(*byte as u64))
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> Circuit<F> for ByteSwapCircuit<F> {
+    type Config = (ByteOrderConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            value: Value::unknown(),
+            num_bytes: self.num_bytes,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let byte_order = ByteOrderChip::configure(meta, instance);
+        (byte_order, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = ByteOrderChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        chip.load_table(&mut layouter)?;
+
+        let value = load_chip.load_private(layouter.namespace(|| "load value"), self.value)?;
+        let swapped = chip.byte_swap(layouter.namespace(|| "byte swap"), &value, self.num_bytes)?;
+        chip.expose_public(layouter.namespace(|| "swapped"), &swapped, 0)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> ValidateWitness<F> for ByteSwapCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let value = known(self.value, "value")?;
+        check_bits(value, self.num_bytes * 8, "value")?;
+        check_instances(instances, &[Self::swapped(value, self.num_bytes)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{ByteOrderChip, ByteOrderConfig, ByteSwapCircuit};
+    use crate::chip::{LoadPrivateChip, LoadPrivateConfig};
+
+    const K: u32 = 9;
+
+    #[test]
+    fn test_byte_swap() {
+        for (value, num_bytes, swapped) in [(0x1234_5678u64, 4, 0x7856_3412u64), (0xab, 1, 0xab), (0x01, 8, 1 << 56)] {
+            assert_eq!(ByteSwapCircuit::swapped(Fp::from(value), num_bytes), Fp::from(swapped));
+            let circuit = ByteSwapCircuit {
+                value: Value::known(Fp::from(value)),
+                num_bytes,
+            };
+
+            let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(swapped)]]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_byte_swap_out_of_range() {
+        let circuit = ByteSwapCircuit {
+            value: Value::known(Fp::from(0x1_0000_0000u64)),
+            num_bytes: 4,
+        };
+
+        let prover = MockProver::run(K, &circuit, vec![vec![Fp::zero()]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// Decomposes a value and recomposes its bytes in both orders.
+    struct RoundTrip(Value<Fp>);
+
+    impl Circuit<Fp> for RoundTrip {
+        type Config = (ByteOrderConfig, LoadPrivateConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self(Value::unknown())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            ByteSwapCircuit::<Fp>::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (config, load_config) = config;
+            let chip = ByteOrderChip::construct(config);
+            let value = LoadPrivateChip::construct(load_config).load_private(layouter.namespace(|| "load"), self.0)?;
+            chip.load_table(&mut layouter)?;
+
+            let bytes = chip.decompose(layouter.namespace(|| "decompose"), &value, 4)?;
+            let le = chip.from_bytes_le(layouter.namespace(|| "le"), &bytes.le)?;
+            let be = chip.from_bytes_be(layouter.namespace(|| "be"), &bytes.be())?;
+            layouter.assign_region(|| "compare", |mut region| {
+                region.constrain_equal(le.cell(), value.cell())?;
+                region.constrain_equal(be.cell(), value.cell())
+            })
+        }
+    }
+
+    #[test]
+    fn test_byte_order_round_trip() {
+        let prover = MockProver::run(K, &RoundTrip(Value::known(Fp::from(0xdead_beef))), vec![vec![]]).unwrap();
+        prover.assert_satisfied();
+    }
+}
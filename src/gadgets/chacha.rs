@@ -0,0 +1,166 @@
+//! The ChaCha20 quarter round of RFC 8439, on four words of `U32Chip`:
+//!
+//!     a += b; d ^= a; d <<<= 16;
+//!     c += d; b ^= c; b <<<= 12;
+//!     a += b; d ^= a; d <<<= 8;
+//!     c += d; b ^= c; b <<<= 7;
+//!
+//! Every step is one operation of the word chip, so a quarter round is 4 additions, 4 xors
+//! and 4 rotations, each returning a range-checked word. The rotations by 16 and 8 move
+//! whole bytes but are still laid out as a split and a recombination of the limbs.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::word::{U32Chip, U32Config, U32};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::Halo2ExamplesError,
+    reference::ref_quarter_round,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+pub struct QuarterRoundChip<F: FieldExt> {
+    words: U32Chip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for QuarterRoundChip<F> {
+    type Config = U32Config;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        self.words.config()
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> QuarterRoundChip<F> {
+    pub fn construct(config: U32Config) -> Self {
+        Self {
+            words: U32Chip::construct(config),
+        }
+    }
+
+    /// The quarter round needs nothing but the word chip.
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> U32Config {
+        U32Chip::configure(meta, instance)
+    }
+
+    /// The chip the words are witnessed with.
+    pub fn words(&self) -> &U32Chip<F> {
+        &self.words
+    }
+
+    /// The quarter round of `[a, b, c, d]`, returning the new `[a, b, c, d]`.
+    pub fn quarter_round(
+        &self,
+        mut layouter: impl Layouter<F>,
+        [a, b, c, d]: [&U32<F>; 4],
+    ) -> Result<[U32<F>; 4], Halo2ExamplesError> {
+        let words = &self.words;
+        let a = words.add(layouter.namespace(|| "a += b"), a, b)?;
+        let d = self.xor_rotate(layouter.namespace(|| "d ^= a; d <<<= 16"), d, &a, 16)?;
+        let c = words.add(layouter.namespace(|| "c += d"), c, &d)?;
+        let b = self.xor_rotate(layouter.namespace(|| "b ^= c; b <<<= 12"), b, &c, 12)?;
+        let a = words.add(layouter.namespace(|| "a += b"), &a, &b)?;
+        let d = self.xor_rotate(layouter.namespace(|| "d ^= a; d <<<= 8"), &d, &a, 8)?;
+        let c = words.add(layouter.namespace(|| "c += d"), &c, &d)?;
+        let b = self.xor_rotate(layouter.namespace(|| "b ^= c; b <<<= 7"), &b, &c, 7)?;
+        Ok([a, b, c, d])
+    }
+
+    // `(x ^ y) <<< bits`
+    fn xor_rotate(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &U32<F>,
+        y: &U32<F>,
+        bits: usize,
+    ) -> Result<U32<F>, Halo2ExamplesError> {
+        let xor = self.words.xor(layouter.namespace(|| "xor"), x, y)?;
+        self.words.rotate_left(layouter.namespace(|| "rotate"), &xor, bits)
+    }
+}
+
+/// Applies a quarter round to four private words and exposes the result.
+#[derive(Default)]
+pub struct QuarterRoundCircuit {
+    pub state: [Value<u32>; 4],
+}
+
+impl<F: FieldExt> Circuit<F> for QuarterRoundCircuit {
+    type Config = (U32Config, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        (QuarterRoundChip::configure(meta, instance), LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = QuarterRoundChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        chip.words().load_tables(&mut layouter)?;
+
+        let values: Vec<_> = self.state.iter().map(|word| word.map(|word| F::from(word as u64))).collect();
+        let cells = load_chip.load_privates(layouter.namespace(|| "load state"), &values)?;
+        let words = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| chip.words().range_check(layouter.namespace(|| format!("word {}", i)), cell))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let [a, b, c, d] = [&words[0], &words[1], &words[2], &words[3]];
+        let state = chip.quarter_round(layouter.namespace(|| "quarter round"), [a, b, c, d])?;
+        for (row, word) in state.iter().enumerate() {
+            chip.words().expose_public(layouter.namespace(|| "output"), word, row)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ValidateWitness<F> for QuarterRoundCircuit {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let state = self.state.iter().map(|word| known(*word, "state")).collect::<Result<Vec<_>, _>>()?;
+        let state: [u32; 4] = state.try_into().expect("4 words");
+        check_instances(instances, &ref_quarter_round(state).map(|word| F::from(word as u64)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{arithmetic::Field, circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::QuarterRoundCircuit;
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 17;
+
+    // RFC 8439, section 2.1.1
+    const STATE: [u32; 4] = [0x1111_1111, 0x0102_0304, 0x9b8d_6f43, 0x0123_4567];
+    const OUTPUT: [u32; 4] = [0xea2a_92f4, 0xcb1c_f8ce, 0x4581_472e, 0x5881_c4bb];
+
+    #[test]
+    fn test_quarter_round() {
+        let circuit = QuarterRoundCircuit {
+            state: STATE.map(Value::known),
+        };
+        let instances = vec![OUTPUT.map(|word| Fp::from(word as u64)).to_vec()];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+
+        MockProver::run(K, &circuit, instances.clone()).unwrap().assert_satisfied();
+
+        for row in 0..4 {
+            let mut wrong = instances.clone();
+            wrong[0][row] += Fp::one();
+            assert!(MockProver::run(K, &circuit, wrong).unwrap().verify().is_err(), "output {} is not bound", row);
+        }
+    }
+}
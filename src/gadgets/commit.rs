@@ -0,0 +1,138 @@
+//! Hiding commitments to a single value, `Poseidon(value, salt)`, over the Pasta `Fp`.
+//!
+//! This is the in-circuit counterpart of `commit`: a thin wrapper around the `Pow5Chip` of
+//! `halo2_gadgets`, with the same `P128Pow5T3` parameters as the Poseidon transcript in
+//! `prover::poseidon`. The salt keeps a small value, e.g. a bid, from being found by
+//! hashing every candidate.
+//!
+//! `commit_all` chains the same hash over a list, `h_0 = n` and `h_{i+1} = Poseidon(x_i, h_i)`,
+//! which is how a circuit can publish all its public inputs as one instance cell. As the
+//! Poseidon `CircuitHasher`, `hash2(a, b)` is `commit(a, b)`.
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*};
+
+use super::hasher::CircuitHasher;
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+};
+
+/// `Poseidon(value, salt)`, computed natively.
+pub fn commit(value: Fp, salt: Fp) -> Fp {
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, 3, 2>::init().hash([value, salt])
+}
+
+/// The hash chain over `values`, starting from their number, computed natively.
+pub fn commit_all(values: &[Fp]) -> Fp {
+    values.iter().fold(Fp::from(values.len() as u64), |hash, value| commit(*value, hash))
+}
+
+#[derive(Debug, Clone)]
+pub struct CommitConfig {
+    poseidon: Pow5Config<Fp, 3, 2>,
+    constants: ConstantsConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommitChip {
+    config: CommitConfig,
+    constants: ConstantsChip<Fp>,
+}
+
+impl Chip<Fp> for CommitChip {
+    type Config = CommitConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl CommitChip {
+    pub fn construct(config: CommitConfig) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    /// Four advice columns for the permutation, and six fixed ones for its round constants.
+    /// The constants the sponge is initialised with are assigned in the fixed column
+    /// `ConstantsChip` shares, and one more advice column holds the length `commit_all`
+    /// starts from, loaded as a constant.
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> CommitConfig {
+        let state = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let partial_sbox = meta.advice_column();
+        let rc_a = [meta.fixed_column(), meta.fixed_column(), meta.fixed_column()];
+        let rc_b = [meta.fixed_column(), meta.fixed_column(), meta.fixed_column()];
+        let length = meta.advice_column();
+        let constants = ConstantsChip::configure_with(meta, length);
+
+        let poseidon = Pow5Chip::configure::<P128Pow5T3>(meta, state, partial_sbox, rc_a, rc_b);
+        CommitConfig { poseidon, constants }
+    }
+
+    /// `Poseidon(value, salt)`.
+    pub fn commit(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        value: &AssignedCell<Fp, Fp>,
+        salt: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        let chip = Pow5Chip::construct(self.config.poseidon.clone());
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, 3, 2>::init(chip, layouter.namespace(|| "init"))
+            .in_chip("CommitChip", "init")?;
+        hasher
+            .hash(layouter.namespace(|| "hash"), [value.clone(), salt.clone()])
+            .in_chip("CommitChip", "hash")
+    }
+
+    /// The hash chain over `values`, like `commit_all`.
+    pub fn commit_all(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        values: &[AssignedCell<Fp, Fp>],
+    ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        let length = Fp::from(values.len() as u64);
+        let mut hash = self.constants.load_constant(layouter.namespace(|| "length"), "h_0", length)?;
+        for (i, value) in values.iter().enumerate() {
+            hash = self.commit(layouter.namespace(|| format!("h_{}", i + 1)), value, &hash)?;
+        }
+        Ok(hash)
+    }
+}
+
+impl CircuitHasher<Fp> for CommitChip {
+    type Config = CommitConfig;
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> CommitConfig {
+        CommitChip::configure(meta)
+    }
+
+    fn construct(config: CommitConfig) -> Self {
+        CommitChip::construct(config)
+    }
+
+    fn hash2_native(a: Fp, b: Fp) -> Fp {
+        commit(a, b)
+    }
+
+    fn hash2(
+        &self,
+        layouter: impl Layouter<Fp>,
+        a: &AssignedCell<Fp, Fp>,
+        b: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        self.commit(layouter, a, b)
+    }
+
+    fn hash_many(&self, layouter: impl Layouter<Fp>, values: &[AssignedCell<Fp, Fp>]) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        self.commit_all(layouter, values)
+    }
+}
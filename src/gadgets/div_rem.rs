@@ -0,0 +1,232 @@
+//! Integer division with remainder, `a = q * d + r` with `0 <= r < d`, for a divisor `d` that
+//! may itself be a witness.
+//!
+//! The quotient and the remainder are witnessed, and one row ties them to `a` and `d`:
+//!
+//!       a   |   d   |   q   |   r   |   gap    |  q_div
+//!     -----------------------------------------------------
+//!       a   |   d   |   q   |   r   | d - r - 1|    1
+//!
+//! `BitsChip` then checks that `q`, `r` and `gap` fit in `NUM_BITS` bits. `gap` not being
+//! negative is `r < d`, which also rules out `d = 0`, and with every factor below
+//! `2^NUM_BITS`, `q * d + r` is below `2^(2 * NUM_BITS + 1)` and equals `a` as an integer,
+//! not just modulo the field, as long as `2 * NUM_BITS + 1` is below the field's bits. So
+//! `q` and `r` are the quotient and the remainder for any `a` and `d` below `2^NUM_BITS`.
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::bits::{BitsChip, BitsConfig};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+#[derive(Debug, Clone)]
+pub struct DivRemConfig {
+    a: Column<Advice>,
+    d: Column<Advice>,
+    q: Column<Advice>,
+    r: Column<Advice>,
+    gap: Column<Advice>,
+    q_div: Selector,
+    bits: BitsConfig,
+}
+
+pub struct DivRemChip<F: FieldExt, const NUM_BITS: usize> {
+    config: DivRemConfig,
+    bits: BitsChip<F>,
+}
+
+impl<F: FieldExt, const NUM_BITS: usize> Chip<F> for DivRemChip<F, NUM_BITS> {
+    type Config = DivRemConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const NUM_BITS: usize> DivRemChip<F, NUM_BITS> {
+    pub fn construct(config: DivRemConfig) -> Self {
+        let bits = BitsChip::construct(config.bits.clone());
+        Self { config, bits }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> DivRemConfig {
+        let bits = BitsChip::configure(meta, instance);
+        let [a, d, q, r, gap] = [(); 5].map(|_| meta.advice_column());
+        let q_div = meta.selector();
+        for column in [a, d, q, r, gap] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("div rem", |meta| {
+            let q_div = meta.query_selector(q_div);
+            let [a, d, q, r, gap] = [a, d, q, r, gap].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_div)
+                .equal("a = q * d + r", a, q * d.clone() + r.clone())
+                .equal("gap = d - r - 1", gap, d - r - Expression::Constant(F::one()))
+        });
+
+        DivRemConfig {
+            a,
+            d,
+            q,
+            r,
+            gap,
+            q_div,
+            bits,
+        }
+    }
+
+    /// The chip the quotient, the remainder and the gap are range checked with, and the
+    /// operands can be range checked with beforehand.
+    pub fn bits(&self) -> &BitsChip<F> {
+        &self.bits
+    }
+
+    /// The quotient and the remainder of `a` divided by `d`, both below `2^NUM_BITS`.
+    pub fn div_rem(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        d: &AssignedCell<F, F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Halo2ExamplesError> {
+        let config = &self.config;
+        let (q, r, gap) = layouter
+            .assign_region(
+                || "div rem",
+                |mut region| {
+                    config.q_div.enable(&mut region, 0)?;
+                    let a = a.copy_advice(|| "a", &mut region, config.a, 0)?;
+                    let d = d.copy_advice(|| "d", &mut region, config.d, 0)?;
+
+                    // below 2^NUM_BITS the operands divide as integers; a zero divisor has no
+                    // remainder that satisfies the gate, so any will do
+                    let qr = a.value().zip(d.value()).map(|(a, d)| {
+                        let (a, d) = (a.get_lower_128(), d.get_lower_128());
+                        if d == 0 {
+                            (0, a)
+                        } else {
+                            (a / d, a % d)
+                        }
+                    });
+                    let q = region.assign_advice(|| "q", config.q, 0, || qr.map(|(q, _)| F::from_u128(q)))?;
+                    let r = region.assign_advice(|| "r", config.r, 0, || qr.map(|(_, r)| F::from_u128(r)))?;
+                    let gap = region.assign_advice(|| "gap", config.gap, 0, || {
+                        d.value().copied() - r.value().copied() - Value::known(F::one())
+                    })?;
+                    Ok((q, r, gap))
+                },
+            )
+            .in_chip("DivRemChip", "div rem")?;
+
+        self.bits.unpack(layouter.namespace(|| "range check q"), &q, NUM_BITS)?;
+        self.bits.unpack(layouter.namespace(|| "range check r"), &r, NUM_BITS)?;
+        self.bits.unpack(layouter.namespace(|| "r < d"), &gap, NUM_BITS)?;
+        Ok((q, r))
+    }
+}
+
+/// Divides a private value by a private divisor, both range checked to `NUM_BITS` bits, and
+/// exposes the quotient and the remainder.
+#[derive(Default)]
+pub struct DivRemCircuit<const NUM_BITS: usize> {
+    pub a: Value<u64>,
+    pub d: Value<u64>,
+}
+
+impl<F: FieldExt + PrimeFieldBits, const NUM_BITS: usize> Circuit<F> for DivRemCircuit<NUM_BITS> {
+    type Config = (DivRemConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let div_rem = DivRemChip::<F, NUM_BITS>::configure(meta, instance);
+        (div_rem, LoadPrivateChip::configure(meta, 2))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = DivRemChip::<F, NUM_BITS>::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let operands = load_chip.load_privates(layouter.namespace(|| "load a, d"), &[self.a.map(F::from), self.d.map(F::from)])?;
+        for (name, operand) in ["a", "d"].iter().zip(&operands) {
+            chip.bits().unpack(layouter.namespace(|| format!("range check {}", name)), operand, NUM_BITS)?;
+        }
+        let (q, r) = chip.div_rem(layouter.namespace(|| "a / d"), &operands[0], &operands[1])?;
+        chip.bits().expose_public(layouter.namespace(|| "q"), &q, 0)?;
+        chip.bits().expose_public(layouter.namespace(|| "r"), &r, 1)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const NUM_BITS: usize> ValidateWitness<F> for DivRemCircuit<NUM_BITS> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let a = known(self.a, "a")?;
+        let d = known(self.d, "d")?;
+        check_below(F::from(a), 1u128 << NUM_BITS, "a")?;
+        check_below(F::from(d), 1u128 << NUM_BITS, "d")?;
+        if d == 0 {
+            return Err(InvalidWitness::InvalidParameter("division by zero".to_string()));
+        }
+        check_instances(instances, &[F::from(a / d), F::from(a % d)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::PrimeFieldBits;
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver};
+
+    use super::DivRemCircuit;
+    use crate::{test_over_fields, validate::ValidateWitness};
+
+    const K: u32 = 8;
+
+    fn div_rem(a: u64, d: u64) -> DivRemCircuit<16> {
+        DivRemCircuit {
+            a: Value::known(a),
+            d: Value::known(d),
+        }
+    }
+
+    fn test_div_rem<F: FieldExt + PrimeFieldBits>() {
+        for (a, d) in [(9635, 97), (96, 97), (97, 97), (0, 1), (65535, 65535), (65535, 2)] {
+            let circuit = div_rem(a, d);
+            let instances = vec![vec![F::from(a / d), F::from(a % d)]];
+            assert_eq!(circuit.validate_witness(&instances), Ok(()));
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+        }
+    }
+
+    fn test_div_rem_wrong<F: FieldExt + PrimeFieldBits>() {
+        // 9635 = 99 * 97 + 32 = 98 * 97 + 129, but 129 is no remainder of a division by 97
+        let circuit = div_rem(9635, 97);
+        for (q, r) in [(98, 129), (100, 0), (99, 33)] {
+            let prover = MockProver::run(K, &circuit, vec![vec![F::from(q), F::from(r)]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // nothing divides by zero
+        let circuit = div_rem(5, 0);
+        assert!(circuit.validate_witness(&[vec![F::zero(), F::from(5)]]).is_err());
+        let prover = MockProver::run(K, &circuit, vec![vec![F::zero(), F::from(5)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    test_over_fields!(test_div_rem, test_div_rem_wrong);
+}
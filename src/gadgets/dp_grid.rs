@@ -0,0 +1,336 @@
+//! A dynamic program over two sequences, `a` of length `m` and `b` of length `n`, filled
+//! into an `(m + 1) × (n + 1)` table row by row:
+//!
+//!     T[i][0], T[0][j]   constants, the transition's `boundary(i, j)`
+//!     T[i][j] = step(a_i, b_j, T[i-1][j], T[i][j-1], T[i-1][j-1])
+//!
+//! `DpGridChip` does the bookkeeping every such program shares. It loads the first row
+//! and column through a `ConstantsChip`, each distinct value once, then walks the table and hands each step its two inputs and three neighbours. What a
+//! step is, and the constraints that make it one, are the `DpTransition`'s: its chip copies
+//! the cells it is given into its own rows, so the permutation argument stitches the table
+//! together, and returns the cell holding `T[i][j]`. Edit distance, longest common
+//! subsequence and sequence alignment scores are all such tables, differing only in the
+//! transition; the table costs `m * n` steps whatever it is.
+
+use std::fmt::Debug;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::Halo2ExamplesError,
+};
+
+/// The cells a step of the table is computed from, besides its two inputs.
+pub struct DpNeighbours<'a, F: FieldExt> {
+    /// `T[i-1][j]`
+    pub up: &'a AssignedCell<F, F>,
+    /// `T[i][j-1]`
+    pub left: &'a AssignedCell<F, F>,
+    /// `T[i-1][j-1]`
+    pub diag: &'a AssignedCell<F, F>,
+}
+
+/// One step of a dynamic program, constrained by the implementing chip.
+pub trait DpTransition<F: FieldExt>: Sized {
+    type Config: Clone + Debug;
+
+    fn construct(config: Self::Config) -> Self;
+
+    /// The constant `T[i][j]` in the first row, `i = 0`, or the first column, `j = 0`.
+    fn boundary(i: usize, j: usize) -> F;
+
+    /// `T[i][j]` from `a_i`, `b_j` and its neighbours, all of them to be copied in.
+    fn step(
+        &self,
+        layouter: impl Layouter<F>,
+        inputs: [&AssignedCell<F, F>; 2],
+        neighbours: DpNeighbours<'_, F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError>;
+}
+
+/// A filled table, `T[i][j]` for `0 <= i <= m` and `0 <= j <= n`.
+#[derive(Debug, Clone)]
+pub struct DpTable<F: FieldExt> {
+    cells: Vec<Vec<AssignedCell<F, F>>>,
+}
+
+impl<F: FieldExt> DpTable<F> {
+    pub fn get(&self, i: usize, j: usize) -> &AssignedCell<F, F> {
+        &self.cells[i][j]
+    }
+
+    /// `T[m][n]`, usually the answer.
+    pub fn last(&self) -> &AssignedCell<F, F> {
+        self.cells.last().and_then(|row| row.last()).expect("a table has a first row")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DpGridConfig<C> {
+    constants: ConstantsConfig,
+    transition: C,
+}
+
+pub struct DpGridChip<F: FieldExt, T: DpTransition<F>> {
+    config: DpGridConfig<T::Config>,
+    transition: T,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt, T: DpTransition<F>> Chip<F> for DpGridChip<F, T> {
+    type Config = DpGridConfig<T::Config>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt, T: DpTransition<F>> DpGridChip<F, T> {
+    pub fn construct(config: DpGridConfig<T::Config>) -> Self {
+        let transition = T::construct(config.transition.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self {
+            config,
+            transition,
+            constants,
+        }
+    }
+
+    /// The constants the boundary is loaded from, around a transition the caller has
+    /// configured.
+    pub fn configure(meta: &mut ConstraintSystem<F>, transition: T::Config) -> DpGridConfig<T::Config> {
+        let constants = ConstantsChip::configure(meta);
+        DpGridConfig { constants, transition }
+    }
+
+    /// The chip the steps are taken with.
+    pub fn transition(&self) -> &T {
+        &self.transition
+    }
+
+    /// The table over `a` and `b`.
+    pub fn fill(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &[AssignedCell<F, F>],
+        b: &[AssignedCell<F, F>],
+    ) -> Result<DpTable<F>, Halo2ExamplesError> {
+        let mut boundary = |i: usize, j: usize| {
+            let name = format!("T[{}][{}]", i, j);
+            self.constants.load_constant(layouter.namespace(|| name.clone()), &name, T::boundary(i, j))
+        };
+        let first_row = (0..=b.len()).map(|j| boundary(0, j)).collect::<Result<Vec<_>, _>>()?;
+        let first_column = (1..=a.len()).map(|i| boundary(i, 0)).collect::<Result<Vec<_>, _>>()?;
+
+        let mut cells = vec![first_row];
+        for (i, (a_i, first)) in a.iter().zip(first_column).enumerate() {
+            let mut row = vec![first];
+            for (j, b_j) in b.iter().enumerate() {
+                let neighbours = DpNeighbours {
+                    up: &cells[i][j + 1],
+                    left: &row[j],
+                    diag: &cells[i][j],
+                };
+                let name = || format!("T[{}][{}]", i + 1, j + 1);
+                let cell = self.transition.step(layouter.namespace(name), [a_i, b_j], neighbours)?;
+                row.push(cell);
+            }
+            cells.push(row);
+        }
+        Ok(DpTable { cells })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+        poly::Rotation,
+    };
+
+    use super::{DpGridChip, DpGridConfig, DpNeighbours, DpTransition};
+    use crate::{
+        chip::{LoadPrivateChip, LoadPrivateConfig},
+        error::{Halo2ExamplesError, ResultExt},
+        gadgets::min_max::{MinMaxChip, MinMaxConfig},
+        gate::GateBuilder,
+    };
+
+    const K: u32 = 10;
+
+    fn lcs(a: &[u8], b: &[u8]) -> usize {
+        let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for (i, a_i) in a.iter().enumerate() {
+            for (j, b_j) in b.iter().enumerate() {
+                table[i + 1][j + 1] =
+                    if a_i == b_j { table[i][j] + 1 } else { table[i][j + 1].max(table[i + 1][j]) };
+            }
+        }
+        table[a.len()][b.len()]
+    }
+
+    /// The longest common subsequence: `max(up, left)` from `MinMaxChip`, then a row
+    ///
+    ///       a   |  b   |  inv  |  diag  |  best  |  out
+    ///
+    /// with `same = 1 - (a - b) * inv` forced to 0 for different characters, and
+    /// `out = diag + 1` for equal ones, `best` otherwise.
+    #[derive(Debug, Clone)]
+    struct LcsConfig {
+        a: Column<Advice>,
+        b: Column<Advice>,
+        inv: Column<Advice>,
+        diag: Column<Advice>,
+        best: Column<Advice>,
+        out: Column<Advice>,
+        q_lcs: Selector,
+        min_max: MinMaxConfig,
+    }
+
+    struct LcsChip {
+        config: LcsConfig,
+        min_max: MinMaxChip<Fp, 8>,
+    }
+
+    impl LcsChip {
+        fn configure(meta: &mut ConstraintSystem<Fp>, instance: Column<Instance>) -> LcsConfig {
+            let min_max = MinMaxChip::<Fp, 8>::configure(meta, instance);
+            let [a, b, inv, diag, best, out] = [(); 6].map(|_| meta.advice_column());
+            let q_lcs = meta.selector();
+            for column in [a, b, diag, best, out] {
+                meta.enable_equality(column);
+            }
+
+            meta.create_gate("lcs step", |meta| {
+                let q_lcs = meta.query_selector(q_lcs);
+                let [a, b, inv, diag, best, out] =
+                    [a, b, inv, diag, best, out].map(|column| meta.query_advice(column, Rotation::cur()));
+                let one = Expression::Constant(Fp::one());
+                let diff = a - b;
+                let same = one.clone() - diff.clone() * inv;
+
+                GateBuilder::with_selector(q_lcs)
+                    .constrain("same is 0 unless a == b", diff * same.clone())
+                    .select("out = same ? diag + 1 : best", out, same, diag + one, best)
+            });
+
+            LcsConfig {
+                a,
+                b,
+                inv,
+                diag,
+                best,
+                out,
+                q_lcs,
+                min_max,
+            }
+        }
+    }
+
+    impl DpTransition<Fp> for LcsChip {
+        type Config = LcsConfig;
+
+        fn construct(config: LcsConfig) -> Self {
+            let min_max = MinMaxChip::construct(config.min_max.clone());
+            Self { config, min_max }
+        }
+
+        fn boundary(_i: usize, _j: usize) -> Fp {
+            Fp::zero()
+        }
+
+        fn step(
+            &self,
+            mut layouter: impl Layouter<Fp>,
+            inputs: [&AssignedCell<Fp, Fp>; 2],
+            neighbours: DpNeighbours<'_, Fp>,
+        ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+            let config = &self.config;
+            let best = self.min_max.max(layouter.namespace(|| "max(up, left)"), &[neighbours.up.clone(), neighbours.left.clone()])?;
+            layouter
+                .assign_region(
+                    || "lcs step",
+                    |mut region| {
+                        config.q_lcs.enable(&mut region, 0)?;
+                        let a = inputs[0].copy_advice(|| "a_i", &mut region, config.a, 0)?;
+                        let b = inputs[1].copy_advice(|| "b_j", &mut region, config.b, 0)?;
+                        let diag = neighbours.diag.copy_advice(|| "diag", &mut region, config.diag, 0)?;
+                        let best = best.copy_advice(|| "best", &mut region, config.best, 0)?;
+
+                        let diff = a.value().copied() - b.value().copied();
+                        let inv = diff.map(|diff| diff.invert().unwrap_or(Fp::zero()));
+                        region.assign_advice(|| "inv", config.inv, 0, || inv)?;
+                        let out = diff.zip(diag.value().copied()).zip(best.value().copied()).map(
+                            |((diff, diag), best)| if diff == Fp::zero() { diag + Fp::one() } else { best },
+                        );
+                        region.assign_advice(|| "out", config.out, 0, || out)
+                    },
+                )
+                .in_chip("LcsChip", "lcs step")
+        }
+    }
+
+    /// Exposes the length of the longest common subsequence of two private strings.
+    struct LcsCircuit {
+        a: Vec<u8>,
+        b: Vec<u8>,
+    }
+
+    impl Circuit<Fp> for LcsCircuit {
+        type Config = (DpGridConfig<LcsConfig>, LoadPrivateConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: self.a.clone(),
+                b: self.b.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            let lcs = LcsChip::configure(meta, instance);
+            (DpGridChip::configure(meta, lcs), LoadPrivateChip::configure(meta, 1))
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (grid_config, load_config) = config;
+            let grid = DpGridChip::<Fp, LcsChip>::construct(grid_config);
+            let load_chip = LoadPrivateChip::construct(load_config);
+
+            let to_field = |chars: &[u8]| chars.iter().map(|c| Value::known(Fp::from(*c as u64))).collect::<Vec<_>>();
+            let a = load_chip.load_privates(layouter.namespace(|| "a"), &to_field(&self.a))?;
+            let b = load_chip.load_privates(layouter.namespace(|| "b"), &to_field(&self.b))?;
+            let table = grid.fill(layouter.namespace(|| "lcs table"), &a, &b)?;
+            grid.transition().min_max.expose_public(layouter.namespace(|| "length"), table.last(), 0)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_lcs() {
+        for (a, b) in [("ABCBDAB", "BDCABA"), ("kitten", "sitting"), ("abc", "xyz"), ("a", "a")] {
+            let (a, b) = (a.as_bytes(), b.as_bytes());
+            let length = lcs(a, b) as u64;
+            let circuit = LcsCircuit {
+                a: a.to_vec(),
+                b: b.to_vec(),
+            };
+            MockProver::run(K, &circuit, vec![vec![Fp::from(length)]]).unwrap().assert_satisfied();
+
+            let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(length + 1)]]).unwrap();
+            assert!(prover.verify().is_err(), "{:?} and {:?} have no longer common subsequence", a, b);
+        }
+        assert_eq!(lcs(b"ABCBDAB", b"BDCABA"), 4);
+    }
+}
@@ -0,0 +1,453 @@
+//! Pallas points as pairs of cells, with assertions on them.
+//!
+//! Pallas is `y^2 = x^3 + 5` over `Fp`, the field these circuits work in, so its points are
+//! native: no limbs, one gate per assertion. The identity has no affine coordinates, and
+//! `EccChip` never witnesses it: every point it assigns is on the curve. `ScalarMulChip`,
+//! whose sums can be the identity, writes it as `(0, 0)` instead, the coordinates of no
+//! point on the curve, and `AssignedPoint::value` reads them back as the identity.
+//!
+//!        x    |   y   |  x_b  |  y_b  | dx_inv | dy_inv | sy_inv | is_equal | is_negation | selector
+//!       ----------------------------------------------------------------------------------------------
+//!        x    |   y   |       |       |        |        |        |          |             | q_on_curve
+//!        x_a  |  y_a  |  x_b  |  y_b  |        |        |        |          |             | q_negation
+//!        x_a  |  y_a  |  x_b  |  y_b  |  ...   |  ...   |  ...   |    e     |      n      | q_compare
+//!
+//! with `y^2 = x^3 + 5` on a `q_on_curve` row, `x_a = x_b` and `y_a = -y_b` on a `q_negation`
+//! row, and on a `q_compare` row, by `IsZeroChip` on `x_a - x_b`, `y_a - y_b` and `y_a + y_b`,
+//!
+//!     e = [x_a == x_b] * [y_a == y_b],   n = [x_a == x_b] * [y_a == -y_b]
+//!
+//! Pallas has prime order, so no point has `y = 0` and `e` and `n` are never both 1.
+
+use group::{prime::PrimeCurveAffine, Curve, Group};
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    circuit::*,
+    pasta::{pallas, Fp},
+    plonk::*,
+    poly::Rotation,
+};
+
+use super::is_zero::{IsZeroChip, IsZeroConfig};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// A point on Pallas, or the identity as `(0, 0)`.
+#[derive(Debug, Clone)]
+pub struct AssignedPoint {
+    pub x: AssignedCell<Fp, Fp>,
+    pub y: AssignedCell<Fp, Fp>,
+}
+
+impl AssignedPoint {
+    pub fn value(&self) -> Value<pallas::Point> {
+        self.x.value().zip(self.y.value()).map(|(x, y)| {
+            if (*x, *y) == (Fp::zero(), Fp::zero()) {
+                pallas::Point::identity()
+            } else {
+                Option::<pallas::Affine>::from(pallas::Affine::from_xy(*x, *y))
+                    .expect("an assigned point is on the curve")
+                    .to_curve()
+            }
+        })
+    }
+}
+
+/// The affine coordinates of `point`, which must not be the identity.
+pub fn coordinates(point: pallas::Affine) -> (Fp, Fp) {
+    let coordinates = Option::from(point.coordinates()).expect("the identity has no affine coordinates");
+    (*coordinates.x(), *coordinates.y())
+}
+
+#[derive(Debug, Clone)]
+pub struct EccConfig {
+    x: Column<Advice>,
+    y: Column<Advice>,
+    x_b: Column<Advice>,
+    y_b: Column<Advice>,
+    is_equal: Column<Advice>,
+    is_negation: Column<Advice>,
+    q_on_curve: Selector,
+    q_negation: Selector,
+    q_compare: Selector,
+    dx_is_zero: IsZeroConfig<Fp>,
+    dy_is_zero: IsZeroConfig<Fp>,
+    sy_is_zero: IsZeroConfig<Fp>,
+}
+
+pub struct EccChip {
+    config: EccConfig,
+}
+
+impl Chip<Fp> for EccChip {
+    type Config = EccConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl EccChip {
+    pub fn construct(config: EccConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> EccConfig {
+        let [x, y, x_b, y_b, dx_inv, dy_inv, sy_inv, is_equal, is_negation] = [(); 9].map(|_| meta.advice_column());
+        let q_on_curve = meta.selector();
+        let q_negation = meta.selector();
+        let q_compare = meta.selector();
+
+        for column in [x, y, x_b, y_b, is_equal, is_negation] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("on curve", |meta| {
+            let q_on_curve = meta.query_selector(q_on_curve);
+            let x = meta.query_advice(x, Rotation::cur());
+            let y = meta.query_advice(y, Rotation::cur());
+
+            GateBuilder::with_selector(q_on_curve).equal(
+                "y^2 = x^3 + 5",
+                y.clone() * y,
+                x.clone() * x.clone() * x + Expression::Constant(Fp::from(5)),
+            )
+        });
+
+        meta.create_gate("negation", |meta| {
+            let q_negation = meta.query_selector(q_negation);
+            let [x, y, x_b, y_b] = [x, y, x_b, y_b].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_negation).equal("x_a = x_b", x, x_b).constrain("y_a = -y_b", y + y_b)
+        });
+
+        let query = move |meta: &mut VirtualCells<'_, Fp>| {
+            [x, y, x_b, y_b].map(|column| meta.query_advice(column, Rotation::cur()))
+        };
+        let dx_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_compare),
+            |meta| {
+                let [x, _, x_b, _] = query(meta);
+                x - x_b
+            },
+            dx_inv,
+        );
+        let dy_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_compare),
+            |meta| {
+                let [_, y, _, y_b] = query(meta);
+                y - y_b
+            },
+            dy_inv,
+        );
+        let sy_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_compare),
+            |meta| {
+                let [_, y, _, y_b] = query(meta);
+                y + y_b
+            },
+            sy_inv,
+        );
+
+        meta.create_gate("compare", |meta| {
+            let q_compare = meta.query_selector(q_compare);
+            let is_equal = meta.query_advice(is_equal, Rotation::cur());
+            let is_negation = meta.query_advice(is_negation, Rotation::cur());
+            let same_x = dx_is_zero.is_zero_expr.clone();
+
+            GateBuilder::with_selector(q_compare)
+                .equal("is_equal", is_equal, same_x.clone() * dy_is_zero.is_zero_expr.clone())
+                .equal("is_negation", is_negation, same_x * sy_is_zero.is_zero_expr.clone())
+        });
+
+        EccConfig {
+            x,
+            y,
+            x_b,
+            y_b,
+            is_equal,
+            is_negation,
+            q_on_curve,
+            q_negation,
+            q_compare,
+            dx_is_zero,
+            dy_is_zero,
+            sy_is_zero,
+        }
+    }
+
+    /// Witnesses `point`, which must not be the identity.
+    pub fn witness_point(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        point: Value<pallas::Affine>,
+    ) -> Result<AssignedPoint, Halo2ExamplesError> {
+        let config = &self.config;
+        let coordinates = point.map(coordinates);
+        layouter
+            .assign_region(
+                || "witness point",
+                |mut region| {
+                    config.q_on_curve.enable(&mut region, 0)?;
+                    let x = region.assign_advice(|| "x", config.x, 0, || coordinates.map(|(x, _)| x))?;
+                    let y = region.assign_advice(|| "y", config.y, 0, || coordinates.map(|(_, y)| y))?;
+                    Ok(AssignedPoint { x, y })
+                },
+            )
+            .in_chip("EccChip", "witness point")
+    }
+
+    /// Constrains `(x, y)` to lie on the curve.
+    pub fn assert_on_curve(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        x: &AssignedCell<Fp, Fp>,
+        y: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedPoint, Halo2ExamplesError> {
+        let config = &self.config;
+        layouter
+            .assign_region(
+                || "on curve",
+                |mut region| {
+                    config.q_on_curve.enable(&mut region, 0)?;
+                    let x = x.copy_advice(|| "x", &mut region, config.x, 0)?;
+                    let y = y.copy_advice(|| "y", &mut region, config.y, 0)?;
+                    Ok(AssignedPoint { x, y })
+                },
+            )
+            .in_chip("EccChip", "on curve")
+    }
+
+    pub fn assert_equal(&self, mut layouter: impl Layouter<Fp>, a: &AssignedPoint, b: &AssignedPoint) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .assign_region(
+                || "points are equal",
+                |mut region| {
+                    region.constrain_equal(a.x.cell(), b.x.cell())?;
+                    region.constrain_equal(a.y.cell(), b.y.cell())
+                },
+            )
+            .in_chip("EccChip", "points are equal")
+    }
+
+    /// Constrains `b = -a`.
+    pub fn assert_negation(&self, mut layouter: impl Layouter<Fp>, a: &AssignedPoint, b: &AssignedPoint) -> Result<(), Halo2ExamplesError> {
+        let config = &self.config;
+        layouter
+            .assign_region(
+                || "points are negations",
+                |mut region| {
+                    config.q_negation.enable(&mut region, 0)?;
+                    self.copy_pair(&mut region, a, b)
+                },
+            )
+            .in_chip("EccChip", "points are negations")
+    }
+
+    /// Boolean cells for `a == b` and `a == -b`.
+    pub fn compare(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        a: &AssignedPoint,
+        b: &AssignedPoint,
+    ) -> Result<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>), Halo2ExamplesError> {
+        let config = &self.config;
+        let dx_is_zero = IsZeroChip::construct(config.dx_is_zero.clone());
+        let dy_is_zero = IsZeroChip::construct(config.dy_is_zero.clone());
+        let sy_is_zero = IsZeroChip::construct(config.sy_is_zero.clone());
+        layouter
+            .assign_region(
+                || "compare points",
+                |mut region| {
+                    config.q_compare.enable(&mut region, 0)?;
+                    self.copy_pair(&mut region, a, b)?;
+
+                    let dx = a.x.value().copied() - b.x.value().copied();
+                    let dy = a.y.value().copied() - b.y.value().copied();
+                    let sy = a.y.value().copied() + b.y.value().copied();
+                    dx_is_zero.assign(&mut region, 0, dx)?;
+                    dy_is_zero.assign(&mut region, 0, dy)?;
+                    sy_is_zero.assign(&mut region, 0, sy)?;
+
+                    let bit = |same_x: bool, same_y: bool| Fp::from((same_x && same_y) as u64);
+                    let is_equal = dx.zip(dy).map(|(dx, dy)| bit(dx == Fp::zero(), dy == Fp::zero()));
+                    let is_negation = dx.zip(sy).map(|(dx, sy)| bit(dx == Fp::zero(), sy == Fp::zero()));
+                    let is_equal = region.assign_advice(|| "is_equal", config.is_equal, 0, || is_equal)?;
+                    let is_negation = region.assign_advice(|| "is_negation", config.is_negation, 0, || is_negation)?;
+                    Ok((is_equal, is_negation))
+                },
+            )
+            .in_chip("EccChip", "compare points")
+    }
+
+    fn copy_pair(&self, region: &mut Region<'_, Fp>, a: &AssignedPoint, b: &AssignedPoint) -> Result<(), Error> {
+        let config = &self.config;
+        a.x.copy_advice(|| "x_a", region, config.x, 0)?;
+        a.y.copy_advice(|| "y_a", region, config.y, 0)?;
+        b.x.copy_advice(|| "x_b", region, config.x_b, 0)?;
+        b.y.copy_advice(|| "y_b", region, config.y_b, 0)?;
+        Ok(())
+    }
+}
+
+/// Witnesses two private points and exposes whether they are equal and whether they are
+/// negations of each other.
+#[derive(Default)]
+pub struct ComparePointsCircuit {
+    pub a: Value<pallas::Affine>,
+    pub b: Value<pallas::Affine>,
+}
+
+impl ComparePointsCircuit {
+    /// `[a == b, a == -b]`, as field elements.
+    pub fn outputs(a: pallas::Affine, b: pallas::Affine) -> [Fp; 2] {
+        [Fp::from((a == b) as u64), Fp::from((a == -b) as u64)]
+    }
+}
+
+impl Circuit<Fp> for ComparePointsCircuit {
+    type Config = (EccConfig, PublicOutputsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        (EccChip::configure(meta), PublicOutputs::configure(meta, instance))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, outputs_config) = config;
+        let chip = EccChip::construct(config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+
+        let a = chip.witness_point(layouter.namespace(|| "a"), self.a)?;
+        let b = chip.witness_point(layouter.namespace(|| "b"), self.b)?;
+        let (is_equal, is_negation) = chip.compare(layouter.namespace(|| "compare"), &a, &b)?;
+        outputs.expose("is_equal", &is_equal);
+        outputs.expose("is_negation", &is_negation);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for ComparePointsCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let a = known(self.a, "a")?;
+        let b = known(self.b, "b")?;
+        if bool::from(a.is_identity()) || bool::from(b.is_identity()) {
+            return Err(InvalidWitness::InvalidParameter("the identity has no affine coordinates".to_string()));
+        }
+        check_instances(instances, &Self::outputs(a, b))
+    }
+}
+
+/// Witnesses a private pair of coordinates and asserts it is a point, for testing the
+/// on-curve gate on pairs `witness_point` would never produce.
+#[derive(Default)]
+pub struct OnCurveCircuit {
+    pub x: Value<Fp>,
+    pub y: Value<Fp>,
+}
+
+impl Circuit<Fp> for OnCurveCircuit {
+    type Config = (EccConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        (EccChip::configure(meta), LoadPrivateChip::configure(meta, 2))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = EccChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let [x, y]: [_; 2] = load_chip
+            .load_privates(layouter.namespace(|| "load coordinates"), &[self.x, self.y])?
+            .try_into()
+            .expect("two cells loaded");
+        chip.assert_on_curve(layouter.namespace(|| "on curve"), &x, &y)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for OnCurveCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let x = known(self.x, "x")?;
+        let y = known(self.y, "y")?;
+        if bool::from(pallas::Affine::from_xy(x, y).is_none()) {
+            return Err(InvalidWitness::InvalidParameter("(x, y) is not on the curve".to_string()));
+        }
+        check_instances(instances, &[])
+    }
+}
+
+/// `[k] G` for the Pallas generator `G`.
+pub fn multiple_of_generator(k: u64) -> pallas::Affine {
+    (pallas::Point::generator() * pallas::Scalar::from(k)).to_affine()
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::{coordinates, multiple_of_generator, ComparePointsCircuit, OnCurveCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 5;
+
+    #[test]
+    fn test_compare_points() {
+        let g = multiple_of_generator(1);
+        for (b, outputs) in [(g, [1, 0]), (-g, [0, 1]), (multiple_of_generator(2), [0, 0])] {
+            let circuit = ComparePointsCircuit {
+                a: Value::known(g),
+                b: Value::known(b),
+            };
+            let instances = vec![outputs.map(Fp::from).to_vec()];
+            assert!(circuit.validate_witness(&instances).is_ok());
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+
+            let wrong = vec![outputs.map(|output| Fp::from(1 - output)).to_vec()];
+            assert!(MockProver::run(K, &circuit, wrong).unwrap().verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_on_curve() {
+        let (x, y) = coordinates(multiple_of_generator(7));
+        let circuit = OnCurveCircuit {
+            x: Value::known(x),
+            y: Value::known(y),
+        };
+        assert!(circuit.validate_witness(&[]).is_ok());
+        MockProver::run(K, &circuit, vec![]).unwrap().assert_satisfied();
+
+        let circuit = OnCurveCircuit {
+            x: Value::known(x),
+            y: Value::known(y + Fp::one()),
+        };
+        assert!(circuit.validate_witness(&[]).is_err());
+        assert!(MockProver::run(K, &circuit, vec![]).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,344 @@
+//! Hashes field elements to a Pallas point by try-and-increment over Poseidon.
+//!
+//! For `h = commit_all(data)` and counters `i = 0, 1, ...` the candidate `x_i = Poseidon(i, h)`
+//! is accepted at the first `i` for which `x_i^3 + 5` is a square, with the even one of its
+//! two roots as `y`. The circuit has to prove that no earlier counter was acceptable, or a
+//! prover could skip ahead and get another point for the same data, so every counter before
+//! the accepted one comes with a root `s_i` of `5 * (x_i^3 + 5)`: 5 generates `Fp^*`, so it
+//! is not a square, and `x_i^3 + 5` was not either.
+//!
+//! The counters run to `MAX_TRIES`, each laid out on a row whether it is used or not:
+//!
+//!        x    |  found  |  after  |    acc    |   s   |  y  |  q_try
+//!       -----------------------------------------------------------------
+//!        x_0  |   f_0   |    0    |     0     |  s_0  |     |    1
+//!        x_1  |   f_1   |   a_1   |   acc_1   |  s_1  |     |    1
+//!        ...  |   ...   |   ...   |    ...    |  ...  |     |    1
+//!             |         |    1    |     x     |       |  y  |
+//!
+//!     f_i boolean,   a_{i+1} = a_i + f_i,   acc_{i+1} = acc_i + f_i * x_i,
+//!     (1 - a_i - f_i) * (s_i^2 - 5 * (x_i^3 + 5)) = 0
+//!
+//! so exactly one `f_i` is set, every row above it proves a non-square, and `acc` ends at the
+//! accepted `x`. `EccChip` then puts `(x, y)` on the curve, and `BitsChip` unpacks `y` into
+//! 254 bits, the lowest 0: an even integer below `2^254 < p`, which only one root can be. If
+//! the even root is `2^254` or more, which happens with probability about `2^-126`, or no
+//! counter is accepted, the data has no point.
+
+use ff::{Field, PrimeField, PrimeFieldBits};
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    circuit::*,
+    pasta::{pallas, Fp},
+    plonk::*,
+    poly::Rotation,
+};
+
+use super::{
+    bits::{BitsChip, BitsConfig},
+    commit::{commit, commit_all, CommitChip, CommitConfig},
+    ecc::{coordinates, AssignedPoint, EccChip, EccConfig},
+};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The number of counters tried; all of them fail for about one input in 2^16.
+pub const MAX_TRIES: usize = 16;
+
+/// A generator of `Fp^*`, hence not a square.
+const NON_SQUARE: u64 = 5;
+
+/// The candidate x-coordinate for counter `i`.
+fn candidate(hash: Fp, i: usize) -> Fp {
+    commit(Fp::from(i as u64), hash)
+}
+
+/// The point `data` hashes to, with the counter it was found at, computed natively.
+pub fn hash_to_curve(data: &[Fp]) -> Option<(pallas::Affine, usize)> {
+    let hash = commit_all(data);
+    (0..MAX_TRIES).find_map(|i| {
+        let x = candidate(hash, i);
+        let y = Option::<Fp>::from((x.square() * x + Fp::from(5)).sqrt())?;
+        Some((x, y, i))
+    })
+    .and_then(|(x, y, i)| {
+        let y = if bool::from(y.is_odd()) { -y } else { y };
+        if y.to_le_bits()[254] {
+            return None;
+        }
+        Option::from(pallas::Affine::from_xy(x, y)).map(|point| (point, i))
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct HashToCurveConfig {
+    x: Column<Advice>,
+    found: Column<Advice>,
+    after: Column<Advice>,
+    acc: Column<Advice>,
+    s: Column<Advice>,
+    y: Column<Advice>,
+    q_try: Selector,
+    commit: CommitConfig,
+    ecc: EccConfig,
+    bits: BitsConfig,
+    constants: ConstantsConfig,
+}
+
+pub struct HashToCurveChip {
+    config: HashToCurveConfig,
+    commit: CommitChip,
+    ecc: EccChip,
+    bits: BitsChip<Fp>,
+    constants: ConstantsChip<Fp>,
+}
+
+impl Chip<Fp> for HashToCurveChip {
+    type Config = HashToCurveConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl HashToCurveChip {
+    pub fn construct(config: HashToCurveConfig) -> Self {
+        let commit = CommitChip::construct(config.commit.clone());
+        let ecc = EccChip::construct(config.ecc.clone());
+        let bits = BitsChip::construct(config.bits.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self {
+            config,
+            commit,
+            ecc,
+            bits,
+            constants,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, instance: Column<Instance>) -> HashToCurveConfig {
+        let commit = CommitChip::configure(meta);
+        let ecc = EccChip::configure(meta);
+        let bits = BitsChip::configure(meta, instance);
+        let [counter, x, found, after, acc, s, y] = [(); 7].map(|_| meta.advice_column());
+        let q_try = meta.selector();
+
+        // the counters, the sums' starting zeros and the final `after` of 1 are loaded constants
+        let constants = ConstantsChip::configure_with(meta, counter);
+        for column in [x, after, acc, y] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("try counter", |meta| {
+            let q_try = meta.query_selector(q_try);
+            let [x, found, after, acc, s] = [x, found, after, acc, s].map(|column| meta.query_advice(column, Rotation::cur()));
+            let after_next = meta.query_advice(after, Rotation::next());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let one = Expression::Constant(Fp::one());
+            let rhs = x.clone() * x.clone() * x.clone() + Expression::Constant(Fp::from(5));
+
+            GateBuilder::with_selector(q_try)
+                .boolean("found is boolean", found.clone())
+                .equal("after_next = after + found", after_next, after.clone() + found.clone())
+                .equal("acc_next = acc + found * x", acc_next, acc + found.clone() * x)
+                .constrain(
+                    "x^3 + 5 is not a square before the found counter",
+                    (one - after - found) * (s.clone() * s - rhs * Fp::from(NON_SQUARE)),
+                )
+        });
+
+        HashToCurveConfig {
+            x,
+            found,
+            after,
+            acc,
+            s,
+            y,
+            q_try,
+            commit,
+            ecc,
+            bits,
+            constants,
+        }
+    }
+
+    /// The point `data` hashes to, like `hash_to_curve`. Fails to synthesize if there is none.
+    pub fn hash_to_curve(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        data: &[AssignedCell<Fp, Fp>],
+    ) -> Result<AssignedPoint, Halo2ExamplesError> {
+        let config = &self.config;
+        let hash = self.commit.commit_all(layouter.namespace(|| "hash data"), data)?;
+
+        let counters = (0..MAX_TRIES)
+            .map(|i| self.constants.load_constant(layouter.namespace(|| format!("counter {}", i)), "counter", Fp::from(i as u64)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let candidates = counters
+            .iter()
+            .enumerate()
+            .map(|(i, counter)| self.commit.commit(layouter.namespace(|| format!("x_{}", i)), counter, &hash))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let point = data
+            .iter()
+            .map(|cell| cell.value().copied())
+            .collect::<Value<Vec<_>>>()
+            .map(|data| hash_to_curve(&data));
+        point.error_if_known_and(Option::is_none).in_chip("HashToCurveChip", "tries")?;
+        let point = point.map(|point| point.expect("checked above"));
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", Fp::zero())?;
+        let one = self.constants.load_constant(layouter.namespace(|| "one"), "one", Fp::one())?;
+
+        let (x, y) = layouter
+            .assign_region(
+                || "tries",
+                |mut region| {
+                    zero.copy_advice(|| "after_0", &mut region, config.after, 0)?;
+                    zero.copy_advice(|| "acc_0", &mut region, config.acc, 0)?;
+
+                    let mut acc = Value::known(Fp::zero());
+                    for (i, x) in candidates.iter().enumerate() {
+                        config.q_try.enable(&mut region, i)?;
+                        x.copy_advice(|| format!("x_{}", i), &mut region, config.x, i)?;
+
+                        let found = point.map(|(_, counter)| counter == i);
+                        let before = point.map(|(_, counter)| i < counter);
+                        let s = x.value().zip(before).map(|(x, before)| {
+                            let rhs = (x.square() * x + Fp::from(5)) * Fp::from(NON_SQUARE);
+                            if before {
+                                Option::from(rhs.sqrt()).expect("x^3 + 5 is not a square before the found counter")
+                            } else {
+                                Fp::zero()
+                            }
+                        });
+                        region.assign_advice(|| format!("f_{}", i), config.found, i, || found.map(|found| Fp::from(found as u64)))?;
+                        region.assign_advice(|| format!("s_{}", i), config.s, i, || s)?;
+
+                        acc = acc.zip(found).zip(x.value()).map(|((acc, found), x)| if found { acc + x } else { acc });
+                        if i + 1 < MAX_TRIES {
+                            let after = point.map(|(_, counter)| Fp::from((i + 1 > counter) as u64));
+                            region.assign_advice(|| format!("a_{}", i + 1), config.after, i + 1, || after)?;
+                            region.assign_advice(|| format!("acc_{}", i + 1), config.acc, i + 1, || acc)?;
+                        }
+                    }
+
+                    one.copy_advice(|| "after", &mut region, config.after, MAX_TRIES)?;
+                    let x = region.assign_advice(|| "x", config.acc, MAX_TRIES, || acc)?;
+                    let y = region.assign_advice(|| "y", config.y, MAX_TRIES, || point.map(|(point, _)| coordinates(point).1))?;
+                    Ok((x, y))
+                },
+            )
+            .in_chip("HashToCurveChip", "tries")?;
+
+        let point = self.ecc.assert_on_curve(layouter.namespace(|| "on curve"), &x, &y)?;
+        let bits = self.bits.unpack(layouter.namespace(|| "y bits"), &point.y, 254)?;
+        layouter
+            .assign_region(|| "y is even", |mut region| region.constrain_equal(bits[0].cell(), zero.cell()))
+            .in_chip("HashToCurveChip", "y is even")?;
+        Ok(point)
+    }
+}
+
+/// Hashes private data to a point and exposes its coordinates.
+#[derive(Default)]
+pub struct HashToCurveCircuit {
+    pub data: Vec<Value<Fp>>,
+}
+
+impl Circuit<Fp> for HashToCurveCircuit {
+    type Config = (HashToCurveConfig, LoadPrivateConfig, PublicOutputsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            data: vec![Value::unknown(); self.data.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let hash_to_curve = HashToCurveChip::configure(meta, instance);
+        (hash_to_curve, LoadPrivateChip::configure(meta, 1), PublicOutputs::configure(meta, instance))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, load_config, outputs_config) = config;
+        let chip = HashToCurveChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+
+        let data = load_chip.load_privates(layouter.namespace(|| "load data"), &self.data)?;
+        let point = chip.hash_to_curve(layouter.namespace(|| "hash to curve"), &data)?;
+        outputs.expose("x", &point.x);
+        outputs.expose("y", &point.y);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for HashToCurveCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let data = self.data.iter().map(|value| known(*value, "data")).collect::<Result<Vec<_>, _>>()?;
+        let (point, _) = hash_to_curve(&data)
+            .ok_or_else(|| InvalidWitness::InvalidParameter("the data hashes to no point".to_string()))?;
+        let (x, y) = coordinates(point);
+        check_instances(instances, &[x, y])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::PrimeField;
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::{hash_to_curve, HashToCurveCircuit};
+    use crate::{gadgets::ecc::coordinates, validate::ValidateWitness};
+
+    const K: u32 = 12;
+
+    fn run(data: &[Fp], instances: Vec<Fp>) -> MockProver<Fp> {
+        let circuit = HashToCurveCircuit {
+            data: data.iter().copied().map(Value::known).collect(),
+        };
+        MockProver::run(K, &circuit, vec![instances]).unwrap()
+    }
+
+    #[test]
+    fn test_hash_to_curve() {
+        // data whose first counter fails, so the non-square rows are exercised
+        let data = (0..)
+            .map(|i| vec![Fp::from(i), Fp::from(7)])
+            .find(|data| hash_to_curve(data).expect("a point").1 > 0)
+            .unwrap();
+        let (point, _) = hash_to_curve(&data).unwrap();
+        let (x, y) = coordinates(point);
+        assert!(!bool::from(y.is_odd()));
+
+        let circuit = HashToCurveCircuit {
+            data: data.iter().copied().map(Value::known).collect(),
+        };
+        assert!(circuit.validate_witness(&[vec![x, y]]).is_ok());
+        run(&data, vec![x, y]).assert_satisfied();
+
+        // the other root
+        assert!(run(&data, vec![x, -y]).verify().is_err());
+
+        // other data
+        let (other, _) = hash_to_curve(&[Fp::from(1), Fp::from(2), Fp::from(3)]).unwrap();
+        assert_ne!(other, point);
+        let (x, y) = coordinates(other);
+        assert!(run(&data, vec![x, y]).verify().is_err());
+    }
+}
@@ -0,0 +1,50 @@
+//! The hash a gadget is generic over, so an application can pick the one its verifier
+//! computes cheaply: Poseidon for another circuit or a Pasta-native contract, MiMC where
+//! its simpler permutation matters, or a bit-oriented hash for an EVM.
+//!
+//! A hasher compresses two field elements, `hash2(a, b)`, with `b` the key or chaining
+//! value, and hashes a list by chaining that from its length, as `commit_all` does:
+//!
+//!     hash_many([x_0, ..., x_{n-1}]) = h_n,   h_0 = n,   h_{i+1} = hash2(x_i, h_i)
+//!
+//! Each chip has a native counterpart, so the trees and commitments gadgets prove against
+//! can be computed outside the circuit with the same hash.
+//!
+//! Implemented by `CommitChip` for Poseidon and `MimcChip` for MiMC. `Sha256Chip` hashes
+//! 32-bit words rather than field elements, so it is not one.
+
+use std::fmt::Debug;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter},
+    plonk::ConstraintSystem,
+};
+
+use crate::error::Halo2ExamplesError;
+
+pub trait CircuitHasher<F: FieldExt>: Sized + Clone + Debug {
+    type Config: Clone + Debug;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config;
+
+    fn construct(config: Self::Config) -> Self;
+
+    /// `hash2(a, b)`, computed natively.
+    fn hash2_native(a: F, b: F) -> F;
+
+    /// The hash chain over `values`, starting from their number, computed natively.
+    fn hash_many_native(values: &[F]) -> F {
+        values.iter().fold(F::from(values.len() as u64), |hash, value| Self::hash2_native(*value, hash))
+    }
+
+    fn hash2(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError>;
+
+    /// The hash chain over `values`, like `hash_many_native`.
+    fn hash_many(&self, layouter: impl Layouter<F>, values: &[AssignedCell<F, F>]) -> Result<AssignedCell<F, F>, Halo2ExamplesError>;
+}
@@ -0,0 +1,217 @@
+//! Polynomial evaluation by Horner's rule, from the highest coefficient down:
+//!
+//!     acc_0 = 0,   acc_{i+1} = acc_i * x + c_{n-1-i},   acc_n = c_0 + c_1 x + ... + c_{n-1} x^{n-1}
+//!
+//! One row per coefficient, with `x` carried down its column so it is copied in only once:
+//!
+//!       coeff    |   x   |   acc    |  q_horner
+//!     ---------------------------------------------
+//!                |   x   |    0     |     1
+//!     c_{n-1}    |   x   |  acc_1   |     1
+//!       ...      |  ...  |   ...    |    ...
+//!       c_1      |   x   | acc_{n-1}|     1
+//!       c_0      |   x   |  acc_n   |
+//!
+//! The gate on row `i` checks `acc_{i+1} = acc_i * x + coeff` and `x` unchanged on the row
+//! below, so a polynomial of degree `d` costs `d + 2` rows.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+#[derive(Debug, Clone)]
+pub struct HornerConfig {
+    coeff: Column<Advice>,
+    x: Column<Advice>,
+    acc: Column<Advice>,
+    q_horner: Selector,
+    instance: Column<Instance>,
+    constants: ConstantsConfig,
+}
+
+pub struct HornerChip<F: FieldExt> {
+    config: HornerConfig,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for HornerChip<F> {
+    type Config = HornerConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> HornerChip<F> {
+    pub fn construct(config: HornerConfig) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> HornerConfig {
+        let coeff = meta.advice_column();
+        let x = meta.advice_column();
+        let acc = meta.advice_column();
+        let q_horner = meta.selector();
+
+        // `acc_0 == 0` is copied from a loaded constant
+        let constants = ConstantsChip::configure_with(meta, acc);
+        meta.enable_equality(coeff);
+        meta.enable_equality(x);
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+
+        meta.create_gate("horner step", |meta| {
+            let q_horner = meta.query_selector(q_horner);
+            let coeff = meta.query_advice(coeff, Rotation::next());
+            let x_cur = meta.query_advice(x, Rotation::cur());
+            let x_next = meta.query_advice(x, Rotation::next());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+
+            GateBuilder::with_selector(q_horner)
+                .equal("acc_next = acc * x + coeff", acc_next, acc_cur * x_cur.clone() + coeff)
+                .equal("x_next = x", x_next, x_cur)
+        });
+
+        HornerConfig {
+            coeff,
+            x,
+            acc,
+            q_horner,
+            instance,
+            constants,
+        }
+    }
+
+    /// `p(x)` for the polynomial with coefficients `coeffs`, the constant term first. 0 if
+    /// there are none.
+    pub fn evaluate(
+        &self,
+        mut layouter: impl Layouter<F>,
+        coeffs: &[AssignedCell<F, F>],
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let config = &self.config;
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", F::zero())?;
+        layouter
+            .assign_region(
+                || "horner",
+                |mut region| {
+                    let mut x_cell = x.copy_advice(|| "x", &mut region, config.x, 0)?;
+                    let mut acc = zero.copy_advice(|| "acc_0", &mut region, config.acc, 0)?;
+
+                    for (i, coeff) in coeffs.iter().rev().enumerate() {
+                        config.q_horner.enable(&mut region, i)?;
+                        let offset = i + 1;
+                        let coeff = coeff.copy_advice(
+                            || format!("c_{}", coeffs.len() - offset),
+                            &mut region,
+                            config.coeff,
+                            offset,
+                        )?;
+                        x_cell = region.assign_advice(|| "x", config.x, offset, || x_cell.value().copied())?;
+                        let next = acc.value().copied() * x_cell.value().copied() + coeff.value().copied();
+                        acc = region.assign_advice(|| format!("acc_{}", offset), config.acc, offset, || next)?;
+                    }
+                    Ok(acc)
+                },
+            )
+            .in_chip("HornerChip", "horner")
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .constrain_instance(cell.cell(), self.config.instance, row)
+            .map_err(|err| Halo2ExamplesError::new("HornerChip", err).at(row, "instance"))
+    }
+}
+
+/// Exposes a private point and the value of a private polynomial there.
+#[derive(Default)]
+pub struct HornerCircuit<F> {
+    pub coeffs: Vec<Value<F>>,
+    pub x: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for HornerCircuit<F> {
+    type Config = (HornerConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { coeffs: vec![Value::unknown(); self.coeffs.len()], x: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let horner = HornerChip::configure(meta, instance);
+        (horner, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = HornerChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let coeffs = load_chip.load_privates(layouter.namespace(|| "load coefficients"), &self.coeffs)?;
+        let x = load_chip.load_private(layouter.namespace(|| "load x"), self.x)?;
+        let y = chip.evaluate(layouter.namespace(|| "p(x)"), &coeffs, &x)?;
+        chip.expose_public(layouter.namespace(|| "x"), &x, 0)?;
+        chip.expose_public(layouter.namespace(|| "y"), &y, 1)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ValidateWitness<F> for HornerCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let x = known(self.x, "x")?;
+        // sum c_i * x^i, term by term rather than by Horner's rule
+        let (y, _) =
+            self.coeffs.iter().try_fold((F::zero(), F::one()), |(y, power), coeff| -> Result<_, InvalidWitness> {
+                Ok((y + known(*coeff, "coeff")? * power, power * x))
+            })?;
+        check_instances(instances, &[x, y])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver};
+
+    use super::HornerCircuit;
+    use crate::{test_over_fields, validate::ValidateWitness};
+
+    const K: u32 = 5;
+
+    fn test_horner<F: FieldExt>() {
+        // p(x) = 7 - 2x + 3x^3, p(5) = 372
+        let circuit = HornerCircuit {
+            coeffs: [F::from(7), -F::from(2), F::zero(), F::from(3)].map(Value::known).to_vec(),
+            x: Value::known(F::from(5)),
+        };
+        let instances = vec![vec![F::from(5), F::from(372)]];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+
+        let prover = MockProver::run(K, &circuit, vec![vec![F::from(5), F::from(371)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    test_over_fields!(test_horner);
+}
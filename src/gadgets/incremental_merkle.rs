@@ -0,0 +1,364 @@
+//! Appends to an incremental Merkle tree, the append-only tree of deposit contracts: leaves
+//! fill the tree from the left, and the contract keeps only the root, the next index and the
+//! frontier, the last left node hashed on each level.
+//!
+//! Every leaf right of the next index `i` is empty, so the path of leaf `i` is known from
+//! the frontier: on a level where `i` goes left, the sibling is an empty subtree, `default_l`
+//! as in `smt`; where it goes right, the sibling is a full subtree, the frontier's node
+//! `f_l`. Hashing the empty leaf and then the new one up that path gives both roots:
+//!
+//!        bit    |  filled  |  zero (fixed)  |  sibling  |  q_frontier
+//!     -------------------------------------------------------------------
+//!        b_0    |   f_0    |   default_0    |    s_0    |      1
+//!        ...    |   ...    |      ...       |    ...    |      1
+//!      b_{d-1}  | f_{d-1}  | default_{d-1}  |  s_{d-1}  |      1
+//!               |          |                |     0     |
+//!
+//!     s_l = b_l ? f_l : default_l
+//!
+//! The bits are the index's, unpacked with `BitsChip`. The frontier is a private witness,
+//! with no need to trust it: the old root, hashed from an empty leaf at `i`, is only right
+//! for the right frontier nodes, and the others are not used.
+//!
+//! Nodes are hashed with the `CircuitHasher` of `MerkleChip`, Poseidon by default.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use super::{
+    bits::{BitsChip, BitsConfig},
+    commit::CommitChip,
+    hasher::CircuitHasher,
+    merkle::{MerkleChip, MerkleConfig},
+    smt::default_nodes,
+};
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+};
+
+/// The state a deposit contract keeps, computed natively.
+#[derive(Debug, Clone)]
+pub struct IncrementalMerkleTree<H: CircuitHasher<Fp> = CommitChip> {
+    zeros: Vec<Fp>,
+    frontier: Vec<Fp>,
+    next_index: u64,
+    root: Fp,
+    _marker: PhantomData<H>,
+}
+
+impl IncrementalMerkleTree {
+    /// The empty tree of `2^depth` leaves over Poseidon.
+    pub fn new(depth: usize) -> Self {
+        Self::with_hasher(depth)
+    }
+}
+
+impl<H: CircuitHasher<Fp>> IncrementalMerkleTree<H> {
+    /// The empty tree of `2^depth` leaves.
+    pub fn with_hasher(depth: usize) -> Self {
+        let zeros = default_nodes::<H>(depth);
+        Self {
+            frontier: zeros[..depth].to_vec(),
+            root: zeros[depth],
+            zeros,
+            next_index: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frontier.len()
+    }
+
+    pub fn root(&self) -> Fp {
+        self.root
+    }
+
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    /// The last left node hashed on each level, bottom up.
+    pub fn frontier(&self) -> &[Fp] {
+        &self.frontier
+    }
+
+    /// The siblings of the leaf at the next index, bottom up.
+    pub fn siblings(&self) -> Vec<Fp> {
+        (0..self.depth())
+            .map(|l| {
+                if (self.next_index >> l) & 1 == 1 {
+                    self.frontier[l]
+                } else {
+                    self.zeros[l]
+                }
+            })
+            .collect()
+    }
+
+    /// Appends `leaf` and returns its index.
+    pub fn append(&mut self, leaf: Fp) -> u64 {
+        let index = self.next_index;
+        assert!(index >> self.depth() == 0, "the tree of depth {} is full", self.depth());
+
+        let mut node = leaf;
+        for l in 0..self.depth() {
+            node = if (index >> l) & 1 == 1 {
+                H::hash2_native(self.frontier[l], node)
+            } else {
+                self.frontier[l] = node;
+                H::hash2_native(node, self.zeros[l])
+            };
+        }
+        self.root = node;
+        self.next_index += 1;
+        index
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IncrementalMerkleConfig<H: CircuitHasher<Fp> = CommitChip> {
+    bit: Column<Advice>,
+    filled: Column<Advice>,
+    zero: Column<Fixed>,
+    sibling: Column<Advice>,
+    q_frontier: Selector,
+    merkle: MerkleConfig<H>,
+    bits: BitsConfig,
+    constants: ConstantsConfig,
+}
+
+pub struct IncrementalMerkleChip<const DEPTH: usize, H: CircuitHasher<Fp> = CommitChip> {
+    config: IncrementalMerkleConfig<H>,
+    merkle: MerkleChip<H>,
+    bits: BitsChip<Fp>,
+    constants: ConstantsChip<Fp>,
+}
+
+impl<const DEPTH: usize, H: CircuitHasher<Fp>> Chip<Fp> for IncrementalMerkleChip<DEPTH, H> {
+    type Config = IncrementalMerkleConfig<H>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<const DEPTH: usize, H: CircuitHasher<Fp>> IncrementalMerkleChip<DEPTH, H> {
+    pub fn construct(config: IncrementalMerkleConfig<H>) -> Self {
+        let merkle = MerkleChip::construct(config.merkle.clone());
+        let bits = BitsChip::construct(config.bits.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self {
+            config,
+            merkle,
+            bits,
+            constants,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, instance: Column<Instance>) -> IncrementalMerkleConfig<H> {
+        let merkle = MerkleChip::configure(meta);
+        let bits = BitsChip::configure(meta, instance);
+        let [bit, filled, sibling] = [(); 3].map(|_| meta.advice_column());
+        let zero = meta.fixed_column();
+        let q_frontier = meta.selector();
+
+        // the empty leaf the old root is computed from is a loaded constant
+        let constants = ConstantsChip::configure_with(meta, sibling);
+        for column in [bit, filled] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("frontier sibling", |meta| {
+            let q_frontier = meta.query_selector(q_frontier);
+            let [bit, filled, sibling] = [bit, filled, sibling].map(|column| meta.query_advice(column, Rotation::cur()));
+            let zero = meta.query_fixed(zero, Rotation::cur());
+
+            GateBuilder::with_selector(q_frontier).select("sibling = bit ? filled : zero", sibling, bit, filled, zero)
+        });
+
+        IncrementalMerkleConfig {
+            bit,
+            filled,
+            zero,
+            sibling,
+            q_frontier,
+            merkle,
+            bits,
+            constants,
+        }
+    }
+
+    /// The roots before and after appending `leaf` at `index`, given the `DEPTH` nodes of the
+    /// frontier, bottom up.
+    pub fn append(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        index: &AssignedCell<Fp, Fp>,
+        leaf: &AssignedCell<Fp, Fp>,
+        frontier: &[AssignedCell<Fp, Fp>],
+    ) -> Result<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>), Halo2ExamplesError> {
+        if frontier.len() != DEPTH {
+            return Err(Halo2ExamplesError::new("IncrementalMerkleChip", Error::Synthesis).in_region("append"));
+        }
+        let config = &self.config;
+        let zeros = default_nodes::<H>(DEPTH);
+        let bits = self.bits.unpack(layouter.namespace(|| "index bits"), index, DEPTH)?;
+        let empty = self.constants.load_constant(layouter.namespace(|| "empty leaf"), "empty leaf", Fp::zero())?;
+
+        let siblings = layouter
+            .assign_region(
+                || "frontier siblings",
+                |mut region| {
+                    let mut siblings = Vec::with_capacity(DEPTH);
+                    for (l, (bit, filled)) in bits.iter().zip(frontier.iter()).enumerate() {
+                        config.q_frontier.enable(&mut region, l)?;
+                        let bit = bit.copy_advice(|| format!("b_{}", l), &mut region, config.bit, l)?;
+                        let filled = filled.copy_advice(|| format!("f_{}", l), &mut region, config.filled, l)?;
+                        region.assign_fixed(|| format!("default_{}", l), config.zero, l, || Value::known(zeros[l]))?;
+
+                        let sibling = bit
+                            .value()
+                            .zip(filled.value())
+                            .map(|(bit, filled)| if *bit == Fp::one() { *filled } else { zeros[l] });
+                        siblings.push(region.assign_advice(|| format!("s_{}", l), config.sibling, l, || sibling)?);
+                    }
+                    Ok(siblings)
+                },
+            )
+            .in_chip("IncrementalMerkleChip", "frontier siblings")?;
+
+        let old_root = self.merkle.compute_root(layouter.namespace(|| "old root"), &empty, &bits, &siblings)?;
+        let new_root = self.merkle.compute_root(layouter.namespace(|| "new root"), leaf, &bits, &siblings)?;
+        Ok((old_root, new_root))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{IncrementalMerkleChip, IncrementalMerkleConfig, IncrementalMerkleTree};
+    use crate::{
+        chip::{LoadPrivateChip, LoadPrivateConfig},
+        gadgets::merkle::MerkleTree,
+        public_inputs::{PublicOutputs, PublicOutputsConfig},
+    };
+
+    const DEPTH: usize = 4;
+    const K: u32 = 10;
+
+    /// Proves appending a public leaf at a public index takes `old_root` to `new_root`.
+    struct AppendCircuit {
+        index: u64,
+        leaf: Fp,
+        frontier: Vec<Fp>,
+    }
+
+    impl AppendCircuit {
+        fn new(tree: &IncrementalMerkleTree, leaf: Fp) -> Self {
+            Self {
+                index: tree.next_index(),
+                leaf,
+                frontier: tree.frontier().to_vec(),
+            }
+        }
+    }
+
+    impl Circuit<Fp> for AppendCircuit {
+        type Config = (IncrementalMerkleConfig, LoadPrivateConfig, PublicOutputsConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                index: self.index,
+                leaf: self.leaf,
+                frontier: self.frontier.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            let chip = IncrementalMerkleChip::<DEPTH>::configure(meta, instance);
+            (chip, LoadPrivateChip::configure(meta, 1), PublicOutputs::configure(meta, instance))
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (config, load_config, outputs_config) = config;
+            let chip = IncrementalMerkleChip::<DEPTH>::construct(config);
+            let load_chip = LoadPrivateChip::construct(load_config);
+            let mut outputs = PublicOutputs::construct(outputs_config);
+
+            let [index, leaf]: [_; 2] = load_chip
+                .load_privates(layouter.namespace(|| "load leaf"), &[Fp::from(self.index), self.leaf].map(Value::known))?
+                .try_into()
+                .expect("two cells loaded");
+            let frontier: Vec<_> = self.frontier.iter().copied().map(Value::known).collect();
+            let frontier = load_chip.load_privates(layouter.namespace(|| "load frontier"), &frontier)?;
+
+            let (old_root, new_root) = chip.append(layouter.namespace(|| "append"), &index, &leaf, &frontier)?;
+            outputs.expose("old_root", &old_root);
+            outputs.expose("new_root", &new_root);
+            outputs.expose("index", &index);
+            outputs.expose("leaf", &leaf);
+            outputs.constrain(layouter.namespace(|| "public outputs"))?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_frontier() {
+        let mut tree = IncrementalMerkleTree::new(DEPTH);
+        let mut leaves = vec![];
+        assert_eq!(tree.root(), MerkleTree::new(DEPTH, &leaves).root());
+
+        for i in 0..1 << DEPTH {
+            let dense = MerkleTree::new(DEPTH, &leaves);
+            assert_eq!(tree.siblings(), dense.path(i));
+
+            let leaf = Fp::from(100 + i);
+            assert_eq!(tree.append(leaf), i);
+            leaves.push(leaf);
+            assert_eq!(tree.root(), MerkleTree::new(DEPTH, &leaves).root());
+        }
+    }
+
+    #[test]
+    fn test_incremental_append() {
+        let mut tree = IncrementalMerkleTree::new(DEPTH);
+        for i in 0..6 {
+            let old_root = tree.root();
+            let circuit = AppendCircuit::new(&tree, Fp::from(100 + i));
+            tree.append(Fp::from(100 + i));
+            let instances = vec![old_root, tree.root(), Fp::from(i), Fp::from(100 + i)];
+            MockProver::run(K, &circuit, vec![instances]).unwrap().assert_satisfied();
+        }
+
+        // appending over a leaf already in the tree
+        let mut circuit = AppendCircuit::new(&tree, Fp::from(200));
+        circuit.index = 2;
+        let mut other = tree.clone();
+        other.append(Fp::from(200));
+        let instances = vec![tree.root(), other.root(), Fp::from(2), Fp::from(200)];
+        assert!(MockProver::run(K, &circuit, vec![instances]).unwrap().verify().is_err());
+
+        // a frontier node made up
+        let mut circuit = AppendCircuit::new(&tree, Fp::from(200));
+        circuit.frontier[1] += Fp::one();
+        let instances = vec![tree.root(), other.root(), Fp::from(6), Fp::from(200)];
+        assert!(MockProver::run(K, &circuit, vec![instances]).unwrap().verify().is_err());
+    }
+}
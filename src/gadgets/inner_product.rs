@@ -0,0 +1,208 @@
+//! The inner product of two equally long lists of cells, accumulated one term per row:
+//!
+//!     acc_0 = 0,   acc_{i+1} = acc_i + a_i * b_i,   acc_n = a_0 b_0 + ... + a_{n-1} b_{n-1}
+//!
+//!        a     |    b     |   acc    |  q_dot
+//!     -----------------------------------------
+//!              |          |    0     |
+//!       a_0    |   b_0    |  acc_1   |    1
+//!       ...    |   ...    |   ...    |   ...
+//!     a_{n-1}  | b_{n-1}  |  acc_n   |    1
+//!
+//! The gate on row `i` checks `acc_i = acc_{i-1} + a_{i-1} * b_{i-1}`, so `n` terms cost
+//! `n + 1` rows. Nothing is reduced: callers comparing the result must have bounded the
+//! inputs so that it cannot wrap around the modulus.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+#[derive(Debug, Clone)]
+pub struct InnerProductConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    acc: Column<Advice>,
+    q_dot: Selector,
+    instance: Column<Instance>,
+    constants: ConstantsConfig,
+}
+
+pub struct InnerProductChip<F: FieldExt> {
+    config: InnerProductConfig,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for InnerProductChip<F> {
+    type Config = InnerProductConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> InnerProductChip<F> {
+    pub fn construct(config: InnerProductConfig) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> InnerProductConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let acc = meta.advice_column();
+        let q_dot = meta.selector();
+
+        // `acc_0 == 0` is copied from a loaded constant
+        let constants = ConstantsChip::configure_with(meta, acc);
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+
+        meta.create_gate("inner product step", |meta| {
+            let q_dot = meta.query_selector(q_dot);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            GateBuilder::with_selector(q_dot).equal("acc = acc_prev + a * b", acc, acc_prev + a * b)
+        });
+
+        InnerProductConfig {
+            a,
+            b,
+            acc,
+            q_dot,
+            instance,
+            constants,
+        }
+    }
+
+    /// `a_0 b_0 + ... + a_{n-1} b_{n-1}`, 0 if there are no terms. Errors if the lists differ
+    /// in length.
+    pub fn inner_product(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &[AssignedCell<F, F>],
+        b: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        if a.len() != b.len() {
+            return Err(Halo2ExamplesError::new("InnerProductChip", Error::Synthesis).in_region("inner product"));
+        }
+
+        let config = &self.config;
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", F::zero())?;
+        layouter
+            .assign_region(
+                || "inner product",
+                |mut region| {
+                    let mut acc = zero.copy_advice(|| "acc_0", &mut region, config.acc, 0)?;
+                    for (i, (a, b)) in a.iter().zip(b).enumerate() {
+                        let offset = i + 1;
+                        config.q_dot.enable(&mut region, offset)?;
+                        let a = a.copy_advice(|| format!("a_{}", i), &mut region, config.a, offset)?;
+                        let b = b.copy_advice(|| format!("b_{}", i), &mut region, config.b, offset)?;
+                        let next = acc.value().copied() + a.value().copied() * b.value().copied();
+                        acc = region.assign_advice(|| format!("acc_{}", offset), config.acc, offset, || next)?;
+                    }
+                    Ok(acc)
+                },
+            )
+            .in_chip("InnerProductChip", "inner product")
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .constrain_instance(cell.cell(), self.config.instance, row)
+            .map_err(|err| Halo2ExamplesError::new("InnerProductChip", err).at(row, "instance"))
+    }
+}
+
+/// Exposes the inner product of two private vectors.
+#[derive(Default)]
+pub struct InnerProductCircuit<F> {
+    pub a: Vec<Value<F>>,
+    pub b: Vec<Value<F>>,
+}
+
+impl<F: FieldExt> Circuit<F> for InnerProductCircuit<F> {
+    type Config = (InnerProductConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: vec![Value::unknown(); self.a.len()], b: vec![Value::unknown(); self.b.len()] }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let inner_product = InnerProductChip::configure(meta, instance);
+        (inner_product, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = InnerProductChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let a = load_chip.load_privates(layouter.namespace(|| "load a"), &self.a)?;
+        let b = load_chip.load_privates(layouter.namespace(|| "load b"), &self.b)?;
+        let product = chip.inner_product(layouter.namespace(|| "a . b"), &a, &b)?;
+        chip.expose_public(layouter.namespace(|| "a . b"), &product, 0)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ValidateWitness<F> for InnerProductCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        if self.a.len() != self.b.len() {
+            return Err(InvalidWitness::InvalidParameter(format!("{} terms against {}", self.a.len(), self.b.len())));
+        }
+        let product = self.a.iter().zip(&self.b).try_fold(F::zero(), |acc, (a, b)| -> Result<_, InvalidWitness> {
+            Ok(acc + known(*a, "a")? * known(*b, "b")?)
+        })?;
+        check_instances(instances, &[product])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver};
+
+    use super::InnerProductCircuit;
+    use crate::{test_over_fields, validate::ValidateWitness};
+
+    const K: u32 = 5;
+
+    fn test_inner_product<F: FieldExt>() {
+        // (3, -1, 0, 4) . (2, 5, 9, 1) = 6 - 5 + 0 + 4 = 5
+        let circuit = InnerProductCircuit {
+            a: [F::from(3), -F::one(), F::zero(), F::from(4)].map(Value::known).to_vec(),
+            b: [2, 5, 9, 1].map(|b| Value::known(F::from(b))).to_vec(),
+        };
+        let instances = vec![vec![F::from(5)]];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+
+        let prover = MockProver::run(K, &circuit, vec![vec![F::from(6)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    test_over_fields!(test_inner_product);
+}
@@ -4,12 +4,22 @@ use halo2_proofs::{
     plonk::*, poly::Rotation,
 };
 
-use super::is_zero::{IsZeroChip, IsZeroConfig};
+use super::{
+    arith::{ArithChip, ArithConfig, Coefficients},
+    is_zero::{IsZeroChip, IsZeroConfig},
+};
+use crate::{
+    chip::{LoadInstructions, LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
 
+/// Checks `a == b` with a row of the standard gate, `s_a = 1, s_b = -1`, and keeps the
+/// is-zero gadget over `a - b` on the same row, so the chip adds no gate of its own beside
+/// the gadget's when combined with other users of `ArithChip`.
 #[derive(Debug, Clone)]
 pub struct IsEqualConfig<F: FieldExt> {
-    a: Column<Advice>,
-    b: Column<Advice>,
+    arith: ArithConfig,
     selector: Selector,
     a_equals_b: IsZeroConfig<F>,
 }
@@ -18,16 +28,36 @@ pub struct IsEqualChip<F: FieldExt> {
     config: IsEqualConfig<F>,
 }
 
+impl<F: FieldExt> Chip<F> for IsEqualChip<F> {
+    type Config = IsEqualConfig<F>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> LoadInstructions<F> for IsEqualChip<F> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.arith.advice[0]
+    }
+}
+
 impl<F: FieldExt> IsEqualChip<F> {
     pub fn construct(config: IsEqualConfig<F>) -> Self {
         Self { config }
     } 
 
-    pub fn configure(meta: &mut ConstraintSystem<F>) -> IsEqualConfig<F> {
+    /// Lays the check out on the rows of `arith`, which the circuit may share with other
+    /// chips.
+    pub fn configure(meta: &mut ConstraintSystem<F>, arith: ArithConfig) -> IsEqualConfig<F> {
         let selector = meta.selector();
-        let a = meta.advice_column();
-        let b = meta.advice_column();
         let is_zero_advice_colum = meta.advice_column();
+        let [a, b, _] = arith.advice;
 
         let a_equals_b = IsZeroChip::configure(
             meta, 
@@ -35,19 +65,9 @@ impl<F: FieldExt> IsEqualChip<F> {
             |meta| meta.query_advice(a, Rotation::cur()) - meta.query_advice(b, Rotation::cur()), 
             is_zero_advice_colum
         );
-        // let is_equal = a_equals_b.is_zero_expr;
-
-        meta.create_gate("Is Equal", |meta| {
-            let s = meta.query_selector(selector);
-            let a: Expression<F> = meta.query_advice(a, Rotation::cur());
-            let b = meta.query_advice(b, Rotation::cur());
-
-            vec![s * (a - b)]
-        });
 
         IsEqualConfig {
-            a,
-            b,
+            arith,
             selector,
             a_equals_b,
         }
@@ -56,30 +76,30 @@ impl<F: FieldExt> IsEqualChip<F> {
     pub fn assign(
         &self,
         mut layouter: impl Layouter<F>,
-        a: Value<F>,
-        b: Value<F>,
-    ) -> Result<(), Error> {
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(), Halo2ExamplesError> {
         let is_zero_chip = IsZeroChip::construct(self.config.a_equals_b.clone());
+        let arith_chip = ArithChip::construct(self.config.arith.clone());
 
         layouter.assign_region(|| "assign value", |mut region| {
             let offset = 0;
             self.config.selector.enable(&mut region, offset)?;
-            region.assign_advice(|| "a", self.config.a, offset, || a)?;
-            region.assign_advice(|| "b", self.config.b, offset, || b)?;
-            is_zero_chip.assign(&mut region, offset, a - b)?;
+            arith_chip.assign_row(&mut region, offset, Coefficients::equal(), a, b)?;
+            is_zero_chip.assign(&mut region, offset, a.value().copied() - b.value().copied())?;
             Ok(())
-        })
+        }).in_chip("IsEqualChip", "assign value")
     }
 }
 
 #[derive(Default)]
 pub struct IsEqualCircuit<F> {
-    a: Value<F>,
-    b: Value<F>,
+    pub a: Value<F>,
+    pub b: Value<F>,
 }
 
 impl<F: FieldExt> Circuit<F> for IsEqualCircuit<F> {
-    type Config = IsEqualConfig<F>;
+    type Config = (IsEqualConfig<F>, LoadPrivateConfig);
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -87,29 +107,48 @@ impl<F: FieldExt> Circuit<F> for IsEqualCircuit<F> {
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        IsEqualChip::configure(meta)
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let arith = ArithChip::configure(meta, advice);
+        let is_equal = IsEqualChip::configure(meta, arith);
+        (is_equal, LoadPrivateChip::configure(meta, 1))
     }
 
-    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
         let chip = IsEqualChip::construct(config);
-        chip.assign(layouter, self.a, self.b)
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let a = load_chip.load_private(layouter.namespace(|| "load a"), self.a)?;
+        let b = load_chip.load_private(layouter.namespace(|| "load b"), self.b)?;
+        chip.assign(layouter.namespace(|| "is equal"), &a, &b)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ValidateWitness<F> for IsEqualCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        if known(self.a, "a")? != known(self.b, "b")? {
+            return Err(InvalidWitness::NotEqual { left: "a", right: "b" });
+        }
+        check_instances(instances, &[])
     }
 }
 
 #[cfg(test)]
 mod test {
-    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver};
 
     use super::IsEqualCircuit;
+    use crate::test_over_fields;
 
-    #[test]
-    fn test_is_equal() {
+    fn test_is_equal<F: FieldExt>() {
         let circuit = IsEqualCircuit {
-            a: Value::known(Fp::from(42)),
-            b: Value::known(Fp::from(42)),
+            a: Value::known(F::from(42)),
+            b: Value::known(F::from(42)),
         };
 
         let prover = MockProver::run(4, &circuit, vec![]).unwrap();
         prover.assert_satisfied();
     }
+
+    test_over_fields!(test_is_equal);
 }
\ No newline at end of file
@@ -4,6 +4,8 @@ use halo2_proofs::{
     plonk::*, poly::Rotation,
 };
 
+use crate::gate::GateBuilder;
+
 #[derive(Debug, Clone)]
 pub struct IsZeroConfig<F: FieldExt> {
     pub value_inv: Column<Advice>,
@@ -14,6 +16,21 @@ pub struct IsZeroChip<F: FieldExt> {
     config: IsZeroConfig<F>,
 }
 
+// IsZero is laid out inside a region owned by the calling chip, so it has no input column
+// of its own and only implements `Chip`.
+impl<F: FieldExt> Chip<F> for IsZeroChip<F> {
+    type Config = IsZeroConfig<F>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
 impl<F: FieldExt> IsZeroChip<F> {
     pub fn construct(config: IsZeroConfig<F>) -> Self {
         Self {
@@ -43,7 +60,7 @@ impl<F: FieldExt> IsZeroChip<F> {
             let value_inv = meta.query_advice(value_inv, Rotation::cur());
 
             is_zero_expr = Expression::Constant(F::one()) - value.clone() * value_inv;
-            vec![q_enable * value * is_zero_expr.clone()]
+            GateBuilder::with_selector(q_enable).constrain("value * (1 - value * value_inv) = 0", value * is_zero_expr.clone())
         });
 
         IsZeroConfig {
@@ -0,0 +1,177 @@
+//! Lagrange interpolation: the value at a point `z`, fixed in the circuit, of the polynomial
+//! of degree `n - 1` through `n` points `(x_i, y_i)`,
+//!
+//!     p(z) = sum_i y_i * l_i,   l_i = prod_{j != i} (z - x_j) / (x_i - x_j)
+//!
+//! all on rows of `ArithChip`. The `z - x_j` are one row each, shared by the terms, and each
+//! term takes about `3 n` more: `n - 1` multiplications for its numerator, `2 n - 3` rows
+//! for the differences in its denominator and their product, two to invert it and one to
+//! divide. `n` points cost about `3 n^2` rows.
+//!
+//! Every denominator goes through `ArithChip::invert`, so two points with the same `x` fail
+//! the proof rather than leave their terms free; the `x_i` need no other check.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::arith::{ArithChip, ArithConfig, Coefficients};
+use crate::error::Halo2ExamplesError;
+
+pub struct LagrangeChip<F: FieldExt> {
+    arith: ArithChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for LagrangeChip<F> {
+    type Config = ArithConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        self.arith.config()
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> LagrangeChip<F> {
+    pub fn construct(config: ArithConfig) -> Self {
+        Self {
+            arith: ArithChip::construct(config),
+        }
+    }
+
+    /// Interpolation needs nothing but the arithmetic gate.
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> ArithConfig {
+        ArithChip::configure(meta, advice)
+    }
+
+    /// The chip the rows are laid out with.
+    pub fn arith(&self) -> &ArithChip<F> {
+        &self.arith
+    }
+
+    /// `p(z)` for the polynomial through `points`, which need distinct `x`s. 0 if there are
+    /// none.
+    pub fn interpolate(
+        &self,
+        mut layouter: impl Layouter<F>,
+        points: &[(AssignedCell<F, F>, AssignedCell<F, F>)],
+        z: F,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let arith = &self.arith;
+        // `z - x_j`, one row each
+        let z_minus = Coefficients {
+            s_m: F::zero(),
+            s_a: -F::one(),
+            s_b: F::zero(),
+            s_c: -F::one(),
+            s_const: z,
+        };
+        let shifted = points
+            .iter()
+            .enumerate()
+            .map(|(j, (x, _))| arith.apply(layouter.namespace(|| format!("z - x_{}", j)), "z - x", z_minus, x, x))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut sum: Option<AssignedCell<F, F>> = None;
+        for (i, (x_i, y_i)) in points.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("term {}", i));
+            let mut num = y_i.clone();
+            let mut den: Option<AssignedCell<F, F>> = None;
+            for (j, (x_j, _)) in points.iter().enumerate().filter(|(j, _)| *j != i) {
+                num = arith.mul(layouter.namespace(|| format!("* (z - x_{})", j)), &num, &shifted[j])?;
+                let diff = arith.sub(layouter.namespace(|| format!("x_{} - x_{}", i, j)), x_i, x_j)?;
+                den = Some(match den {
+                    None => diff,
+                    Some(den) => arith.mul(layouter.namespace(|| format!("* (x_{} - x_{})", i, j)), &den, &diff)?,
+                });
+            }
+            let term = match den {
+                None => num,
+                Some(den) => {
+                    let inverse = arith.invert(layouter.namespace(|| "1 / den"), &den)?;
+                    arith.mul(layouter.namespace(|| "num / den"), &num, &inverse)?
+                }
+            };
+            sum = Some(match sum {
+                None => term,
+                Some(sum) => arith.add(layouter.namespace(|| "+ term"), &sum, &term)?,
+            });
+        }
+
+        match sum {
+            Some(sum) => Ok(sum),
+            None => arith.load_constant(layouter.namespace(|| "empty sum"), F::zero()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::LagrangeChip;
+    use crate::{chip::LoadInstructions, constants::ConstantsChip, gadgets::arith::ArithConfig, test_over_fields};
+
+    /// Interpolates private points at 0 and checks the result against a private `expected`.
+    #[derive(Default)]
+    struct LagrangeCircuit<F> {
+        points: Vec<(Value<F>, Value<F>)>,
+        expected: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for LagrangeCircuit<F> {
+        type Config = ArithConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                points: vec![(Value::unknown(), Value::unknown()); self.points.len()],
+                expected: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            // an empty sum is loaded from a constant
+            ConstantsChip::configure_with(meta, advice[0]);
+            LagrangeChip::configure(meta, advice)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+            let chip = LagrangeChip::construct(config);
+            let arith = chip.arith();
+            let points = self
+                .points
+                .iter()
+                .map(|(x, y)| Ok((arith.load_private(layouter.namespace(|| "x"), *x)?, arith.load_private(layouter.namespace(|| "y"), *y)?)))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let expected = arith.load_private(layouter.namespace(|| "expected"), self.expected)?;
+
+            let value = chip.interpolate(layouter.namespace(|| "p(0)"), &points, F::zero())?;
+            arith.assert_equal(layouter.namespace(|| "p(0) == expected"), &value, &expected)?;
+            Ok(())
+        }
+    }
+
+    fn test_lagrange<F: FieldExt>() {
+        // p(x) = 5 + 2x + 3x^2 through x = 1, 2, 4
+        let p = |x: u64| F::from(5 + 2 * x + 3 * x * x);
+        let circuit = |xs: [u64; 3], expected: u64| LagrangeCircuit {
+            points: xs.iter().map(|x| (Value::known(F::from(*x)), Value::known(p(*x)))).collect(),
+            expected: Value::known(F::from(expected)),
+        };
+
+        MockProver::run(6, &circuit([1, 2, 4], 5), vec![]).unwrap().assert_satisfied();
+        assert!(MockProver::run(6, &circuit([1, 2, 4], 6), vec![]).unwrap().verify().is_err());
+        // a repeated point, whose denominators are 0
+        assert!(MockProver::run(6, &circuit([1, 2, 2], 5), vec![]).unwrap().verify().is_err());
+    }
+
+    test_over_fields!(test_lagrange);
+}
@@ -0,0 +1,475 @@
+//! Checks that a time-ordered log of memory accesses is consistent: every read returns the
+//! value last written to its address, or 0 if there was none.
+//!
+//! Consistency is easy to check on the same accesses sorted by address, then time, where
+//! every read follows the access it must agree with. So the chip lays out the log as given,
+//! with the time of an access being its index, and a sorted copy the prover witnesses, and
+//! proves the two hold the same accesses with `MultisetEqChip`, each access packed into one
+//! field element
+//!
+//!     packed = addr + 2^16 * time + 2^32 * is_write + 2^33 * value
+//!
+//! which is unique as long as addresses and times fit in 16 bits and values in 32, all of
+//! which `DecomposeChip` checks on both sides. The sorted copy then gets two more columns,
+//!
+//!        addr  |  time  |  value  | is_write | packed |  same  |  delta  |  selector
+//!       ---------------------------------------------------------------------------------
+//!         a_0  |  t_0   |   v_0   |   w_0    |  p_0   |        |         |  q_first
+//!         a_1  |  t_1   |   v_1   |   w_1    |  p_1   |  s_1   |   d_1   |  q_sorted
+//!         ...  |  ...   |   ...   |   ...    |  ...   |  ...   |   ...   |  q_sorted
+//!
+//! where `same` is 1 if an access is to the same address as the one above it, and
+//!
+//!     same = 1:  delta = t_i - t_{i-1} - 1,  so time strictly increases,
+//!     same = 0:  delta = a_i - a_{i-1} - 1,  so the address strictly increases,
+//!
+//! with `delta` range checked to 16 bits. A read (`is_write = 0`) has the value above it if
+//! `same` is 1, and 0 otherwise.
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use super::multiset::{MultisetEqChip, MultisetEqConfig};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    decompose::decompose_range_check::{DecomposeChip, DecomposeConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_below, known, InvalidWitness, ValidateWitness},
+};
+
+pub const ADDR_BITS: usize = 16;
+pub const TIME_BITS: usize = 16;
+pub const VALUE_BITS: usize = 32;
+
+/// A memory access, as plain values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Access {
+    pub addr: u64,
+    pub value: u64,
+    pub is_write: bool,
+}
+
+/// A memory access, as cells. `is_write` is constrained to be boolean by `MemoryChip`.
+#[derive(Debug, Clone)]
+pub struct MemoryOp {
+    pub addr: AssignedCell<Fp, Fp>,
+    pub value: AssignedCell<Fp, Fp>,
+    pub is_write: AssignedCell<Fp, Fp>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryConfig {
+    addr: Column<Advice>,
+    time: Column<Advice>,
+    value: Column<Advice>,
+    is_write: Column<Advice>,
+    packed: Column<Advice>,
+    same: Column<Advice>,
+    delta: Column<Advice>,
+    q_pack: Selector,
+    q_first: Selector,
+    q_sorted: Selector,
+    range: DecomposeConfig<Fp, 256>,
+    multiset: MultisetEqConfig,
+    constants: ConstantsConfig,
+}
+
+pub struct MemoryChip {
+    config: MemoryConfig,
+    range: DecomposeChip<Fp, 256>,
+    multiset: MultisetEqChip,
+    constants: ConstantsChip<Fp>,
+}
+
+impl Chip<Fp> for MemoryChip {
+    type Config = MemoryConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+/// The cells of one row of a `MemoryChip` region that get range checked.
+struct Row {
+    addr: AssignedCell<Fp, Fp>,
+    time: AssignedCell<Fp, Fp>,
+    value: AssignedCell<Fp, Fp>,
+    packed: AssignedCell<Fp, Fp>,
+    delta: Option<AssignedCell<Fp, Fp>>,
+}
+
+impl MemoryChip {
+    pub fn construct(config: MemoryConfig) -> Self {
+        let range = DecomposeChip::construct(config.range.clone());
+        let multiset = MultisetEqChip::construct(config.multiset.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self {
+            config,
+            range,
+            multiset,
+            constants,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, instance: Column<Instance>) -> MemoryConfig {
+        let range = DecomposeConfig::configure(meta);
+        let multiset = MultisetEqChip::configure(meta, instance);
+        let [addr, time, value, is_write, packed, same, delta] = [(); 7].map(|_| meta.advice_column());
+        let q_pack = meta.selector();
+        let q_first = meta.selector();
+        let q_sorted = meta.selector();
+
+        // the log's timestamps are loaded constants
+        let constants = ConstantsChip::configure_with(meta, time);
+        for column in [addr, value, is_write, packed, delta] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("memory pack", |meta| {
+            let q_pack = meta.query_selector(q_pack);
+            let [addr, time, value, is_write, packed] =
+                [addr, time, value, is_write, packed].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_pack).boolean("is_write is boolean", is_write.clone()).equal(
+                "packed = addr + 2^16 * time + 2^32 * is_write + 2^33 * value",
+                packed,
+                addr + time * Fp::from(1u64 << ADDR_BITS)
+                    + is_write * Fp::from(1u64 << (ADDR_BITS + TIME_BITS))
+                    + value * Fp::from(1u64 << (ADDR_BITS + TIME_BITS + 1)),
+            )
+        });
+
+        meta.create_gate("memory first access", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let value = meta.query_advice(value, Rotation::cur());
+            let is_write = meta.query_advice(is_write, Rotation::cur());
+
+            GateBuilder::with_selector(q_first).constrain("a first read returns 0", (Expression::Constant(Fp::one()) - is_write) * value)
+        });
+
+        meta.create_gate("memory sorted access", |meta| {
+            let q_sorted = meta.query_selector(q_sorted);
+            let [addr_prev, time_prev, value_prev] =
+                [addr, time, value].map(|column| meta.query_advice(column, Rotation::prev()));
+            let [addr, time, value, is_write, same, delta] =
+                [addr, time, value, is_write, same, delta].map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(Fp::one());
+
+            GateBuilder::with_selector(q_sorted)
+                .boolean("same is boolean", same.clone())
+                .constrain("same address", same.clone() * (addr.clone() - addr_prev.clone()))
+                .select(
+                    "delta",
+                    delta,
+                    same.clone(),
+                    time - time_prev - one.clone(),
+                    addr - addr_prev - one.clone(),
+                )
+                .constrain("a read returns the last value", (one - is_write) * (value - same * value_prev))
+        });
+
+        MemoryConfig {
+            addr,
+            time,
+            value,
+            is_write,
+            packed,
+            same,
+            delta,
+            q_pack,
+            q_first,
+            q_sorted,
+            range,
+            multiset,
+            constants,
+        }
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<Fp>) -> Result<(), Halo2ExamplesError> {
+        self.range.load_table(layouter)
+    }
+
+    /// Constrains the log `ops`, the i-th access happening at time i, to be consistent.
+    pub fn check(&self, mut layouter: impl Layouter<Fp>, ops: &[MemoryOp]) -> Result<(), Halo2ExamplesError> {
+        if ops.len() >= 1 << TIME_BITS {
+            return Err(Halo2ExamplesError::new("MemoryChip", Error::Synthesis).in_region("memory log"));
+        }
+
+        let log = self.lay_out_log(layouter.namespace(|| "memory log"), ops)?;
+        let sorted = self.lay_out_sorted(layouter.namespace(|| "sorted memory"), ops)?;
+        for (i, row) in log.iter().enumerate() {
+            self.range.assign(layouter.namespace(|| format!("log addr {}", i)), &row.addr, ADDR_BITS)?;
+            self.range.assign(layouter.namespace(|| format!("log value {}", i)), &row.value, VALUE_BITS)?;
+        }
+        for (i, row) in sorted.iter().enumerate() {
+            self.range.assign(layouter.namespace(|| format!("sorted addr {}", i)), &row.addr, ADDR_BITS)?;
+            self.range.assign(layouter.namespace(|| format!("sorted time {}", i)), &row.time, TIME_BITS)?;
+            self.range.assign(layouter.namespace(|| format!("sorted value {}", i)), &row.value, VALUE_BITS)?;
+            if let Some(delta) = &row.delta {
+                self.range.assign(layouter.namespace(|| format!("sorted delta {}", i)), delta, TIME_BITS)?;
+            }
+        }
+
+        let log: Vec<_> = log.into_iter().map(|row| row.packed).collect();
+        let sorted: Vec<_> = sorted.into_iter().map(|row| row.packed).collect();
+        self.multiset.check(layouter.namespace(|| "log ~ sorted"), &log, &sorted)
+    }
+
+    fn lay_out_log(&self, mut layouter: impl Layouter<Fp>, ops: &[MemoryOp]) -> Result<Vec<Row>, Halo2ExamplesError> {
+        let config = &self.config;
+        let times = (0..ops.len())
+            .map(|i| self.constants.load_constant(layouter.namespace(|| format!("time {}", i)), "time", Fp::from(i as u64)))
+            .collect::<Result<Vec<_>, _>>()?;
+        layouter
+            .assign_region(
+                || "memory log",
+                |mut region| {
+                    ops.iter()
+                        .enumerate()
+                        .map(|(i, op)| {
+                            config.q_pack.enable(&mut region, i)?;
+                            let addr = op.addr.copy_advice(|| "addr", &mut region, config.addr, i)?;
+                            let time = times[i].copy_advice(|| "time", &mut region, config.time, i)?;
+                            let value = op.value.copy_advice(|| "value", &mut region, config.value, i)?;
+                            let is_write = op.is_write.copy_advice(|| "is_write", &mut region, config.is_write, i)?;
+                            let packed = region.assign_advice(|| "packed", config.packed, i, || {
+                                Self::pack(addr.value(), time.value(), value.value(), is_write.value())
+                            })?;
+                            Ok(Row {
+                                addr,
+                                time,
+                                value,
+                                packed,
+                                delta: None,
+                            })
+                        })
+                        .collect()
+                },
+            )
+            .in_chip("MemoryChip", "memory log")
+    }
+
+    fn lay_out_sorted(&self, mut layouter: impl Layouter<Fp>, ops: &[MemoryOp]) -> Result<Vec<Row>, Halo2ExamplesError> {
+        let config = &self.config;
+        let log: Value<Vec<[Fp; 3]>> = ops
+            .iter()
+            .map(|op| {
+                op.addr.value().zip(op.value.value()).zip(op.is_write.value()).map(|((addr, value), is_write)| [*addr, *value, *is_write])
+            })
+            .collect();
+        // (addr, time, value, is_write), sorted by address, then time
+        let sorted = log.map(|log| {
+            let mut sorted: Vec<_> = log
+                .iter()
+                .enumerate()
+                .map(|(time, [addr, value, is_write])| (*addr, Fp::from(time as u64), *value, *is_write))
+                .collect();
+            sorted.sort_by_key(|(addr, time, _, _)| (addr.get_lower_128(), time.get_lower_128()));
+            sorted
+        });
+
+        layouter
+            .assign_region(
+                || "sorted memory",
+                |mut region| {
+                    let mut rows: Vec<Row> = Vec::with_capacity(ops.len());
+                    for i in 0..ops.len() {
+                        let access = sorted.as_ref().map(|sorted| sorted[i]);
+                        config.q_pack.enable(&mut region, i)?;
+                        let addr = region.assign_advice(|| "addr", config.addr, i, || access.map(|access| access.0))?;
+                        let time = region.assign_advice(|| "time", config.time, i, || access.map(|access| access.1))?;
+                        let value = region.assign_advice(|| "value", config.value, i, || access.map(|access| access.2))?;
+                        let is_write = region.assign_advice(|| "is_write", config.is_write, i, || access.map(|access| access.3))?;
+                        let packed = region.assign_advice(|| "packed", config.packed, i, || {
+                            Self::pack(addr.value(), time.value(), value.value(), is_write.value())
+                        })?;
+
+                        let delta = match rows.last() {
+                            None => {
+                                config.q_first.enable(&mut region, i)?;
+                                None
+                            }
+                            Some(prev) => {
+                                config.q_sorted.enable(&mut region, i)?;
+                                let same = addr.value().zip(prev.addr.value()).map(|(addr, prev)| addr == prev);
+                                region.assign_advice(|| "same", config.same, i, || same.map(|same| Fp::from(same as u64)))?;
+                                let delta = same
+                                    .zip(addr.value().zip(prev.addr.value()))
+                                    .zip(time.value().zip(prev.time.value()))
+                                    .map(|((same, (addr, addr_prev)), (time, time_prev))| {
+                                        if same {
+                                            *time - time_prev - Fp::one()
+                                        } else {
+                                            *addr - addr_prev - Fp::one()
+                                        }
+                                    });
+                                Some(region.assign_advice(|| "delta", config.delta, i, || delta)?)
+                            }
+                        };
+                        rows.push(Row {
+                            addr,
+                            time,
+                            value,
+                            packed,
+                            delta,
+                        });
+                    }
+                    Ok(rows)
+                },
+            )
+            .in_chip("MemoryChip", "sorted memory")
+    }
+
+    fn pack(addr: Value<&Fp>, time: Value<&Fp>, value: Value<&Fp>, is_write: Value<&Fp>) -> Value<Fp> {
+        addr.zip(time).zip(value.zip(is_write)).map(|((addr, time), (value, is_write))| {
+            *addr
+                + *time * Fp::from(1u64 << ADDR_BITS)
+                + *is_write * Fp::from(1u64 << (ADDR_BITS + TIME_BITS))
+                + *value * Fp::from(1u64 << (ADDR_BITS + TIME_BITS + 1))
+        })
+    }
+}
+
+/// Checks a private log of memory accesses for consistency.
+#[derive(Default)]
+pub struct MemoryCircuit {
+    pub log: Vec<Value<Access>>,
+}
+
+impl MemoryCircuit {
+    /// Whether every read in `log` returns the last value written to its address, or 0.
+    pub fn is_consistent(log: &[Access]) -> bool {
+        let mut memory = std::collections::HashMap::new();
+        log.iter().all(|access| {
+            if access.is_write {
+                memory.insert(access.addr, access.value);
+                true
+            } else {
+                memory.get(&access.addr).copied().unwrap_or(0) == access.value
+            }
+        })
+    }
+}
+
+impl Circuit<Fp> for MemoryCircuit {
+    type Config = (MemoryConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            log: vec![Value::unknown(); self.log.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let memory = MemoryChip::configure(meta, instance);
+        (memory, LoadPrivateChip::configure(meta, 3))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = MemoryChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        chip.load_table(&mut layouter)?;
+
+        let ops = self
+            .log
+            .iter()
+            .enumerate()
+            .map(|(i, access)| {
+                let values = [
+                    access.map(|access| Fp::from(access.addr)),
+                    access.map(|access| Fp::from(access.value)),
+                    access.map(|access| Fp::from(access.is_write as u64)),
+                ];
+                let [addr, value, is_write]: [_; 3] = load_chip
+                    .load_privates(layouter.namespace(|| format!("load access {}", i)), &values)?
+                    .try_into()
+                    .expect("three cells loaded");
+                Ok(MemoryOp { addr, value, is_write })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        chip.check(layouter.namespace(|| "memory"), &ops)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for MemoryCircuit {
+    fn validate_witness(&self, _instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let log = self
+            .log
+            .iter()
+            .map(|access| {
+                let access = known(*access, "access")?;
+                check_below(Fp::from(access.addr), 1 << ADDR_BITS, "addr")?;
+                check_below(Fp::from(access.value), 1 << VALUE_BITS, "value")?;
+                Ok(access)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        if Self::is_consistent(&log) {
+            Ok(())
+        } else {
+            Err(InvalidWitness::InvalidParameter("a read does not return the last value written".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::{Access, MemoryCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 11;
+
+    fn write(addr: u64, value: u64) -> Access {
+        Access {
+            addr,
+            value,
+            is_write: true,
+        }
+    }
+
+    fn read(addr: u64, value: u64) -> Access {
+        Access {
+            addr,
+            value,
+            is_write: false,
+        }
+    }
+
+    fn run(log: &[Access]) -> MockProver<Fp> {
+        let circuit = MemoryCircuit {
+            log: log.iter().copied().map(Value::known).collect(),
+        };
+        assert_eq!(circuit.validate_witness(&[]).is_ok(), MemoryCircuit::is_consistent(log));
+        MockProver::run(K, &circuit, vec![vec![]]).unwrap()
+    }
+
+    #[test]
+    fn test_memory_consistent() {
+        let log = [write(7, 100), read(3, 0), read(7, 100), write(3, 5), write(7, 200), read(7, 200), read(3, 5)];
+        run(&log).assert_satisfied();
+    }
+
+    #[test]
+    fn test_memory_stale_read() {
+        let log = [write(7, 100), write(7, 200), read(7, 100)];
+        assert!(run(&log).verify().is_err());
+    }
+
+    #[test]
+    fn test_memory_uninitialised_read() {
+        let log = [write(7, 100), read(8, 100)];
+        assert!(run(&log).verify().is_err());
+    }
+}
@@ -0,0 +1,327 @@
+//! Merkle paths: the root a leaf hashes up to, given its index bits and the siblings on the
+//! way.
+//!
+//! A node is `hash2(left, right)` of a `CircuitHasher`, Poseidon's `commit` unless a gadget
+//! picks another. On every level a swap row orders the current node and its sibling by the
+//! index bit, 1 when the current node is a right child, and the hasher hashes the pair into
+//! the next level's node:
+//!
+//!       cur   |  sibling  |  bit  |  left  |  right  |  q_swap
+//!      ----------------------------------------------------------
+//!       n_i   |    s_i    |  b_i  |  l_i   |   r_i   |    1
+//!
+//!     l_i = b_i ? s_i : n_i,   r_i = b_i ? n_i : s_i,   n_{i+1} = hash2(l_i, r_i)
+//!
+//! The bits and siblings are cells the caller assigned, so a circuit can tie the bits to an
+//! index or a key, and hash two leaves along the same siblings.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use super::{commit::CommitChip, hasher::CircuitHasher};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    params::{MerkleParams, ParamsError},
+};
+
+/// The root `leaf` hashes up to, at `index` and along `siblings`, bottom up.
+pub fn root_from_path<H: CircuitHasher<Fp>>(leaf: Fp, index: u64, siblings: &[Fp]) -> Fp {
+    siblings.iter().enumerate().fold(leaf, |node, (i, sibling)| {
+        if (index >> i) & 1 == 1 {
+            H::hash2_native(*sibling, node)
+        } else {
+            H::hash2_native(node, *sibling)
+        }
+    })
+}
+
+/// A full tree of `2^depth` leaves, the ones not given being 0, computed natively.
+#[derive(Debug, Clone)]
+pub struct MerkleTree<H: CircuitHasher<Fp> = CommitChip> {
+    layers: Vec<Vec<Fp>>,
+    _marker: PhantomData<H>,
+}
+
+impl MerkleTree {
+    /// The tree over Poseidon.
+    pub fn new(depth: usize, leaves: &[Fp]) -> Self {
+        Self::with_hasher(depth, leaves)
+    }
+}
+
+impl<H: CircuitHasher<Fp>> MerkleTree<H> {
+    pub fn with_hasher(depth: usize, leaves: &[Fp]) -> Self {
+        assert!(leaves.len() <= 1 << depth, "{} leaves do not fit in a tree of depth {}", leaves.len(), depth);
+        let mut layer = leaves.to_vec();
+        layer.resize(1 << depth, Fp::zero());
+
+        let mut layers = vec![layer];
+        for _ in 0..depth {
+            let next = layers
+                .last()
+                .expect("at least the leaves")
+                .chunks(2)
+                .map(|pair| H::hash2_native(pair[0], pair[1]))
+                .collect();
+            layers.push(next);
+        }
+        Self {
+            layers,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    pub fn root(&self) -> Fp {
+        self.layers[self.depth()][0]
+    }
+
+    pub fn leaf(&self, index: u64) -> Fp {
+        self.layers[0][index as usize]
+    }
+
+    /// The siblings of the leaf at `index`, bottom up.
+    pub fn path(&self, index: u64) -> Vec<Fp> {
+        (0..self.depth())
+            .map(|i| self.layers[i][((index >> i) ^ 1) as usize])
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleConfig<H: CircuitHasher<Fp> = CommitChip> {
+    cur: Column<Advice>,
+    sibling: Column<Advice>,
+    bit: Column<Advice>,
+    left: Column<Advice>,
+    right: Column<Advice>,
+    q_swap: Selector,
+    hasher: H::Config,
+}
+
+pub struct MerkleChip<H: CircuitHasher<Fp> = CommitChip> {
+    config: MerkleConfig<H>,
+    hasher: H,
+}
+
+impl<H: CircuitHasher<Fp>> Chip<Fp> for MerkleChip<H> {
+    type Config = MerkleConfig<H>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<H: CircuitHasher<Fp>> MerkleChip<H> {
+    pub fn construct(config: MerkleConfig<H>) -> Self {
+        let hasher = H::construct(config.hasher.clone());
+        Self { config, hasher }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> MerkleConfig<H> {
+        let hasher = H::configure(meta);
+        let [cur, sibling, bit, left, right] = [(); 5].map(|_| meta.advice_column());
+        let q_swap = meta.selector();
+        for column in [cur, sibling, bit, left, right] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("swap", |meta| {
+            let q_swap = meta.query_selector(q_swap);
+            let [cur, sibling, bit, left, right] =
+                [cur, sibling, bit, left, right].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_swap)
+                .boolean("bit is boolean", bit.clone())
+                .select("left = bit ? sibling : cur", left, bit.clone(), sibling.clone(), cur.clone())
+                .select("right = bit ? cur : sibling", right, bit, cur, sibling)
+        });
+
+        MerkleConfig {
+            cur,
+            sibling,
+            bit,
+            left,
+            right,
+            q_swap,
+            hasher,
+        }
+    }
+
+    /// The chip nodes are hashed with, which can hash leaves as well.
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// The root `leaf` hashes up to, like `root_from_path`, for the index with little-endian
+    /// `bits`, each constrained to be boolean, and the `siblings` bottom up.
+    pub fn compute_root(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        leaf: &AssignedCell<Fp, Fp>,
+        bits: &[AssignedCell<Fp, Fp>],
+        siblings: &[AssignedCell<Fp, Fp>],
+    ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        if bits.len() != siblings.len() {
+            return Err(Halo2ExamplesError::new("MerkleChip", Error::Synthesis).in_region("compute root"));
+        }
+        let config = &self.config;
+        let mut node = leaf.clone();
+        for (i, (bit, sibling)) in bits.iter().zip(siblings.iter()).enumerate() {
+            let (left, right) = layouter
+                .assign_region(
+                    || format!("swap {}", i),
+                    |mut region| {
+                        config.q_swap.enable(&mut region, 0)?;
+                        let cur = node.copy_advice(|| "cur", &mut region, config.cur, 0)?;
+                        let sibling = sibling.copy_advice(|| "sibling", &mut region, config.sibling, 0)?;
+                        let bit = bit.copy_advice(|| "bit", &mut region, config.bit, 0)?;
+
+                        let swap = bit.value().map(|bit| *bit == Fp::one());
+                        let pair = cur.value().zip(sibling.value()).zip(swap).map(|((cur, sibling), swap)| {
+                            if swap {
+                                (*sibling, *cur)
+                            } else {
+                                (*cur, *sibling)
+                            }
+                        });
+                        let left = region.assign_advice(|| "left", config.left, 0, || pair.map(|(left, _)| left))?;
+                        let right = region.assign_advice(|| "right", config.right, 0, || pair.map(|(_, right)| right))?;
+                        Ok((left, right))
+                    },
+                )
+                .in_chip("MerkleChip", "swap")?;
+            node = self.hasher.hash2(layouter.namespace(|| format!("node {}", i + 1)), &left, &right)?;
+        }
+        Ok(node)
+    }
+}
+
+/// Exposes the root a private leaf hashes up to, in a tree whose depth is read from the
+/// params rather than fixed at compile time.
+pub struct MerkleRootCircuit<H: CircuitHasher<Fp> = CommitChip> {
+    pub params: MerkleParams,
+    pub leaf: Value<Fp>,
+    pub index: Value<u64>,
+    pub siblings: Vec<Value<Fp>>,
+    _marker: PhantomData<H>,
+}
+
+impl<H: CircuitHasher<Fp>> MerkleRootCircuit<H> {
+    /// The path of the leaf at `index` of `tree`, which has to be `params.depth` deep.
+    pub fn new(params: MerkleParams, tree: &MerkleTree<H>, index: u64) -> Result<Self, ParamsError> {
+        params.validate()?;
+        if tree.depth() != params.depth {
+            return Err(ParamsError::Invalid(format!(
+                "tree of depth {} does not match depth {}",
+                tree.depth(),
+                params.depth
+            )));
+        }
+        Ok(Self {
+            params,
+            leaf: Value::known(tree.leaf(index)),
+            index: Value::known(index),
+            siblings: tree.path(index).into_iter().map(Value::known).collect(),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<H: CircuitHasher<Fp>> Circuit<Fp> for MerkleRootCircuit<H> {
+    type Config = (MerkleConfig<H>, LoadPrivateConfig, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            params: self.params.clone(),
+            leaf: Value::unknown(),
+            index: Value::unknown(),
+            siblings: vec![Value::unknown(); self.params.depth],
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (MerkleChip::configure(meta), LoadPrivateChip::configure(meta, 1), instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, load_config, instance) = config;
+        let chip = MerkleChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let leaf = load_chip.load_private(layouter.namespace(|| "leaf"), self.leaf)?;
+        let bits: Vec<_> = (0..self.params.depth)
+            .map(|i| self.index.map(|index| Fp::from((index >> i) & 1)))
+            .collect();
+        let bits = load_chip.load_privates(layouter.namespace(|| "bits"), &bits)?;
+        let siblings = load_chip.load_privates(layouter.namespace(|| "siblings"), &self.siblings)?;
+
+        let root = chip.compute_root(layouter.namespace(|| "root"), &leaf, &bits, &siblings)?;
+        layouter.constrain_instance(root.cell(), instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::{root_from_path, MerkleRootCircuit, MerkleTree};
+    use crate::{
+        gadgets::{commit::CommitChip, hasher::CircuitHasher, mimc::MimcChip},
+        params::{CircuitParams, MerkleParams},
+    };
+
+    const DEPTH: usize = 3;
+
+    fn check_merkle_root<H: CircuitHasher<Fp>>(params: MerkleParams) {
+        let leaves: Vec<_> = (1..=6).map(Fp::from).collect();
+        let tree = MerkleTree::<H>::with_hasher(params.depth, &leaves);
+        for index in [0, 3, 5, 7] {
+            assert_eq!(root_from_path::<H>(tree.leaf(index), index, &tree.path(index)), tree.root());
+            let circuit = MerkleRootCircuit::new(params.clone(), &tree, index).unwrap();
+            MockProver::run(params.k, &circuit, vec![vec![tree.root()]]).unwrap().assert_satisfied();
+        }
+
+        // a leaf at the wrong index
+        let mut circuit = MerkleRootCircuit::new(params.clone(), &tree, 3).unwrap();
+        circuit.index = Value::known(2);
+        assert!(MockProver::run(params.k, &circuit, vec![vec![tree.root()]]).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        check_merkle_root::<CommitChip>(MerkleParams::default());
+    }
+
+    #[test]
+    fn test_merkle_root_mimc() {
+        // three hashes of 112 rows each
+        check_merkle_root::<MimcChip<Fp>>(MerkleParams::default());
+        assert_ne!(MerkleTree::new(DEPTH, &[Fp::one()]).root(), MerkleTree::<MimcChip<Fp>>::with_hasher(DEPTH, &[Fp::one()]).root());
+    }
+
+    #[test]
+    fn test_merkle_root_from_params() {
+        let params = CircuitParams::from_toml("[merkle]\nk = 10\ndepth = 5").unwrap().merkle;
+        check_merkle_root::<CommitChip>(params.clone());
+
+        // the tree has to be as deep as the params say
+        let tree = MerkleTree::new(DEPTH, &[Fp::one()]);
+        assert!(MerkleRootCircuit::new(params, &tree, 0).is_err());
+    }
+}
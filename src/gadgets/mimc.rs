@@ -0,0 +1,293 @@
+//! The MiMC hash, with the `x^5` permutation Poseidon also uses over Pasta, as a
+//! `CircuitHasher`.
+//!
+//! The cipher `E_k(x)` adds the key and a round constant and raises to the fifth power,
+//! `rounds::<F>()` times, enough for `5^rounds` to exceed the field; the hash compresses with
+//! Miyaguchi-Preneel, the key being the chaining value:
+//!
+//!     x_0 = a,   x_{i+1} = (x_i + b + c_i)^5,   E_b(a) = x_r + b,   hash2(a, b) = E_b(a) + b + a
+//!
+//! One row per round, with the key and the message carried down their columns:
+//!
+//!       x     |  key  |  msg  |  c (fixed)  |  selectors
+//!     ----------------------------------------------------
+//!       a     |   b   |   a   |    c_0      |  q_round
+//!      ...    |  ...  |  ...  |    ...      |  q_round
+//!      x_r    |   b   |   a   |             |  q_out
+//!      out    |       |       |             |
+//!
+//! The round constants come from ChaCha20 seeded with `SEED`, `c_0 = 0`. MiMC needs no
+//! table and has the simplest gate of any hash here, but takes 110 rows on the 255-bit Pasta
+//! fields and bn254 alike, where `Pow5Chip` takes about 40 for Poseidon.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use super::hasher::CircuitHasher;
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+};
+
+/// `ceil(log_5(2^NUM_BITS))`, the number of rounds for `F`.
+pub fn rounds<F: FieldExt>() -> usize {
+    (F::NUM_BITS as f64 / 5f64.log2()).ceil() as usize
+}
+
+/// The seed of the round constants, "mimc".
+const SEED: u64 = 0x6d69_6d63;
+
+pub fn round_constants<F: FieldExt>() -> Vec<F> {
+    let mut rng = ChaCha20Rng::seed_from_u64(SEED);
+    (0..rounds::<F>())
+        .map(|i| if i == 0 { F::zero() } else { F::random(&mut rng) })
+        .collect()
+}
+
+fn pow5<F: FieldExt>(x: F) -> F {
+    x.square().square() * x
+}
+
+/// `hash2(a, b)`, computed natively.
+pub fn mimc<F: FieldExt>(a: F, b: F) -> F {
+    let x = round_constants::<F>().into_iter().fold(a, |x, c| pow5(x + b + c));
+    x + b + b + a
+}
+
+#[derive(Debug, Clone)]
+pub struct MimcConfig {
+    x: Column<Advice>,
+    key: Column<Advice>,
+    msg: Column<Advice>,
+    c: Column<Fixed>,
+    q_round: Selector,
+    q_out: Selector,
+    constants: ConstantsConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct MimcChip<F: FieldExt> {
+    config: MimcConfig,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for MimcChip<F> {
+    type Config = MimcConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> MimcChip<F> {
+    pub fn construct(config: MimcConfig) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MimcConfig {
+        let [x, key, msg] = [(); 3].map(|_| meta.advice_column());
+        let c = meta.fixed_column();
+        let q_round = meta.selector();
+        let q_out = meta.selector();
+
+        // the length `hash_many` starts from is copied from a loaded constant
+        let constants = ConstantsChip::configure_with(meta, key);
+        for column in [x, key, msg] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("mimc round", |meta| {
+            let q_round = meta.query_selector(q_round);
+            let [x_next, key_next, msg_next] = [x, key, msg].map(|column| meta.query_advice(column, Rotation::next()));
+            let [x, key, msg] = [x, key, msg].map(|column| meta.query_advice(column, Rotation::cur()));
+            let c = meta.query_fixed(c, Rotation::cur());
+            let t = x + key.clone() + c;
+
+            GateBuilder::with_selector(q_round)
+                .equal("x_next = (x + key + c)^5", x_next, t.clone() * t.clone() * t.clone() * t.clone() * t)
+                .equal("key_next = key", key_next, key)
+                .equal("msg_next = msg", msg_next, msg)
+        });
+
+        meta.create_gate("mimc out", |meta| {
+            let q_out = meta.query_selector(q_out);
+            let out = meta.query_advice(x, Rotation::next());
+            let [x, key, msg] = [x, key, msg].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_out).equal("out = x_r + 2 * key + msg", out, x + key * F::from(2) + msg)
+        });
+
+        MimcConfig {
+            x,
+            key,
+            msg,
+            c,
+            q_round,
+            q_out,
+            constants,
+        }
+    }
+
+    /// `hash2(a, b)`, like `mimc`.
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let config = &self.config;
+        let constants = round_constants::<F>();
+        layouter
+            .assign_region(
+                || "mimc",
+                |mut region| {
+                    let mut x = a.copy_advice(|| "x_0", &mut region, config.x, 0)?;
+                    let mut key = b.copy_advice(|| "key", &mut region, config.key, 0)?;
+                    let mut msg = a.copy_advice(|| "msg", &mut region, config.msg, 0)?;
+                    for (i, c) in constants.iter().enumerate() {
+                        config.q_round.enable(&mut region, i)?;
+                        region.assign_fixed(|| format!("c_{}", i), config.c, i, || Value::known(*c))?;
+
+                        let next = x.value().zip(key.value()).map(|(x, key)| pow5(*x + key + c));
+                        x = region.assign_advice(|| format!("x_{}", i + 1), config.x, i + 1, || next)?;
+                        key = region.assign_advice(|| "key", config.key, i + 1, || key.value().copied())?;
+                        msg = region.assign_advice(|| "msg", config.msg, i + 1, || msg.value().copied())?;
+                    }
+
+                    let rounds = constants.len();
+                    config.q_out.enable(&mut region, rounds)?;
+                    let out = x.value().zip(key.value()).zip(msg.value()).map(|((x, key), msg)| *x + key + key + msg);
+                    region.assign_advice(|| "out", config.x, rounds + 1, || out)
+                },
+            )
+            .in_chip("MimcChip", "mimc")
+    }
+
+    /// The hash chain over `values`, like `CircuitHasher::hash_many_native`.
+    pub fn hash_all(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let length = F::from(values.len() as u64);
+        let mut hash = self.constants.load_constant(layouter.namespace(|| "length"), "h_0", length)?;
+        for (i, value) in values.iter().enumerate() {
+            hash = self.hash(layouter.namespace(|| format!("h_{}", i + 1)), value, &hash)?;
+        }
+        Ok(hash)
+    }
+}
+
+impl<F: FieldExt> CircuitHasher<F> for MimcChip<F> {
+    type Config = MimcConfig;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MimcConfig {
+        MimcChip::configure(meta)
+    }
+
+    fn construct(config: MimcConfig) -> Self {
+        MimcChip::construct(config)
+    }
+
+    fn hash2_native(a: F, b: F) -> F {
+        mimc(a, b)
+    }
+
+    fn hash2(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.hash(layouter, a, b)
+    }
+
+    fn hash_many(&self, layouter: impl Layouter<F>, values: &[AssignedCell<F, F>]) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.hash_all(layouter, values)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{mimc, rounds, MimcChip, MimcConfig};
+    use crate::{
+        chip::{LoadPrivateChip, LoadPrivateConfig},
+        gadgets::hasher::CircuitHasher,
+        public_inputs::{PublicOutputs, PublicOutputsConfig},
+        test_over_fields,
+    };
+
+    /// Exposes `hash2(a, b)` and `hash_many(values)`.
+    struct MimcCircuit<F> {
+        a: F,
+        b: F,
+        values: Vec<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MimcCircuit<F> {
+        type Config = (MimcConfig, LoadPrivateConfig, PublicOutputsConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: self.a,
+                b: self.b,
+                values: self.values.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            (MimcChip::configure(meta), LoadPrivateChip::configure(meta, 1), PublicOutputs::configure(meta, instance))
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+            let (config, load_config, outputs_config) = config;
+            let chip = MimcChip::construct(config);
+            let load_chip = LoadPrivateChip::construct(load_config);
+            let mut outputs = PublicOutputs::construct(outputs_config);
+
+            let a = load_chip.load_private(layouter.namespace(|| "a"), Value::known(self.a))?;
+            let b = load_chip.load_private(layouter.namespace(|| "b"), Value::known(self.b))?;
+            let values: Vec<_> = self.values.iter().copied().map(Value::known).collect();
+            let values = load_chip.load_privates(layouter.namespace(|| "values"), &values)?;
+
+            let hash = chip.hash2(layouter.namespace(|| "hash2"), &a, &b)?;
+            let hash_many = chip.hash_many(layouter.namespace(|| "hash_many"), &values)?;
+            outputs.expose("hash2", &hash);
+            outputs.expose("hash_many", &hash_many);
+            outputs.constrain(layouter.namespace(|| "public outputs"))?;
+            Ok(())
+        }
+    }
+
+    fn test_mimc<F: FieldExt>() {
+        assert_eq!(rounds::<F>(), 110);
+        let (a, b) = (F::from(3), F::from(5));
+        let values = vec![F::from(1), F::from(2), F::from(3)];
+        let hash_many = MimcChip::<F>::hash_many_native(&values);
+        assert_ne!(mimc(a, b), mimc(b, a));
+
+        let circuit = MimcCircuit { a, b, values };
+        MockProver::run(10, &circuit, vec![vec![mimc(a, b), hash_many]]).unwrap().assert_satisfied();
+        let prover = MockProver::run(10, &circuit, vec![vec![mimc(b, a), hash_many]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    test_over_fields!(test_mimc);
+}
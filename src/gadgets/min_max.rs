@@ -0,0 +1,409 @@
+//! The minimum or maximum of a list of cells, folded one comparison at a time.
+//!
+//! Every row takes the extremum so far, `ext` on the row above, and the next value `x`, and
+//! keeps one of them as the new `ext`, picked by a boolean `keep`. What proves the pick right
+//! is the difference between the two, `diff = ext - other` for `max` and `other - ext` for
+//! `min`, where `other = prev + x - ext` is the one not kept: `BitsChip` checks that it fits
+//! in `NUM_BITS` bits, i.e. is not negative.
+//!
+//!         x      |  keep  |   ext    |  diff  |  selector
+//!       -------------------------------------------------
+//!                |        |   x_0    |        |
+//!         x_1    |  k_1   |  ext_1   |  d_1   |  q_max / q_min
+//!         ...    |  ...   |   ...    |  ...   |  q_max / q_min
+//!       x_{n-1}  |        | ext_{n-1}|        |
+//!
+//!     ext_i = keep * ext_{i-1} + (1 - keep) * x_i
+//!     d_i   = ±(2 * ext_i - ext_{i-1} - x_i)
+//!
+//! So every `ext_i` is one of the values and no smaller (larger) than any of them so far.
+//! A negative difference only wraps around to a large field element if the values are
+//! themselves below `2^NUM_BITS`, which callers must have checked, e.g. with `BitsChip`.
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::bits::{BitsChip, BitsConfig};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+#[derive(Debug, Clone)]
+pub struct MinMaxConfig {
+    x: Column<Advice>,
+    keep: Column<Advice>,
+    ext: Column<Advice>,
+    diff: Column<Advice>,
+    q_max: Selector,
+    q_min: Selector,
+    bits: BitsConfig,
+}
+
+pub struct MinMaxChip<F: FieldExt, const NUM_BITS: usize> {
+    config: MinMaxConfig,
+    bits: BitsChip<F>,
+}
+
+impl<F: FieldExt, const NUM_BITS: usize> Chip<F> for MinMaxChip<F, NUM_BITS> {
+    type Config = MinMaxConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const NUM_BITS: usize> MinMaxChip<F, NUM_BITS> {
+    pub fn construct(config: MinMaxConfig) -> Self {
+        let bits = BitsChip::construct(config.bits.clone());
+        Self { config, bits }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> MinMaxConfig {
+        let bits = BitsChip::configure(meta, instance);
+        let x = meta.advice_column();
+        let keep = meta.advice_column();
+        let ext = meta.advice_column();
+        let diff = meta.advice_column();
+        let q_max = meta.selector();
+        let q_min = meta.selector();
+
+        meta.enable_equality(x);
+        meta.enable_equality(ext);
+        meta.enable_equality(diff);
+
+        for (name, selector, sign) in [("max", q_max, F::one()), ("min", q_min, -F::one())] {
+            meta.create_gate(name, |meta| {
+                let selector = meta.query_selector(selector);
+                let x = meta.query_advice(x, Rotation::cur());
+                let keep = meta.query_advice(keep, Rotation::cur());
+                let prev = meta.query_advice(ext, Rotation::prev());
+                let ext = meta.query_advice(ext, Rotation::cur());
+                let diff = meta.query_advice(diff, Rotation::cur());
+
+                GateBuilder::with_selector(selector)
+                    .boolean("keep is boolean", keep.clone())
+                    .select("ext = keep ? prev : x", ext.clone(), keep, prev.clone(), x.clone())
+                    .equal("diff = ext - other", diff, (ext * F::from(2) - prev - x) * sign)
+            });
+        }
+
+        MinMaxConfig {
+            x,
+            keep,
+            ext,
+            diff,
+            q_max,
+            q_min,
+            bits,
+        }
+    }
+
+    /// The chip the differences are range checked with, and values can be range checked with
+    /// before comparing them.
+    pub fn bits(&self) -> &BitsChip<F> {
+        &self.bits
+    }
+
+    /// The smallest of `values`, all below `2^NUM_BITS`.
+    pub fn min(
+        &self,
+        layouter: impl Layouter<F>,
+        values: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.fold(layouter, false, values)
+    }
+
+    /// The largest of `values`, all below `2^NUM_BITS`.
+    pub fn max(
+        &self,
+        layouter: impl Layouter<F>,
+        values: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.fold(layouter, true, values)
+    }
+
+    /// `a` and `b` in ascending order, both below `2^NUM_BITS`. Since the smaller and the
+    /// larger are each one of the two, and the smaller is not above the larger, together
+    /// they are always `a` and `b` again, which is what makes sorting networks out of this.
+    pub fn sort_pair(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Halo2ExamplesError> {
+        let pair = [a.clone(), b.clone()];
+        let lo = self.min(layouter.namespace(|| "lo"), &pair)?;
+        let hi = self.max(layouter.namespace(|| "hi"), &pair)?;
+        Ok((lo, hi))
+    }
+
+    /// Checks that `values`, all below `2^NUM_BITS`, are in ascending order: each is the
+    /// larger of itself and the one before, one comparison per neighbouring pair.
+    pub fn assert_sorted(&self, mut layouter: impl Layouter<F>, values: &[AssignedCell<F, F>]) -> Result<(), Halo2ExamplesError> {
+        for (i, pair) in values.windows(2).enumerate() {
+            let max = self.max(layouter.namespace(|| format!("max(v_{}, v_{})", i, i + 1)), pair)?;
+            layouter
+                .assign_region(|| "sorted", |mut region| region.constrain_equal(max.cell(), pair[1].cell()))
+                .in_chip("MinMaxChip", "sorted")?;
+        }
+        Ok(())
+    }
+
+    pub fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Halo2ExamplesError> {
+        self.bits.expose_public(layouter, cell, row)
+    }
+
+    fn fold(
+        &self,
+        mut layouter: impl Layouter<F>,
+        is_max: bool,
+        values: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let (name, selector) = if is_max { ("max", self.config.q_max) } else { ("min", self.config.q_min) };
+        if values.is_empty() {
+            return Err(Halo2ExamplesError::new("MinMaxChip", Error::Synthesis).in_region(name));
+        }
+
+        let (ext, diffs) = layouter
+            .assign_region(
+                || name,
+                |mut region| {
+                    let mut ext = values[0].copy_advice(|| "ext_0", &mut region, self.config.ext, 0)?;
+                    let mut diffs = Vec::with_capacity(values.len() - 1);
+
+                    for (i, value) in values.iter().enumerate().skip(1) {
+                        selector.enable(&mut region, i)?;
+                        let x = value.copy_advice(|| format!("x_{}", i), &mut region, self.config.x, i)?;
+
+                        // below 2^NUM_BITS the values compare as integers
+                        let keep = ext.value().zip(x.value()).map(|(prev, x)| {
+                            let prev_is_larger = prev.get_lower_128() >= x.get_lower_128();
+                            prev_is_larger == is_max
+                        });
+                        region.assign_advice(|| format!("keep_{}", i), self.config.keep, i, || {
+                            keep.map(|keep| F::from(keep as u64))
+                        })?;
+
+                        let next = keep.zip(ext.value().zip(x.value())).map(|(keep, (prev, x))| if keep { *prev } else { *x });
+                        let diff = next.zip(ext.value().zip(x.value())).map(|(next, (prev, x))| {
+                            let diff = next.double() - prev - x;
+                            if is_max {
+                                diff
+                            } else {
+                                -diff
+                            }
+                        });
+                        diffs.push(region.assign_advice(|| format!("diff_{}", i), self.config.diff, i, || diff)?);
+                        ext = region.assign_advice(|| format!("ext_{}", i), self.config.ext, i, || next)?;
+                    }
+                    Ok((ext, diffs))
+                },
+            )
+            .in_chip("MinMaxChip", name)?;
+
+        for (i, diff) in diffs.iter().enumerate() {
+            self.bits.unpack(layouter.namespace(|| format!("diff_{}", i + 1)), diff, NUM_BITS)?;
+        }
+        Ok(ext)
+    }
+}
+
+/// Range checks private values to `NUM_BITS` bits and exposes their minimum and maximum.
+#[derive(Default)]
+pub struct MinMaxCircuit<const NUM_BITS: usize> {
+    pub values: Vec<Value<u64>>,
+}
+
+impl<const NUM_BITS: usize> MinMaxCircuit<NUM_BITS> {
+    /// The minimum and maximum of `values`.
+    pub fn outputs(values: &[u64]) -> [u64; 2] {
+        let min = values.iter().copied().min().unwrap_or_default();
+        let max = values.iter().copied().max().unwrap_or_default();
+        [min, max]
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const NUM_BITS: usize> Circuit<F> for MinMaxCircuit<NUM_BITS> {
+    type Config = (MinMaxConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let min_max = MinMaxChip::<F, NUM_BITS>::configure(meta, instance);
+        (min_max, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = MinMaxChip::<F, NUM_BITS>::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let values = self
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let cell = load_chip.load_private(layouter.namespace(|| format!("load v_{}", i)), value.map(F::from))?;
+                chip.bits().unpack(layouter.namespace(|| format!("range check v_{}", i)), &cell, NUM_BITS)?;
+                Ok(cell)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let min = chip.min(layouter.namespace(|| "min"), &values)?;
+        let max = chip.max(layouter.namespace(|| "max"), &values)?;
+        chip.expose_public(layouter.namespace(|| "min"), &min, 0)?;
+        chip.expose_public(layouter.namespace(|| "max"), &max, 1)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const NUM_BITS: usize> ValidateWitness<F> for MinMaxCircuit<NUM_BITS> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        if self.values.is_empty() {
+            return Err(InvalidWitness::InvalidParameter("no values to compare".to_string()));
+        }
+        let values = self
+            .values
+            .iter()
+            .map(|value| {
+                let value = known(*value, "value")?;
+                check_below(F::from(value), 1u128 << NUM_BITS, "value")?;
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        check_instances(instances, &Self::outputs(&values).map(F::from))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::PrimeFieldBits;
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use proptest::prelude::*;
+
+    use super::{MinMaxChip, MinMaxCircuit, MinMaxConfig};
+    use crate::{
+        chip::{LoadPrivateChip, LoadPrivateConfig},
+        test_over_fields,
+    };
+
+    const K: u32 = 9;
+
+    fn instances<F: FieldExt>(values: &[u64]) -> Vec<Vec<F>> {
+        vec![MinMaxCircuit::<16>::outputs(values).iter().map(|output| F::from(*output)).collect()]
+    }
+
+    fn test_min_max<F: FieldExt + PrimeFieldBits>() {
+        for values in [vec![7], vec![3, 9], vec![9, 3], vec![5, 1, 65535, 0, 42, 42], vec![4, 4, 4]] {
+            let circuit = MinMaxCircuit::<16> {
+                values: values.iter().copied().map(Value::known).collect(),
+            };
+
+            let prover = MockProver::run(K, &circuit, instances::<F>(&values)).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    fn test_min_max_wrong_extremum<F: FieldExt + PrimeFieldBits>() {
+        let values = [5, 1, 9, 3];
+        let circuit = MinMaxCircuit::<16> {
+            values: values.iter().copied().map(Value::known).collect(),
+        };
+
+        // 3 is one of the values, just not the largest
+        let prover = MockProver::run(K, &circuit, vec![vec![F::from(1), F::from(3)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// Only checks that `values` are in ascending order.
+    #[derive(Default)]
+    struct SortedCircuit {
+        values: Vec<u64>,
+    }
+
+    impl Circuit<Fp> for SortedCircuit {
+        type Config = (MinMaxConfig, LoadPrivateConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            let min_max = MinMaxChip::<Fp, 16>::configure(meta, instance);
+            (min_max, LoadPrivateChip::configure(meta, 1))
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (config, load_config) = config;
+            let chip = MinMaxChip::<Fp, 16>::construct(config);
+            let load_chip = LoadPrivateChip::construct(load_config);
+
+            let values = self
+                .values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let cell = load_chip.load_private(layouter.namespace(|| format!("load v_{}", i)), Value::known(Fp::from(*value)))?;
+                    chip.bits().unpack(layouter.namespace(|| format!("range check v_{}", i)), &cell, 16)?;
+                    Ok(cell)
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            chip.assert_sorted(layouter.namespace(|| "sorted"), &values)?;
+            Ok(())
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn prop_min_max_agree_with_native(values in prop::collection::vec(0u64..1 << 16, 1..6)) {
+            let circuit = MinMaxCircuit::<16> {
+                values: values.iter().copied().map(Value::known).collect(),
+            };
+            let prover = MockProver::run(K, &circuit, instances::<Fp>(&values)).unwrap();
+            prop_assert!(prover.verify().is_ok());
+        }
+
+        #[test]
+        fn prop_assert_sorted_accepts_exactly_sorted(mut values in prop::collection::vec(0u64..1 << 16, 2..6)) {
+            let is_sorted = values.windows(2).all(|pair| pair[0] <= pair[1]);
+            let prover = MockProver::run(K, &SortedCircuit { values: values.clone() }, vec![vec![]]).unwrap();
+            prop_assert_eq!(prover.verify().is_ok(), is_sorted);
+
+            values.sort_unstable();
+            let prover = MockProver::run(K, &SortedCircuit { values }, vec![vec![]]).unwrap();
+            prop_assert!(prover.verify().is_ok());
+        }
+    }
+
+    test_over_fields!(test_min_max, test_min_max_wrong_extremum);
+}
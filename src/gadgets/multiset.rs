@@ -0,0 +1,319 @@
+//! Proves that two lists of cells hold the same multiset of values.
+//!
+//! `a` is a permutation of `b` iff `prod(a_i + gamma) == prod(b_i + gamma)` as polynomials in
+//! `gamma`, and by Schwartz-Zippel two different products of degree `n` agree on at most `n`
+//! points, so checking them at one random `gamma` is enough, provided the prover cannot pick
+//! the values after seeing it. halo2_proofs 0.2 has no verifier challenges to draw `gamma`
+//! from, so it is derived in the circuit instead, Fiat-Shamir style, as a Poseidon hash chain
+//! over every value of both lists:
+//!
+//!     h_0 = 0,   h_{i+1} = Poseidon(x_i, h_i)   for x = a_0, ..., a_{n-1}, b_0, ..., b_{n-1},
+//!     gamma = h_{2n}
+//!
+//! Every value is shifted by `gamma` in a row of its own, and `RunningProductChip::ratio`
+//! pins `prod(a_i + gamma) / prod(b_i + gamma)` to 1:
+//!
+//!        value   |  gamma   |    shifted    |  q_shift
+//!       -------------------------------------------------
+//!         x_i    |  gamma   |  x_i + gamma  |    1
+//!
+//! A `b_i + gamma` of zero would leave the ratio free, but the prover would have to find a
+//! `b_i` that hashes to its own negation. `multiset` in the `halo2-examples-next` crate, in
+//! `next/`, takes `gamma` from a verifier challenge instead.
+
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use super::{
+    commit::{CommitChip, CommitConfig},
+    running_product::{RunningProductChip, RunningProductConfig},
+};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{known, InvalidWitness, ValidateWitness},
+};
+
+#[derive(Debug, Clone)]
+pub struct MultisetEqConfig {
+    value: Column<Advice>,
+    gamma: Column<Advice>,
+    shifted: Column<Advice>,
+    q_shift: Selector,
+    hash: CommitConfig,
+    product: RunningProductConfig,
+    constants: ConstantsConfig,
+}
+
+pub struct MultisetEqChip {
+    config: MultisetEqConfig,
+    hash: CommitChip,
+    product: RunningProductChip<Fp>,
+    constants: ConstantsChip<Fp>,
+}
+
+impl Chip<Fp> for MultisetEqChip {
+    type Config = MultisetEqConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl MultisetEqChip {
+    pub fn construct(config: MultisetEqConfig) -> Self {
+        let hash = CommitChip::construct(config.hash.clone());
+        let product = RunningProductChip::construct(config.product.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self {
+            config,
+            hash,
+            product,
+            constants,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, instance: Column<Instance>) -> MultisetEqConfig {
+        let hash = CommitChip::configure(meta);
+        let product = RunningProductChip::configure(meta, instance);
+        let value = meta.advice_column();
+        let gamma = meta.advice_column();
+        let shifted = meta.advice_column();
+        let q_shift = meta.selector();
+
+        // the hash chain the challenge is drawn from starts at a loaded zero
+        let constants = ConstantsChip::configure_with(meta, gamma);
+        meta.enable_equality(value);
+        meta.enable_equality(shifted);
+
+        meta.create_gate("shift by gamma", |meta| {
+            let q_shift = meta.query_selector(q_shift);
+            let value = meta.query_advice(value, Rotation::cur());
+            let gamma = meta.query_advice(gamma, Rotation::cur());
+            let shifted = meta.query_advice(shifted, Rotation::cur());
+
+            GateBuilder::with_selector(q_shift).equal("shifted = value + gamma", shifted, value + gamma)
+        });
+
+        MultisetEqConfig {
+            value,
+            gamma,
+            shifted,
+            q_shift,
+            hash,
+            product,
+            constants,
+        }
+    }
+
+    /// Constrains `a` and `b` to hold the same values with the same multiplicities.
+    pub fn check(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        a: &[AssignedCell<Fp, Fp>],
+        b: &[AssignedCell<Fp, Fp>],
+    ) -> Result<(), Halo2ExamplesError> {
+        if a.len() != b.len() {
+            return Err(Halo2ExamplesError::new("MultisetEqChip", Error::Synthesis).in_region("multiset equality"));
+        }
+        let gamma = self.challenge(layouter.namespace(|| "challenge"), a, b)?;
+        self.check_at(layouter, a, b, &gamma)
+    }
+
+    /// Constrains `prod(a_i + gamma) == prod(b_i + gamma)` for a `gamma` given by the caller.
+    /// This only proves multiset equality if `gamma` is out of the prover's control, which is
+    /// what `check` takes care of.
+    pub fn check_at(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        a: &[AssignedCell<Fp, Fp>],
+        b: &[AssignedCell<Fp, Fp>],
+        gamma: &AssignedCell<Fp, Fp>,
+    ) -> Result<(), Halo2ExamplesError> {
+        let nums = self.shift(layouter.namespace(|| "shift a"), a, gamma)?;
+        let dens = self.shift(layouter.namespace(|| "shift b"), b, gamma)?;
+        let ratio = self.product.ratio(layouter.namespace(|| "ratio"), &nums, &dens)?;
+        self.product.assert_one(layouter.namespace(|| "ratio is one"), &ratio)
+    }
+
+    // The hash chain over `a` then `b`.
+    fn challenge(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        a: &[AssignedCell<Fp, Fp>],
+        b: &[AssignedCell<Fp, Fp>],
+    ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        let mut h = self.constants.load_constant(layouter.namespace(|| "h_0"), "h_0", Fp::zero())?;
+        for (i, x) in a.iter().chain(b.iter()).enumerate() {
+            h = self.hash.commit(layouter.namespace(|| format!("h_{}", i + 1)), x, &h)?;
+        }
+        Ok(h)
+    }
+
+    fn shift(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        values: &[AssignedCell<Fp, Fp>],
+        gamma: &AssignedCell<Fp, Fp>,
+    ) -> Result<Vec<AssignedCell<Fp, Fp>>, Halo2ExamplesError> {
+        layouter
+            .assign_region(
+                || "shift by gamma",
+                |mut region| {
+                    values
+                        .iter()
+                        .enumerate()
+                        .map(|(i, value)| {
+                            self.config.q_shift.enable(&mut region, i)?;
+                            let value = value.copy_advice(|| format!("x_{}", i), &mut region, self.config.value, i)?;
+                            let gamma = gamma.copy_advice(|| "gamma", &mut region, self.config.gamma, i)?;
+                            let shifted = value.value().copied() + gamma.value().copied();
+                            region.assign_advice(|| format!("x_{} + gamma", i), self.config.shifted, i, || shifted)
+                        })
+                        .collect()
+                },
+            )
+            .in_chip("MultisetEqChip", "shift by gamma")
+    }
+}
+
+/// Proves that the private lists `a` and `b` are permutations of each other.
+#[derive(Default)]
+pub struct MultisetEqCircuit {
+    pub a: Vec<Value<Fp>>,
+    pub b: Vec<Value<Fp>>,
+}
+
+impl Circuit<Fp> for MultisetEqCircuit {
+    type Config = (MultisetEqConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: vec![Value::unknown(); self.a.len()],
+            b: vec![Value::unknown(); self.b.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let multiset = MultisetEqChip::configure(meta, instance);
+        (multiset, LoadPrivateChip::configure(meta, 2))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = MultisetEqChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let a = load_chip.load_privates(layouter.namespace(|| "load a"), &self.a)?;
+        let b = load_chip.load_privates(layouter.namespace(|| "load b"), &self.b)?;
+        chip.check(layouter.namespace(|| "a ~ b"), &a, &b)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for MultisetEqCircuit {
+    fn validate_witness(&self, _instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let sorted = |values: &[Value<Fp>], name: &'static str| -> Result<Vec<_>, InvalidWitness> {
+            let mut values = values
+                .iter()
+                .map(|value| known(*value, name).map(|value| value.to_repr()))
+                .collect::<Result<Vec<_>, _>>()?;
+            values.sort_unstable();
+            Ok(values)
+        };
+        if sorted(&self.a, "a")? == sorted(&self.b, "b")? {
+            Ok(())
+        } else {
+            Err(InvalidWitness::NotEqual { left: "a", right: "b" })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{MultisetEqChip, MultisetEqCircuit, MultisetEqConfig};
+    use crate::{chip::{LoadPrivateChip, LoadPrivateConfig}, validate::ValidateWitness};
+
+    const K: u32 = 11;
+
+    fn circuit(a: &[u64], b: &[u64]) -> MultisetEqCircuit {
+        let values = |values: &[u64]| values.iter().map(|value| Value::known(Fp::from(*value))).collect();
+        MultisetEqCircuit { a: values(a), b: values(b) }
+    }
+
+    #[test]
+    fn test_multiset_eq() {
+        let circuit = circuit(&[4, 8, 15, 16, 23, 42], &[42, 16, 4, 23, 15, 8]);
+        assert_eq!(circuit.validate_witness(&[]), Ok(()));
+
+        let prover = MockProver::run(K, &circuit, vec![vec![]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_multiset_not_eq() {
+        // same values, different multiplicities
+        for (a, b) in [([1, 1, 2], [1, 2, 2]), ([0, 3, 7], [1, 1, 7])] {
+            let circuit = circuit(&a, &b);
+            assert!(circuit.validate_witness(&[]).is_err());
+
+            let prover = MockProver::run(K, &circuit, vec![vec![]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    /// `MultisetEqChip::check_at` with a `gamma` the prover picked.
+    struct ChosenGamma(MultisetEqCircuit, u64);
+
+    impl Circuit<Fp> for ChosenGamma {
+        type Config = (MultisetEqConfig, LoadPrivateConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            ChosenGamma(self.0.without_witnesses(), self.1)
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            MultisetEqCircuit::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (config, load_config) = config;
+            let chip = MultisetEqChip::construct(config);
+            let load_chip = LoadPrivateChip::construct(load_config);
+
+            let a = load_chip.load_privates(layouter.namespace(|| "load a"), &self.0.a)?;
+            let b = load_chip.load_privates(layouter.namespace(|| "load b"), &self.0.b)?;
+            let gamma = load_chip.load_private(layouter.namespace(|| "gamma"), Value::known(Fp::from(self.1)))?;
+            chip.check_at(layouter.namespace(|| "a ~ b"), &a, &b, &gamma)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_multiset_chosen_gamma() {
+        // gamma * (gamma + 3) == (gamma + 1)^2 at gamma = 1, so a prover picking gamma after
+        // the values passes off [0, 3] as [1, 1]; hashing the values into gamma stops that
+        let prover = MockProver::run(K, &ChosenGamma(circuit(&[0, 3], &[1, 1]), 1), vec![vec![]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(K, &circuit(&[0, 3], &[1, 1]), vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
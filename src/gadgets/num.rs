@@ -0,0 +1,137 @@
+//! An assigned field element with arithmetic methods, in the style of bellman's
+//! `AllocatedNum`: `x.mul(&chip, layouter, &y)?` lays out the gate and returns the product,
+//! so circuits can be written as expressions over the cells other chips return rather than
+//! as regions.
+//!
+//! Every operation is one row of `arith::ArithChip`, with its operands copied in, and
+//! `square` a multiplication with the same cell copied into both.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::arith::{ArithChip, ArithConfig};
+use crate::{
+    chip::LoadInstructions,
+    error::{Halo2ExamplesError, ResultExt},
+};
+
+/// A cell holding a field element, with the arithmetic of `ArithChip` as methods. Any
+/// `AssignedCell<F, F>`, e.g. the output of another chip, converts into one.
+#[derive(Debug, Clone)]
+pub struct Num<F: FieldExt>(pub AssignedCell<F, F>);
+
+impl<F: FieldExt> From<AssignedCell<F, F>> for Num<F> {
+    fn from(cell: AssignedCell<F, F>) -> Self {
+        Num(cell)
+    }
+}
+
+impl<F: FieldExt> Num<F> {
+    /// Witnesses a private value.
+    pub fn alloc(chip: &ArithChip<F>, layouter: impl Layouter<F>, value: Value<F>) -> Result<Self, Halo2ExamplesError> {
+        chip.load_private(layouter, value).map(Num)
+    }
+
+    /// Witnesses a constant. The circuit must have a constant column, e.g. the one
+    /// `ConstantsChip::configure` enables.
+    pub fn constant(chip: &ArithChip<F>, layouter: impl Layouter<F>, constant: F) -> Result<Self, Halo2ExamplesError> {
+        chip.load_constant(layouter, constant).map(Num)
+    }
+
+    pub fn value(&self) -> Value<F> {
+        self.0.value().copied()
+    }
+
+    pub fn cell(&self) -> Cell {
+        self.0.cell()
+    }
+
+    pub fn add(&self, chip: &ArithChip<F>, layouter: impl Layouter<F>, other: &Self) -> Result<Self, Halo2ExamplesError> {
+        chip.add(layouter, &self.0, &other.0).map(Num)
+    }
+
+    pub fn sub(&self, chip: &ArithChip<F>, layouter: impl Layouter<F>, other: &Self) -> Result<Self, Halo2ExamplesError> {
+        chip.sub(layouter, &self.0, &other.0).map(Num)
+    }
+
+    pub fn mul(&self, chip: &ArithChip<F>, layouter: impl Layouter<F>, other: &Self) -> Result<Self, Halo2ExamplesError> {
+        chip.mul(layouter, &self.0, &other.0).map(Num)
+    }
+
+    pub fn square(&self, chip: &ArithChip<F>, layouter: impl Layouter<F>) -> Result<Self, Halo2ExamplesError> {
+        chip.mul(layouter, &self.0, &self.0).map(Num)
+    }
+
+    /// Constrains the two cells to hold the same value. Takes no row.
+    pub fn constrain_equal(&self, mut layouter: impl Layouter<F>, other: &Self) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .assign_region(|| "constrain equal", |mut region| region.constrain_equal(self.cell(), other.cell()))
+            .in_chip("Num", "constrain equal")
+    }
+}
+
+/// Checks `(x + y)^2 - x * y == out` for private `x`, `y` and `out`, written with `Num`.
+#[derive(Default)]
+pub struct NumCircuit<F> {
+    pub x: Value<F>,
+    pub y: Value<F>,
+    pub out: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for NumCircuit<F> {
+    type Config = ArithConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        ArithChip::configure(meta, advice)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = ArithChip::construct(config);
+        let x = Num::alloc(&chip, layouter.namespace(|| "x"), self.x)?;
+        let y = Num::alloc(&chip, layouter.namespace(|| "y"), self.y)?;
+        let out = Num::alloc(&chip, layouter.namespace(|| "out"), self.out)?;
+
+        let sum = x.add(&chip, layouter.namespace(|| "x + y"), &y)?;
+        let product = x.mul(&chip, layouter.namespace(|| "x * y"), &y)?;
+        let result = sum
+            .square(&chip, layouter.namespace(|| "(x + y)^2"))?
+            .sub(&chip, layouter.namespace(|| "(x + y)^2 - x * y"), &product)?;
+        result.constrain_equal(layouter.namespace(|| "result == out"), &out)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver};
+
+    use super::NumCircuit;
+    use crate::test_over_fields;
+
+    fn test_num<F: FieldExt>() {
+        let circuit = |out: u64| NumCircuit {
+            x: Value::known(F::from(3)),
+            y: Value::known(F::from(4)),
+            out: Value::known(F::from(out)),
+        };
+
+        // (3 + 4)^2 - 3 * 4 = 37
+        MockProver::run(4, &circuit(37), vec![]).unwrap().assert_satisfied();
+        assert!(MockProver::run(4, &circuit(38), vec![]).unwrap().verify().is_err());
+
+        // (3 - 4)^2 + 3 * 4, with y negative
+        let circuit = NumCircuit {
+            x: Value::known(F::from(3)),
+            y: Value::known(-F::from(4)),
+            out: Value::known(F::from(1 + 12)),
+        };
+        MockProver::run(4, &circuit, vec![]).unwrap().assert_satisfied();
+    }
+
+    test_over_fields!(test_num);
+}
@@ -0,0 +1,132 @@
+//! Padding for traces shorter than the rows allocated to them.
+//!
+//! A circuit's layout is fixed at keygen, so a trace of data-dependent length, like a VM run,
+//! is laid out over as many rows as the longest run needs and the rest are padding. The
+//! convention here: padding rows repeat the state of the row above, i.e. behave like a halt
+//! instruction whose transition is the identity, and are marked by a boolean column.
+//!
+//!        state ...   |  is_padding  |  q_padding
+//!       ------------------------------------------
+//!          s_0       |      0       |
+//!          s_1       |      0       |     1
+//!          ...       |     ...      |     1
+//!       s_h (halt)   |      0       |     1
+//!          s_h       |      1       |     1        s_i = s_{i-1}
+//!          ...       |      1       |     1
+//!
+//! with, on every row but the first,
+//!
+//! - `is_padding` boolean, and once set never cleared again;
+//! - the first padding row following a row the caller reports as halted;
+//! - every state column equal to the row above on padding rows.
+//!
+//! The first row is never padding. The caller's own transition gate, checking row `i`
+//! against row `i + 1`, should be turned off with `not_padding_next` where padding starts.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::Halo2ExamplesError,
+    gate::GateBuilder,
+};
+
+#[derive(Debug, Clone)]
+pub struct PaddingConfig {
+    is_padding: Column<Advice>,
+    q_padding: Selector,
+    constants: ConstantsConfig,
+}
+
+impl PaddingConfig {
+    /// `halted` must be 1 on a row whose predecessor halted and 0 otherwise, e.g. a halt
+    /// opcode bit queried at `Rotation::prev()`. `state` lists the columns padding repeats.
+    pub fn configure<F: FieldExt>(
+        meta: &mut ConstraintSystem<F>,
+        halted: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        state: &[Column<Advice>],
+    ) -> Self {
+        let is_padding = meta.advice_column();
+        let q_padding = meta.selector();
+
+        // the first row's `is_padding == 0` is copied from a loaded constant
+        let constants = ConstantsChip::configure_with(meta, is_padding);
+
+        meta.create_gate("padding", |meta| {
+            let q_padding = meta.query_selector(q_padding);
+            let halted = halted(meta);
+            let prev = meta.query_advice(is_padding, Rotation::prev());
+            let cur = meta.query_advice(is_padding, Rotation::cur());
+            let one = Expression::Constant(F::one());
+
+            let mut gate = GateBuilder::with_selector(q_padding)
+                .boolean("is_padding is boolean", cur.clone())
+                .constrain("padding stays padding", prev.clone() * (one.clone() - cur.clone()))
+                .constrain("padding starts after a halt", (cur.clone() - prev) * (one - halted));
+            for column in state {
+                let state_prev = meta.query_advice(*column, Rotation::prev());
+                let state_cur = meta.query_advice(*column, Rotation::cur());
+                gate = gate.constrain("padding repeats the state", cur.clone() * (state_cur - state_prev));
+            }
+            gate
+        });
+
+        Self {
+            is_padding,
+            q_padding,
+            constants,
+        }
+    }
+
+    /// 1 unless the row below is padding, for turning off the caller's transition gate.
+    pub fn not_padding_next<F: FieldExt>(&self, meta: &mut VirtualCells<'_, F>) -> Expression<F> {
+        Expression::Constant(F::one()) - meta.query_advice(self.is_padding, Rotation::next())
+    }
+
+    /// The zero row 0's `is_padding` is copied from, to load before the trace's region.
+    pub fn load_zero<F: FieldExt>(&self, layouter: impl Layouter<F>) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        ConstantsChip::construct(self.constants.clone()).load_constant(layouter, "zero", F::zero())
+    }
+
+    /// Marks row `offset` of a trace as padding or not; row 0 must never be, and copies
+    /// `zero` from `load_zero`.
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        is_padding: Value<bool>,
+        zero: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        if offset == 0 {
+            zero.copy_advice(|| "is_padding", region, self.is_padding, 0)?;
+        } else {
+            self.q_padding.enable(region, offset)?;
+            region.assign_advice(|| "is_padding", self.is_padding, offset, || is_padding.map(|is_padding| F::from(is_padding as u64)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Pads `trace` to `rows` rows by repeating its last row, with whether each row is padding.
+/// A trace already `rows` long or longer is cut, with no padding.
+pub fn pad<T: Clone>(trace: &[T], rows: usize) -> Vec<(T, bool)> {
+    let last = trace.last().expect("a trace has at least one row");
+    trace
+        .iter()
+        .cloned()
+        .map(|row| (row, false))
+        .chain(std::iter::repeat((last.clone(), true)))
+        .take(rows)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::pad;
+
+    #[test]
+    fn test_pad() {
+        assert_eq!(pad(&[1, 2, 3], 5), vec![(1, false), (2, false), (3, false), (3, true), (3, true)]);
+        assert_eq!(pad(&[1, 2, 3], 2), vec![(1, false), (2, false)]);
+    }
+}
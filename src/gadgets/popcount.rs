@@ -0,0 +1,220 @@
+//! The number of set bits of a value: `BitsChip::unpack` splits it into checked bits, and a
+//! running count over them adds them up,
+//!
+//!     count_0 = 0,   count_{i+1} = count_i + b_i,   count_n = b_0 + ... + b_{n-1}
+//!
+//!         bit      |  count   |  q_count
+//!       -----------------------------------
+//!                  |    0     |
+//!         b_0      | count_1  |    1
+//!         ...      |   ...    |    1
+//!       b_{n-1}    | count_n  |    1
+//!
+//! The bits are copied in from cells `BitsChip` already constrained to be boolean, so the
+//! count needs no check of its own. `count_ones` takes bits the caller has, e.g. the xor of
+//! two values' bits for a Hamming distance, and `popcount` unpacks a value first: `2n` rows
+//! for `n` bits.
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::bits::{BitsChip, BitsConfig};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_bits, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+#[derive(Debug, Clone)]
+pub struct PopcountConfig {
+    bits: BitsConfig,
+    bit: Column<Advice>,
+    count: Column<Advice>,
+    q_count: Selector,
+    constants: ConstantsConfig,
+}
+
+pub struct PopcountChip<F: FieldExt> {
+    config: PopcountConfig,
+    bits: BitsChip<F>,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for PopcountChip<F> {
+    type Config = PopcountConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> PopcountChip<F> {
+    pub fn construct(config: PopcountConfig) -> Self {
+        let bits = BitsChip::construct(config.bits.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, bits, constants }
+    }
+
+    /// `BitsChip`, and two more advice columns for the count, with the constants copied into
+    /// the count column.
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> PopcountConfig {
+        let bits = BitsChip::configure(meta, instance);
+        let bit = meta.advice_column();
+        let count = meta.advice_column();
+        let q_count = meta.selector();
+        meta.enable_equality(bit);
+
+        // `count_0 == 0` is copied from a loaded constant
+        let constants = ConstantsChip::configure_with(meta, count);
+
+        meta.create_gate("popcount", |meta| {
+            let q_count = meta.query_selector(q_count);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let count_prev = meta.query_advice(count, Rotation::prev());
+            let count = meta.query_advice(count, Rotation::cur());
+
+            GateBuilder::with_selector(q_count).equal("count = count_prev + bit", count, count_prev + bit)
+        });
+
+        PopcountConfig {
+            bits,
+            bit,
+            count,
+            q_count,
+            constants,
+        }
+    }
+
+    /// The chip values are unpacked with.
+    pub fn bits(&self) -> &BitsChip<F> {
+        &self.bits
+    }
+
+    /// The number of set bits among `bits`, which must already be constrained to be boolean.
+    pub fn count_ones(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bits: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let config = &self.config;
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", F::zero())?;
+        layouter
+            .assign_region(
+                || "count ones",
+                |mut region| {
+                    let mut count = zero.copy_advice(|| "count_0", &mut region, config.count, 0)?;
+                    for (i, bit) in bits.iter().enumerate() {
+                        let offset = i + 1;
+                        config.q_count.enable(&mut region, offset)?;
+                        let bit = bit.copy_advice(|| format!("b_{}", i), &mut region, config.bit, offset)?;
+                        let next = count.value().copied() + bit.value().copied();
+                        count = region.assign_advice(|| format!("count_{}", offset), config.count, offset, || next)?;
+                    }
+                    Ok(count)
+                },
+            )
+            .in_chip("PopcountChip", "count ones")
+    }
+
+    /// The number of set bits of `value`, after checking it fits in `num_bits` bits.
+    pub fn popcount(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let bits = self.bits.unpack(layouter.namespace(|| "unpack"), value, num_bits)?;
+        self.count_ones(layouter.namespace(|| "count"), &bits)
+    }
+}
+
+/// Exposes the number of set bits of a private `num_bits`-bit value.
+#[derive(Default)]
+pub struct PopcountCircuit<F> {
+    pub value: Value<F>,
+    pub num_bits: usize,
+}
+
+impl<F: FieldExt + PrimeFieldBits> Circuit<F> for PopcountCircuit<F> {
+    type Config = (PopcountConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            value: Value::unknown(),
+            num_bits: self.num_bits,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let popcount = PopcountChip::configure(meta, instance);
+        (popcount, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = PopcountChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let value = load_chip.load_private(layouter.namespace(|| "load value"), self.value)?;
+        let count = chip.popcount(layouter.namespace(|| "popcount"), &value, self.num_bits)?;
+        chip.bits().expose_public(layouter.namespace(|| "count"), &count, 0)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> ValidateWitness<F> for PopcountCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let value = known(self.value, "value")?;
+        check_bits(value, self.num_bits, "value")?;
+        let count = value.to_le_bits().iter().take(self.num_bits).filter(|bit| **bit).count();
+        check_instances(instances, &[F::from(count as u64)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::PrimeFieldBits;
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver};
+
+    use super::PopcountCircuit;
+    use crate::{test_over_fields, validate::ValidateWitness};
+
+    const K: u32 = 8;
+
+    fn test_popcount<F: FieldExt + PrimeFieldBits>() {
+        for (value, num_bits) in [(0xdead_beefu64, 32), (0, 8), (1, 1), (u64::MAX, 64), (0x8000_0000_0000_0001, 64)] {
+            let circuit = PopcountCircuit {
+                value: Value::known(F::from(value)),
+                num_bits,
+            };
+            let instances = vec![vec![F::from(value.count_ones() as u64)]];
+            assert_eq!(circuit.validate_witness(&instances), Ok(()));
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+
+            let prover = MockProver::run(K, &circuit, vec![vec![F::from(value.count_ones() as u64 + 1)]]).unwrap();
+            assert!(prover.verify().is_err(), "{:#x} has one more bit set", value);
+        }
+    }
+
+    fn test_popcount_out_of_range<F: FieldExt + PrimeFieldBits>() {
+        // a 33-bit value as 32 bits, whose low bits have a single one
+        let value = (1u64 << 32) | 1;
+        let circuit = PopcountCircuit {
+            value: Value::known(F::from(value)),
+            num_bits: 32,
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![F::one()]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    test_over_fields!(test_popcount, test_popcount_out_of_range);
+}
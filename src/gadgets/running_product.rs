@@ -0,0 +1,320 @@
+//! A running product over a column of terms, the multiplicative twin of the running sum in
+//! `DecomposeChip`:
+//!
+//!     z_0 = 1,   z_{i+1} = z_i * t_i,   z_n = t_0 * t_1 * ... * t_{n-1}
+//!
+//!        term    |    z     |  q_product
+//!       -----------------------------------
+//!        t_0     |    1     |     1
+//!        t_1     |   z_1    |     1
+//!        ...     |   ...    |    ...
+//!      t_{n-1}   | z_{n-1}  |     1
+//!                |   z_n    |
+//!
+//! `ratio` runs the grand product of a permutation argument instead, with a denominator
+//! next to every term, `z_{i+1} * d_i = z_i * t_i`, so that `z_n = prod(t_i) / prod(d_i)`.
+//! That is 1 exactly when the two products agree, e.g. for `t_i = a_i + gamma` and
+//! `d_i = b_i + gamma` when the `a` are a permutation of the `b`. A zero denominator
+//! leaves `z_{i+1}` free, so callers must make sure there are none.
+//!
+//! The final `z_n` can be exposed with `expose_public` or pinned to 1 with `assert_one`.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+#[derive(Debug, Clone)]
+pub struct RunningProductConfig {
+    term: Column<Advice>,
+    den: Column<Advice>,
+    z: Column<Advice>,
+    q_product: Selector,
+    q_ratio: Selector,
+    instance: Column<Instance>,
+    constants: ConstantsConfig,
+}
+
+pub struct RunningProductChip<F: FieldExt> {
+    config: RunningProductConfig,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for RunningProductChip<F> {
+    type Config = RunningProductConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> RunningProductChip<F> {
+    pub fn construct(config: RunningProductConfig) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> RunningProductConfig {
+        let term = meta.advice_column();
+        let den = meta.advice_column();
+        let z = meta.advice_column();
+        let q_product = meta.selector();
+        let q_ratio = meta.selector();
+
+        // `z_0 == 1`, and the one of `assert_one`, are copied from a loaded constant
+        let constants = ConstantsChip::configure_with(meta, z);
+        meta.enable_equality(term);
+        meta.enable_equality(den);
+        meta.enable_equality(z);
+        meta.enable_equality(instance);
+
+        meta.create_gate("running product", |meta| {
+            let q_product = meta.query_selector(q_product);
+            let term = meta.query_advice(term, Rotation::cur());
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+
+            GateBuilder::with_selector(q_product).equal("z_next = z * term", z_next, z_cur * term)
+        });
+
+        meta.create_gate("running ratio", |meta| {
+            let q_ratio = meta.query_selector(q_ratio);
+            let term = meta.query_advice(term, Rotation::cur());
+            let den = meta.query_advice(den, Rotation::cur());
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+
+            GateBuilder::with_selector(q_ratio).equal("z_next * den = z * term", z_next * den, z_cur * term)
+        });
+
+        RunningProductConfig {
+            term,
+            den,
+            z,
+            q_product,
+            q_ratio,
+            instance,
+            constants,
+        }
+    }
+
+    /// The product of `terms`, 1 if there are none.
+    pub fn product(
+        &self,
+        layouter: impl Layouter<F>,
+        terms: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        self.lay_out(layouter, "running product", terms, None)
+    }
+
+    /// `prod(nums) / prod(dens)`, where no denominator may be zero.
+    pub fn ratio(
+        &self,
+        layouter: impl Layouter<F>,
+        nums: &[AssignedCell<F, F>],
+        dens: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        if nums.len() != dens.len() {
+            return Err(Halo2ExamplesError::new("RunningProductChip", Error::Synthesis).in_region("running ratio"));
+        }
+        self.lay_out(layouter, "running ratio", nums, Some(dens))
+    }
+
+    /// Constrains a final product to be 1.
+    pub fn assert_one(&self, mut layouter: impl Layouter<F>, z: &AssignedCell<F, F>) -> Result<(), Halo2ExamplesError> {
+        let one = self.constants.load_constant(layouter.namespace(|| "one"), "one", F::one())?;
+        layouter
+            .assign_region(|| "product is one", |mut region| region.constrain_equal(z.cell(), one.cell()))
+            .in_chip("RunningProductChip", "product is one")
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .constrain_instance(cell.cell(), self.config.instance, row)
+            .map_err(|err| Halo2ExamplesError::new("RunningProductChip", err).at(row, "instance"))
+    }
+
+    fn lay_out(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        terms: &[AssignedCell<F, F>],
+        dens: Option<&[AssignedCell<F, F>]>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let config = &self.config;
+        let one = self.constants.load_constant(layouter.namespace(|| "one"), "one", F::one())?;
+        layouter
+            .assign_region(
+                || name,
+                |mut region| {
+                    let mut z = one.copy_advice(|| "z_0", &mut region, config.z, 0)?;
+
+                    for (i, term) in terms.iter().enumerate() {
+                        let term = term.copy_advice(|| format!("t_{}", i), &mut region, config.term, i)?;
+                        let next = match dens {
+                            None => {
+                                config.q_product.enable(&mut region, i)?;
+                                z.value().copied() * term.value().copied()
+                            }
+                            Some(dens) => {
+                                config.q_ratio.enable(&mut region, i)?;
+                                let den = dens[i].copy_advice(|| format!("d_{}", i), &mut region, config.den, i)?;
+                                let den_inv = den.value().map(|den| den.invert().unwrap_or(F::zero()));
+                                z.value().copied() * term.value().copied() * den_inv
+                            }
+                        };
+                        z = region.assign_advice(|| format!("z_{}", i + 1), config.z, i + 1, || next)?;
+                    }
+                    Ok(z)
+                },
+            )
+            .in_chip("RunningProductChip", name)
+    }
+}
+
+/// Exposes the product of private terms.
+#[derive(Default)]
+pub struct RunningProductCircuit<F> {
+    pub terms: Vec<Value<F>>,
+}
+
+impl<F: FieldExt> Circuit<F> for RunningProductCircuit<F> {
+    type Config = (RunningProductConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            terms: vec![Value::unknown(); self.terms.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let product = RunningProductChip::configure(meta, instance);
+        (product, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = RunningProductChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let terms = load_chip.load_privates(layouter.namespace(|| "load terms"), &self.terms)?;
+        let product = chip.product(layouter.namespace(|| "product"), &terms)?;
+        chip.expose_public(layouter.namespace(|| "product"), &product, 0)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ValidateWitness<F> for RunningProductCircuit<F> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let product = self
+            .terms
+            .iter()
+            .try_fold(F::one(), |product, term| -> Result<F, InvalidWitness> { Ok(product * known(*term, "term")?) })?;
+        check_instances(instances, &[product])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{RunningProductChip, RunningProductCircuit, RunningProductConfig};
+    use crate::{
+        chip::{LoadPrivateChip, LoadPrivateConfig},
+        test_over_fields,
+    };
+
+    const K: u32 = 6;
+
+    fn test_running_product<F: FieldExt>() {
+        let terms = [3, 5, 7, 11].map(F::from);
+        let circuit = RunningProductCircuit {
+            terms: terms.iter().copied().map(Value::known).collect(),
+        };
+
+        let prover = MockProver::run(K, &circuit, vec![vec![F::from(1155)]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(K, &circuit, vec![vec![F::from(1154)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    test_over_fields!(test_running_product);
+
+    /// Checks `prod(a_i + gamma) / prod(b_i + gamma) == 1`. With a `gamma` the prover knows in
+    /// advance this is no proof of a permutation, only a test of the gate.
+    struct MultisetCircuit {
+        a: Vec<u64>,
+        b: Vec<u64>,
+    }
+
+    impl Circuit<Fp> for MultisetCircuit {
+        type Config = (RunningProductConfig, LoadPrivateConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: self.a.clone(),
+                b: self.b.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            RunningProductCircuit::<Fp>::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (config, load_config) = config;
+            let chip = RunningProductChip::construct(config);
+            let load_chip = LoadPrivateChip::construct(load_config);
+
+            let gamma = Fp::from(1000);
+            let shifted = |values: &[u64]| values.iter().map(|value| Value::known(Fp::from(*value) + gamma)).collect::<Vec<_>>();
+            let nums = load_chip.load_privates(layouter.namespace(|| "a"), &shifted(&self.a))?;
+            let dens = load_chip.load_privates(layouter.namespace(|| "b"), &shifted(&self.b))?;
+            let ratio = chip.ratio(layouter.namespace(|| "ratio"), &nums, &dens)?;
+            chip.assert_one(layouter.namespace(|| "is one"), &ratio)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_running_ratio() {
+        let circuit = MultisetCircuit {
+            a: vec![4, 8, 15, 16, 23, 42],
+            b: vec![42, 16, 4, 23, 15, 8],
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![]]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = MultisetCircuit {
+            a: vec![4, 8, 15, 16, 23, 42],
+            b: vec![42, 16, 4, 23, 15, 15],
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
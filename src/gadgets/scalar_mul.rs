@@ -0,0 +1,373 @@
+//! Variable-base scalar multiplication on Pallas, by double-and-add over complete addition.
+//!
+//! Points are `AssignedPoint`s, with `(0, 0)` standing for the identity: no point of Pallas
+//! has `x = 0`, as 5 is not a square. One row adds `P + Q = R` for any two points, the
+//! identity and `P = ±Q` included, with the constraints of the complete addition gate of
+//! `halo2_gadgets` (see the halo2 book), for witnessed `lambda` and inverses `alpha = 1/(x_q - x_p)`,
+//! `beta = 1/x_p`, `gamma = 1/x_q` and `delta = 1/(y_q + y_p)`, each 0 where it does not exist:
+//!
+//!     (x_q - x_p) * ((x_q - x_p) * lambda - (y_q - y_p)) = 0
+//!     (1 - (x_q - x_p) * alpha) * (2 * y_p * lambda - 3 * x_p^2) = 0
+//!     x_p * x_q * (x_q - x_p) * (lambda^2 - x_p - x_q - x_r) = 0
+//!     x_p * x_q * (x_q - x_p) * (lambda * (x_p - x_r) - y_p - y_r) = 0
+//!     x_p * x_q * (y_q + y_p) * (lambda^2 - x_p - x_q - x_r) = 0
+//!     x_p * x_q * (y_q + y_p) * (lambda * (x_p - x_r) - y_p - y_r) = 0
+//!     (1 - x_p * beta) * (x_r - x_q) = 0,   (1 - x_p * beta) * (y_r - y_q) = 0
+//!     (1 - x_q * gamma) * (x_r - x_p) = 0,  (1 - x_q * gamma) * (y_r - y_p) = 0
+//!     (1 - (x_q - x_p) * alpha - (y_q + y_p) * delta) * x_r = 0, and the same for y_r
+//!
+//! `mul` takes the scalar as little-endian bit cells and walks them from the top, two rows
+//! per bit: `D = A + A`, then `T = D + P` and `A' = b ? T : D` on the second row.
+//!
+//!       x_p | y_p | x_q | y_q | x_r | y_r | lambda, alpha, ... |  b  | x_o | y_o | selectors
+//!      ------------------------------------------------------------------------------------------
+//!        A  |     |  A  |     |  D  |     |                    |     |     |     | q_add
+//!        D  |     |  P  |     |  T  |     |                    | b_i |  A' |     | q_add, q_select
+
+use ff::Field;
+use group::prime::PrimeCurveAffine;
+use halo2_proofs::{
+    circuit::*,
+    pasta::{pallas, Fp},
+    plonk::*,
+    poly::Rotation,
+};
+
+use super::ecc::{coordinates, AssignedPoint};
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+};
+
+/// The coordinates of `point`, `(0, 0)` for the identity.
+pub fn coordinates_or_zero(point: pallas::Affine) -> (Fp, Fp) {
+    if bool::from(point.is_identity()) {
+        (Fp::zero(), Fp::zero())
+    } else {
+        coordinates(point)
+    }
+}
+
+/// The witnesses of one complete addition.
+struct AddWitness {
+    lambda: Fp,
+    alpha: Fp,
+    beta: Fp,
+    gamma: Fp,
+    delta: Fp,
+    x: Fp,
+    y: Fp,
+}
+
+fn inv0(value: Fp) -> Fp {
+    Option::from(value.invert()).unwrap_or(Fp::zero())
+}
+
+fn add_witness((x_p, y_p): (Fp, Fp), (x_q, y_q): (Fp, Fp)) -> AddWitness {
+    let lambda = if x_q != x_p {
+        (y_q - y_p) * inv0(x_q - x_p)
+    } else {
+        x_p.square() * Fp::from(3) * inv0(y_p.double())
+    };
+    let (x, y) = if (x_p, y_p) == (Fp::zero(), Fp::zero()) {
+        (x_q, y_q)
+    } else if (x_q, y_q) == (Fp::zero(), Fp::zero()) {
+        (x_p, y_p)
+    } else if x_p == x_q && y_p == -y_q {
+        (Fp::zero(), Fp::zero())
+    } else {
+        let x = lambda.square() - x_p - x_q;
+        (x, lambda * (x_p - x) - y_p)
+    };
+    AddWitness {
+        lambda,
+        alpha: inv0(x_q - x_p),
+        beta: inv0(x_p),
+        gamma: inv0(x_q),
+        delta: if x_q == x_p { inv0(y_q + y_p) } else { Fp::zero() },
+        x,
+        y,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScalarMulConfig {
+    x_p: Column<Advice>,
+    y_p: Column<Advice>,
+    x_q: Column<Advice>,
+    y_q: Column<Advice>,
+    x_r: Column<Advice>,
+    y_r: Column<Advice>,
+    lambda: Column<Advice>,
+    alpha: Column<Advice>,
+    beta: Column<Advice>,
+    gamma: Column<Advice>,
+    delta: Column<Advice>,
+    bit: Column<Advice>,
+    x_o: Column<Advice>,
+    y_o: Column<Advice>,
+    q_add: Selector,
+    q_select: Selector,
+    constants: ConstantsConfig,
+}
+
+pub struct ScalarMulChip {
+    config: ScalarMulConfig,
+    constants: ConstantsChip<Fp>,
+}
+
+impl Chip<Fp> for ScalarMulChip {
+    type Config = ScalarMulConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl ScalarMulChip {
+    pub fn construct(config: ScalarMulConfig) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> ScalarMulConfig {
+        let [x_p, y_p, x_q, y_q, x_r, y_r, lambda, alpha, beta, gamma, delta, bit, x_o, y_o] =
+            [(); 14].map(|_| meta.advice_column());
+        let q_add = meta.selector();
+        let q_select = meta.selector();
+
+        // the identity `mul` starts from, and `constant_point`, are loaded constants
+        let constants = ConstantsChip::configure_with(meta, x_o);
+        for column in [x_p, y_p, x_q, y_q, x_r, y_r, bit, x_o, y_o] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("complete addition", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let [x_p, y_p, x_q, y_q, x_r, y_r, lambda, alpha, beta, gamma, delta] =
+                [x_p, y_p, x_q, y_q, x_r, y_r, lambda, alpha, beta, gamma, delta]
+                    .map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(Fp::one());
+
+            let dx = x_q.clone() - x_p.clone();
+            let sy = y_q.clone() + y_p.clone();
+            let x_r_is = lambda.clone().square() - x_p.clone() - x_q.clone() - x_r.clone();
+            let y_r_is = lambda.clone() * (x_p.clone() - x_r.clone()) - y_p.clone() - y_r.clone();
+            let not_p = one.clone() - x_p.clone() * beta;
+            let not_q = one.clone() - x_q.clone() * gamma;
+            let neither = one.clone() - dx.clone() * alpha.clone() - sy.clone() * delta;
+
+            GateBuilder::with_selector(q_add)
+                .constrain("lambda, x_q != x_p", dx.clone() * (dx.clone() * lambda.clone() - (y_q.clone() - y_p.clone())))
+                .constrain(
+                    "lambda, x_q == x_p",
+                    (one - dx.clone() * alpha) * (y_p.clone() * lambda * Fp::from(2) - x_p.clone().square() * Fp::from(3)),
+                )
+                .constrain("x_r, x_q != x_p", x_p.clone() * x_q.clone() * dx.clone() * x_r_is.clone())
+                .constrain("y_r, x_q != x_p", x_p.clone() * x_q.clone() * dx * y_r_is.clone())
+                .constrain("x_r, y_q != -y_p", x_p.clone() * x_q.clone() * sy.clone() * x_r_is)
+                .constrain("y_r, y_q != -y_p", x_p.clone() * x_q.clone() * sy * y_r_is)
+                .constrain("x_r, P = O", not_p.clone() * (x_r.clone() - x_q.clone()))
+                .constrain("y_r, P = O", not_p * (y_r.clone() - y_q))
+                .constrain("x_r, Q = O", not_q.clone() * (x_r.clone() - x_p))
+                .constrain("y_r, Q = O", not_q * (y_r.clone() - y_p))
+                .constrain("x_r, Q = -P", neither.clone() * x_r)
+                .constrain("y_r, Q = -P", neither * y_r)
+        });
+
+        meta.create_gate("select", |meta| {
+            let q_select = meta.query_selector(q_select);
+            let [x_p, y_p, x_r, y_r, bit, x_o, y_o] =
+                [x_p, y_p, x_r, y_r, bit, x_o, y_o].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_select)
+                .boolean("bit is boolean", bit.clone())
+                .select("x_o = bit ? x_r : x_p", x_o, bit.clone(), x_r, x_p)
+                .select("y_o = bit ? y_r : y_p", y_o, bit, y_r, y_p)
+        });
+
+        ScalarMulConfig {
+            x_p,
+            y_p,
+            x_q,
+            y_q,
+            x_r,
+            y_r,
+            lambda,
+            alpha,
+            beta,
+            gamma,
+            delta,
+            bit,
+            x_o,
+            y_o,
+            q_add,
+            q_select,
+            constants,
+        }
+    }
+
+    /// A fixed point, e.g. a generator, as constant cells.
+    pub fn constant_point(&self, mut layouter: impl Layouter<Fp>, point: pallas::Affine) -> Result<AssignedPoint, Halo2ExamplesError> {
+        let (x, y) = coordinates_or_zero(point);
+        Ok(AssignedPoint {
+            x: self.constants.load_constant(layouter.namespace(|| "x"), "x", x)?,
+            y: self.constants.load_constant(layouter.namespace(|| "y"), "y", y)?,
+        })
+    }
+
+    /// `a + b`.
+    pub fn add(&self, mut layouter: impl Layouter<Fp>, a: &AssignedPoint, b: &AssignedPoint) -> Result<AssignedPoint, Halo2ExamplesError> {
+        layouter
+            .assign_region(|| "add", |mut region| self.assign_add(&mut region, 0, a, b))
+            .in_chip("ScalarMulChip", "add")
+    }
+
+    /// `[k] point` for `k = bits[0] + 2 * bits[1] + ...`, where each bit is constrained to be
+    /// boolean.
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        point: &AssignedPoint,
+        bits: &[AssignedCell<Fp, Fp>],
+    ) -> Result<AssignedPoint, Halo2ExamplesError> {
+        let config = &self.config;
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", Fp::zero())?;
+        layouter
+            .assign_region(
+                || "scalar mul",
+                |mut region| {
+                    let mut acc = AssignedPoint {
+                        x: zero.copy_advice(|| "x_0", &mut region, config.x_o, 0)?,
+                        y: zero.copy_advice(|| "y_0", &mut region, config.y_o, 0)?,
+                    };
+                    for (i, bit) in bits.iter().rev().enumerate() {
+                        let offset = 2 * i + 1;
+                        let double = self.assign_add(&mut region, offset, &acc, &acc)?;
+                        let sum = self.assign_add(&mut region, offset + 1, &double, point)?;
+
+                        config.q_select.enable(&mut region, offset + 1)?;
+                        let bit = bit.copy_advice(|| "bit", &mut region, config.bit, offset + 1)?;
+                        let select = |d: Value<&Fp>, t: Value<&Fp>| {
+                            bit.value().zip(d.zip(t)).map(|(bit, (d, t))| if *bit == Fp::one() { *t } else { *d })
+                        };
+                        acc = AssignedPoint {
+                            x: region.assign_advice(|| "x_o", config.x_o, offset + 1, || select(double.x.value(), sum.x.value()))?,
+                            y: region.assign_advice(|| "y_o", config.y_o, offset + 1, || select(double.y.value(), sum.y.value()))?,
+                        };
+                    }
+                    Ok(acc)
+                },
+            )
+            .in_chip("ScalarMulChip", "scalar mul")
+    }
+
+    // Lays out `p + q` on row `offset`, with `p` and `q` copied in.
+    fn assign_add(&self, region: &mut Region<'_, Fp>, offset: usize, p: &AssignedPoint, q: &AssignedPoint) -> Result<AssignedPoint, Error> {
+        let config = &self.config;
+        config.q_add.enable(region, offset)?;
+        let x_p = p.x.copy_advice(|| "x_p", region, config.x_p, offset)?;
+        let y_p = p.y.copy_advice(|| "y_p", region, config.y_p, offset)?;
+        let x_q = q.x.copy_advice(|| "x_q", region, config.x_q, offset)?;
+        let y_q = q.y.copy_advice(|| "y_q", region, config.y_q, offset)?;
+
+        let witness = x_p
+            .value()
+            .zip(y_p.value())
+            .zip(x_q.value().zip(y_q.value()))
+            .map(|((x_p, y_p), (x_q, y_q))| add_witness((*x_p, *y_p), (*x_q, *y_q)));
+        region.assign_advice(|| "lambda", config.lambda, offset, || witness.as_ref().map(|w| w.lambda))?;
+        region.assign_advice(|| "alpha", config.alpha, offset, || witness.as_ref().map(|w| w.alpha))?;
+        region.assign_advice(|| "beta", config.beta, offset, || witness.as_ref().map(|w| w.beta))?;
+        region.assign_advice(|| "gamma", config.gamma, offset, || witness.as_ref().map(|w| w.gamma))?;
+        region.assign_advice(|| "delta", config.delta, offset, || witness.as_ref().map(|w| w.delta))?;
+        Ok(AssignedPoint {
+            x: region.assign_advice(|| "x_r", config.x_r, offset, || witness.as_ref().map(|w| w.x))?,
+            y: region.assign_advice(|| "y_r", config.y_r, offset, || witness.as_ref().map(|w| w.y))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use group::Curve;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::{pallas, Fp},
+        plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+    };
+
+    use super::{coordinates_or_zero, ScalarMulChip, ScalarMulConfig};
+    use crate::{
+        chip::{LoadPrivateChip, LoadPrivateConfig},
+        gadgets::ecc::multiple_of_generator,
+    };
+
+    /// `[k] point`, with the result exposed as `(x, y)`.
+    struct MulCircuit {
+        point: pallas::Affine,
+        k: u64,
+    }
+
+    impl Circuit<Fp> for MulCircuit {
+        type Config = (ScalarMulConfig, LoadPrivateConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { point: self.point, k: self.k }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (ScalarMulChip::configure(meta), LoadPrivateChip::configure(meta, 1), instance)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (config, load_config, instance) = config;
+            let chip = ScalarMulChip::construct(config);
+            let load_chip = LoadPrivateChip::construct(load_config);
+
+            let point = chip.constant_point(layouter.namespace(|| "point"), self.point)?;
+            let bits: Vec<_> = (0..8).map(|i| Value::known(Fp::from((self.k >> i) & 1))).collect();
+            let bits = load_chip.load_privates(layouter.namespace(|| "bits"), &bits)?;
+            let product = chip.mul(layouter.namespace(|| "mul"), &point, &bits)?;
+            // `(0, 0)` reads back as the identity for k = 0
+            product.value().assert_if_known(|product| *product == self.point * pallas::Scalar::from(self.k));
+            let doubled = chip.add(layouter.namespace(|| "double"), &product, &product)?;
+            layouter.constrain_instance(product.x.cell(), instance, 0)?;
+            layouter.constrain_instance(product.y.cell(), instance, 1)?;
+            layouter.constrain_instance(doubled.x.cell(), instance, 2)?;
+            layouter.constrain_instance(doubled.y.cell(), instance, 3)?;
+            Ok(())
+        }
+    }
+
+    fn expected(point: pallas::Affine, k: u64) -> Vec<Fp> {
+        let product = (point * pallas::Scalar::from(k)).to_affine();
+        let doubled = (product * pallas::Scalar::from(2)).to_affine();
+        let (x, y) = coordinates_or_zero(product);
+        let (x2, y2) = coordinates_or_zero(doubled);
+        vec![x, y, x2, y2]
+    }
+
+    #[test]
+    fn test_scalar_mul() {
+        let point = multiple_of_generator(3);
+        // 0 stays at the identity throughout, 1 adds to it, 255 doubles and adds every bit
+        for k in [0, 1, 2, 5, 200, 255] {
+            let circuit = MulCircuit { point, k };
+            MockProver::run(7, &circuit, vec![expected(point, k)]).unwrap().assert_satisfied();
+        }
+
+        let circuit = MulCircuit { point, k: 5 };
+        assert!(MockProver::run(7, &circuit, vec![expected(point, 6)]).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Selects `values[index]` for an index only known at proving time.
+//!
+//! The prover witnesses a one-hot vector `e`, booleans of which exactly one is set, and
+//! three running sums over it, each starting from 0 on the row above the values:
+//!
+//!         e      |  value   |  count   |  index   |   out    | position | q_select
+//!       ---------------------------------------------------------------------------
+//!                |          |    0     |    0     |    0     |          |
+//!        e_0     |   v_0    | count_1  |  i_1     |  o_1     |    0     |    1
+//!        ...     |   ...    |   ...    |  ...     |  ...     |   ...    |    1
+//!      e_{n-1}   | v_{n-1}  | count_n  |  i_n     |  o_n     |   n-1    |    1
+//!
+//!     count_n = sum(e) = 1,   i_n = sum(e_i * i) = index,   o_n = sum(e_i * v_i) = values[index]
+//!
+//! An index outside `0..n` leaves no valid `e`, so the proof fails.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+};
+
+#[derive(Debug, Clone)]
+pub struct SelectConfig {
+    e: Column<Advice>,
+    value: Column<Advice>,
+    count: Column<Advice>,
+    index: Column<Advice>,
+    out: Column<Advice>,
+    position: Column<Fixed>,
+    q_select: Selector,
+    constants: ConstantsConfig,
+}
+
+pub struct SelectChip<F: FieldExt> {
+    config: SelectConfig,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for SelectChip<F> {
+    type Config = SelectConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> SelectChip<F> {
+    pub fn construct(config: SelectConfig) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SelectConfig {
+        let [e, value, count, index, out] = [(); 5].map(|_| meta.advice_column());
+        let position = meta.fixed_column();
+        let q_select = meta.selector();
+
+        // the zeros the sums start from and the final count of 1 are loaded constants
+        let constants = ConstantsChip::configure_with(meta, count);
+        for column in [value, count, index, out] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("select", |meta| {
+            let q_select = meta.query_selector(q_select);
+            let e = meta.query_advice(e, Rotation::cur());
+            let value = meta.query_advice(value, Rotation::cur());
+            let position = meta.query_fixed(position, Rotation::cur());
+            let [count, index, out] = [count, index, out]
+                .map(|column| meta.query_advice(column, Rotation::cur()) - meta.query_advice(column, Rotation::prev()));
+
+            GateBuilder::with_selector(q_select)
+                .boolean("e is boolean", e.clone())
+                .equal("count = count_prev + e", count, e.clone())
+                .equal("index = index_prev + e * position", index, e.clone() * position)
+                .equal("out = out_prev + e * value", out, e * value)
+        });
+
+        SelectConfig {
+            e,
+            value,
+            count,
+            index,
+            out,
+            position,
+            q_select,
+            constants,
+        }
+    }
+
+    /// `values[index]`.
+    pub fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[AssignedCell<F, F>],
+        index: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let config = &self.config;
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", F::zero())?;
+        let one = self.constants.load_constant(layouter.namespace(|| "one"), "one", F::one())?;
+        layouter
+            .assign_region(
+                || "select",
+                |mut region| {
+                    let mut count = zero.copy_advice(|| "count_0", &mut region, config.count, 0)?;
+                    let mut acc = zero.copy_advice(|| "index_0", &mut region, config.index, 0)?;
+                    let mut out = zero.copy_advice(|| "out_0", &mut region, config.out, 0)?;
+
+                    for (i, value) in values.iter().enumerate() {
+                        let offset = i + 1;
+                        let position = F::from(i as u64);
+                        config.q_select.enable(&mut region, offset)?;
+                        region.assign_fixed(|| format!("position_{}", i), config.position, offset, || Value::known(position))?;
+
+                        let e = index.value().map(|index| F::from((*index == position) as u64));
+                        region.assign_advice(|| format!("e_{}", i), config.e, offset, || e)?;
+                        let value = value.copy_advice(|| format!("v_{}", i), &mut region, config.value, offset)?;
+
+                        let next = count.value().copied() + e;
+                        count = region.assign_advice(|| format!("count_{}", offset), config.count, offset, || next)?;
+                        let next = acc.value().copied() + e * Value::known(position);
+                        acc = region.assign_advice(|| format!("index_{}", offset), config.index, offset, || next)?;
+                        let next = out.value().copied() + e * value.value().copied();
+                        out = region.assign_advice(|| format!("out_{}", offset), config.out, offset, || next)?;
+                    }
+
+                    region.constrain_equal(count.cell(), one.cell())?;
+                    region.constrain_equal(acc.cell(), index.cell())?;
+                    Ok(out)
+                },
+            )
+            .in_chip("SelectChip", "select")
+    }
+}
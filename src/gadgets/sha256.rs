@@ -0,0 +1,236 @@
+//! SHA-256 (FIPS 180-4) on the words of `U32Chip`.
+//!
+//! The message schedule and the 64 rounds are written out of the word chip's operations,
+//! with the two bitwise choices rewritten to need nothing but `and` and `xor`:
+//!
+//!     Ch(e, f, g)  = (e & f) ^ (!e & g)           = g ^ (e & (f ^ g))
+//!     Maj(a, b, c) = (a & b) ^ (a & c) ^ (b & c)  = (a & (b ^ c)) ^ (b & c)
+//!
+//! A compression is about 9000 rows: a round is 3 rotations and 2 xors for each `Σ`, 5 more
+//! bitwise operations for `Ch` and `Maj`, and 7 additions, and each of the 48 scheduled words
+//! takes two `σ`s and 3 additions. The round constants and the initial hash value are fixed
+//! in the circuit.
+//!
+//! `digest` hashes a message of whole words whose length is fixed when the circuit is built,
+//! so its padding is made of constant words too. Words are big-endian, as SHA-256 reads
+//! them: the digest bytes are the 8 output words' bytes, most significant first.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use super::word::{U32Chip, U32Config, U32};
+use crate::{
+    error::Halo2ExamplesError,
+    reference::sha256::{IV, K},
+};
+
+pub struct Sha256Chip<F: FieldExt> {
+    words: U32Chip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for Sha256Chip<F> {
+    type Config = U32Config;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        self.words.config()
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> Sha256Chip<F> {
+    pub fn construct(config: U32Config) -> Self {
+        Self {
+            words: U32Chip::construct(config),
+        }
+    }
+
+    /// SHA-256 needs nothing but the word chip.
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> U32Config {
+        U32Chip::configure(meta, instance)
+    }
+
+    /// The chip the words are witnessed with.
+    pub fn words(&self) -> &U32Chip<F> {
+        &self.words
+    }
+
+    /// The SHA-256 digest of `message`, `4 * message.len()` bytes long.
+    pub fn digest(&self, mut layouter: impl Layouter<F>, message: &[U32<F>]) -> Result<[U32<F>; 8], Halo2ExamplesError> {
+        let bits = 32 * message.len() as u64;
+        let mut padding = vec![0x8000_0000];
+        while (message.len() + padding.len()) % 16 != 14 {
+            padding.push(0);
+        }
+        padding.extend([(bits >> 32) as u32, bits as u32]);
+
+        let mut words = message.to_vec();
+        for (i, word) in padding.into_iter().enumerate() {
+            words.push(self.words.assign_constant(layouter.namespace(|| format!("padding {}", i)), word)?);
+        }
+
+        let iv = self.constants(layouter.namespace(|| "initial hash value"), &IV)?;
+        let mut state: [U32<F>; 8] = iv.try_into().expect("8 words");
+        for (i, block) in words.chunks(16).enumerate() {
+            let block = block.to_vec().try_into().expect("16 words");
+            state = self.compress(layouter.namespace(|| format!("block {}", i)), &state, &block)?;
+        }
+        Ok(state)
+    }
+
+    /// The compression function: `state` after `block`.
+    pub fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[U32<F>; 8],
+        block: &[U32<F>; 16],
+    ) -> Result<[U32<F>; 8], Halo2ExamplesError> {
+        let chip = &self.words;
+
+        let mut w = block.to_vec();
+        for t in 16..64 {
+            let mut layouter = layouter.namespace(|| format!("w_{}", t));
+            let sigma0 = self.sigma(layouter.namespace(|| "σ0"), &w[t - 15], [7, 18], 3, false)?;
+            let sigma1 = self.sigma(layouter.namespace(|| "σ1"), &w[t - 2], [17, 19], 10, false)?;
+            let sum = chip.add(layouter.namespace(|| "σ1 + w_t-7"), &sigma1, &w[t - 7])?;
+            let sum = chip.add(layouter.namespace(|| "+ σ0"), &sum, &sigma0)?;
+            w.push(chip.add(layouter.namespace(|| "+ w_t-16"), &sum, &w[t - 16])?);
+        }
+        let k = self.constants(layouter.namespace(|| "round constants"), &K)?;
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state.clone();
+        for t in 0..64 {
+            let mut layouter = layouter.namespace(|| format!("round {}", t));
+            let big_sigma1 = self.sigma(layouter.namespace(|| "Σ1"), &e, [6, 11], 25, true)?;
+            let f_xor_g = chip.xor(layouter.namespace(|| "f ^ g"), &f, &g)?;
+            let ch = chip.and(layouter.namespace(|| "e & (f ^ g)"), &e, &f_xor_g)?;
+            let ch = chip.xor(layouter.namespace(|| "Ch"), &g, &ch)?;
+
+            let t1 = chip.add(layouter.namespace(|| "h + Σ1"), &h, &big_sigma1)?;
+            let t1 = chip.add(layouter.namespace(|| "+ Ch"), &t1, &ch)?;
+            let t1 = chip.add(layouter.namespace(|| "+ k_t"), &t1, &k[t])?;
+            let t1 = chip.add(layouter.namespace(|| "+ w_t"), &t1, &w[t])?;
+
+            let big_sigma0 = self.sigma(layouter.namespace(|| "Σ0"), &a, [2, 13], 22, true)?;
+            let b_xor_c = chip.xor(layouter.namespace(|| "b ^ c"), &b, &c)?;
+            let maj = chip.and(layouter.namespace(|| "a & (b ^ c)"), &a, &b_xor_c)?;
+            let b_and_c = chip.and(layouter.namespace(|| "b & c"), &b, &c)?;
+            let maj = chip.xor(layouter.namespace(|| "Maj"), &maj, &b_and_c)?;
+            let t2 = chip.add(layouter.namespace(|| "Σ0 + Maj"), &big_sigma0, &maj)?;
+
+            (h, g, f) = (g, f, e);
+            e = chip.add(layouter.namespace(|| "d + T1"), &d, &t1)?;
+            (d, c, b) = (c, b, a);
+            a = chip.add(layouter.namespace(|| "T1 + T2"), &t1, &t2)?;
+        }
+
+        let state = [a, b, c, d, e, f, g, h]
+            .iter()
+            .zip(state)
+            .enumerate()
+            .map(|(i, (working, word))| chip.add(layouter.namespace(|| format!("h_{}", i)), word, working))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(state.try_into().expect("8 words"))
+    }
+
+    // `(x >>> r_0) ^ (x >>> r_1) ^ (x >>> last)`, or `^ (x >> last)` if not `rotate_last`
+    fn sigma(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &U32<F>,
+        rotations: [usize; 2],
+        last: usize,
+        rotate_last: bool,
+    ) -> Result<U32<F>, Halo2ExamplesError> {
+        let chip = &self.words;
+        let r_0 = chip.rotate_right(layouter.namespace(|| "x >>> r_0"), x, rotations[0])?;
+        let r_1 = chip.rotate_right(layouter.namespace(|| "x >>> r_1"), x, rotations[1])?;
+        let last = if rotate_last {
+            chip.rotate_right(layouter.namespace(|| "x >>> r_2"), x, last)?
+        } else {
+            chip.shift_right(layouter.namespace(|| "x >> s"), x, last)?
+        };
+        let xor = chip.xor(layouter.namespace(|| "r_0 ^ r_1"), &r_0, &r_1)?;
+        chip.xor(layouter.namespace(|| "^ last"), &xor, &last)
+    }
+
+    fn constants(&self, mut layouter: impl Layouter<F>, values: &[u32]) -> Result<Vec<U32<F>>, Halo2ExamplesError> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| self.words.assign_constant(layouter.namespace(|| format!("{}", i)), *value))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        arithmetic::Field,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::Sha256Chip;
+    use crate::{gadgets::word::U32Config, reference::ref_sha256};
+
+    /// Hashes private words and exposes the digest.
+    struct Sha256Circuit {
+        message: Vec<u32>,
+    }
+
+    impl Circuit<Fp> for Sha256Circuit {
+        type Config = U32Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: self.message.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            Sha256Chip::configure(meta, instance)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = Sha256Chip::construct(config);
+            chip.words().load_tables(&mut layouter)?;
+
+            let message = self
+                .message
+                .iter()
+                .map(|word| chip.words().assign(layouter.namespace(|| "message"), Value::known(*word)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let digest = chip.digest(layouter.namespace(|| "sha256"), &message)?;
+            for (row, word) in digest.iter().enumerate() {
+                chip.words().expose_public(layouter.namespace(|| "digest"), word, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sha256() {
+        // FIPS 180-2, appendix B.2: 14 words, padded to two blocks
+        let message = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        let words = message.chunks(4).map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap())).collect();
+        let digest: Vec<Fp> = ref_sha256(message)
+            .chunks(4)
+            .map(|bytes| Fp::from(u32::from_be_bytes(bytes.try_into().unwrap()) as u64))
+            .collect();
+        assert_eq!(digest[0], Fp::from(0x248d_6a61));
+
+        let circuit = Sha256Circuit { message: words };
+        MockProver::run(17, &circuit, vec![digest.clone()]).unwrap().assert_satisfied();
+
+        let mut wrong = digest;
+        wrong[7] += Fp::one();
+        assert!(MockProver::run(17, &circuit, vec![wrong]).unwrap().verify().is_err());
+    }
+}
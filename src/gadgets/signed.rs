@@ -0,0 +1,279 @@
+//! The absolute value and sign of a signed integer stored as N-bit two's complement.
+//!
+//! A negative `x` is stored as the field element `2^N + x`, exactly its N-bit pattern, so a
+//! value is only well-formed below `2^N`, and its top bit is the sign. `abs_and_sign` unpacks
+//! the value with `BitsChip`, which checks both, and takes the top bit as the sign:
+//!
+//!     abs = value                  if sign == 0
+//!     abs = 2^N - value            if sign == 1
+//!
+//! i.e. `abs = value + sign * (2^N - 2 * value)`, in a single row. Since N is chosen at
+//! synthesis time, `2^N` sits in a fixed column next to it.
+//!
+//!        value   |  sign  |  abs  |  two_pow_n  |  q_abs
+//!       -------------------------------------------------
+//!          v     |   s    |  |v|  |     2^N     |    1
+//!
+//! `-2^{N-1}` has no N-bit positive counterpart, but its absolute value `2^{N-1}` is still a
+//! field element, so it is returned as such.
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::bits::{BitsChip, BitsConfig};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+#[derive(Debug, Clone)]
+pub struct SignedConfig {
+    value: Column<Advice>,
+    sign: Column<Advice>,
+    abs: Column<Advice>,
+    two_pow_n: Column<Fixed>,
+    q_abs: Selector,
+    bits: BitsConfig,
+}
+
+pub struct SignedChip<F: FieldExt> {
+    config: SignedConfig,
+    bits: BitsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for SignedChip<F> {
+    type Config = SignedConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> SignedChip<F> {
+    pub fn construct(config: SignedConfig) -> Self {
+        let bits = BitsChip::construct(config.bits.clone());
+        Self { config, bits }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> SignedConfig {
+        let bits = BitsChip::configure(meta, instance);
+        let value = meta.advice_column();
+        let sign = meta.advice_column();
+        let abs = meta.advice_column();
+        let two_pow_n = meta.fixed_column();
+        let q_abs = meta.selector();
+
+        meta.enable_equality(value);
+        meta.enable_equality(sign);
+        meta.enable_equality(abs);
+
+        meta.create_gate("abs and sign", |meta| {
+            let q_abs = meta.query_selector(q_abs);
+            let value = meta.query_advice(value, Rotation::cur());
+            let sign = meta.query_advice(sign, Rotation::cur());
+            let abs = meta.query_advice(abs, Rotation::cur());
+            let two_pow_n = meta.query_fixed(two_pow_n, Rotation::cur());
+
+            GateBuilder::with_selector(q_abs).equal(
+                "abs = value + sign * (2^N - 2 * value)",
+                abs,
+                value.clone() + sign * (two_pow_n - value * F::from(2)),
+            )
+        });
+
+        SignedConfig {
+            value,
+            sign,
+            abs,
+            two_pow_n,
+            q_abs,
+            bits,
+        }
+    }
+
+    /// `|value|` and the sign bit, 1 for negative, of `value` read as N-bit two's complement,
+    /// after checking it is below `2^N`.
+    pub fn abs_and_sign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Halo2ExamplesError> {
+        if num_bits == 0 {
+            return Err(Halo2ExamplesError::new("SignedChip", Error::Synthesis).in_region("abs and sign"));
+        }
+        let bits = self.bits.unpack(layouter.namespace(|| "unpack"), value, num_bits)?;
+        let sign = &bits[num_bits - 1];
+
+        layouter
+            .assign_region(
+                || "abs and sign",
+                |mut region| {
+                    self.config.q_abs.enable(&mut region, 0)?;
+                    let two_pow_n = F::from(2).pow(&[num_bits as u64, 0, 0, 0]);
+                    region.assign_fixed(|| "2^N", self.config.two_pow_n, 0, || Value::known(two_pow_n))?;
+
+                    let value = value.copy_advice(|| "value", &mut region, self.config.value, 0)?;
+                    let sign = sign.copy_advice(|| "sign", &mut region, self.config.sign, 0)?;
+                    let abs = value.value().zip(sign.value()).map(|(value, sign)| {
+                        if *sign == F::one() {
+                            two_pow_n - value
+                        } else {
+                            *value
+                        }
+                    });
+                    let abs = region.assign_advice(|| "abs", self.config.abs, 0, || abs)?;
+                    Ok((abs, sign))
+                },
+            )
+            .in_chip("SignedChip", "abs and sign")
+    }
+
+    pub fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Halo2ExamplesError> {
+        self.bits.expose_public(layouter, cell, row)
+    }
+}
+
+/// Exposes the absolute value and sign of a private `num_bits`-bit signed integer.
+#[derive(Default)]
+pub struct AbsCircuit {
+    pub value: Value<i64>,
+    pub num_bits: usize,
+}
+
+impl AbsCircuit {
+    /// The N-bit two's complement pattern of `value`.
+    pub fn encode(value: i64, num_bits: usize) -> u64 {
+        if num_bits >= 64 {
+            value as u64
+        } else {
+            value as u64 & ((1u64 << num_bits) - 1)
+        }
+    }
+
+    /// `|value|` and the sign bit.
+    pub fn outputs(value: i64) -> [u64; 2] {
+        [value.unsigned_abs(), (value < 0) as u64]
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> Circuit<F> for AbsCircuit {
+    type Config = (SignedConfig, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            value: Value::unknown(),
+            num_bits: self.num_bits,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let signed = SignedChip::configure(meta, instance);
+        (signed, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = SignedChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let encoded = self.value.map(|value| F::from(Self::encode(value, self.num_bits)));
+        let value = load_chip.load_private(layouter.namespace(|| "load value"), encoded)?;
+        let (abs, sign) = chip.abs_and_sign(layouter.namespace(|| "abs and sign"), &value, self.num_bits)?;
+        chip.expose_public(layouter.namespace(|| "abs"), &abs, 0)?;
+        chip.expose_public(layouter.namespace(|| "sign"), &sign, 1)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> ValidateWitness<F> for AbsCircuit {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let value = known(self.value, "value")?;
+        if self.num_bits == 0 || self.num_bits > 64 {
+            return Err(InvalidWitness::InvalidParameter(format!(
+                "num_bits must be between 1 and 64, found {}",
+                self.num_bits
+            )));
+        }
+        let half = 1i128 << (self.num_bits - 1);
+        if !(-half..half).contains(&(value as i128)) {
+            return Err(InvalidWitness::OutOfRange {
+                name: "value",
+                value: value.to_string(),
+                bound: format!("2^{} in absolute value", self.num_bits - 1),
+            });
+        }
+        check_instances(instances, &Self::outputs(value).map(F::from))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::PrimeFieldBits;
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::AbsCircuit;
+    use crate::{test_over_fields, validate::ValidateWitness};
+
+    const K: u32 = 8;
+
+    fn instances<F: FieldExt>(value: i64) -> Vec<Vec<F>> {
+        vec![AbsCircuit::outputs(value).iter().map(|output| F::from(*output)).collect()]
+    }
+
+    fn test_abs_and_sign<F: FieldExt + PrimeFieldBits>() {
+        for (value, num_bits) in [(5, 8), (-5, 8), (0, 8), (-128, 8), (127, 8), (-1, 64), (i64::MIN, 64)] {
+            let circuit = AbsCircuit {
+                value: Value::known(value),
+                num_bits,
+            };
+
+            let prover = MockProver::run(K, &circuit, instances::<F>(value)).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    fn test_abs_and_sign_wrong_sign<F: FieldExt + PrimeFieldBits>() {
+        let circuit = AbsCircuit {
+            value: Value::known(-5),
+            num_bits: 8,
+        };
+
+        let mut wrong = instances::<F>(-5);
+        wrong[0][1] = F::zero();
+        let prover = MockProver::run(K, &circuit, wrong).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    test_over_fields!(test_abs_and_sign, test_abs_and_sign_wrong_sign);
+
+    #[test]
+    fn test_abs_and_sign_validate() {
+        // 200 is not an 8-bit signed integer: its pattern would read back as -56
+        let circuit = AbsCircuit {
+            value: Value::known(200),
+            num_bits: 8,
+        };
+        assert!(circuit.validate_witness(&instances::<Fp>(200)).is_err());
+
+        let circuit = AbsCircuit {
+            value: Value::known(200),
+            num_bits: 16,
+        };
+        assert!(circuit.validate_witness(&instances::<Fp>(200)).is_ok());
+    }
+}
@@ -0,0 +1,338 @@
+//! Updates of a sparse Merkle tree: the value at a key changes from `old_value` to
+//! `new_value`, and the root from `old_root` to `new_root`.
+//!
+//! The tree has a leaf for each of the `2^DEPTH` keys, the key's bits giving the path from
+//! the leaf up, and a leaf is its value, 0 when the key is unset. Almost every subtree is
+//! then empty, with a root depending only on its height:
+//!
+//!     default_0 = 0,   default_{i+1} = hash2(default_i, default_i)
+//!
+//! so `SparseMerkleTree` stores the nodes that differ from their default only, and paths
+//! through empty subtrees are made of default siblings.
+//!
+//! In the circuit, the key is unpacked into `DEPTH` bits with `BitsChip`, which keeps it
+//! below `2^DEPTH`, and both values are hashed up along the same bits and siblings. The
+//! siblings are the ones the update leaves alone, so the two roots differ in the key's leaf
+//! alone. Inserting is updating from 0, and deleting is updating to 0.
+//!
+//! A key is absent from a tree when its leaf is 0, so `non_membership` hashes an empty leaf,
+//! a constant, up the key's path; the root it ends at is the tree's only if the key is unset.
+//!
+//! Nodes are hashed with the `CircuitHasher` of `MerkleChip`, Poseidon by default.
+
+use std::{collections::HashMap, marker::PhantomData};
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*};
+
+use super::{
+    bits::{BitsChip, BitsConfig},
+    commit::CommitChip,
+    hasher::CircuitHasher,
+    merkle::{MerkleChip, MerkleConfig},
+};
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::Halo2ExamplesError,
+};
+
+/// The roots of the empty subtrees of heights `0..=depth`.
+pub fn default_nodes<H: CircuitHasher<Fp>>(depth: usize) -> Vec<Fp> {
+    let mut defaults = vec![Fp::zero()];
+    for i in 0..depth {
+        defaults.push(H::hash2_native(defaults[i], defaults[i]));
+    }
+    defaults
+}
+
+/// A sparse Merkle tree with `2^depth` leaves, computed natively.
+#[derive(Debug, Clone)]
+pub struct SparseMerkleTree<H: CircuitHasher<Fp> = CommitChip> {
+    depth: usize,
+    defaults: Vec<Fp>,
+    // the nodes other than their default, by height and index
+    nodes: HashMap<(usize, u64), Fp>,
+    _marker: PhantomData<H>,
+}
+
+impl SparseMerkleTree {
+    /// The empty tree over Poseidon.
+    pub fn new(depth: usize) -> Self {
+        Self::with_hasher(depth)
+    }
+}
+
+impl<H: CircuitHasher<Fp>> SparseMerkleTree<H> {
+    /// The empty tree.
+    pub fn with_hasher(depth: usize) -> Self {
+        assert!(depth < 64, "keys are u64s");
+        Self {
+            depth,
+            defaults: default_nodes::<H>(depth),
+            nodes: HashMap::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn root(&self) -> Fp {
+        self.node(self.depth, 0)
+    }
+
+    /// The value at `key`, 0 if it is unset.
+    pub fn get(&self, key: u64) -> Fp {
+        self.node(0, key)
+    }
+
+    /// The siblings of the leaf at `key`, bottom up.
+    pub fn path(&self, key: u64) -> Vec<Fp> {
+        (0..self.depth).map(|i| self.node(i, (key >> i) ^ 1)).collect()
+    }
+
+    /// Sets the value at `key`, unsetting it for 0.
+    pub fn set(&mut self, key: u64, value: Fp) {
+        assert!(key >> self.depth == 0, "key {} is outside a tree of depth {}", key, self.depth);
+        self.put(0, key, value);
+        for i in 0..self.depth {
+            let index = key >> (i + 1);
+            let node = H::hash2_native(self.node(i, 2 * index), self.node(i, 2 * index + 1));
+            self.put(i + 1, index, node);
+        }
+    }
+
+    fn node(&self, height: usize, index: u64) -> Fp {
+        self.nodes.get(&(height, index)).copied().unwrap_or(self.defaults[height])
+    }
+
+    fn put(&mut self, height: usize, index: u64, node: Fp) {
+        if node == self.defaults[height] {
+            self.nodes.remove(&(height, index));
+        } else {
+            self.nodes.insert((height, index), node);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SmtConfig<H: CircuitHasher<Fp> = CommitChip> {
+    merkle: MerkleConfig<H>,
+    bits: BitsConfig,
+    constants: ConstantsConfig,
+}
+
+pub struct SmtChip<const DEPTH: usize, H: CircuitHasher<Fp> = CommitChip> {
+    config: SmtConfig<H>,
+    merkle: MerkleChip<H>,
+    bits: BitsChip<Fp>,
+    constants: ConstantsChip<Fp>,
+}
+
+impl<const DEPTH: usize, H: CircuitHasher<Fp>> Chip<Fp> for SmtChip<DEPTH, H> {
+    type Config = SmtConfig<H>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<const DEPTH: usize, H: CircuitHasher<Fp>> SmtChip<DEPTH, H> {
+    pub fn construct(config: SmtConfig<H>) -> Self {
+        let merkle = MerkleChip::construct(config.merkle.clone());
+        let bits = BitsChip::construct(config.bits.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self {
+            config,
+            merkle,
+            bits,
+            constants,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, instance: Column<Instance>) -> SmtConfig<H> {
+        SmtConfig {
+            merkle: MerkleChip::configure(meta),
+            bits: BitsChip::configure(meta, instance),
+            // the empty leaf of `non_membership`
+            constants: ConstantsChip::configure(meta),
+        }
+    }
+
+    /// The chip the paths are hashed with.
+    pub fn merkle(&self) -> &MerkleChip<H> {
+        &self.merkle
+    }
+
+    /// The roots before and after setting `key` from `old_value` to `new_value`, for the
+    /// `DEPTH` siblings of its leaf, bottom up.
+    pub fn update(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        key: &AssignedCell<Fp, Fp>,
+        old_value: &AssignedCell<Fp, Fp>,
+        new_value: &AssignedCell<Fp, Fp>,
+        siblings: &[AssignedCell<Fp, Fp>],
+    ) -> Result<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>), Halo2ExamplesError> {
+        if siblings.len() != DEPTH {
+            return Err(Halo2ExamplesError::new("SmtChip", Error::Synthesis).in_region("update"));
+        }
+        let bits = self.bits.unpack(layouter.namespace(|| "key bits"), key, DEPTH)?;
+        let old_root = self.merkle.compute_root(layouter.namespace(|| "old root"), old_value, &bits, siblings)?;
+        let new_root = self.merkle.compute_root(layouter.namespace(|| "new root"), new_value, &bits, siblings)?;
+        Ok((old_root, new_root))
+    }
+
+    /// The root of a tree in which `key` is unset, for the `DEPTH` siblings of its leaf,
+    /// bottom up. Constraining it to a committed root proves `key` absent from that tree.
+    pub fn non_membership(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        key: &AssignedCell<Fp, Fp>,
+        siblings: &[AssignedCell<Fp, Fp>],
+    ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        if siblings.len() != DEPTH {
+            return Err(Halo2ExamplesError::new("SmtChip", Error::Synthesis).in_region("non-membership"));
+        }
+        let empty = self.constants.load_constant(layouter.namespace(|| "empty leaf"), "empty leaf", Fp::zero())?;
+        let bits = self.bits.unpack(layouter.namespace(|| "key bits"), key, DEPTH)?;
+        self.merkle.compute_root(layouter.namespace(|| "root"), &empty, &bits, siblings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{default_nodes, SmtChip, SmtConfig, SparseMerkleTree};
+    use crate::{
+        chip::{LoadPrivateChip, LoadPrivateConfig},
+        gadgets::{
+            commit::CommitChip,
+            merkle::{root_from_path, MerkleTree},
+        },
+        public_inputs::{PublicOutputs, PublicOutputsConfig},
+    };
+
+    const DEPTH: usize = 8;
+    const K: u32 = 11;
+
+    /// Proves `old_root` becomes `new_root` when `key` changes from `old_value` to `new_value`.
+    struct UpdateCircuit {
+        key: u64,
+        old_value: Fp,
+        new_value: Fp,
+        siblings: Vec<Fp>,
+    }
+
+    impl UpdateCircuit {
+        fn new(tree: &SparseMerkleTree, key: u64, new_value: Fp) -> Self {
+            Self {
+                key,
+                old_value: tree.get(key),
+                new_value,
+                siblings: tree.path(key),
+            }
+        }
+    }
+
+    impl Circuit<Fp> for UpdateCircuit {
+        type Config = (SmtConfig, LoadPrivateConfig, PublicOutputsConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                key: self.key,
+                old_value: self.old_value,
+                new_value: self.new_value,
+                siblings: self.siblings.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            let smt = SmtChip::<DEPTH>::configure(meta, instance);
+            (smt, LoadPrivateChip::configure(meta, 1), PublicOutputs::configure(meta, instance))
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (config, load_config, outputs_config) = config;
+            let chip = SmtChip::<DEPTH>::construct(config);
+            let load_chip = LoadPrivateChip::construct(load_config);
+            let mut outputs = PublicOutputs::construct(outputs_config);
+
+            let values = [Fp::from(self.key), self.old_value, self.new_value].map(Value::known);
+            let [key, old_value, new_value]: [_; 3] = load_chip
+                .load_privates(layouter.namespace(|| "load update"), &values)?
+                .try_into()
+                .expect("three cells loaded");
+            let siblings: Vec<_> = self.siblings.iter().copied().map(Value::known).collect();
+            let siblings = load_chip.load_privates(layouter.namespace(|| "load siblings"), &siblings)?;
+
+            let (old_root, new_root) = chip.update(layouter.namespace(|| "update"), &key, &old_value, &new_value, &siblings)?;
+            outputs.expose("old_root", &old_root);
+            outputs.expose("new_root", &new_root);
+            outputs.constrain(layouter.namespace(|| "public outputs"))?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sparse_tree() {
+        let mut tree = SparseMerkleTree::new(DEPTH);
+        assert_eq!(tree.root(), default_nodes::<CommitChip>(DEPTH)[DEPTH]);
+
+        let mut dense = vec![Fp::zero(); 1 << DEPTH];
+        for (key, value) in [(5, 10), (200, 20), (5, 30), (17, 40)] {
+            tree.set(key, Fp::from(value));
+            dense[key as usize] = Fp::from(value);
+            assert_eq!(tree.root(), MerkleTree::new(DEPTH, &dense).root());
+            assert_eq!(root_from_path::<CommitChip>(tree.get(key), key, &tree.path(key)), tree.root());
+        }
+
+        // unsetting every key brings back the empty tree, and only defaults are left
+        for key in [5, 200, 17] {
+            tree.set(key, Fp::zero());
+        }
+        assert_eq!(tree.root(), default_nodes::<CommitChip>(DEPTH)[DEPTH]);
+        assert!(tree.nodes.is_empty());
+    }
+
+    #[test]
+    fn test_smt_update() {
+        let mut tree = SparseMerkleTree::new(DEPTH);
+        tree.set(3, Fp::from(7));
+
+        // an insert, an update and a delete
+        for (key, value) in [(200, 11), (3, 8), (3, 0)] {
+            let old_root = tree.root();
+            let circuit = UpdateCircuit::new(&tree, key, Fp::from(value));
+            tree.set(key, Fp::from(value));
+            MockProver::run(K, &circuit, vec![vec![old_root, tree.root()]]).unwrap().assert_satisfied();
+        }
+
+        // the old value claimed wrong
+        let mut circuit = UpdateCircuit::new(&tree, 200, Fp::from(12));
+        let old_root = tree.root();
+        tree.set(200, Fp::from(12));
+        circuit.old_value = Fp::from(10);
+        assert!(MockProver::run(K, &circuit, vec![vec![old_root, tree.root()]]).unwrap().verify().is_err());
+
+        // a key outside the tree, whose low bits name a leaf of it
+        let mut circuit = UpdateCircuit::new(&tree, 200, Fp::from(13));
+        let old_root = tree.root();
+        circuit.key += 1 << DEPTH;
+        tree.set(200, Fp::from(13));
+        assert!(MockProver::run(K, &circuit, vec![vec![old_root, tree.root()]]).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Commitments to a vector of fixed length, opened at one position:
+//!
+//!     commitment = hash2(hash_many([v_0, ..., v_{n-1}]), blinding)
+//!
+//! with a `CircuitHasher`, Poseidon unless a gadget picks another, and the blinding factor
+//! keeping a vector of small values from being found by committing to each candidate.
+//!
+//! `open` recomputes the commitment from the whole vector and picks `v_index` with
+//! `SelectChip`, returning both cells: the caller ties the commitment and the index to its
+//! public inputs, or to other cells, and constrains the opened value further. That is `n`
+//! hashes an opening, where a Merkle path (`MerkleChip`) takes `log n`, but it needs no
+//! path witnessed, and the index is a cell like any other rather than bits.
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*};
+
+use super::{
+    commit::CommitChip,
+    hasher::CircuitHasher,
+    select::{SelectChip, SelectConfig},
+};
+use crate::error::Halo2ExamplesError;
+
+/// The commitment to `values` under `blinding`, computed natively.
+pub fn vector_commitment<H: CircuitHasher<Fp>>(values: &[Fp], blinding: Fp) -> Fp {
+    H::hash2_native(H::hash_many_native(values), blinding)
+}
+
+#[derive(Debug, Clone)]
+pub struct VectorCommitConfig<H: CircuitHasher<Fp> = CommitChip> {
+    hasher: H::Config,
+    select: SelectConfig,
+}
+
+pub struct VectorCommitChip<H: CircuitHasher<Fp> = CommitChip> {
+    config: VectorCommitConfig<H>,
+    hasher: H,
+    select: SelectChip<Fp>,
+}
+
+impl<H: CircuitHasher<Fp>> Chip<Fp> for VectorCommitChip<H> {
+    type Config = VectorCommitConfig<H>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<H: CircuitHasher<Fp>> VectorCommitChip<H> {
+    pub fn construct(config: VectorCommitConfig<H>) -> Self {
+        let hasher = H::construct(config.hasher.clone());
+        let select = SelectChip::construct(config.select.clone());
+        Self { config, hasher, select }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> VectorCommitConfig<H> {
+        let hasher = H::configure(meta);
+        let select = SelectChip::configure(meta);
+        VectorCommitConfig { hasher, select }
+    }
+
+    /// The chip the vector is hashed with.
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// The commitment to `values` under `blinding`, like `vector_commitment`.
+    pub fn commit(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        values: &[AssignedCell<Fp, Fp>],
+        blinding: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        let hash = self.hasher.hash_many(layouter.namespace(|| "hash values"), values)?;
+        self.hasher.hash2(layouter.namespace(|| "blind"), &hash, blinding)
+    }
+
+    /// The commitment to `values` under `blinding`, and `values[index]`. An index outside
+    /// the vector fails the proof.
+    pub fn open(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        values: &[AssignedCell<Fp, Fp>],
+        blinding: &AssignedCell<Fp, Fp>,
+        index: &AssignedCell<Fp, Fp>,
+    ) -> Result<(AssignedCell<Fp, Fp>, AssignedCell<Fp, Fp>), Halo2ExamplesError> {
+        let commitment = self.commit(layouter.namespace(|| "commit"), values, blinding)?;
+        let opened = self.select.select(layouter.namespace(|| "open"), values, index)?;
+        Ok((commitment, opened))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::marker::PhantomData;
+
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use rand::rngs::OsRng;
+
+    use super::{vector_commitment, VectorCommitChip, VectorCommitConfig};
+    use crate::{
+        chip::{LoadPrivateChip, LoadPrivateConfig},
+        gadgets::{commit::CommitChip, hasher::CircuitHasher, mimc::MimcChip},
+        public_inputs::{InstanceChip, InstanceConfig},
+    };
+
+    const K: u32 = 10;
+
+    /// Opens a private vector at a public index: the public inputs are the commitment, the
+    /// index and the value there.
+    struct OpenCircuit<H> {
+        values: Vec<Fp>,
+        blinding: Fp,
+        _marker: PhantomData<H>,
+    }
+
+    impl<H: CircuitHasher<Fp>> Circuit<Fp> for OpenCircuit<H> {
+        type Config = (VectorCommitConfig<H>, LoadPrivateConfig, InstanceConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: self.values.clone(),
+                blinding: self.blinding,
+                _marker: PhantomData,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            let vector = VectorCommitChip::configure(meta);
+            let load = LoadPrivateChip::configure(meta, 1);
+            let public = meta.advice_column();
+            (vector, load, InstanceChip::configure(meta, instance, public))
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (vector_config, load_config, instance_config) = config;
+            let chip = VectorCommitChip::<H>::construct(vector_config);
+            let load_chip = LoadPrivateChip::construct(load_config);
+            let instance = InstanceChip::construct(instance_config);
+
+            let values: Vec<_> = self.values.iter().copied().map(Value::known).collect();
+            let values = load_chip.load_privates(layouter.namespace(|| "values"), &values)?;
+            let blinding = load_chip.load_private(layouter.namespace(|| "blinding"), Value::known(self.blinding))?;
+            let index = instance.load(layouter.namespace(|| "index"), "index", 1)?;
+
+            let (commitment, opened) = chip.open(layouter.namespace(|| "open"), &values, &blinding, &index)?;
+            instance.expose(layouter.namespace(|| "commitment"), "commitment", &commitment, 0)?;
+            instance.expose(layouter.namespace(|| "value"), "value", &opened, 2)?;
+            Ok(())
+        }
+    }
+
+    fn test_open<H: CircuitHasher<Fp>>() {
+        let raw = [3, 1, 4, 1, 5];
+        let values = raw.map(Fp::from).to_vec();
+        let blinding = Fp::random(OsRng);
+        let commitment = vector_commitment::<H>(&values, blinding);
+        let circuit = OpenCircuit::<H> {
+            values: values.clone(),
+            blinding,
+            _marker: PhantomData,
+        };
+        let run = |index: u64, value: u64| {
+            let instances = vec![vec![commitment, Fp::from(index), Fp::from(value)]];
+            MockProver::run(K, &circuit, instances).unwrap().verify()
+        };
+
+        for (index, value) in raw.into_iter().enumerate() {
+            assert!(run(index as u64, value).is_ok(), "index {}", index);
+        }
+        // the wrong value, and an index past the end
+        assert!(run(2, 1).is_err());
+        assert!(run(5, 0).is_err());
+
+        // the same vector under another blinding
+        let other = OpenCircuit::<H> {
+            values,
+            blinding: Fp::random(OsRng),
+            _marker: PhantomData,
+        };
+        let instances = vec![vec![commitment, Fp::from(2), Fp::from(4)]];
+        assert!(MockProver::run(K, &other, instances).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_open_poseidon() {
+        test_open::<CommitChip>();
+    }
+
+    #[test]
+    fn test_open_mimc() {
+        test_open::<MimcChip<Fp>>();
+    }
+}
@@ -0,0 +1,611 @@
+//! 32-bit words, the arithmetic of hash functions like SHA-256 and Blake2.
+//!
+//! A word is a cell together with its four little-endian bytes, assigned on a row where the
+//! bytes are looked up in a byte table and recomposed into the word. Every operation copies
+//! the words it reads and returns a new one laid out the same way, so its result is always
+//! a range-checked 32-bit value:
+//!
+//! - `add` witnesses the carry out of bit 31 and checks `a + b = sum + carry * 2^32`.
+//! - `xor` looks up every pair of input bytes with the output byte in an 8-bit xor table.
+//! - `and` does the same lookup and witnesses one more word, checking byte by byte that
+//!   `a_i + b_i = (a_i ^ b_i) + 2 * (a_i & b_i)`.
+//! - `rotate_right`, `shift_right` and `shift_left` split every byte into a high and a low
+//!   piece at the same bit, then put the result bytes together from those pieces, or from
+//!   zero for the bits a shift drops. `rotate_left` is the matching right rotation.
+//!
+//! The byte lookup is scaled by the fixed `scale` column: a row with scale 1 checks its
+//! limbs are bytes, a row with scale `2^(8 - n)` that they are below `2^n`, and scale 0 turns
+//! the check off.
+//!
+//!        word   | limb_0 ... limb_3 | scale |  selector
+//!       ------------------------------------------------
+//!          a    |   a_0  ...  a_3   |   1   |  q_word
+//!
+//!  add:    a    |                   |       |  q_add
+//!          b    |   carry           |       |
+//!         sum   |   s_0  ...  s_3   |   1   |  q_word
+//!
+//!  xor:         |   a_0  ...  a_3   |       |  q_xor
+//!               |   b_0  ...  b_3   |       |
+//!          c    |   c_0  ...  c_3   |   1   |  q_word
+//!
+//!  and:         |   a_0  ...  a_3   |       |  q_xor, q_and
+//!               |   b_0  ...  b_3   |       |
+//!        a ^ b  |   x_0  ...  x_3   |   1   |  q_word
+//!          c    |   c_0  ...  c_3   |   1   |  q_word
+//!
+//!  shift:       |   a_0  ...  a_3   |       |  q_split
+//!               |   hi_0 ...  hi_3  |   1   |
+//!               |   lo_0 ...  lo_3  | 2^8-n |
+//!               |   high pieces     |       |  q_combine
+//!               |   low pieces      | 2^8-n |
+//!          c    |   c_0  ...  c_3   |   1   |  q_word
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    table,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+const LIMBS: usize = 4;
+const LIMB_BITS: usize = 8;
+
+/// A range-checked 32-bit word and its little-endian bytes.
+#[derive(Debug, Clone)]
+pub struct U32<F: FieldExt> {
+    pub word: AssignedCell<F, F>,
+    pub limbs: [AssignedCell<F, F>; LIMBS],
+}
+
+impl<F: FieldExt> U32<F> {
+    pub fn value(&self) -> Value<u32> {
+        self.word.value().map(|word| word.get_lower_128() as u32)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct U32Config {
+    word: Column<Advice>,
+    limbs: [Column<Advice>; LIMBS],
+    scale: Column<Fixed>,
+    q_word: Selector,
+    q_add: Selector,
+    q_xor: Selector,
+    q_and: Selector,
+    q_split: Selector,
+    q_combine: Selector,
+    bytes: TableColumn,
+    xor_table: [TableColumn; 3],
+    instance: Column<Instance>,
+    constants: ConstantsConfig,
+}
+
+pub struct U32Chip<F: FieldExt> {
+    config: U32Config,
+    constants: ConstantsChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for U32Chip<F> {
+    type Config = U32Config;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> U32Chip<F> {
+    pub fn construct(config: U32Config) -> Self {
+        let constants = ConstantsChip::construct(config.constants.clone());
+        Self { config, constants }
+    }
+
+    /// Also sets up the constants the zero pieces of a shift and constant words are
+    /// checked against.
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> U32Config {
+        let word = meta.advice_column();
+        let limbs = [(); LIMBS].map(|_| meta.advice_column());
+        let scale = meta.fixed_column();
+        let constants = ConstantsChip::configure_with(meta, word);
+
+        meta.enable_equality(word);
+        for limb in limbs {
+            meta.enable_equality(limb);
+        }
+        meta.enable_equality(instance);
+
+        let q_word = meta.selector();
+        let q_add = meta.selector();
+        let q_xor = meta.complex_selector();
+        let q_and = meta.selector();
+        let q_split = meta.selector();
+        let q_combine = meta.selector();
+        let bytes = meta.lookup_table_column();
+        let xor_table = [(); 3].map(|_| meta.lookup_table_column());
+
+        meta.create_gate("u32 word", |meta| {
+            let q_word = meta.query_selector(q_word);
+            let word = meta.query_advice(word, Rotation::cur());
+            let sum = limbs.iter().rev().fold(Expression::Constant(F::zero()), |acc, limb| {
+                acc * F::from(1u64 << LIMB_BITS) + meta.query_advice(*limb, Rotation::cur())
+            });
+
+            GateBuilder::with_selector(q_word).equal("word = sum of limbs", word, sum)
+        });
+
+        for limb in limbs {
+            meta.lookup(|meta| {
+                let scale = meta.query_fixed(scale, Rotation::cur());
+                let limb = meta.query_advice(limb, Rotation::cur());
+
+                vec![(scale * limb, bytes)]
+            });
+        }
+
+        meta.create_gate("u32 add", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let a = meta.query_advice(word, Rotation::cur());
+            let b = meta.query_advice(word, Rotation::next());
+            let sum = meta.query_advice(word, Rotation(2));
+            let carry = meta.query_advice(limbs[0], Rotation::next());
+
+            GateBuilder::with_selector(q_add)
+                .equal("a + b = sum + carry * 2^32", a + b, sum + carry.clone() * F::from(1u64 << 32))
+                .boolean("carry is boolean", carry)
+        });
+
+        for limb in limbs {
+            meta.lookup(|meta| {
+                let q_xor = meta.query_selector(q_xor);
+                let [lhs, rhs, out] = [0, 1, 2].map(|rotation| meta.query_advice(limb, Rotation(rotation)));
+
+                vec![
+                    (q_xor.clone() * lhs, xor_table[0]),
+                    (q_xor.clone() * rhs, xor_table[1]),
+                    (q_xor * out, xor_table[2]),
+                ]
+            });
+        }
+
+        // the xor lookup has checked x_i = a_i ^ b_i, and a_i + b_i = (a_i ^ b_i) + 2 * (a_i & b_i)
+        meta.create_gate("u32 and", |meta| {
+            let q_and = meta.query_selector(q_and);
+            limbs.iter().fold(GateBuilder::with_selector(q_and), |gate, limb| {
+                let [a, b, x, c] = [0, 1, 2, 3].map(|rotation| meta.query_advice(*limb, Rotation(rotation)));
+                gate.equal("a + b = xor + 2 * and", a + b, x + c * F::from(2))
+            })
+        });
+
+        // with scale = 2^(8 - n): limb * scale = hi * 2^8 + lo * scale, i.e. limb = hi * 2^n + lo
+        meta.create_gate("u32 split limbs", |meta| {
+            let q_split = meta.query_selector(q_split);
+            let scale = meta.query_fixed(scale, Rotation(2));
+            limbs.iter().fold(GateBuilder::with_selector(q_split), |gate, limb| {
+                let limb_value = meta.query_advice(*limb, Rotation::cur());
+                let hi = meta.query_advice(*limb, Rotation::next());
+                let lo = meta.query_advice(*limb, Rotation(2));
+                gate.equal("(limb - lo) * scale = hi * 2^8", (limb_value - lo) * scale.clone(), hi * F::from(1u64 << LIMB_BITS))
+            })
+        });
+
+        meta.create_gate("u32 combine pieces", |meta| {
+            let q_combine = meta.query_selector(q_combine);
+            let scale = meta.query_fixed(scale, Rotation::next());
+            limbs.iter().fold(GateBuilder::with_selector(q_combine), |gate, limb| {
+                let hi = meta.query_advice(*limb, Rotation::cur());
+                let lo = meta.query_advice(*limb, Rotation::next());
+                let out = meta.query_advice(*limb, Rotation(2));
+                gate.equal("out = hi + lo * scale", out, hi + lo * scale.clone())
+            })
+        });
+
+        U32Config {
+            word,
+            limbs,
+            scale,
+            q_word,
+            q_add,
+            q_xor,
+            q_and,
+            q_split,
+            q_combine,
+            bytes,
+            xor_table,
+            instance,
+            constants,
+        }
+    }
+
+    /// Loads the byte table and the 2^16-row xor table, so the circuit needs `k >= 17`.
+    pub fn load_tables(&self, layouter: &mut impl Layouter<F>) -> Result<(), Halo2ExamplesError> {
+        table::load_range_table(layouter, self.config.bytes, 1 << LIMB_BITS)
+            .in_chip("U32Chip", "load range check table")?;
+
+        let [lhs, rhs, out] = self.config.xor_table;
+        layouter
+            .assign_table(
+                || "load xor table",
+                |mut table| {
+                    let pairs = (0..1u64 << LIMB_BITS).flat_map(|a| (0..1u64 << LIMB_BITS).map(move |b| (a, b)));
+                    for (offset, (a, b)) in pairs.enumerate() {
+                        table.assign_cell(|| "lhs", lhs, offset, || Value::known(F::from(a)))?;
+                        table.assign_cell(|| "rhs", rhs, offset, || Value::known(F::from(b)))?;
+                        table.assign_cell(|| "xor", out, offset, || Value::known(F::from(a ^ b)))?;
+                    }
+                    Ok(())
+                },
+            )
+            .in_chip("U32Chip", "load xor table")
+    }
+
+    /// Witnesses a word.
+    pub fn assign(&self, mut layouter: impl Layouter<F>, value: Value<u32>) -> Result<U32<F>, Halo2ExamplesError> {
+        layouter
+            .assign_region(|| "assign word", |mut region| self.assign_word(&mut region, 0, value))
+            .in_chip("U32Chip", "assign word")
+    }
+
+    /// Witnesses a word fixed to `value`, like an initialization vector.
+    pub fn assign_constant(&self, mut layouter: impl Layouter<F>, value: u32) -> Result<U32<F>, Halo2ExamplesError> {
+        let name = format!("{:#010x}", value);
+        let constant = self.constants.load_constant(layouter.namespace(|| name.clone()), &name, F::from(value as u64))?;
+        layouter
+            .assign_region(
+                || "assign constant word",
+                |mut region| {
+                    let word = self.assign_word(&mut region, 0, Value::known(value))?;
+                    region.constrain_equal(word.word.cell(), constant.cell())?;
+                    Ok(word)
+                },
+            )
+            .in_chip("U32Chip", "assign constant word")
+    }
+
+    /// Checks that `cell` holds a 32-bit value and returns it as a word.
+    pub fn range_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+    ) -> Result<U32<F>, Halo2ExamplesError> {
+        layouter
+            .assign_region(
+                || "range check word",
+                |mut region| {
+                    let value = cell.value().map(|value| value.get_lower_128() as u32);
+                    let word = self.assign_word(&mut region, 0, value)?;
+                    region.constrain_equal(cell.cell(), word.word.cell())?;
+                    Ok(word)
+                },
+            )
+            .in_chip("U32Chip", "range check word")
+    }
+
+    /// `a + b mod 2^32`.
+    pub fn add(&self, layouter: impl Layouter<F>, a: &U32<F>, b: &U32<F>) -> Result<U32<F>, Halo2ExamplesError> {
+        self.add_with_carry(layouter, a, b).map(|(sum, _)| sum)
+    }
+
+    /// `a + b mod 2^32`, and the carry out of bit 31, constrained to be 0 or 1.
+    pub fn add_with_carry(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &U32<F>,
+        b: &U32<F>,
+    ) -> Result<(U32<F>, AssignedCell<F, F>), Halo2ExamplesError> {
+        layouter
+            .assign_region(
+                || "u32 add",
+                |mut region| {
+                    self.config.q_add.enable(&mut region, 0)?;
+                    a.word.copy_advice(|| "a", &mut region, self.config.word, 0)?;
+                    b.word.copy_advice(|| "b", &mut region, self.config.word, 1)?;
+
+                    let sum = a.value().zip(b.value()).map(|(a, b)| a.overflowing_add(b));
+                    let carry = region.assign_advice(|| "carry", self.config.limbs[0], 1, || {
+                        sum.map(|(_, carry)| F::from(carry as u64))
+                    })?;
+                    Ok((self.assign_word(&mut region, 2, sum.map(|(sum, _)| sum))?, carry))
+                },
+            )
+            .in_chip("U32Chip", "u32 add")
+    }
+
+    pub fn xor(&self, mut layouter: impl Layouter<F>, a: &U32<F>, b: &U32<F>) -> Result<U32<F>, Halo2ExamplesError> {
+        layouter
+            .assign_region(
+                || "u32 xor",
+                |mut region| {
+                    self.config.q_xor.enable(&mut region, 0)?;
+                    self.copy_limbs(&mut region, 0, a)?;
+                    self.copy_limbs(&mut region, 1, b)?;
+                    self.assign_word(&mut region, 2, a.value().zip(b.value()).map(|(a, b)| a ^ b))
+                },
+            )
+            .in_chip("U32Chip", "u32 xor")
+    }
+
+    pub fn and(&self, mut layouter: impl Layouter<F>, a: &U32<F>, b: &U32<F>) -> Result<U32<F>, Halo2ExamplesError> {
+        layouter
+            .assign_region(
+                || "u32 and",
+                |mut region| {
+                    self.config.q_xor.enable(&mut region, 0)?;
+                    self.config.q_and.enable(&mut region, 0)?;
+                    self.copy_limbs(&mut region, 0, a)?;
+                    self.copy_limbs(&mut region, 1, b)?;
+                    self.assign_word(&mut region, 2, a.value().zip(b.value()).map(|(a, b)| a ^ b))?;
+                    self.assign_word(&mut region, 3, a.value().zip(b.value()).map(|(a, b)| a & b))
+                },
+            )
+            .in_chip("U32Chip", "u32 and")
+    }
+
+    pub fn rotate_right(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &U32<F>,
+        bits: usize,
+    ) -> Result<U32<F>, Halo2ExamplesError> {
+        let bits = bits % 32;
+        let (limbs, split) = (bits / LIMB_BITS, bits % LIMB_BITS);
+        let pieces = [0, 1, 2, 3].map(|i| (Some((i + limbs) % LIMBS), Some((i + limbs + 1) % LIMBS)));
+        self.rearrange(layouter, "u32 rotate right", a, split, pieces, a.value().map(|a| a.rotate_right(bits as u32)))
+    }
+
+    /// `a <<< bits`, laid out as a right rotation by `32 - bits`.
+    pub fn rotate_left(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &U32<F>,
+        bits: usize,
+    ) -> Result<U32<F>, Halo2ExamplesError> {
+        self.rotate_right(layouter, a, 32 - bits % 32)
+    }
+
+    /// `a >> bits`, for `bits < 32`.
+    pub fn shift_right(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &U32<F>,
+        bits: usize,
+    ) -> Result<U32<F>, Halo2ExamplesError> {
+        assert!(bits < 32, "cannot shift a u32 by {} bits", bits);
+        let (limbs, split) = (bits / LIMB_BITS, bits % LIMB_BITS);
+        let piece = |index: usize| (index < LIMBS).then_some(index);
+        let pieces = [0, 1, 2, 3].map(|i| (piece(i + limbs), piece(i + limbs + 1)));
+        self.rearrange(layouter, "u32 shift right", a, split, pieces, a.value().map(|a| a >> bits))
+    }
+
+    /// `a << bits`, for `bits < 32`. Laid out as a right rotation by `32 - bits` that keeps
+    /// only the bits which wrapped around.
+    pub fn shift_left(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &U32<F>,
+        bits: usize,
+    ) -> Result<U32<F>, Halo2ExamplesError> {
+        assert!(bits < 32, "cannot shift a u32 by {} bits", bits);
+        let rotation = 32 - bits;
+        let (limbs, split) = (rotation / LIMB_BITS, rotation % LIMB_BITS);
+        let piece = |index: usize| (LIMBS..2 * LIMBS).contains(&index).then_some(index % LIMBS);
+        let pieces = [0, 1, 2, 3].map(|i| (piece(i + limbs), piece(i + limbs + 1)));
+        self.rearrange(layouter, "u32 shift left", a, split, pieces, a.value().map(|a| a << bits))
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        word: &U32<F>,
+        row: usize,
+    ) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .constrain_instance(word.word.cell(), self.config.instance, row)
+            .map_err(|err| Halo2ExamplesError::new("U32Chip", err).at(row, "instance"))
+    }
+
+    /// Splits every limb of `a` into its low `split` bits and the rest, then builds limb `i`
+    /// of the result from high piece `pieces[i].0` and low piece `pieces[i].1`, the latter
+    /// moved up by `8 - split` bits. A `None` piece is zero.
+    fn rearrange(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        a: &U32<F>,
+        split: usize,
+        pieces: [(Option<usize>, Option<usize>); LIMBS],
+        result: Value<u32>,
+    ) -> Result<U32<F>, Halo2ExamplesError> {
+        let scale = F::from(1u64 << (LIMB_BITS - split));
+        let bytes = a.value().map(|a| a.to_le_bytes().map(u32::from));
+        let zero = self.constants.load_constant(layouter.namespace(|| "zero"), "zero", F::zero())?;
+
+        layouter
+            .assign_region(
+                || name,
+                |mut region| {
+                    self.config.q_split.enable(&mut region, 0)?;
+                    self.copy_limbs(&mut region, 0, a)?;
+                    region.assign_fixed(|| "scale", self.config.scale, 1, || Value::known(F::one()))?;
+                    let high = self.assign_limbs(&mut region, 1, "high", bytes.map(|bytes| bytes.map(|b| b >> split)))?;
+                    region.assign_fixed(|| "scale", self.config.scale, 2, || Value::known(scale))?;
+                    let low = self.assign_limbs(&mut region, 2, "low", bytes.map(|bytes| bytes.map(|b| b & ((1 << split) - 1))))?;
+
+                    self.config.q_combine.enable(&mut region, 3)?;
+                    region.assign_fixed(|| "scale", self.config.scale, 4, || Value::known(scale))?;
+                    for (i, (hi, lo)) in pieces.into_iter().enumerate() {
+                        self.assign_piece(&mut region, 3, i, hi.map(|j| &high[j]), &zero)?;
+                        self.assign_piece(&mut region, 4, i, lo.map(|j| &low[j]), &zero)?;
+                    }
+                    self.assign_word(&mut region, 5, result)
+                },
+            )
+            .in_chip("U32Chip", name)
+    }
+
+    /// Assigns `value` and its bytes on row `offset`, where the bytes are range checked and
+    /// recomposed into the word.
+    fn assign_word(&self, region: &mut Region<'_, F>, offset: usize, value: Value<u32>) -> Result<U32<F>, Error> {
+        self.config.q_word.enable(region, offset)?;
+        region.assign_fixed(|| "scale", self.config.scale, offset, || Value::known(F::one()))?;
+        let word = region.assign_advice(|| "word", self.config.word, offset, || value.map(|v| F::from(v as u64)))?;
+        let limbs = self.assign_limbs(region, offset, "limb", value.map(|v| v.to_le_bytes().map(u32::from)))?;
+        Ok(U32 { word, limbs })
+    }
+
+    fn assign_limbs(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        name: &str,
+        values: Value<[u32; LIMBS]>,
+    ) -> Result<[AssignedCell<F, F>; LIMBS], Error> {
+        let limbs = (0..LIMBS)
+            .map(|i| {
+                region.assign_advice(|| format!("{} {}", name, i), self.config.limbs[i], offset, || {
+                    values.map(|values| F::from(values[i] as u64))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(limbs.try_into().expect("one cell per limb"))
+    }
+
+    fn copy_limbs(&self, region: &mut Region<'_, F>, offset: usize, word: &U32<F>) -> Result<(), Error> {
+        for (i, limb) in word.limbs.iter().enumerate() {
+            limb.copy_advice(|| format!("limb {}", i), region, self.config.limbs[i], offset)?;
+        }
+        Ok(())
+    }
+
+    fn assign_piece(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        limb: usize,
+        piece: Option<&AssignedCell<F, F>>,
+        zero: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        let column = self.config.limbs[limb];
+        piece.unwrap_or(zero).copy_advice(|| "piece", region, column, offset).map(|_| ())
+    }
+}
+
+/// Exercises every operation on two words, and SHA-256's `σ0(a) = (a >>> 7) ^ (a >>> 18) ^
+/// (a >> 3)` built from them. The results are public, in the order of `U32Circuit::outputs`.
+#[derive(Default)]
+pub struct U32Circuit {
+    pub a: Value<u32>,
+    pub b: Value<u32>,
+}
+
+impl U32Circuit {
+    /// `a + b`, `a ^ b`, `a & b`, `a >>> 7`, `a >> 3`, `b << 10` and `σ0(a)`.
+    pub fn outputs(a: u32, b: u32) -> [u32; 7] {
+        let sigma0 = a.rotate_right(7) ^ a.rotate_right(18) ^ (a >> 3);
+        [a.wrapping_add(b), a ^ b, a & b, a.rotate_right(7), a >> 3, b << 10, sigma0]
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for U32Circuit {
+    type Config = (U32Config, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let u32_config = U32Chip::configure(meta, instance);
+        (u32_config, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = U32Chip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        chip.load_tables(&mut layouter)?;
+
+        let a = load_chip.load_private(layouter.namespace(|| "load a"), self.a.map(|a| F::from(a as u64)))?;
+        let b = load_chip.load_private(layouter.namespace(|| "load b"), self.b.map(|b| F::from(b as u64)))?;
+        let a = chip.range_check(layouter.namespace(|| "a"), &a)?;
+        let b = chip.range_check(layouter.namespace(|| "b"), &b)?;
+
+        let sum = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+        let xor = chip.xor(layouter.namespace(|| "a ^ b"), &a, &b)?;
+        let and = chip.and(layouter.namespace(|| "a & b"), &a, &b)?;
+        let rotated = chip.rotate_right(layouter.namespace(|| "a >>> 7"), &a, 7)?;
+        let shifted_right = chip.shift_right(layouter.namespace(|| "a >> 3"), &a, 3)?;
+        let shifted_left = chip.shift_left(layouter.namespace(|| "b << 10"), &b, 10)?;
+
+        let rotated_18 = chip.rotate_right(layouter.namespace(|| "a >>> 18"), &a, 18)?;
+        let sigma0 = chip.xor(layouter.namespace(|| "sigma0"), &rotated, &rotated_18)?;
+        let sigma0 = chip.xor(layouter.namespace(|| "sigma0"), &sigma0, &shifted_right)?;
+
+        for (row, word) in [sum, xor, and, rotated, shifted_right, shifted_left, sigma0].iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "output"), word, row)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ValidateWitness<F> for U32Circuit {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let outputs = U32Circuit::outputs(known(self.a, "a")?, known(self.b, "b")?);
+        check_instances(instances, &outputs.map(|output| F::from(output as u64)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::U32Circuit;
+    use crate::dev::rotation::assert_no_wrapping_queries;
+
+    const K: u32 = 17;
+
+    fn instances(a: u32, b: u32) -> Vec<Vec<Fp>> {
+        vec![U32Circuit::outputs(a, b).iter().map(|output| Fp::from(*output as u64)).collect()]
+    }
+
+    #[test]
+    fn test_u32_ops() {
+        // the first two SHA-256 initial hash values, whose sum overflows
+        for (a, b) in [(0x6a09_e667, 0xbb67_ae85), (0, u32::MAX)] {
+            let circuit = U32Circuit {
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+
+            let prover = MockProver::run(K, &circuit, instances(a, b)).unwrap();
+            prover.assert_satisfied();
+            // the and gate reads up to `Rotation(3)`
+            assert_no_wrapping_queries(K, &circuit);
+        }
+    }
+
+    #[test]
+    fn test_u32_wrong_output() {
+        let (a, b) = (0x6a09_e667, 0xbb67_ae85);
+        let circuit = U32Circuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+
+        for row in 0..7 {
+            let mut wrong = instances(a, b);
+            wrong[0][row] += Fp::one();
+            let prover = MockProver::run(K, &circuit, wrong).unwrap();
+            assert!(prover.verify().is_err(), "output {} is not bound", row);
+        }
+    }
+}
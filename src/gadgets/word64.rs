@@ -0,0 +1,429 @@
+use std::marker::PhantomData;
+
+/// 64-bit words with overflow-checked arithmetic, for balances and other amounts.
+///
+/// A word is a cell together with its low and high 32-bit limbs, each range checked by a
+/// `DecomposeChip` running sum over the byte table. Every operation returns a new word and a
+/// flag cell, 1 if the result did not fit in 64 bits:
+///
+/// - `add`: `a + b = result + carry * 2^64`
+/// - `sub`: `a - b = result - borrow * 2^64`
+/// - `mul`: `a * b = result + high * 2^64`, with `high` itself a 64-bit word, and the flag
+///   set iff `high` is not zero
+///
+/// The products stay far below the field modulus, so the range checks on `result` (and
+/// `high`) leave only one way to satisfy each equation.
+///
+///         a0   |   a1   |   a2    |   a3    | high_inv |  selector
+///       --------------------------------------------------------------
+///         lo   |   hi   |  value  |         |          |  q_compose
+///         a    |   b    | result  |  carry  |          |  q_add / q_sub
+///         a    |   b    | result  |  high   | 1 / high |  q_mul
+///      overflow|        |         |         |          |
+use ff::PrimeFieldBits;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::is_zero::{IsZeroChip, IsZeroConfig};
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    decompose::decompose_range_check::{DecomposeChip, DecomposeConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The size of the lookup table the limbs are decomposed with, i.e. 8-bit chunks.
+const LIMB_RANGE: usize = 256;
+const LIMB_BITS: usize = 32;
+
+/// A range-checked 64-bit word and its 32-bit limbs.
+#[derive(Debug, Clone)]
+pub struct U64<F: FieldExt> {
+    pub value: AssignedCell<F, F>,
+    pub lo: AssignedCell<F, F>,
+    pub hi: AssignedCell<F, F>,
+}
+
+impl<F: FieldExt> U64<F> {
+    pub fn value(&self) -> Value<u64> {
+        self.value.value().map(|value| value.get_lower_128() as u64)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct U64Config<F: FieldExt> {
+    advice: [Column<Advice>; 4],
+    q_compose: Selector,
+    q_add: Selector,
+    q_sub: Selector,
+    q_mul: Selector,
+    high_is_zero: IsZeroConfig<F>,
+    limbs: DecomposeConfig<F, LIMB_RANGE>,
+    instance: Column<Instance>,
+}
+
+pub struct U64Chip<F: FieldExt> {
+    config: U64Config<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Chip<F> for U64Chip<F> {
+    type Config = U64Config<F>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> U64Chip<F> {
+    pub fn construct(config: U64Config<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> U64Config<F> {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let high_inv = meta.advice_column();
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        let q_compose = meta.selector();
+        let q_add = meta.selector();
+        let q_sub = meta.selector();
+        let q_mul = meta.selector();
+        let limbs = DecomposeConfig::configure(meta);
+
+        let two_pow_32 = F::from(1u64 << LIMB_BITS);
+        let two_pow_64 = F::from_u128(1u128 << (2 * LIMB_BITS));
+
+        meta.create_gate("u64 compose", |meta| {
+            let q_compose = meta.query_selector(q_compose);
+            let [lo, hi, value] = [advice[0], advice[1], advice[2]].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_compose).equal("value = lo + hi * 2^32", value, lo + hi * two_pow_32)
+        });
+
+        meta.create_gate("u64 add", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let [a, b, result, carry] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_add)
+                .equal("a + b = result + carry * 2^64", a + b, result + carry.clone() * two_pow_64)
+                .boolean("carry is boolean", carry)
+        });
+
+        meta.create_gate("u64 sub", |meta| {
+            let q_sub = meta.query_selector(q_sub);
+            let [a, b, result, borrow] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_sub)
+                .equal("a - b = result - borrow * 2^64", a - b, result - borrow.clone() * two_pow_64)
+                .boolean("borrow is boolean", borrow)
+        });
+
+        let high_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_mul),
+            |meta| meta.query_advice(advice[3], Rotation::cur()),
+            high_inv,
+        );
+
+        meta.create_gate("u64 mul", |meta| {
+            let q_mul = meta.query_selector(q_mul);
+            let [a, b, result, high] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let overflow = meta.query_advice(advice[0], Rotation::next());
+
+            GateBuilder::with_selector(q_mul)
+                .equal("a * b = result + high * 2^64", a * b, result + high * two_pow_64)
+                .equal(
+                    "overflow = high != 0",
+                    overflow,
+                    Expression::Constant(F::one()) - high_is_zero.is_zero_expr.clone(),
+                )
+        });
+
+        U64Config {
+            advice,
+            q_compose,
+            q_add,
+            q_sub,
+            q_mul,
+            high_is_zero,
+            limbs,
+            instance,
+        }
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Halo2ExamplesError> {
+        DecomposeChip::construct(self.config.limbs.clone()).load_table(layouter)
+    }
+
+    /// Witnesses a word.
+    pub fn assign(&self, layouter: impl Layouter<F>, value: Value<u64>) -> Result<U64<F>, Halo2ExamplesError> {
+        self.compose(layouter, value, None)
+    }
+
+    /// Checks that `cell` holds a 64-bit value and returns it as a word.
+    pub fn range_check(
+        &self,
+        layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+    ) -> Result<U64<F>, Halo2ExamplesError> {
+        let value = cell.value().map(|value| value.get_lower_128() as u64);
+        self.compose(layouter, value, Some(cell))
+    }
+
+    /// `a + b mod 2^64`, and whether it overflowed.
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &U64<F>,
+        b: &U64<F>,
+    ) -> Result<(U64<F>, AssignedCell<F, F>), Halo2ExamplesError> {
+        let sum = a.value().zip(b.value()).map(|(a, b)| a.overflowing_add(b));
+        let result = self.assign(layouter.namespace(|| "sum"), sum.map(|(sum, _)| sum))?;
+        let carry = self.checked(layouter, "u64 add", &self.config.q_add, a, b, &result, sum.map(|(_, carry)| carry))?;
+        Ok((result, carry))
+    }
+
+    /// `a - b mod 2^64`, and whether it underflowed.
+    pub fn sub(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &U64<F>,
+        b: &U64<F>,
+    ) -> Result<(U64<F>, AssignedCell<F, F>), Halo2ExamplesError> {
+        let difference = a.value().zip(b.value()).map(|(a, b)| a.overflowing_sub(b));
+        let result = self.assign(layouter.namespace(|| "difference"), difference.map(|(difference, _)| difference))?;
+        let borrow = self.checked(
+            layouter,
+            "u64 sub",
+            &self.config.q_sub,
+            a,
+            b,
+            &result,
+            difference.map(|(_, borrow)| borrow),
+        )?;
+        Ok((result, borrow))
+    }
+
+    /// `a * b mod 2^64`, and whether it overflowed.
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &U64<F>,
+        b: &U64<F>,
+    ) -> Result<(U64<F>, AssignedCell<F, F>), Halo2ExamplesError> {
+        let product = a.value().zip(b.value()).map(|(a, b)| a as u128 * b as u128);
+        let result = self.assign(layouter.namespace(|| "product"), product.map(|product| product as u64))?;
+        let high = self.assign(layouter.namespace(|| "product high"), product.map(|product| (product >> 64) as u64))?;
+        let is_zero_chip = IsZeroChip::construct(self.config.high_is_zero.clone());
+
+        layouter
+            .assign_region(
+                || "u64 mul",
+                |mut region| {
+                    self.config.q_mul.enable(&mut region, 0)?;
+                    self.copy_operands(&mut region, a, b, &result)?;
+                    high.value.copy_advice(|| "high", &mut region, self.config.advice[3], 0)?;
+                    is_zero_chip.assign(&mut region, 0, high.value.value().copied())?;
+                    region.assign_advice(|| "overflow", self.config.advice[0], 1, || {
+                        high.value().map(|high| F::from((high != 0) as u64))
+                    })
+                },
+            )
+            .in_chip("U64Chip", "u64 mul")
+            .map(|overflow| (result, overflow))
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .constrain_instance(cell.cell(), self.config.instance, row)
+            .map_err(|err| Halo2ExamplesError::new("U64Chip", err).at(row, "instance"))
+    }
+
+    /// Assigns `value` next to its limbs, copied from `input` if there is one, and range
+    /// checks both limbs.
+    fn compose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<u64>,
+        input: Option<&AssignedCell<F, F>>,
+    ) -> Result<U64<F>, Halo2ExamplesError> {
+        let [lo_column, hi_column, value_column, _] = self.config.advice;
+        let word = layouter
+            .assign_region(
+                || "u64 compose",
+                |mut region| {
+                    self.config.q_compose.enable(&mut region, 0)?;
+                    let lo = region.assign_advice(|| "lo", lo_column, 0, || value.map(|v| F::from(v & u32::MAX as u64)))?;
+                    let hi = region.assign_advice(|| "hi", hi_column, 0, || value.map(|v| F::from(v >> LIMB_BITS)))?;
+                    let value = match input {
+                        Some(input) => input.copy_advice(|| "value", &mut region, value_column, 0)?,
+                        None => region.assign_advice(|| "value", value_column, 0, || value.map(F::from))?,
+                    };
+                    Ok(U64 { value, lo, hi })
+                },
+            )
+            .in_chip("U64Chip", "u64 compose")?;
+
+        let limbs = DecomposeChip::construct(self.config.limbs.clone());
+        limbs.assign(layouter.namespace(|| "lo"), &word.lo, LIMB_BITS)?;
+        limbs.assign(layouter.namespace(|| "hi"), &word.hi, LIMB_BITS)?;
+        Ok(word)
+    }
+
+    /// Lays out `a`, `b`, `result` and the flag on one row with `selector` enabled.
+    #[allow(clippy::too_many_arguments)]
+    fn checked(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &str,
+        selector: &Selector,
+        a: &U64<F>,
+        b: &U64<F>,
+        result: &U64<F>,
+        flag: Value<bool>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        layouter
+            .assign_region(
+                || name,
+                |mut region| {
+                    selector.enable(&mut region, 0)?;
+                    self.copy_operands(&mut region, a, b, result)?;
+                    region.assign_advice(|| "flag", self.config.advice[3], 0, || flag.map(|flag| F::from(flag as u64)))
+                },
+            )
+            .in_chip("U64Chip", name)
+    }
+
+    fn copy_operands(&self, region: &mut Region<'_, F>, a: &U64<F>, b: &U64<F>, result: &U64<F>) -> Result<(), Error> {
+        let [a_column, b_column, result_column, _] = self.config.advice;
+        a.value.copy_advice(|| "a", region, a_column, 0)?;
+        b.value.copy_advice(|| "b", region, b_column, 0)?;
+        result.value.copy_advice(|| "result", region, result_column, 0)?;
+        Ok(())
+    }
+}
+
+/// Adds, subtracts and multiplies two words. The results and their flags are public, in the
+/// order of `U64Circuit::outputs`.
+#[derive(Default)]
+pub struct U64Circuit {
+    pub a: Value<u64>,
+    pub b: Value<u64>,
+}
+
+impl U64Circuit {
+    /// `a + b`, its carry, `a - b`, its borrow, `a * b` and its overflow flag, all wrapping.
+    pub fn outputs(a: u64, b: u64) -> [u64; 6] {
+        let (sum, carry) = a.overflowing_add(b);
+        let (difference, borrow) = a.overflowing_sub(b);
+        let (product, overflow) = a.overflowing_mul(b);
+        [sum, carry as u64, difference, borrow as u64, product, overflow as u64]
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> Circuit<F> for U64Circuit {
+    type Config = (U64Config<F>, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let u64_config = U64Chip::configure(meta, instance);
+        (u64_config, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = U64Chip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        chip.load_table(&mut layouter)?;
+
+        let a = load_chip.load_private(layouter.namespace(|| "load a"), self.a.map(F::from))?;
+        let b = load_chip.load_private(layouter.namespace(|| "load b"), self.b.map(F::from))?;
+        let a = chip.range_check(layouter.namespace(|| "a"), &a)?;
+        let b = chip.range_check(layouter.namespace(|| "b"), &b)?;
+
+        let (sum, carry) = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+        let (difference, borrow) = chip.sub(layouter.namespace(|| "a - b"), &a, &b)?;
+        let (product, overflow) = chip.mul(layouter.namespace(|| "a * b"), &a, &b)?;
+
+        let outputs = [&sum.value, &carry, &difference.value, &borrow, &product.value, &overflow];
+        for (row, cell) in outputs.into_iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "output"), cell, row)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ValidateWitness<F> for U64Circuit {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let outputs = U64Circuit::outputs(known(self.a, "a")?, known(self.b, "b")?);
+        check_instances(instances, &outputs.map(F::from))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::PrimeFieldBits;
+    use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver};
+
+    use super::U64Circuit;
+    use crate::test_over_fields;
+
+    const K: u32 = 9;
+
+    fn instances<F: FieldExt>(a: u64, b: u64) -> Vec<Vec<F>> {
+        vec![U64Circuit::outputs(a, b).map(F::from).to_vec()]
+    }
+
+    fn test_u64_arithmetic<F: FieldExt + PrimeFieldBits>() {
+        let cases = [(3, 5), (u64::MAX, 1), (1 << 32, 1 << 32), (u64::MAX, u64::MAX), (0, 0)];
+        for (a, b) in cases {
+            let circuit = U64Circuit {
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+
+            let prover = MockProver::run(K, &circuit, instances::<F>(a, b)).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    fn test_u64_wrong_flag<F: FieldExt + PrimeFieldBits>() {
+        let (a, b) = (1 << 40, 1 << 30);
+        let circuit = U64Circuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+
+        // claiming no overflow, or a borrow on 2^40 - 2^30
+        for row in [5, 3] {
+            let mut wrong = instances::<F>(a, b);
+            wrong[0][row] = F::one() - wrong[0][row];
+            let prover = MockProver::run(K, &circuit, wrong).unwrap();
+            assert!(prover.verify().is_err(), "flag {} is not bound", row);
+        }
+    }
+
+    test_over_fields!(test_u64_arithmetic, test_u64_wrong_flag);
+}
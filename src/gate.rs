@@ -0,0 +1,193 @@
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    plonk::Expression,
+};
+
+/// Builds the constraints of one `create_gate`, each under a name, so that a failing
+/// constraint reads as e.g. `Constraint 0 ("bit is boolean") in gate 0 ("bits")` in
+/// `MockProver`'s report instead of an index. The patterns that recur across the gadgets are
+/// one call each:
+///
+/// ```ignore
+/// meta.create_gate("bits", |meta| {
+///     let q_bit = meta.query_selector(q_bit);
+///     ...
+///     GateBuilder::with_selector(q_bit)
+///         .boolean("bit is boolean", bit.clone())
+///         .equal("acc = 2 * acc_prev + bit", acc, acc_prev * F::from(2) + bit)
+/// });
+/// ```
+///
+/// The builder is the closure's return value: it iterates over its constraints, each
+/// multiplied by the selector.
+pub struct GateBuilder<F: Field> {
+    selector: Option<Expression<F>>,
+    constraints: Vec<(&'static str, Expression<F>)>,
+}
+
+impl<F: Field> GateBuilder<F> {
+    /// A gate whose constraints all apply where `selector` is set. Any expression will do,
+    /// e.g. a product of selectors or a tag comparison.
+    pub fn with_selector(selector: Expression<F>) -> Self {
+        Self {
+            selector: Some(selector),
+            constraints: vec![],
+        }
+    }
+
+    /// A gate with no selector, for constraints that switch themselves off.
+    pub fn without_selector() -> Self {
+        Self {
+            selector: None,
+            constraints: vec![],
+        }
+    }
+
+    /// `expr == 0`
+    pub fn constrain(mut self, name: &'static str, expr: Expression<F>) -> Self {
+        self.constraints.push((name, expr));
+        self
+    }
+
+    /// `lhs == rhs`
+    pub fn equal(self, name: &'static str, lhs: Expression<F>, rhs: Expression<F>) -> Self {
+        self.constrain(name, lhs - rhs)
+    }
+
+    /// `x` is 0 or 1: `x * (1 - x) == 0`.
+    pub fn boolean(self, name: &'static str, x: Expression<F>) -> Self {
+        self.constrain(name, x.clone() * (Expression::Constant(F::one()) - x))
+    }
+
+    /// `out == cond ? when_true : when_false`, for a boolean `cond` constrained elsewhere.
+    pub fn select(
+        self,
+        name: &'static str,
+        out: Expression<F>,
+        cond: Expression<F>,
+        when_true: Expression<F>,
+        when_false: Expression<F>,
+    ) -> Self {
+        let one = Expression::Constant(F::one());
+        self.equal(name, out, cond.clone() * when_true + (one - cond) * when_false)
+    }
+}
+
+impl<F: FieldExt> GateBuilder<F> {
+    /// `x` is in `0..range`: `x * (1 - x) * ... * (range - 1 - x) == 0`, of degree `range`.
+    pub fn range(self, name: &'static str, x: Expression<F>, range: usize) -> Self {
+        assert!(range > 0);
+        let product = (1..range).fold(x.clone(), |acc, i| acc * (Expression::Constant(F::from(i as u64)) - x.clone()));
+        self.constrain(name, product)
+    }
+}
+
+impl<F: Field> IntoIterator for GateBuilder<F> {
+    type Item = (&'static str, Expression<F>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let selector = self.selector;
+        self.constraints
+            .into_iter()
+            .map(|(name, expr)| match &selector {
+                Some(selector) => (name, selector.clone() * expr),
+                None => (name, expr),
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::{MockProver, VerifyFailure},
+        pasta::Fp,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+        poly::Rotation,
+    };
+
+    use super::GateBuilder;
+
+    /// `out = cond ? a : b` with a boolean `cond` and `a` in `0..4`.
+    #[derive(Default)]
+    struct SelectCircuit {
+        row: [u64; 4],
+    }
+
+    impl Circuit<Fp> for SelectCircuit {
+        type Config = ([Column<Advice>; 4], Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let columns = [(); 4].map(|_| meta.advice_column());
+            let q = meta.selector();
+            meta.create_gate("select", |meta| {
+                let [cond, a, b, out] = columns.map(|column| meta.query_advice(column, Rotation::cur()));
+                GateBuilder::with_selector(meta.query_selector(q))
+                    .boolean("cond is boolean", cond.clone())
+                    .range("a in 0..4", a.clone(), 4)
+                    .select("out = cond ? a : b", out, cond, a, b)
+            });
+            (columns, q)
+        }
+
+        fn synthesize(&self, (columns, q): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            layouter.assign_region(
+                || "select",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    for (column, value) in columns.into_iter().zip(self.row) {
+                        region.assign_advice(|| "cell", column, 0, || Value::known(Fp::from(value)))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn failures(row: [u64; 4]) -> Vec<&'static str> {
+        let prover = MockProver::run(4, &SelectCircuit { row }, vec![]).unwrap();
+        prover
+            .verify()
+            .err()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|failure| match failure {
+                VerifyFailure::ConstraintNotSatisfied { constraint, .. } => {
+                    ["cond is boolean", "a in 0..4", "out = cond ? a : b"]
+                        .into_iter()
+                        .find(|name| constraint.to_string().contains(name))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gate_builder() {
+        assert!(failures([1, 3, 7, 3]).is_empty());
+        assert!(failures([0, 3, 7, 7]).is_empty());
+        assert_eq!(failures([2, 3, 7, 3]), vec!["cond is boolean", "out = cond ? a : b"]);
+        assert_eq!(failures([1, 4, 7, 4]), vec!["a in 0..4"]);
+        assert_eq!(failures([1, 3, 7, 7]), vec!["out = cond ? a : b"]);
+
+        // the name is reported against the constraint's index in its gate
+        let prover = MockProver::run(4, &SelectCircuit { row: [1, 4, 7, 4] }, vec![]).unwrap();
+        assert!(matches!(
+            &prover.verify().unwrap_err()[..],
+            [VerifyFailure::ConstraintNotSatisfied { constraint, .. }]
+                if constraint.to_string() == "Constraint 1 ('a in 0..4') in gate 0 ('select')"
+        ));
+
+        let mut meta = ConstraintSystem::<Fp>::default();
+        SelectCircuit::configure(&mut meta);
+        assert_eq!(meta.degree(), 5);
+    }
+}
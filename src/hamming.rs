@@ -0,0 +1,174 @@
+//! Biometric-style matching: a private bitstring, e.g. an iris code, lies within a public
+//! Hamming distance of a public template,
+//!
+//!     distance = popcount(sample ^ template) < threshold
+//!
+//! for bitstrings of `WORDS` 32-bit words. The sample and the template are words of
+//! `U32Chip`, whose `xor` looks up their bytes in the xor table; `PopcountChip` unpacks the
+//! xored words into bits and counts them, all `32 * WORDS` of them in one running count.
+//!
+//! The comparison is `MinMaxChip`'s: `distance + 1` is at most the threshold exactly when
+//! the threshold is `max(distance + 1, threshold)`. Both must be below `2^DISTANCE_BITS` for
+//! that to mean anything; the threshold is range checked, and `distance + 1` is at most
+//! `32 * WORDS + 1`, so `WORDS` must stay below 2048.
+//!
+//! The public inputs are the template words followed by the threshold. The sample, and how
+//! far from the template it is, stay private.
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::LoadInstructions,
+    error::ResultExt,
+    gadgets::{
+        arith::{ArithChip, ArithConfig},
+        min_max::{MinMaxChip, MinMaxConfig},
+        popcount::{PopcountChip, PopcountConfig},
+        word::{U32Chip, U32Config},
+    },
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The bits the threshold and `distance + 1` are compared in.
+pub const DISTANCE_BITS: usize = 16;
+
+/// The number of bits in which `a` and `b` differ.
+pub fn distance(a: &[u32], b: &[u32]) -> u32 {
+    a.iter().zip(b).map(|(a, b)| (a ^ b).count_ones()).sum()
+}
+
+/// Proves a private `WORDS`-word sample is closer than a public threshold to a public template.
+pub struct HammingCircuit<const WORDS: usize> {
+    pub sample: [Value<u32>; WORDS],
+    pub template: [Value<u32>; WORDS],
+    pub threshold: Value<u64>,
+}
+
+impl<const WORDS: usize> Default for HammingCircuit<WORDS> {
+    fn default() -> Self {
+        Self {
+            sample: [Value::unknown(); WORDS],
+            template: [Value::unknown(); WORDS],
+            threshold: Value::unknown(),
+        }
+    }
+}
+
+impl<const WORDS: usize> HammingCircuit<WORDS> {
+    pub fn new(sample: [u32; WORDS], template: [u32; WORDS], threshold: u64) -> Self {
+        Self {
+            sample: sample.map(Value::known),
+            template: template.map(Value::known),
+            threshold: Value::known(threshold),
+        }
+    }
+
+    /// The public inputs: the template words, then the threshold.
+    pub fn instances(template: [u32; WORDS], threshold: u64) -> Vec<Fp> {
+        template.into_iter().map(|word| Fp::from(word as u64)).chain([Fp::from(threshold)]).collect()
+    }
+}
+
+impl<const WORDS: usize> Circuit<Fp> for HammingCircuit<WORDS> {
+    type Config = (U32Config, PopcountConfig, MinMaxConfig, ArithConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let words = U32Chip::configure(meta, instance);
+        let popcount = PopcountChip::configure(meta, instance);
+        let min_max = MinMaxChip::<Fp, DISTANCE_BITS>::configure(meta, instance);
+        let advice = [(); 3].map(|_| meta.advice_column());
+        (words, popcount, min_max, ArithChip::configure(meta, advice))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (words_config, popcount_config, min_max_config, arith_config) = config;
+        let words = U32Chip::construct(words_config);
+        let popcount = PopcountChip::construct(popcount_config);
+        let min_max = MinMaxChip::<Fp, DISTANCE_BITS>::construct(min_max_config);
+        let arith = ArithChip::construct(arith_config);
+        words.load_tables(&mut layouter)?;
+
+        // 1. The bits the sample and the template differ in.
+        let mut bits = Vec::with_capacity(32 * WORDS);
+        for (i, (sample, template)) in self.sample.iter().zip(self.template.iter()).enumerate() {
+            let mut layouter = layouter.namespace(|| format!("word {}", i));
+            let sample = words.assign(layouter.namespace(|| "sample"), *sample)?;
+            let template = words.assign(layouter.namespace(|| "template"), *template)?;
+            words.expose_public(layouter.namespace(|| "template"), &template, i)?;
+            let diff = words.xor(layouter.namespace(|| "sample ^ template"), &sample, &template)?;
+            bits.extend(popcount.bits().unpack(layouter.namespace(|| "unpack"), &diff.word, 32)?);
+        }
+        let distance = popcount.count_ones(layouter.namespace(|| "distance"), &bits)?;
+
+        // 2. distance < threshold
+        let threshold = arith.load_private(layouter.namespace(|| "threshold"), self.threshold.map(Fp::from))?;
+        min_max.bits().unpack(layouter.namespace(|| "range check threshold"), &threshold, DISTANCE_BITS)?;
+        min_max.expose_public(layouter.namespace(|| "threshold"), &threshold, WORDS)?;
+        let distance = arith.add_constant(layouter.namespace(|| "distance + 1"), &distance, Fp::one())?;
+        let max = min_max.max(layouter.namespace(|| "max(distance + 1, threshold)"), &[distance, threshold.clone()])?;
+        layouter
+            .assign_region(|| "distance below threshold", |mut region| region.constrain_equal(max.cell(), threshold.cell()))
+            .in_chip("HammingCircuit", "distance below threshold")?;
+        Ok(())
+    }
+}
+
+impl<const WORDS: usize> ValidateWitness<Fp> for HammingCircuit<WORDS> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let words = |words: &[Value<u32>], name: &'static str| -> Result<Vec<u32>, InvalidWitness> {
+            words.iter().map(|word| known(*word, name)).collect()
+        };
+        let sample = words(&self.sample, "sample")?;
+        let template = words(&self.template, "template")?;
+        let threshold = known(self.threshold, "threshold")?;
+        check_below(Fp::from(threshold), 1 << DISTANCE_BITS, "threshold")?;
+        check_below(Fp::from(distance(&sample, &template) as u64), threshold as u128, "distance")?;
+
+        let template = template.try_into().expect("WORDS words");
+        check_instances(instances, &Self::instances(template, threshold))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::{distance, HammingCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 17;
+
+    const TEMPLATE: [u32; 2] = [0xdead_beef, 0x0123_4567];
+    // 3 bits off in the first word, 2 in the second
+    const SAMPLE: [u32; 2] = [0xdead_beef ^ 0x8000_0101, 0x0123_4567 ^ 0x0010_0001];
+
+    #[test]
+    fn test_hamming() {
+        assert_eq!(distance(&SAMPLE, &TEMPLATE), 5);
+        let circuit = HammingCircuit::new(SAMPLE, TEMPLATE, 6);
+        let instances = vec![HammingCircuit::instances(TEMPLATE, 6)];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+    }
+
+    #[test]
+    fn test_hamming_too_far() {
+        // a threshold the distance reaches but is not below
+        let circuit = HammingCircuit::new(SAMPLE, TEMPLATE, 5);
+        let instances = vec![HammingCircuit::instances(TEMPLATE, 5)];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+
+        // the right sample against another template
+        let circuit = HammingCircuit::new(SAMPLE, TEMPLATE, 6);
+        let mut instances = HammingCircuit::instances(TEMPLATE, 6);
+        instances[1] += Fp::one();
+        assert!(MockProver::run(K, &circuit, vec![instances]).unwrap().verify().is_err());
+    }
+}
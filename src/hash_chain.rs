@@ -0,0 +1,138 @@
+//! An iterated hash, PBKDF or proof-of-work style: the prover knows a `secret` whose `n`-th
+//! hash under a public `salt` is a public `target`,
+//!
+//!     x_0 = secret,   x_{i+1} = hash2(x_i, salt),   x_n = target
+//!
+//! with the circuit's `CircuitHasher`, Poseidon unless another is picked. The salt keeps a
+//! table of chains computed for one salt useless for another.
+//!
+//! `n` is part of the circuit, as the number of hashes laid out, so the verifying key pins
+//! it down and the public inputs are the salt and the target alone. Every step is the same
+//! region, one after the other, which makes the circuit a workload of any size: about 40
+//! rows per Poseidon hash, so `n = 16` fits in `k = 10` and every quadrupling of `n` takes two
+//! more. `benches/hash_chain.rs` proves it at several sizes.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    gadgets::{commit::CommitChip, hasher::CircuitHasher},
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// `x_n`, computed natively.
+pub fn hash_chain<H: CircuitHasher<Fp>>(secret: Fp, salt: Fp, iterations: usize) -> Fp {
+    (0..iterations).fold(secret, |x, _| H::hash2_native(x, salt))
+}
+
+/// Proves the `iterations`-th hash of `secret` under `salt`, hashing with `H`.
+pub struct HashChainCircuit<H: CircuitHasher<Fp> = CommitChip> {
+    pub secret: Value<Fp>,
+    pub salt: Value<Fp>,
+    pub iterations: usize,
+    pub hasher: PhantomData<H>,
+}
+
+impl<H: CircuitHasher<Fp>> HashChainCircuit<H> {
+    pub fn new(secret: Fp, salt: Fp, iterations: usize) -> Self {
+        Self {
+            secret: Value::known(secret),
+            salt: Value::known(salt),
+            iterations,
+            hasher: PhantomData,
+        }
+    }
+
+    /// The salt and the target, in instance order.
+    pub fn outputs(secret: Fp, salt: Fp, iterations: usize) -> [Fp; 2] {
+        [salt, hash_chain::<H>(secret, salt, iterations)]
+    }
+}
+
+impl<H: CircuitHasher<Fp>> Circuit<Fp> for HashChainCircuit<H> {
+    type Config = (H::Config, LoadPrivateConfig, PublicOutputsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            secret: Value::unknown(),
+            salt: Value::unknown(),
+            iterations: self.iterations,
+            hasher: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let hasher = H::configure(meta);
+        (hasher, LoadPrivateChip::configure(meta, 1), PublicOutputs::configure(meta, instance))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, load_config, outputs_config) = config;
+        let hasher = H::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+
+        let [secret, salt]: [_; 2] = load_chip
+            .load_privates(layouter.namespace(|| "load secret and salt"), &[self.secret, self.salt])?
+            .try_into()
+            .expect("two cells loaded");
+
+        let mut x = secret;
+        for i in 0..self.iterations {
+            x = hasher.hash2(layouter.namespace(|| format!("x_{}", i + 1)), &x, &salt)?;
+        }
+
+        outputs.expose("salt", &salt);
+        outputs.expose("target", &x);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl<H: CircuitHasher<Fp>> ValidateWitness<Fp> for HashChainCircuit<H> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let outputs = Self::outputs(known(self.secret, "secret")?, known(self.salt, "salt")?, self.iterations);
+        check_instances(instances, &outputs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::HashChainCircuit;
+    use crate::{gadgets::mimc::MimcChip, reference::ref_poseidon, validate::ValidateWitness};
+
+    const K: u32 = 10;
+
+    #[test]
+    fn test_hash_chain() {
+        let (secret, salt) = (Fp::from(0x5ec2e7), Fp::from(0x5a17));
+        let target = (0..16).fold(secret, |x, _| ref_poseidon(x, salt));
+        assert_eq!(HashChainCircuit::<MimcChip<Fp>>::outputs(secret, salt, 0), [salt, secret]);
+
+        let circuit: HashChainCircuit = HashChainCircuit::new(secret, salt, 16);
+        let instances = vec![vec![salt, target]];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+
+        // one hash short of the target
+        let circuit: HashChainCircuit = HashChainCircuit::new(secret, salt, 15);
+        let prover = MockProver::run(K, &circuit, vec![vec![salt, target]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_hash_chain_mimc() {
+        let (secret, salt) = (Fp::from(3), Fp::from(5));
+        let circuit = HashChainCircuit::<MimcChip<Fp>>::new(secret, salt, 4);
+        let instances = vec![HashChainCircuit::<MimcChip<Fp>>::outputs(secret, salt, 4).to_vec()];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+    }
+}
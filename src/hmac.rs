@@ -0,0 +1,203 @@
+//! Knowledge of an HMAC-SHA256 key: a private `key` such that
+//!
+//!     HMAC(key, message) = SHA-256((K ^ opad) || SHA-256((K ^ ipad) || message)) = tag
+//!
+//! for a public `message` and `tag`, where `K` is the key padded with zeros to a 64-byte
+//! block, or its SHA-256 digest padded the same way if the key is longer than a block, and
+//! `ipad`, `opad` are the bytes `0x36` and `0x5c` repeated (RFC 2104).
+//!
+//! The key and message lengths are part of the circuit, so whether the key is hashed first
+//! and where the padding goes is decided when it is built: the padding words of `K ^ ipad`
+//! and `K ^ opad` are the constants `0x36363636` and `0x5c5c5c5c`, and only the key words
+//! are xored in the circuit. The two hashes are two calls to `Sha256Chip::digest`, the inner
+//! digest's words feeding the outer message directly.
+//!
+//! Key and message are whole big-endian words, as SHA-256 reads them. The public inputs are
+//! the message words followed by the 8 words of the tag.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::{
+    error::Halo2ExamplesError,
+    gadgets::{
+        sha256::Sha256Chip,
+        word::{U32Config, U32},
+    },
+    reference::ref_hmac_sha256,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+const IPAD: u32 = 0x3636_3636;
+const OPAD: u32 = 0x5c5c_5c5c;
+
+/// The words of `K ^ pad` for the block-sized key `key`, at most 16 words, whose missing
+/// words are zero.
+fn xor_pad<F: FieldExt>(
+    chip: &Sha256Chip<F>,
+    mut layouter: impl Layouter<F>,
+    key: &[U32<F>],
+    pad: u32,
+) -> Result<Vec<U32<F>>, Halo2ExamplesError> {
+    let pad = chip.words().assign_constant(layouter.namespace(|| "pad"), pad)?;
+    let mut block = key
+        .iter()
+        .enumerate()
+        .map(|(i, word)| chip.words().xor(layouter.namespace(|| format!("k_{} ^ pad", i)), word, &pad))
+        .collect::<Result<Vec<_>, _>>()?;
+    block.resize(16, pad);
+    Ok(block)
+}
+
+/// HMAC-SHA256 of `message` under `key`.
+pub fn hmac_sha256<F: FieldExt>(
+    chip: &Sha256Chip<F>,
+    mut layouter: impl Layouter<F>,
+    key: &[U32<F>],
+    message: &[U32<F>],
+) -> Result<[U32<F>; 8], Halo2ExamplesError> {
+    let hashed_key;
+    let key = if key.len() > 16 {
+        hashed_key = chip.digest(layouter.namespace(|| "hash key"), key)?;
+        &hashed_key[..]
+    } else {
+        key
+    };
+
+    let mut inner = xor_pad(chip, layouter.namespace(|| "K ^ ipad"), key, IPAD)?;
+    inner.extend(message.iter().cloned());
+    let inner = chip.digest(layouter.namespace(|| "inner hash"), &inner)?;
+
+    let mut outer = xor_pad(chip, layouter.namespace(|| "K ^ opad"), key, OPAD)?;
+    outer.extend(inner);
+    chip.digest(layouter.namespace(|| "outer hash"), &outer)
+}
+
+/// Proves knowledge of a `KEY_WORDS`-word key tagging a public `MESSAGE_WORDS`-word message.
+pub struct HmacCircuit<const KEY_WORDS: usize, const MESSAGE_WORDS: usize> {
+    pub key: [Value<u32>; KEY_WORDS],
+    pub message: [Value<u32>; MESSAGE_WORDS],
+}
+
+impl<const KEY_WORDS: usize, const MESSAGE_WORDS: usize> Default for HmacCircuit<KEY_WORDS, MESSAGE_WORDS> {
+    fn default() -> Self {
+        Self {
+            key: [Value::unknown(); KEY_WORDS],
+            message: [Value::unknown(); MESSAGE_WORDS],
+        }
+    }
+}
+
+impl<const KEY_WORDS: usize, const MESSAGE_WORDS: usize> HmacCircuit<KEY_WORDS, MESSAGE_WORDS> {
+    /// The public inputs: the message words, then the words of its tag under `key`.
+    pub fn instances(key: [u32; KEY_WORDS], message: [u32; MESSAGE_WORDS]) -> Vec<u64> {
+        let bytes = |words: &[u32]| -> Vec<u8> { words.iter().flat_map(|word| word.to_be_bytes()).collect() };
+        let tag = ref_hmac_sha256(&bytes(&key), &bytes(&message));
+        let tag = tag.chunks(4).map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()));
+        message.into_iter().chain(tag).map(u64::from).collect()
+    }
+}
+
+impl<F: FieldExt, const KEY_WORDS: usize, const MESSAGE_WORDS: usize> Circuit<F> for HmacCircuit<KEY_WORDS, MESSAGE_WORDS> {
+    type Config = U32Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        Sha256Chip::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = Sha256Chip::construct(config);
+        let words = chip.words();
+        words.load_tables(&mut layouter)?;
+
+        let key = self
+            .key
+            .iter()
+            .enumerate()
+            .map(|(i, word)| words.assign(layouter.namespace(|| format!("key {}", i)), *word))
+            .collect::<Result<Vec<_>, _>>()?;
+        let message = self
+            .message
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                let word = words.assign(layouter.namespace(|| format!("message {}", i)), *word)?;
+                words.expose_public(layouter.namespace(|| "message"), &word, i)?;
+                Ok(word)
+            })
+            .collect::<Result<Vec<_>, Halo2ExamplesError>>()?;
+
+        let tag = hmac_sha256(&chip, layouter.namespace(|| "hmac"), &key, &message)?;
+        for (i, word) in tag.iter().enumerate() {
+            words.expose_public(layouter.namespace(|| "tag"), word, MESSAGE_WORDS + i)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: FieldExt, const KEY_WORDS: usize, const MESSAGE_WORDS: usize> ValidateWitness<F>
+    for HmacCircuit<KEY_WORDS, MESSAGE_WORDS>
+{
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let words = |words: &[Value<u32>], name: &'static str| -> Result<Vec<u32>, InvalidWitness> {
+            words.iter().map(|word| known(*word, name)).collect()
+        };
+        let key = words(&self.key, "key")?.try_into().unwrap();
+        let message = words(&self.message, "message")?.try_into().unwrap();
+
+        let expected: Vec<_> = Self::instances(key, message).into_iter().map(F::from).collect();
+        check_instances(instances, &expected)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{arithmetic::Field, circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::HmacCircuit;
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 17;
+
+    fn words<const N: usize>(bytes: &[u8]) -> [u32; N] {
+        std::array::from_fn(|i| u32::from_be_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap()))
+    }
+
+    // RFC 4231, test case 2
+    fn rfc_4231() -> (HmacCircuit<1, 7>, Vec<Fp>) {
+        let (key, message) = (words(b"Jefe"), words(b"what do ya want for nothing?"));
+        let instances: Vec<Fp> = HmacCircuit::instances(key, message).into_iter().map(Fp::from).collect();
+        let circuit = HmacCircuit {
+            key: key.map(Value::known),
+            message: message.map(Value::known),
+        };
+        (circuit, instances)
+    }
+
+    #[test]
+    fn test_hmac() {
+        let (circuit, instances) = rfc_4231();
+        assert_eq!(instances[7..9], [Fp::from(0x5bdc_c146), Fp::from(0xbf60_754e)]);
+        assert_eq!(circuit.validate_witness(&[instances.clone()]), Ok(()));
+
+        MockProver::run(K, &circuit, vec![instances]).unwrap().assert_satisfied();
+    }
+
+    #[test]
+    fn test_hmac_wrong_public_inputs() {
+        let (circuit, instances) = rfc_4231();
+
+        // a different message, then a different tag
+        for row in [0, 14] {
+            let mut wrong = instances.clone();
+            wrong[row] += Fp::one();
+            let prover = MockProver::run(K, &circuit, vec![wrong]).unwrap();
+            assert!(prover.verify().is_err(), "public input {} is not bound", row);
+        }
+    }
+}
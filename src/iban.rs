@@ -0,0 +1,272 @@
+//! IBAN validation: a private IBAN passes its ISO 7064 mod-97 check, and only its country
+//! code is revealed.
+//!
+//! The check moves the country code and the check digits, the first four characters, to the
+//! end, reads every letter as two digits, `A = 10` to `Z = 35`, and requires the resulting
+//! number to be 1 modulo 97. That number has up to 70 digits, too many for one field
+//! element to divide, so it is reduced a symbol at a time, `DivRemChip` dividing by 97:
+//!
+//!     r_0 = 0,   r_{i+1} = (r_i * (letter_i ? 100 : 10) + s_i) mod 97,   r_LEN = 1
+//!
+//!       symbol  |  letter  |   rem   |   acc    |  q_step
+//!     ------------------------------------------------------
+//!        s_i    |   l_i    |   r_i   |  acc_i   |    1       acc_i = r_i * (10 + 90 * l_i) + s_i
+//!
+//! with `acc_i` then divided by 97 for `r_{i+1}`. The lookup of `(s_i, l_i)` in a fixed
+//! table of the 36 symbols and whether each is a letter is the range check on every
+//! character, and decides how many digits it stands for.
+//!
+//! The public inputs are the two letters of the country code, as symbols. The rest of the
+//! IBAN, the account it names included, stays private; its length, `LEN`, is fixed by the
+//! circuit, as it is by each country's format.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::LoadInstructions,
+    error::ResultExt,
+    gadgets::{
+        arith::{ArithChip, ArithConfig},
+        div_rem::{DivRemChip, DivRemConfig},
+    },
+    gate::GateBuilder,
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    table::FixedTableConfig,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The number of symbols, the digits and the letters.
+pub const SYMBOLS: u64 = 36;
+
+/// The bits `acc_i`, at most `96 * 100 + 35`, and its quotient and remainder fit in.
+pub const DIV_BITS: usize = 14;
+
+/// The symbol of an IBAN character: 0 to 9 for the digits, 10 to 35 for `A` to `Z`.
+pub fn symbol(c: char) -> Option<u64> {
+    c.to_digit(SYMBOLS as u32).map(u64::from)
+}
+
+/// The symbols of `iban`, ignoring spaces, or `None` if it has other characters.
+pub fn symbols(iban: &str) -> Option<Vec<u64>> {
+    iban.chars().filter(|c| *c != ' ').map(symbol).collect()
+}
+
+/// The rearranged IBAN modulo 97, 1 for a valid one.
+pub fn checksum(symbols: &[u64]) -> u64 {
+    let (head, tail) = symbols.split_at(symbols.len().min(4));
+    tail.iter().chain(head).fold(0, |rem, symbol| {
+        let digits = if *symbol < 10 { 10 } else { 100 };
+        (rem * digits + symbol) % 97
+    })
+}
+
+/// Whether `iban` is well formed and passes the mod-97 check.
+pub fn is_valid(iban: &str) -> bool {
+    match symbols(iban) {
+        Some(symbols) => symbols.len() > 4 && symbols[..2].iter().all(|symbol| *symbol >= 10) && checksum(&symbols) == 1,
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IbanConfig {
+    div_rem: DivRemConfig,
+    arith: ArithConfig,
+    outputs: PublicOutputsConfig,
+    symbol: Column<Advice>,
+    letter: Column<Advice>,
+    rem: Column<Advice>,
+    acc: Column<Advice>,
+    q_step: Selector,
+    alphabet: [FixedTableConfig<Fp>; 2],
+}
+
+/// Proves a private IBAN of `LEN` characters valid, and exposes its country code.
+pub struct IbanCircuit<const LEN: usize> {
+    pub symbols: [Value<u64>; LEN],
+}
+
+impl<const LEN: usize> Default for IbanCircuit<LEN> {
+    fn default() -> Self {
+        Self {
+            symbols: [Value::unknown(); LEN],
+        }
+    }
+}
+
+impl<const LEN: usize> IbanCircuit<LEN> {
+    /// The circuit for `iban`, spaces allowed. Panics if it is not `LEN` letters and digits.
+    pub fn new(iban: &str) -> Self {
+        let symbols: [u64; LEN] = symbols(iban)
+            .expect("an IBAN of letters and digits")
+            .try_into()
+            .unwrap_or_else(|symbols: Vec<_>| panic!("{} characters, not {}", symbols.len(), LEN));
+        Self {
+            symbols: symbols.map(Value::known),
+        }
+    }
+
+    /// The public inputs: the symbols of the two letters of `country`.
+    pub fn instances(country: &str) -> Vec<Fp> {
+        country.chars().map(|c| Fp::from(symbol(c).expect("a letter"))).collect()
+    }
+}
+
+impl<const LEN: usize> Circuit<Fp> for IbanCircuit<LEN> {
+    type Config = IbanConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let div_rem = DivRemChip::<Fp, DIV_BITS>::configure(meta, instance);
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let arith = ArithChip::configure(meta, advice);
+        let [symbol, letter, rem, acc] = [(); 4].map(|_| meta.advice_column());
+        let q_step = meta.complex_selector();
+        let alphabet = ["iban symbol", "iban letter"].map(|name| FixedTableConfig::configure(meta, name));
+        for column in [symbol, rem, acc] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("iban step", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let [symbol, letter, rem, acc] = [symbol, letter, rem, acc].map(|column| meta.query_advice(column, Rotation::cur()));
+            let digits = Expression::Constant(Fp::from(10)) + letter * Fp::from(90);
+
+            GateBuilder::with_selector(q_step).equal("acc = rem * (10 + 90 * letter) + symbol", acc, rem * digits + symbol)
+        });
+
+        meta.lookup(|meta| {
+            let q_step = meta.query_selector(q_step);
+            let symbol = meta.query_advice(symbol, Rotation::cur());
+            let letter = meta.query_advice(letter, Rotation::cur());
+            vec![(q_step.clone() * symbol, alphabet[0].column), (q_step * letter, alphabet[1].column)]
+        });
+
+        IbanConfig {
+            div_rem,
+            arith,
+            outputs: PublicOutputs::configure(meta, instance),
+            symbol,
+            letter,
+            rem,
+            acc,
+            q_step,
+            alphabet,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let div_rem = DivRemChip::<Fp, DIV_BITS>::construct(config.div_rem.clone());
+        let arith = ArithChip::construct(config.arith.clone());
+        let mut outputs = PublicOutputs::construct(config.outputs.clone());
+
+        config.alphabet[0].load_with(&mut layouter, SYMBOLS as usize, |symbol| Fp::from(symbol as u64))?;
+        config.alphabet[1].load_with(&mut layouter, SYMBOLS as usize, |symbol| Fp::from((symbol >= 10) as u64))?;
+
+        let symbols = self
+            .symbols
+            .iter()
+            .enumerate()
+            .map(|(i, symbol)| arith.load_private(layouter.namespace(|| format!("load s_{}", i)), symbol.map(Fp::from)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let modulus = arith.load_constant(layouter.namespace(|| "97"), Fp::from(97))?;
+        let mut rem = arith.load_constant(layouter.namespace(|| "r_0"), Fp::zero())?;
+
+        // the country code and the check digits go last
+        for i in (4..LEN).chain(0..LEN.min(4)) {
+            let acc = layouter
+                .assign_region(
+                    || format!("symbol {}", i),
+                    |mut region| {
+                        config.q_step.enable(&mut region, 0)?;
+                        let symbol = symbols[i].copy_advice(|| "symbol", &mut region, config.symbol, 0)?;
+                        let letter = symbol.value().map(|symbol| symbol.get_lower_128() >= 10);
+                        region.assign_advice(|| "letter", config.letter, 0, || letter.map(|letter| Fp::from(letter as u64)))?;
+                        let rem = rem.copy_advice(|| "rem", &mut region, config.rem, 0)?;
+                        let digits = letter.map(|letter| Fp::from(if letter { 100 } else { 10 }));
+                        let acc = rem.value().copied() * digits + symbol.value().copied();
+                        region.assign_advice(|| "acc", config.acc, 0, || acc)
+                    },
+                )
+                .in_chip("IbanCircuit", "symbol")?;
+            let (_, next) = div_rem.div_rem(layouter.namespace(|| format!("acc_{} mod 97", i)), &acc, &modulus)?;
+            rem = next;
+        }
+        let one = arith.load_constant(layouter.namespace(|| "1"), Fp::one())?;
+        arith.assert_equal(layouter.namespace(|| "checksum"), &rem, &one)?;
+
+        outputs.expose("country_0", &symbols[0]);
+        outputs.expose("country_1", &symbols[1]);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl<const LEN: usize> ValidateWitness<Fp> for IbanCircuit<LEN> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let symbols = self
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let symbol = known(*symbol, "symbol")?;
+                check_below(Fp::from(symbol), SYMBOLS as u128, "symbol")?;
+                Ok(symbol)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        if LEN <= 4 || symbols[..2].iter().any(|symbol| *symbol < 10) {
+            return Err(InvalidWitness::InvalidParameter("no country code and account".to_string()));
+        }
+        if checksum(&symbols) != 1 {
+            return Err(InvalidWitness::InvalidParameter(format!("the checksum is {}, not 1", checksum(&symbols))));
+        }
+        check_instances(instances, &[Fp::from(symbols[0]), Fp::from(symbols[1])])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::dev::MockProver;
+
+    use super::{is_valid, IbanCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 11;
+
+    #[test]
+    fn test_iban() {
+        for (iban, country) in [("GB82 WEST 1234 5698 7654 32", "GB"), ("DE89 3704 0044 0532 0130 00", "DE")] {
+            assert!(is_valid(iban));
+            let circuit = IbanCircuit::<22>::new(iban);
+            let instances = vec![IbanCircuit::<22>::instances(country)];
+            assert_eq!(circuit.validate_witness(&instances), Ok(()));
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+        }
+
+        // letters in the account number too
+        let iban = "FR14 2004 1010 0505 0001 3M02 606";
+        assert!(is_valid(iban));
+        let circuit = IbanCircuit::<27>::new(iban);
+        MockProver::run(K, &circuit, vec![IbanCircuit::<27>::instances("FR")]).unwrap().assert_satisfied();
+    }
+
+    #[test]
+    fn test_iban_invalid() {
+        // a mistyped digit, and two swapped ones
+        for iban in ["GB82 WEST 1234 5698 7654 33", "GB82 WEST 1234 5698 7645 32"] {
+            assert!(!is_valid(iban));
+            let circuit = IbanCircuit::<22>::new(iban);
+            let instances = vec![IbanCircuit::<22>::instances("GB")];
+            assert!(circuit.validate_witness(&instances).is_err());
+            assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+        }
+
+        // a valid IBAN from another country than the one claimed
+        let circuit = IbanCircuit::<22>::new("DE89 3704 0044 0532 0130 00");
+        assert!(MockProver::run(K, &circuit, vec![IbanCircuit::<22>::instances("GB")]).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,305 @@
+//! Booking privacy: a private interval, e.g. a requested time slot, overlaps one of the
+//! intervals of a committed calendar, or none of them, without revealing either.
+//!
+//! The calendar is `N` half-open intervals `[s_k, e_k)` in order, committed to with
+//! `VectorCommitChip` as the list of their endpoints, which `MinMaxChip::assert_sorted`
+//! checks are sorted,
+//!
+//!     s_0 <= e_0 <= s_1 <= e_1 <= ... <= s_{N-1} <= e_{N-1}
+//!
+//! and so the intervals disjoint and ascending. That is what makes either claim about the
+//! booking `[s, e)`, itself committed to and nonempty, one or two comparisons at a private
+//! slot picked with `SelectChip`:
+//!
+//!     Overlaps   s < e_k   and   s_k < e,         for the interval k it overlaps
+//!     Disjoint   e_{g-1} <= s   and   e <= s_g,    for the gap g it fits in
+//!
+//! where the gaps run from before the first interval, `e_{-1} = 0`, to after the last,
+//! `s_N = 2^TIME_BITS`. Times are range checked to `TIME_BITS` bits and compared in one bit
+//! more, for `s + 1` and `s_N`.
+//!
+//! Which claim a circuit proves is part of its shape, like a bit width, so the verifier
+//! knows it from the verifying key. The public inputs are the two commitments.
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::{LoadInstructions, LoadPrivateChip, LoadPrivateConfig},
+    error::Halo2ExamplesError,
+    gadgets::{
+        arith::{ArithChip, ArithConfig},
+        commit::CommitChip,
+        min_max::{MinMaxChip, MinMaxConfig},
+        select::{SelectChip, SelectConfig},
+        vector_commit::{vector_commitment, VectorCommitChip, VectorCommitConfig},
+    },
+    public_inputs::{InstanceLayout, PublicOutputs, PublicOutputsConfig},
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The public inputs of `IntervalCircuit`.
+pub const INSTANCES: InstanceLayout = InstanceLayout(&[&["calendar", "booking"]]);
+
+/// The bits times are range checked to.
+pub const TIME_BITS: usize = 16;
+
+const COMPARE_BITS: usize = TIME_BITS + 1;
+
+/// What an `IntervalCircuit` proves about the booking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Claim {
+    /// It overlaps an interval of the calendar.
+    Overlaps,
+    /// It overlaps none of them.
+    Disjoint,
+}
+
+/// Whether the half-open intervals `a` and `b` share a point.
+pub fn overlaps(a: (u64, u64), b: (u64, u64)) -> bool {
+    a.0 < b.1 && b.0 < a.1
+}
+
+/// The slot a `claim` about `booking` is checked at: the first interval of `calendar` it
+/// overlaps, or the number of intervals ending before it starts, the gap it fits in.
+pub fn slot(calendar: &[(u64, u64)], booking: (u64, u64), claim: Claim) -> usize {
+    match claim {
+        Claim::Overlaps => calendar.iter().position(|interval| overlaps(*interval, booking)).unwrap_or_default(),
+        Claim::Disjoint => calendar.iter().filter(|(_, end)| *end <= booking.0).count(),
+    }
+}
+
+// A time, range checked.
+fn load_time(
+    load_chip: &LoadPrivateChip<Fp>,
+    min_max: &MinMaxChip<Fp, COMPARE_BITS>,
+    mut layouter: impl Layouter<Fp>,
+    time: Value<u64>,
+) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+    let cell = load_chip.load_private(layouter.namespace(|| "load"), time.map(Fp::from))?;
+    min_max.bits().unpack(layouter.namespace(|| "range check"), &cell, TIME_BITS)?;
+    Ok(cell)
+}
+
+/// Proves a committed booking overlaps, or does not, a committed calendar of `N` intervals.
+pub struct IntervalCircuit<const N: usize> {
+    pub calendar: [(Value<u64>, Value<u64>); N],
+    pub blinding: Value<Fp>,
+    pub booking: (Value<u64>, Value<u64>),
+    pub salt: Value<Fp>,
+    /// The interval, or the gap, `claim` is checked at.
+    pub slot: Value<u64>,
+    pub claim: Claim,
+}
+
+impl<const N: usize> IntervalCircuit<N> {
+    pub fn new(calendar: [(u64, u64); N], blinding: Fp, booking: (u64, u64), salt: Fp, claim: Claim) -> Self {
+        let slot = slot(&calendar, booking, claim);
+        Self {
+            calendar: calendar.map(|(start, end)| (Value::known(start), Value::known(end))),
+            blinding: Value::known(blinding),
+            booking: (Value::known(booking.0), Value::known(booking.1)),
+            salt: Value::known(salt),
+            slot: Value::known(slot as u64),
+            claim,
+        }
+    }
+
+    /// The commitment to `calendar`, to the list of its endpoints.
+    pub fn calendar_commitment(calendar: &[(u64, u64); N], blinding: Fp) -> Fp {
+        let endpoints: Vec<_> = calendar.iter().flat_map(|(start, end)| [Fp::from(*start), Fp::from(*end)]).collect();
+        vector_commitment::<CommitChip>(&endpoints, blinding)
+    }
+
+    /// The commitment to `booking`.
+    pub fn booking_commitment(booking: (u64, u64), salt: Fp) -> Fp {
+        vector_commitment::<CommitChip>(&[Fp::from(booking.0), Fp::from(booking.1)], salt)
+    }
+
+    /// The public inputs, as laid out by `INSTANCES`.
+    pub fn instances(calendar: &[(u64, u64); N], blinding: Fp, booking: (u64, u64), salt: Fp) -> Vec<Fp> {
+        vec![Self::calendar_commitment(calendar, blinding), Self::booking_commitment(booking, salt)]
+    }
+}
+
+impl<const N: usize> Circuit<Fp> for IntervalCircuit<N> {
+    type Config = (VectorCommitConfig, MinMaxConfig, SelectConfig, ArithConfig, LoadPrivateConfig, PublicOutputsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            calendar: [(Value::unknown(), Value::unknown()); N],
+            blinding: Value::unknown(),
+            booking: (Value::unknown(), Value::unknown()),
+            salt: Value::unknown(),
+            slot: Value::unknown(),
+            claim: self.claim,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let vector = VectorCommitChip::configure(meta);
+        let min_max = MinMaxChip::<Fp, COMPARE_BITS>::configure(meta, instance);
+        let select = SelectChip::configure(meta);
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let arith = ArithChip::configure(meta, advice);
+        let load = LoadPrivateChip::configure(meta, 1);
+        (vector, min_max, select, arith, load, PublicOutputs::configure(meta, instance))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (vector_config, min_max_config, select_config, arith_config, load_config, outputs_config) = config;
+        let vector = VectorCommitChip::construct(vector_config);
+        let min_max = MinMaxChip::<Fp, COMPARE_BITS>::construct(min_max_config);
+        let select = SelectChip::construct(select_config);
+        let arith = ArithChip::construct(arith_config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+
+        // 1. the committed calendar, in order
+        let mut endpoints = Vec::with_capacity(2 * N);
+        for (k, (start, end)) in self.calendar.iter().enumerate() {
+            endpoints.push(load_time(&load_chip, &min_max, layouter.namespace(|| format!("s_{}", k)), *start)?);
+            endpoints.push(load_time(&load_chip, &min_max, layouter.namespace(|| format!("e_{}", k)), *end)?);
+        }
+        let blinding = load_chip.load_private(layouter.namespace(|| "load blinding"), self.blinding)?;
+        let calendar = vector.commit(layouter.namespace(|| "commit calendar"), &endpoints, &blinding)?;
+        min_max.assert_sorted(layouter.namespace(|| "sorted endpoints"), &endpoints)?;
+        let starts: Vec<_> = endpoints.iter().step_by(2).cloned().collect();
+        let ends: Vec<_> = endpoints.iter().skip(1).step_by(2).cloned().collect();
+
+        // 2. the committed booking, s < e
+        let start = load_time(&load_chip, &min_max, layouter.namespace(|| "s"), self.booking.0)?;
+        let end = load_time(&load_chip, &min_max, layouter.namespace(|| "e"), self.booking.1)?;
+        let salt = load_chip.load_private(layouter.namespace(|| "load salt"), self.salt)?;
+        let booking = vector.commit(layouter.namespace(|| "commit booking"), &[start.clone(), end.clone()], &salt)?;
+        let start_plus_one = arith.add_constant(layouter.namespace(|| "s + 1"), &start, Fp::one())?;
+        min_max.assert_sorted(layouter.namespace(|| "s < e"), &[start_plus_one.clone(), end.clone()])?;
+
+        // 3. the claim, at the slot
+        let slot = load_chip.load_private(layouter.namespace(|| "load slot"), self.slot.map(Fp::from))?;
+        match self.claim {
+            Claim::Overlaps => {
+                let start_k = select.select(layouter.namespace(|| "s_k"), &starts, &slot)?;
+                let end_k = select.select(layouter.namespace(|| "e_k"), &ends, &slot)?;
+                let start_k_plus_one = arith.add_constant(layouter.namespace(|| "s_k + 1"), &start_k, Fp::one())?;
+                min_max.assert_sorted(layouter.namespace(|| "s < e_k"), &[start_plus_one, end_k])?;
+                min_max.assert_sorted(layouter.namespace(|| "s_k < e"), &[start_k_plus_one, end])?;
+            }
+            Claim::Disjoint => {
+                let mut before = vec![arith.load_constant(layouter.namespace(|| "e_{-1}"), Fp::zero())?];
+                before.extend(ends);
+                let mut after = starts;
+                after.push(arith.load_constant(layouter.namespace(|| "s_N"), Fp::from(1 << TIME_BITS))?);
+
+                let before = select.select(layouter.namespace(|| "e_{g-1}"), &before, &slot)?;
+                let after = select.select(layouter.namespace(|| "s_g"), &after, &slot)?;
+                min_max.assert_sorted(layouter.namespace(|| "e_{g-1} <= s"), &[before, start])?;
+                min_max.assert_sorted(layouter.namespace(|| "e <= s_g"), &[end, after])?;
+            }
+        }
+
+        outputs.expose("calendar", &calendar);
+        outputs.expose("booking", &booking);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl<const N: usize> ValidateWitness<Fp> for IntervalCircuit<N> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let time = |time: Value<u64>, name: &'static str| -> Result<u64, InvalidWitness> {
+            let time = known(time, name)?;
+            check_below(Fp::from(time), 1 << TIME_BITS, name)?;
+            Ok(time)
+        };
+        let calendar = self
+            .calendar
+            .iter()
+            .map(|(start, end)| Ok((time(*start, "interval start")?, time(*end, "interval end")?)))
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        let calendar: [(u64, u64); N] = calendar.try_into().expect("N intervals");
+        let booking = (time(self.booking.0, "booking start")?, time(self.booking.1, "booking end")?);
+        let blinding = known(self.blinding, "blinding")?;
+        let salt = known(self.salt, "salt")?;
+
+        let endpoints: Vec<_> = calendar.iter().flat_map(|(start, end)| [*start, *end]).collect();
+        if endpoints.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(InvalidWitness::InvalidParameter("the calendar is not sorted".to_string()));
+        }
+        if booking.0 >= booking.1 {
+            return Err(InvalidWitness::InvalidParameter("the booking is empty".to_string()));
+        }
+        let overlapping = calendar.iter().any(|interval| overlaps(*interval, booking));
+        if overlapping != (self.claim == Claim::Overlaps) {
+            return Err(InvalidWitness::InvalidParameter(format!("the claim {:?} is false", self.claim)));
+        }
+        if known(self.slot, "slot")? != slot(&calendar, booking, self.claim) as u64 {
+            return Err(InvalidWitness::InvalidParameter("the slot does not witness the claim".to_string()));
+        }
+        check_instances(instances, &Self::instances(&calendar, blinding, booking, salt))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use rand::rngs::OsRng;
+
+    use super::{Claim, IntervalCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 11;
+
+    // meetings at 9:00-10:00, 10:00-11:30 and 14:00-15:00, in minutes
+    const CALENDAR: [(u64, u64); 3] = [(540, 600), (600, 690), (840, 900)];
+
+    fn run(booking: (u64, u64), claim: Claim) -> bool {
+        let (blinding, salt) = (Fp::random(OsRng), Fp::random(OsRng));
+        let circuit = IntervalCircuit::new(CALENDAR, blinding, booking, salt, claim);
+        let instances = vec![IntervalCircuit::instances(&CALENDAR, blinding, booking, salt)];
+        let valid = circuit.validate_witness(&instances).is_ok();
+        let verified = MockProver::run(K, &circuit, instances).unwrap().verify().is_ok();
+        assert_eq!(valid, verified, "{:?} claimed {:?}", booking, claim);
+        verified
+    }
+
+    #[test]
+    fn test_interval_disjoint() {
+        // before, between and after the meetings, right up to their ends
+        for booking in [(0, 540), (690, 840), (700, 720), (900, 1440), (65000, 65535)] {
+            assert!(run(booking, Claim::Disjoint), "{:?}", booking);
+            assert!(!run(booking, Claim::Overlaps), "{:?}", booking);
+        }
+    }
+
+    #[test]
+    fn test_interval_overlaps() {
+        for booking in [(530, 541), (599, 601), (600, 690), (500, 1000), (899, 960)] {
+            assert!(run(booking, Claim::Overlaps), "{:?}", booking);
+            assert!(!run(booking, Claim::Disjoint), "{:?}", booking);
+        }
+    }
+
+    #[test]
+    fn test_interval_bad_witness() {
+        let (blinding, salt) = (Fp::random(OsRng), Fp::random(OsRng));
+        let booking = (700, 720);
+        let instances = vec![IntervalCircuit::instances(&CALENDAR, blinding, booking, salt)];
+
+        // a gap the booking does not fit in
+        let mut circuit = IntervalCircuit::new(CALENDAR, blinding, booking, salt, Claim::Disjoint);
+        circuit.slot = circuit.slot.map(|slot| slot + 1);
+        assert!(MockProver::run(K, &circuit, instances.clone()).unwrap().verify().is_err());
+
+        // an empty booking fits anywhere, so is refused
+        assert!(!run((700, 700), Claim::Disjoint));
+
+        // a calendar out of order, hiding the second meeting before the first
+        let calendar = [(600, 690), (540, 600), (840, 900)];
+        let circuit = IntervalCircuit::new(calendar, blinding, booking, salt, Claim::Disjoint);
+        let instances = vec![IntervalCircuit::instances(&calendar, blinding, booking, salt)];
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! A KYC check on a customer's country of residence: it is on the service's allowlist, and
+//! not on a sanctions denylist, without saying which country it is.
+//!
+//! The country is an ISO 3166-1 numeric code, committed to as `hash2(country, salt)` by
+//! whoever verified the customer's documents, so the proof is about that country and not
+//! any the customer picks. Two set proofs, one of each kind, then follow:
+//!
+//! 1. membership: the allowlist is a fixed table of `(1, code)` rows and a `(0, 0)` row for
+//!    the rows the lookup is off, part of the circuit like the ledger's chart of accounts;
+//!
+//!        country  |  q_allowed          (q_allowed, q_allowed * country) in allowlist
+//!       ----------------------
+//!        country  |      1
+//!
+//! 2. non-membership: the denylist is a `SparseMerkleTree` keyed by country, its sanctioned
+//!    countries set to 1, whose root is public so it can be updated without changing the
+//!    circuit. `SmtChip::non_membership` hashes an empty leaf up the country's path, which
+//!    ends at that root only if the country's leaf is unset. Unpacking the key also keeps the
+//!    country below `2^DENYLIST_DEPTH`.
+//!
+//! The public inputs are the commitment and the denylist root.
+
+use std::iter;
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::ResultExt,
+    gadgets::{
+        commit::CommitChip,
+        hasher::CircuitHasher,
+        merkle::root_from_path,
+        smt::{SmtChip, SmtConfig, SparseMerkleTree},
+    },
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    table::FixedTableConfig,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The depth of the denylist tree, whose keys cover the three-digit country codes.
+pub const DENYLIST_DEPTH: usize = 10;
+
+/// The commitment to `country` under `salt`.
+pub fn commitment(country: u64, salt: Fp) -> Fp {
+    CommitChip::hash2_native(Fp::from(country), salt)
+}
+
+/// The denylist tree with `countries` set.
+pub fn denylist(countries: &[u64]) -> SparseMerkleTree {
+    let mut tree = SparseMerkleTree::new(DENYLIST_DEPTH);
+    for country in countries {
+        tree.set(*country, Fp::one());
+    }
+    tree
+}
+
+#[derive(Debug, Clone)]
+pub struct KycConfig {
+    smt: SmtConfig,
+    load: LoadPrivateConfig,
+    outputs: PublicOutputsConfig,
+    country: Column<Advice>,
+    q_allowed: Selector,
+    allowlist: [FixedTableConfig<Fp>; 2],
+}
+
+/// Proves a committed country is on `allowlist` and absent from a public denylist tree.
+pub struct KycCircuit {
+    /// Allowed country codes, fixed in the circuit.
+    pub allowlist: Vec<u64>,
+    pub country: Value<u64>,
+    pub salt: Value<Fp>,
+    /// The siblings of the country's leaf in the denylist tree, bottom up.
+    pub siblings: Vec<Value<Fp>>,
+}
+
+impl KycCircuit {
+    pub fn new(allowlist: &[u64], denylist: &SparseMerkleTree, country: u64, salt: Fp) -> Self {
+        Self {
+            allowlist: allowlist.to_vec(),
+            country: Value::known(country),
+            salt: Value::known(salt),
+            siblings: denylist.path(country).into_iter().map(Value::known).collect(),
+        }
+    }
+
+    /// The public inputs: the commitment to `country` and the root of `denylist`.
+    pub fn instances(country: u64, salt: Fp, denylist: &SparseMerkleTree) -> Vec<Fp> {
+        vec![commitment(country, salt), denylist.root()]
+    }
+}
+
+impl Circuit<Fp> for KycCircuit {
+    type Config = KycConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            allowlist: self.allowlist.clone(),
+            country: Value::unknown(),
+            salt: Value::unknown(),
+            siblings: vec![Value::unknown(); DENYLIST_DEPTH],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let smt = SmtChip::<DENYLIST_DEPTH>::configure(meta, instance);
+        let country = meta.advice_column();
+        let q_allowed = meta.complex_selector();
+        let allowlist = ["allowlist tag", "allowlist"].map(|name| FixedTableConfig::configure(meta, name));
+        meta.enable_equality(country);
+
+        meta.lookup(|meta| {
+            let q_allowed = meta.query_selector(q_allowed);
+            let country = meta.query_advice(country, Rotation::cur());
+            vec![(q_allowed.clone(), allowlist[0].column), (q_allowed * country, allowlist[1].column)]
+        });
+
+        KycConfig {
+            smt,
+            load: LoadPrivateChip::configure(meta, 2),
+            outputs: PublicOutputs::configure(meta, instance),
+            country,
+            q_allowed,
+            allowlist,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let smt = SmtChip::<DENYLIST_DEPTH>::construct(config.smt.clone());
+        let load_chip = LoadPrivateChip::construct(config.load.clone());
+        let mut outputs = PublicOutputs::construct(config.outputs.clone());
+
+        let tags: Vec<_> = iter::once(Fp::zero()).chain(self.allowlist.iter().map(|_| Fp::one())).collect();
+        let codes: Vec<_> = iter::once(Fp::zero()).chain(self.allowlist.iter().copied().map(Fp::from)).collect();
+        config.allowlist[0].load(&mut layouter, &tags)?;
+        config.allowlist[1].load(&mut layouter, &codes)?;
+
+        let [country, salt]: [_; 2] = load_chip
+            .load_privates(layouter.namespace(|| "load country"), &[self.country.map(Fp::from), self.salt])?
+            .try_into()
+            .expect("two cells loaded");
+        let siblings = load_chip.load_privates(layouter.namespace(|| "load siblings"), &self.siblings)?;
+
+        // 1. the country is the committed one
+        let commitment = smt.merkle().hasher().hash2(layouter.namespace(|| "commitment"), &country, &salt)?;
+
+        // 2. it is allowed
+        layouter
+            .assign_region(
+                || "allowlist",
+                |mut region| {
+                    config.q_allowed.enable(&mut region, 0)?;
+                    country.copy_advice(|| "country", &mut region, config.country, 0)?;
+                    Ok(())
+                },
+            )
+            .in_chip("KycCircuit", "allowlist")?;
+
+        // 3. it is not denied
+        let denylist = smt.non_membership(layouter.namespace(|| "denylist"), &country, &siblings)?;
+
+        outputs.expose("commitment", &commitment);
+        outputs.expose("denylist", &denylist);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for KycCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let country = known(self.country, "country")?;
+        let salt = known(self.salt, "salt")?;
+        let siblings = self.siblings.iter().map(|sibling| known(*sibling, "sibling")).collect::<Result<Vec<_>, _>>()?;
+        if siblings.len() != DENYLIST_DEPTH {
+            return Err(InvalidWitness::InvalidParameter(format!("{} siblings for a tree of depth {}", siblings.len(), DENYLIST_DEPTH)));
+        }
+        check_below(Fp::from(country), 1 << DENYLIST_DEPTH, "country")?;
+        if !self.allowlist.contains(&country) {
+            return Err(InvalidWitness::InvalidParameter(format!("country {} is not on the allowlist", country)));
+        }
+
+        // a denied country's path ends elsewhere from an empty leaf
+        let root = root_from_path::<CommitChip>(Fp::zero(), country, &siblings);
+        check_instances(instances, &[commitment(country, salt), root])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use rand::rngs::OsRng;
+
+    use super::{denylist, KycCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 11;
+
+    // Austria, Belarus, France, Germany, Italy, Switzerland
+    const ALLOWLIST: [u64; 6] = [40, 112, 250, 276, 380, 756];
+    // Belarus, Iran, North Korea, Syria
+    const DENYLIST: [u64; 4] = [112, 364, 408, 760];
+
+    #[test]
+    fn test_kyc() {
+        let denylist = denylist(&DENYLIST);
+        let salt = Fp::random(OsRng);
+        for country in [40, 276, 756] {
+            let circuit = KycCircuit::new(&ALLOWLIST, &denylist, country, salt);
+            let instances = vec![KycCircuit::instances(country, salt, &denylist)];
+            assert_eq!(circuit.validate_witness(&instances), Ok(()));
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_kyc_denied() {
+        // allowed, but since sanctioned
+        let denylist = denylist(&DENYLIST);
+        let salt = Fp::random(OsRng);
+        let circuit = KycCircuit::new(&ALLOWLIST, &denylist, 112, salt);
+        let instances = vec![KycCircuit::instances(112, salt, &denylist)];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+
+        // the same proof against the list before the sanction passes
+        let before = super::denylist(&DENYLIST[1..]);
+        let circuit = KycCircuit::new(&ALLOWLIST, &before, 112, salt);
+        let instances = vec![KycCircuit::instances(112, salt, &before)];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+    }
+
+    #[test]
+    fn test_kyc_not_allowed() {
+        let denylist = denylist(&DENYLIST);
+        let salt = Fp::random(OsRng);
+        // neither allowed nor denied
+        let circuit = KycCircuit::new(&ALLOWLIST, &denylist, 840, salt);
+        let instances = vec![KycCircuit::instances(840, salt, &denylist)];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+
+        // an allowed country, but not the committed one
+        let circuit = KycCircuit::new(&ALLOWLIST, &denylist, 276, salt);
+        let instances = vec![KycCircuit::instances(840, salt, &denylist)];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,357 @@
+//! Double-entry bookkeeping: a private batch of journal postings balances, every account's
+//! debits matching its credits and so the batch's total debits its total credits, and only
+//! posts to accounts of a fixed chart of accounts.
+//!
+//! The postings are laid out grouped by account, in one region with two accumulators: the
+//! running balance of the current account, which restarts at every new account, and the
+//! running totals of the batch.
+//!
+//!      account  | debit | credit |  inv  |   bal   |   debits   |   credits   | q_post
+//!     ------------------------------------------------------------------------------------
+//!         0     |       |        |       |    0    |     0      |      0      |
+//!        a_0    |  d_0  |  c_0   | inv_0 |  bal_1  |  debits_1  |  credits_1  |   1
+//!        ...    |  ...  |  ...   |  ...  |   ...   |    ...     |     ...     |   1
+//!      a_{n-1}  |d_{n-1}|c_{n-1} |  ...  |  bal_n  |  debits_n  |  credits_n  |   1
+//!
+//!     same  = (a_i == a_{i-1})                               IsZeroChip on a_i - a_{i-1}
+//!     bal   = same * bal_prev + d_i - c_i,   (1 - same) * bal_prev = 0
+//!     debits = debits_prev + d_i,   credits = credits_prev + c_i
+//!
+//! So every run of postings to one account closes at 0 before the next begins, and the last
+//! one at the end, `bal_n = 0`. An account split over several runs still nets to 0, each of
+//! them doing; grouping is how the prover makes each run a whole account, not something
+//! the circuit has to check.
+//!
+//! Two lookups check the postings themselves: the account against the chart of accounts, a
+//! table of `(1, account)` rows and a `(0, 0)` row for the rows the lookup is off, and both
+//! amounts against a range table of `AMOUNT_BITS` bits. The chart is a fixed table, part of
+//! the circuit like a bit width, and the public input is the total posted, `debits_n`,
+//! constrained equal to `credits_n`.
+
+use std::collections::BTreeMap;
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::ResultExt,
+    gadgets::is_zero::{IsZeroChip, IsZeroConfig},
+    gate::GateBuilder,
+    table::load_range_table,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The bits amounts are range checked to, with a table of `2^AMOUNT_BITS` rows.
+pub const AMOUNT_BITS: usize = 12;
+
+/// One line of a journal entry: an amount debited or credited to an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Posting {
+    pub account: u64,
+    pub debit: u64,
+    pub credit: u64,
+}
+
+impl Posting {
+    pub fn debit(account: u64, amount: u64) -> Self {
+        Self {
+            account,
+            debit: amount,
+            credit: 0,
+        }
+    }
+
+    pub fn credit(account: u64, amount: u64) -> Self {
+        Self {
+            account,
+            debit: 0,
+            credit: amount,
+        }
+    }
+}
+
+/// The debits minus the credits of every account `postings` post to.
+pub fn balances(postings: &[Posting]) -> BTreeMap<u64, i128> {
+    let mut balances = BTreeMap::new();
+    for posting in postings {
+        *balances.entry(posting.account).or_default() += posting.debit as i128 - posting.credit as i128;
+    }
+    balances
+}
+
+#[derive(Debug, Clone)]
+pub struct LedgerConfig {
+    account: Column<Advice>,
+    debit: Column<Advice>,
+    credit: Column<Advice>,
+    bal: Column<Advice>,
+    debits: Column<Advice>,
+    credits: Column<Advice>,
+    q_post: Selector,
+    same_account: IsZeroConfig<Fp>,
+    chart: [TableColumn; 2],
+    range: TableColumn,
+    instance: Column<Instance>,
+    constants: ConstantsConfig,
+}
+
+/// Proves a private batch of postings to the accounts of `chart` balances, and exposes its
+/// total.
+pub struct LedgerCircuit {
+    pub postings: Vec<Value<Posting>>,
+    pub chart: Vec<u64>,
+}
+
+impl LedgerCircuit {
+    /// The circuit for `postings`, grouped by account.
+    pub fn new(postings: &[Posting], chart: &[u64]) -> Self {
+        let mut postings = postings.to_vec();
+        postings.sort_by_key(|posting| posting.account);
+        Self {
+            postings: postings.into_iter().map(Value::known).collect(),
+            chart: chart.to_vec(),
+        }
+    }
+
+    /// The public input: the total debited, and credited.
+    pub fn instances(postings: &[Posting]) -> Vec<Fp> {
+        vec![Fp::from(postings.iter().map(|posting| posting.debit).sum::<u64>())]
+    }
+}
+
+impl Circuit<Fp> for LedgerCircuit {
+    type Config = LedgerConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            postings: vec![Value::unknown(); self.postings.len()],
+            chart: self.chart.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let [account, debit, credit, inv, bal, debits, credits] = [(); 7].map(|_| meta.advice_column());
+        let q_post = meta.complex_selector();
+        let chart = [(); 2].map(|_| meta.lookup_table_column());
+        let range = meta.lookup_table_column();
+
+        // the first row of the region is all copies of a loaded zero
+        let constants = ConstantsChip::configure_with(meta, bal);
+        for column in [account, bal, debits, credits] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        let same_account = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_post),
+            |meta| meta.query_advice(account, Rotation::cur()) - meta.query_advice(account, Rotation::prev()),
+            inv,
+        );
+
+        let same = same_account.is_zero_expr.clone();
+        meta.create_gate("post", |meta| {
+            let q_post = meta.query_selector(q_post);
+            let debit = meta.query_advice(debit, Rotation::cur());
+            let credit = meta.query_advice(credit, Rotation::cur());
+            let [bal_prev, debits_prev, credits_prev] = [bal, debits, credits].map(|column| meta.query_advice(column, Rotation::prev()));
+            let [bal, debits, credits] = [bal, debits, credits].map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(Fp::one());
+
+            GateBuilder::with_selector(q_post)
+                .equal("bal = same * bal_prev + debit - credit", bal, same.clone() * bal_prev.clone() + debit.clone() - credit.clone())
+                .constrain("a new account only after a balanced one", (one - same.clone()) * bal_prev)
+                .equal("debits = debits_prev + debit", debits, debits_prev + debit)
+                .equal("credits = credits_prev + credit", credits, credits_prev + credit)
+        });
+
+        meta.lookup(|meta| {
+            let q_post = meta.query_selector(q_post);
+            let account = meta.query_advice(account, Rotation::cur());
+            vec![(q_post.clone(), chart[0]), (q_post * account, chart[1])]
+        });
+        for amount in [debit, credit] {
+            meta.lookup(|meta| {
+                let q_post = meta.query_selector(q_post);
+                vec![(q_post * meta.query_advice(amount, Rotation::cur()), range)]
+            });
+        }
+
+        LedgerConfig {
+            account,
+            debit,
+            credit,
+            bal,
+            debits,
+            credits,
+            q_post,
+            same_account,
+            chart,
+            range,
+            instance,
+            constants,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let same_account = IsZeroChip::construct(config.same_account.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+
+        layouter.assign_table(
+            || "load chart of accounts",
+            |mut table| {
+                for (offset, (enabled, account)) in [(0, 0)].into_iter().chain(self.chart.iter().map(|account| (1, *account))).enumerate() {
+                    table.assign_cell(|| "enabled", config.chart[0], offset, || Value::known(Fp::from(enabled)))?;
+                    table.assign_cell(|| "account", config.chart[1], offset, || Value::known(Fp::from(account)))?;
+                }
+                Ok(())
+            },
+        )?;
+        load_range_table(&mut layouter, config.range, 1 << AMOUNT_BITS)?;
+
+        let zero = constants.load_constant(layouter.namespace(|| "zero"), "zero", Fp::zero())?;
+        let debits = layouter
+            .assign_region(
+                || "journal",
+                |mut region| {
+                    let mut account = zero.copy_advice(|| "account_0", &mut region, config.account, 0)?;
+                    let mut bal = zero.copy_advice(|| "bal_0", &mut region, config.bal, 0)?;
+                    let mut debits = zero.copy_advice(|| "debits_0", &mut region, config.debits, 0)?;
+                    let mut credits = zero.copy_advice(|| "credits_0", &mut region, config.credits, 0)?;
+
+                    for (i, posting) in self.postings.iter().enumerate() {
+                        let offset = i + 1;
+                        config.q_post.enable(&mut region, offset)?;
+                        let prev = account.value().copied();
+                        let next = posting.map(|posting| Fp::from(posting.account));
+                        account = region.assign_advice(|| format!("account_{}", i), config.account, offset, || next)?;
+                        same_account.assign(&mut region, offset, next - prev)?;
+
+                        let debit = posting.map(|posting| Fp::from(posting.debit));
+                        let credit = posting.map(|posting| Fp::from(posting.credit));
+                        region.assign_advice(|| format!("debit_{}", i), config.debit, offset, || debit)?;
+                        region.assign_advice(|| format!("credit_{}", i), config.credit, offset, || credit)?;
+
+                        let same = (next - prev).map(|diff| diff == Fp::zero());
+                        let next_bal = same.zip(bal.value().copied()).map(|(same, bal)| if same { bal } else { Fp::zero() }) + debit - credit;
+                        bal = region.assign_advice(|| format!("bal_{}", offset), config.bal, offset, || next_bal)?;
+                        let next = debits.value().copied() + debit;
+                        debits = region.assign_advice(|| format!("debits_{}", offset), config.debits, offset, || next)?;
+                        let next = credits.value().copied() + credit;
+                        credits = region.assign_advice(|| format!("credits_{}", offset), config.credits, offset, || next)?;
+                    }
+
+                    region.constrain_equal(bal.cell(), zero.cell())?;
+                    region.constrain_equal(debits.cell(), credits.cell())?;
+                    Ok(debits)
+                },
+            )
+            .in_chip("LedgerCircuit", "journal")?;
+
+        layouter.constrain_instance(debits.cell(), config.instance, 0)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for LedgerCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let postings = self
+            .postings
+            .iter()
+            .map(|posting| {
+                let posting = known(*posting, "posting")?;
+                check_below(Fp::from(posting.debit), 1 << AMOUNT_BITS, "debit")?;
+                check_below(Fp::from(posting.credit), 1 << AMOUNT_BITS, "credit")?;
+                if !self.chart.contains(&posting.account) {
+                    return Err(InvalidWitness::InvalidParameter(format!("account {} is not in the chart", posting.account)));
+                }
+                Ok(posting)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        if let Some((account, balance)) = balances(&postings).into_iter().find(|(_, balance)| *balance != 0) {
+            return Err(InvalidWitness::InvalidParameter(format!("account {} is off by {}", account, balance)));
+        }
+        check_instances(instances, &Self::instances(&postings))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::{LedgerCircuit, Posting};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 13;
+
+    const CASH: u64 = 1000;
+    const RECEIVABLES: u64 = 1200;
+    const REVENUE: u64 = 4000;
+    const SUSPENSE: u64 = 9999;
+    const CHART: [u64; 4] = [CASH, RECEIVABLES, REVENUE, SUSPENSE];
+
+    // a sale on credit, its payment, and the revenue closed out
+    fn postings() -> Vec<Posting> {
+        vec![
+            Posting::debit(RECEIVABLES, 250),
+            Posting::credit(REVENUE, 250),
+            Posting::debit(CASH, 250),
+            Posting::credit(RECEIVABLES, 250),
+            Posting::debit(REVENUE, 250),
+            Posting::credit(CASH, 100),
+            Posting::credit(CASH, 150),
+        ]
+    }
+
+    fn run(postings: &[Posting]) -> bool {
+        let circuit = LedgerCircuit::new(postings, &CHART);
+        let instances = vec![LedgerCircuit::instances(postings)];
+        let valid = circuit.validate_witness(&instances).is_ok();
+        let verified = MockProver::run(K, &circuit, instances).unwrap().verify().is_ok();
+        assert_eq!(valid, verified, "{:?}", postings);
+        verified
+    }
+
+    #[test]
+    fn test_ledger() {
+        assert!(run(&postings()));
+        assert!(run(&[]));
+
+        // the same postings in another order, grouped by the circuit
+        let mut postings = postings();
+        postings.reverse();
+        assert!(run(&postings));
+    }
+
+    #[test]
+    fn test_ledger_unbalanced() {
+        // the batch balances in total but not per account
+        let mut postings = postings();
+        postings[5] = Posting::credit(SUSPENSE, 100);
+        assert!(!run(&postings));
+
+        // nor in total
+        let mut postings = self::postings();
+        postings[0].debit += 1;
+        assert!(!run(&postings));
+
+        // an account outside the chart, and an amount out of range
+        assert!(!run(&[Posting::debit(1234, 5), Posting::credit(1234, 5)]));
+        assert!(!run(&[Posting::debit(CASH, 1 << 12), Posting::credit(CASH, 1 << 12)]));
+    }
+
+    #[test]
+    fn test_ledger_ungrouped() {
+        // ungrouped postings that net to 0 per account still fail: every run of one account
+        // must close, and CASH's first does not
+        let postings = [Posting::debit(CASH, 5), Posting::credit(REVENUE, 5), Posting::credit(CASH, 5), Posting::debit(REVENUE, 5)];
+        let circuit = LedgerCircuit {
+            postings: postings.iter().copied().map(Value::known).collect(),
+            chart: CHART.to_vec(),
+        };
+        let instances = vec![vec![Fp::from(10)]];
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+}
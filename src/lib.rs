@@ -1,4 +1,87 @@
-mod fibonaci;
-mod range_check;
-mod gadgets;
-mod decompose;
\ No newline at end of file
+#[cfg(feature = "fibonacci")]
+pub mod fibonaci;
+#[cfg(feature = "range-check")]
+pub mod range_check;
+#[cfg(feature = "gadgets")]
+pub mod gadgets;
+#[cfg(feature = "decompose")]
+pub mod decompose;
+#[cfg(all(feature = "fibonacci", feature = "range-check", feature = "decompose"))]
+pub mod witness;
+pub mod params;
+pub mod chip;
+pub mod region;
+pub mod gate;
+pub mod reference;
+pub mod error;
+pub mod validate;
+pub mod public_inputs;
+pub mod dev;
+pub mod prover;
+pub mod constants;
+pub mod table;
+pub mod floor_planner;
+#[cfg(feature = "auction")]
+pub mod auction;
+#[cfg(feature = "median")]
+pub mod median;
+#[cfg(feature = "aes")]
+pub mod aes;
+#[cfg(feature = "blake2s")]
+pub mod blake2s;
+#[cfg(feature = "hmac")]
+pub mod hmac;
+#[cfg(feature = "hash-chain")]
+pub mod hash_chain;
+#[cfg(feature = "otp")]
+pub mod otp;
+#[cfg(feature = "poly-commit")]
+pub mod poly_commit;
+#[cfg(feature = "shamir")]
+pub mod shamir;
+#[cfg(feature = "hamming")]
+pub mod hamming;
+#[cfg(feature = "edit-distance")]
+pub mod edit_distance;
+#[cfg(feature = "binary-search")]
+pub mod binary_search;
+#[cfg(feature = "interval")]
+pub mod interval;
+#[cfg(feature = "ledger")]
+pub mod ledger;
+#[cfg(feature = "reserves")]
+pub mod reserves;
+#[cfg(feature = "payroll")]
+pub mod payroll;
+#[cfg(feature = "credit-score")]
+pub mod credit_score;
+#[cfg(feature = "kyc")]
+pub mod kyc;
+#[cfg(feature = "iban")]
+pub mod iban;
+#[cfg(feature = "ean")]
+pub mod ean;
+#[cfg(feature = "sudoku")]
+pub mod sudoku;
+#[cfg(feature = "nonogram")]
+pub mod nonogram;
+#[cfg(feature = "chess")]
+pub mod chess;
+#[cfg(feature = "tic-tac-toe")]
+pub mod tic_tac_toe;
+#[cfg(feature = "dice")]
+pub mod dice;
+#[cfg(feature = "lottery")]
+pub mod lottery;
+#[cfg(feature = "vm")]
+pub mod vm;
+#[cfg(all(feature = "hash", feature = "fibonacci"))]
+pub mod committed;
+#[cfg(feature = "hash")]
+pub mod sinsemilla;
+#[cfg(feature = "ecc")]
+pub mod eddsa;
+#[cfg(feature = "ecc")]
+pub mod credential;
+#[cfg(feature = "merkle")]
+pub mod airdrop;
@@ -0,0 +1,276 @@
+//! A lottery claim: the winner proves they hold a ticket that was sold and that it matches
+//! the draw, without revealing which ticket is theirs.
+//!
+//! Every buyer holds a secret `k`, and the ticket they buy is a hash of it. The tickets sold
+//! are the leaves of a Merkle tree whose root is published when sales close. The draw is a
+//! public field element fixed after that, e.g. a hash of a randomness beacon, and a ticket
+//! wins when its `WIN_BITS` low bits are those of the draw:
+//!
+//!     ticket = hash2(k, 0),   nullifier = hash2(k, 1),   ticket = draw  (mod 2^WIN_BITS)
+//!
+//! so each ticket wins with odds of `2^-WIN_BITS`, and some draws have several winners or
+//! none. The public inputs are the root, the draw, the nullifier and the recipient of the
+//! prize, as in the airdrop claim: the nullifier stops a ticket from being paid twice, and
+//! the recipient is bound to the proof by the transcript.
+//!
+//! The low bits come from `BitsChip` unpacking the ticket and the draw whole, into
+//! `Fp::CAPACITY` bits each. Any shorter decomposition would leave the prover a second one
+//! that wraps around the field, with other low bits. That rules out values above `2^254`,
+//! a `2^-125` fraction of hashes.
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::ResultExt,
+    gadgets::{
+        bits::{BitsChip, BitsConfig},
+        commit::CommitChip,
+        hasher::CircuitHasher,
+        merkle::{root_from_path, MerkleChip, MerkleConfig},
+    },
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The second input of the hash giving the ticket.
+const TICKET_TAG: u64 = 0;
+
+/// The second input of the hash giving the nullifier.
+const NULLIFIER_TAG: u64 = 1;
+
+/// The ticket bought with `secret`.
+pub fn ticket<H: CircuitHasher<Fp>>(secret: Fp) -> Fp {
+    H::hash2_native(secret, Fp::from(TICKET_TAG))
+}
+
+/// The nullifier of the claim of `secret`.
+pub fn nullifier<H: CircuitHasher<Fp>>(secret: Fp) -> Fp {
+    H::hash2_native(secret, Fp::from(NULLIFIER_TAG))
+}
+
+/// Whether `ticket` wins `draw`, its `win_bits` low bits being those of the draw.
+pub fn wins(ticket: Fp, draw: Fp, win_bits: usize) -> bool {
+    let mask = (1u128 << win_bits) - 1;
+    ticket.get_lower_128() & mask == draw.get_lower_128() & mask
+}
+
+/// Claims the prize of the ticket at `index` of a tree of depth `DEPTH` for `recipient`,
+/// for a draw matched on `WIN_BITS` bits, hashing with `H`.
+pub struct LotteryCircuit<const DEPTH: usize, const WIN_BITS: usize, H: CircuitHasher<Fp> = CommitChip> {
+    pub secret: Value<Fp>,
+    pub index: Value<u64>,
+    pub siblings: [Value<Fp>; DEPTH],
+    pub draw: Value<Fp>,
+    pub recipient: Value<Fp>,
+    pub hasher: PhantomData<H>,
+}
+
+impl<const DEPTH: usize, const WIN_BITS: usize, H: CircuitHasher<Fp>> LotteryCircuit<DEPTH, WIN_BITS, H> {
+    /// The root, the draw, the nullifier and the recipient, in instance order.
+    pub fn outputs(root: Fp, draw: Fp, secret: Fp, recipient: Fp) -> [Fp; 4] {
+        [root, draw, nullifier::<H>(secret), recipient]
+    }
+}
+
+impl<const DEPTH: usize, const WIN_BITS: usize, H: CircuitHasher<Fp>> Circuit<Fp> for LotteryCircuit<DEPTH, WIN_BITS, H> {
+    type Config = (MerkleConfig<H>, BitsConfig, LoadPrivateConfig, PublicOutputsConfig, ConstantsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            secret: Value::unknown(),
+            index: Value::unknown(),
+            siblings: [Value::unknown(); DEPTH],
+            draw: Value::unknown(),
+            recipient: Value::unknown(),
+            hasher: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let merkle = MerkleChip::configure(meta);
+        let bits = BitsChip::configure(meta, instance);
+        (
+            merkle,
+            bits,
+            LoadPrivateChip::configure(meta, 1),
+            PublicOutputs::configure(meta, instance),
+            ConstantsChip::configure(meta),
+        )
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (config, bits_config, load_config, outputs_config, constants_config) = config;
+        let chip = MerkleChip::construct(config);
+        let bits_chip = BitsChip::construct(bits_config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+        let constants = ConstantsChip::construct(constants_config);
+
+        let values = [self.secret, self.draw, self.recipient];
+        let [secret, draw, recipient]: [_; 3] = load_chip
+            .load_privates(layouter.namespace(|| "load claim"), &values)?
+            .try_into()
+            .expect("three cells loaded");
+        let ticket_tag = constants.load_constant(layouter.namespace(|| "ticket tag"), "ticket tag", Fp::from(TICKET_TAG))?;
+        let nullifier_tag =
+            constants.load_constant(layouter.namespace(|| "nullifier tag"), "nullifier tag", Fp::from(NULLIFIER_TAG))?;
+
+        // 1. The ticket was sold.
+        let ticket = chip.hasher().hash2(layouter.namespace(|| "ticket"), &secret, &ticket_tag)?;
+        let index_bits: Vec<_> = (0..DEPTH)
+            .map(|i| self.index.map(|index| Fp::from((index >> i) & 1)))
+            .collect();
+        let index_bits = load_chip.load_privates(layouter.namespace(|| "load index bits"), &index_bits)?;
+        let siblings = load_chip.load_privates(layouter.namespace(|| "load siblings"), &self.siblings)?;
+        let root = chip.compute_root(layouter.namespace(|| "root"), &ticket, &index_bits, &siblings)?;
+
+        // 2. It wins the draw.
+        let ticket_bits = bits_chip.unpack(layouter.namespace(|| "ticket bits"), &ticket, Fp::CAPACITY as usize)?;
+        let draw_bits = bits_chip.unpack(layouter.namespace(|| "draw bits"), &draw, Fp::CAPACITY as usize)?;
+        layouter
+            .assign_region(
+                || "winning bits",
+                |mut region| {
+                    for (ticket_bit, draw_bit) in ticket_bits.iter().zip(&draw_bits).take(WIN_BITS) {
+                        region.constrain_equal(ticket_bit.cell(), draw_bit.cell())?;
+                    }
+                    Ok(())
+                },
+            )
+            .in_chip("LotteryCircuit", "winning bits")?;
+
+        // 3. Its nullifier.
+        let nullifier = chip.hasher().hash2(layouter.namespace(|| "nullifier"), &secret, &nullifier_tag)?;
+
+        outputs.expose("root", &root);
+        outputs.expose("draw", &draw);
+        outputs.expose("nullifier", &nullifier);
+        outputs.expose("recipient", &recipient);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl<const DEPTH: usize, const WIN_BITS: usize, H: CircuitHasher<Fp>> ValidateWitness<Fp> for LotteryCircuit<DEPTH, WIN_BITS, H> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let secret = known(self.secret, "secret")?;
+        let index = known(self.index, "index")?;
+        let draw = known(self.draw, "draw")?;
+        if index >> DEPTH != 0 {
+            return Err(InvalidWitness::InvalidParameter(format!(
+                "index {} is outside a tree of depth {}",
+                index, DEPTH
+            )));
+        }
+        if !wins(ticket::<H>(secret), draw, WIN_BITS) {
+            return Err(InvalidWitness::InvalidParameter("the ticket does not win the draw".to_string()));
+        }
+        let siblings = self
+            .siblings
+            .iter()
+            .map(|sibling| known(*sibling, "siblings"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let root = root_from_path::<H>(ticket::<H>(secret), index, &siblings);
+        check_instances(instances, &Self::outputs(root, draw, secret, known(self.recipient, "recipient")?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::marker::PhantomData;
+
+    use ff::Field;
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+    use rand::rngs::OsRng;
+
+    use super::{ticket, wins, LotteryCircuit};
+    use crate::{
+        gadgets::{commit::CommitChip, merkle::MerkleTree},
+        validate::ValidateWitness,
+    };
+
+    const DEPTH: usize = 4;
+    const WIN_BITS: usize = 2;
+    const K: u32 = 10;
+
+    type Lottery = LotteryCircuit<DEPTH, WIN_BITS>;
+
+    // A random secret whose ticket wins `draw`, or loses it.
+    fn random_secret(draw: Fp, winning: bool) -> Fp {
+        loop {
+            let secret = Fp::random(OsRng);
+            if wins(ticket::<CommitChip>(secret), draw, WIN_BITS) == winning {
+                return secret;
+            }
+        }
+    }
+
+    #[test]
+    fn test_lottery_claim() {
+        let draw = Fp::random(OsRng);
+        let secrets = [random_secret(draw, false), random_secret(draw, true), random_secret(draw, false), Fp::random(OsRng)];
+        let tickets: Vec<_> = secrets.iter().map(|secret| ticket::<CommitChip>(*secret)).collect();
+        let tree = MerkleTree::new(DEPTH, &tickets);
+
+        let claim = |index: u64, secret: Fp, recipient: Fp| Lottery {
+            secret: Value::known(secret),
+            index: Value::known(index),
+            siblings: tree.path(index).try_into().map(|siblings: [Fp; DEPTH]| siblings.map(Value::known)).unwrap(),
+            draw: Value::known(draw),
+            recipient: Value::known(recipient),
+            hasher: PhantomData,
+        };
+        let run = |circuit: &Lottery, secret: Fp, recipient: Fp| {
+            let instances = vec![Lottery::outputs(tree.root(), draw, secret, recipient).to_vec()];
+            let valid = circuit.validate_witness(&instances).is_ok();
+            assert_eq!(MockProver::run(K, circuit, instances).unwrap().verify().is_ok(), valid);
+            valid
+        };
+
+        let recipient = Fp::from(0xa11ce);
+        assert!(run(&claim(1, secrets[1], recipient), secrets[1], recipient));
+
+        // a ticket that was sold but loses
+        assert!(!run(&claim(0, secrets[0], recipient), secrets[0], recipient));
+        // a winning ticket that was never sold
+        let stranger = random_secret(draw, true);
+        assert!(!run(&claim(1, stranger, recipient), stranger, recipient));
+        // the winning ticket against another draw
+        let circuit = claim(1, secrets[1], recipient);
+        let other_draw = draw + Fp::one();
+        let instances = vec![Lottery::outputs(tree.root(), other_draw, secrets[1], recipient).to_vec()];
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+        // the proof for one recipient does not verify for another
+        let instances = vec![Lottery::outputs(tree.root(), draw, secrets[1], Fp::from(0xb0b)).to_vec()];
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_lottery_losing_draw() {
+        // a losing ticket cannot borrow the winning bits by proving against a draw of its own
+        let draw = Fp::random(OsRng);
+        let loser = random_secret(draw, false);
+        let tree = MerkleTree::new(DEPTH, &[ticket::<CommitChip>(loser)]);
+        let mut circuit = Lottery {
+            secret: Value::known(loser),
+            index: Value::known(0),
+            siblings: tree.path(0).try_into().map(|siblings: [Fp; DEPTH]| siblings.map(Value::known)).unwrap(),
+            draw: Value::known(ticket::<CommitChip>(loser)),
+            recipient: Value::known(Fp::from(0xa11ce)),
+            hasher: PhantomData,
+        };
+        let instances = vec![Lottery::outputs(tree.root(), draw, loser, Fp::from(0xa11ce)).to_vec()];
+        assert!(MockProver::run(K, &circuit, instances.clone()).unwrap().verify().is_err());
+
+        circuit.draw = Value::known(draw);
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+}
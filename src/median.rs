@@ -0,0 +1,202 @@
+//! Proves that a public value sits at a public rank of a private dataset, e.g. is its median.
+//!
+//! halo2 has no argument for a permutation the prover chooses, but copy constraints, which
+//! the permutation argument enforces, can wire up a sorting network: a fixed pattern of
+//! compare-and-swaps that sorts any input. Here that is odd-even transposition sort, `n`
+//! rounds that each `MinMaxChip::sort_pair` the neighbours `(0, 1), (2, 3), ...` or
+//! `(1, 2), (3, 4), ...` in turn. Every compare-and-swap outputs its two inputs again, so the
+//! last round holds exactly the dataset, in order, and `SelectChip` picks the cell at the
+//! public rank out of it.
+//!
+//! Each of the `n * (n - 1) / 2` compare-and-swaps costs two range checks, so this is for
+//! small datasets.
+//!
+//! The rank is a public input the circuit reads, copied from its instance row into the
+//! selection, and the value at that rank the output it exposes, both through `InstanceChip`.
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    error::Halo2ExamplesError,
+    gadgets::{
+        min_max::{MinMaxChip, MinMaxConfig},
+        select::{SelectChip, SelectConfig},
+    },
+    public_inputs::{InstanceChip, InstanceConfig, InstanceLayout},
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The public inputs of `PercentileCircuit`.
+pub const INSTANCES: InstanceLayout = InstanceLayout(&[&["rank", "value"]]);
+
+/// `values` in ascending order, through odd-even transposition sort.
+pub fn sort<F: FieldExt + PrimeFieldBits, const NUM_BITS: usize>(
+    chip: &MinMaxChip<F, NUM_BITS>,
+    mut layouter: impl Layouter<F>,
+    values: &[AssignedCell<F, F>],
+) -> Result<Vec<AssignedCell<F, F>>, Halo2ExamplesError> {
+    let mut sorted = values.to_vec();
+    for round in 0..sorted.len() {
+        for i in (round % 2..sorted.len().saturating_sub(1)).step_by(2) {
+            let (lo, hi) = chip.sort_pair(layouter.namespace(|| format!("round {}, {}", round, i)), &sorted[i], &sorted[i + 1])?;
+            sorted[i] = lo;
+            sorted[i + 1] = hi;
+        }
+    }
+    Ok(sorted)
+}
+
+/// Exposes the private value at a public rank, 0 for the smallest.
+#[derive(Default)]
+pub struct PercentileCircuit<const NUM_BITS: usize> {
+    pub values: Vec<Value<u64>>,
+}
+
+impl<const NUM_BITS: usize> PercentileCircuit<NUM_BITS> {
+    /// The rank of the median of `n` values, the upper one for even `n`.
+    pub fn median_rank(n: usize) -> usize {
+        n / 2
+    }
+
+    /// The public inputs: `rank` and the value at that rank, as laid out by `INSTANCES`.
+    pub fn instances(values: &[u64], rank: usize) -> Vec<u64> {
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable();
+        vec![rank as u64, sorted[rank]]
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const NUM_BITS: usize> Circuit<F> for PercentileCircuit<NUM_BITS> {
+    type Config = (MinMaxConfig, SelectConfig, LoadPrivateConfig, InstanceConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        let min_max = MinMaxChip::<F, NUM_BITS>::configure(meta, instance);
+        let select = SelectChip::configure(meta);
+        let load = LoadPrivateChip::configure(meta, 1);
+        let public = meta.advice_column();
+        (min_max, select, load, InstanceChip::configure(meta, instance, public))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (min_max_config, select_config, load_config, instance_config) = config;
+        let min_max = MinMaxChip::<F, NUM_BITS>::construct(min_max_config);
+        let select = SelectChip::construct(select_config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let instance = InstanceChip::construct(instance_config);
+
+        let values = self
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let cell = load_chip.load_private(layouter.namespace(|| format!("load v_{}", i)), value.map(F::from))?;
+                min_max.bits().unpack(layouter.namespace(|| format!("range check v_{}", i)), &cell, NUM_BITS)?;
+                Ok(cell)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let rank = instance.load(layouter.namespace(|| "load rank"), "rank", INSTANCES.row("rank"))?;
+
+        let sorted = sort(&min_max, layouter.namespace(|| "sort"), &values)?;
+        let value = select.select(layouter.namespace(|| "select"), &sorted, &rank)?;
+        instance.expose(layouter.namespace(|| "value"), "value", &value, INSTANCES.row("value"))?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const NUM_BITS: usize> ValidateWitness<F> for PercentileCircuit<NUM_BITS> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        let values = self
+            .values
+            .iter()
+            .map(|value| {
+                let value = known(*value, "value")?;
+                check_below(F::from(value), 1u128 << NUM_BITS, "value")?;
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        let rank = *instances
+            .first()
+            .and_then(|column| column.first())
+            .ok_or(InvalidWitness::WrongInstanceCount {
+                expected: 2,
+                found: instances.iter().map(Vec::len).sum(),
+            })?;
+        check_below(rank, values.len() as u128, "rank")?;
+
+        let expected: Vec<_> = Self::instances(&values, rank.get_lower_128() as usize).into_iter().map(F::from).collect();
+        check_instances(instances, &expected)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::PercentileCircuit;
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 11;
+
+    type Circuit = PercentileCircuit<16>;
+
+    fn circuit(values: &[u64], rank: usize) -> (Circuit, Vec<Vec<Fp>>) {
+        let circuit = Circuit {
+            values: values.iter().copied().map(Value::known).collect(),
+        };
+        let instances = Circuit::instances(values, rank).into_iter().map(Fp::from).collect();
+        (circuit, vec![instances])
+    }
+
+    #[test]
+    fn test_median() {
+        let values = [42, 7, 65535, 7, 0, 300, 12];
+        let (circuit, instances) = circuit(&values, Circuit::median_rank(values.len()));
+        assert_eq!(instances[0][1], Fp::from(12));
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let values = [5, 3, 9, 1];
+        for rank in 0..values.len() {
+            let (circuit, instances) = circuit(&values, rank);
+            let prover = MockProver::run(K, &circuit, instances).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_wrong_median() {
+        // 42 is in the dataset, just not in the middle of it
+        let values = [42, 7, 65535, 7, 0, 300, 12];
+        let (circuit, mut instances) = circuit(&values, Circuit::median_rank(values.len()));
+        instances[0][1] = Fp::from(42);
+
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_rank_out_of_range() {
+        let values = [5, 3, 9, 1];
+        let circuit = Circuit {
+            values: values.iter().copied().map(Value::known).collect(),
+        };
+
+        let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(4), Fp::from(9)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,333 @@
+//! Nonogram, or picross: a private black and white grid of `W` columns and `H` rows has the
+//! public run-length clues of every row and column, the lengths of its blocks of filled
+//! cells in order.
+//!
+//! The grid is copied into one region per line, row or column, where a counter runs over its
+//! cells: it counts up through a block, resets on an empty cell, and where a block ends its
+//! length is added into `acc`, the first as the units of base `B = max(W, H) + 1`, the next
+//! as the `B`s and so on, by `power` moving up a digit at every block end:
+//!
+//!        b     |   count   |  end  |    power     |     acc     |  q_line
+//!     ------------------------------------------------------------------------
+//!              |     0     |       |      1       |      0      |
+//!       b_0    |  count_1  |  e_0  |   power_1    |    acc_1    |    1
+//!       ...    |    ...    |  ...  |     ...      |     ...     |   ...
+//!     b_{n-1}  |  count_n  |e_{n-1}|   power_n    |    acc_n    |    1
+//!        0     |           |       |              |             |
+//!
+//!     count_i = b_i * (count_{i-1} + 1),      e_i = b_i * (1 - b_{i+1}),
+//!     acc_i = acc_{i-1} + e_i * count_i * power_{i-1},
+//!     power_i = power_{i-1} * (1 + e_i * (B - 1))
+//!
+//! A block is at most `B - 1` long, so `acc_n` is the line's clue as the digits of a base
+//! `B` number, which `HornerChip` evaluates from the public clue at `B` for the two to be
+//! equal. A line of `n` cells has at most `(n + 1) / 2` blocks, and with sides of up to
+//! `MAX_SIDE` cells that number stays below the modulus, so equal numbers are equal clues.
+//!
+//! The public inputs are the clues of the rows, then of the columns, each padded with zeros
+//! to the most blocks its line can hold.
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::ResultExt,
+    gadgets::horner::{HornerChip, HornerConfig},
+    gate::GateBuilder,
+    public_inputs::{InstanceChip, InstanceConfig},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The longest side a grid can have for its clues to be packed into one field element.
+pub const MAX_SIDE: usize = 64;
+
+/// The most blocks a line of `len` cells can hold.
+pub fn max_runs(len: usize) -> usize {
+    (len + 1) / 2
+}
+
+/// The lengths of the blocks of filled cells in `line`.
+pub fn runs(line: &[bool]) -> Vec<u64> {
+    line.split(|filled| !filled).filter(|run| !run.is_empty()).map(|run| run.len() as u64).collect()
+}
+
+/// The clues of `grid`: those of its rows, then those of its columns.
+pub fn clues<const W: usize, const H: usize>(grid: &[[bool; W]; H]) -> (Vec<Vec<u64>>, Vec<Vec<u64>>) {
+    let rows = grid.iter().map(|row| runs(row)).collect();
+    let cols = (0..W).map(|c| runs(&grid.iter().map(|row| row[c]).collect::<Vec<_>>())).collect();
+    (rows, cols)
+}
+
+#[derive(Debug, Clone)]
+pub struct NonogramConfig {
+    horner: HornerConfig,
+    instance: InstanceConfig,
+    cell: Column<Advice>,
+    b: Column<Advice>,
+    count: Column<Advice>,
+    end: Column<Advice>,
+    power: Column<Advice>,
+    acc: Column<Advice>,
+    q_line: Selector,
+    constants: ConstantsConfig,
+}
+
+/// Proves a private grid of `W` columns and `H` rows has public row and column clues.
+pub struct NonogramCircuit<const W: usize, const H: usize> {
+    pub grid: [[Value<bool>; W]; H],
+}
+
+impl<const W: usize, const H: usize> Default for NonogramCircuit<W, H> {
+    fn default() -> Self {
+        Self {
+            grid: [[Value::unknown(); W]; H],
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> NonogramCircuit<W, H> {
+    pub fn new(grid: [[bool; W]; H]) -> Self {
+        Self {
+            grid: grid.map(|row| row.map(Value::known)),
+        }
+    }
+
+    /// The base the clues are packed in.
+    pub fn base() -> u64 {
+        W.max(H) as u64 + 1
+    }
+
+    /// The public inputs: the clues of the `H` rows, then of the `W` columns, each padded
+    /// with zeros. Panics if a clue has more blocks than its line can hold.
+    pub fn instances(rows: &[Vec<u64>], cols: &[Vec<u64>]) -> Vec<Fp> {
+        assert_eq!((rows.len(), cols.len()), (H, W), "a clue for every row and column");
+        let pad = |clue: &Vec<u64>, len: usize| {
+            assert!(clue.len() <= max_runs(len), "{} blocks in a line of {}", clue.len(), len);
+            clue.iter().copied().chain(std::iter::repeat(0)).take(max_runs(len)).map(Fp::from).collect::<Vec<_>>()
+        };
+        rows.iter().flat_map(|clue| pad(clue, W)).chain(cols.iter().flat_map(|clue| pad(clue, H))).collect()
+    }
+
+    /// The length-`n` run-length accumulation over `cells`, `acc_n` above, starting from
+    /// the loaded constants `zero` and `one`.
+    fn line(
+        config: &NonogramConfig,
+        mut layouter: impl Layouter<Fp>,
+        cells: &[&AssignedCell<Fp, Fp>],
+        zero: &AssignedCell<Fp, Fp>,
+        one: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let base = Fp::from(Self::base());
+        layouter.assign_region(
+            || "line",
+            |mut region| {
+                let mut count = zero.copy_advice(|| "count_0", &mut region, config.count, 0)?;
+                let mut power = one.copy_advice(|| "power_0", &mut region, config.power, 0)?;
+                let mut acc = zero.copy_advice(|| "acc_0", &mut region, config.acc, 0)?;
+
+                for (i, cell) in cells.iter().enumerate() {
+                    let offset = i + 1;
+                    config.q_line.enable(&mut region, offset)?;
+                    let b = cell.copy_advice(|| format!("b_{}", i), &mut region, config.b, offset)?;
+                    let b = b.value().copied();
+                    let b_next = cells.get(i + 1).map_or(Value::known(Fp::zero()), |cell| cell.value().copied());
+
+                    let next = b * (count.value().copied() + Value::known(Fp::one()));
+                    count = region.assign_advice(|| format!("count_{}", offset), config.count, offset, || next)?;
+                    let end = b * (Value::known(Fp::one()) - b_next);
+                    region.assign_advice(|| format!("e_{}", i), config.end, offset, || end)?;
+                    let next = acc.value().copied() + end * count.value().copied() * power.value().copied();
+                    acc = region.assign_advice(|| format!("acc_{}", offset), config.acc, offset, || next)?;
+                    let next = power.value().copied() * (Value::known(Fp::one()) + end * Value::known(base - Fp::one()));
+                    power = region.assign_advice(|| format!("power_{}", offset), config.power, offset, || next)?;
+                }
+                zero.copy_advice(|| "b_n", &mut region, config.b, cells.len() + 1)?;
+                Ok(acc)
+            },
+        )
+    }
+}
+
+impl<const W: usize, const H: usize> Circuit<Fp> for NonogramCircuit<W, H> {
+    type Config = NonogramConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        assert!(W.max(H) <= MAX_SIDE, "a grid of {}x{}, more than {} a side", W, H, MAX_SIDE);
+        let instance = meta.instance_column();
+        let horner = HornerChip::configure(meta, instance);
+        let [cell, b, count, end, power, acc, public] = [(); 7].map(|_| meta.advice_column());
+        let q_line = meta.selector();
+
+        // the counter's, power's and acc's first values, the empty cell after the line and
+        // the base are loaded constants
+        let constants = ConstantsChip::configure_with(meta, count);
+        for column in [cell, b, count, power, acc] {
+            meta.enable_equality(column);
+        }
+
+        let base = Fp::from(Self::base());
+        meta.create_gate("run length", |meta| {
+            let q_line = meta.query_selector(q_line);
+            let b_next = meta.query_advice(b, Rotation::next());
+            let b = meta.query_advice(b, Rotation::cur());
+            let end = meta.query_advice(end, Rotation::cur());
+            let [count_prev, power_prev, acc_prev] = [count, power, acc].map(|column| meta.query_advice(column, Rotation::prev()));
+            let [count, power, acc] = [count, power, acc].map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(Fp::one());
+
+            GateBuilder::with_selector(q_line)
+                .boolean("b is boolean", b.clone())
+                .equal("count = b * (count_prev + 1)", count.clone(), b.clone() * (count_prev + one.clone()))
+                .equal("end = b * (1 - b_next)", end.clone(), b * (one.clone() - b_next))
+                .equal("acc = acc_prev + end * count * power_prev", acc, acc_prev + end.clone() * count * power_prev.clone())
+                .equal("power = power_prev * (1 + end * (B - 1))", power, power_prev * (one + end * (base - Fp::one())))
+        });
+
+        NonogramConfig {
+            horner,
+            instance: InstanceChip::configure(meta, instance, public),
+            cell,
+            b,
+            count,
+            end,
+            power,
+            acc,
+            q_line,
+            constants,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let horner = HornerChip::construct(config.horner.clone());
+        let instance = InstanceChip::construct(config.instance.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+
+        let zero = constants.load_constant(layouter.namespace(|| "zero"), "zero", Fp::zero())?;
+        let one = constants.load_constant(layouter.namespace(|| "one"), "one", Fp::one())?;
+        let base = constants.load_constant(layouter.namespace(|| "base"), "B", Fp::from(Self::base()))?;
+        let grid = layouter
+            .assign_region(
+                || "grid",
+                |mut region| {
+                    let mut grid = Vec::with_capacity(H);
+                    for (r, row) in self.grid.iter().enumerate() {
+                        let mut cells = Vec::with_capacity(W);
+                        for (c, filled) in row.iter().enumerate() {
+                            let value = filled.map(|filled| Fp::from(filled as u64));
+                            cells.push(region.assign_advice(|| format!("g_{}_{}", r, c), config.cell, r * W + c, || value)?);
+                        }
+                        grid.push(cells);
+                    }
+                    Ok(grid)
+                },
+            )
+            .in_chip("NonogramCircuit", "grid")?;
+
+        let rows = grid.iter().map(|row| row.iter().collect::<Vec<_>>());
+        let cols = (0..W).map(|c| grid.iter().map(|row| &row[c]).collect::<Vec<_>>());
+        let mut offset = 0;
+        for (name, line) in rows.map(|line| ("row", line)).chain(cols.map(|line| ("column", line))) {
+            let mut layouter = layouter.namespace(|| format!("{} at instance {}", name, offset));
+            let acc = Self::line(&config, layouter.namespace(|| "runs"), &line, &zero, &one).in_chip("NonogramCircuit", "line")?;
+
+            let clue = (0..max_runs(line.len()))
+                .map(|j| instance.load(layouter.namespace(|| format!("load clue {}", j)), "clue", offset + j))
+                .collect::<Result<Vec<_>, _>>()?;
+            offset += clue.len();
+            let packed = horner.evaluate(layouter.namespace(|| "pack clue"), &clue, &base)?;
+            layouter
+                .assign_region(|| "clue", |mut region| region.constrain_equal(acc.cell(), packed.cell()))
+                .in_chip("NonogramCircuit", "clue")?;
+        }
+        Ok(())
+    }
+}
+
+impl<const W: usize, const H: usize> ValidateWitness<Fp> for NonogramCircuit<W, H> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let mut grid = [[false; W]; H];
+        for (row, values) in grid.iter_mut().zip(&self.grid) {
+            for (cell, value) in row.iter_mut().zip(values) {
+                *cell = known(*value, "cell")?;
+            }
+        }
+        let (rows, cols) = clues(&grid);
+        check_instances(instances, &Self::instances(&rows, &cols))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::dev::MockProver;
+
+    use super::{clues, NonogramCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 8;
+
+    fn grid<const W: usize, const H: usize>(rows: [&str; H]) -> [[bool; W]; H] {
+        rows.map(|row| row.chars().map(|c| c == '#').collect::<Vec<_>>().try_into().expect("W cells a row"))
+    }
+
+    fn heart() -> [[bool; 5]; 5] {
+        grid([".#.#.", "#####", "#####", ".###.", "..#.."])
+    }
+
+    #[test]
+    fn test_nonogram() {
+        let (rows, cols) = clues(&heart());
+        assert_eq!(rows, vec![vec![1, 1], vec![5], vec![5], vec![3], vec![1]]);
+        assert_eq!(cols, vec![vec![2], vec![4], vec![4], vec![4], vec![2]]);
+        let circuit = NonogramCircuit::new(heart());
+        let instances = vec![NonogramCircuit::<5, 5>::instances(&rows, &cols)];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+
+        // not square, with an empty column
+        let puzzle = grid(["##.#..", ".#.###", "#...##"]);
+        let (rows, cols) = clues(&puzzle);
+        assert_eq!(rows, vec![vec![2, 1], vec![1, 3], vec![1, 2]]);
+        assert_eq!(cols[2], Vec::<u64>::new());
+        let circuit = NonogramCircuit::new(puzzle);
+        let instances = vec![NonogramCircuit::<6, 3>::instances(&rows, &cols)];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+    }
+
+    #[test]
+    fn test_nonogram_wrong_grid() {
+        let (rows, cols) = clues(&heart());
+        let instances = vec![NonogramCircuit::<5, 5>::instances(&rows, &cols)];
+
+        // a cell flipped, and a row shifted, which keeps its own clue but not the columns'
+        let mut flipped = heart();
+        flipped[2][2] = false;
+        let mut shifted = heart();
+        shifted[3] = [true, true, true, false, false];
+        for grid in [flipped, shifted] {
+            let circuit = NonogramCircuit::new(grid);
+            assert!(circuit.validate_witness(&instances).is_err());
+            assert!(MockProver::run(K, &circuit, instances.clone()).unwrap().verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_nonogram_wrong_clue() {
+        let puzzle = grid(["##.#..", ".#.###", "#...##"]);
+        let circuit = NonogramCircuit::new(puzzle);
+        let (mut rows, cols) = clues(&puzzle);
+
+        // the blocks of a row in the wrong order, then merged into one
+        for clue in [vec![1, 2], vec![3]] {
+            rows[0] = clue;
+            let instances = vec![NonogramCircuit::<6, 3>::instances(&rows, &cols)];
+            assert!(circuit.validate_witness(&instances).is_err());
+            assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+        }
+    }
+}
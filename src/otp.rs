@@ -0,0 +1,185 @@
+//! One-time passwords from a hash chain, S/Key style, behind a commitment.
+//!
+//! The user picks a secret seed and hashes it `LENGTH` times,
+//!
+//!     c_0 = seed,   c_{j+1} = hash2(c_j, 0),   anchor = c_LENGTH
+//!
+//! and registers `commitment = hash2(anchor, blinding)` with the server. The `k`-th login
+//! discloses `c_{LENGTH - k}` as its password, and proves that its `k`-th hash is the anchor
+//! behind the commitment. Each password is a preimage of the one before it, so seeing
+//! passwords `1..k` tells nothing about the ones after, and the anchor stays hidden.
+//!
+//! The circuit reads the password and `k` from their instance rows, hashes the password
+//! `LENGTH` times and lets `SelectChip` pick the `k`-th hash, so one circuit checks any
+//! `k <= LENGTH`. The server only accepts the `k` after the last one it accepted, which
+//! rules out `k = 0`, the anchor itself.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    gadgets::{
+        commit::CommitChip,
+        hasher::CircuitHasher,
+        select::{SelectChip, SelectConfig},
+    },
+    public_inputs::{InstanceChip, InstanceConfig, InstanceLayout},
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The public inputs of `OtpCircuit`.
+pub const INSTANCES: InstanceLayout = InstanceLayout(&[&["password", "index", "commitment"]]);
+
+/// The second input of every hash along the chain.
+const CHAIN_TAG: u64 = 0;
+
+/// `c_0, ..., c_length` for `seed`.
+pub fn chain<H: CircuitHasher<Fp>>(seed: Fp, length: usize) -> Vec<Fp> {
+    let mut chain = vec![seed];
+    for j in 0..length {
+        chain.push(H::hash2_native(chain[j], Fp::from(CHAIN_TAG)));
+    }
+    chain
+}
+
+/// The commitment the server stores for the chain ending in `anchor`.
+pub fn commitment<H: CircuitHasher<Fp>>(anchor: Fp, blinding: Fp) -> Fp {
+    H::hash2_native(anchor, blinding)
+}
+
+/// Proves that the disclosed password is the `index`-th preimage of the committed anchor,
+/// for a chain of at most `LENGTH` passwords, hashing with `H`.
+pub struct OtpCircuit<const LENGTH: usize, H: CircuitHasher<Fp> = CommitChip> {
+    pub blinding: Value<Fp>,
+    pub hasher: PhantomData<H>,
+}
+
+impl<const LENGTH: usize, H: CircuitHasher<Fp>> OtpCircuit<LENGTH, H> {
+    pub fn new(blinding: Fp) -> Self {
+        Self {
+            blinding: Value::known(blinding),
+            hasher: PhantomData,
+        }
+    }
+
+    /// The public inputs, as laid out by `INSTANCES`: the password, its index and the
+    /// commitment it opens.
+    pub fn instances(password: Fp, index: usize, blinding: Fp) -> Vec<Fp> {
+        let anchor = chain::<H>(password, index)[index];
+        vec![password, Fp::from(index as u64), commitment::<H>(anchor, blinding)]
+    }
+}
+
+impl<const LENGTH: usize, H: CircuitHasher<Fp>> Circuit<Fp> for OtpCircuit<LENGTH, H> {
+    type Config = (H::Config, SelectConfig, LoadPrivateConfig, InstanceConfig, ConstantsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            blinding: Value::unknown(),
+            hasher: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let hasher = H::configure(meta);
+        let select = SelectChip::configure(meta);
+        let load = LoadPrivateChip::configure(meta, 1);
+        let public = meta.advice_column();
+        (
+            hasher,
+            select,
+            load,
+            InstanceChip::configure(meta, instance, public),
+            ConstantsChip::configure(meta),
+        )
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (hasher_config, select_config, load_config, instance_config, constants_config) = config;
+        let hasher = H::construct(hasher_config);
+        let select = SelectChip::construct(select_config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let instance = InstanceChip::construct(instance_config);
+        let constants = ConstantsChip::construct(constants_config);
+
+        let blinding = load_chip.load_private(layouter.namespace(|| "load blinding"), self.blinding)?;
+        let tag = constants.load_constant(layouter.namespace(|| "chain tag"), "chain tag", Fp::from(CHAIN_TAG))?;
+        let password = instance.load(layouter.namespace(|| "load password"), "password", INSTANCES.row("password"))?;
+        let index = instance.load(layouter.namespace(|| "load index"), "index", INSTANCES.row("index"))?;
+
+        let mut hashes = vec![password];
+        for j in 0..LENGTH {
+            let hash = hasher.hash2(layouter.namespace(|| format!("hash {}", j + 1)), &hashes[j], &tag)?;
+            hashes.push(hash);
+        }
+        let anchor = select.select(layouter.namespace(|| "anchor"), &hashes, &index)?;
+
+        let commitment = hasher.hash2(layouter.namespace(|| "commitment"), &anchor, &blinding)?;
+        instance.expose(layouter.namespace(|| "commitment"), "commitment", &commitment, INSTANCES.row("commitment"))?;
+        Ok(())
+    }
+}
+
+impl<const LENGTH: usize, H: CircuitHasher<Fp>> ValidateWitness<Fp> for OtpCircuit<LENGTH, H> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let blinding = known(self.blinding, "blinding")?;
+        let public = |name: &str| {
+            instances.first().and_then(|column| column.get(INSTANCES.row(name))).copied().ok_or(
+                InvalidWitness::WrongInstanceCount {
+                    expected: 3,
+                    found: instances.iter().map(Vec::len).sum(),
+                },
+            )
+        };
+        let (password, index) = (public("password")?, public("index")?);
+        check_below(index, LENGTH as u128 + 1, "index")?;
+
+        check_instances(instances, &Self::instances(password, index.get_lower_128() as usize, blinding))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use rand::rngs::OsRng;
+
+    use super::{chain, commitment, OtpCircuit};
+    use crate::{gadgets::commit::CommitChip, validate::ValidateWitness};
+
+    const LENGTH: usize = 8;
+    const K: u32 = 10;
+
+    #[test]
+    fn test_otp() {
+        let (seed, blinding) = (Fp::random(OsRng), Fp::random(OsRng));
+        let passwords = chain::<CommitChip>(seed, LENGTH);
+        let registered = commitment::<CommitChip>(passwords[LENGTH], blinding);
+        let circuit = OtpCircuit::<LENGTH>::new(blinding);
+
+        let run = |password: Fp, index: u64| {
+            let instances = vec![vec![password, Fp::from(index), registered]];
+            let valid = circuit.validate_witness(&instances).is_ok();
+            assert_eq!(MockProver::run(K, &circuit, instances).unwrap().verify().is_ok(), valid);
+            valid
+        };
+
+        // the first, third and last logins
+        for k in [1, 3, LENGTH] {
+            assert!(run(passwords[LENGTH - k], k as u64), "login {} is rejected", k);
+        }
+        // a password at the wrong index, and an index past the chain
+        assert!(!run(passwords[LENGTH - 3], 2));
+        assert!(!run(passwords[LENGTH - 3], 4));
+        assert!(!run(passwords[0], LENGTH as u64 + 1));
+        // the right password against another blinding
+        let other = OtpCircuit::<LENGTH>::new(Fp::random(OsRng));
+        let instances = vec![vec![passwords[LENGTH - 1], Fp::one(), registered]];
+        assert!(MockProver::run(K, &other, instances).unwrap().verify().is_err());
+    }
+}
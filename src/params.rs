@@ -0,0 +1,281 @@
+//! Runtime circuit parameters read from a TOML file.
+//!
+//! The const-generic examples have to be recompiled to change their sizes. The runtime
+//! variants (`fibonaci::runtime`, `decompose::runtime`, `DynamicRangeCheckCircuit::from_params`
+//! and `MerkleRootCircuit`) take these parameters instead:
+//!
+//!     [fibonacci]
+//!     k = 5
+//!     rows = 20
+//!
+//!     [decompose]
+//!     k = 9
+//!     lookup_bits = 4
+//!     num_bits = 128
+//!
+//!     [range_check]
+//!     k = 9
+//!     range = 100
+//!
+//!     [merkle]
+//!     k = 9
+//!     depth = 3
+//!
+//! Missing sections and keys fall back to the sizes used by the const-generic examples.
+
+use std::{fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub enum ParamsError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamsError::Io(err) => write!(f, "failed to read params file: {}", err),
+            ParamsError::Toml(err) => write!(f, "malformed params toml: {}", err),
+            ParamsError::Invalid(reason) => write!(f, "invalid circuit params: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ParamsError {}
+
+impl From<io::Error> for ParamsError {
+    fn from(err: io::Error) -> Self {
+        ParamsError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ParamsError {
+    fn from(err: toml::de::Error) -> Self {
+        ParamsError::Toml(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FibonacciParams {
+    pub k: u32,
+    /// Number of Fibonacci terms laid out, including the two seeds.
+    pub rows: usize,
+}
+
+impl Default for FibonacciParams {
+    fn default() -> Self {
+        Self { k: 4, rows: 10 }
+    }
+}
+
+impl FibonacciParams {
+    pub fn validate(&self) -> Result<(), ParamsError> {
+        if self.rows < 3 {
+            return Err(ParamsError::Invalid(format!(
+                "fibonacci needs at least 3 rows, got {}",
+                self.rows
+            )));
+        }
+        check_k(self.k, self.rows)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecomposeParams {
+    pub k: u32,
+    /// Bit-width K of each chunk, i.e. the lookup table holds 0..2^K.
+    pub lookup_bits: usize,
+    /// Bit-width N of the decomposed value. Must be a multiple of `lookup_bits`.
+    pub num_bits: usize,
+}
+
+impl Default for DecomposeParams {
+    fn default() -> Self {
+        Self {
+            k: 9,
+            lookup_bits: 8,
+            num_bits: 64,
+        }
+    }
+}
+
+impl DecomposeParams {
+    /// Size of the lookup table.
+    pub fn range(&self) -> usize {
+        1 << self.lookup_bits
+    }
+
+    /// Number of K-bit chunks, C = N / K.
+    pub fn num_chunks(&self) -> usize {
+        self.num_bits / self.lookup_bits
+    }
+
+    pub fn validate(&self) -> Result<(), ParamsError> {
+        if self.lookup_bits == 0 || self.lookup_bits >= 32 {
+            return Err(ParamsError::Invalid(format!(
+                "lookup_bits must be in 1..32, got {}",
+                self.lookup_bits
+            )));
+        }
+        if self.num_bits == 0 || self.num_bits % self.lookup_bits != 0 {
+            return Err(ParamsError::Invalid(format!(
+                "num_bits ({}) must be a non-zero multiple of lookup_bits ({})",
+                self.num_bits, self.lookup_bits
+            )));
+        }
+        check_k(self.k, self.range().max(self.num_chunks() + 2))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RangeCheckParams {
+    pub k: u32,
+    /// Values are checked to lie in `0..range`.
+    pub range: usize,
+}
+
+impl Default for RangeCheckParams {
+    fn default() -> Self {
+        Self { k: 9, range: 256 }
+    }
+}
+
+impl RangeCheckParams {
+    pub fn validate(&self) -> Result<(), ParamsError> {
+        if self.range == 0 {
+            return Err(ParamsError::Invalid("range must be at least 1".to_string()));
+        }
+        // the smallest `0..2^MAX_BITS` table that holds the range
+        check_k(self.k, self.range.next_power_of_two())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MerkleParams {
+    pub k: u32,
+    /// Levels between a leaf and the root, i.e. the tree holds `2^depth` leaves.
+    pub depth: usize,
+}
+
+impl Default for MerkleParams {
+    fn default() -> Self {
+        Self { k: 9, depth: 3 }
+    }
+}
+
+impl MerkleParams {
+    pub fn validate(&self) -> Result<(), ParamsError> {
+        if self.depth == 0 || self.depth > 32 {
+            return Err(ParamsError::Invalid(format!(
+                "depth must be in 1..=32, got {}",
+                self.depth
+            )));
+        }
+        // the rows each level takes depend on the hasher, so only the leaf path is counted
+        check_k(self.k, self.depth + 1)
+    }
+}
+
+// Leave some headroom for the blinding rows the prover reserves at the end of each column.
+fn check_k(k: u32, rows: usize) -> Result<(), ParamsError> {
+    const BLINDING_ROWS: usize = 6;
+    if k >= 32 || (1usize << k) < rows + BLINDING_ROWS {
+        return Err(ParamsError::Invalid(format!(
+            "k = {} is too small for {} rows",
+            k, rows
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitParams {
+    pub fibonacci: FibonacciParams,
+    pub decompose: DecomposeParams,
+    pub range_check: RangeCheckParams,
+    pub merkle: MerkleParams,
+}
+
+impl CircuitParams {
+    pub fn from_toml(toml: &str) -> Result<Self, ParamsError> {
+        let params: Self = toml::from_str(toml)?;
+        params.fibonacci.validate()?;
+        params.decompose.validate()?;
+        params.range_check.validate()?;
+        params.merkle.validate()?;
+        Ok(params)
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, ParamsError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CircuitParams, ParamsError};
+
+    #[test]
+    fn test_params_from_toml() {
+        let params = CircuitParams::from_toml(
+            r#"
+            [fibonacci]
+            k = 5
+            rows = 20
+
+            [decompose]
+            lookup_bits = 4
+            num_bits = 128
+
+            [range_check]
+            range = 100
+
+            [merkle]
+            depth = 5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(params.fibonacci.rows, 20);
+        assert_eq!(params.decompose.k, 9);
+        assert_eq!(params.decompose.range(), 16);
+        assert_eq!(params.decompose.num_chunks(), 32);
+        assert_eq!((params.range_check.k, params.range_check.range), (9, 100));
+        assert_eq!(params.merkle.depth, 5);
+
+        assert_eq!(
+            CircuitParams::from_toml("").unwrap(),
+            CircuitParams::default()
+        );
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        for toml in [
+            "[fibonacci]\nrows = 2",
+            "[fibonacci]\nk = 3\nrows = 10",
+            "[decompose]\nlookup_bits = 3\nnum_bits = 64",
+            "[decompose]\nk = 8\nlookup_bits = 8",
+            "[range_check]\nrange = 0",
+            "[range_check]\nk = 4\nrange = 100",
+            "[merkle]\ndepth = 0",
+        ] {
+            assert!(matches!(
+                CircuitParams::from_toml(toml),
+                Err(ParamsError::Invalid(_))
+            ));
+        }
+        assert!(matches!(
+            CircuitParams::from_toml("[fibonacci]\nrows = -1"),
+            Err(ParamsError::Toml(_))
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+//! Payroll compliance: every salary in a private payroll lies within a public band, and
+//! together they stay within a public budget,
+//!
+//!     min <= salary_i <= max,   salary_0 + ... + salary_{N-1} <= budget
+//!
+//! Amounts are fixed-point, in whole cents, as `cents` parses them from "61250.50"; a
+//! salary is a `U32Chip` word, so it is at most $42,949,672.95, and the budget has 64 bits.
+//!
+//! The band is a batch of range checks: `salary_i - min` and `max - salary_i`, for every
+//! salary, are decomposed into bytes by one `DecomposeChip::assign_many`, since a salary out
+//! of the band makes one of them negative, a field element near the modulus with no 32-bit
+//! decomposition. The total is `AddManyChip`'s accumulator, which adds the words unreduced
+//! and splits the result into its low word and a carry, recombined here as
+//!
+//!     total = sum + carry * 2^32,   budget - total  decomposed into 64 bits
+//!
+//! The public inputs are the band and the budget. The salaries, how many of them sit at
+//! either end of the band and how much of the budget is left stay private; the number of
+//! salaries is fixed by the circuit.
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    decompose::decompose_range_check::{DecomposeChip, DecomposeConfig},
+    gadgets::{
+        add_many::{AddManyChip, AddManyConfig},
+        arith::{ArithChip, ArithConfig},
+    },
+    public_inputs::{InstanceChip, InstanceConfig, InstanceLayout},
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The public inputs of `PayrollCircuit`.
+pub const INSTANCES: InstanceLayout = InstanceLayout(&[&["min", "max", "budget"]]);
+
+/// Cents to the unit of currency.
+pub const CENTS: u32 = 100;
+
+/// The bits a salary, and its distance to either end of the band, are range checked to.
+pub const SALARY_BITS: usize = 32;
+
+/// The bits the budget, and what is left of it, are range checked to.
+pub const BUDGET_BITS: usize = 64;
+
+/// The amount "61250.50", or "61250", in cents, if it is a valid one that fits a word.
+pub fn cents(amount: &str) -> Option<u32> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, "00"));
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+    if !digits(whole) || !digits(fraction) || fraction.len() != 2 {
+        return None;
+    }
+    let fraction: u32 = fraction.parse().ok()?;
+    whole.parse::<u32>().ok()?.checked_mul(CENTS)?.checked_add(fraction)
+}
+
+/// The total of `salaries`, in cents.
+pub fn total(salaries: &[u32]) -> u64 {
+    salaries.iter().map(|salary| *salary as u64).sum()
+}
+
+/// Proves a private payroll fits a public salary band and budget.
+pub struct PayrollCircuit {
+    /// Salaries, in cents.
+    pub salaries: Vec<Value<u32>>,
+}
+
+impl PayrollCircuit {
+    pub fn new(salaries: &[u32]) -> Self {
+        Self {
+            salaries: salaries.iter().copied().map(Value::known).collect(),
+        }
+    }
+
+    /// The public inputs, as laid out by `INSTANCES`, all in cents.
+    pub fn instances(min: u32, max: u32, budget: u64) -> Vec<Fp> {
+        vec![Fp::from(min as u64), Fp::from(max as u64), Fp::from(budget)]
+    }
+}
+
+impl Circuit<Fp> for PayrollCircuit {
+    type Config = (AddManyConfig, DecomposeConfig<Fp, 256>, ArithConfig, InstanceConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            salaries: vec![Value::unknown(); self.salaries.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let add_many = AddManyChip::configure(meta, instance);
+        let decompose = DecomposeConfig::configure(meta);
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let arith = ArithChip::configure(meta, advice);
+        let public = meta.advice_column();
+        (add_many, decompose, arith, InstanceChip::configure(meta, instance, public))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (add_many_config, decompose_config, arith_config, instance_config) = config;
+        let add_many = AddManyChip::construct(add_many_config);
+        let decompose = DecomposeChip::construct(decompose_config);
+        let arith = ArithChip::construct(arith_config);
+        let instance = InstanceChip::construct(instance_config);
+        add_many.words().load_tables(&mut layouter)?;
+        decompose.load_table(&mut layouter)?;
+
+        let min = instance.load(layouter.namespace(|| "load min"), "min", INSTANCES.row("min"))?;
+        let max = instance.load(layouter.namespace(|| "load max"), "max", INSTANCES.row("max"))?;
+        let budget = instance.load(layouter.namespace(|| "load budget"), "budget", INSTANCES.row("budget"))?;
+        let salaries = self
+            .salaries
+            .iter()
+            .enumerate()
+            .map(|(i, salary)| add_many.words().assign(layouter.namespace(|| format!("salary_{}", i)), *salary))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // 1. min <= salary_i <= max, as one batch of range checks
+        let mut margins = Vec::with_capacity(2 * salaries.len());
+        for (i, salary) in salaries.iter().enumerate() {
+            margins.push(arith.sub(layouter.namespace(|| format!("salary_{} - min", i)), &salary.word, &min)?);
+            margins.push(arith.sub(layouter.namespace(|| format!("max - salary_{}", i)), &max, &salary.word)?);
+        }
+        decompose.assign_many(layouter.namespace(|| "within band"), &margins, SALARY_BITS)?;
+
+        // 2. total <= budget
+        let (sum, carry) = add_many.add_many(layouter.namespace(|| "total"), &salaries)?;
+        let carry = arith.scale(layouter.namespace(|| "carry * 2^32"), &carry.word, Fp::from(1u64 << 32))?;
+        let total = arith.add(layouter.namespace(|| "sum + carry * 2^32"), &sum.word, &carry)?;
+        let left = arith.sub(layouter.namespace(|| "budget - total"), &budget, &total)?;
+        decompose.assign(layouter.namespace(|| "within budget"), &left, BUDGET_BITS)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for PayrollCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let salaries = self
+            .salaries
+            .iter()
+            .map(|salary| known(*salary, "salary"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let public = instances.first().filter(|column| column.len() == 3).ok_or(InvalidWitness::WrongInstanceCount {
+            expected: 3,
+            found: instances.iter().map(Vec::len).sum(),
+        })?;
+        let (min, max, budget) = (public[INSTANCES.row("min")], public[INSTANCES.row("max")], public[INSTANCES.row("budget")]);
+        check_below(min, 1 << SALARY_BITS, "min")?;
+        check_below(max, 1 << SALARY_BITS, "max")?;
+        check_below(budget, 1 << BUDGET_BITS, "budget")?;
+        let (min, max, budget) = (min.get_lower_128() as u32, max.get_lower_128() as u32, budget.get_lower_128() as u64);
+
+        if let Some(i) = salaries.iter().position(|salary| !(min..=max).contains(salary)) {
+            return Err(InvalidWitness::InvalidParameter(format!("salary_{} is outside the band", i)));
+        }
+        if total(&salaries) > budget {
+            return Err(InvalidWitness::InvalidParameter("the payroll is over budget".to_string()));
+        }
+        check_instances(instances, &Self::instances(min, max, budget))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::dev::MockProver;
+
+    use super::{cents, total, PayrollCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 17;
+
+    fn salaries() -> Vec<u32> {
+        ["61250.50", "98500.00", "52000.00", "77833.33"].iter().map(|amount| cents(amount).unwrap()).collect()
+    }
+
+    fn band() -> (u32, u32) {
+        (cents("52000").unwrap(), cents("98500").unwrap())
+    }
+
+    #[test]
+    fn test_cents() {
+        assert_eq!(cents("61250.50"), Some(6_125_050));
+        assert_eq!(cents("61250"), Some(6_125_000));
+        assert_eq!(cents("0.07"), Some(7));
+        for amount in ["61250.5", "61,250.50", "-1.00", ".50", "1.", "42949672.96"] {
+            assert_eq!(cents(amount), None, "{}", amount);
+        }
+    }
+
+    #[test]
+    fn test_payroll() {
+        let salaries = salaries();
+        let (min, max) = band();
+        let circuit = PayrollCircuit::new(&salaries);
+
+        // a budget met to the cent, and one with room to spare
+        for budget in [total(&salaries), cents("300000").unwrap() as u64] {
+            let instances = vec![PayrollCircuit::instances(min, max, budget)];
+            assert_eq!(circuit.validate_witness(&instances), Ok(()));
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_payroll_carry() {
+        // four executives whose total no longer fits a word
+        let salaries = ["39500000.00", "40000000.00", "41250000.99", "42000000.00"].map(|amount| cents(amount).unwrap());
+        assert!(total(&salaries) > u32::MAX as u64);
+        let circuit = PayrollCircuit::new(&salaries);
+        let (min, max) = (cents("39000000").unwrap(), cents("42500000").unwrap());
+
+        let instances = vec![PayrollCircuit::instances(min, max, total(&salaries))];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+
+        let instances = vec![PayrollCircuit::instances(min, max, total(&salaries) - 1)];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_payroll_out_of_band() {
+        let salaries = salaries();
+        let (min, max) = band();
+        let circuit = PayrollCircuit::new(&salaries);
+        let run = |min: u32, max: u32, budget: u64| {
+            let instances = vec![PayrollCircuit::instances(min, max, budget)];
+            assert!(circuit.validate_witness(&instances).is_err());
+            MockProver::run(K, &circuit, instances).unwrap().verify().is_err()
+        };
+
+        // a cent below the band, a cent above it, a cent over budget
+        assert!(run(min + 1, max, total(&salaries)));
+        assert!(run(min, max - 1, total(&salaries)));
+        assert!(run(min, max, total(&salaries) - 1));
+    }
+}
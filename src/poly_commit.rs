@@ -0,0 +1,177 @@
+//! A toy polynomial commitment scheme: commit to the coefficients of a polynomial, then
+//! prove what it evaluates to at a point, without disclosing it.
+//!
+//!     commitment = commit(commit_all([c_0, ..., c_{N-1}]), blinding)
+//!     opening at x:  y = p(x) = c_0 + c_1 x + ... + c_{N-1} x^{N-1}
+//!
+//! The commitment is the Poseidon chain of `commit_all` over the coefficients, salted with
+//! a blinding factor so that a polynomial from a small set can't be found by committing to
+//! each. The circuit reads the commitment and `x` from their instance rows, recomputes the
+//! commitment from the private coefficients, evaluates them at `x` with `HornerChip` and
+//! exposes `y`, so one circuit opens the polynomial at any point.
+//!
+//! This is the shape of an evaluation argument, with a hash and a SNARK standing in for the
+//! algebra of KZG or IPA: the opening proof is a whole proof of this circuit, the prover's
+//! work grows with the degree for every opening, and commitments can't be added together
+//! the way homomorphic ones can. Opening the same commitment at `N` points discloses the
+//! polynomial, as it would in any scheme.
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    gadgets::{
+        commit::{commit, commit_all, CommitChip, CommitConfig},
+        horner::{HornerChip, HornerConfig},
+    },
+    public_inputs::{InstanceChip, InstanceConfig, InstanceLayout},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The public inputs of `PolyCommitCircuit`.
+pub const INSTANCES: InstanceLayout = InstanceLayout(&[&["commitment", "x", "y"]]);
+
+/// The commitment to the polynomial with coefficients `coeffs`, the constant term first.
+pub fn commitment(coeffs: &[Fp], blinding: Fp) -> Fp {
+    commit(commit_all(coeffs), blinding)
+}
+
+/// `p(x)`, computed natively from the powers of `x`.
+pub fn evaluate(coeffs: &[Fp], x: Fp) -> Fp {
+    let mut power = Fp::one();
+    let mut y = Fp::zero();
+    for coeff in coeffs {
+        y += coeff * power;
+        power *= x;
+    }
+    y
+}
+
+/// Opens a committed polynomial with `N` coefficients at a public point.
+pub struct PolyCommitCircuit<const N: usize> {
+    pub coeffs: [Value<Fp>; N],
+    pub blinding: Value<Fp>,
+}
+
+impl<const N: usize> PolyCommitCircuit<N> {
+    pub fn new(coeffs: [Fp; N], blinding: Fp) -> Self {
+        Self {
+            coeffs: coeffs.map(Value::known),
+            blinding: Value::known(blinding),
+        }
+    }
+
+    /// The public inputs, as laid out by `INSTANCES`: the commitment, `x` and `p(x)`.
+    pub fn instances(coeffs: &[Fp; N], blinding: Fp, x: Fp) -> Vec<Fp> {
+        vec![commitment(coeffs, blinding), x, evaluate(coeffs, x)]
+    }
+}
+
+impl<const N: usize> Circuit<Fp> for PolyCommitCircuit<N> {
+    type Config = (CommitConfig, HornerConfig, LoadPrivateConfig, InstanceConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            coeffs: [Value::unknown(); N],
+            blinding: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let commit = CommitChip::configure(meta);
+        let horner = HornerChip::configure(meta, instance);
+        let load = LoadPrivateChip::configure(meta, 1);
+        let public = meta.advice_column();
+        (commit, horner, load, InstanceChip::configure(meta, instance, public))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (commit_config, horner_config, load_config, instance_config) = config;
+        let commit = CommitChip::construct(commit_config);
+        let horner = HornerChip::construct(horner_config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let instance = InstanceChip::construct(instance_config);
+
+        let coeffs = load_chip.load_privates(layouter.namespace(|| "load coefficients"), &self.coeffs)?;
+        let blinding = load_chip.load_private(layouter.namespace(|| "load blinding"), self.blinding)?;
+
+        let hash = commit.commit_all(layouter.namespace(|| "hash coefficients"), &coeffs)?;
+        let commitment = commit.commit(layouter.namespace(|| "commitment"), &hash, &blinding)?;
+        instance.expose(layouter.namespace(|| "commitment"), "commitment", &commitment, INSTANCES.row("commitment"))?;
+
+        let x = instance.load(layouter.namespace(|| "load x"), "x", INSTANCES.row("x"))?;
+        let y = horner.evaluate(layouter.namespace(|| "p(x)"), &coeffs, &x)?;
+        instance.expose(layouter.namespace(|| "y"), "y", &y, INSTANCES.row("y"))?;
+        Ok(())
+    }
+}
+
+impl<const N: usize> ValidateWitness<Fp> for PolyCommitCircuit<N> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let coeffs = self.coeffs.iter().map(|coeff| known(*coeff, "coeff")).collect::<Result<Vec<_>, _>>()?;
+        let blinding = known(self.blinding, "blinding")?;
+        let x = instances.first().and_then(|column| column.get(INSTANCES.row("x"))).copied().ok_or(
+            InvalidWitness::WrongInstanceCount {
+                expected: 3,
+                found: instances.iter().map(Vec::len).sum(),
+            },
+        )?;
+
+        let coeffs: [Fp; N] = coeffs.try_into().expect("N coefficients");
+        check_instances(instances, &Self::instances(&coeffs, blinding, x))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use rand::rngs::OsRng;
+
+    use super::{commitment, evaluate, PolyCommitCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 10;
+
+    // p(x) = 7 - 2x + 3x^3
+    fn coeffs() -> [Fp; 4] {
+        [Fp::from(7), -Fp::from(2), Fp::zero(), Fp::from(3)]
+    }
+
+    #[test]
+    fn test_poly_commit() {
+        let (coeffs, blinding) = (coeffs(), Fp::random(OsRng));
+        assert_eq!(evaluate(&coeffs, Fp::from(5)), Fp::from(372));
+        let circuit = PolyCommitCircuit::new(coeffs, blinding);
+
+        // the same commitment opened at several points, the constant term at 0
+        for x in [Fp::zero(), Fp::from(5), Fp::random(OsRng)] {
+            let instances = vec![PolyCommitCircuit::instances(&coeffs, blinding, x)];
+            assert_eq!(circuit.validate_witness(&instances), Ok(()));
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_poly_commit_wrong_opening() {
+        let (coeffs, blinding) = (coeffs(), Fp::random(OsRng));
+        let committed = commitment(&coeffs, blinding);
+        let x = Fp::from(5);
+
+        // a wrong value at x
+        let circuit = PolyCommitCircuit::new(coeffs, blinding);
+        let instances = vec![vec![committed, x, Fp::from(371)]];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+
+        // another polynomial that agrees at x, against the first one's commitment
+        let other = [-Fp::from(3), Fp::zero(), Fp::zero(), Fp::from(3)];
+        assert_eq!(evaluate(&other, x), Fp::from(372));
+        let circuit = PolyCommitCircuit::new(other, blinding);
+        let instances = vec![vec![committed, x, Fp::from(372)]];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Real proofs of the example circuits, over the Pasta curves with the IPA commitment.
+//!
+//! `prove` and `verify` use a Blake2b transcript. The `_with_transcript` variants take the
+//! transcript as a type parameter, `Blake2bTranscript` or, with the `hash` feature,
+//! `PoseidonTranscript`; a proof only verifies with the transcript it was made with.
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey, SingleVerifier, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptRead, TranscriptWrite},
+};
+use rand::{rngs::OsRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+#[cfg(feature = "hash")]
+pub mod poseidon;
+
+#[cfg(feature = "hash")]
+use poseidon::{PoseidonRead, PoseidonWrite};
+
+/// The Fiat-Shamir transcript a proof is written to and read from.
+pub trait TranscriptScheme {
+    type Writer: TranscriptWrite<EqAffine, Challenge255<EqAffine>>;
+    type Reader<'a>: TranscriptRead<EqAffine, Challenge255<EqAffine>>;
+
+    fn writer() -> Self::Writer;
+    fn finalize(writer: Self::Writer) -> Vec<u8>;
+    fn reader(proof: &[u8]) -> Self::Reader<'_>;
+}
+
+/// The transcript `halo2_proofs` ships with, and the default.
+#[derive(Debug, Clone, Copy)]
+pub struct Blake2bTranscript;
+
+impl TranscriptScheme for Blake2bTranscript {
+    type Writer = Blake2bWrite<Vec<u8>, EqAffine, Challenge255<EqAffine>>;
+    type Reader<'a> = Blake2bRead<&'a [u8], EqAffine, Challenge255<EqAffine>>;
+
+    fn writer() -> Self::Writer {
+        Blake2bWrite::init(vec![])
+    }
+
+    fn finalize(writer: Self::Writer) -> Vec<u8> {
+        writer.finalize()
+    }
+
+    fn reader(proof: &[u8]) -> Self::Reader<'_> {
+        Blake2bRead::init(proof)
+    }
+}
+
+/// A transcript that is cheap to verify in a circuit, see `poseidon`.
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Copy)]
+pub struct PoseidonTranscript;
+
+#[cfg(feature = "hash")]
+impl TranscriptScheme for PoseidonTranscript {
+    type Writer = PoseidonWrite<Vec<u8>>;
+    type Reader<'a> = PoseidonRead<&'a [u8]>;
+
+    fn writer() -> Self::Writer {
+        PoseidonWrite::init(vec![])
+    }
+
+    fn finalize(writer: Self::Writer) -> Vec<u8> {
+        writer.finalize()
+    }
+
+    fn reader(proof: &[u8]) -> Self::Reader<'_> {
+        PoseidonRead::init(proof)
+    }
+}
+
+/// Parameters for `2^k` rows and the proving key of `circuit`.
+pub fn setup<C: Circuit<Fp>>(k: u32, circuit: &C) -> Result<(Params<EqAffine>, ProvingKey<EqAffine>), Error> {
+    let params = Params::new(k);
+    let vk = keygen_vk(&params, circuit)?;
+    let pk = keygen_pk(&params, vk, circuit)?;
+    Ok((params, pk))
+}
+
+/// A proof that `circuit` is satisfied with the given public inputs, one `Vec` per
+/// instance column, blinded with randomness from the OS.
+pub fn prove<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instances: &[Vec<Fp>],
+) -> Result<Vec<u8>, Error> {
+    prove_with_rng(params, pk, circuit, instances, OsRng)
+}
+
+/// Like `prove`, but the proof is a function of `seed`: proving the same circuit and
+/// instances with the same seed gives the same bytes, on every platform. For benchmarks and
+/// golden tests only, a proof whose randomness is known is not zero-knowledge.
+pub fn prove_deterministic<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instances: &[Vec<Fp>],
+    seed: u64,
+) -> Result<Vec<u8>, Error> {
+    prove_with_rng(params, pk, circuit, instances, ChaCha20Rng::seed_from_u64(seed))
+}
+
+/// `prove` with the blinding randomness drawn from `rng`.
+pub fn prove_with_rng<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instances: &[Vec<Fp>],
+    rng: impl RngCore,
+) -> Result<Vec<u8>, Error> {
+    prove_with_transcript::<Blake2bTranscript, C>(params, pk, circuit, instances, rng)
+}
+
+/// `prove_with_rng`, writing the proof to a `T` transcript.
+pub fn prove_with_transcript<T: TranscriptScheme, C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instances: &[Vec<Fp>],
+    rng: impl RngCore,
+) -> Result<Vec<u8>, Error> {
+    let instances: Vec<&[Fp]> = instances.iter().map(Vec::as_slice).collect();
+    let mut transcript = T::writer();
+    create_proof(params, pk, std::slice::from_ref(circuit), &[&instances], rng, &mut transcript)?;
+    Ok(T::finalize(transcript))
+}
+
+pub fn verify(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    instances: &[Vec<Fp>],
+    proof: &[u8],
+) -> Result<(), Error> {
+    verify_with_transcript::<Blake2bTranscript>(params, vk, instances, proof)
+}
+
+/// `verify`, reading the proof from a `T` transcript.
+pub fn verify_with_transcript<T: TranscriptScheme>(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    instances: &[Vec<Fp>],
+    proof: &[u8],
+) -> Result<(), Error> {
+    let instances: Vec<&[Fp]> = instances.iter().map(Vec::as_slice).collect();
+    let mut transcript = T::reader(proof);
+    verify_proof(params, vk, SingleVerifier::new(params), &[&instances], &mut transcript)
+}
+
+#[cfg(all(test, feature = "fibonacci"))]
+mod test {
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    #[cfg(feature = "hash")]
+    use rand::rngs::OsRng;
+
+    use super::{prove, prove_deterministic, setup, verify};
+    #[cfg(feature = "hash")]
+    use super::{prove_with_transcript, verify_with_transcript, Blake2bTranscript, PoseidonTranscript};
+    use crate::fibonaci::example1::FiboCircuit;
+
+    #[test]
+    fn test_prove_deterministic() {
+        let circuit = FiboCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        let (params, pk) = setup(4, &circuit).unwrap();
+
+        let proof = prove_deterministic(&params, &pk, &circuit, &instances, 7).unwrap();
+        assert_eq!(prove_deterministic(&params, &pk, &circuit, &instances, 7).unwrap(), proof);
+        assert_ne!(prove_deterministic(&params, &pk, &circuit, &instances, 8).unwrap(), proof);
+        assert_ne!(prove(&params, &pk, &circuit, &instances).unwrap(), proof);
+        verify(&params, pk.get_vk(), &instances, &proof).unwrap();
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_transcripts_do_not_mix() {
+        let circuit = FiboCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        let instances = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+        let (params, pk) = setup(4, &circuit).unwrap();
+        let vk = pk.get_vk();
+
+        let poseidon = prove_with_transcript::<PoseidonTranscript, _>(&params, &pk, &circuit, &instances, OsRng).unwrap();
+        verify_with_transcript::<PoseidonTranscript>(&params, vk, &instances, &poseidon).unwrap();
+        assert!(verify_with_transcript::<Blake2bTranscript>(&params, vk, &instances, &poseidon).is_err());
+
+        let blake2b = prove(&params, &pk, &circuit, &instances).unwrap();
+        assert_eq!(blake2b.len(), poseidon.len());
+        assert!(verify_with_transcript::<PoseidonTranscript>(&params, vk, &instances, &blake2b).is_err());
+
+        // a Poseidon proof still binds the public inputs
+        let mut wrong = instances.clone();
+        wrong[0][2] += Fp::one();
+        assert!(verify_with_transcript::<PoseidonTranscript>(&params, vk, &wrong, &poseidon).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! A Fiat-Shamir transcript over the Poseidon hash, so that a circuit verifying these proofs
+//! recursively hashes field elements natively instead of emulating Blake2b.
+//!
+//! The sponge works over `Fq`, the base field of the `EqAffine` commitments: points are
+//! absorbed as their two coordinates and scalars (`Fp`, which is smaller than `Fq`) as they
+//! are. Each absorbed element is chained into the state as `H(state, tag, element)`, and a
+//! challenge is `H(state, SQUEEZE, 0)`.
+
+use std::io::{self, Read, Write};
+
+use ff::PrimeField;
+use group::GroupEncoding;
+use halo2_gadgets::poseidon::primitives::{ConstantLength, Hash, P128Pow5T3};
+use halo2_proofs::{
+    arithmetic::{Coordinates, CurveAffine, FieldExt},
+    pasta::{EqAffine, Fp, Fq},
+    transcript::{Challenge255, EncodedChallenge, Transcript, TranscriptRead, TranscriptWrite},
+};
+
+const POINT: u64 = 1;
+const SCALAR: u64 = 2;
+const SQUEEZE: u64 = 3;
+
+#[derive(Debug, Clone)]
+struct Sponge {
+    state: Fq,
+}
+
+impl Sponge {
+    fn new() -> Self {
+        // distinct from the state any absorb or squeeze can produce in practice
+        Self {
+            state: Fq::from_u128(u128::from_le_bytes(*b"halo2-examples:P")),
+        }
+    }
+
+    fn chain(&mut self, tag: u64, element: Fq) {
+        self.state = Hash::<Fq, P128Pow5T3, ConstantLength<3>, 3, 2>::init().hash([self.state, Fq::from(tag), element]);
+    }
+
+    fn absorb_point(&mut self, point: EqAffine) -> io::Result<()> {
+        let coordinates: Coordinates<EqAffine> = Option::from(point.coordinates()).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "cannot write points at infinity to the transcript")
+        })?;
+        self.chain(POINT, *coordinates.x());
+        self.chain(POINT, *coordinates.y());
+        Ok(())
+    }
+
+    fn absorb_scalar(&mut self, scalar: Fp) {
+        let scalar = Fq::from_repr(scalar.to_repr()).expect("p < q, so every Fp fits in Fq");
+        self.chain(SCALAR, scalar);
+    }
+
+    fn squeeze(&mut self) -> Challenge255<EqAffine> {
+        self.chain(SQUEEZE, Fq::zero());
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(self.state.to_repr().as_ref());
+        Challenge255::new(&bytes)
+    }
+}
+
+/// Writes a proof, like `Blake2bWrite`.
+#[derive(Debug, Clone)]
+pub struct PoseidonWrite<W: Write> {
+    sponge: Sponge,
+    writer: W,
+}
+
+impl<W: Write> PoseidonWrite<W> {
+    pub fn init(writer: W) -> Self {
+        Self {
+            sponge: Sponge::new(),
+            writer,
+        }
+    }
+
+    pub fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Transcript<EqAffine, Challenge255<EqAffine>> for PoseidonWrite<W> {
+    fn squeeze_challenge(&mut self) -> Challenge255<EqAffine> {
+        self.sponge.squeeze()
+    }
+
+    fn common_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.sponge.absorb_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.sponge.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl<W: Write> TranscriptWrite<EqAffine, Challenge255<EqAffine>> for PoseidonWrite<W> {
+    fn write_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(point.to_bytes().as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(scalar.to_repr().as_ref())
+    }
+}
+
+/// Reads a proof written by `PoseidonWrite`, like `Blake2bRead`.
+#[derive(Debug, Clone)]
+pub struct PoseidonRead<R: Read> {
+    sponge: Sponge,
+    reader: R,
+}
+
+impl<R: Read> PoseidonRead<R> {
+    pub fn init(reader: R) -> Self {
+        Self {
+            sponge: Sponge::new(),
+            reader,
+        }
+    }
+}
+
+impl<R: Read> Transcript<EqAffine, Challenge255<EqAffine>> for PoseidonRead<R> {
+    fn squeeze_challenge(&mut self) -> Challenge255<EqAffine> {
+        self.sponge.squeeze()
+    }
+
+    fn common_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.sponge.absorb_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.sponge.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read> TranscriptRead<EqAffine, Challenge255<EqAffine>> for PoseidonRead<R> {
+    fn read_point(&mut self) -> io::Result<EqAffine> {
+        let mut compressed = <EqAffine as GroupEncoding>::Repr::default();
+        self.reader.read_exact(compressed.as_mut())?;
+        let point: EqAffine = Option::from(EqAffine::from_bytes(&compressed))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof"))?;
+        self.common_point(point)?;
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<Fp> {
+        let mut data = <Fp as PrimeField>::Repr::default();
+        self.reader.read_exact(data.as_mut())?;
+        let scalar: Fp = Option::from(Fp::from_repr(data))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid field element encoding in proof"))?;
+        self.common_scalar(scalar)?;
+        Ok(scalar)
+    }
+}
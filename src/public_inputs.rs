@@ -0,0 +1,440 @@
+//! Named public inputs.
+//!
+//! A circuit declares the names of its instance rows once, as an `InstanceLayout`. The
+//! circuit looks rows up by name when it exposes a cell, and tests or the witness loader
+//! fill a `PublicInputs` by name, which produces the `Vec<Vec<F>>` `MockProver` and the
+//! prover expect, in the right order.
+//!
+//! In synthesis, a circuit hands the cells it makes public to a `PublicOutputs`, by name,
+//! and has them all constrained against consecutive instance rows at the end, instead of
+//! every chip keeping an instance column and an `expose_public` of its own.
+//!
+//! Over Pasta `Fp`, the outputs can instead be published as a single Poseidon commitment,
+//! `commit_all` of every value in row order, which the verifier recomputes from the values
+//! it knows. One instance cell costs the same to verify whatever the number of outputs.
+//! That needs Poseidon, so the `hash` feature.
+//!
+//! `InstanceChip` reads and writes single instance rows anywhere in synthesis: it copies a
+//! public value into an advice cell any chip can take as an input, and constrains any cell
+//! to any row, for circuits whose public inputs are not all outputs at the end.
+
+use std::{collections::HashMap, error::Error, fmt, marker::PhantomData};
+
+#[cfg(feature = "hash")]
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{AssignedCell, Chip, Layouter},
+    plonk::{self, Advice, Column, ConstraintSystem, Instance},
+};
+
+use crate::{chip::LoadInstructions, error::Halo2ExamplesError};
+#[cfg(feature = "hash")]
+use crate::gadgets::{commit::commit_all, hasher::CircuitHasher};
+
+/// The names of a circuit's instance rows, one slice per instance column.
+#[derive(Debug, Clone, Copy)]
+pub struct InstanceLayout(pub &'static [&'static [&'static str]]);
+
+impl InstanceLayout {
+    /// The `(column, row)` of the slot named `name`.
+    pub fn position(&self, name: &str) -> Option<(usize, usize)> {
+        self.0.iter().enumerate().find_map(|(column, names)| {
+            names.iter().position(|slot| *slot == name).map(|row| (column, row))
+        })
+    }
+
+    /// The row of the slot named `name`, for circuits with a single instance column.
+    ///
+    /// Panics if there is no such slot: the names a circuit exposes are part of its code,
+    /// so a typo is a bug rather than bad input.
+    pub fn row(&self, name: &str) -> usize {
+        match self.position(name) {
+            Some((_, row)) => row,
+            None => panic!("no public input named `{}`", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicInputsError {
+    UnknownSlot(String),
+    MissingSlot(&'static str),
+}
+
+impl fmt::Display for PublicInputsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PublicInputsError::UnknownSlot(name) => write!(f, "no public input named `{}`", name),
+            PublicInputsError::MissingSlot(name) => write!(f, "public input `{}` was not set", name),
+        }
+    }
+}
+
+impl Error for PublicInputsError {}
+
+/// Public input values, filled by slot name.
+#[derive(Debug, Clone)]
+pub struct PublicInputs<F: Field> {
+    layout: InstanceLayout,
+    values: HashMap<String, F>,
+}
+
+impl<F: Field> PublicInputs<F> {
+    pub fn new(layout: InstanceLayout) -> Self {
+        Self {
+            layout,
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn set(mut self, name: impl Into<String>, value: F) -> Self {
+        self.values.insert(name.into(), value);
+        self
+    }
+
+    /// The instance columns, with every slot of the layout set and no other names given.
+    pub fn build(self) -> Result<Vec<Vec<F>>, PublicInputsError> {
+        if let Some(name) = self.values.keys().find(|name| self.layout.position(name).is_none()) {
+            return Err(PublicInputsError::UnknownSlot(name.clone()));
+        }
+
+        self.layout
+            .0
+            .iter()
+            .map(|names| {
+                names
+                    .iter()
+                    .map(|name| self.values.get(*name).copied().ok_or(PublicInputsError::MissingSlot(name)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "hash")]
+impl PublicInputs<Fp> {
+    /// Like `build`, with every value replaced by their commitment, in one instance cell.
+    pub fn build_committed(self) -> Result<Vec<Vec<Fp>>, PublicInputsError> {
+        self.build().map(|instances| committed(&instances))
+    }
+}
+
+/// The instances of a circuit that commits to its public inputs, given the ones it would
+/// expose without: the commitment to all of them, column by column.
+#[cfg(feature = "hash")]
+pub fn committed(instances: &[Vec<Fp>]) -> Vec<Vec<Fp>> {
+    vec![vec![commit_all(&instances.concat())]]
+}
+
+#[derive(Debug, Clone)]
+pub struct PublicOutputsConfig {
+    instance: Column<Instance>,
+}
+
+/// The cells a circuit makes public, collected during synthesis.
+///
+/// The i-th cell exposed goes to row `first_row + i` of the instance column, so a circuit
+/// exposes its outputs in the order of its `InstanceLayout`. Nothing is constrained until
+/// `constrain` is called, usually last in `synthesize`.
+#[derive(Debug)]
+pub struct PublicOutputs<F: FieldExt> {
+    config: PublicOutputsConfig,
+    first_row: usize,
+    cells: Vec<(String, AssignedCell<F, F>)>,
+}
+
+impl<F: FieldExt> PublicOutputs<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> PublicOutputsConfig {
+        meta.enable_equality(instance);
+        PublicOutputsConfig { instance }
+    }
+
+    pub fn construct(config: PublicOutputsConfig) -> Self {
+        Self {
+            config,
+            first_row: 0,
+            cells: Vec::new(),
+        }
+    }
+
+    /// Starts the outputs at `row`, for circuits whose first instance rows are inputs they
+    /// read with `assign_advice_from_instance`.
+    pub fn starting_at(mut self, row: usize) -> Self {
+        self.first_row = row;
+        self
+    }
+
+    /// Makes `cell` public as the next instance row.
+    pub fn expose(&mut self, name: impl Into<String>, cell: &AssignedCell<F, F>) {
+        self.cells.push((name.into(), cell.clone()));
+    }
+
+    /// The instance row of the output named `name`.
+    pub fn row(&self, name: &str) -> Option<usize> {
+        self.cells.iter().position(|(slot, _)| slot == name).map(|i| self.first_row + i)
+    }
+
+    /// The names exposed so far, in row order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.cells.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Constrains every exposed cell to its instance row. Fails if a name was exposed twice.
+    pub fn constrain(&self, mut layouter: impl Layouter<F>) -> Result<(), Halo2ExamplesError> {
+        for (i, (name, cell)) in self.cells.iter().enumerate() {
+            let row = self.first_row + i;
+            if self.row(name) != Some(row) {
+                return Err(Halo2ExamplesError::new("PublicOutputs", plonk::Error::Synthesis).at(row, name.as_str()));
+            }
+            layouter
+                .constrain_instance(cell.cell(), self.config.instance, row)
+                .map_err(|err| Halo2ExamplesError::new("PublicOutputs", err).at(row, name.as_str()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "hash")]
+impl PublicOutputs<Fp> {
+    /// Constrains the commitment to every exposed cell, in row order, to the first instance
+    /// row instead of the cells themselves. The commitment is `hash_many` of `chip`,
+    /// `commit_all` for `CommitChip`.
+    pub fn constrain_committed<H: CircuitHasher<Fp>>(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        chip: &H,
+    ) -> Result<(), Halo2ExamplesError> {
+        let cells: Vec<_> = self.cells.iter().map(|(_, cell)| cell.clone()).collect();
+        let commitment = chip.hash_many(layouter.namespace(|| "commit outputs"), &cells)?;
+        layouter
+            .constrain_instance(commitment.cell(), self.config.instance, self.first_row)
+            .map_err(|err| Halo2ExamplesError::new("PublicOutputs", err).at(self.first_row, "commitment"))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InstanceConfig {
+    instance: Column<Instance>,
+    advice: Column<Advice>,
+}
+
+/// Copies between an instance column and advice cells, at rows the caller picks.
+///
+/// `load` is `assign_advice_from_instance` in a region of its own: the cell it returns holds
+/// the public value, and copy constraints carry it into whichever chip it is passed to, as
+/// with any loaded input. `expose` is `constrain_instance` for one cell, for an output whose
+/// row is given by an `InstanceLayout` rather than by the order of a `PublicOutputs`.
+pub struct InstanceChip<F: FieldExt> {
+    config: InstanceConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Chip<F> for InstanceChip<F> {
+    type Config = InstanceConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> LoadInstructions<F> for InstanceChip<F> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.advice
+    }
+}
+
+impl<F: FieldExt> InstanceChip<F> {
+    pub fn construct(config: InstanceConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Public values are loaded into `advice`. Enables equality on both columns.
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>, advice: Column<Advice>) -> InstanceConfig {
+        meta.enable_equality(instance);
+        meta.enable_equality(advice);
+        InstanceConfig { instance, advice }
+    }
+
+    /// The public value at `row` of the instance column, in an advice cell named `name`.
+    pub fn load(&self, mut layouter: impl Layouter<F>, name: &str, row: usize) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
+        let config = &self.config;
+        layouter
+            .assign_region(
+                || "load instance",
+                |mut region| region.assign_advice_from_instance(|| name, config.instance, row, config.advice, 0),
+            )
+            .map_err(|err| Halo2ExamplesError::new("InstanceChip", err).in_region("load instance").at(row, name))
+    }
+
+    /// Constrains `cell` to row `row` of the instance column. Takes no row of the circuit.
+    pub fn expose(&self, mut layouter: impl Layouter<F>, name: &str, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .constrain_instance(cell.cell(), self.config.instance, row)
+            .map_err(|err| Halo2ExamplesError::new("InstanceChip", err).at(row, name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{InstanceLayout, PublicInputs, PublicInputsError, PublicOutputs, PublicOutputsConfig};
+    use crate::chip::{LoadPrivateChip, LoadPrivateConfig};
+
+    const LAYOUT: InstanceLayout = InstanceLayout(&[&["fib_a", "fib_b", "fib_out"], &["root"]]);
+
+    #[test]
+    fn test_public_inputs() {
+        assert_eq!(LAYOUT.position("fib_out"), Some((0, 2)));
+        assert_eq!(LAYOUT.position("root"), Some((1, 0)));
+        assert_eq!(LAYOUT.row("fib_b"), 1);
+
+        // filled out of order
+        let instances = PublicInputs::new(LAYOUT)
+            .set("root", Fp::from(7))
+            .set("fib_out", Fp::from(55))
+            .set("fib_a", Fp::from(1))
+            .set("fib_b", Fp::from(1))
+            .build();
+        assert_eq!(instances, Ok(vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)], vec![Fp::from(7)]]));
+
+        let missing = PublicInputs::new(LAYOUT).set("fib_a", Fp::from(1)).build();
+        assert_eq!(missing, Err(PublicInputsError::MissingSlot("fib_b")));
+
+        let unknown = PublicInputs::new(LAYOUT).set("nullifier", Fp::from(1)).build();
+        assert_eq!(unknown, Err(PublicInputsError::UnknownSlot("nullifier".to_string())));
+    }
+
+    const OUTPUTS: InstanceLayout = InstanceLayout(&[&["x", "y", "x + y"]]);
+
+    /// Exposes two private values and their sum.
+    struct SumCircuit(Value<Fp>, Value<Fp>);
+
+    impl Circuit<Fp> for SumCircuit {
+        type Config = (LoadPrivateConfig, PublicOutputsConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self(Value::unknown(), Value::unknown())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            (LoadPrivateChip::configure(meta, 1), PublicOutputs::configure(meta, instance))
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (load_config, outputs_config) = config;
+            let load_chip = LoadPrivateChip::construct(load_config);
+            let mut outputs = PublicOutputs::construct(outputs_config);
+
+            let [x, y, sum]: [_; 3] = load_chip
+                .load_privates(layouter.namespace(|| "load"), &[self.0, self.1, self.0 + self.1])?
+                .try_into()
+                .expect("three cells loaded");
+            outputs.expose("x", &x);
+            outputs.expose("y", &y);
+            outputs.expose("x + y", &sum);
+
+            assert!(outputs.names().eq(OUTPUTS.0[0].iter().copied()));
+            assert_eq!(outputs.row("x + y"), Some(OUTPUTS.row("x + y")));
+            outputs.constrain(layouter.namespace(|| "public outputs"))?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_public_outputs() {
+        let circuit = SumCircuit(Value::known(Fp::from(3)), Value::known(Fp::from(4)));
+        let public_inputs = PublicInputs::new(OUTPUTS).set("x", Fp::from(3)).set("y", Fp::from(4));
+
+        let instances = public_inputs.clone().set("x + y", Fp::from(7)).build().unwrap();
+        MockProver::run(4, &circuit, instances).unwrap().assert_satisfied();
+
+        let instances = public_inputs.set("x + y", Fp::from(8)).build().unwrap();
+        assert!(MockProver::run(4, &circuit, instances).unwrap().verify().is_err());
+    }
+
+    // `InstanceChip` feeding a public value to another chip, here `ArithChip`
+    #[cfg(feature = "gadgets")]
+    mod instance_chip {
+        use halo2_proofs::{
+            circuit::{Layouter, SimpleFloorPlanner, Value},
+            dev::MockProver,
+            pasta::Fp,
+            plonk::{Circuit, ConstraintSystem, Error},
+        };
+
+        use crate::{
+            chip::LoadInstructions,
+            gadgets::arith::{ArithChip, ArithConfig},
+            public_inputs::{InstanceChip, InstanceConfig, InstanceLayout, PublicInputs},
+        };
+
+        const SCALED: InstanceLayout = InstanceLayout(&[&["k", "x * k", "x"]]);
+
+        /// Reads a public `k`, and exposes a private `x` and `x * k` out of order.
+        struct ScaleCircuit(Value<Fp>);
+
+        impl Circuit<Fp> for ScaleCircuit {
+            type Config = (InstanceConfig, ArithConfig);
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                Self(Value::unknown())
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let instance = meta.instance_column();
+                let advice = [(); 3].map(|_| meta.advice_column());
+                (InstanceChip::configure(meta, instance, advice[0]), ArithChip::configure(meta, advice))
+            }
+
+            fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+                let (instance_config, arith_config) = config;
+                let instance = InstanceChip::construct(instance_config);
+                let arith = ArithChip::construct(arith_config);
+
+                let k = instance.load(layouter.namespace(|| "k"), "k", SCALED.row("k"))?;
+                let x = instance.load_private(layouter.namespace(|| "x"), self.0)?;
+                let product = arith.mul(layouter.namespace(|| "x * k"), &x, &k)?;
+                instance.expose(layouter.namespace(|| "x"), "x", &x, SCALED.row("x"))?;
+                instance.expose(layouter.namespace(|| "x * k"), "x * k", &product, SCALED.row("x * k"))?;
+                Ok(())
+            }
+        }
+
+        #[test]
+        fn test_instance_chip() {
+            let circuit = ScaleCircuit(Value::known(Fp::from(6)));
+            let instances = |k: u64, product: u64| {
+                PublicInputs::new(SCALED)
+                    .set("k", Fp::from(k))
+                    .set("x * k", Fp::from(product))
+                    .set("x", Fp::from(6))
+                    .build()
+                    .unwrap()
+            };
+
+            MockProver::run(4, &circuit, instances(7, 42)).unwrap().assert_satisfied();
+            assert!(MockProver::run(4, &circuit, instances(7, 43)).unwrap().verify().is_err());
+            // `k` is whatever the instance says
+            MockProver::run(4, &circuit, instances(8, 48)).unwrap().assert_satisfied();
+            assert!(MockProver::run(4, &circuit, instances(8, 42)).unwrap().verify().is_err());
+        }
+    }
+}
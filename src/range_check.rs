@@ -1,2 +1,4 @@
-mod example1;
-mod example2;
\ No newline at end of file
+pub mod example1;
+pub mod example2;
+pub mod dynamic;
+pub mod tagged;
\ No newline at end of file
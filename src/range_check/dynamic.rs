@@ -0,0 +1,289 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation
+};
+
+use super::example2::{RangeConstrained, RangeTableConfig};
+use crate::{
+    chip::{LoadInstructions, LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    params::{ParamsError, RangeCheckParams},
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// This helper checks that the value witnessed in a given cell is within a range `R` chosen
+/// at assign time rather than through a const generic. The only thing fixed at configure
+/// time is the size of the lookup table, 0..2^MAX_BITS, which bounds `R`.
+///
+/// A value `v` lies in `0..R` iff both `v` and `v + (2^MAX_BITS - R)` lie in `0..2^MAX_BITS`.
+/// The offset `2^MAX_BITS - R` is assigned in a fixed column next to the value, so every
+/// row can use a different range:
+///
+///        value     |    offset            |   q_lookup  |  lookup_table  |
+///       ------------------------------------------------------------------
+///          v_0     |  2^MAX_BITS - R_0    |      1      |       0       |
+///          v_1     |  2^MAX_BITS - R_1    |      1      |       1       |
+#[derive(Debug, Clone)]
+pub struct DynamicRangeCheckConfig<F: FieldExt, const MAX_BITS: usize> {
+    value: Column<Advice>,
+    offset: Column<Fixed>,
+    q_lookup: Selector,
+    lookup_table: RangeTableConfig<F, MAX_BITS>,
+}
+
+impl<F: FieldExt, const MAX_BITS: usize> DynamicRangeCheckConfig<F, MAX_BITS> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
+        let offset = meta.fixed_column();
+        let q_lookup = meta.complex_selector();
+        let lookup_table = RangeTableConfig::configure(meta);
+
+        meta.enable_equality(value);
+
+        // v in 0..2^MAX_BITS
+        meta.lookup(|meta| {
+            let q_lookup = meta.query_selector(q_lookup);
+            let value = meta.query_advice(value, Rotation::cur());
+
+            vec![(q_lookup * value, lookup_table.value)]
+        });
+
+        // v + 2^MAX_BITS - R in 0..2^MAX_BITS
+        meta.lookup(|meta| {
+            let q_lookup = meta.query_selector(q_lookup);
+            let value = meta.query_advice(value, Rotation::cur());
+            let offset = meta.query_fixed(offset, Rotation::cur());
+
+            vec![(q_lookup * (value + offset), lookup_table.value)]
+        });
+
+        Self {
+            value,
+            offset,
+            q_lookup,
+            lookup_table,
+        }
+    }
+}
+
+pub struct DynamicRangeCheckChip<F: FieldExt, const MAX_BITS: usize> {
+    config: DynamicRangeCheckConfig<F, MAX_BITS>,
+}
+
+impl<F: FieldExt, const MAX_BITS: usize> Chip<F> for DynamicRangeCheckChip<F, MAX_BITS> {
+    type Config = DynamicRangeCheckConfig<F, MAX_BITS>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt, const MAX_BITS: usize> LoadInstructions<F> for DynamicRangeCheckChip<F, MAX_BITS> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.value
+    }
+}
+
+impl<F: FieldExt, const MAX_BITS: usize> DynamicRangeCheckChip<F, MAX_BITS> {
+    pub fn construct(config: DynamicRangeCheckConfig<F, MAX_BITS>) -> Self {
+        Self { config }
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Halo2ExamplesError> {
+        self.config.lookup_table.load(layouter).in_chip("DynamicRangeCheckChip", "load range check table")
+    }
+
+    /// Constrains `value` to lie in `0..range`, for any `0 < range <= 2^MAX_BITS`; any other
+    /// range is a synthesis error.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        range: usize,
+    ) -> Result<RangeConstrained<F>, Halo2ExamplesError> {
+        if range == 0 || range > 1 << MAX_BITS {
+            return Err(Halo2ExamplesError::new("DynamicRangeCheckChip", Error::Synthesis)
+                .in_region("assign value for dynamic range check"));
+        }
+
+        layouter.assign_region(|| "assign value for dynamic range check", |mut region| {
+            let offset = 0;
+            self.config.q_lookup.enable(&mut region, offset)?;
+
+            region.assign_fixed(
+                || "2^MAX_BITS - range",
+                self.config.offset,
+                offset,
+                || Value::known(F::from(((1 << MAX_BITS) - range) as u64)),
+            )?;
+            value.copy_advice(|| "value", &mut region, self.config.value, offset).map(RangeConstrained)
+        }).in_chip("DynamicRangeCheckChip", "assign value for dynamic range check")
+    }
+
+    /// Constrains `value` to be `num_bits` bits wide, for any `num_bits <= MAX_BITS`.
+    pub fn assign_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<RangeConstrained<F>, Halo2ExamplesError> {
+        // a width too large to shift by is out of the table too, as range 0
+        self.assign(layouter, value, 1usize.checked_shl(num_bits as u32).unwrap_or(0))
+    }
+}
+
+/// Range-checks each value against its own range, all in one circuit.
+#[derive(Default)]
+pub struct DynamicRangeCheckCircuit<F: FieldExt, const MAX_BITS: usize> {
+    pub values: Vec<(Value<Assigned<F>>, usize)>,
+}
+
+impl<F: FieldExt, const MAX_BITS: usize> DynamicRangeCheckCircuit<F, MAX_BITS> {
+    /// Checks every value against the one `range` read from the params, which the
+    /// `0..2^MAX_BITS` table has to hold.
+    pub fn from_params(params: &RangeCheckParams, values: &[Value<Assigned<F>>]) -> Result<Self, ParamsError> {
+        params.validate()?;
+        if params.range > 1 << MAX_BITS {
+            return Err(ParamsError::Invalid(format!(
+                "range {} does not fit a table of 2^{}",
+                params.range, MAX_BITS
+            )));
+        }
+        Ok(Self {
+            values: values.iter().map(|value| (*value, params.range)).collect(),
+        })
+    }
+}
+
+impl<F: FieldExt, const MAX_BITS: usize> Circuit<F> for DynamicRangeCheckCircuit<F, MAX_BITS> {
+    type Config = (DynamicRangeCheckConfig<F, MAX_BITS>, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: self.values.iter().map(|(_, range)| (Value::unknown(), *range)).collect(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        let range_check = DynamicRangeCheckConfig::configure(meta, value);
+        (range_check, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = DynamicRangeCheckChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        chip.load_table(&mut layouter)?;
+
+        for (value, range) in self.values.iter() {
+            let value = load_chip.load_private(layouter.namespace(|| "load value"), value.map(|v| v.evaluate()))?;
+            chip.assign(layouter.namespace(|| format!("range {}", range)), &value, *range)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: FieldExt, const MAX_BITS: usize> ValidateWitness<F> for DynamicRangeCheckCircuit<F, MAX_BITS> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        for (value, range) in self.values.iter() {
+            if *range == 0 || *range > 1 << MAX_BITS {
+                return Err(InvalidWitness::InvalidParameter(format!(
+                    "range {} is not in 1..=2^{}",
+                    range, MAX_BITS
+                )));
+            }
+            check_below(known(*value, "value")?.evaluate(), *range as u128, "value")?;
+        }
+        check_instances(instances, &[])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::{FailureLocation, MockProver, VerifyFailure}, pasta::Fp};
+    use proptest::prelude::*;
+    use super::DynamicRangeCheckCircuit;
+    use crate::params::CircuitParams;
+
+    const K: u32 = 9;
+    const MAX_BITS: usize = 8;
+
+    fn circuit(values: &[(u64, usize)]) -> DynamicRangeCheckCircuit<Fp, MAX_BITS> {
+        DynamicRangeCheckCircuit {
+            values: values
+                .iter()
+                .map(|&(v, range)| (Value::known(Fp::from(v).into()), range))
+                .collect(),
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn prop_dynamic_range_check_accepts_exactly_the_range(range in 1usize..=256, value in 0u64..512) {
+            let prover = MockProver::run(K, &circuit(&[(value, range)]), vec![]).unwrap();
+            prop_assert_eq!(prover.verify().is_ok(), value < range as u64);
+        }
+    }
+
+    #[test]
+    fn test_dynamic_range_check() {
+        // several ranges, including non powers of two, in the same circuit
+        let circuit = circuit(&[(0, 1), (7, 8), (99, 100), (255, 256), (15, 16), (0, 256)]);
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_out_of_dynamic_range_check() {
+        let circuit = circuit(&[(7, 8), (8, 8), (99, 100)]);
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert_eq!(
+            prover.verify(),
+            Err(vec![VerifyFailure::Lookup {
+                lookup_index: 1,
+                location: FailureLocation::InRegion {
+                    region: (4, "assign value for dynamic range check").into(),
+                    offset: 0
+                }
+            }])
+        );
+
+        // outside of the table entirely
+        let circuit = circuit(&[(256, 256)]);
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_dynamic_range_check_from_params() {
+        let params = CircuitParams::from_toml("[range_check]\nrange = 100").unwrap().range_check;
+        let values = |v: u64| vec![Value::known(Fp::from(v).into()), Value::known(Fp::from(0).into())];
+
+        let circuit = DynamicRangeCheckCircuit::<Fp, MAX_BITS>::from_params(&params, &values(99)).unwrap();
+        let prover = MockProver::run(params.k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = DynamicRangeCheckCircuit::<Fp, MAX_BITS>::from_params(&params, &values(100)).unwrap();
+        let prover = MockProver::run(params.k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // the params' range has to fit the compiled table
+        let params = CircuitParams::from_toml("[range_check]\nk = 10\nrange = 300").unwrap().range_check;
+        assert!(DynamicRangeCheckCircuit::<Fp, MAX_BITS>::from_params(&params, &values(0)).is_err());
+    }
+
+    #[test]
+    fn test_range_exceeds_table() {
+        assert!(MockProver::run(K, &circuit(&[(0, 257)]), vec![]).is_err());
+        assert!(MockProver::run(K, &circuit(&[(0, 0)]), vec![]).is_err());
+    }
+}
@@ -10,17 +10,26 @@ use halo2_proofs::{
     arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation
 };
 
+use crate::{
+    chip::{LoadInstructions, LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
 #[derive(Debug, Clone)]
-struct RangeCheckConfig<F: FieldExt, const RANGE: usize> {
+pub struct RangeCheckConfig<F: FieldExt, const RANGE: usize> {
     value: Column<Advice>,
     q_range_check: Selector,
     _marker: PhantomData<F>,
 }
 
 impl<F: FieldExt, const RANGE: usize> RangeCheckConfig<F, RANGE> {
-    fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
+    pub fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
         let q_range_check = meta.selector();
 
+        meta.enable_equality(value);
+
         // Range-Check gate
         // for a value `V` and a range `R`, check that `V` within range of `R`
         // V * (1 - V) * (2 - V) * ... * (R - 1 - V) == 0
@@ -28,13 +37,7 @@ impl<F: FieldExt, const RANGE: usize> RangeCheckConfig<F, RANGE> {
             let q_range_check = meta.query_selector(q_range_check);
             let value = meta.query_advice(value, Rotation::cur());
 
-            let range_check = |range: usize, value: Expression<F>| {
-                assert!(range > 0);
-                
-                (0..range).fold(value.clone(), |acc, num| acc * (Expression::Constant(F::from(num as u64)) - value.clone()))
-            };
-
-            Constraints::with_selector(q_range_check, [("range check", range_check(RANGE, value))])
+            GateBuilder::with_selector(q_range_check).range("range check", value, RANGE)
         });
 
         Self {
@@ -43,29 +46,57 @@ impl<F: FieldExt, const RANGE: usize> RangeCheckConfig<F, RANGE> {
             _marker: PhantomData,
         }
     }
+}
+
+pub struct RangeCheckChip<F: FieldExt, const RANGE: usize> {
+    config: RangeCheckConfig<F, RANGE>,
+}
+
+impl<F: FieldExt, const RANGE: usize> Chip<F> for RangeCheckChip<F, RANGE> {
+    type Config = RangeCheckConfig<F, RANGE>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize> LoadInstructions<F> for RangeCheckChip<F, RANGE> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.value
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize> RangeCheckChip<F, RANGE> {
+    pub fn construct(config: RangeCheckConfig<F, RANGE>) -> Self {
+        Self { config }
+    }
 
-    fn assign(
+    pub fn assign(
         &self,
         mut layouter: impl Layouter<F>,
-        value: Value<Assigned<F>>,
-    ) -> Result<(), Error> {
+        value: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Halo2ExamplesError> {
         layouter.assign_region(|| "assign region", |mut region| {
             let offset = 0;
-            self.q_range_check.enable(&mut region, offset)?;
+            self.config.q_range_check.enable(&mut region, offset)?;
 
-            region.assign_advice(|| "value", self.value, offset, || value)?;
-            Ok(())
-        })
+            value.copy_advice(|| "value", &mut region, self.config.value, offset)
+        }).in_chip("RangeCheckChip", "assign region")
     }
 }
 
 #[derive(Default)]
-struct RangeCheckCircuit<F: FieldExt, const RANGE: usize> {
-    value: Value<Assigned<F>>,
+pub struct RangeCheckCircuit<F: FieldExt, const RANGE: usize> {
+    pub value: Value<Assigned<F>>,
 }
 
 impl<F: FieldExt, const RANGE: usize> Circuit<F> for RangeCheckCircuit<F, RANGE> {
-    type Config = RangeCheckConfig<F, RANGE>;
+    type Config = (RangeCheckConfig<F, RANGE>, LoadPrivateConfig);
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -74,21 +105,49 @@ impl<F: FieldExt, const RANGE: usize> Circuit<F> for RangeCheckCircuit<F, RANGE>
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
         let value = meta.advice_column();
-        RangeCheckConfig::configure(meta, value)
+        let range_check = RangeCheckConfig::configure(meta, value);
+        (range_check, LoadPrivateChip::configure(meta, 1))
     }
 
     fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
-        config.assign(layouter.namespace(|| "assign value"), self.value)?;
+        let (config, load_config) = config;
+        let chip = RangeCheckChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+
+        let value = load_chip.load_private(layouter.namespace(|| "load value"), self.value.map(|v| v.evaluate()))?;
+        chip.assign(layouter.namespace(|| "assign value"), &value)?;
         Ok(())
     }
 }
 
+impl<F: FieldExt, const RANGE: usize> ValidateWitness<F> for RangeCheckCircuit<F, RANGE> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        check_below(known(self.value, "value")?.evaluate(), RANGE as u128, "value")?;
+        check_instances(instances, &[])
+    }
+}
+
 #[cfg(test)]
 mod test {
     use halo2_proofs::{circuit::Value, dev::{FailureLocation, MockProver, VerifyFailure}, pasta::Fp, plonk::Any};
 
+    use proptest::prelude::*;
+
     use super::RangeCheckCircuit;
 
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn prop_range_check1_accepts_exactly_the_range(value in 0u64..16) {
+            let circuit = RangeCheckCircuit::<Fp, 8> {
+                value: Value::known(Fp::from(value).into()),
+            };
+            let prover = MockProver::run(4, &circuit, vec![]).unwrap();
+            prop_assert_eq!(prover.verify().is_ok(), value < 8);
+        }
+    }
+
     #[test]
     fn test_range_check1() {
         let k = 4;
@@ -116,7 +175,7 @@ mod test {
                 Err(vec![VerifyFailure::ConstraintNotSatisfied {
                     constraint: ((0, "Range Check").into(), 0, "range check").into(),
                     location: FailureLocation::InRegion {
-                        region: (0, "assign region").into(),
+                        region: (1, "assign region").into(),
                         offset: 0
                     },
                     cell_values: vec![(((Any::Advice, 0).into(), 0).into(), "0x8".to_string())]
@@ -124,23 +183,4 @@ mod test {
             )
         }
     }
-
-    #[cfg(feature = "dev-graph")]
-    #[test]
-    fn plot_range_check_1() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("range-check-1-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root
-            .titled("Range Check 1 Layout", ("sans-serif", 60))
-            .unwrap();
-
-        let circuit = RangeCheckCircuit::<Fp, 8> {
-            value: Value::unknown(),
-        };
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(3, &circuit, &root)
-            .unwrap();
-    }
 }
@@ -13,20 +13,28 @@ use halo2_proofs::{
     arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation
 };
 
+use crate::{
+    chip::{LoadInstructions, LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    table,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
 #[derive(Debug, Clone)]
 /// A range-constrained value in the circuit produced by the RangeCheckConfig.
-struct RangeConstrained<F: FieldExt>(AssignedCell<Assigned<F>, F>);
+pub struct RangeConstrained<F: FieldExt>(pub AssignedCell<F, F>);
 
 /// A lookup table of values from 0..(1 << NUM_BITS).
 /// 
 #[derive(Debug, Clone)]
 pub struct RangeTableConfig<F: FieldExt, const NUM_BITS: usize> {
-    value: TableColumn,
+    pub value: TableColumn,
     _marker: PhantomData<F>,
 }
 
 impl<F: FieldExt, const NUM_BITS: usize> RangeTableConfig<F, NUM_BITS> {
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
         let value = meta.lookup_table_column();
 
         Self {
@@ -35,18 +43,13 @@ impl<F: FieldExt, const NUM_BITS: usize> RangeTableConfig<F, NUM_BITS> {
         }
     }
 
-    fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        layouter.assign_table(|| "load range check table", |mut table| {
-            for value in 0..(1 << NUM_BITS) {
-                table.assign_cell(|| "num bits", self.value, value, || Value::known(F::from(value as u64)))?;
-            }
-            Ok(())
-        })
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        table::load_range_table(layouter, self.value, 1 << NUM_BITS)
     }
 }
 
 #[derive(Debug, Clone)]
-struct RangeCheckConfig<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> {
+pub struct RangeCheckConfig<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> {
     value: Column<Advice>,
     lookup_table: RangeTableConfig<F, NUM_BITS>,
     q_range_check: Selector,
@@ -55,11 +58,13 @@ struct RangeCheckConfig<F: FieldExt, const RANGE: usize, const NUM_BITS: usize>
 }
 
 impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> RangeCheckConfig<F, RANGE, NUM_BITS> {
-    fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
+    pub fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
         let q_range_check = meta.selector();
         let q_lookup = meta.complex_selector();
         let lookup_table = RangeTableConfig::configure(meta);
 
+        meta.enable_equality(value);
+
         // Range-Check gate
         // for a value `V` and a range `R`, check that `V` within range of `R`
         // V * (1 - V) * (2 - V) * ... * (R - 1 - V) == 0
@@ -67,13 +72,7 @@ impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> RangeCheckConfig<F,
             let q_range_check = meta.query_selector(q_range_check);
             let value = meta.query_advice(value, Rotation::cur());
 
-            let range_check = |range: usize, value: Expression<F>| {
-                assert!(range > 0);
-                
-                (1..range).fold(value.clone(), |acc, num| acc * (Expression::Constant(F::from(num as u64)) - value.clone()))
-            };
-
-            Constraints::with_selector(q_range_check, [("range check", range_check(RANGE, value))])
+            GateBuilder::with_selector(q_range_check).range("range check", value, RANGE)
         });
 
         // lookup table
@@ -92,42 +91,75 @@ impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> RangeCheckConfig<F,
             _marker: PhantomData,
         }
     }
+}
+
+pub struct RangeCheckChip<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> {
+    config: RangeCheckConfig<F, RANGE, NUM_BITS>,
+}
 
-    fn assign_simple(
+impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> Chip<F> for RangeCheckChip<F, RANGE, NUM_BITS> {
+    type Config = RangeCheckConfig<F, RANGE, NUM_BITS>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> LoadInstructions<F> for RangeCheckChip<F, RANGE, NUM_BITS> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.value
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> RangeCheckChip<F, RANGE, NUM_BITS> {
+    pub fn construct(config: RangeCheckConfig<F, RANGE, NUM_BITS>) -> Self {
+        Self { config }
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Halo2ExamplesError> {
+        self.config.lookup_table.load(layouter).in_chip("RangeCheckChip", "load range check table")
+    }
+
+    pub fn assign_simple(
         &self,
         mut layouter: impl Layouter<F>,
-        value: Value<Assigned<F>>,
-    ) -> Result<RangeConstrained<F>, Error> {
+        value: &AssignedCell<F, F>,
+    ) -> Result<RangeConstrained<F>, Halo2ExamplesError> {
         layouter.assign_region(|| "assign value for simple range check", |mut region| {
             let offset = 0;
-            self.q_range_check.enable(&mut region, offset)?;
+            self.config.q_range_check.enable(&mut region, offset)?;
 
-            region.assign_advice(|| "value", self.value, offset, || value).map(RangeConstrained)
-        })
+            value.copy_advice(|| "value", &mut region, self.config.value, offset).map(RangeConstrained)
+        }).in_chip("RangeCheckChip", "assign value for simple range check")
     }
 
-    fn assign_lookup(
+    pub fn assign_lookup(
         &self,
         mut layouter: impl Layouter<F>,
-        value: Value<Assigned<F>>,
-    ) -> Result<RangeConstrained<F>, Error> {
+        value: &AssignedCell<F, F>,
+    ) -> Result<RangeConstrained<F>, Halo2ExamplesError> {
         layouter.assign_region(|| "assign value for lookup range check", |mut region| {
             let offset = 0;
-            self.q_lookup.enable(&mut region, offset)?;
+            self.config.q_lookup.enable(&mut region, offset)?;
 
-            region.assign_advice(|| "value", self.value, offset, || value).map(RangeConstrained)
-        })
+            value.copy_advice(|| "value", &mut region, self.config.value, offset).map(RangeConstrained)
+        }).in_chip("RangeCheckChip", "assign value for lookup range check")
     }
 }
 
 #[derive(Default)]
-struct RangeCheckCircuit<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> {
-    value: Value<Assigned<F>>,
-    lookup_value: Value<Assigned<F>>,
+pub struct RangeCheckCircuit<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> {
+    pub value: Value<Assigned<F>>,
+    pub lookup_value: Value<Assigned<F>>,
 }
 
 impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> Circuit<F> for RangeCheckCircuit<F, RANGE, NUM_BITS> {
-    type Config = RangeCheckConfig<F, RANGE, NUM_BITS>;
+    type Config = (RangeCheckConfig<F, RANGE, NUM_BITS>, LoadPrivateConfig);
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -136,22 +168,38 @@ impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> Circuit<F> for Rang
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
         let value = meta.advice_column();
-        RangeCheckConfig::configure(meta, value)
+        let range_check = RangeCheckConfig::configure(meta, value);
+        (range_check, LoadPrivateChip::configure(meta, 1))
     }
 
     fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
-        config.lookup_table.load(&mut layouter)?;
+        let (config, load_config) = config;
+        let chip = RangeCheckChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        chip.load_table(&mut layouter)?;
 
-        config.assign_simple(layouter.namespace(|| "assign value"), self.value)?;
-        config.assign_lookup(layouter.namespace(|| "assign lookup"), self.lookup_value)?;
+        let value = load_chip.load_private(layouter.namespace(|| "load value"), self.value.map(|v| v.evaluate()))?;
+        let lookup_value = load_chip.load_private(layouter.namespace(|| "load lookup value"), self.lookup_value.map(|v| v.evaluate()))?;
+
+        chip.assign_simple(layouter.namespace(|| "assign value"), &value)?;
+        chip.assign_lookup(layouter.namespace(|| "assign lookup"), &lookup_value)?;
         Ok(())
     }
 }
 
+impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> ValidateWitness<F> for RangeCheckCircuit<F, RANGE, NUM_BITS> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        check_below(known(self.value, "value")?.evaluate(), RANGE as u128, "value")?;
+        check_below(known(self.lookup_value, "lookup_value")?.evaluate(), 1 << NUM_BITS, "lookup_value")?;
+        check_instances(instances, &[])
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use halo2_proofs::{circuit::Value, dev::{FailureLocation, MockProver, VerifyFailure}, pasta::Fp, plonk::Any};
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
     use super::RangeCheckCircuit;
+    use crate::{assert_constraint_fails, dev::layout_diff::assert_region_rows};
 
     const K: u32 = 9;
     const RANGE: usize = 8;
@@ -172,6 +220,9 @@ mod test {
                 prover.assert_satisfied();
             }
         }
+
+        let circuit = RangeCheckCircuit::<Fp, RANGE, NUM_BITS>::default();
+        assert_region_rows(&circuit, &[("assign value for simple range check", 1), ("assign value for lookup range check", 1)]);
     }
 
     #[test]
@@ -181,46 +232,7 @@ mod test {
             lookup_value: Value::known(Fp::from((1 << NUM_BITS) as u64).into()),
         };
 
-        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
-        assert_eq!(
-            prover.verify(),
-            Err(vec![
-                VerifyFailure::ConstraintNotSatisfied {
-                    constraint: ((0, "Range Check").into(), 0, "range check").into(),
-                    location: FailureLocation::InRegion {
-                        region: (1, "assign value for simple range check").into(),
-                        offset: 0
-                    },
-                    cell_values: vec![(((Any::Advice, 0).into(), 0).into(), format!("0x{:x}", RANGE))]
-                },
-                VerifyFailure::Lookup {
-                    lookup_index: 0,
-                    location: FailureLocation::InRegion {
-                        region: (2, "assign value for lookup range check").into(),
-                        offset: 0
-                    }
-                }
-            ])
-        )
-    }
-
-    #[cfg(feature = "dev-graph")]
-    #[test]
-    fn plot_range_check_1() {
-        use plotters::prelude::*;
-
-        let root = BitMapBackend::new("range-check-1-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root
-            .titled("Range Check 1 Layout", ("sans-serif", 60))
-            .unwrap();
-
-        let circuit = RangeCheckCircuit::<Fp, RANGE, NUM_BITS> {
-            value: Value::unknown(),
-            lookup_value: Value::unknown(),
-        };
-        halo2_proofs::dev::CircuitLayout::default()
-            .render(3, &circuit, &root)
-            .unwrap();
+        assert_constraint_fails!(circuit, K, vec![], "Range Check", "assign value for simple range check");
+        assert_constraint_fails!(circuit, K, vec![], lookup 0, "assign value for lookup range check");
     }
 }
@@ -0,0 +1,230 @@
+use std::marker::PhantomData;
+
+/// The range check of `example2`, with its two selectors folded into one fixed column.
+///
+/// `q_range_check` and `q_lookup` are never enabled on the same row, so a single `tag`
+/// column can say which check applies: 1 for the range-check expression, 2 for the lookup,
+/// 0 for neither. Each check recovers its own selector from the tag with a polynomial that
+/// is 1 on its tag and 0 on the other two:
+///
+///     is_range_check = tag * (2 - tag)
+///     is_lookup      = tag * (tag - 1) / 2
+///
+///        value     |   tag   |  lookup_table  |
+///       ---------------------------------------
+///          v_0     |    1    |       0        |
+///          v_1     |    2    |       1        |
+///
+/// This saves a fixed column over the two-selector version (the complex `q_lookup` cannot be
+/// combined with the simple `q_range_check`), at the cost of raising the degree of both
+/// checks by one. See `dev::stats` for how that trades off in proof size and proving time.
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::example2::{RangeConstrained, RangeTableConfig};
+use crate::{
+    chip::{LoadInstructions, LoadPrivateChip, LoadPrivateConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gate::GateBuilder,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+const RANGE_CHECK: u64 = 1;
+const LOOKUP: u64 = 2;
+
+#[derive(Debug, Clone)]
+pub struct TaggedRangeCheckConfig<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> {
+    value: Column<Advice>,
+    tag: Column<Fixed>,
+    lookup_table: RangeTableConfig<F, NUM_BITS>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> TaggedRangeCheckConfig<F, RANGE, NUM_BITS> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
+        let tag = meta.fixed_column();
+        let lookup_table = RangeTableConfig::configure(meta);
+
+        meta.enable_equality(value);
+
+        let is_range_check = |tag: Expression<F>| tag.clone() * (Expression::Constant(F::from(2)) - tag);
+        let is_lookup = |tag: Expression<F>| tag.clone() * (tag - Expression::Constant(F::one())) * F::TWO_INV;
+
+        meta.create_gate("Range Check", |meta| {
+            let tag = meta.query_fixed(tag, Rotation::cur());
+            let value = meta.query_advice(value, Rotation::cur());
+
+            GateBuilder::with_selector(is_range_check(tag)).range("range check", value, RANGE)
+        });
+
+        meta.lookup(|meta| {
+            let tag = meta.query_fixed(tag, Rotation::cur());
+            let value = meta.query_advice(value, Rotation::cur());
+
+            vec![(is_lookup(tag) * value, lookup_table.value)]
+        });
+
+        Self {
+            value,
+            tag,
+            lookup_table,
+            _marker: PhantomData,
+        }
+    }
+}
+
+pub struct TaggedRangeCheckChip<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> {
+    config: TaggedRangeCheckConfig<F, RANGE, NUM_BITS>,
+}
+
+impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> Chip<F> for TaggedRangeCheckChip<F, RANGE, NUM_BITS> {
+    type Config = TaggedRangeCheckConfig<F, RANGE, NUM_BITS>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> LoadInstructions<F> for TaggedRangeCheckChip<F, RANGE, NUM_BITS> {
+    fn input_column(&self) -> Column<Advice> {
+        self.config.value
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> TaggedRangeCheckChip<F, RANGE, NUM_BITS> {
+    pub fn construct(config: TaggedRangeCheckConfig<F, RANGE, NUM_BITS>) -> Self {
+        Self { config }
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Halo2ExamplesError> {
+        self.config.lookup_table.load(layouter).in_chip("TaggedRangeCheckChip", "load range check table")
+    }
+
+    pub fn assign_simple(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<RangeConstrained<F>, Halo2ExamplesError> {
+        self.assign_tagged(layouter, value, RANGE_CHECK, "assign value for simple range check")
+    }
+
+    pub fn assign_lookup(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<RangeConstrained<F>, Halo2ExamplesError> {
+        self.assign_tagged(layouter, value, LOOKUP, "assign value for lookup range check")
+    }
+
+    fn assign_tagged(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        tag: u64,
+        region_name: &str,
+    ) -> Result<RangeConstrained<F>, Halo2ExamplesError> {
+        layouter.assign_region(|| region_name, |mut region| {
+            let offset = 0;
+            region.assign_fixed(|| "tag", self.config.tag, offset, || Value::known(F::from(tag)))?;
+
+            value.copy_advice(|| "value", &mut region, self.config.value, offset).map(RangeConstrained)
+        }).in_chip("TaggedRangeCheckChip", region_name)
+    }
+}
+
+#[derive(Default)]
+pub struct TaggedRangeCheckCircuit<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> {
+    pub value: Value<Assigned<F>>,
+    pub lookup_value: Value<Assigned<F>>,
+}
+
+impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> Circuit<F> for TaggedRangeCheckCircuit<F, RANGE, NUM_BITS> {
+    type Config = (TaggedRangeCheckConfig<F, RANGE, NUM_BITS>, LoadPrivateConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        let range_check = TaggedRangeCheckConfig::configure(meta, value);
+        (range_check, LoadPrivateChip::configure(meta, 1))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (config, load_config) = config;
+        let chip = TaggedRangeCheckChip::construct(config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        chip.load_table(&mut layouter)?;
+
+        let value = load_chip.load_private(layouter.namespace(|| "load value"), self.value.map(|v| v.evaluate()))?;
+        let lookup_value = load_chip.load_private(layouter.namespace(|| "load lookup value"), self.lookup_value.map(|v| v.evaluate()))?;
+
+        chip.assign_simple(layouter.namespace(|| "assign value"), &value)?;
+        chip.assign_lookup(layouter.namespace(|| "assign lookup"), &lookup_value)?;
+        Ok(())
+    }
+}
+
+impl<F: FieldExt, const RANGE: usize, const NUM_BITS: usize> ValidateWitness<F> for TaggedRangeCheckCircuit<F, RANGE, NUM_BITS> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness> {
+        check_below(known(self.value, "value")?.evaluate(), RANGE as u128, "value")?;
+        check_below(known(self.lookup_value, "lookup_value")?.evaluate(), 1 << NUM_BITS, "lookup_value")?;
+        check_instances(instances, &[])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp, plonk::{Circuit, ConstraintSystem}};
+    use super::TaggedRangeCheckCircuit;
+    use crate::{assert_constraint_fails, range_check::example2::RangeCheckCircuit};
+
+    const K: u32 = 9;
+    const RANGE: usize = 8;
+    const NUM_BITS: usize = 4;
+
+    #[test]
+    fn test_tagged_range_check() {
+        for i in 0..RANGE {
+            for j in 0..(1 << NUM_BITS) {
+                let circuit = TaggedRangeCheckCircuit::<Fp, RANGE, NUM_BITS> {
+                    value: Value::known(Fp::from(i as u64).into()),
+                    lookup_value: Value::known(Fp::from(j as u64).into()),
+                };
+
+                let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+                prover.assert_satisfied();
+            }
+        }
+    }
+
+    #[test]
+    fn test_tagged_out_of_range_check() {
+        let circuit = TaggedRangeCheckCircuit::<Fp, RANGE, NUM_BITS> {
+            value: Value::known(Fp::from(RANGE as u64).into()),
+            lookup_value: Value::known(Fp::from((1 << NUM_BITS) as u64).into()),
+        };
+
+        assert_constraint_fails!(circuit, K, vec![], "Range Check", "assign value for simple range check");
+        assert_constraint_fails!(circuit, K, vec![], lookup 0, "assign value for lookup range check");
+    }
+
+    #[test]
+    fn test_tagged_columns() {
+        // two selectors, which keygen turns into two fixed columns since `q_lookup` is complex
+        let mut two_selectors = ConstraintSystem::<Fp>::default();
+        RangeCheckCircuit::<Fp, RANGE, NUM_BITS>::configure(&mut two_selectors);
+        assert_eq!((two_selectors.num_selectors(), two_selectors.num_fixed_columns()), (2, 0));
+
+        let mut tagged = ConstraintSystem::<Fp>::default();
+        TaggedRangeCheckCircuit::<Fp, RANGE, NUM_BITS>::configure(&mut tagged);
+        assert_eq!((tagged.num_selectors(), tagged.num_fixed_columns()), (0, 1));
+        assert_eq!(tagged.degree(), two_selectors.degree() + 1);
+    }
+}
@@ -0,0 +1,26 @@
+//! Plain Rust versions of what the example circuits compute, written from the definitions
+//! rather than from the chips' own witness code, so that a test comparing a circuit's output
+//! with one of them checks the circuit against something independent of it. The binaries
+//! use them for the public inputs of the examples they run.
+
+pub mod fibonacci;
+pub mod running_sum;
+#[cfg(feature = "hash")]
+pub mod poseidon;
+#[cfg(feature = "hash")]
+pub mod merkle;
+pub mod aes;
+pub mod chacha;
+pub mod blake2s;
+pub mod sha256;
+
+pub use aes::ref_aes_round;
+pub use blake2s::{ref_blake2s, ref_blake2s_compress};
+pub use chacha::ref_quarter_round;
+pub use fibonacci::ref_fibonacci;
+#[cfg(feature = "hash")]
+pub use merkle::ref_merkle_root;
+#[cfg(feature = "hash")]
+pub use poseidon::ref_poseidon;
+pub use running_sum::ref_running_sum;
+pub use sha256::{ref_hmac_sha256, ref_sha256};
@@ -0,0 +1,60 @@
+/// Multiplication by `x` in AES's `GF(2^8)`, modulo `x^8 + x^4 + x^3 + x + 1`.
+pub fn xtime(a: u8) -> u8 {
+    (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    product
+}
+
+/// The AES S-box: the inverse in `GF(2^8)`, 0 for 0, followed by the affine map.
+pub fn sbox(a: u8) -> u8 {
+    // a^-1 = a^254 = a^2 * a^4 * ... * a^128, which is also 0 for 0
+    let (mut inverse, mut square) = (1, a);
+    for _ in 0..7 {
+        square = gf_mul(square, square);
+        inverse = gf_mul(inverse, square);
+    }
+    inverse ^ inverse.rotate_left(1) ^ inverse.rotate_left(2) ^ inverse.rotate_left(3) ^ inverse.rotate_left(4) ^ 0x63
+}
+
+/// One full AES round with the initial key addition, `AddRoundKey(MixColumns(ShiftRows(
+/// SubBytes(state ^ key_0))), key_1)`, over a state given column by column as in FIPS-197.
+pub fn ref_aes_round(state: [u8; 16], key_0: [u8; 16], key_1: [u8; 16]) -> [u8; 16] {
+    let state: [u8; 16] = std::array::from_fn(|i| sbox(state[i] ^ key_0[i]));
+    // row r moves r columns to the left
+    let state: [u8; 16] = std::array::from_fn(|i| state[(i + 4 * (i % 4)) % 16]);
+    std::array::from_fn(|i| {
+        let (c, r) = (i / 4, i % 4);
+        let a = |j: usize| state[4 * c + (r + j) % 4];
+        xtime(a(0)) ^ xtime(a(1)) ^ a(1) ^ a(2) ^ a(3) ^ key_1[i]
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ref_aes_round, sbox};
+
+    #[test]
+    fn test_sbox() {
+        assert_eq!([0x00, 0x01, 0x53, 0xff].map(sbox), [0x63, 0x7c, 0xed, 0x16]);
+    }
+
+    #[test]
+    fn test_ref_aes_round() {
+        // the first round of the example in FIPS-197, appendix B
+        let input = [0x32, 0x43, 0xf6, 0xa8, 0x88, 0x5a, 0x30, 0x8d, 0x31, 0x31, 0x98, 0xa2, 0xe0, 0x37, 0x07, 0x34];
+        let key_0 = [0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c];
+        let key_1 = [0xa0, 0xfa, 0xfe, 0x17, 0x88, 0x54, 0x2c, 0xb1, 0x23, 0xa3, 0x39, 0x39, 0x2a, 0x6c, 0x76, 0x05];
+        let output = [0xa4, 0x9c, 0x7f, 0xf2, 0x68, 0x9f, 0x35, 0x2b, 0x6b, 0x5b, 0xea, 0x43, 0x02, 0x6a, 0x50, 0x49];
+        assert_eq!(ref_aes_round(input, key_0, key_1), output);
+    }
+}
@@ -0,0 +1,116 @@
+/// The initialization vector of Blake2s, the same as SHA-256's initial hash value.
+pub const IV: [u32; 8] = [
+    0x6a09_e667, 0xbb67_ae85, 0x3c6e_f372, 0xa54f_f53a, 0x510e_527f, 0x9b05_688c, 0x1f83_d9ab, 0x5be0_cd19,
+];
+
+/// The message word permutation of every round.
+pub const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The `[a, b, c, d]` of the column steps of a round, then of the diagonal steps.
+pub const MIX_INDICES: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+/// The initial hash value of unkeyed Blake2s with a 32-byte digest.
+pub fn initial_hash() -> [u32; 8] {
+    let mut h = IV;
+    h[0] ^= 0x0101_0020;
+    h
+}
+
+/// The mixing function `G` of RFC 7693, section 3.1.
+fn mix(v: &mut [u32; 16], [a, b, c, d]: [usize; 4], x: u32, y: u32) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(12);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(8);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(7);
+}
+
+/// The compression function `F` of RFC 7693, section 3.2: `h` after the block `m`, with
+/// `t` bytes hashed so far including this block.
+pub fn ref_blake2s_compress(h: [u32; 8], m: [u32; 16], t: u64, last: bool) -> [u32; 8] {
+    let mut v = [0; 16];
+    v[..8].copy_from_slice(&h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= t as u32;
+    v[13] ^= (t >> 32) as u32;
+    if last {
+        v[14] ^= u32::MAX;
+    }
+
+    for sigma in SIGMA {
+        for (i, indices) in MIX_INDICES.into_iter().enumerate() {
+            mix(&mut v, indices, m[sigma[2 * i]], m[sigma[2 * i + 1]]);
+        }
+    }
+    std::array::from_fn(|i| h[i] ^ v[i] ^ v[i + 8])
+}
+
+/// The 16 little-endian words of a 64-byte block.
+pub fn block_words(block: &[u8; 64]) -> [u32; 16] {
+    std::array::from_fn(|i| u32::from_le_bytes(block[4 * i..4 * i + 4].try_into().unwrap()))
+}
+
+/// The unkeyed, 32-byte Blake2s digest of `data`.
+pub fn ref_blake2s(data: &[u8]) -> [u8; 32] {
+    let mut h = initial_hash();
+    let blocks = data.len().max(1).div_ceil(64);
+    for (i, chunk) in data.chunks(64).chain(data.is_empty().then_some(&[][..])).enumerate() {
+        let mut block = [0; 64];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let t = (64 * i + chunk.len()) as u64;
+        h = ref_blake2s_compress(h, block_words(&block), t, i + 1 == blocks);
+    }
+
+    let mut digest = [0; 32];
+    for (bytes, word) in digest.chunks_mut(4).zip(h) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod test {
+    use blake2::{Blake2s256, Digest};
+
+    use super::ref_blake2s;
+
+    #[test]
+    fn test_ref_blake2s() {
+        // RFC 7693, appendix B
+        assert_eq!(
+            ref_blake2s(b"abc"),
+            [
+                0x50, 0x8c, 0x5e, 0x8c, 0x32, 0x7c, 0x14, 0xe2, 0xe1, 0xa7, 0x2b, 0xa3, 0x4e, 0xeb, 0x45, 0x2f, 0x37, 0x45,
+                0x8b, 0x20, 0x9e, 0xd6, 0x3a, 0x29, 0x4d, 0x99, 0x9b, 0x4c, 0x86, 0x67, 0x59, 0x82,
+            ]
+        );
+
+        let data: Vec<u8> = (0..200).map(|i| (i * 7 + 3) as u8).collect();
+        for len in [0, 1, 63, 64, 65, 128, 200] {
+            assert_eq!(ref_blake2s(&data[..len])[..], Blake2s256::digest(&data[..len])[..], "length {}", len);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+/// The ChaCha quarter round of RFC 8439, section 2.1, on `[a, b, c, d]`.
+pub fn ref_quarter_round([mut a, mut b, mut c, mut d]: [u32; 4]) -> [u32; 4] {
+    a = a.wrapping_add(b);
+    d = (d ^ a).rotate_left(16);
+    c = c.wrapping_add(d);
+    b = (b ^ c).rotate_left(12);
+    a = a.wrapping_add(b);
+    d = (d ^ a).rotate_left(8);
+    c = c.wrapping_add(d);
+    b = (b ^ c).rotate_left(7);
+    [a, b, c, d]
+}
+
+#[cfg(test)]
+mod test {
+    use super::ref_quarter_round;
+
+    #[test]
+    fn test_ref_quarter_round() {
+        // RFC 8439, section 2.1.1
+        assert_eq!(
+            ref_quarter_round([0x1111_1111, 0x0102_0304, 0x9b8d_6f43, 0x0123_4567]),
+            [0xea2a_92f4, 0xcb1c_f8ce, 0x4581_472e, 0x5881_c4bb]
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use ff::Field;
+
+/// The `n`-th of the terms `a, b, a + b, ...`, counting `a` as the first, i.e. the output of
+/// a Fibonacci circuit of `n` rows. `n` is at least 2, the two seeds.
+pub fn ref_fibonacci<F: Field>(a: F, b: F, n: usize) -> F {
+    assert!(n >= 2, "a Fibonacci sequence starts with its two seeds");
+    let mut terms = (a, b);
+    for _ in 2..n {
+        terms = (terms.1, terms.0 + terms.1);
+    }
+    terms.1
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::pasta::Fp;
+
+    use super::ref_fibonacci;
+
+    #[test]
+    fn test_ref_fibonacci() {
+        let terms: Vec<_> = (2..=10).map(|n| ref_fibonacci(Fp::from(1), Fp::from(1), n)).collect();
+        assert_eq!(terms, [1, 2, 3, 5, 8, 13, 21, 34, 55].map(Fp::from));
+        assert_eq!(ref_fibonacci(Fp::from(2), Fp::from(5), 4), Fp::from(12));
+    }
+}
@@ -0,0 +1,34 @@
+use halo2_proofs::pasta::Fp;
+
+use super::ref_poseidon;
+
+/// The root of the Poseidon Merkle tree in which `leaf` sits at `index`, given its siblings
+/// bottom up: on level `i` the node is the right child when bit `i` of `index` is set.
+pub fn ref_merkle_root(leaf: Fp, index: u64, siblings: &[Fp]) -> Fp {
+    let mut node = leaf;
+    for (i, sibling) in siblings.iter().enumerate() {
+        node = match (index >> i) & 1 {
+            0 => ref_poseidon(node, *sibling),
+            _ => ref_poseidon(*sibling, node),
+        };
+    }
+    node
+}
+
+#[cfg(all(test, feature = "merkle"))]
+mod test {
+    use halo2_proofs::pasta::Fp;
+
+    use super::ref_merkle_root;
+    use crate::gadgets::merkle::MerkleTree;
+
+    #[test]
+    fn test_ref_merkle_root() {
+        let leaves: Vec<_> = (0..6).map(|i| Fp::from(100 + i)).collect();
+        let tree = MerkleTree::new(3, &leaves);
+        for index in 0..8 {
+            assert_eq!(ref_merkle_root(tree.leaf(index), index, &tree.path(index)), tree.root());
+        }
+        assert_ne!(ref_merkle_root(Fp::from(1), 0, &tree.path(0)), tree.root());
+    }
+}
@@ -0,0 +1,23 @@
+use halo2_gadgets::poseidon::primitives::{ConstantLength, Hash, P128Pow5T3};
+use halo2_proofs::pasta::Fp;
+
+/// Poseidon over `Fp` with the `P128Pow5T3` parameters, width 3 and rate 2, of a message of
+/// two elements: the hash behind `CommitChip`, commitments and the Merkle trees.
+pub fn ref_poseidon(a: Fp, b: Fp) -> Fp {
+    Hash::<_, P128Pow5T3, ConstantLength<2>, 3, 2>::init().hash([a, b])
+}
+
+#[cfg(all(test, feature = "hash"))]
+mod test {
+    use halo2_proofs::pasta::Fp;
+
+    use super::ref_poseidon;
+    use crate::gadgets::commit::commit;
+
+    #[test]
+    fn test_ref_poseidon() {
+        let (a, b) = (Fp::from(7), Fp::from(11));
+        assert_eq!(ref_poseidon(a, b), commit(a, b));
+        assert_ne!(ref_poseidon(a, b), ref_poseidon(b, a));
+    }
+}
@@ -0,0 +1,45 @@
+use ff::PrimeFieldBits;
+
+/// The running sum `z_0, ..., z_C` of `value` in `K`-bit chunks, `K = lookup_num_bits`, as
+/// the decompose chip assigns it: `z_i` is `value` shifted right by `K * i` bits, so a value
+/// that fits in `num_bits` ends in `z_C = 0` and any other in its bits above `num_bits`.
+/// `num_bits` is a multiple of `K`.
+pub fn ref_running_sum<F: PrimeFieldBits>(value: F, num_bits: usize, lookup_num_bits: usize) -> Vec<F> {
+    assert_eq!(num_bits % lookup_num_bits, 0, "{} bits are not whole {}-bit chunks", num_bits, lookup_num_bits);
+    let bits = value.to_le_bits();
+    (0..=num_bits / lookup_num_bits)
+        .map(|i| {
+            (lookup_num_bits * i..F::NUM_BITS as usize)
+                .rev()
+                .fold(F::zero(), |z, j| z.double() + F::from(bits[j] as u64))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::pasta::Fp;
+
+    use super::ref_running_sum;
+
+    #[test]
+    fn test_ref_running_sum() {
+        let value = 0x1234_5678_9abc_def0u64;
+        let expected: Vec<_> = (0..=8).map(|i| Fp::from(value.checked_shr(8 * i).unwrap_or(0))).collect();
+        assert_eq!(ref_running_sum(Fp::from(value), 64, 8), expected);
+
+        // a value wider than `num_bits` leaves its high bits in z_C
+        assert_eq!(ref_running_sum(Fp::from(0x1ff), 8, 4), [0x1ff, 0x1f, 0x1].map(Fp::from));
+    }
+
+    #[cfg(feature = "decompose")]
+    #[test]
+    fn test_ref_running_sum_matches_helpers() {
+        use crate::decompose::helpers::compute_running_sum;
+
+        let value = Fp::from(rand::random::<u64>());
+        let decomposition = compute_running_sum(value.into(), 64, 8).unwrap();
+        let running_sum: Vec<_> = decomposition.running_sum.iter().map(|z| z.evaluate()).collect();
+        assert_eq!(ref_running_sum(value, 64, 8)[1..], running_sum[..]);
+    }
+}
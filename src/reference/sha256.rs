@@ -0,0 +1,116 @@
+/// The initial hash value of FIPS 180-4, section 5.3.3.
+pub const IV: [u32; 8] = [
+    0x6a09_e667, 0xbb67_ae85, 0x3c6e_f372, 0xa54f_f53a, 0x510e_527f, 0x9b05_688c, 0x1f83_d9ab, 0x5be0_cd19,
+];
+
+/// The round constants of FIPS 180-4, section 4.2.2.
+pub const K: [u32; 64] = [
+    0x428a_2f98, 0x7137_4491, 0xb5c0_fbcf, 0xe9b5_dba5, 0x3956_c25b, 0x59f1_11f1, 0x923f_82a4, 0xab1c_5ed5,
+    0xd807_aa98, 0x1283_5b01, 0x2431_85be, 0x550c_7dc3, 0x72be_5d74, 0x80de_b1fe, 0x9bdc_06a7, 0xc19b_f174,
+    0xe49b_69c1, 0xefbe_4786, 0x0fc1_9dc6, 0x240c_a1cc, 0x2de9_2c6f, 0x4a74_84aa, 0x5cb0_a9dc, 0x76f9_88da,
+    0x983e_5152, 0xa831_c66d, 0xb003_27c8, 0xbf59_7fc7, 0xc6e0_0bf3, 0xd5a7_9147, 0x06ca_6351, 0x1429_2967,
+    0x27b7_0a85, 0x2e1b_2138, 0x4d2c_6dfc, 0x5338_0d13, 0x650a_7354, 0x766a_0abb, 0x81c2_c92e, 0x9272_2c85,
+    0xa2bf_e8a1, 0xa81a_664b, 0xc24b_8b70, 0xc76c_51a3, 0xd192_e819, 0xd699_0624, 0xf40e_3585, 0x106a_a070,
+    0x19a4_c116, 0x1e37_6c08, 0x2748_774c, 0x34b0_bcb5, 0x391c_0cb3, 0x4ed8_aa4a, 0x5b9c_ca4f, 0x682e_6ff3,
+    0x748f_82ee, 0x78a5_636f, 0x84c8_7814, 0x8cc7_0208, 0x90be_fffa, 0xa450_6ceb, 0xbef9_a3f7, 0xc671_78f2,
+];
+
+/// The input block size of SHA-256, and of HMAC-SHA256's padded key.
+pub const BLOCK_BYTES: usize = 64;
+
+/// The compression function of FIPS 180-4, section 6.2.2: `state` after `block`.
+pub fn ref_sha256_compress(state: [u32; 8], block: [u32; 16]) -> [u32; 8] {
+    let mut w = [0; 64];
+    w[..16].copy_from_slice(&block);
+    for t in 16..64 {
+        let sigma0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+        let sigma1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+        w[t] = sigma1.wrapping_add(w[t - 7]).wrapping_add(sigma0).wrapping_add(w[t - 16]);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+    for t in 0..64 {
+        let big_sigma1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h.wrapping_add(big_sigma1).wrapping_add(ch).wrapping_add(K[t]).wrapping_add(w[t]);
+        let big_sigma0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = big_sigma0.wrapping_add(maj);
+        [h, g, f, e, d, c, b, a] = [g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2)];
+    }
+
+    let working = [a, b, c, d, e, f, g, h];
+    std::array::from_fn(|i| state[i].wrapping_add(working[i]))
+}
+
+/// `data` padded to whole blocks (FIPS 180-4, section 5.1.1), as big-endian words.
+pub fn padded_blocks(data: &[u8]) -> Vec<[u32; 16]> {
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % BLOCK_BYTES != BLOCK_BYTES - 8 {
+        padded.push(0);
+    }
+    padded.extend((8 * data.len() as u64).to_be_bytes());
+
+    padded
+        .chunks(BLOCK_BYTES)
+        .map(|block| std::array::from_fn(|i| u32::from_be_bytes(block[4 * i..4 * i + 4].try_into().unwrap())))
+        .collect()
+}
+
+pub fn ref_sha256(data: &[u8]) -> [u8; 32] {
+    let state = padded_blocks(data).into_iter().fold(IV, ref_sha256_compress);
+    let mut digest = [0; 32];
+    for (bytes, word) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// HMAC-SHA256 of RFC 2104, `H((K ^ opad) || H((K ^ ipad) || message))`, where `K` is the key
+/// hashed if it is longer than a block, then padded with zeros to a block.
+pub fn ref_hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut padded_key = [0; BLOCK_BYTES];
+    if key.len() > BLOCK_BYTES {
+        padded_key[..32].copy_from_slice(&ref_sha256(key));
+    } else {
+        padded_key[..key.len()].copy_from_slice(key);
+    }
+
+    let inner: Vec<u8> = padded_key.iter().map(|byte| byte ^ 0x36).chain(message.iter().copied()).collect();
+    let outer: Vec<u8> = padded_key.iter().map(|byte| byte ^ 0x5c).chain(ref_sha256(&inner)).collect();
+    ref_sha256(&outer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ref_hmac_sha256, ref_sha256};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn test_ref_sha256() {
+        // FIPS 180-2, appendix B, one and two blocks
+        assert_eq!(hex(&ref_sha256(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(
+            hex(&ref_sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(hex(&ref_sha256(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    }
+
+    #[test]
+    fn test_ref_hmac_sha256() {
+        // RFC 4231, test cases 2 and 6: a short key and one longer than a block
+        assert_eq!(
+            hex(&ref_hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex(&ref_hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*};
+
+use crate::error::ErrorSite;
+
+/// A thin wrapper over `Region` that keeps track of the current row.
+///
+/// Every `assign_advice`/`copy_advice` writes at the current offset and then moves on to
+/// the next row, so a single-column layout such as a running sum never has to manage
+/// `offset` by hand. Selectors are enabled on the current row. Every assigned cell is
+/// recorded under its name so later copy-constraints can refer to it, and, when an
+/// `ErrorSite` is attached, the offset and name of a failing assignment are reported there.
+pub struct RegionBuilder<'a, 'r, F: FieldExt> {
+    region: &'a mut Region<'r, F>,
+    offset: usize,
+    cells: HashMap<String, Cell>,
+    site: Option<&'a ErrorSite>,
+}
+
+impl<'a, 'r, F: FieldExt> RegionBuilder<'a, 'r, F> {
+    pub fn new(region: &'a mut Region<'r, F>) -> Self {
+        Self {
+            region,
+            offset: 0,
+            cells: HashMap::new(),
+            site: None,
+        }
+    }
+
+    pub fn report_to(mut self, site: &'a ErrorSite) -> Self {
+        self.site = Some(site);
+        self
+    }
+
+    /// The row the next assignment goes to.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Skips `rows` rows without assigning them.
+    pub fn advance(&mut self, rows: usize) {
+        self.offset += rows;
+    }
+
+    /// Escape hatch to the underlying region, for layouts that assign several columns per row.
+    pub fn region(&mut self) -> &mut Region<'r, F> {
+        self.region
+    }
+
+    pub fn enable_selector(&mut self, selector: &Selector) -> Result<(), Error> {
+        let result = selector.enable(self.region, self.offset);
+        self.report(result, "selector")
+    }
+
+    pub fn assign_advice<V, VR>(
+        &mut self,
+        name: impl Into<String>,
+        column: Column<Advice>,
+        to: V,
+    ) -> Result<AssignedCell<VR, F>, Error>
+    where
+        V: FnMut() -> Value<VR>,
+        for<'vr> Assigned<F>: From<&'vr VR>,
+    {
+        let name = name.into();
+        let result = self.region.assign_advice(|| name.clone(), column, self.offset, to);
+        let cell = self.report(result, &name)?;
+        self.record(name, cell.cell());
+        Ok(cell)
+    }
+
+    pub fn copy_advice(
+        &mut self,
+        name: impl Into<String>,
+        cell: &AssignedCell<F, F>,
+        column: Column<Advice>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let name = name.into();
+        let result = cell.copy_advice(|| name.clone(), self.region, column, self.offset);
+        let cell = self.report(result, &name)?;
+        self.record(name, cell.cell());
+        Ok(cell)
+    }
+
+    /// A previously assigned cell, by name.
+    pub fn cell(&self, name: &str) -> Option<Cell> {
+        self.cells.get(name).copied()
+    }
+
+    pub fn constrain_equal(&mut self, left: &str, right: &str) -> Result<(), Error> {
+        let left_cell = self.named(left)?;
+        let right_cell = self.named(right)?;
+        let result = self.region.constrain_equal(left_cell, right_cell);
+        self.report(result, left)
+    }
+
+    /// Constrains the cell named `name` to equal `other`, e.g. a constant loaded by
+    /// `ConstantsChip`.
+    pub fn constrain_equal_to(&mut self, name: &str, other: &AssignedCell<F, F>) -> Result<(), Error> {
+        let cell = self.named(name)?;
+        let result = self.region.constrain_equal(cell, other.cell());
+        self.report(result, name)
+    }
+
+    fn named(&self, name: &str) -> Result<Cell, Error> {
+        // Referring to a cell that was never assigned is a bug in the chip, not in the witness.
+        let result = self.cell(name).ok_or(Error::Synthesis);
+        self.report(result, name)
+    }
+
+    fn report<T>(&self, result: Result<T, Error>, assigning: &str) -> Result<T, Error> {
+        if let (Err(_), Some(site)) = (&result, self.site) {
+            site.record(self.offset, assigning);
+        }
+        result
+    }
+
+    fn record(&mut self, name: String, cell: Cell) {
+        self.cells.insert(name, cell);
+        self.offset += 1;
+    }
+}
@@ -0,0 +1,222 @@
+//! Proof of reserves, the liabilities half: an exchange publishes the root of a Merkle tree
+//! of its customers' balances and proves that they add up to a public total, with none of
+//! them negative, so the total is at least what it owes every customer.
+//!
+//! Each of the `2^DEPTH` leaves commits to a customer's id, e.g. the hash of their account
+//! number and a nonce they are given, and their balance; slots without a customer hold id
+//! and balance 0:
+//!
+//!     leaf_i = hash2(id_i, balance_i),   root = MerkleTree(leaf_0, ..., leaf_{2^DEPTH - 1})
+//!     total = balance_0 + ... + balance_{2^DEPTH - 1},   0 <= balance_i < 2^BALANCE_BITS
+//!
+//! with the circuit's `CircuitHasher`, Poseidon unless another is picked. Every customer
+//! checks their own leaf against the root with a `MerkleTree::path`. The range check is
+//! what makes the sum mean anything: a balance of `-x`, the field element `p - x`, would
+//! otherwise cancel `x` of the others' and shrink the total.
+//!
+//! The circuit hashes every leaf and every node of the tree, `2^(DEPTH + 1) - 1` hashes, and
+//! unpacks every balance into bits. The public inputs are the root and the total.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    gadgets::{
+        arith::{ArithChip, ArithConfig},
+        bits::{BitsChip, BitsConfig},
+        commit::CommitChip,
+        hasher::CircuitHasher,
+        merkle::MerkleTree,
+    },
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The bits every balance is range checked to.
+pub const BALANCE_BITS: usize = 64;
+
+/// The leaf of the customer `id` holding `balance`.
+pub fn leaf<H: CircuitHasher<Fp>>(id: Fp, balance: u64) -> Fp {
+    H::hash2_native(id, Fp::from(balance))
+}
+
+/// The liabilities tree of depth `depth` over `accounts`, the slots after them empty.
+pub fn liabilities_tree<H: CircuitHasher<Fp>>(depth: usize, accounts: &[(Fp, u64)]) -> MerkleTree<H> {
+    let mut leaves: Vec<_> = accounts.iter().map(|(id, balance)| leaf::<H>(*id, *balance)).collect();
+    leaves.resize(1 << depth, leaf::<H>(Fp::zero(), 0));
+    MerkleTree::with_hasher(depth, &leaves)
+}
+
+/// Proves that the balances of a tree of depth `DEPTH` add up to a public total, hashing
+/// with `H`.
+pub struct ReservesCircuit<const DEPTH: usize, H: CircuitHasher<Fp> = CommitChip> {
+    /// Customer ids and balances, `2^DEPTH` of them.
+    pub accounts: Vec<(Value<Fp>, Value<Fp>)>,
+    pub hasher: PhantomData<H>,
+}
+
+impl<const DEPTH: usize, H: CircuitHasher<Fp>> ReservesCircuit<DEPTH, H> {
+    pub fn new(accounts: &[(Fp, u64)]) -> Self {
+        assert!(accounts.len() <= 1 << DEPTH, "{} accounts do not fit in a tree of depth {}", accounts.len(), DEPTH);
+        let mut accounts: Vec<_> = accounts.iter().map(|(id, balance)| (Value::known(*id), Value::known(Fp::from(*balance)))).collect();
+        accounts.resize(1 << DEPTH, (Value::known(Fp::zero()), Value::known(Fp::zero())));
+        Self {
+            accounts,
+            hasher: PhantomData,
+        }
+    }
+
+    /// The public inputs: the root of the liabilities tree and the total of its balances.
+    pub fn instances(accounts: &[(Fp, u64)]) -> Vec<Fp> {
+        let root = liabilities_tree::<H>(DEPTH, accounts).root();
+        let total = accounts.iter().map(|(_, balance)| *balance as u128).sum();
+        vec![root, Fp::from_u128(total)]
+    }
+}
+
+impl<const DEPTH: usize, H: CircuitHasher<Fp>> Circuit<Fp> for ReservesCircuit<DEPTH, H> {
+    type Config = (H::Config, BitsConfig, ArithConfig, LoadPrivateConfig, PublicOutputsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            accounts: vec![(Value::unknown(), Value::unknown()); 1 << DEPTH],
+            hasher: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let hasher = H::configure(meta);
+        let bits = BitsChip::configure(meta, instance);
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let arith = ArithChip::configure(meta, advice);
+        let load = LoadPrivateChip::configure(meta, 2);
+        (hasher, bits, arith, load, PublicOutputs::configure(meta, instance))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (hasher_config, bits_config, arith_config, load_config, outputs_config) = config;
+        let hasher = H::construct(hasher_config);
+        let bits = BitsChip::construct(bits_config);
+        let arith = ArithChip::construct(arith_config);
+        let load_chip = LoadPrivateChip::construct(load_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+
+        // 1. the leaves, and the total of their balances
+        let mut layer = Vec::with_capacity(1 << DEPTH);
+        let mut total: Option<AssignedCell<Fp, Fp>> = None;
+        for (i, (id, balance)) in self.accounts.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("account {}", i));
+            let account = load_chip.load_privates(layouter.namespace(|| "load"), &[*id, *balance])?;
+            let (id, balance) = (&account[0], &account[1]);
+            bits.unpack(layouter.namespace(|| "range check balance"), balance, BALANCE_BITS)?;
+            layer.push(hasher.hash2(layouter.namespace(|| "leaf"), id, balance)?);
+            total = Some(match total {
+                Some(total) => arith.add(layouter.namespace(|| "total"), &total, balance)?,
+                None => balance.clone(),
+            });
+        }
+
+        // 2. the tree above them
+        for level in 0..DEPTH {
+            layer = layer
+                .chunks(2)
+                .enumerate()
+                .map(|(i, pair)| hasher.hash2(layouter.namespace(|| format!("level {}, node {}", level + 1, i)), &pair[0], &pair[1]))
+                .collect::<Result<_, _>>()?;
+        }
+
+        outputs.expose("root", &layer[0]);
+        outputs.expose("total", &total.expect("a tree has a leaf"));
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl<const DEPTH: usize, H: CircuitHasher<Fp>> ValidateWitness<Fp> for ReservesCircuit<DEPTH, H> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        if self.accounts.len() != 1 << DEPTH {
+            return Err(InvalidWitness::InvalidParameter(format!("{} accounts for {} slots", self.accounts.len(), 1 << DEPTH)));
+        }
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|(id, balance)| {
+                let balance = known(*balance, "balance")?;
+                check_below(balance, u64::MAX as u128 + 1, "balance")?;
+                Ok((known(*id, "id")?, balance.get_lower_128() as u64))
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        check_instances(instances, &Self::instances(&accounts))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+    use rand::rngs::OsRng;
+
+    use super::{leaf, liabilities_tree, ReservesCircuit};
+    use crate::{
+        gadgets::{commit::CommitChip, hasher::CircuitHasher, merkle::MerkleTree, mimc::MimcChip},
+        validate::ValidateWitness,
+    };
+
+    const K: u32 = 11;
+
+    fn accounts() -> Vec<(Fp, u64)> {
+        [1_000, 250_000, 0, 42, u64::MAX / 4].into_iter().map(|balance| (Fp::random(OsRng), balance)).collect()
+    }
+
+    #[test]
+    fn test_reserves() {
+        let accounts = accounts();
+        let circuit: ReservesCircuit<3> = ReservesCircuit::new(&accounts);
+        let instances = vec![ReservesCircuit::<3>::instances(&accounts)];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+
+        // every customer finds their leaf under the root
+        let tree = liabilities_tree(3, &accounts);
+        for (i, (id, balance)) in accounts.iter().enumerate() {
+            assert_eq!(tree.leaf(i as u64), leaf::<CommitChip>(*id, *balance));
+        }
+    }
+
+    #[test]
+    fn test_reserves_understated() {
+        let accounts = accounts();
+        let circuit: ReservesCircuit<3> = ReservesCircuit::new(&accounts);
+
+        // a total short by one
+        let mut instances = ReservesCircuit::<3>::instances(&accounts);
+        instances[1] -= Fp::one();
+        assert!(circuit.validate_witness(&[instances.clone()]).is_err());
+        assert!(MockProver::run(K, &circuit, vec![instances]).unwrap().verify().is_err());
+
+        // a hidden account with a negative balance, cancelling another one's
+        let mut circuit: ReservesCircuit<3> = ReservesCircuit::new(&accounts);
+        let hidden = Fp::random(OsRng);
+        circuit.accounts[7] = (Value::known(hidden), Value::known(-Fp::from(250_000)));
+        let mut leaves: Vec<_> = accounts.iter().map(|(id, balance)| leaf::<CommitChip>(*id, *balance)).collect();
+        leaves.resize(7, leaf::<CommitChip>(Fp::zero(), 0));
+        leaves.push(CommitChip::hash2_native(hidden, -Fp::from(250_000)));
+        let total: u64 = accounts.iter().map(|(_, balance)| balance).sum();
+        let instances = vec![MerkleTree::new(3, &leaves).root(), Fp::from(total - 250_000)];
+        assert!(circuit.validate_witness(&[instances.clone()]).is_err());
+        assert!(MockProver::run(K, &circuit, vec![instances]).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_reserves_mimc() {
+        let accounts = [(Fp::from(1), 10), (Fp::from(2), 20), (Fp::from(3), 30)];
+        let circuit = ReservesCircuit::<2, MimcChip<Fp>>::new(&accounts);
+        let instances = vec![ReservesCircuit::<2, MimcChip<Fp>>::instances(&accounts)];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+    }
+}
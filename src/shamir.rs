@@ -0,0 +1,190 @@
+//! Reconstruction of a Shamir-shared secret: the prover holds `T` shares of a secret split
+//! with threshold `T`, and proves they reconstruct the secret behind a public hash, without
+//! disclosing the shares or the secret.
+//!
+//! A dealer hides the secret as the constant term of a random polynomial of degree `T - 1`
+//! and hands out its values at distinct nonzero points as the shares,
+//!
+//!     p(x) = secret + a_1 x + ... + a_{T-1} x^{T-1},   share_i = (x_i, p(x_i))
+//!
+//! Any `T` shares determine `p` and so the secret `p(0)`; fewer tell nothing about it. The
+//! circuit interpolates its private shares at 0 with `LagrangeChip` and exposes the hash of
+//! the result, `hash_many([secret])` with the circuit's `CircuitHasher`, Poseidon unless
+//! another is picked. The share indices are private too, and two equal ones fail the proof.
+
+use std::marker::PhantomData;
+
+use ff::Field;
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*};
+use rand::RngCore;
+
+use crate::{
+    chip::LoadInstructions,
+    gadgets::{arith::ArithConfig, commit::CommitChip, hasher::CircuitHasher, lagrange::LagrangeChip},
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// `count` shares of `secret` at `x = 1, ..., count`, any `threshold` of which reconstruct it.
+pub fn split(secret: Fp, threshold: usize, count: usize, mut rng: impl RngCore) -> Vec<(Fp, Fp)> {
+    assert!(threshold >= 1, "a threshold of 0 shares");
+    let mut coeffs = vec![secret];
+    coeffs.extend((1..threshold).map(|_| Fp::random(&mut rng)));
+    (1..=count as u64)
+        .map(|x| {
+            let x = Fp::from(x);
+            let y = coeffs.iter().rev().fold(Fp::zero(), |y, coeff| y * x + coeff);
+            (x, y)
+        })
+        .collect()
+}
+
+/// The secret the `shares` reconstruct, computed natively, or `None` if two of them share
+/// an index.
+pub fn reconstruct(shares: &[(Fp, Fp)]) -> Option<Fp> {
+    shares.iter().enumerate().try_fold(Fp::zero(), |secret, (i, (x_i, y_i))| {
+        let (num, den) = shares
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold((Fp::one(), Fp::one()), |(num, den), (_, (x_j, _))| (num * -x_j, den * (x_i - x_j)));
+        Option::<Fp>::from(den.invert()).map(|inverse| secret + y_i * num * inverse)
+    })
+}
+
+/// Proves that `T` private shares reconstruct the secret behind a public hash, hashing
+/// with `H`.
+pub struct ShamirCircuit<const T: usize, H: CircuitHasher<Fp> = CommitChip> {
+    pub shares: [(Value<Fp>, Value<Fp>); T],
+    pub hasher: PhantomData<H>,
+}
+
+impl<const T: usize, H: CircuitHasher<Fp>> ShamirCircuit<T, H> {
+    pub fn new(shares: [(Fp, Fp); T]) -> Self {
+        Self {
+            shares: shares.map(|(x, y)| (Value::known(x), Value::known(y))),
+            hasher: PhantomData,
+        }
+    }
+
+    /// The public hash of `secret`.
+    pub fn secret_hash(secret: Fp) -> Fp {
+        H::hash_many_native(&[secret])
+    }
+}
+
+impl<const T: usize, H: CircuitHasher<Fp>> Circuit<Fp> for ShamirCircuit<T, H> {
+    type Config = (ArithConfig, H::Config, PublicOutputsConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            shares: [(Value::unknown(), Value::unknown()); T],
+            hasher: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let lagrange = LagrangeChip::configure(meta, advice);
+        let hasher = H::configure(meta);
+        (lagrange, hasher, PublicOutputs::configure(meta, instance))
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let (lagrange_config, hasher_config, outputs_config) = config;
+        let lagrange = LagrangeChip::construct(lagrange_config);
+        let hasher = H::construct(hasher_config);
+        let mut outputs = PublicOutputs::construct(outputs_config);
+
+        let arith = lagrange.arith();
+        let shares = self
+            .shares
+            .iter()
+            .enumerate()
+            .map(|(i, (x, y))| {
+                let x = arith.load_private(layouter.namespace(|| format!("x_{}", i)), *x)?;
+                let y = arith.load_private(layouter.namespace(|| format!("y_{}", i)), *y)?;
+                Ok((x, y))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let secret = lagrange.interpolate(layouter.namespace(|| "p(0)"), &shares, Fp::zero())?;
+        let hash = hasher.hash_many(layouter.namespace(|| "hash secret"), &[secret])?;
+
+        outputs.expose("secret hash", &hash);
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl<const T: usize, H: CircuitHasher<Fp>> ValidateWitness<Fp> for ShamirCircuit<T, H> {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let shares = self
+            .shares
+            .iter()
+            .map(|(x, y)| Ok((known(*x, "share index")?, known(*y, "share")?)))
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        let secret = reconstruct(&shares)
+            .ok_or_else(|| InvalidWitness::InvalidParameter("two shares have the same index".to_string()))?;
+        check_instances(instances, &[Self::secret_hash(secret)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use rand::rngs::OsRng;
+
+    use super::{reconstruct, split, ShamirCircuit};
+    use crate::{gadgets::mimc::MimcChip, validate::ValidateWitness};
+
+    const K: u32 = 8;
+
+    #[test]
+    fn test_shamir() {
+        let secret = Fp::random(OsRng);
+        let shares = split(secret, 3, 5, OsRng);
+        let hash = ShamirCircuit::<3>::secret_hash(secret);
+
+        // any three of the five shares
+        for picked in [[0, 1, 2], [4, 0, 3], [1, 3, 4]] {
+            let shares = picked.map(|i| shares[i]);
+            assert_eq!(reconstruct(&shares), Some(secret));
+            let circuit: ShamirCircuit<3> = ShamirCircuit::new(shares);
+            assert_eq!(circuit.validate_witness(&[vec![hash]]), Ok(()));
+            MockProver::run(K, &circuit, vec![vec![hash]]).unwrap().assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_shamir_wrong_shares() {
+        let secret = Fp::random(OsRng);
+        let shares = split(secret, 3, 5, OsRng);
+        let hash = ShamirCircuit::<3>::secret_hash(secret);
+        let run = |shares: [(Fp, Fp); 3]| {
+            let circuit: ShamirCircuit<3> = ShamirCircuit::new(shares);
+            assert!(circuit.validate_witness(&[vec![hash]]).is_err());
+            MockProver::run(K, &circuit, vec![vec![hash]]).unwrap().verify().is_err()
+        };
+
+        // a share short of the threshold, made up for
+        assert!(run([shares[0], shares[1], (Fp::from(6), Fp::random(OsRng))]));
+        // a tampered share
+        assert!(run([shares[0], shares[1], (shares[2].0, shares[2].1 + Fp::one())]));
+        // the same share twice
+        assert!(run([shares[0], shares[1], shares[1]]));
+    }
+
+    #[test]
+    fn test_shamir_mimc() {
+        let secret = Fp::from(0x5ec2e7);
+        let shares: [_; 2] = split(secret, 2, 2, OsRng).try_into().unwrap();
+        let circuit = ShamirCircuit::<2, MimcChip<Fp>>::new(shares);
+        let instances = vec![vec![ShamirCircuit::<2, MimcChip<Fp>>::secret_hash(secret)]];
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+    }
+}
@@ -0,0 +1,303 @@
+//! Hashes a private message with the Sinsemilla gadget of `halo2_gadgets` and exposes the
+//! hash, a binding commitment to the message.
+//!
+//! Sinsemilla splits its message into 10-bit words and adds, for every word `m_j`, a point
+//! `S(m_j)` taken from a 1024-row generator table:
+//!
+//!     Acc_0 = Q,   Acc_{j+1} = (Acc_j + S(m_j)) + Acc_j,   hash = x(Acc_n)
+//!
+//! so the circuit is mostly lookups into that table, which `SinsemillaChip::load` fills,
+//! and a running sum decomposing each message piece into words. The message here is a
+//! list of `u32`s, each padded to a 40-bit piece of 4 words.
+//!
+//! The gadget is generic over the domains it hashes and commits in and the fixed bases of
+//! the `EccChip` it runs on, which the Orchard protocol defines for its own use; this
+//! example defines its own. Only `HashDomain` is used: `CommitDomain` adds a blinding
+//! `[r] R` by fixed-base multiplication, for which the fixed bases below would need their
+//! window tables, computed here on demand but far too slowly for every proof.
+
+use ff::PrimeField;
+use group::{prime::PrimeCurveAffine, Curve};
+use halo2_gadgets::{
+    ecc::{
+        chip::{
+            constants::find_zs_and_us, BaseFieldElem, EccChip, EccConfig, FixedPoint, FixedScalarKind, FullScalar,
+            ShortScalar, H,
+        },
+        FixedPoints,
+    },
+    sinsemilla::{
+        chip::{SinsemillaChip, SinsemillaConfig},
+        primitives as native, CommitDomains, HashDomain, HashDomains, Message, MessagePiece,
+    },
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::{
+    circuit::*,
+    pasta::{pallas, Fp},
+    plonk::*,
+};
+
+use crate::{
+    constants::ConstantsChip,
+    error::ResultExt,
+    public_inputs::{PublicOutputs, PublicOutputsConfig},
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The personalization of the hash domain, which determines its starting point `Q`.
+pub const DOMAIN: &str = "halo2-examples:sinsemilla";
+
+/// Sinsemilla words per message piece; each `u32` of the message takes one piece.
+pub const WORDS_PER_PIECE: usize = 4;
+
+/// The bits Sinsemilla hashes for `message`: each value little-endian, padded to 40 bits.
+pub fn message_bits(message: &[u32]) -> Vec<bool> {
+    message
+        .iter()
+        .flat_map(|value| (0..WORDS_PER_PIECE * native::K).map(move |i| i < 32 && (value >> i) & 1 == 1))
+        .collect()
+}
+
+/// The hash of `message` in `DOMAIN`, computed natively.
+pub fn hash(message: &[u32]) -> Fp {
+    Option::from(native::HashDomain::new(DOMAIN).hash(message_bits(message).into_iter()))
+        .expect("a random message does not hash to the identity")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExampleHashDomain;
+
+impl HashDomains<pallas::Affine> for ExampleHashDomain {
+    fn Q(&self) -> pallas::Affine {
+        native::HashDomain::new(DOMAIN).Q().to_affine()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExampleCommitDomain;
+
+impl CommitDomains<pallas::Affine, ExampleFixedBases, ExampleHashDomain> for ExampleCommitDomain {
+    fn r(&self) -> ExampleFullScalar {
+        ExampleFullScalar
+    }
+
+    fn hash_domain(&self) -> ExampleHashDomain {
+        ExampleHashDomain
+    }
+}
+
+/// The fixed bases of the `EccChip`, all the Pallas generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExampleFixedBases;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExampleFullScalar;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExampleShortScalar;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExampleBaseFieldElem;
+
+impl FixedPoints<pallas::Affine> for ExampleFixedBases {
+    type FullScalar = ExampleFullScalar;
+    type ShortScalar = ExampleShortScalar;
+    type Base = ExampleBaseFieldElem;
+}
+
+/// The window tables of the generator, for `num_windows` 3-bit windows.
+fn zs_and_us(num_windows: usize) -> Vec<(u64, [pallas::Base; H])> {
+    find_zs_and_us(pallas::Affine::generator(), num_windows).expect("the generator has window tables")
+}
+
+fn us(num_windows: usize) -> Vec<[[u8; 32]; H]> {
+    zs_and_us(num_windows).into_iter().map(|(_, us)| us.map(|u| u.to_repr())).collect()
+}
+
+fn zs(num_windows: usize) -> Vec<u64> {
+    zs_and_us(num_windows).into_iter().map(|(z, _)| z).collect()
+}
+
+impl FixedPoint<pallas::Affine> for ExampleFullScalar {
+    type FixedScalarKind = FullScalar;
+
+    fn generator(&self) -> pallas::Affine {
+        pallas::Affine::generator()
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        us(FullScalar::NUM_WINDOWS)
+    }
+
+    fn z(&self) -> Vec<u64> {
+        zs(FullScalar::NUM_WINDOWS)
+    }
+}
+
+impl FixedPoint<pallas::Affine> for ExampleShortScalar {
+    type FixedScalarKind = ShortScalar;
+
+    fn generator(&self) -> pallas::Affine {
+        pallas::Affine::generator()
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        us(ShortScalar::NUM_WINDOWS)
+    }
+
+    fn z(&self) -> Vec<u64> {
+        zs(ShortScalar::NUM_WINDOWS)
+    }
+}
+
+impl FixedPoint<pallas::Affine> for ExampleBaseFieldElem {
+    type FixedScalarKind = BaseFieldElem;
+
+    fn generator(&self) -> pallas::Affine {
+        pallas::Affine::generator()
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        us(BaseFieldElem::NUM_WINDOWS)
+    }
+
+    fn z(&self) -> Vec<u64> {
+        zs(BaseFieldElem::NUM_WINDOWS)
+    }
+}
+
+type ExampleSinsemillaChip = SinsemillaChip<ExampleHashDomain, ExampleCommitDomain, ExampleFixedBases>;
+
+#[derive(Debug, Clone)]
+pub struct SinsemillaExampleConfig {
+    ecc: EccConfig<ExampleFixedBases>,
+    sinsemilla: SinsemillaConfig<ExampleHashDomain, ExampleCommitDomain, ExampleFixedBases>,
+    outputs: PublicOutputsConfig,
+}
+
+/// Exposes the Sinsemilla hash of a private message.
+#[derive(Default)]
+pub struct SinsemillaCircuit {
+    pub message: Vec<Value<u32>>,
+}
+
+impl Circuit<Fp> for SinsemillaCircuit {
+    type Config = SinsemillaExampleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: vec![Value::unknown(); self.message.len()],
+        }
+    }
+
+    /// The column layout of the gadget's own tests: ten advice columns shared by both chips,
+    /// the generator table next to the 10-bit range check table it extends, and eight fixed
+    /// columns for the Lagrange coefficients of fixed-base multiplication, the first of which
+    /// doubles as Sinsemilla's `y_Q` column.
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advices = [(); 10].map(|_| meta.advice_column());
+        for advice in advices {
+            meta.enable_equality(advice);
+        }
+        let instance = meta.instance_column();
+
+        // both chips assign their constants themselves, in the column `ConstantsChip` shares
+        ConstantsChip::enable_constants(meta);
+
+        let table_idx = meta.lookup_table_column();
+        let lookup = (table_idx, meta.lookup_table_column(), meta.lookup_table_column());
+        let lagrange_coeffs = [(); 8].map(|_| meta.fixed_column());
+
+        let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+        let ecc = EccChip::<ExampleFixedBases>::configure(meta, advices, lagrange_coeffs, range_check);
+        let sinsemilla = ExampleSinsemillaChip::configure(
+            meta,
+            advices[..5].try_into().expect("five advice columns"),
+            advices[2],
+            lagrange_coeffs[0],
+            lookup,
+            range_check,
+        );
+
+        SinsemillaExampleConfig {
+            ecc,
+            sinsemilla,
+            outputs: PublicOutputs::configure(meta, instance),
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        ExampleSinsemillaChip::load(config.sinsemilla.clone(), &mut layouter).in_chip("SinsemillaCircuit", "generator table")?;
+        let chip = ExampleSinsemillaChip::construct(config.sinsemilla);
+        let ecc_chip = EccChip::construct(config.ecc);
+        let mut outputs = PublicOutputs::construct(config.outputs);
+
+        let pieces = self
+            .message
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                MessagePiece::from_field_elem(
+                    chip.clone(),
+                    layouter.namespace(|| format!("piece {}", i)),
+                    value.map(|value| Fp::from(value as u64)),
+                    WORDS_PER_PIECE,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let message = Message::from_pieces(chip.clone(), pieces);
+
+        let domain = HashDomain::new(chip, ecc_chip, &ExampleHashDomain);
+        let (hash, _) = domain.hash(layouter.namespace(|| "hash"), message).in_chip("SinsemillaCircuit", "hash")?;
+
+        outputs.expose("hash", hash.inner());
+        outputs.constrain(layouter.namespace(|| "public outputs"))?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for SinsemillaCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        if self.message.len() * WORDS_PER_PIECE > native::C {
+            return Err(InvalidWitness::InvalidParameter(format!(
+                "a message of {} pieces is longer than {} words",
+                self.message.len(),
+                native::C
+            )));
+        }
+        let message = self
+            .message
+            .iter()
+            .map(|value| known(*value, "message"))
+            .collect::<Result<Vec<_>, _>>()?;
+        check_instances(instances, &[hash(&message)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver};
+
+    use super::{hash, SinsemillaCircuit};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 11;
+
+    #[test]
+    fn test_sinsemilla_hash() {
+        let message = [0xdead_beef, 7, 0, u32::MAX];
+        let circuit = SinsemillaCircuit {
+            message: message.iter().copied().map(Value::known).collect(),
+        };
+        let instances = vec![vec![hash(&message)]];
+        assert!(circuit.validate_witness(&instances).is_ok());
+        MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+
+        // the hash of another message
+        let instances = vec![vec![hash(&[0xdead_beef, 7, 1, u32::MAX])]];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+}
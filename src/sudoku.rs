@@ -0,0 +1,389 @@
+//! Sudoku: a private grid solves a puzzle whose clues are public, as a sparse list of
+//! `(row, col, value)` triples rather than a full 81-cell instance with blanks.
+//!
+//! The grid is laid out a cell per row, each looked up with its bit `2^(digit - 1)` in a
+//! fixed table of the digits 1 to 9, and a `(0, 0)` row for the rows the lookup is off:
+//!
+//!       cell   |    bit     |  q_cell          (q_cell * cell, q_cell * bit) in digits
+//!     ---------------------------------
+//!       g_i    | 2^(g_i - 1)|    1
+//!
+//! Each of the 27 rows, columns and boxes then sums its cells' bits to `511`, `2^9 - 1`. A
+//! sum of nine powers of two has nine bits set only if the powers are distinct, so that is
+//! the group holding every digit once; a 0, whose bit is 0, leaves at most eight set.
+//!
+//! A clue's row and column are looked up in `0..9`, its index is `9 * row + col`, and
+//! `SelectChip` picks the grid cell at that index for the clue's value to equal:
+//!
+//!       row   |   col   |     index       |  q_clue
+//!     ---------------------------------------------------
+//!        r    |    c    |   9 * r + c     |    1          r, c in 0..9
+//!
+//! The public inputs are the clues, three instance rows each. Their number is a parameter of
+//! the circuit, `clues`, as the number of rows to read has to be known when it is laid out,
+//! so one verifying key serves every puzzle with that many clues.
+
+use std::array;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::ResultExt,
+    gadgets::select::{SelectChip, SelectConfig},
+    gate::GateBuilder,
+    public_inputs::{InstanceChip, InstanceConfig},
+    table::FixedTableConfig,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The side of the grid.
+pub const SIZE: usize = 9;
+
+/// The sum of the bits of a group holding every digit.
+const ALL_DIGITS: u64 = (1 << SIZE) - 1;
+
+/// A clue: the row, the column and the digit there.
+pub type Clue = (usize, usize, u64);
+
+/// The bit of `digit` in its group's sum, 0 for anything but 1 to 9.
+pub fn bit(digit: u64) -> u64 {
+    if (1..=SIZE as u64).contains(&digit) {
+        1 << (digit - 1)
+    } else {
+        0
+    }
+}
+
+/// The 27 groups of cells that hold every digit once, as `(name, cells)`: the rows, the
+/// columns and the 3x3 boxes.
+pub fn groups() -> Vec<(String, [(usize, usize); SIZE])> {
+    let rows = (0..SIZE).map(|r| (format!("row {}", r), array::from_fn(|i| (r, i))));
+    let cols = (0..SIZE).map(|c| (format!("column {}", c), array::from_fn(|i| (i, c))));
+    let boxes = (0..SIZE).map(|b| (format!("box {}", b), array::from_fn(|i| (b / 3 * 3 + i / 3, b % 3 * 3 + i % 3))));
+    rows.chain(cols).chain(boxes).collect()
+}
+
+/// Whether `grid` is a solved Sudoku.
+pub fn is_solution(grid: &[[u64; SIZE]; SIZE]) -> bool {
+    groups().iter().all(|(_, cells)| cells.iter().map(|(r, c)| bit(grid[*r][*c])).fold(0, |bits, bit| bits | bit) == ALL_DIGITS)
+}
+
+/// The clues of `puzzle`, 81 digits and `.` for the blanks, whitespace ignored.
+pub fn clues(puzzle: &str) -> Vec<Clue> {
+    puzzle
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .enumerate()
+        .filter_map(|(i, c)| c.to_digit(10).map(|digit| (i / SIZE, i % SIZE, digit as u64)))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct SudokuConfig {
+    select: SelectConfig,
+    instance: InstanceConfig,
+    cell: Column<Advice>,
+    bit: Column<Advice>,
+    sum: Column<Advice>,
+    row: Column<Advice>,
+    col: Column<Advice>,
+    index: Column<Advice>,
+    q_cell: Selector,
+    q_sum: Selector,
+    q_clue: Selector,
+    digits: [FixedTableConfig<Fp>; 2],
+    positions: FixedTableConfig<Fp>,
+    constants: ConstantsConfig,
+}
+
+/// Proves a private grid is a solution that agrees with `clues` public clues.
+pub struct SudokuCircuit {
+    pub grid: [[Value<u64>; SIZE]; SIZE],
+    /// The number of clues, fixed in the circuit.
+    pub clues: usize,
+}
+
+impl SudokuCircuit {
+    pub fn new(grid: [[u64; SIZE]; SIZE], clues: usize) -> Self {
+        Self {
+            grid: grid.map(|row| row.map(Value::known)),
+            clues,
+        }
+    }
+
+    /// The public inputs: the row, the column and the value of every clue.
+    pub fn instances(clues: &[Clue]) -> Vec<Fp> {
+        clues.iter().flat_map(|(row, col, value)| [Fp::from(*row as u64), Fp::from(*col as u64), Fp::from(*value)]).collect()
+    }
+}
+
+impl Circuit<Fp> for SudokuCircuit {
+    type Config = SudokuConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            grid: [[Value::unknown(); SIZE]; SIZE],
+            clues: self.clues,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let select = SelectChip::configure(meta);
+        let [cell, bit, sum, row, col, index, public] = [(); 7].map(|_| meta.advice_column());
+        let q_cell = meta.complex_selector();
+        let q_sum = meta.selector();
+        let q_clue = meta.complex_selector();
+        let digits = ["sudoku digit", "sudoku bit"].map(|name| FixedTableConfig::configure(meta, name));
+        let positions = FixedTableConfig::configure(meta, "sudoku position");
+
+        // the zero the group sums start from and the 511 they end at are loaded constants
+        let constants = ConstantsChip::configure_with(meta, sum);
+        for column in [cell, bit, sum, row, col, index] {
+            meta.enable_equality(column);
+        }
+
+        meta.lookup(|meta| {
+            let q_cell = meta.query_selector(q_cell);
+            let cell = meta.query_advice(cell, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            vec![(q_cell.clone() * cell, digits[0].column), (q_cell * bit, digits[1].column)]
+        });
+
+        meta.create_gate("group sum", |meta| {
+            let q_sum = meta.query_selector(q_sum);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let sum_prev = meta.query_advice(sum, Rotation::prev());
+            let sum = meta.query_advice(sum, Rotation::cur());
+
+            GateBuilder::with_selector(q_sum).equal("sum = sum_prev + bit", sum, sum_prev + bit)
+        });
+
+        meta.create_gate("clue index", |meta| {
+            let q_clue = meta.query_selector(q_clue);
+            let [row, col, index] = [row, col, index].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_clue).equal("index = 9 * row + col", index, row * Fp::from(SIZE as u64) + col)
+        });
+
+        // one lookup each, a pair in one lookup would be a row of a two-column table
+        for column in [row, col] {
+            meta.lookup(|meta| {
+                let q_clue = meta.query_selector(q_clue);
+                let position = meta.query_advice(column, Rotation::cur());
+                vec![(q_clue * position, positions.column)]
+            });
+        }
+
+        SudokuConfig {
+            select,
+            instance: InstanceChip::configure(meta, instance, public),
+            cell,
+            bit,
+            sum,
+            row,
+            col,
+            index,
+            q_cell,
+            q_sum,
+            q_clue,
+            digits,
+            positions,
+            constants,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let select = SelectChip::construct(config.select.clone());
+        let instance = InstanceChip::construct(config.instance.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+
+        config.digits[0].load_with(&mut layouter, SIZE + 1, |digit| Fp::from(digit as u64))?;
+        config.digits[1].load_with(&mut layouter, SIZE + 1, |digit| Fp::from(bit(digit as u64)))?;
+        config.positions.load_with(&mut layouter, SIZE, |position| Fp::from(position as u64))?;
+
+        // 1. the grid, every cell a digit with its bit
+        let (cells, bits) = layouter
+            .assign_region(
+                || "grid",
+                |mut region| {
+                    let mut cells = Vec::with_capacity(SIZE * SIZE);
+                    let mut bits = Vec::with_capacity(SIZE * SIZE);
+                    for (offset, value) in self.grid.iter().flatten().enumerate() {
+                        config.q_cell.enable(&mut region, offset)?;
+                        let name = format!("g_{}_{}", offset / SIZE, offset % SIZE);
+                        cells.push(region.assign_advice(|| name.clone(), config.cell, offset, || value.map(Fp::from))?);
+                        bits.push(region.assign_advice(|| format!("bit of {}", name), config.bit, offset, || value.map(|value| Fp::from(bit(value))))?);
+                    }
+                    Ok((cells, bits))
+                },
+            )
+            .in_chip("SudokuCircuit", "grid")?;
+
+        // 2. every group holds every digit
+        let zero = constants.load_constant(layouter.namespace(|| "zero"), "zero", Fp::zero())?;
+        let all_digits = constants.load_constant(layouter.namespace(|| "all digits"), "all digits", Fp::from(ALL_DIGITS))?;
+        for (name, group) in groups() {
+            layouter
+                .assign_region(
+                    || name.clone(),
+                    |mut region| {
+                        let mut sum = zero.copy_advice(|| "sum_0", &mut region, config.sum, 0)?;
+                        for (i, (row, col)) in group.iter().enumerate() {
+                            let offset = i + 1;
+                            config.q_sum.enable(&mut region, offset)?;
+                            let bit = bits[row * SIZE + col].copy_advice(|| "bit", &mut region, config.bit, offset)?;
+                            let next = sum.value().copied() + bit.value().copied();
+                            sum = region.assign_advice(|| format!("sum_{}", offset), config.sum, offset, || next)?;
+                        }
+                        region.constrain_equal(sum.cell(), all_digits.cell())
+                    },
+                )
+                .in_chip("SudokuCircuit", "group")?;
+        }
+
+        // 3. the grid agrees with every clue
+        for k in 0..self.clues {
+            let mut layouter = layouter.namespace(|| format!("clue {}", k));
+            let row = instance.load(layouter.namespace(|| "load row"), "row", 3 * k)?;
+            let col = instance.load(layouter.namespace(|| "load col"), "col", 3 * k + 1)?;
+            let value = instance.load(layouter.namespace(|| "load value"), "value", 3 * k + 2)?;
+            let index = layouter
+                .assign_region(
+                    || "clue index",
+                    |mut region| {
+                        config.q_clue.enable(&mut region, 0)?;
+                        let row = row.copy_advice(|| "row", &mut region, config.row, 0)?;
+                        let col = col.copy_advice(|| "col", &mut region, config.col, 0)?;
+                        let index = row.value().copied() * Value::known(Fp::from(SIZE as u64)) + col.value().copied();
+                        region.assign_advice(|| "index", config.index, 0, || index)
+                    },
+                )
+                .in_chip("SudokuCircuit", "clue index")?;
+            let cell = select.select(layouter.namespace(|| "grid[index]"), &cells, &index)?;
+            layouter
+                .assign_region(|| "clue value", |mut region| region.constrain_equal(cell.cell(), value.cell()))
+                .in_chip("SudokuCircuit", "clue value")?;
+        }
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for SudokuCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let mut grid = [[0; SIZE]; SIZE];
+        for (row, values) in grid.iter_mut().zip(&self.grid) {
+            for (cell, value) in row.iter_mut().zip(values) {
+                *cell = known(*value, "cell")?;
+            }
+        }
+        if !is_solution(&grid) {
+            return Err(InvalidWitness::InvalidParameter("the grid is not a solution".to_string()));
+        }
+
+        let public = instances.first().filter(|column| column.len() == 3 * self.clues).ok_or(InvalidWitness::WrongInstanceCount {
+            expected: 3 * self.clues,
+            found: instances.iter().map(Vec::len).sum(),
+        })?;
+        let clues = public
+            .chunks(3)
+            .map(|clue| {
+                check_below(clue[0], SIZE as u128, "clue row")?;
+                check_below(clue[1], SIZE as u128, "clue col")?;
+                let (row, col) = (clue[0].get_lower_128() as usize, clue[1].get_lower_128() as usize);
+                if clue[2] != Fp::from(grid[row][col]) {
+                    return Err(InvalidWitness::InvalidParameter(format!("the clue at ({}, {}) is not the solution's {}", row, col, grid[row][col])));
+                }
+                Ok((row, col, grid[row][col]))
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        check_instances(instances, &Self::instances(&clues))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::dev::MockProver;
+
+    use super::{clues, is_solution, SudokuCircuit, SIZE};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 12;
+
+    const PUZZLE: &str = "
+        53..7....
+        6..195...
+        .98....6.
+        8...6...3
+        4..8.3..1
+        7...2...6
+        .6....28.
+        ...419..5
+        ....8..79";
+
+    const SOLUTION: [[u64; SIZE]; SIZE] = [
+        [5, 3, 4, 6, 7, 8, 9, 1, 2],
+        [6, 7, 2, 1, 9, 5, 3, 4, 8],
+        [1, 9, 8, 3, 4, 2, 5, 6, 7],
+        [8, 5, 9, 7, 6, 1, 4, 2, 3],
+        [4, 2, 6, 8, 5, 3, 7, 9, 1],
+        [7, 1, 3, 9, 2, 4, 8, 5, 6],
+        [9, 6, 1, 5, 3, 7, 2, 8, 4],
+        [2, 8, 7, 4, 1, 9, 6, 3, 5],
+        [3, 4, 5, 2, 8, 6, 1, 7, 9],
+    ];
+
+    #[test]
+    fn test_sudoku() {
+        assert!(is_solution(&SOLUTION));
+        let clues = clues(PUZZLE);
+        assert_eq!(clues.len(), 30);
+
+        // the whole puzzle, a few of its clues and none
+        for clues in [&clues[..], &clues[..3], &clues[..0]] {
+            let circuit = SudokuCircuit::new(SOLUTION, clues.len());
+            let instances = vec![SudokuCircuit::instances(clues)];
+            assert_eq!(circuit.validate_witness(&instances), Ok(()));
+            MockProver::run(K, &circuit, instances).unwrap().assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_sudoku_wrong_clue() {
+        let circuit = SudokuCircuit::new(SOLUTION, 2);
+        // a clue the solution disagrees with
+        let instances = vec![SudokuCircuit::instances(&[(0, 0, 5), (0, 1, 4)])];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+
+        // column 10 of row 0 is index 10, the 7 at (1, 1), but no cell of the grid
+        let instances = vec![SudokuCircuit::instances(&[(0, 0, 5), (0, 10, 7)])];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+
+        // fewer clues than the circuit reads
+        let instances = vec![SudokuCircuit::instances(&[(0, 0, 5)])];
+        assert!(circuit.validate_witness(&instances).is_err());
+        assert!(MockProver::run(K, &circuit, instances).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_sudoku_not_a_solution() {
+        // swapping two cells of a row keeps the rows right but breaks two columns
+        let mut grid = SOLUTION;
+        grid[0].swap(0, 1);
+        let circuit = SudokuCircuit::new(grid, 0);
+        assert!(circuit.validate_witness(&[vec![]]).is_err());
+        assert!(MockProver::run(K, &circuit, vec![vec![]]).unwrap().verify().is_err());
+
+        // a 10 and a 0 in place of a row's 9 and 1 keep its digits summing to 45, but are no digits
+        let mut grid = SOLUTION;
+        grid[0][6] = 10;
+        grid[0][7] = 0;
+        let circuit = SudokuCircuit::new(grid, 0);
+        assert!(circuit.validate_witness(&[vec![]]).is_err());
+        assert!(MockProver::run(K, &circuit, vec![vec![]]).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! Fixed lookup tables, with their values computed once per process.
+//!
+//! `halo2_proofs` has no way to keep a table between synthesis runs: keygen and every proof
+//! each call `synthesize` (the V1 floor planner twice), and each of those must assign the
+//! whole table again, even though only keygen keeps the fixed values. What can be shared is
+//! the vector of table values, so it is computed the first time a table is loaded and
+//! reused by every later load, in this proof or the next.
+//!
+//! A table's values come from a function of the row, `table_values`, or from a file read
+//! with `read_csv` or `read_binary`, e.g. an S-box, a CRC table or precomputed round
+//! constants. `FixedTableConfig` is a single-column table loaded from any of them, and the
+//! range tables `0..range` of the range-check chips are the special case `range_values`.
+//!
+//! `cargo bench --bench table_load` compares the two at 2^16 rows.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt, fs, io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+    plonk::{ConstraintSystem, Error, TableColumn},
+};
+
+// A table is identified by its source and its length, for each field it is computed over.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Source {
+    Named(&'static str),
+    Csv(PathBuf),
+    Binary(PathBuf),
+}
+
+type Cache = Mutex<HashMap<(TypeId, Source, usize), Arc<dyn Any + Send + Sync>>>;
+
+fn cache() -> &'static Cache {
+    static CACHE: OnceLock<Cache> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+// The cached values of `source`, computed by `compute` on a miss. The lock is not held
+// while computing, so two threads missing at once both compute and the first one wins.
+fn cached<F: FieldExt, E>(
+    source: Source,
+    len: usize,
+    compute: impl FnOnce() -> Result<Vec<F>, E>,
+) -> Result<Arc<Vec<F>>, E> {
+    let key = (TypeId::of::<F>(), source, len);
+    if let Some(values) = cache().lock().expect("table cache poisoned").get(&key) {
+        return Ok(values.clone().downcast::<Vec<F>>().expect("the cache is keyed by field type"));
+    }
+    let values = Arc::new(compute()?) as Arc<dyn Any + Send + Sync>;
+    let values = cache().lock().expect("table cache poisoned").entry(key).or_insert(values).clone();
+    Ok(values.downcast::<Vec<F>>().expect("the cache is keyed by field type"))
+}
+
+/// The `len` values `f(0), ..., f(len - 1)`, shared between every caller asking for the
+/// table named `name` over the same field and length. The name stands for `f`: two tables
+/// computed by different functions must not share it.
+pub fn table_values<F: FieldExt>(name: &'static str, len: usize, f: impl Fn(usize) -> F) -> Arc<Vec<F>> {
+    cached::<_, ()>(Source::Named(name), len, || Ok((0..len).map(f).collect())).expect("computing values cannot fail")
+}
+
+/// The values `0..range` as field elements, shared between every caller asking for the
+/// same field and range.
+pub fn range_values<F: FieldExt>(range: usize) -> Arc<Vec<F>> {
+    table_values("range", range, |i| F::from(i as u64))
+}
+
+#[derive(Debug)]
+pub enum TableError {
+    Io(PathBuf, io::Error),
+    /// An entry of a CSV file that is not a decimal or `0x` hexadecimal field element.
+    Parse { path: PathBuf, line: usize, entry: String },
+    /// A binary file whose length is not a multiple of its entry width.
+    Truncated { path: PathBuf, len: usize, width: usize },
+    /// An entry width other than 1 to 16 bytes.
+    Width(usize),
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::Io(path, err) => write!(f, "failed to read table {}: {}", path.display(), err),
+            TableError::Parse { path, line, entry } => {
+                write!(f, "{}:{}: {:?} is not a field element", path.display(), line, entry)
+            }
+            TableError::Truncated { path, len, width } => {
+                write!(f, "table {} is {} bytes, not a multiple of its {}-byte entries", path.display(), len, width)
+            }
+            TableError::Width(width) => write!(f, "table entries are 1 to 16 bytes wide, not {}", width),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+fn parse_entry<F: FieldExt>(entry: &str) -> Option<F> {
+    match entry.strip_prefix("0x") {
+        Some(hex) => u128::from_str_radix(hex, 16).ok().map(F::from_u128),
+        None => F::from_str_vartime(entry),
+    }
+}
+
+/// The entries of a CSV file, row by row and left to right, each a decimal or `0x`
+/// hexadecimal field element. Blank lines and lines starting with `#` are skipped. Read once
+/// per path and field.
+pub fn read_csv<F: FieldExt>(path: impl AsRef<Path>) -> Result<Arc<Vec<F>>, TableError> {
+    let path = path.as_ref().to_path_buf();
+    cached(Source::Csv(path.clone()), 0, || {
+        let text = fs::read_to_string(&path).map_err(|err| TableError::Io(path.clone(), err))?;
+        let mut values = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            for entry in line.split(',').map(str::trim) {
+                let value = parse_entry(entry).ok_or_else(|| TableError::Parse {
+                    path: path.clone(),
+                    line: i + 1,
+                    entry: entry.to_string(),
+                })?;
+                values.push(value);
+            }
+        }
+        Ok(values)
+    })
+}
+
+/// The entries of a binary file of little-endian unsigned integers `width` bytes wide, e.g.
+/// `1` for an S-box stored as its 256 output bytes. Read once per path, width and field.
+pub fn read_binary<F: FieldExt>(path: impl AsRef<Path>, width: usize) -> Result<Arc<Vec<F>>, TableError> {
+    if !(1..=16).contains(&width) {
+        return Err(TableError::Width(width));
+    }
+    let path = path.as_ref().to_path_buf();
+    cached(Source::Binary(path.clone()), width, || {
+        let bytes = fs::read(&path).map_err(|err| TableError::Io(path.clone(), err))?;
+        if bytes.len() % width != 0 {
+            return Err(TableError::Truncated { path: path.clone(), len: bytes.len(), width });
+        }
+        Ok(bytes
+            .chunks(width)
+            .map(|entry| {
+                let mut le = [0u8; 16];
+                le[..width].copy_from_slice(entry);
+                F::from_u128(u128::from_le_bytes(le))
+            })
+            .collect())
+    })
+}
+
+/// Assigns `values` to `column`, from row 0.
+pub fn load_table<F: FieldExt>(
+    layouter: &mut impl Layouter<F>,
+    name: &str,
+    column: TableColumn,
+    values: &[F],
+) -> Result<(), Error> {
+    layouter.assign_table(|| format!("load {}", name), |mut table| {
+        for (offset, value) in values.iter().enumerate() {
+            table.assign_cell(|| name, column, offset, || Value::known(*value))?;
+        }
+        Ok(())
+    })
+}
+
+/// Assigns `0..range` to `column`.
+pub fn load_range_table<F: FieldExt>(
+    layouter: &mut impl Layouter<F>,
+    column: TableColumn,
+    range: usize,
+) -> Result<(), Error> {
+    load_table(layouter, "range check table", column, &range_values::<F>(range))
+}
+
+/// A single-column lookup table of arbitrary values, e.g. the outputs of an S-box, with the
+/// input being the row when it is paired with a range table:
+///
+///     meta.lookup(|meta| vec![(q * x, range.value), (q * y, sbox.column)]);
+///
+/// The values are given when the table is loaded, from a slice, a function of the row or a
+/// file, so the same config serves every table of a circuit.
+#[derive(Debug, Clone)]
+pub struct FixedTableConfig<F: FieldExt> {
+    pub column: TableColumn,
+    name: &'static str,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FixedTableConfig<F> {
+    /// `name` annotates the table and, for `load_with`, identifies its values in the cache.
+    pub fn configure(meta: &mut ConstraintSystem<F>, name: &'static str) -> Self {
+        Self {
+            column: meta.lookup_table_column(),
+            name,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Loads `values`, e.g. from `read_csv` or `read_binary`.
+    pub fn load(&self, layouter: &mut impl Layouter<F>, values: &[F]) -> Result<(), Error> {
+        load_table(layouter, self.name, self.column, values)
+    }
+
+    /// Loads `f(0), ..., f(len - 1)`, computed the first time the table is loaded.
+    pub fn load_with(&self, layouter: &mut impl Layouter<F>, len: usize, f: impl Fn(usize) -> F) -> Result<(), Error> {
+        self.load(layouter, &table_values(self.name, len, f))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, sync::Arc};
+
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::{Fp, Fq},
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+        poly::Rotation,
+    };
+
+    use super::{range_values, read_binary, read_csv, table_values, FixedTableConfig, TableError};
+
+    #[test]
+    fn test_range_values_cached() {
+        let values = range_values::<Fp>(1 << 10);
+        assert_eq!(values.len(), 1 << 10);
+        assert_eq!(values[1000], Fp::from(1000));
+        assert!(Arc::ptr_eq(&values, &range_values::<Fp>(1 << 10)));
+
+        // keyed by field and size
+        assert_eq!(range_values::<Fp>(1 << 4).len(), 1 << 4);
+        assert_eq!(range_values::<Fq>(1 << 10)[1000], Fq::from(1000));
+
+        // and by name
+        let squares = table_values::<Fp>("squares", 1 << 4, |i| Fp::from((i * i) as u64));
+        assert_eq!(squares[5], Fp::from(25));
+        assert!(!Arc::ptr_eq(&squares, &range_values::<Fp>(1 << 4)));
+    }
+
+    #[test]
+    fn test_read_table_files() {
+        let dir = std::env::temp_dir().join(format!("halo2-examples-table-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let csv = dir.join("table.csv");
+        fs::write(&csv, "# a comment\n1, 2, 0x10\n\n18446744073709551616\n").unwrap();
+        let values = read_csv::<Fp>(&csv).unwrap();
+        assert_eq!(*values, vec![Fp::from(1), Fp::from(2), Fp::from(16), Fp::from_u128(1 << 64)]);
+
+        let bad = dir.join("bad.csv");
+        fs::write(&bad, "1, 2\n3, x\n").unwrap();
+        assert!(matches!(read_csv::<Fp>(&bad), Err(TableError::Parse { line: 2, .. })));
+
+        let binary = dir.join("table.bin");
+        fs::write(&binary, [0x01, 0x02, 0xff, 0x00]).unwrap();
+        assert_eq!(*read_binary::<Fp>(&binary, 1).unwrap(), [1, 2, 255, 0].map(Fp::from));
+        assert_eq!(*read_binary::<Fp>(&binary, 2).unwrap(), [0x0201, 0xff].map(Fp::from));
+        assert!(matches!(read_binary::<Fp>(&binary, 3), Err(TableError::Truncated { len: 4, .. })));
+        assert!(matches!(read_binary::<Fp>(&binary, 17), Err(TableError::Width(17))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Looks `value` up in the table of the cubes of `0..16`.
+    struct CubeCircuit(u64);
+
+    impl Circuit<Fp> for CubeCircuit {
+        type Config = (Column<Advice>, Selector, FixedTableConfig<Fp>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self(0)
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let value = meta.advice_column();
+            let q = meta.complex_selector();
+            let cubes = FixedTableConfig::configure(meta, "cubes");
+            meta.lookup(|meta| {
+                let q = meta.query_selector(q);
+                vec![(q * meta.query_advice(value, Rotation::cur()), cubes.column)]
+            });
+            (value, q, cubes)
+        }
+
+        fn synthesize(&self, (value, q, cubes): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            cubes.load_with(&mut layouter, 16, |i| Fp::from((i * i * i) as u64))?;
+            layouter.assign_region(
+                || "value",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    region.assign_advice(|| "value", value, 0, || Value::known(Fp::from(self.0)))
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fixed_table() {
+        MockProver::run(5, &CubeCircuit(27), vec![]).unwrap().assert_satisfied();
+        MockProver::run(5, &CubeCircuit(3375), vec![]).unwrap().assert_satisfied();
+        assert!(MockProver::run(5, &CubeCircuit(28), vec![]).unwrap().verify().is_err());
+    }
+}
@@ -0,0 +1,398 @@
+//! Tic-tac-toe: a private sequence of moves is a complete game by the rules, with a public
+//! result, 0 for a draw, 1 if X won and 2 if O won.
+//!
+//! The game is a state machine traced a turn per row, like the toy VM's. The state is the
+//! squares each player holds, as 9-bit masks `x` and `o`, and the number of moves made; a
+//! move is the bit `m` of its square, X's on even turns, O's on odd ones:
+//!
+//!        x    |   o   |   m   | count | active | won_x | won_o | full | x_turn |  q_step
+//!     -----------------------------------------------------------------------------------
+//!        0    |   0   |  m_0  |   0   |   1    |   0   |   0   |  0   |   1    |    1
+//!       ...   |  ...  |  ...  |  ...  |  ...   |  ...  |  ...  |  ... |  ...   |    1
+//!       x_9   |  o_9  |       | count |   0    | won_x | won_o | full |        |
+//!
+//!     x_next = x + x_turn * m,   o_next = o + (1 - x_turn) * m,   count_next = count + active
+//!
+//! Three tables hold the rules, each state looked up in them on every row:
+//!
+//! - `(x + o, count, full)` is a mask with its number of squares, and whether that is all 9.
+//!   Adding the bit of a square already taken carries into the bits above, so the count
+//!   would be off by at least 2: this is what makes every move go to an empty square;
+//! - `(x, won_x)` and `(o, won_o)` in a table of masks and whether they hold a line;
+//! - `(active, m)` is `(0, 0)` or `(1, 2^square)`: a move is made exactly on the turns the
+//!   game is still going, `active = (1 - won_x - won_o) * (1 - full)`.
+//!
+//! So a game stops at the first line, or when the board is full, and has to go on until
+//! then. The last row is after 9 turns, when every game is over, and its result is
+//! `won_x + 2 * won_o`, at most one of which is 1 as play stops at the first line.
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    constants::{ConstantsChip, ConstantsConfig},
+    error::ResultExt,
+    gate::GateBuilder,
+    table::FixedTableConfig,
+    validate::{check_below, check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+/// The most moves a game can have, and the move that is none, after the game is over.
+pub const MOVES: usize = 9;
+pub const PASS: u64 = 9;
+
+pub const DRAW: u64 = 0;
+pub const X_WINS: u64 = 1;
+pub const O_WINS: u64 = 2;
+
+/// The rows, columns and diagonals, by square, numbered row by row from the top left.
+pub const LINES: [[usize; 3]; 8] = [[0, 1, 2], [3, 4, 5], [6, 7, 8], [0, 3, 6], [1, 4, 7], [2, 5, 8], [0, 4, 8], [2, 4, 6]];
+
+/// Whether the squares of `mask` include a line.
+pub fn has_line(mask: u64) -> bool {
+    LINES.iter().any(|line| line.iter().all(|square| mask >> square & 1 == 1))
+}
+
+/// The result of the game `moves`, or `None` if it breaks the rules or is unfinished.
+pub fn play(moves: &[u64]) -> Option<u64> {
+    let (mut x, mut o) = (0, 0);
+    for (turn, square) in moves.iter().enumerate() {
+        if has_line(x) || has_line(o) || *square >= PASS || (x | o) >> square & 1 == 1 {
+            return None;
+        }
+        if turn % 2 == 0 {
+            x |= 1 << square;
+        } else {
+            o |= 1 << square;
+        }
+    }
+    match (has_line(x), has_line(o)) {
+        (true, _) => Some(X_WINS),
+        (_, true) => Some(O_WINS),
+        _ if (x | o).count_ones() as usize == MOVES => Some(DRAW),
+        _ => None,
+    }
+}
+
+/// One row of the trace.
+#[derive(Debug, Clone, Copy, Default)]
+struct State {
+    x: u64,
+    o: u64,
+    m: u64,
+    count: u64,
+    active: bool,
+    won_x: bool,
+    won_o: bool,
+    full: bool,
+}
+
+/// The trace of `moves`, `PASS` after the game is over, whether or not they follow the rules.
+fn trace(moves: &[u64; MOVES]) -> Vec<State> {
+    let mut state = State::default();
+    let mut trace = Vec::with_capacity(MOVES + 1);
+    for turn in 0..=MOVES {
+        state.won_x = has_line(state.x);
+        state.won_o = has_line(state.o);
+        state.full = (state.x + state.o).count_ones() as usize == MOVES;
+        state.active = !(state.won_x || state.won_o || state.full);
+        state.m = moves.get(turn).filter(|square| **square < PASS).map_or(0, |square| 1 << square);
+        trace.push(state);
+
+        if turn % 2 == 0 {
+            state.x += state.m;
+        } else {
+            state.o += state.m;
+        }
+        state.count += state.active as u64;
+    }
+    trace
+}
+
+#[derive(Debug, Clone)]
+pub struct TicTacToeConfig {
+    x: Column<Advice>,
+    o: Column<Advice>,
+    m: Column<Advice>,
+    count: Column<Advice>,
+    active: Column<Advice>,
+    won_x: Column<Advice>,
+    won_o: Column<Advice>,
+    full: Column<Advice>,
+    result: Column<Advice>,
+    x_turn: Column<Fixed>,
+    q_state: Selector,
+    q_step: Selector,
+    q_result: Selector,
+    masks: [FixedTableConfig<Fp>; 3],
+    lines: [FixedTableConfig<Fp>; 2],
+    moves: [FixedTableConfig<Fp>; 2],
+    instance: Column<Instance>,
+    constants: ConstantsConfig,
+}
+
+/// Proves the private moves are a game with the public result.
+pub struct TicTacToeCircuit {
+    /// The square of every move, `PASS` after the game is over.
+    pub moves: [Value<u64>; MOVES],
+}
+
+impl Default for TicTacToeCircuit {
+    fn default() -> Self {
+        Self {
+            moves: [Value::unknown(); MOVES],
+        }
+    }
+}
+
+impl TicTacToeCircuit {
+    /// The circuit for the game `moves`, padded with `PASS`.
+    pub fn new(moves: &[u64]) -> Self {
+        let mut padded = [Value::known(PASS); MOVES];
+        for (padded, square) in padded.iter_mut().zip(moves) {
+            *padded = Value::known(*square);
+        }
+        Self { moves: padded }
+    }
+
+    /// The public input: the result.
+    pub fn instances(result: u64) -> Vec<Fp> {
+        vec![Fp::from(result)]
+    }
+}
+
+impl Circuit<Fp> for TicTacToeCircuit {
+    type Config = TicTacToeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let [x, o, m, count, active, won_x, won_o, full, result] = [(); 9].map(|_| meta.advice_column());
+        let x_turn = meta.fixed_column();
+        let q_state = meta.complex_selector();
+        let q_step = meta.complex_selector();
+        let q_result = meta.selector();
+        let masks = ["tic-tac-toe mask", "tic-tac-toe mask count", "tic-tac-toe mask full"]
+            .map(|name| FixedTableConfig::configure(meta, name));
+        let lines = ["tic-tac-toe line mask", "tic-tac-toe has line"].map(|name| FixedTableConfig::configure(meta, name));
+        let moves = ["tic-tac-toe move active", "tic-tac-toe move bit"].map(|name| FixedTableConfig::configure(meta, name));
+
+        // the empty board the game starts from is copied from a loaded constant
+        let constants = ConstantsChip::configure_with(meta, result);
+        for column in [x, o, count, won_x, won_o, result] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.lookup(|meta| {
+            let q_state = meta.query_selector(q_state);
+            let [x, o, count, full] = [x, o, count, full].map(|column| meta.query_advice(column, Rotation::cur()));
+            let inputs = [x + o, count, full];
+            inputs.into_iter().map(|input| q_state.clone() * input).zip(masks.iter().map(|table| table.column)).collect()
+        });
+        for (mask, won) in [(x, won_x), (o, won_o)] {
+            meta.lookup(|meta| {
+                let q_state = meta.query_selector(q_state);
+                let mask = meta.query_advice(mask, Rotation::cur());
+                let won = meta.query_advice(won, Rotation::cur());
+                vec![(q_state.clone() * mask, lines[0].column), (q_state * won, lines[1].column)]
+            });
+        }
+        meta.lookup(|meta| {
+            let q_step = meta.query_selector(q_step);
+            let active = meta.query_advice(active, Rotation::cur());
+            let m = meta.query_advice(m, Rotation::cur());
+            vec![(q_step.clone() * active, moves[0].column), (q_step * m, moves[1].column)]
+        });
+
+        meta.create_gate("status", |meta| {
+            let q_state = meta.query_selector(q_state);
+            let [active, won_x, won_o, full] = [active, won_x, won_o, full].map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(Fp::one());
+
+            GateBuilder::with_selector(q_state).equal(
+                "active = (1 - won_x - won_o) * (1 - full)",
+                active,
+                (one.clone() - won_x - won_o) * (one - full),
+            )
+        });
+
+        meta.create_gate("turn", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let x_turn = meta.query_fixed(x_turn, Rotation::cur());
+            let [m, active] = [m, active].map(|column| meta.query_advice(column, Rotation::cur()));
+            let [x_next, o_next, count_next] = [x, o, count].map(|column| meta.query_advice(column, Rotation::next()));
+            let [x, o, count] = [x, o, count].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_step)
+                .equal("x_next = x + x_turn * m", x_next, x + x_turn.clone() * m.clone())
+                .equal("o_next = o + (1 - x_turn) * m", o_next, o + (Expression::Constant(Fp::one()) - x_turn) * m)
+                .equal("count_next = count + active", count_next, count + active)
+        });
+
+        meta.create_gate("result", |meta| {
+            let q_result = meta.query_selector(q_result);
+            let [won_x, won_o, result] = [won_x, won_o, result].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            GateBuilder::with_selector(q_result).equal("result = won_x + 2 * won_o", result, won_x + won_o * Fp::from(2))
+        });
+
+        TicTacToeConfig {
+            x,
+            o,
+            m,
+            count,
+            active,
+            won_x,
+            won_o,
+            full,
+            result,
+            x_turn,
+            q_state,
+            q_step,
+            q_result,
+            masks,
+            lines,
+            moves,
+            instance,
+            constants,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let masks = 1 << MOVES;
+        config.masks[0].load_with(&mut layouter, masks, |mask| Fp::from(mask as u64))?;
+        config.masks[1].load_with(&mut layouter, masks, |mask| Fp::from(mask.count_ones() as u64))?;
+        config.masks[2].load_with(&mut layouter, masks, |mask| Fp::from((mask.count_ones() as usize == MOVES) as u64))?;
+        config.lines[0].load_with(&mut layouter, masks, |mask| Fp::from(mask as u64))?;
+        config.lines[1].load_with(&mut layouter, masks, |mask| Fp::from(has_line(mask as u64) as u64))?;
+        config.moves[0].load_with(&mut layouter, MOVES + 1, |row| Fp::from((row > 0) as u64))?;
+        config.moves[1].load_with(&mut layouter, MOVES + 1, |row| if row == 0 { Fp::zero() } else { Fp::from(1 << (row - 1)) })?;
+
+        let moves: Value<Vec<u64>> = self.moves.iter().copied().collect();
+        let trace = moves.map(|moves| trace(&moves.try_into().expect("MOVES moves")));
+
+        let constants = ConstantsChip::construct(config.constants.clone());
+        let zero = constants.load_constant(layouter.namespace(|| "zero"), "zero", Fp::zero())?;
+        let (won_x, won_o) = layouter
+            .assign_region(
+                || "game",
+                |mut region| {
+                    let mut cells = None;
+                    for turn in 0..=MOVES {
+                        let state = trace.as_ref().map(|trace| trace[turn]);
+                        let assign = |region: &mut Region<'_, Fp>, name: &str, column, value: Value<u64>| {
+                            region.assign_advice(|| format!("{}_{}", name, turn), column, turn, || value.map(Fp::from))
+                        };
+
+                        config.q_state.enable(&mut region, turn)?;
+                        if turn == 0 {
+                            for column in [config.x, config.o, config.count] {
+                                zero.copy_advice(|| "empty board", &mut region, column, 0)?;
+                            }
+                        } else {
+                            assign(&mut region, "x", config.x, state.map(|state| state.x))?;
+                            assign(&mut region, "o", config.o, state.map(|state| state.o))?;
+                            assign(&mut region, "count", config.count, state.map(|state| state.count))?;
+                        }
+                        assign(&mut region, "active", config.active, state.map(|state| state.active as u64))?;
+                        let won_x = assign(&mut region, "won_x", config.won_x, state.map(|state| state.won_x as u64))?;
+                        let won_o = assign(&mut region, "won_o", config.won_o, state.map(|state| state.won_o as u64))?;
+                        assign(&mut region, "full", config.full, state.map(|state| state.full as u64))?;
+
+                        if turn < MOVES {
+                            config.q_step.enable(&mut region, turn)?;
+                            region.assign_fixed(|| format!("x_turn_{}", turn), config.x_turn, turn, || Value::known(Fp::from((turn % 2 == 0) as u64)))?;
+                            assign(&mut region, "m", config.m, state.map(|state| state.m))?;
+                        }
+                        cells = Some((won_x, won_o));
+                    }
+                    Ok(cells.expect("a last row"))
+                },
+            )
+            .in_chip("TicTacToeCircuit", "game")?;
+
+        // the result of the final state, at most one of X and O having won
+        let result = layouter
+            .assign_region(
+                || "result",
+                |mut region| {
+                    config.q_result.enable(&mut region, 0)?;
+                    let won_x = won_x.copy_advice(|| "won_x", &mut region, config.won_x, 0)?;
+                    won_o.copy_advice(|| "won_o", &mut region, config.won_o, 0)?;
+                    let result = won_x.value().copied() + won_o.value().copied() * Value::known(Fp::from(2));
+                    region.assign_advice(|| "result", config.result, 0, || result)
+                },
+            )
+            .in_chip("TicTacToeCircuit", "result")?;
+        layouter.constrain_instance(result.cell(), config.instance, 0)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for TicTacToeCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let moves = self
+            .moves
+            .iter()
+            .map(|square| {
+                let square = known(*square, "move")?;
+                check_below(Fp::from(square), PASS as u128 + 1, "move")?;
+                Ok(square)
+            })
+            .collect::<Result<Vec<_>, InvalidWitness>>()?;
+        let played = moves.iter().position(|square| *square == PASS).unwrap_or(MOVES);
+        if moves[played..].iter().any(|square| *square != PASS) {
+            return Err(InvalidWitness::InvalidParameter("a move after a pass".to_string()));
+        }
+        let result = play(&moves[..played]).ok_or_else(|| InvalidWitness::InvalidParameter("not a finished game".to_string()))?;
+        check_instances(instances, &Self::instances(result))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::dev::MockProver;
+
+    use super::{play, TicTacToeCircuit, DRAW, O_WINS, X_WINS};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 10;
+
+    fn run(moves: &[u64], result: u64) -> bool {
+        let circuit = TicTacToeCircuit::new(moves);
+        let instances = vec![TicTacToeCircuit::instances(result)];
+        assert_eq!(circuit.validate_witness(&instances).is_ok(), play(moves) == Some(result));
+        MockProver::run(K, &circuit, instances).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn test_tic_tac_toe() {
+        // X takes the top row, O the middle one, and a full board with no line
+        assert!(run(&[0, 3, 1, 4, 2], X_WINS));
+        assert!(run(&[0, 3, 1, 4, 8, 5], O_WINS));
+        assert!(run(&[0, 1, 2, 4, 3, 5, 7, 6, 8], DRAW));
+
+        // X completes a line with the last square
+        assert!(run(&[0, 1, 2, 3, 4, 6, 5, 7, 8], X_WINS));
+    }
+
+    #[test]
+    fn test_tic_tac_toe_wrong_result() {
+        assert!(!run(&[0, 3, 1, 4, 2], DRAW));
+        assert!(!run(&[0, 3, 1, 4, 2], O_WINS));
+        assert!(!run(&[0, 1, 2, 4, 3, 5, 7, 6, 8], X_WINS));
+    }
+
+    #[test]
+    fn test_tic_tac_toe_illegal() {
+        // O onto X's square, O playing on after X won, and a game given up unfinished
+        assert!(!run(&[0, 0, 1, 4, 2], X_WINS));
+        assert!(!run(&[0, 3, 1, 4, 2, 5], X_WINS));
+        assert!(!run(&[0, 3, 1, 4, 2, 5], O_WINS));
+        assert!(!run(&[0, 1, 2, 4], DRAW));
+    }
+}
@@ -0,0 +1,232 @@
+use std::fmt;
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value};
+
+/// Why a witness was rejected by `ValidateWitness`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidWitness {
+    /// The value is `Value::unknown()`, e.g. a circuit built by `without_witnesses`.
+    Unknown(&'static str),
+    OutOfRange {
+        name: &'static str,
+        value: String,
+        bound: String,
+    },
+    NotEqual {
+        left: &'static str,
+        right: &'static str,
+    },
+    NotZero(&'static str),
+    WrongInstanceCount {
+        expected: usize,
+        found: usize,
+    },
+    InstanceMismatch {
+        row: usize,
+        expected: String,
+        found: String,
+    },
+    InvalidParameter(String),
+}
+
+impl fmt::Display for InvalidWitness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidWitness::Unknown(name) => write!(f, "`{}` is not known", name),
+            InvalidWitness::OutOfRange { name, value, bound } => {
+                write!(f, "`{}` = {} is not below {}", name, value, bound)
+            }
+            InvalidWitness::NotEqual { left, right } => {
+                write!(f, "`{}` and `{}` must be equal", left, right)
+            }
+            InvalidWitness::NotZero(name) => write!(f, "`{}` must be 0", name),
+            InvalidWitness::WrongInstanceCount { expected, found } => {
+                write!(f, "expected {} public inputs, found {}", expected, found)
+            }
+            InvalidWitness::InstanceMismatch { row, expected, found } => write!(
+                f,
+                "public input {} should be {}, found {}",
+                row, expected, found
+            ),
+            InvalidWitness::InvalidParameter(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for InvalidWitness {}
+
+/// An opt-in check of a circuit's witness and public inputs against the relation the
+/// circuit enforces, run before synthesis to get a precise diagnostic instead of a
+/// constraint failure deep inside `MockProver`.
+pub trait ValidateWitness<F: FieldExt> {
+    fn validate_witness(&self, instances: &[Vec<F>]) -> Result<(), InvalidWitness>;
+}
+
+/// Extracts a known value, or reports `name` as unknown.
+pub fn known<V: Copy>(value: Value<V>, name: &'static str) -> Result<V, InvalidWitness> {
+    let mut inner = None;
+    value.map(|v| inner = Some(v));
+    inner.ok_or(InvalidWitness::Unknown(name))
+}
+
+/// Checks that `value < bound`.
+pub fn check_below<F: FieldExt>(value: F, bound: u128, name: &'static str) -> Result<(), InvalidWitness> {
+    let lower = value.get_lower_128();
+    if F::from_u128(lower) == value && lower < bound {
+        Ok(())
+    } else {
+        Err(InvalidWitness::OutOfRange {
+            name,
+            value: format!("{:?}", value),
+            bound: bound.to_string(),
+        })
+    }
+}
+
+/// Checks that `value` fits in `num_bits` bits.
+pub fn check_bits<F: FieldExt + PrimeFieldBits>(value: F, num_bits: usize, name: &'static str) -> Result<(), InvalidWitness> {
+    if value.to_le_bits().iter().skip(num_bits).any(|bit| *bit) {
+        Err(InvalidWitness::OutOfRange {
+            name,
+            value: format!("{:?}", value),
+            bound: format!("2^{}", num_bits),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that `value` is 0.
+pub fn check_zero<F: FieldExt>(value: F, name: &'static str) -> Result<(), InvalidWitness> {
+    if value == F::zero() {
+        Ok(())
+    } else {
+        Err(InvalidWitness::NotZero(name))
+    }
+}
+
+/// With the `debug-assertions` feature, runs `check` on a witness value as a chip assigns
+/// it, and panics with the reason if it fails. A bad witness then stops synthesis at the
+/// cell it went wrong in, where `MockProver` would only report the lookup or gate that
+/// fails later. Unknown values are skipped, and without the feature this does nothing.
+#[allow(unused_variables)]
+pub fn debug_check<V>(value: &Value<V>, at: impl FnOnce() -> String, check: impl FnOnce(&V) -> Result<(), InvalidWitness>) {
+    #[cfg(feature = "debug-assertions")]
+    value.assert_if_known(|value| match check(value) {
+        Ok(()) => true,
+        Err(err) => panic!("bad witness at {}: {}", at(), err),
+    });
+}
+
+/// Checks that the single instance column holds exactly `expected`.
+pub fn check_instances<F: FieldExt>(instances: &[Vec<F>], expected: &[F]) -> Result<(), InvalidWitness> {
+    let found = instances.first().map(|column| column.as_slice()).unwrap_or(&[]);
+    if instances.len() > 1 || found.len() != expected.len() {
+        return Err(InvalidWitness::WrongInstanceCount {
+            expected: expected.len(),
+            found: instances.iter().map(|column| column.len()).sum(),
+        });
+    }
+    for (row, (expected, found)) in expected.iter().zip(found.iter()).enumerate() {
+        if expected != found {
+            return Err(InvalidWitness::InstanceMismatch {
+                row,
+                expected: format!("{:?}", expected),
+                found: format!("{:?}", found),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "fibonacci", feature = "range-check", feature = "gadgets"))]
+mod test {
+    use std::marker::PhantomData;
+
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    use super::{InvalidWitness, ValidateWitness};
+    use crate::{
+        decompose::decompose_range_check::DecomposeRangeCheckCircuit,
+        fibonaci::{example1, example2},
+        gadgets::is_equal::IsEqualCircuit,
+        range_check::{dynamic::DynamicRangeCheckCircuit, example1::RangeCheckCircuit},
+    };
+
+    #[test]
+    fn test_validate_fibonacci() {
+        let public_input = vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]];
+
+        let circuit = example1::FiboCircuit {
+            a: Value::known(Fp::from(1)),
+            b: Value::known(Fp::from(1)),
+        };
+        assert_eq!(circuit.validate_witness(&public_input), Ok(()));
+        assert!(matches!(
+            circuit.validate_witness(&[vec![Fp::from(1), Fp::from(1), Fp::from(56)]]),
+            Err(InvalidWitness::InstanceMismatch { row: 2, .. })
+        ));
+        assert!(matches!(
+            circuit.validate_witness(&[vec![Fp::from(1), Fp::from(1)]]),
+            Err(InvalidWitness::WrongInstanceCount { expected: 3, found: 2 })
+        ));
+        assert_eq!(
+            example1::FiboCircuit::<Fp>::default().validate_witness(&public_input),
+            Err(InvalidWitness::Unknown("a"))
+        );
+
+        let circuit = example2::FiboCircuit::<Fp>(PhantomData);
+        assert_eq!(circuit.validate_witness(&public_input), Ok(()));
+        assert!(circuit.validate_witness(&[vec![Fp::from(2), Fp::from(1), Fp::from(55)]]).is_err());
+    }
+
+    #[test]
+    fn test_validate_range_checks() {
+        let circuit = RangeCheckCircuit::<Fp, 8> { value: Value::known(Fp::from(7).into()) };
+        assert_eq!(circuit.validate_witness(&[]), Ok(()));
+        let circuit = RangeCheckCircuit::<Fp, 8> { value: Value::known(Fp::from(8).into()) };
+        assert!(matches!(
+            circuit.validate_witness(&[]),
+            Err(InvalidWitness::OutOfRange { name: "value", .. })
+        ));
+
+        let circuit = DynamicRangeCheckCircuit::<Fp, 8> {
+            values: vec![(Value::known(Fp::from(99).into()), 100), (Value::known(Fp::from(100).into()), 100)],
+        };
+        assert!(matches!(circuit.validate_witness(&[]), Err(InvalidWitness::OutOfRange { .. })));
+        let circuit = DynamicRangeCheckCircuit::<Fp, 8> {
+            values: vec![(Value::known(Fp::from(0).into()), 257)],
+        };
+        assert!(matches!(circuit.validate_witness(&[]), Err(InvalidWitness::InvalidParameter(_))));
+    }
+
+    #[test]
+    fn test_validate_decompose() {
+        let circuit = DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::known(Fp::from(u64::MAX).into()),
+            num_bits: 64,
+        };
+        assert_eq!(circuit.validate_witness(&[]), Ok(()));
+
+        let circuit = DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::known(Fp::from(1u64 << 32).into()),
+            num_bits: 32,
+        };
+        assert!(matches!(circuit.validate_witness(&[]), Err(InvalidWitness::OutOfRange { .. })));
+
+        let circuit = DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+            value: Value::known(Fp::from(1).into()),
+            num_bits: 12,
+        };
+        assert!(matches!(circuit.validate_witness(&[]), Err(InvalidWitness::InvalidParameter(_))));
+    }
+
+    #[test]
+    fn test_validate_is_equal() {
+        let circuit = IsEqualCircuit { a: Value::known(Fp::from(42)), b: Value::known(Fp::from(42)) };
+        assert_eq!(circuit.validate_witness(&[]), Ok(()));
+        let circuit = IsEqualCircuit { a: Value::known(Fp::from(42)), b: Value::known(Fp::from(43)) };
+        assert_eq!(circuit.validate_witness(&[]), Err(InvalidWitness::NotEqual { left: "a", right: "b" }));
+    }
+}
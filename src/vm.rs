@@ -0,0 +1,450 @@
+//! A toy zkVM: an accumulator machine running a fixed program for a fixed number of steps.
+//!
+//! The program lives in a read-only memory, three lookup table columns mapping `pc + 1` to
+//! the instruction's opcode and operand (row 0 is left as `(0, 0, 0)` for disabled rows to
+//! look up). Every row of the trace fetches its instruction from there, decodes the opcode
+//! into one-hot bits and, except on the last row, steps to the next state:
+//!
+//!       opcode         |  acc_next      |  pc_next
+//!     -------------------------------------------------------------------
+//!       0  HALT        |  acc           |  pc
+//!       1  ADD k       |  acc + k       |  pc + 1
+//!       2  MUL k       |  acc * k       |  pc + 1
+//!       3  JMP k       |  acc           |  k
+//!       4  JZ k        |  acc           |  acc == 0 ? k : pc + 1
+//!
+//!         pc   |  acc  |  opcode  | operand |  b_0 .. b_4  | acc_inv |  q_fetch  |  q_step
+//!       -------------------------------------------------------------------------------------
+//!          0   |  in   |   op_0   |   k_0   |   one-hot    |         |     1     |    1
+//!         ...  |  ...  |   ...    |   ...   |     ...      |   ...   |     1     |    1
+//!        pc_n  | out   |    0     |    0    |  1 0 0 0 0   |         |     1     |
+//!
+//! `acc == 0` is `IsZeroChip` on the accumulator. The rows after the first `HALT` are
+//! padding, see `PaddingConfig`, which repeat the halted state, so the last row must be a
+//! `HALT` whether the program halted there or earlier. The machine
+//! starts at pc 0 with the private input in `acc`, and the public inputs are a Poseidon hash
+//! of the program and the final `acc`.
+
+use halo2_proofs::{circuit::*, pasta::Fp, plonk::*, poly::Rotation};
+
+use crate::{
+    chip::{LoadPrivateChip, LoadPrivateConfig},
+    constants::{ConstantsChip, ConstantsConfig},
+    error::{Halo2ExamplesError, ResultExt},
+    gadgets::{
+        commit::{commit, CommitChip, CommitConfig},
+        is_zero::{IsZeroChip, IsZeroConfig},
+        padding::{pad, PaddingConfig},
+    },
+    gate::GateBuilder,
+    validate::{check_instances, known, InvalidWitness, ValidateWitness},
+};
+
+const NUM_OPCODES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Halt,
+    Add(i64),
+    Mul(u64),
+    Jmp(u64),
+    Jz(u64),
+}
+
+impl Instruction {
+    pub fn opcode(&self) -> u64 {
+        match self {
+            Instruction::Halt => 0,
+            Instruction::Add(_) => 1,
+            Instruction::Mul(_) => 2,
+            Instruction::Jmp(_) => 3,
+            Instruction::Jz(_) => 4,
+        }
+    }
+
+    pub fn operand(&self) -> Fp {
+        match self {
+            Instruction::Halt => Fp::zero(),
+            Instruction::Add(k) if *k < 0 => -Fp::from(k.unsigned_abs()),
+            Instruction::Add(k) => Fp::from(*k as u64),
+            Instruction::Mul(k) | Instruction::Jmp(k) | Instruction::Jz(k) => Fp::from(*k),
+        }
+    }
+}
+
+/// One row of the trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct State {
+    pub pc: u64,
+    pub acc: Fp,
+}
+
+/// Runs `program` for `steps` steps from `input`, returning the `steps + 1` states. Running
+/// off the end of the program is treated as `HALT`.
+pub fn execute(program: &[Instruction], input: Fp, steps: usize) -> Vec<State> {
+    let mut states = vec![State { pc: 0, acc: input }];
+    for _ in 0..steps {
+        let State { pc, acc } = *states.last().expect("starts with one state");
+        let next = match program.get(pc as usize).copied().unwrap_or(Instruction::Halt) {
+            Instruction::Halt => State { pc, acc },
+            instruction @ Instruction::Add(_) => State {
+                pc: pc + 1,
+                acc: acc + instruction.operand(),
+            },
+            Instruction::Mul(k) => State {
+                pc: pc + 1,
+                acc: acc * Fp::from(k),
+            },
+            Instruction::Jmp(k) => State { pc: k, acc },
+            Instruction::Jz(k) => State {
+                pc: if acc == Fp::zero() { k } else { pc + 1 },
+                acc,
+            },
+        };
+        states.push(next);
+    }
+    states
+}
+
+/// The Poseidon hash chain over every opcode and operand of `program`, starting from 0.
+pub fn program_hash(program: &[Instruction]) -> Fp {
+    program.iter().fold(Fp::zero(), |hash, instruction| {
+        let hash = commit(Fp::from(instruction.opcode()), hash);
+        commit(instruction.operand(), hash)
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct VmConfig {
+    pc: Column<Advice>,
+    acc: Column<Advice>,
+    opcode: Column<Advice>,
+    operand: Column<Advice>,
+    bits: [Column<Advice>; NUM_OPCODES],
+    acc_is_zero: IsZeroConfig<Fp>,
+    padding: PaddingConfig,
+    q_fetch: Selector,
+    q_step: Selector,
+    rom: [TableColumn; 3],
+    hash: CommitConfig,
+    load: LoadPrivateConfig,
+    constants: ConstantsConfig,
+    instance: Column<Instance>,
+}
+
+/// Proves the final accumulator of `program` run for `steps` steps on a private input.
+#[derive(Default)]
+pub struct VmCircuit {
+    pub program: Vec<Instruction>,
+    pub steps: usize,
+    pub input: Value<Fp>,
+}
+
+impl VmCircuit {
+    /// The public inputs: the program hash and the final accumulator.
+    pub fn instances(program: &[Instruction], input: Fp, steps: usize) -> Vec<Fp> {
+        let last = *execute(program, input, steps).last().expect("starts with one state");
+        vec![program_hash(program), last.acc]
+    }
+
+    fn load_rom(&self, config: &VmConfig, layouter: &mut impl Layouter<Fp>) -> Result<(), Halo2ExamplesError> {
+        layouter
+            .assign_table(
+                || "program rom",
+                |mut table| {
+                    let rows = std::iter::once((Fp::zero(), Fp::zero(), Fp::zero())).chain(
+                        self.program.iter().enumerate().map(|(pc, instruction)| {
+                            (Fp::from(pc as u64 + 1), Fp::from(instruction.opcode()), instruction.operand())
+                        }),
+                    );
+                    for (offset, row) in rows.enumerate() {
+                        for (column, value) in config.rom.iter().zip([row.0, row.1, row.2]) {
+                            table.assign_cell(|| "rom", *column, offset, || Value::known(value))?;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+            .in_chip("VmCircuit", "program rom")
+    }
+
+    fn hash_program(
+        &self,
+        config: &VmConfig,
+        constants: &ConstantsChip<Fp>,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        let chip = CommitChip::construct(config.hash.clone());
+        let mut words = Vec::with_capacity(2 * self.program.len());
+        for (i, instruction) in self.program.iter().enumerate() {
+            let opcode = Fp::from(instruction.opcode());
+            words.push(constants.load_constant(layouter.namespace(|| format!("opcode {}", i)), "opcode", opcode)?);
+            words.push(constants.load_constant(layouter.namespace(|| format!("operand {}", i)), "operand", instruction.operand())?);
+        }
+
+        let mut hash = constants.load_constant(layouter.namespace(|| "h_0"), "h_0", Fp::zero())?;
+        for (i, word) in words.iter().enumerate() {
+            hash = chip.commit(layouter.namespace(|| format!("h_{}", i + 1)), word, &hash)?;
+        }
+        Ok(hash)
+    }
+
+    fn run(
+        &self,
+        config: &VmConfig,
+        constants: &ConstantsChip<Fp>,
+        mut layouter: impl Layouter<Fp>,
+        input: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Halo2ExamplesError> {
+        let acc_is_zero = IsZeroChip::construct(config.acc_is_zero.clone());
+        let zero = config.padding.load_zero(layouter.namespace(|| "zero"))?;
+        let pc_0 = constants.load_constant(layouter.namespace(|| "pc_0"), "pc_0", Fp::zero())?;
+        let halt = constants.load_constant(layouter.namespace(|| "one"), "one", Fp::one())?;
+        let trace = input.value().map(|input| {
+            let trace = execute(&self.program, *input, self.steps);
+            let halted = trace
+                .iter()
+                .position(|state| matches!(self.program.get(state.pc as usize), None | Some(Instruction::Halt)))
+                .unwrap_or(self.steps);
+            pad(&trace[..=halted], self.steps + 1)
+        });
+
+        layouter
+            .assign_region(
+                || "trace",
+                |mut region| {
+                    let mut acc = None;
+                    for row in 0..=self.steps {
+                        let (state, is_padding) = trace.as_ref().map(|trace| trace[row]).unzip();
+                        let instruction = state.map(|state| self.program.get(state.pc as usize).copied().unwrap_or(Instruction::Halt));
+
+                        config.q_fetch.enable(&mut region, row)?;
+                        config.padding.assign(&mut region, row, is_padding, &zero)?;
+                        if row < self.steps {
+                            config.q_step.enable(&mut region, row)?;
+                            acc_is_zero.assign(&mut region, row, state.map(|state| state.acc))?;
+                        }
+
+                        if row == 0 {
+                            pc_0.copy_advice(|| "pc_0", &mut region, config.pc, 0)?;
+                            acc = Some(input.copy_advice(|| "acc_0", &mut region, config.acc, 0)?);
+                        } else {
+                            region.assign_advice(|| "pc", config.pc, row, || state.map(|state| Fp::from(state.pc)))?;
+                            acc = Some(region.assign_advice(|| "acc", config.acc, row, || state.map(|state| state.acc))?);
+                        }
+                        region.assign_advice(|| "opcode", config.opcode, row, || {
+                            instruction.map(|instruction| Fp::from(instruction.opcode()))
+                        })?;
+                        region.assign_advice(|| "operand", config.operand, row, || instruction.map(|instruction| instruction.operand()))?;
+
+                        for (opcode, column) in config.bits.iter().enumerate() {
+                            let bit = region.assign_advice(|| format!("b_{}", opcode), *column, row, || {
+                                instruction.map(|instruction| Fp::from((instruction.opcode() == opcode as u64) as u64))
+                            })?;
+                            if row == self.steps && opcode == 0 {
+                                region.constrain_equal(bit.cell(), halt.cell())?;
+                            }
+                        }
+                    }
+                    Ok(acc.expect("at least one row"))
+                },
+            )
+            .in_chip("VmCircuit", "trace")
+    }
+}
+
+impl Circuit<Fp> for VmCircuit {
+    type Config = VmConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            program: self.program.clone(),
+            steps: self.steps,
+            input: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        let hash = CommitChip::configure(meta);
+        let load = LoadPrivateChip::configure(meta, 1);
+        let [pc, acc, opcode, operand, acc_inv] = [(); 5].map(|_| meta.advice_column());
+        let bits = [(); NUM_OPCODES].map(|_| meta.advice_column());
+        let q_fetch = meta.complex_selector();
+        let q_step = meta.selector();
+        let rom = [(); 3].map(|_| meta.lookup_table_column());
+
+        // the program's words, the starting pc and the final HALT bit are loaded constants
+        let constants = ConstantsChip::configure_with(meta, operand);
+        meta.enable_equality(pc);
+        meta.enable_equality(acc);
+        meta.enable_equality(bits[0]);
+        meta.enable_equality(instance);
+
+        let acc_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_step),
+            |meta| meta.query_advice(acc, Rotation::cur()),
+            acc_inv,
+        );
+
+        meta.create_gate("decode", |meta| {
+            let q_fetch = meta.query_selector(q_fetch);
+            let opcode = meta.query_advice(opcode, Rotation::cur());
+            let bits = bits.map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(Fp::one());
+
+            let sum = bits.iter().fold(Expression::Constant(Fp::zero()), |sum, bit| sum + bit.clone());
+            let decoded = bits
+                .iter()
+                .enumerate()
+                .fold(Expression::Constant(Fp::zero()), |sum, (i, bit)| sum + bit.clone() * Fp::from(i as u64));
+
+            bits.into_iter()
+                .fold(GateBuilder::with_selector(q_fetch), |gate, bit| gate.boolean("opcode bit is boolean", bit))
+                .equal("one opcode bit is set", sum, one)
+                .equal("opcode = sum(i * b_i)", opcode, decoded)
+        });
+
+        meta.lookup(|meta| {
+            let q_fetch = meta.query_selector(q_fetch);
+            let pc = meta.query_advice(pc, Rotation::cur());
+            let opcode = meta.query_advice(opcode, Rotation::cur());
+            let operand = meta.query_advice(operand, Rotation::cur());
+            let one = Expression::Constant(Fp::one());
+
+            vec![
+                (q_fetch.clone() * (pc + one), rom[0]),
+                (q_fetch.clone() * opcode, rom[1]),
+                (q_fetch * operand, rom[2]),
+            ]
+        });
+
+        let padding = PaddingConfig::configure(meta, |meta| meta.query_advice(bits[0], Rotation::prev()), &[pc, acc]);
+
+        let is_zero = acc_is_zero.is_zero_expr.clone();
+        meta.create_gate("step", |meta| {
+            let q_step = meta.query_selector(q_step) * padding.not_padding_next(meta);
+            let pc = meta.query_advice(pc, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let k = meta.query_advice(operand, Rotation::cur());
+            let pc_next = meta.query_advice(pc, Rotation::next());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let [halt, add, mul, jmp, jz] = bits.map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(Fp::one());
+            let pc_inc = pc.clone() + one.clone();
+
+            let acc_expected = (halt.clone() + jmp.clone() + jz.clone()) * acc.clone()
+                + add.clone() * (acc.clone() + k.clone())
+                + mul.clone() * acc * k.clone();
+            let pc_expected = halt * pc
+                + (add + mul) * pc_inc.clone()
+                + jmp * k.clone()
+                + jz * (is_zero.clone() * k + (one - is_zero) * pc_inc);
+
+            GateBuilder::with_selector(q_step)
+                .equal("acc_next", acc_next, acc_expected)
+                .equal("pc_next", pc_next, pc_expected)
+        });
+
+        VmConfig {
+            pc,
+            acc,
+            opcode,
+            operand,
+            bits,
+            acc_is_zero,
+            padding,
+            q_fetch,
+            q_step,
+            rom,
+            hash,
+            load,
+            constants,
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        self.load_rom(&config, &mut layouter)?;
+        let load_chip = LoadPrivateChip::construct(config.load.clone());
+        let constants = ConstantsChip::construct(config.constants.clone());
+
+        let input = load_chip.load_private(layouter.namespace(|| "load input"), self.input)?;
+        let output = self.run(&config, &constants, layouter.namespace(|| "run"), &input)?;
+        let hash = self.hash_program(&config, &constants, layouter.namespace(|| "program hash"))?;
+
+        layouter.constrain_instance(hash.cell(), config.instance, 0)?;
+        layouter.constrain_instance(output.cell(), config.instance, 1)?;
+        Ok(())
+    }
+}
+
+impl ValidateWitness<Fp> for VmCircuit {
+    fn validate_witness(&self, instances: &[Vec<Fp>]) -> Result<(), InvalidWitness> {
+        let input = known(self.input, "input")?;
+        let last = *execute(&self.program, input, self.steps).last().expect("starts with one state");
+        if !matches!(self.program.get(last.pc as usize), None | Some(Instruction::Halt)) {
+            return Err(InvalidWitness::InvalidParameter(format!(
+                "the program has not halted after {} steps",
+                self.steps
+            )));
+        }
+        check_instances(instances, &Self::instances(&self.program, input, self.steps))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::{Instruction::*, *};
+    use crate::validate::ValidateWitness;
+
+    const K: u32 = 11;
+
+    // acc := 3 * acc + 1, then count it down to zero
+    fn program() -> Vec<Instruction> {
+        vec![Mul(3), Add(1), Jz(5), Add(-1), Jmp(2), Halt]
+    }
+
+    fn circuit(input: u64, steps: usize) -> (VmCircuit, Vec<Vec<Fp>>) {
+        let circuit = VmCircuit {
+            program: program(),
+            steps,
+            input: Value::known(Fp::from(input)),
+        };
+        let instances = VmCircuit::instances(&program(), Fp::from(input), steps);
+        (circuit, vec![instances])
+    }
+
+    #[test]
+    fn test_vm() {
+        // 2 -> 7, then seven rounds of JZ, ADD -1, JMP and a last JZ to HALT
+        let (circuit, instances) = circuit(2, 2 + 7 * 3 + 1 + 2);
+        assert_eq!(instances[0][1], Fp::zero());
+        assert_eq!(circuit.validate_witness(&instances), Ok(()));
+
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_vm_not_halted() {
+        let (circuit, instances) = circuit(2, 10);
+        assert!(circuit.validate_witness(&instances).is_err());
+
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_vm_wrong_program() {
+        let (circuit, mut instances) = circuit(2, 30);
+        instances[0][0] = program_hash(&[Mul(3), Add(2), Jz(5), Add(-1), Jmp(2), Halt]);
+
+        let prover = MockProver::run(K, &circuit, instances).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
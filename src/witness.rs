@@ -0,0 +1,298 @@
+//! JSON descriptions of the witnesses and public inputs of the example circuits.
+//!
+//! A witness file names the circuit it is meant for and carries its private values
+//! and, where the circuit has an instance column, the expected public inputs:
+//!
+//!     { "circuit": "fibonacci", "a": 1, "b": 1, "out": 55 }
+//!     { "circuit": "range_check", "value": 3, "lookup_value": 12 }
+//!     { "circuit": "decompose", "value": "18446744073709551615", "num_bits": 64 }
+//!
+//! Field elements are given either as JSON numbers (u64) or as decimal strings.
+
+use std::{fmt, fs, io, marker::PhantomData, path::Path};
+
+use ff::{PrimeField, PrimeFieldBits};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value};
+use serde::{Deserialize, Serialize};
+
+use crate::{decompose::decompose_range_check, fibonaci, public_inputs::PublicInputs, range_check};
+
+#[derive(Debug)]
+pub enum WitnessError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    InvalidFieldElement(String),
+    MissingField(&'static str),
+    WrongCircuit {
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+impl fmt::Display for WitnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WitnessError::Io(err) => write!(f, "failed to read witness file: {}", err),
+            WitnessError::Json(err) => write!(f, "malformed witness json: {}", err),
+            WitnessError::InvalidFieldElement(s) => write!(f, "invalid field element: {:?}", s),
+            WitnessError::MissingField(name) => write!(f, "missing witness field `{}`", name),
+            WitnessError::WrongCircuit { expected, found } => {
+                write!(f, "expected a `{}` witness, found `{}`", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WitnessError {}
+
+impl From<io::Error> for WitnessError {
+    fn from(err: io::Error) -> Self {
+        WitnessError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for WitnessError {
+    fn from(err: serde_json::Error) -> Self {
+        WitnessError::Json(err)
+    }
+}
+
+/// A field element as written in a witness file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FieldElement {
+    U64(u64),
+    Decimal(String),
+}
+
+impl FieldElement {
+    pub fn to_field<F: PrimeField>(&self) -> Result<F, WitnessError> {
+        match self {
+            FieldElement::U64(v) => Ok(F::from(*v)),
+            FieldElement::Decimal(s) => F::from_str_vartime(s.trim())
+                .ok_or_else(|| WitnessError::InvalidFieldElement(s.clone())),
+        }
+    }
+}
+
+impl From<u64> for FieldElement {
+    fn from(v: u64) -> Self {
+        FieldElement::U64(v)
+    }
+}
+
+/// Seeds and expected output of the Fibonacci examples.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FibonacciWitness {
+    pub a: FieldElement,
+    pub b: FieldElement,
+    pub out: FieldElement,
+}
+
+impl FibonacciWitness {
+    pub fn example1_circuit<F: FieldExt>(
+        &self,
+    ) -> Result<fibonaci::example1::FiboCircuit<F>, WitnessError> {
+        Ok(fibonaci::example1::FiboCircuit {
+            a: Value::known(self.a.to_field()?),
+            b: Value::known(self.b.to_field()?),
+        })
+    }
+
+    // example2 reads its seeds straight from the instance column, so the circuit itself
+    // carries no witness.
+    pub fn example2_circuit<F: PrimeField>(&self) -> fibonaci::example2::FiboCircuit<F> {
+        fibonaci::example2::FiboCircuit(PhantomData)
+    }
+
+    pub fn public_inputs<F: PrimeField>(&self) -> Result<Vec<Vec<F>>, WitnessError> {
+        Ok(PublicInputs::new(fibonaci::INSTANCES)
+            .set("fib_a", self.a.to_field()?)
+            .set("fib_b", self.b.to_field()?)
+            .set("fib_out", self.out.to_field()?)
+            .build()
+            .expect("every Fibonacci public input is set"))
+    }
+}
+
+/// Values for the range-check examples. `lookup_value` is only used by example2.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RangeCheckWitness {
+    pub value: FieldElement,
+    #[serde(default)]
+    pub lookup_value: Option<FieldElement>,
+}
+
+impl RangeCheckWitness {
+    pub fn example1_circuit<F: FieldExt, const RANGE: usize>(
+        &self,
+    ) -> Result<range_check::example1::RangeCheckCircuit<F, RANGE>, WitnessError> {
+        Ok(range_check::example1::RangeCheckCircuit {
+            value: Value::known(self.value.to_field::<F>()?.into()),
+        })
+    }
+
+    pub fn example2_circuit<F: FieldExt, const RANGE: usize, const NUM_BITS: usize>(
+        &self,
+    ) -> Result<range_check::example2::RangeCheckCircuit<F, RANGE, NUM_BITS>, WitnessError> {
+        let lookup_value = self
+            .lookup_value
+            .as_ref()
+            .ok_or(WitnessError::MissingField("lookup_value"))?;
+        Ok(range_check::example2::RangeCheckCircuit {
+            value: Value::known(self.value.to_field::<F>()?.into()),
+            lookup_value: Value::known(lookup_value.to_field::<F>()?.into()),
+        })
+    }
+
+    // Neither range-check example has an instance column.
+    pub fn public_inputs<F: FieldExt>(&self) -> Vec<Vec<F>> {
+        vec![]
+    }
+}
+
+/// Value and bit-length for the decompose range-check example.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecomposeWitness {
+    pub value: FieldElement,
+    pub num_bits: usize,
+}
+
+impl DecomposeWitness {
+    pub fn circuit<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize, const RANGE: usize>(
+        &self,
+    ) -> Result<
+        decompose_range_check::DecomposeRangeCheckCircuit<F, LOOKUP_NUM_BITS, RANGE>,
+        WitnessError,
+    > {
+        Ok(decompose_range_check::DecomposeRangeCheckCircuit {
+            value: Value::known(self.value.to_field::<F>()?.into()),
+            num_bits: self.num_bits,
+        })
+    }
+
+    pub fn public_inputs<F: FieldExt>(&self) -> Vec<Vec<F>> {
+        vec![]
+    }
+}
+
+/// The contents of a witness file, tagged by the `circuit` field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "circuit", rename_all = "snake_case")]
+pub enum WitnessFile {
+    Fibonacci(FibonacciWitness),
+    RangeCheck(RangeCheckWitness),
+    Decompose(DecomposeWitness),
+}
+
+impl WitnessFile {
+    pub fn from_json(json: &str) -> Result<Self, WitnessError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, WitnessError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, WitnessError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn circuit_name(&self) -> &'static str {
+        match self {
+            WitnessFile::Fibonacci(_) => "fibonacci",
+            WitnessFile::RangeCheck(_) => "range_check",
+            WitnessFile::Decompose(_) => "decompose",
+        }
+    }
+
+    pub fn fibonacci(&self) -> Result<&FibonacciWitness, WitnessError> {
+        match self {
+            WitnessFile::Fibonacci(w) => Ok(w),
+            _ => Err(self.wrong_circuit("fibonacci")),
+        }
+    }
+
+    pub fn range_check(&self) -> Result<&RangeCheckWitness, WitnessError> {
+        match self {
+            WitnessFile::RangeCheck(w) => Ok(w),
+            _ => Err(self.wrong_circuit("range_check")),
+        }
+    }
+
+    pub fn decompose(&self) -> Result<&DecomposeWitness, WitnessError> {
+        match self {
+            WitnessFile::Decompose(w) => Ok(w),
+            _ => Err(self.wrong_circuit("decompose")),
+        }
+    }
+
+    fn wrong_circuit(&self, expected: &'static str) -> WitnessError {
+        WitnessError::WrongCircuit {
+            expected,
+            found: self.circuit_name(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::{FieldElement, WitnessError, WitnessFile};
+
+    #[test]
+    fn test_fibonacci_witness() {
+        let witness = WitnessFile::from_json(r#"{ "circuit": "fibonacci", "a": 1, "b": 1, "out": "55" }"#).unwrap();
+        let fibo = witness.fibonacci().unwrap();
+        let public_inputs = fibo.public_inputs::<Fp>().unwrap();
+
+        let circuit = fibo.example1_circuit::<Fp>().unwrap();
+        MockProver::run(4, &circuit, public_inputs.clone()).unwrap().assert_satisfied();
+
+        let circuit = fibo.example2_circuit::<Fp>();
+        MockProver::run(4, &circuit, public_inputs).unwrap().assert_satisfied();
+    }
+
+    #[test]
+    fn test_range_check_witness() {
+        let witness = WitnessFile::from_json(r#"{ "circuit": "range_check", "value": 3, "lookup_value": 12 }"#).unwrap();
+        let range_check = witness.range_check().unwrap();
+
+        let circuit = range_check.example1_circuit::<Fp, 8>().unwrap();
+        MockProver::run(4, &circuit, range_check.public_inputs()).unwrap().assert_satisfied();
+
+        let circuit = range_check.example2_circuit::<Fp, 8, 4>().unwrap();
+        MockProver::run(9, &circuit, range_check.public_inputs()).unwrap().assert_satisfied();
+    }
+
+    #[test]
+    fn test_decompose_witness() {
+        let witness = WitnessFile::from_json(r#"{ "circuit": "decompose", "value": "18446744073709551615", "num_bits": 64 }"#).unwrap();
+        let decompose = witness.decompose().unwrap();
+
+        let circuit = decompose.circuit::<Fp, 8, 256>().unwrap();
+        MockProver::run(9, &circuit, decompose.public_inputs()).unwrap().assert_satisfied();
+    }
+
+    #[test]
+    fn test_witness_errors() {
+        let witness = WitnessFile::from_json(r#"{ "circuit": "range_check", "value": 3 }"#).unwrap();
+        assert!(matches!(
+            witness.range_check().unwrap().example2_circuit::<Fp, 8, 4>(),
+            Err(WitnessError::MissingField("lookup_value"))
+        ));
+        assert!(matches!(
+            witness.fibonacci(),
+            Err(WitnessError::WrongCircuit { expected: "fibonacci", found: "range_check" })
+        ));
+        assert!(matches!(
+            FieldElement::Decimal("0xzz".to_string()).to_field::<Fp>(),
+            Err(WitnessError::InvalidFieldElement(_))
+        ));
+        assert!(matches!(WitnessFile::from_json("{}"), Err(WitnessError::Json(_))));
+
+        let roundtrip = WitnessFile::from_json(&witness.to_json().unwrap()).unwrap();
+        assert_eq!(roundtrip, witness);
+    }
+}
@@ -0,0 +1,108 @@
+//! Keygen, a real proof and its verification for every example, with the parameters, the
+//! verifying key and the proof all serialized and read back before verifying.
+
+use std::marker::PhantomData;
+
+use halo2_examples::{
+    decompose::{decompose_range_check::DecomposeRangeCheckCircuit, runtime::RuntimeDecomposeCircuit},
+    fibonaci::{example1, example2, runtime::RuntimeFiboCircuit},
+    gadgets::is_equal::IsEqualCircuit,
+    params::CircuitParams,
+    prover::{prove, setup, verify},
+    range_check::{self, dynamic::DynamicRangeCheckCircuit, tagged::TaggedRangeCheckCircuit},
+};
+use halo2_proofs::{
+    circuit::Value,
+    pasta::{EqAffine, Fp},
+    plonk::{Circuit, VerifyingKey},
+    poly::commitment::Params,
+};
+
+fn round_trip<C: Circuit<Fp>>(k: u32, circuit: &C, instances: Vec<Vec<Fp>>) {
+    let (params, pk) = setup(k, circuit).unwrap();
+    let proof = prove(&params, &pk, circuit, &instances).unwrap();
+
+    let mut params_bytes = vec![];
+    params.write(&mut params_bytes).unwrap();
+    let params = Params::<EqAffine>::read(&mut &params_bytes[..]).unwrap();
+
+    let mut vk_bytes = vec![];
+    pk.get_vk().write(&mut vk_bytes).unwrap();
+    let vk = VerifyingKey::<EqAffine>::read::<_, C>(&mut &vk_bytes[..], &params).unwrap();
+
+    verify(&params, &vk, &instances, &proof).unwrap();
+
+    // the same proof must not verify against other public inputs
+    if let Some(value) = instances.first().and_then(|column| column.last()) {
+        let mut wrong = instances.clone();
+        *wrong[0].last_mut().unwrap() = *value + Fp::one();
+        assert!(verify(&params, &vk, &wrong, &proof).is_err());
+    }
+}
+
+fn fibonacci_instances() -> Vec<Vec<Fp>> {
+    vec![vec![Fp::from(1), Fp::from(1), Fp::from(55)]]
+}
+
+#[test]
+fn test_e2e_fibonacci() {
+    let circuit = example1::FiboCircuit {
+        a: Value::known(Fp::from(1)),
+        b: Value::known(Fp::from(1)),
+    };
+    round_trip(4, &circuit, fibonacci_instances());
+    round_trip(4, &example2::FiboCircuit::<Fp>(PhantomData), fibonacci_instances());
+
+    let params = CircuitParams::default().fibonacci;
+    round_trip(params.k, &RuntimeFiboCircuit::<Fp>::new(params), fibonacci_instances());
+}
+
+#[test]
+fn test_e2e_range_checks() {
+    let circuit = range_check::example1::RangeCheckCircuit::<Fp, 8> {
+        value: Value::known(Fp::from(3).into()),
+    };
+    round_trip(4, &circuit, vec![]);
+
+    let circuit = range_check::example2::RangeCheckCircuit::<Fp, 8, 4> {
+        value: Value::known(Fp::from(3).into()),
+        lookup_value: Value::known(Fp::from(12).into()),
+    };
+    round_trip(9, &circuit, vec![]);
+
+    let circuit = TaggedRangeCheckCircuit::<Fp, 8, 4> {
+        value: Value::known(Fp::from(3).into()),
+        lookup_value: Value::known(Fp::from(12).into()),
+    };
+    round_trip(9, &circuit, vec![]);
+
+    let circuit = DynamicRangeCheckCircuit::<Fp, 8> {
+        values: vec![(Value::known(Fp::from(99).into()), 100), (Value::known(Fp::from(7).into()), 8)],
+    };
+    round_trip(9, &circuit, vec![]);
+}
+
+#[test]
+fn test_e2e_decompose() {
+    let circuit = DecomposeRangeCheckCircuit::<Fp, 8, 256> {
+        value: Value::known(Fp::from(rand::random::<u64>()).into()),
+        num_bits: 64,
+    };
+    round_trip(9, &circuit, vec![]);
+
+    let params = CircuitParams::default().decompose;
+    let circuit = RuntimeDecomposeCircuit::<Fp> {
+        value: Value::known(Fp::from(rand::random::<u64>()).into()),
+        params: params.clone(),
+    };
+    round_trip(params.k, &circuit, vec![]);
+}
+
+#[test]
+fn test_e2e_is_equal() {
+    let circuit = IsEqualCircuit {
+        a: Value::known(Fp::from(42)),
+        b: Value::known(Fp::from(42)),
+    };
+    round_trip(4, &circuit, vec![]);
+}
@@ -0,0 +1,23 @@
+use std::env;
+
+use halo2_examples::dev::golden::{bless, golden_dir, goldens, mismatches};
+
+// Fails when the verifying key of an example changes, which breaks the verifiers deployed
+// for it, or when its golden file is missing. If the change is intended, rerun
+// `cargo run --bin golden -- --bless`, or this test with BLESS=1.
+#[test]
+fn test_vk_goldens() {
+    let goldens = goldens();
+    let dir = golden_dir();
+
+    if env::var_os("BLESS").is_some() {
+        bless(&goldens, &dir).unwrap();
+        return;
+    }
+    let mismatches: Vec<_> = mismatches(&goldens, &dir).iter().map(|golden| golden.name).collect();
+    assert!(
+        mismatches.is_empty(),
+        "verifying keys changed or missing for {:?}, rerun `cargo run --bin golden -- --bless` if this is intended",
+        mismatches
+    );
+}
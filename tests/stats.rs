@@ -0,0 +1,21 @@
+use std::{env, fs, path::Path};
+
+use halo2_examples::dev::stats::{examples, to_csv};
+
+// Fails when an example's k, rows, columns, gates, lookups or proof size change, or when
+// the snapshot is missing. If the change is intended, rerun with UPDATE_STATS=1 to rewrite it.
+#[test]
+fn test_stats_snapshot() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/stats.csv");
+    let csv = to_csv(&examples(), false);
+
+    if env::var_os("UPDATE_STATS").is_some() {
+        fs::write(&path, csv).unwrap();
+        return;
+    }
+    assert_eq!(
+        fs::read_to_string(&path).expect("tests/stats.csv is missing, rerun with UPDATE_STATS=1"),
+        csv,
+        "circuit sizes changed, rerun with UPDATE_STATS=1 if this is intended"
+    );
+}